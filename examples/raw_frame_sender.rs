@@ -0,0 +1,129 @@
+//!
+//! Reference sender of the raw frame protocol (see docs/raw-frame-protocol.md).
+//! Streams a generated 32×24 scene, the size of an MLX90640, to a thermal-cat
+//! receiving raw frames (File > Receive Raw Frames…).
+//!
+//! ```sh
+//! cargo run --example raw_frame_sender -- udp 127.0.0.1:5510
+//! cargo run --example raw_frame_sender -- tcp 127.0.0.1:5510
+//! ```
+//!
+
+use std::{
+    env,
+    f32::consts::TAU,
+    io::Write,
+    net::{TcpStream, UdpSocket},
+    process, thread,
+    time::{Duration, Instant},
+};
+
+const MAGIC: &[u8; 4] = b"TCRF";
+const PROTOCOL_VERSION: u8 = 1;
+const PIXEL_FORMAT_CENTIKELVIN: u8 = 1;
+const PIXEL_FORMAT_CELSIUS: u8 = 2;
+
+const WIDTH: usize = 32;
+const HEIGHT: usize = 24;
+const FRAME_RATE: f32 = 8.0;
+// Rows per UDP datagram, keeps the datagrams below the usual MTU of 1500 bytes
+const ROWS_PER_DATAGRAM: usize = 8;
+
+///
+/// Header of a packet carrying `row_count` rows starting at `first_row`, all little-endian.
+///
+fn header(pixel_format: u8, frame_number: u32, first_row: usize, row_count: usize) -> [u8; 20] {
+    let mut header = [0; 20];
+    header[0..4].copy_from_slice(MAGIC);
+    header[4] = PROTOCOL_VERSION;
+    header[5] = pixel_format;
+    header[6..8].copy_from_slice(&(WIDTH as u16).to_le_bytes());
+    header[8..10].copy_from_slice(&(HEIGHT as u16).to_le_bytes());
+    header[10..14].copy_from_slice(&frame_number.to_le_bytes());
+    header[14..16].copy_from_slice(&(first_row as u16).to_le_bytes());
+    header[16..18].copy_from_slice(&(row_count as u16).to_le_bytes());
+    // bytes 18 and 19 are reserved and stay zero
+    header
+}
+
+///
+/// A warm spot circling over a room temperature background, in °C.
+///
+fn scene(time: f32) -> Vec<f32> {
+    let (spot_x, spot_y) = (
+        WIDTH as f32 / 2.0 + (time * TAU / 10.0).cos() * WIDTH as f32 / 3.0,
+        HEIGHT as f32 / 2.0 + (time * TAU / 10.0).sin() * HEIGHT as f32 / 3.0,
+    );
+    (0..HEIGHT)
+        .flat_map(|y| (0..WIDTH).map(move |x| (x as f32, y as f32)))
+        .map(|(x, y)| {
+            let distance_squared = (x - spot_x).powi(2) + (y - spot_y).powi(2);
+            22.0 + 15.0 * (-distance_squared / 8.0).exp()
+        })
+        .collect()
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let (transport, address) = match args.as_slice() {
+        [_, transport, address] => (transport.as_str(), address.as_str()),
+        _ => {
+            eprintln!("Usage: raw_frame_sender <udp|tcp> <host:port>");
+            process::exit(1);
+        }
+    };
+
+    let start = Instant::now();
+    let frame_interval = Duration::from_secs_f32(1.0 / FRAME_RATE);
+    match transport {
+        "udp" => {
+            let socket = UdpSocket::bind("0.0.0.0:0").expect("Failed to bind the socket");
+            // f32 °C as delivered by the MLX90640 libraries, split into datagrams of a few rows
+            for frame_number in 0u32.. {
+                let temperatures = scene(start.elapsed().as_secs_f32());
+                for first_row in (0..HEIGHT).step_by(ROWS_PER_DATAGRAM) {
+                    let row_count = ROWS_PER_DATAGRAM.min(HEIGHT - first_row);
+                    let mut packet =
+                        header(PIXEL_FORMAT_CELSIUS, frame_number, first_row, row_count).to_vec();
+                    for temperature in
+                        &temperatures[first_row * WIDTH..(first_row + row_count) * WIDTH]
+                    {
+                        packet.extend_from_slice(&temperature.to_le_bytes());
+                    }
+                    if let Err(err) = socket.send_to(&packet, address) {
+                        eprintln!("Failed to send: {}", err);
+                    }
+                }
+                thread::sleep(frame_interval);
+            }
+        }
+        "tcp" => loop {
+            // centikelvin, whole frames in a single packet
+            let mut stream = match TcpStream::connect(address) {
+                Ok(stream) => stream,
+                Err(err) => {
+                    eprintln!("Failed to connect, retrying: {}", err);
+                    thread::sleep(Duration::from_secs(1));
+                    continue;
+                }
+            };
+            for frame_number in 0u32.. {
+                let temperatures = scene(start.elapsed().as_secs_f32());
+                let mut packet = header(PIXEL_FORMAT_CENTIKELVIN, frame_number, 0, HEIGHT).to_vec();
+                for temperature in temperatures {
+                    let centikelvin = ((temperature + 273.15) * 100.0).round() as u16;
+                    packet.extend_from_slice(&centikelvin.to_le_bytes());
+                }
+                if let Err(err) = stream.write_all(&packet) {
+                    eprintln!("Disconnected, reconnecting: {}", err);
+                    break;
+                }
+                thread::sleep(frame_interval);
+            }
+        },
+        _ => {
+            eprintln!("Unknown transport {}, use udp or tcp", transport);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,300 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    gizmos::GizmoResult,
+    temperature::{format_temp, Temp, TempRange, TemperatureUnit},
+};
+
+// Bump when the alarms file changes in a way older versions cannot read
+const ALARMS_VERSION: u32 = 1;
+
+///
+/// Which temperature of a gizmo is compared against the threshold.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AlarmMetric {
+    // The temperature shown next to the gizmo
+    Value,
+    Min,
+    Max,
+    Mean,
+}
+
+impl AlarmMetric {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AlarmMetric::Value => "Value",
+            AlarmMetric::Min => "Min",
+            AlarmMetric::Max => "Max",
+            AlarmMetric::Mean => "Mean",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AlarmSource {
+    // Any pixel of the image, i.e. the hottest pixel for Above and the coldest for Below
+    AnyPixel,
+    Gizmo { uuid: Uuid, metric: AlarmMetric },
+    // The fastest change of any pixel, rising or falling. The threshold is a rate,
+    // Kelvin per second stored as a Temp, see ChangeDetectionSettings.
+    AnyPixelChange,
+}
+
+impl AlarmSource {
+    pub fn is_rate(&self) -> bool {
+        matches!(self, AlarmSource::AnyPixelChange)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AlarmCondition {
+    Above,
+    Below,
+}
+
+impl AlarmCondition {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AlarmCondition::Above => "Above",
+            AlarmCondition::Below => "Below",
+        }
+    }
+}
+
+///
+/// e.g. "any pixel above 80 °C" or "mean of the area below 5 °C for 10 s".
+///
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AlarmRule {
+    pub uuid: Uuid,
+    pub enabled: bool,
+    pub name: String,
+    pub source: AlarmSource,
+    pub condition: AlarmCondition,
+    pub threshold: Temp,
+    // How long the condition has to hold before the alarm goes off
+    pub hold_secs: f32,
+    // Play the system alert sound when the alarm goes off
+    pub sound: bool,
+}
+
+impl AlarmRule {
+    pub fn new(name: String) -> Self {
+        Self {
+            uuid: Uuid::new_v4(),
+            enabled: true,
+            name,
+            source: AlarmSource::AnyPixel,
+            condition: AlarmCondition::Above,
+            threshold: Temp::from_celsius(80.0),
+            hold_secs: 0.0,
+            sound: false,
+        }
+    }
+
+    ///
+    /// The temperature the rule looks at, None if its gizmo has no result (e.g. was removed)
+    /// or the rates of change aren't known yet.
+    ///
+    fn observed_temperature(
+        &self,
+        image_range: TempRange,
+        gizmo_results: &HashMap<Uuid, GizmoResult>,
+        change_rate_kelvin_per_sec: Option<f32>,
+    ) -> Option<Temp> {
+        match self.source {
+            AlarmSource::AnyPixelChange => change_rate_kelvin_per_sec.map(Temp::new),
+            AlarmSource::AnyPixel => Some(match self.condition {
+                AlarmCondition::Above => image_range.max,
+                AlarmCondition::Below => image_range.min,
+            }),
+            AlarmSource::Gizmo { uuid, metric } => {
+                let result = gizmo_results.get(&uuid)?;
+                Some(match (metric, result.stats) {
+                    (AlarmMetric::Min, Some(stats)) => stats.min,
+                    (AlarmMetric::Max, Some(stats)) => stats.max,
+                    (AlarmMetric::Mean, Some(stats)) => stats.mean,
+                    _ => result.temperature,
+                })
+            }
+        }
+    }
+
+    fn is_violated(&self, temp: Temp) -> bool {
+        match self.condition {
+            AlarmCondition::Above => temp > self.threshold,
+            AlarmCondition::Below => temp < self.threshold,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ActiveAlarm {
+    pub rule_uuid: Uuid,
+    pub name: String,
+    pub source: AlarmSource,
+    // A rate for AnyPixelChange, see format_value
+    pub temperature: Temp,
+    pub condition: AlarmCondition,
+    pub threshold: Temp,
+    pub sound: bool,
+}
+
+impl ActiveAlarm {
+    ///
+    /// The temperature or threshold of the alarm in the unit, or the rate per second.
+    ///
+    pub fn value_to_unit(&self, value: Temp, unit: TemperatureUnit) -> f32 {
+        if self.source.is_rate() {
+            value.as_delta().to_unit(unit)
+        } else {
+            value.to_unit(unit)
+        }
+    }
+
+    pub fn format_value(&self, value: Temp, unit: TemperatureUnit, precision: usize) -> String {
+        if self.source.is_rate() {
+            format!("{}/s", value.as_delta().format(unit, precision))
+        } else {
+            format_temp(value, unit, precision)
+        }
+    }
+}
+
+///
+/// Owned by the capturer thread, keeps track of how long the condition of each rule has held.
+///
+pub struct AlarmEvaluator {
+    violated_since: HashMap<Uuid, Instant>,
+}
+
+impl AlarmEvaluator {
+    pub fn new() -> Self {
+        Self {
+            violated_since: HashMap::new(),
+        }
+    }
+
+    pub fn evaluate(
+        &mut self,
+        rules: &[AlarmRule],
+        image_range: TempRange,
+        gizmo_results: &HashMap<Uuid, GizmoResult>,
+        change_rate_kelvin_per_sec: Option<f32>,
+    ) -> Vec<ActiveAlarm> {
+        let now = Instant::now();
+        let mut active = vec![];
+        for rule in rules.iter().filter(|rule| rule.enabled) {
+            let temperature = rule
+                .observed_temperature(image_range, gizmo_results, change_rate_kelvin_per_sec)
+                .filter(|temp| rule.is_violated(*temp));
+            let Some(temperature) = temperature else {
+                self.violated_since.remove(&rule.uuid);
+                continue;
+            };
+
+            let since = *self.violated_since.entry(rule.uuid).or_insert(now);
+            if now.duration_since(since) >= Duration::from_secs_f32(rule.hold_secs.max(0.0)) {
+                active.push(ActiveAlarm {
+                    rule_uuid: rule.uuid,
+                    name: rule.name.clone(),
+                    source: rule.source,
+                    temperature,
+                    condition: rule.condition,
+                    threshold: rule.threshold,
+                    sound: rule.sound,
+                });
+            }
+        }
+        self.violated_since
+            .retain(|uuid, _| rules.iter().any(|rule| rule.enabled && rule.uuid == *uuid));
+        active
+    }
+}
+
+///
+/// Plays the alert sound of the system, using the tools which come with it.
+/// Failures are only logged, the banner is shown either way.
+///
+pub fn play_alarm_sound() {
+    #[cfg(target_os = "macos")]
+    let command = std::process::Command::new("afplay")
+        .arg("/System/Library/Sounds/Sosumi.aiff")
+        .spawn();
+    #[cfg(target_os = "windows")]
+    let command = std::process::Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            "[System.Media.SystemSounds]::Exclamation.Play()",
+        ])
+        .spawn();
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let command = std::process::Command::new("canberra-gtk-play")
+        .args(["-i", "dialog-warning"])
+        .spawn();
+
+    if let Err(err) = command {
+        tracing::warn!(error = %err, "Failed to play the alarm sound");
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct AlarmsFile {
+    version: u32,
+    rules: Vec<AlarmRule>,
+}
+
+pub fn alarms_path() -> PathBuf {
+    let mut path = dirs::config_dir().unwrap();
+    path.push("thermal-viewer");
+    path.push("alarms.json");
+    path
+}
+
+pub fn load_alarm_rules() -> Result<Vec<AlarmRule>> {
+    let path = alarms_path();
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+    let file: AlarmsFile = serde_json::from_reader(reader)?;
+    if file.version > ALARMS_VERSION {
+        return Err(anyhow!(
+            "Unknown alarms version {}. Please update the application.",
+            file.version
+        ));
+    }
+    Ok(file.rules)
+}
+
+pub fn save_alarm_rules(rules: &[AlarmRule]) -> Result<()> {
+    let path = alarms_path();
+    let dir_path = path.parent().unwrap();
+    if !dir_path.exists() {
+        fs::create_dir_all(dir_path)?;
+    }
+
+    let file = File::create(path)?;
+    let writer = BufWriter::new(file);
+    serde_json::to_writer_pretty(
+        writer,
+        &AlarmsFile {
+            version: ALARMS_VERSION,
+            rules: rules.to_vec(),
+        },
+    )?;
+    Ok(())
+}
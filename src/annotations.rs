@@ -0,0 +1,75 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use eframe::epaint::Color32;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub const DEFAULT_ANNOTATION_COLOR: Color32 = Color32::from_rgb(255, 230, 0);
+
+///
+/// Positions are in pixels of the thermal data, continuous rather than pixel centers,
+/// so annotations stay in place regardless of the upscaling of the image.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AnnotationKind {
+    Text { pos: [f32; 2], text: String },
+    // Points at `end`
+    Arrow { start: [f32; 2], end: [f32; 2] },
+    Freehand { points: Vec<[f32; 2]> },
+}
+
+///
+/// A label, arrow or drawing on the thermal view. Kept as a model alongside the settings
+/// rather than in the pixels, so it stays editable after being saved with a snapshot.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    pub uuid: Uuid,
+    pub kind: AnnotationKind,
+    pub color: Color32,
+}
+
+impl Annotation {
+    pub fn new(kind: AnnotationKind, color: Color32) -> Self {
+        Self {
+            uuid: Uuid::new_v4(),
+            kind,
+            color,
+        }
+    }
+
+    ///
+    /// The points the annotation is drawn through, e.g. for finding the one under the mouse.
+    ///
+    pub fn points(&self) -> Vec<[f32; 2]> {
+        match &self.kind {
+            AnnotationKind::Text { pos, .. } => vec![*pos],
+            AnnotationKind::Arrow { start, end } => vec![*start, *end],
+            AnnotationKind::Freehand { points } => points.clone(),
+        }
+    }
+}
+
+///
+/// Radiometric recordings have no room for settings, so their annotations are written
+/// next to them, e.g. "recording.tcrad" gets "recording.annotations.json".
+///
+pub fn annotations_sidecar_path(recording: &Path) -> PathBuf {
+    recording.with_extension("annotations.json")
+}
+
+pub fn save_annotations(path: &Path, annotations: &[Annotation]) -> Result<()> {
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(writer, annotations)?;
+    Ok(())
+}
+
+pub fn load_annotations(path: &Path) -> Result<Vec<Annotation>> {
+    let reader = BufReader::new(File::open(path)?);
+    Ok(serde_json::from_reader(reader)?)
+}
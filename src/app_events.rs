@@ -0,0 +1,17 @@
+use crate::{
+    keyboard_shortcuts::ShortcutAction, thermal_capturer::ThermalCapturerSettings, AppGlobalState,
+};
+
+///
+/// What the panes, menus and shortcuts ask of the app. Sent while the frame is drawn
+/// and handled once all the panes are, which is the only place the state changes.
+/// The panes only ever see the state read-only (see `PaneContext`), so none of them
+/// acts on state another pane is still drawing, and the capturer is only touched from one place.
+///
+pub enum AppEvent {
+    // Edits the settings, the capturer gets them once all events are handled
+    EditSettings(Box<dyn FnOnce(&mut ThermalCapturerSettings)>),
+    // Any other change of the state, e.g. opening a camera or saving the preferences
+    Update(Box<dyn FnOnce(&mut AppGlobalState)>),
+    Action(ShortcutAction),
+}
@@ -0,0 +1,257 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use log::Level;
+use once_cell::sync::Lazy;
+use tracing::{
+    field::{Field, Visit},
+    level_filters::LevelFilter,
+    span, Event, Metadata, Subscriber,
+};
+use tracing_log::{AsLog, LogTracer, NormalizeEvent};
+use tracing_subscriber::{
+    filter::filter_fn, layer::Context, prelude::*, registry::LookupSpan, EnvFilter, Layer,
+};
+
+// Messages kept for the log pane, the oldest are dropped first
+const MAX_LOG_ENTRIES: usize = 2000;
+
+// Messages of this app are kept down to Debug, the ones of its dependencies
+// (e.g. wgpu, which is chatty) only from Warn
+const APP_LOG_LEVEL: LevelFilter = LevelFilter::DEBUG;
+const DEPENDENCY_LOG_LEVEL: LevelFilter = LevelFilter::WARN;
+
+#[derive(Clone)]
+pub struct LogEntry {
+    pub time: DateTime<Local>,
+    pub level: Level,
+    // Module which logged the message, e.g. "thermal_cat::thermal_capturer"
+    pub target: String,
+    // Spans the message was logged in, outermost first, e.g. `capturer{camera=P2 Pro}`
+    pub spans: String,
+    // The message followed by its fields, e.g. "Frames received width=256 height=192"
+    pub message: String,
+}
+
+impl LogEntry {
+    pub fn to_line(&self) -> String {
+        let time = self.time.format("%Y-%m-%d %H:%M:%S%.3f");
+        if self.spans.is_empty() {
+            format!(
+                "{} {:<5} {}: {}",
+                time, self.level, self.target, self.message
+            )
+        } else {
+            format!(
+                "{} {:<5} {}: {}: {}",
+                time, self.level, self.target, self.spans, self.message
+            )
+        }
+    }
+}
+
+#[derive(Default)]
+struct LogState {
+    entries: VecDeque<LogEntry>,
+    // Every message is also appended to this file while it's set
+    file: Option<(PathBuf, BufWriter<File>)>,
+}
+
+static LOG_STATE: Lazy<Mutex<LogState>> = Lazy::new(Default::default);
+
+fn is_captured(metadata: &Metadata<'_>) -> bool {
+    let max_level = if metadata.target().starts_with("thermal_cat") {
+        APP_LOG_LEVEL
+    } else {
+        DEPENDENCY_LOG_LEVEL
+    };
+    *metadata.level() <= max_level
+}
+
+///
+/// Collects the message and the other fields of an event or span as text.
+///
+#[derive(Default)]
+struct FieldsText {
+    message: String,
+    fields: String,
+}
+
+impl Visit for FieldsText {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        match field.name() {
+            "message" => self.message = format!("{:?}", value),
+            // where messages of the log crate came from, already in their metadata
+            name if name.starts_with("log.") => {}
+            name => {
+                if !self.fields.is_empty() {
+                    self.fields.push(' ');
+                }
+                self.fields.push_str(&format!("{}={:?}", name, value));
+            }
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        // without the quotes of Debug
+        self.record_debug(field, &format_args!("{}", value));
+    }
+}
+
+impl FieldsText {
+    fn into_message(self) -> String {
+        match (self.message.is_empty(), self.fields.is_empty()) {
+            (_, true) => self.message,
+            (true, false) => self.fields,
+            (false, false) => format!("{} {}", self.message, self.fields),
+        }
+    }
+}
+
+// Fields of a span, formatted once when it's created
+struct SpanFields(String);
+
+///
+/// Keeps the recent messages for the log pane and the log file. Messages of the log crate,
+/// which the dependencies use, arrive as events through tracing-log.
+///
+struct CaptureLayer;
+
+impl<S> Layer<S> for CaptureLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let mut fields = FieldsText::default();
+        attrs.record(&mut fields);
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut()
+                .insert(SpanFields(fields.into_message()));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let normalized = event.normalized_metadata();
+        let metadata = normalized.as_ref().unwrap_or_else(|| event.metadata());
+
+        let spans = ctx
+            .event_scope(event)
+            .map(|scope| {
+                scope
+                    .from_root()
+                    .map(|span| match span.extensions().get::<SpanFields>() {
+                        Some(SpanFields(fields)) if !fields.is_empty() => {
+                            format!("{}{{{}}}", span.name(), fields)
+                        }
+                        _ => span.name().to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(":")
+            })
+            .unwrap_or_default();
+        let mut fields = FieldsText::default();
+        event.record(&mut fields);
+
+        let entry = LogEntry {
+            time: Local::now(),
+            level: metadata.level().as_log(),
+            target: metadata.target().to_string(),
+            spans,
+            message: fields.into_message(),
+        };
+        let mut state = LOG_STATE.lock().unwrap();
+        if let Some((_, file)) = state.file.as_mut() {
+            // flushed right away, the log is most useful right before a crash
+            if writeln!(file, "{}", entry.to_line())
+                .and_then(|_| file.flush())
+                .is_err()
+            {
+                // can't be logged, it would come right back here
+                eprintln!("Failed to write to the log file, stopping");
+                state.file = None;
+            }
+        }
+        state.entries.push_back(entry);
+        while state.entries.len() > MAX_LOG_ENTRIES {
+            state.entries.pop_front();
+        }
+    }
+}
+
+///
+/// Installs the subscriber, call once at startup before anything is logged.
+/// Messages are kept for the log pane, and printed to stderr as configured by `RUST_LOG`.
+/// The log crate's messages are passed on to it by tracing-log.
+///
+pub fn init_logging() {
+    let stderr_filter = EnvFilter::from_default_env();
+    let max_level = stderr_filter
+        .max_level_hint()
+        .unwrap_or(LevelFilter::TRACE)
+        .max(APP_LOG_LEVEL);
+    let subscriber = tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(std::io::stderr)
+                .with_filter(stderr_filter),
+        )
+        .with(CaptureLayer.with_filter(filter_fn(is_captured)));
+    if tracing::subscriber::set_global_default(subscriber).is_ok() {
+        // the levels no layer wants are dropped before the messages are formatted
+        if let Err(err) = LogTracer::init_with_filter(max_level.as_log()) {
+            eprintln!("Failed to capture the messages of the log crate: {}", err);
+        }
+    }
+}
+
+///
+/// The kept messages at `min_level` or more severe, oldest first.
+///
+pub fn recent_log_entries(min_level: Level) -> Vec<LogEntry> {
+    LOG_STATE
+        .lock()
+        .unwrap()
+        .entries
+        .iter()
+        .filter(|entry| entry.level <= min_level)
+        .cloned()
+        .collect()
+}
+
+pub fn clear_log_entries() {
+    LOG_STATE.lock().unwrap().entries.clear();
+}
+
+///
+/// Starts appending the messages to `path`, beginning with the ones kept so far.
+///
+pub fn start_log_file(path: &Path) -> Result<()> {
+    let mut file = BufWriter::new(File::options().create(true).append(true).open(path)?);
+    let mut state = LOG_STATE.lock().unwrap();
+    for entry in state.entries.iter() {
+        writeln!(file, "{}", entry.to_line())?;
+    }
+    file.flush()?;
+    state.file = Some((path.to_path_buf(), file));
+    Ok(())
+}
+
+pub fn stop_log_file() {
+    LOG_STATE.lock().unwrap().file = None;
+}
+
+pub fn log_file_path() -> Option<PathBuf> {
+    LOG_STATE
+        .lock()
+        .unwrap()
+        .file
+        .as_ref()
+        .map(|(path, _)| path.clone())
+}
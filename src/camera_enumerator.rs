@@ -4,11 +4,16 @@ use eframe::{
     egui::TextFormat,
     epaint::{text::LayoutJob, Color32, FontFamily, FontId},
 };
-use nokhwa::{native_api_backend, query, utils::CameraInfo};
+use nokhwa::{
+    native_api_backend, query,
+    utils::{CameraIndex, CameraInfo},
+};
 
 use once_cell::sync::Lazy;
 use regex::Regex;
 
+use crate::camera_adapter::CameraTransport;
+
 #[derive(Debug, Clone)]
 pub struct EnumerationError {
     message: String,
@@ -25,6 +30,7 @@ impl Error for EnumerationError {}
 //
 // Contains extended information about a camera, including the USB PID/VID.
 //
+#[derive(Clone)]
 pub struct EnumeratedCamera {
     pub info: CameraInfo,
     pub usb_vid_pid: Option<(u16, u16)>,
@@ -51,6 +57,17 @@ impl EnumeratedCamera {
                     ..Default::default()
                 },
             );
+            if !shorten {
+                let (width, height) = adapter.capabilities().resolution;
+                job.append(
+                    &format!("\n{}×{}", width, height),
+                    0.0,
+                    TextFormat {
+                        color: Color32::GRAY,
+                        ..Default::default()
+                    },
+                );
+            }
         }
         if let Some((vid, pid)) = self.usb_vid_pid {
             if !shorten {
@@ -68,6 +85,16 @@ impl EnumeratedCamera {
 
         job
     }
+
+    ///
+    /// Whether the camera generates its frames instead of filming anything.
+    /// Demo devices are only opened when picked by the user.
+    ///
+    pub fn is_demo(&self) -> bool {
+        self.adapter
+            .as_ref()
+            .is_some_and(|adapter| adapter.transport() == CameraTransport::Synthetic)
+    }
 }
 
 pub fn enumerate_cameras() -> Result<Vec<EnumeratedCamera>, anyhow::Error> {
@@ -77,29 +104,89 @@ pub fn enumerate_cameras() -> Result<Vec<EnumeratedCamera>, anyhow::Error> {
 
     let nokhwa_cameras = query(backend)?;
 
-    return Ok::<Vec<EnumeratedCamera>, anyhow::Error>(
+    Ok::<Vec<EnumeratedCamera>, anyhow::Error>(
         nokhwa_cameras
             .into_iter()
             .map(|info| {
                 let usb_vid_pid = get_vid_pid_for_camera(&info);
-                let adapter = crate::camera_adapter::CAMERA_ADAPTERS
-                    .iter()
-                    .find(|adapter| {
-                        if let Some((vid, pid)) = usb_vid_pid {
-                            adapter.usb_vid_pid() == (vid, pid)
-                        } else {
-                            false
-                        }
-                    })
-                    .cloned();
+                let adapter = usb_vid_pid.and_then(|(vid, pid)| {
+                    let mut candidates = crate::camera_adapter::CAMERA_ADAPTERS
+                        .iter()
+                        .filter(|adapter| {
+                            adapter.transport() == CameraTransport::Uvc
+                                && adapter.usb_vid_pid() == (vid, pid)
+                        })
+                        .peekable();
+                    // only read the descriptor of devices an adapter is interested in
+                    candidates.peek()?;
+                    let product = crate::camera_adapter::usb_product_string(vid, pid);
+                    candidates
+                        .find(|adapter| adapter.matches_usb_product(product.as_deref()))
+                        .cloned()
+                });
                 EnumeratedCamera {
                     info,
                     usb_vid_pid,
                     adapter,
                 }
             })
+            .chain(enumerate_usb_cameras())
+            .chain(demo_cameras())
             .collect(),
-    );
+    )
+}
+
+///
+/// Cameras which aren't UVC devices, so nokhwa doesn't list them. They are looked for
+/// by the PID/VID of their adapters, at most one per model.
+///
+fn enumerate_usb_cameras() -> Vec<EnumeratedCamera> {
+    crate::camera_adapter::CAMERA_ADAPTERS
+        .iter()
+        .filter(|adapter| adapter.transport() == CameraTransport::Usb)
+        .filter_map(|adapter| {
+            let (vid, pid) = adapter.usb_vid_pid();
+            let handle = rusb::open_device_with_vid_pid(vid, pid)?;
+            let product = handle
+                .device()
+                .device_descriptor()
+                .ok()
+                .and_then(|descriptor| handle.read_product_string_ascii(&descriptor).ok());
+            if !adapter.matches_usb_product(product.as_deref()) {
+                return None;
+            }
+            Some(EnumeratedCamera {
+                info: CameraInfo::new(
+                    &product.unwrap_or_else(|| adapter.name()),
+                    "USB",
+                    "",
+                    CameraIndex::String(adapter.short_name()),
+                ),
+                usb_vid_pid: Some((vid, pid)),
+                adapter: Some(adapter.clone()),
+            })
+        })
+        .collect()
+}
+
+///
+/// The synthetic cameras, listed last so a connected camera is preferred.
+///
+fn demo_cameras() -> Vec<EnumeratedCamera> {
+    crate::camera_adapter::CAMERA_ADAPTERS
+        .iter()
+        .filter(|adapter| adapter.transport() == CameraTransport::Synthetic)
+        .map(|adapter| EnumeratedCamera {
+            info: CameraInfo::new(
+                "Demo Device",
+                "Synthetic",
+                "",
+                CameraIndex::String(adapter.short_name()),
+            ),
+            usb_vid_pid: None,
+            adapter: Some(adapter.clone()),
+        })
+        .collect()
 }
 
 #[cfg(target_os = "linux")]
@@ -0,0 +1,147 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use eframe::egui;
+use uuid::Uuid;
+
+use crate::{
+    camera_enumerator::EnumeratedCamera,
+    thermal_capturer::{
+        ThermalCapturer, ThermalCapturerEvent, ThermalCapturerResult, ThermalCapturerSettings,
+    },
+    thermal_source::{camera_source::CameraSource, ThermalSource},
+};
+
+///
+/// A camera opened next to the main one, with its own capturer and settings.
+///
+pub struct ManagedCamera {
+    pub uuid: Uuid,
+    pub name: String,
+    // None once the capturer has stopped
+    pub capturer: Option<ThermalCapturer>,
+    pub settings: ThermalCapturerSettings,
+    pub last_result: Option<Box<ThermalCapturerResult>>,
+    pub error: Option<String>,
+}
+
+impl ManagedCamera {
+    pub fn apply_settings(&mut self) {
+        if let Some(capturer) = self.capturer.as_mut() {
+            capturer.set_settings(self.settings.clone());
+        }
+    }
+}
+
+///
+/// Additional cameras displayed simultaneously, each in its own pane.
+/// The main camera (AppGlobalState::thermal_capturer_inst) drives the measurements,
+/// recordings and the rest of the panes, the additional ones are only displayed.
+///
+#[derive(Default)]
+pub struct CameraManager {
+    cameras: Vec<ManagedCamera>,
+    // Opened cameras the app hasn't created a pane for yet
+    opened: Vec<Uuid>,
+}
+
+impl CameraManager {
+    ///
+    /// Opens the camera with a capturer of its own, starting from `settings`.
+    ///
+    pub fn open(
+        &mut self,
+        camera: &EnumeratedCamera,
+        settings: ThermalCapturerSettings,
+        ctx: &egui::Context,
+    ) -> Result<Uuid> {
+        let adapter = camera
+            .adapter
+            .clone()
+            .ok_or_else(|| anyhow!("The camera is not supported"))?;
+        let source = CameraSource::new(camera.info.index().clone(), adapter.clone());
+        Ok(self.open_source(
+            Box::new(source),
+            format!("{} ({})", adapter.name(), camera.info.human_name()),
+            settings,
+            ctx,
+        ))
+    }
+
+    ///
+    /// Opens any other live source, e.g. a network camera, like `open`.
+    ///
+    pub fn open_source(
+        &mut self,
+        source: Box<dyn ThermalSource>,
+        name: String,
+        settings: ThermalCapturerSettings,
+        ctx: &egui::Context,
+    ) -> Uuid {
+        let cloned_ctx = ctx.clone();
+        let mut capturer = ThermalCapturer::new(
+            source,
+            settings.clone(),
+            Arc::new(move || {
+                cloned_ctx.request_repaint(); // repaint so that the result can be read out
+            }),
+        );
+        capturer.start();
+
+        let uuid = Uuid::new_v4();
+        self.cameras.push(ManagedCamera {
+            uuid,
+            name,
+            capturer: Some(capturer),
+            settings,
+            last_result: None,
+            error: None,
+        });
+        self.opened.push(uuid);
+        uuid
+    }
+
+    pub fn close(&mut self, uuid: Uuid) {
+        self.cameras.retain(|camera| camera.uuid != uuid);
+    }
+
+    pub fn get(&self, uuid: Uuid) -> Option<&ManagedCamera> {
+        self.cameras.iter().find(|camera| camera.uuid == uuid)
+    }
+
+    pub fn get_mut(&mut self, uuid: Uuid) -> Option<&mut ManagedCamera> {
+        self.cameras.iter_mut().find(|camera| camera.uuid == uuid)
+    }
+
+    ///
+    /// Returns the cameras opened since the last call, so the app can create their panes.
+    ///
+    pub fn take_opened(&mut self) -> Vec<Uuid> {
+        std::mem::take(&mut self.opened)
+    }
+
+    ///
+    /// Drains the events of all capturers, called once per UI frame.
+    ///
+    pub fn update(&mut self) {
+        for camera in self.cameras.iter_mut() {
+            while let Some(event) = camera.capturer.as_mut().and_then(|c| c.try_recv_event()) {
+                match event {
+                    ThermalCapturerEvent::Frame(result) => {
+                        if let (Some(previous), Some(capturer)) =
+                            (camera.last_result.replace(result), camera.capturer.as_mut())
+                        {
+                            capturer.recycle_result(previous);
+                        }
+                    }
+                    ThermalCapturerEvent::Error(err) => camera.error = Some(format!("{:#}", err)),
+                    ThermalCapturerEvent::Disconnected(_) => {}
+                    ThermalCapturerEvent::Stopped(reason) => {
+                        camera.error = reason.map(|err| format!("{:#}", err));
+                        camera.capturer = None;
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use eframe::{
+    egui::{self, Button, RichText},
+    epaint::Color32,
+};
+use nokhwa::utils::CameraIndex;
+
+use crate::{
+    camera_adapter::CameraAdapter,
+    camera_enumerator::{enumerate_cameras, EnumeratedCamera},
+    thermal_source::camera_source::camera_id,
+};
+
+// How long the last used camera is waited for at startup, e.g. while the system
+// is still setting up its USB devices, before the user is asked to pick a camera
+const LAST_CAMERA_TIMEOUT: Duration = Duration::from_secs(5);
+const LAST_CAMERA_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+///
+/// A supported camera found by the enumeration, with the details the dialog lists.
+///
+pub struct DetectedCamera {
+    pub index: CameraIndex,
+    pub adapter: Arc<dyn CameraAdapter>,
+    // See `camera_id`
+    pub id: String,
+    // Read from the USB descriptor when listed, None if the camera has none
+    pub serial: Option<String>,
+    pub is_demo: bool,
+}
+
+impl DetectedCamera {
+    fn from_enumerated(camera: &EnumeratedCamera) -> Option<Self> {
+        let adapter = camera.adapter.clone()?;
+        Some(Self {
+            index: camera.info.index().clone(),
+            id: camera_id(adapter.as_ref()),
+            serial: adapter.serial_number(),
+            is_demo: camera.is_demo(),
+            adapter,
+        })
+    }
+}
+
+///
+/// Lists the connected supported cameras to pick the one to open, at startup when there
+/// are several of them, and from the menu to switch cameras while the app runs.
+///
+#[derive(Default)]
+pub struct CameraSelectionDialog {
+    pub open: bool,
+    // Connected cameras first, the demo devices last
+    cameras: Vec<DetectedCamera>,
+    selected: usize,
+    error: Option<String>,
+    // Set at startup until the camera to open is known
+    startup: Option<StartupSelection>,
+    // The camera opened at startup hasn't delivered a frame yet, the dialog is shown if it fails
+    startup_camera_opening: bool,
+}
+
+struct StartupSelection {
+    // Camera used last time, waited for until LAST_CAMERA_TIMEOUT
+    last_camera_id: Option<String>,
+    started: Instant,
+    last_poll: Option<Instant>,
+}
+
+impl CameraSelectionDialog {
+    ///
+    /// Enumerates the cameras again, keeping the selection if that camera is still there.
+    ///
+    pub fn refresh(&mut self) {
+        let selected = self
+            .cameras
+            .get(self.selected)
+            .map(|camera| camera.index.clone());
+        match enumerate_cameras() {
+            Ok(cameras) => {
+                self.cameras = cameras
+                    .iter()
+                    .filter_map(DetectedCamera::from_enumerated)
+                    .collect();
+                self.error = None;
+            }
+            Err(err) => {
+                tracing::error!(error = %format_args!("{:#}", err), "Failed to enumerate cameras");
+                self.cameras = vec![];
+                self.error = Some(format!("Failed to enumerate cameras: {}", err));
+            }
+        }
+        self.selected = selected
+            .and_then(|index| self.cameras.iter().position(|camera| camera.index == index))
+            .unwrap_or(0);
+    }
+
+    ///
+    /// The supported cameras which are really connected, without the demo devices.
+    ///
+    pub fn connected_cameras(&self) -> impl Iterator<Item = &DetectedCamera> {
+        self.cameras.iter().filter(|camera| !camera.is_demo)
+    }
+
+    ///
+    /// Starts looking for the camera to open at startup, see `poll_startup`.
+    ///
+    pub fn start(&mut self, last_camera_id: Option<String>) {
+        self.startup = Some(StartupSelection {
+            last_camera_id,
+            started: Instant::now(),
+            last_poll: None,
+        });
+    }
+
+    ///
+    /// Called every frame while the app starts, returns the camera to open once it's known:
+    /// the last used one as soon as it's connected, otherwise the only connected camera.
+    /// With several cameras, or the last one missing for LAST_CAMERA_TIMEOUT, the dialog is shown instead.
+    ///
+    pub fn poll_startup(
+        &mut self,
+        ctx: &egui::Context,
+    ) -> Option<(CameraIndex, Arc<dyn CameraAdapter>)> {
+        let startup = self.startup.as_mut()?;
+        if startup
+            .last_poll
+            .is_none_or(|last_poll| last_poll.elapsed() >= LAST_CAMERA_POLL_INTERVAL)
+        {
+            startup.last_poll = Some(Instant::now());
+            self.refresh();
+        }
+
+        let startup = self.startup.as_ref()?;
+        let camera = match startup.last_camera_id.as_ref() {
+            Some(last_camera_id) => {
+                match self
+                    .connected_cameras()
+                    .position(|camera| &camera.id == last_camera_id)
+                {
+                    Some(position) => self.connected_cameras().nth(position),
+                    None if startup.started.elapsed() < LAST_CAMERA_TIMEOUT => {
+                        ctx.request_repaint_after(LAST_CAMERA_POLL_INTERVAL);
+                        return None;
+                    }
+                    None => {
+                        tracing::warn!(%last_camera_id, "The last used camera is not connected");
+                        self.single_connected_camera()
+                    }
+                }
+            }
+            None => self.single_connected_camera(),
+        }
+        .map(|camera| (camera.index.clone(), camera.adapter.clone()));
+
+        self.startup = None;
+        match camera {
+            Some(camera) => {
+                self.startup_camera_opening = true;
+                Some(camera)
+            }
+            None => {
+                // with none connected, the setup pane opens the first one plugged in
+                let any_connected = self.connected_cameras().next().is_some();
+                self.open = any_connected;
+                None
+            }
+        }
+    }
+
+    fn single_connected_camera(&self) -> Option<&DetectedCamera> {
+        let mut cameras = self.connected_cameras();
+        match (cameras.next(), cameras.next()) {
+            (Some(camera), None) => Some(camera),
+            _ => None,
+        }
+    }
+
+    ///
+    /// The camera opened at startup delivered a frame, or failed to open
+    /// in which case the user is asked to pick another one.
+    ///
+    pub fn startup_camera_finished(&mut self, failed: bool) {
+        if std::mem::take(&mut self.startup_camera_opening) && failed {
+            self.refresh();
+            self.open = true;
+        }
+    }
+
+    ///
+    /// Shows the dialog while it's open, returns the camera to open once one was picked.
+    ///
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<(CameraIndex, Arc<dyn CameraAdapter>)> {
+        if let Some(last_camera_id) = self
+            .startup
+            .as_ref()
+            .and_then(|startup| startup.last_camera_id.clone())
+        {
+            egui::Window::new("Opening Camera")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(format!("Waiting for {} to be connected...", last_camera_id));
+                    });
+                    if ui.button("Select Another Camera").clicked() {
+                        self.startup = None;
+                        self.open = true;
+                    }
+                });
+        }
+
+        let mut open = self.open;
+        let mut picked = None;
+        egui::Window::new("Select Camera")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if self.cameras.is_empty() {
+                    ui.label("No supported camera is connected.");
+                }
+                egui::Grid::new("camera_selection_grid")
+                    .num_columns(3)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (i, camera) in self.cameras.iter().enumerate() {
+                            let response =
+                                ui.selectable_label(self.selected == i, camera.adapter.name());
+                            if response.clicked() {
+                                self.selected = i;
+                            }
+                            if response.double_clicked() {
+                                picked = Some(i);
+                            }
+                            let (width, height) = camera.adapter.capabilities().resolution;
+                            ui.label(format!("{}×{}", width, height));
+                            match (&camera.serial, camera.is_demo) {
+                                (_, true) => ui.weak("Generated frames"),
+                                (Some(serial), false) => ui.label(format!("S/N {}", serial)),
+                                (None, false) => ui.weak("No serial number"),
+                            };
+                            ui.end_row();
+                        }
+                    });
+                if let Some(error) = &self.error {
+                    ui.colored_label(Color32::RED, error);
+                }
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(
+                            self.selected < self.cameras.len(),
+                            Button::new(RichText::new("Open").strong()),
+                        )
+                        .clicked()
+                    {
+                        picked = Some(self.selected);
+                    }
+                    if ui
+                        .button("Refresh")
+                        .on_hover_text("Look for cameras again, e.g. after plugging one in")
+                        .clicked()
+                    {
+                        self.refresh();
+                    }
+                });
+            });
+        self.open = open && picked.is_none();
+
+        let camera = self.cameras.get(picked?)?;
+        Some((camera.index.clone(), camera.adapter.clone()))
+    }
+}
@@ -6,15 +6,16 @@ use eframe::{
     epaint::Vec2,
 };
 use egui_plot::{Line, MarkerShape, Plot, PlotBounds, PlotImage, PlotPoint, PlotPoints, Points};
+use serde::{Deserialize, Serialize};
 
 use crate::{
     temperature::{TempRange, TemperatureUnit},
-    thermal_capturer::ThermalCapturerSettings,
+    thermal_gradient::ThermalGradient,
     types::image_rotation::ImageRotation,
     util::rotate_image,
 };
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum CurvePoint {
     Sharp(f32, f32),
     Smooth(f32, f32),
@@ -65,7 +66,7 @@ impl CurvePoint {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DynamicRangeCurve {
     pub points: Vec<CurvePoint>,
 }
@@ -79,7 +80,7 @@ impl Default for DynamicRangeCurve {
 }
 
 impl DynamicRangeCurve {
-    pub fn is_default(&mut self) -> bool {
+    pub fn is_default(&self) -> bool {
         self.points.len() == 2
             && self.points[0] == CurvePoint::Smooth(0.0, 0.0)
             && self.points[1] == CurvePoint::Smooth(1.0, 1.0)
@@ -220,16 +221,14 @@ impl CurveEditorResponse {
 pub fn dynamic_curve_editor(
     ui: &mut Ui,
     id: impl std::hash::Hash,
-    settings: &mut ThermalCapturerSettings,
+    curve: &mut DynamicRangeCurve,
+    gradient: &ThermalGradient,
     current_range: TempRange,
     unit: TemperatureUnit,
 ) -> CurveEditorResponse {
     let mut response = CurveEditorResponse::default();
     let memory_id = Id::new(id);
 
-    let curve = &mut settings.dynamic_range_curve;
-    let gradient = &settings.gradient;
-
     ui.with_layout(
         Layout::right_to_left(Align::Min).with_cross_justify(false),
         |ui| {
@@ -0,0 +1,241 @@
+use eframe::epaint::{Color32, ColorImage};
+use serde::{Deserialize, Serialize};
+
+pub const MIN_FUSION_SCALE: f32 = 0.1;
+pub const MAX_FUSION_SCALE: f32 = 2.0;
+pub const MAX_EDGE_STRENGTH: f32 = 8.0;
+pub const MIN_PIP_SIZE: f32 = 0.1;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FusionMode {
+    // The thermal image over the visible one, with the given opacity
+    #[default]
+    Blend,
+    // The edges of the visible image drawn into the thermal one (MSX-style),
+    // the colors stay those of the temperatures
+    Edges,
+}
+
+impl FusionMode {
+    pub const ALL: [FusionMode; 2] = [FusionMode::Blend, FusionMode::Edges];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FusionMode::Blend => "Blend",
+            FusionMode::Edges => "Edge enhancement",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DisplayMode {
+    Thermal,
+    // The visible image aligned onto the thermal one, at its resolution
+    Visible,
+    // Combined according to FusionSettings::mode
+    #[default]
+    Fused,
+    // The visible image with the thermal one in a movable insert
+    PictureInPicture,
+}
+
+impl DisplayMode {
+    pub const ALL: [DisplayMode; 4] = [
+        DisplayMode::Thermal,
+        DisplayMode::Visible,
+        DisplayMode::Fused,
+        DisplayMode::PictureInPicture,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DisplayMode::Thermal => "Thermal only",
+            DisplayMode::Visible => "Visible only",
+            DisplayMode::Fused => "Fused",
+            DisplayMode::PictureInPicture => "Picture in picture",
+        }
+    }
+}
+
+///
+/// How the image of the visible-light camera is combined with the thermal image.
+///
+/// The two cameras sit next to each other and have different fields of view,
+/// so the thermal image is placed onto the visible one with an offset and a scale.
+///
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FusionSettings {
+    pub display: DisplayMode,
+    pub mode: FusionMode,
+    // Blend: 1 shows only the thermal image, 0 only the visible one
+    pub opacity: f32,
+    // Edges: how much of the visible detail is added onto the thermal colors
+    pub edge_strength: f32,
+    // Center of the thermal image within the visible one, relative to the visible center,
+    // as fractions of the visible width and height
+    pub offset: (f32, f32),
+    // Width of the thermal field of view as a fraction of the visible one
+    pub scale: f32,
+    // Picture in picture: top left corner of the thermal insert
+    // as fractions of the displayed visible image
+    pub pip_position: (f32, f32),
+    // Picture in picture: width of the thermal insert as a fraction of the visible image,
+    // the height follows the aspect ratio of the thermal image
+    pub pip_size: f32,
+}
+
+impl Default for FusionSettings {
+    fn default() -> Self {
+        Self {
+            display: DisplayMode::default(),
+            mode: FusionMode::default(),
+            opacity: 0.6,
+            edge_strength: 2.0,
+            offset: (0.0, 0.0),
+            scale: 0.8,
+            pip_position: (0.6, 0.6),
+            pip_size: 0.35,
+        }
+    }
+}
+
+impl FusionSettings {
+    ///
+    /// Combines the images at the resolution of the thermal one, so the measurements
+    /// drawn over it stay in place. In picture in picture the thermal image is kept as is,
+    /// the display puts it over the visible one.
+    ///
+    pub fn compose(&self, thermal: ColorImage, visible: &ColorImage) -> ColorImage {
+        let [width, height] = thermal.size;
+        if width == 0 || height == 0 || visible.size[0] == 0 || visible.size[1] == 0 {
+            return thermal;
+        }
+        let fusion_mode = match self.display {
+            DisplayMode::Thermal | DisplayMode::PictureInPicture => return thermal,
+            DisplayMode::Visible => None,
+            DisplayMode::Fused => Some(self.mode),
+        };
+        let aligned = self.align(visible, width, height);
+
+        let pixels = match fusion_mode {
+            None => aligned
+                .iter()
+                .map(|visible| visible.unwrap_or(Color32::BLACK))
+                .collect(),
+            Some(FusionMode::Blend) => {
+                let opacity = self.opacity.clamp(0.0, 1.0);
+                thermal
+                    .pixels
+                    .iter()
+                    .zip(aligned.iter())
+                    .map(|(thermal, visible)| match visible {
+                        Some(visible) => lerp_color(*visible, *thermal, opacity),
+                        None => *thermal,
+                    })
+                    .collect()
+            }
+            Some(FusionMode::Edges) => {
+                let luminance: Vec<Option<f32>> = aligned
+                    .iter()
+                    .map(|visible| visible.map(luminance))
+                    .collect();
+                let strength = self.edge_strength.clamp(0.0, MAX_EDGE_STRENGTH);
+                thermal
+                    .pixels
+                    .iter()
+                    .enumerate()
+                    .map(|(i, thermal)| {
+                        match high_pass(&luminance, width, height, i % width, i / width) {
+                            Some(edge) => add_to_color(*thermal, edge * strength),
+                            None => *thermal,
+                        }
+                    })
+                    .collect()
+            }
+        };
+
+        ColorImage {
+            size: thermal.size,
+            pixels,
+        }
+    }
+
+    ///
+    /// Samples the visible image under every pixel of the thermal one,
+    /// None where the thermal image reaches beyond the visible one.
+    ///
+    fn align(&self, visible: &ColorImage, width: usize, height: usize) -> Vec<Option<Color32>> {
+        let [visible_width, visible_height] = visible.size;
+        let scale = self.scale.clamp(MIN_FUSION_SCALE, MAX_FUSION_SCALE);
+        // visible pixels per thermal pixel, the same in both directions to keep the aspect ratio
+        let step = scale * visible_width as f32 / width as f32;
+        let center_x = visible_width as f32 * (0.5 + self.offset.0);
+        let center_y = visible_height as f32 * (0.5 + self.offset.1);
+
+        let mut aligned = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let vx = center_x + (x as f32 + 0.5 - width as f32 / 2.0) * step - 0.5;
+                let vy = center_y + (y as f32 + 0.5 - height as f32 / 2.0) * step - 0.5;
+                aligned.push(sample_bilinear(visible, vx, vy));
+            }
+        }
+        aligned
+    }
+}
+
+fn sample_bilinear(image: &ColorImage, x: f32, y: f32) -> Option<Color32> {
+    let [width, height] = image.size;
+    if x < -0.5 || y < -0.5 || x > width as f32 - 0.5 || y > height as f32 - 0.5 {
+        return None;
+    }
+    let x = x.clamp(0.0, (width - 1) as f32);
+    let y = y.clamp(0.0, (height - 1) as f32);
+    let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+    let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+    let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+    let pixel = |x: usize, y: usize| image.pixels[y * width + x];
+    let top = lerp_color(pixel(x0, y0), pixel(x1, y0), fx);
+    let bottom = lerp_color(pixel(x0, y1), pixel(x1, y1), fx);
+    Some(lerp_color(top, bottom, fy))
+}
+
+fn lerp_color(a: Color32, b: Color32, t: f32) -> Color32 {
+    let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    Color32::from_rgb(lerp(a.r(), b.r()), lerp(a.g(), b.g()), lerp(a.b(), b.b()))
+}
+
+fn luminance(color: Color32) -> f32 {
+    0.299 * color.r() as f32 + 0.587 * color.g() as f32 + 0.114 * color.b() as f32
+}
+
+///
+/// Difference between the pixel and the mean of its 3×3 neighbourhood,
+/// positive on the bright side of an edge.
+///
+fn high_pass(
+    luminance: &[Option<f32>],
+    width: usize,
+    height: usize,
+    x: usize,
+    y: usize,
+) -> Option<f32> {
+    let center = luminance[y * width + x]?;
+    let mut sum = 0.0;
+    let mut count = 0;
+    for ny in y.saturating_sub(1)..=(y + 1).min(height - 1) {
+        for nx in x.saturating_sub(1)..=(x + 1).min(width - 1) {
+            if let Some(value) = luminance[ny * width + nx] {
+                sum += value;
+                count += 1;
+            }
+        }
+    }
+    Some(center - sum / count as f32)
+}
+
+fn add_to_color(color: Color32, amount: f32) -> Color32 {
+    let add = |channel: u8| (channel as f32 + amount).round().clamp(0.0, 255.0) as u8;
+    Color32::from_rgb(add(color.r()), add(color.g()), add(color.b()))
+}
@@ -1,23 +1,273 @@
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::PathBuf,
+};
+
+use anyhow::Result;
 use eframe::epaint::{Color32, Hsva};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::{temperature::Temp, thermal_data::ThermalDataPos};
+use crate::{
+    temperature::{Temp, TemperatureUnit},
+    thermal_data::{ThermalData, ThermalDataPos, ThermalDataRect},
+    util::pathify_string,
+};
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub enum GizmoKind {
-    Root { children: Vec<Gizmo> },
+    Root {
+        children: Vec<Gizmo>,
+    },
     MaxTemp,
     MinTemp,
-    TempAt { pos: ThermalDataPos },
+    TempAt {
+        pos: ThermalDataPos,
+    },
+    // Reports the hottest point inside of the region. With an emissivity set,
+    // the pixels of the region are corrected with it instead of the global emissivity.
+    Rect {
+        rect: ThermalDataRect,
+        emissivity: Option<f32>,
+    },
+    // Reports the hottest point along the line, both ends inclusive
+    Line {
+        start: ThermalDataPos,
+        end: ThermalDataPos,
+    },
+    // Reports the hottest point inside of the ellipse inscribed in `rect`
+    Ellipse {
+        rect: ThermalDataRect,
+    },
 }
 
-#[derive(Clone)]
+///
+/// A point of a gizmo which can be grabbed with the mouse.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GizmoHandle {
+    // Moves the whole gizmo
+    Move,
+    LineStart,
+    LineEnd,
+    // Resizes a rect or ellipse by one of its corners
+    Corner { left: bool, top: bool },
+}
+
+impl GizmoKind {
+    ///
+    /// Replaces `pixels` with the pixels measured by this gizmo, for an image of the given size.
+    /// Gizmos which search the whole image (min/max) measure no pixels.
+    ///
+    pub fn measured_pixels_into(
+        &self,
+        width: usize,
+        height: usize,
+        pixels: &mut Vec<ThermalDataPos>,
+    ) {
+        pixels.clear();
+        match self {
+            GizmoKind::TempAt { pos } => pixels.push(pos.clamped(width, height)),
+            GizmoKind::Rect { rect, .. } => {
+                let rect = rect.clamped(width, height);
+                pixels.extend((rect.min.y..=rect.max.y).flat_map(|y| {
+                    (rect.min.x..=rect.max.x).map(move |x| ThermalDataPos::new(x, y))
+                }))
+            }
+            GizmoKind::Ellipse { rect } => {
+                let rect = rect.clamped(width, height);
+                let center_x = (rect.min.x + rect.max.x + 1) as f32 / 2.0;
+                let center_y = (rect.min.y + rect.max.y + 1) as f32 / 2.0;
+                let radius_x = (rect.max.x - rect.min.x + 1) as f32 / 2.0;
+                let radius_y = (rect.max.y - rect.min.y + 1) as f32 / 2.0;
+                pixels.extend(
+                    (rect.min.y..=rect.max.y)
+                        .flat_map(|y| {
+                            (rect.min.x..=rect.max.x).map(move |x| ThermalDataPos::new(x, y))
+                        })
+                        .filter(|p| {
+                            // pixel centers are half a pixel from the pixel coordinates
+                            let dx = (p.x as f32 + 0.5 - center_x) / radius_x;
+                            let dy = (p.y as f32 + 0.5 - center_y) / radius_y;
+                            dx * dx + dy * dy <= 1.0
+                        }),
+                )
+            }
+            GizmoKind::Line { start, end } => {
+                let start = start.clamped(width, height);
+                let end = end.clamped(width, height);
+                line_pixels(start, end, pixels)
+            }
+            GizmoKind::Root { .. } | GizmoKind::MaxTemp | GizmoKind::MinTemp => {}
+        }
+    }
+
+    ///
+    /// Returns the handles of this gizmo with their pixel positions.
+    ///
+    pub fn handles(&self) -> Vec<(GizmoHandle, ThermalDataPos)> {
+        match self {
+            GizmoKind::TempAt { pos } => vec![(GizmoHandle::Move, *pos)],
+            GizmoKind::Line { start, end } => vec![
+                (GizmoHandle::LineStart, *start),
+                (GizmoHandle::LineEnd, *end),
+                (
+                    GizmoHandle::Move,
+                    ThermalDataPos::new((start.x + end.x) / 2, (start.y + end.y) / 2),
+                ),
+            ],
+            GizmoKind::Rect { rect, .. } | GizmoKind::Ellipse { rect } => {
+                let mut handles: Vec<(GizmoHandle, ThermalDataPos)> =
+                    [(true, true), (false, true), (true, false), (false, false)]
+                        .iter()
+                        .map(|(left, top)| {
+                            (
+                                GizmoHandle::Corner {
+                                    left: *left,
+                                    top: *top,
+                                },
+                                ThermalDataPos::new(
+                                    if *left { rect.min.x } else { rect.max.x },
+                                    if *top { rect.min.y } else { rect.max.y },
+                                ),
+                            )
+                        })
+                        .collect();
+                handles.push((
+                    GizmoHandle::Move,
+                    ThermalDataPos::new(
+                        (rect.min.x + rect.max.x) / 2,
+                        (rect.min.y + rect.max.y) / 2,
+                    ),
+                ));
+                handles
+            }
+            GizmoKind::Root { .. } | GizmoKind::MaxTemp | GizmoKind::MinTemp => vec![],
+        }
+    }
+
+    ///
+    /// Applies a mouse drag of `handle` from `from` to `to`, keeping the gizmo
+    /// inside of an image of the given size.
+    ///
+    pub fn drag_handle(
+        &mut self,
+        handle: GizmoHandle,
+        from: ThermalDataPos,
+        to: ThermalDataPos,
+        width: usize,
+        height: usize,
+    ) {
+        match (handle, self) {
+            (GizmoHandle::Move, kind) => {
+                let mut points = kind.points_mut();
+                if points.is_empty() {
+                    return;
+                }
+                let min_x = points.iter().map(|p| p.x).min().unwrap_or(0) as isize;
+                let min_y = points.iter().map(|p| p.y).min().unwrap_or(0) as isize;
+                let max_x = points.iter().map(|p| p.x).max().unwrap_or(0) as isize;
+                let max_y = points.iter().map(|p| p.y).max().unwrap_or(0) as isize;
+                // move the gizmo as a whole, so that it keeps its shape at the borders
+                let dx =
+                    (to.x as isize - from.x as isize).clamp(-min_x, width as isize - 1 - max_x);
+                let dy =
+                    (to.y as isize - from.y as isize).clamp(-min_y, height as isize - 1 - max_y);
+                for point in points.iter_mut() {
+                    point.x = (point.x as isize + dx) as usize;
+                    point.y = (point.y as isize + dy) as usize;
+                }
+            }
+            (GizmoHandle::LineStart, GizmoKind::Line { start, .. }) => *start = to,
+            (GizmoHandle::LineEnd, GizmoKind::Line { end, .. }) => *end = to,
+            (
+                GizmoHandle::Corner { left, top },
+                GizmoKind::Rect { rect, .. } | GizmoKind::Ellipse { rect },
+            ) => {
+                // a corner can't be dragged past the opposite one
+                if left {
+                    rect.min.x = to.x.min(rect.max.x);
+                } else {
+                    rect.max.x = to.x.max(rect.min.x);
+                }
+                if top {
+                    rect.min.y = to.y.min(rect.max.y);
+                } else {
+                    rect.max.y = to.y.max(rect.min.y);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    ///
+    /// Sets the size of a line, rect or ellipse from the two points it was dragged out between.
+    ///
+    pub fn set_extent(&mut self, a: ThermalDataPos, b: ThermalDataPos) {
+        match self {
+            GizmoKind::Line { start, end } => {
+                *start = a;
+                *end = b;
+            }
+            GizmoKind::Rect { rect, .. } | GizmoKind::Ellipse { rect } => {
+                *rect = ThermalDataRect::from_corners(a, b);
+            }
+            _ => {}
+        }
+    }
+
+    fn points_mut(&mut self) -> Vec<&mut ThermalDataPos> {
+        match self {
+            GizmoKind::TempAt { pos } => vec![pos],
+            GizmoKind::Line { start, end } => vec![start, end],
+            GizmoKind::Rect { rect, .. } | GizmoKind::Ellipse { rect } => {
+                vec![&mut rect.min, &mut rect.max]
+            }
+            GizmoKind::Root { .. } | GizmoKind::MaxTemp | GizmoKind::MinTemp => vec![],
+        }
+    }
+}
+
+///
+/// Adds the pixels on the line between `start` and `end`, both inclusive (Bresenham).
+///
+fn line_pixels(start: ThermalDataPos, end: ThermalDataPos, pixels: &mut Vec<ThermalDataPos>) {
+    let (mut x, mut y) = (start.x as isize, start.y as isize);
+    let (end_x, end_y) = (end.x as isize, end.y as isize);
+    let dx = (end_x - x).abs();
+    let dy = -(end_y - y).abs();
+    let step_x = if x < end_x { 1 } else { -1 };
+    let step_y = if y < end_y { 1 } else { -1 };
+    let mut error = dx + dy;
+
+    loop {
+        pixels.push(ThermalDataPos::new(x as usize, y as usize));
+        if x == end_x && y == end_y {
+            break;
+        }
+        let error2 = 2 * error;
+        if error2 >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if error2 <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct Gizmo {
     pub uuid: Uuid,
     pub kind: GizmoKind,
     pub name: String,
     pub color: Color32,
     pub show_temperature_label: bool,
+    // Not drawn on the image, but still measured
+    #[serde(default)]
+    pub hidden: bool,
 }
 
 impl Gizmo {
@@ -28,6 +278,7 @@ impl Gizmo {
             name,
             color,
             show_temperature_label: true,
+            hidden: false,
         }
     }
     pub fn new_root(children: Vec<Gizmo>) -> Self {
@@ -37,6 +288,14 @@ impl Gizmo {
             name: "Root".to_string(),
             color: Color32::WHITE,
             show_temperature_label: true,
+            hidden: false,
+        }
+    }
+
+    pub fn children(&self) -> Option<&Vec<Gizmo>> {
+        match &self.kind {
+            GizmoKind::Root { children } => Some(children),
+            _ => None,
         }
     }
 
@@ -47,7 +306,10 @@ impl Gizmo {
         }
     }
 
-    pub fn push_child(&mut self, kind: GizmoKind, name: String) {
+    ///
+    /// Adds a child gizmo with the next color in line and returns its uuid.
+    ///
+    pub fn push_child(&mut self, kind: GizmoKind, name: String) -> Uuid {
         match &mut self.kind {
             GizmoKind::Root { children } => {
                 let last_child_color = children
@@ -56,16 +318,204 @@ impl Gizmo {
                     .unwrap_or(Color32::from_rgb(255, 0, 0));
                 let mut new_color = Hsva::from(last_child_color);
                 new_color.h += 0.1;
-                children.push(Gizmo::new(kind, name, new_color.into()));
+                let child = Gizmo::new(kind, name, new_color.into());
+                let uuid = child.uuid;
+                children.push(child);
+                uuid
             }
             _ => panic!("Cannot push child to non-root gizmo"),
         }
     }
+
+    ///
+    /// Returns the regions of this gizmo and its children which override the emissivity,
+    /// in the order they were added. Later regions take precedence where they overlap.
+    ///
+    pub fn emissivity_overrides(&self) -> Vec<(ThermalDataRect, f32)> {
+        match &self.kind {
+            GizmoKind::Root { children } => children
+                .iter()
+                .flat_map(|child| child.emissivity_overrides())
+                .collect(),
+            GizmoKind::Rect {
+                rect,
+                emissivity: Some(emissivity),
+            } => vec![(*rect, *emissivity)],
+            _ => vec![],
+        }
+    }
+
+    ///
+    /// Returns the region of the rectangle gizmo with `uuid`, this one or one of its children.
+    ///
+    pub fn find_rect(&self, uuid: Uuid) -> Option<ThermalDataRect> {
+        match &self.kind {
+            GizmoKind::Root { children } => children.iter().find_map(|child| child.find_rect(uuid)),
+            GizmoKind::Rect { rect, .. } if self.uuid == uuid => Some(*rect),
+            _ => None,
+        }
+    }
+
+    pub fn clear_emissivity_overrides(&mut self) {
+        match &mut self.kind {
+            GizmoKind::Root { children } => {
+                for child in children.iter_mut() {
+                    child.clear_emissivity_overrides();
+                }
+            }
+            GizmoKind::Rect { emissivity, .. } => *emissivity = None,
+            _ => {}
+        }
+    }
+
+    ///
+    /// Moves this gizmo and all of its children inside of an image of the given size.
+    ///
+    pub fn clamp_to_bounds(&mut self, width: usize, height: usize) {
+        match &mut self.kind {
+            GizmoKind::Root { children } => {
+                for child in children.iter_mut() {
+                    child.clamp_to_bounds(width, height);
+                }
+            }
+            GizmoKind::TempAt { pos } => {
+                *pos = pos.clamped(width, height);
+            }
+            GizmoKind::Rect { rect, .. } | GizmoKind::Ellipse { rect } => {
+                *rect = rect.clamped(width, height);
+            }
+            GizmoKind::Line { start, end } => {
+                *start = start.clamped(width, height);
+                *end = end.clamped(width, height);
+            }
+            GizmoKind::MaxTemp | GizmoKind::MinTemp => {}
+        }
+    }
+}
+
+///
+/// A saved set of gizmos, which can be reused between measurement sessions.
+///
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GizmoLayout {
+    pub root: Gizmo,
+}
+
+impl GizmoLayout {
+    pub fn layouts_dir() -> PathBuf {
+        let mut path = dirs::config_dir().unwrap();
+        path.push("thermal-viewer");
+        path.push("gizmo_layouts");
+        path
+    }
+
+    ///
+    /// Returns the names of all saved layouts, sorted alphabetically.
+    ///
+    pub fn list_saved() -> Result<Vec<String>> {
+        let dir_path = Self::layouts_dir();
+        if !dir_path.exists() {
+            return Ok(vec![]);
+        }
+        let mut names: Vec<String> = dir_path
+            .read_dir()?
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != "json" {
+                    return None;
+                }
+                Some(path.file_stem()?.to_string_lossy().to_string())
+            })
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    pub fn save(&self, name: &str) -> Result<()> {
+        let dir_path = Self::layouts_dir();
+        if !dir_path.exists() {
+            fs::create_dir_all(&dir_path)?;
+        }
+
+        let file =
+            File::create(dir_path.join(format!("{}.json", pathify_string(name.to_string()))))?;
+        let writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+
+    pub fn load(name: &str) -> Result<Self> {
+        let file = File::open(Self::layouts_dir().join(format!("{}.json", name)))?;
+        let reader = BufReader::new(file);
+        let layout: GizmoLayout = serde_json::from_reader(reader)?;
+        if !matches!(layout.root.kind, GizmoKind::Root { .. }) {
+            return Err(anyhow::anyhow!("Gizmo layout {} has no root gizmo", name));
+        }
+        Ok(layout)
+    }
+}
+
+///
+/// Statistics over the pixels measured by a gizmo.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct GizmoStats {
+    pub min: Temp,
+    pub max: Temp,
+    pub mean: Temp,
+    // Standard deviation in Kelvin, a temperature difference rather than a temperature
+    pub stddev_kelvin: f32,
+    pub pixel_count: usize,
+}
+
+impl GizmoStats {
+    ///
+    /// Computes the statistics of the given pixels, along with the position of the hottest one.
+    /// Returns None if there are no pixels.
+    ///
+    pub fn from_pixels(
+        data: &ThermalData,
+        pixels: &[ThermalDataPos],
+    ) -> Option<(Self, ThermalDataPos)> {
+        let mut max_pos = *pixels.first()?;
+        let mut min = Temp::MAX;
+        let mut max = Temp::MIN;
+        let mut sum = 0.0f64;
+        let mut sum_sq = 0.0f64;
+        for pos in pixels.iter() {
+            let temp = data.temperature_at(pos.x, pos.y);
+            if temp < min {
+                min = temp;
+            }
+            if temp > max {
+                max = temp;
+                max_pos = *pos;
+            }
+            let kelvin = temp.to_unit(TemperatureUnit::Kelvin) as f64;
+            sum += kelvin;
+            sum_sq += kelvin * kelvin;
+        }
+        let count = pixels.len() as f64;
+        let mean = sum / count;
+        let variance = (sum_sq / count - mean * mean).max(0.0);
+
+        Some((
+            Self {
+                min,
+                max,
+                mean: Temp::new(mean as f32),
+                stddev_kelvin: variance.sqrt() as f32,
+                pixel_count: pixels.len(),
+            },
+            max_pos,
+        ))
+    }
 }
 
 #[derive(Clone)]
 pub struct GizmoResult {
-    pub uuid: Uuid,
     pub temperature: Temp,
     pub pos: ThermalDataPos,
+    // Only for gizmos measuring a set of pixels, min/max gizmos search the whole image
+    pub stats: Option<GizmoStats>,
 }
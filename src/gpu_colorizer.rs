@@ -0,0 +1,465 @@
+use eframe::{
+    egui::TextureId,
+    egui_wgpu::{self, wgpu},
+    epaint::{textures::TextureFilter, Color32, Rgba},
+};
+
+use crate::{
+    isotherm::{IsothermMode, MAX_ISOTHERMS},
+    temperature::TemperatureUnit,
+    thermal_capturer::{ThermalCapturerResult, ThermalCapturerSettings},
+    thermal_data::ThermalData,
+    thermal_gradient::{GradientLut, DELTA_GRADIENT, GRADIENT_LUT_SIZE},
+    upscaling::{UpscaleFilter, MAX_UPSCALE_FACTOR, MIN_UPSCALE_FACTOR},
+};
+
+const SHADER: &str = include_str!("gpu_colorizer.wgsl");
+
+// egui expects its textures in sRGB, the shader works with linear colors
+const COLOR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+// Floats of the Params struct of the shader
+const PARAMS_LEN: usize = 4 + MAX_ISOTHERMS * 8;
+
+///
+/// Textures of the frames of one size, recreated when the size changes.
+///
+struct GpuFrame {
+    temperatures: wgpu::Texture,
+    // Rendered into, egui draws it with texture_id
+    output: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+    texture_id: TextureId,
+    // (width, height) of the thermal data and of the colorized image
+    data_size: (usize, usize),
+    output_size: (usize, usize),
+    texture_filter: TextureFilter,
+}
+
+///
+/// Colorizes the displayed frames on the GPU: the temperatures are uploaded as a float texture
+/// and mapped through the gradient, the isotherms and the bilinear upscaling in a fragment shader.
+/// Only the display uses it, snapshots and recordings still use the image colorized on the CPU,
+/// which is also shown when the GPU can't reproduce it (see `colorize`).
+///
+pub struct GpuColorizer {
+    render_state: egui_wgpu::RenderState,
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    params_buffer: wgpu::Buffer,
+    gradient_texture: wgpu::Texture,
+    // Last uploaded table, it's only uploaded again when it changes
+    gradient_colors: Vec<Color32>,
+    frame: Option<GpuFrame>,
+}
+
+fn texture_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+        },
+        count: None,
+    }
+}
+
+fn filter_mode(texture_filter: TextureFilter) -> wgpu::FilterMode {
+    match texture_filter {
+        TextureFilter::Nearest => wgpu::FilterMode::Nearest,
+        TextureFilter::Linear => wgpu::FilterMode::Linear,
+    }
+}
+
+fn floats_to_bytes(floats: &[f32]) -> Vec<u8> {
+    floats.iter().flat_map(|f| f.to_ne_bytes()).collect()
+}
+
+impl GpuColorizer {
+    pub fn new(render_state: egui_wgpu::RenderState) -> Self {
+        let device = &render_state.device;
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("gpu_colorizer"),
+            source: wgpu::ShaderSource::Wgsl(
+                SHADER
+                    .replace("{MAX_ISOTHERMS}", &MAX_ISOTHERMS.to_string())
+                    .into(),
+            ),
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("gpu_colorizer"),
+            entries: &[
+                texture_entry(0),
+                texture_entry(1),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("gpu_colorizer"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("gpu_colorizer"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: COLOR_FORMAT,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("gpu_colorizer_params"),
+            size: (PARAMS_LEN * std::mem::size_of::<f32>()) as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let gradient_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("gpu_colorizer_gradient"),
+            size: wgpu::Extent3d {
+                width: GRADIENT_LUT_SIZE as u32,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: COLOR_FORMAT,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        Self {
+            render_state,
+            pipeline,
+            bind_group_layout,
+            params_buffer,
+            gradient_texture,
+            gradient_colors: vec![],
+            frame: None,
+        }
+    }
+
+    ///
+    /// Colorizes the frame of `result` into a texture for egui, the same way the capturer did.
+    /// Returns None for what only the CPU path does: histogram equalization, fusion
+    /// with the visible camera and the sharper upscaling filters.
+    ///
+    pub fn colorize(
+        &mut self,
+        result: &ThermalCapturerResult,
+        settings: &ThermalCapturerSettings,
+    ) -> Option<TextureId> {
+        let data = &result.thermal_data;
+        if data.width == 0
+            || data.height == 0
+            || result.equalization.is_some()
+            || settings.visible_frames.is_some()
+        {
+            return None;
+        }
+        let factor = match settings.upscale_filter {
+            UpscaleFilter::Off => 1,
+            UpscaleFilter::Nearest | UpscaleFilter::Bilinear => settings
+                .upscale_factor
+                .clamp(MIN_UPSCALE_FACTOR, MAX_UPSCALE_FACTOR),
+            UpscaleFilter::Bicubic | UpscaleFilter::Lanczos => return None,
+        };
+        let output_size = (data.width * factor, data.height * factor);
+
+        let needs_frame = self.frame.as_ref().is_none_or(|frame| {
+            frame.data_size != (data.width, data.height) || frame.output_size != output_size
+        });
+        if needs_frame {
+            self.frame = Some(self.create_frame(
+                (data.width, data.height),
+                output_size,
+                settings.texture_filter,
+            ));
+        }
+        self.update_texture_filter(settings.texture_filter);
+
+        let lut = if result.is_delta {
+            GradientLut::from_gradient(&DELTA_GRADIENT)
+        } else {
+            settings.gradient_lut()
+        };
+        self.upload_gradient(&lut);
+        self.upload_params(
+            result,
+            settings,
+            settings.upscale_filter == UpscaleFilter::Bilinear,
+        );
+        let frame = self.frame.as_ref()?;
+        self.upload_temperatures(&frame.temperatures, data);
+        self.render(frame);
+        Some(frame.texture_id)
+    }
+
+    fn create_frame(
+        &self,
+        data_size: (usize, usize),
+        output_size: (usize, usize),
+        texture_filter: TextureFilter,
+    ) -> GpuFrame {
+        let device = &self.render_state.device;
+        let texture = |label, size: (usize, usize), format, usage| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width: size.0 as u32,
+                    height: size.1 as u32,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage,
+                view_formats: &[],
+            })
+        };
+        let temperatures = texture(
+            "gpu_colorizer_temperatures",
+            data_size,
+            wgpu::TextureFormat::R32Float,
+            wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        );
+        let output = texture(
+            "gpu_colorizer_output",
+            output_size,
+            COLOR_FORMAT,
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        );
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("gpu_colorizer"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(
+                        &temperatures.create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(
+                        &self
+                            .gradient_texture
+                            .create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: self.params_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let output_view = output.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut renderer = self.render_state.renderer.write();
+        let texture_id = match self.frame.as_ref() {
+            Some(frame) => {
+                renderer.update_egui_texture_from_wgpu_texture(
+                    device,
+                    &output_view,
+                    filter_mode(texture_filter),
+                    frame.texture_id,
+                );
+                frame.texture_id
+            }
+            None => {
+                renderer.register_native_texture(device, &output_view, filter_mode(texture_filter))
+            }
+        };
+
+        GpuFrame {
+            temperatures,
+            bind_group,
+            texture_id,
+            data_size,
+            output_size,
+            texture_filter,
+            output,
+        }
+    }
+
+    fn update_texture_filter(&mut self, texture_filter: TextureFilter) {
+        let Some(frame) = self.frame.as_mut() else {
+            return;
+        };
+        if frame.texture_filter == texture_filter {
+            return;
+        }
+        let output_view = frame
+            .output
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        self.render_state
+            .renderer
+            .write()
+            .update_egui_texture_from_wgpu_texture(
+                &self.render_state.device,
+                &output_view,
+                filter_mode(texture_filter),
+                frame.texture_id,
+            );
+        frame.texture_filter = texture_filter;
+    }
+
+    fn upload_gradient(&mut self, lut: &GradientLut) {
+        if self.gradient_colors == lut.colors() {
+            return;
+        }
+        let bytes: Vec<u8> = lut.colors().iter().flat_map(|c| c.to_array()).collect();
+        self.render_state.queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.gradient_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &bytes,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * lut.colors().len() as u32),
+                rows_per_image: Some(1),
+            },
+            self.gradient_texture.size(),
+        );
+        self.gradient_colors = lut.colors().to_vec();
+    }
+
+    fn upload_params(
+        &self,
+        result: &ThermalCapturerResult,
+        settings: &ThermalCapturerSettings,
+        bilinear: bool,
+    ) {
+        let range = result.image_range;
+        let min = range.min.to_unit(TemperatureUnit::Kelvin);
+        let span = (range.max - range.min).to_unit(TemperatureUnit::Kelvin);
+        // same guard as ThermalData::map_range_to_image
+        let span = if span.abs() < f32::EPSILON {
+            f32::EPSILON
+        } else {
+            span
+        };
+
+        let mut params = vec![0.0; PARAMS_LEN];
+        params[..4].copy_from_slice(&[min, 1.0 / span, if bilinear { 1.0 } else { 0.0 }, 0.0]);
+        // the isotherms are temperatures, they don't apply to differences
+        let isotherms = if result.is_delta {
+            &[][..]
+        } else {
+            &settings.isotherms[..]
+        };
+        for (i, isotherm) in isotherms.iter().take(MAX_ISOTHERMS).enumerate() {
+            let mode = match isotherm.mode {
+                IsothermMode::Above => 0.0,
+                IsothermMode::Below => 1.0,
+                IsothermMode::Between => 2.0,
+            };
+            let bounds = 4 + i * 4;
+            params[bounds..bounds + 4].copy_from_slice(&[
+                isotherm.lower.to_unit(TemperatureUnit::Kelvin),
+                isotherm.upper.to_unit(TemperatureUnit::Kelvin),
+                mode,
+                if isotherm.enabled { 1.0 } else { 0.0 },
+            ]);
+            let color = 4 + MAX_ISOTHERMS * 4 + i * 4;
+            params[color..color + 4].copy_from_slice(&Rgba::from(isotherm.color).to_array());
+        }
+        self.render_state
+            .queue
+            .write_buffer(&self.params_buffer, 0, &floats_to_bytes(&params));
+    }
+
+    fn upload_temperatures(&self, texture: &wgpu::Texture, data: &ThermalData) {
+        let kelvin: Vec<f32> = data
+            .data
+            .iter()
+            .map(|t| t.to_unit(TemperatureUnit::Kelvin))
+            .collect();
+        self.render_state.queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &floats_to_bytes(&kelvin),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * data.width as u32),
+                rows_per_image: Some(data.height as u32),
+            },
+            texture.size(),
+        );
+    }
+
+    fn render(&self, frame: &GpuFrame) {
+        let device = &self.render_state.device;
+        let view = frame
+            .output
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("gpu_colorizer"),
+        });
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("gpu_colorizer"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &frame.bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
+        self.render_state.queue.submit(Some(encoder.finish()));
+    }
+}
+
+impl Drop for GpuColorizer {
+    fn drop(&mut self) {
+        if let Some(frame) = self.frame.as_ref() {
+            self.render_state
+                .renderer
+                .write()
+                .free_texture(&frame.texture_id);
+        }
+    }
+}
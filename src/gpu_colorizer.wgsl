@@ -0,0 +1,81 @@
+// Colorizes the temperatures of a frame, see gpu_colorizer.rs
+
+struct Params {
+    // x: lower end of the range in Kelvin, y: 1 / span of the range, z: 1 for bilinear upscaling
+    range: vec4<f32>,
+    // x: lower, y: upper (Kelvin), z: mode (0 above, 1 below, 2 between), w: 1 if enabled
+    isotherm_bounds: array<vec4<f32>, {MAX_ISOTHERMS}>,
+    // linear RGBA
+    isotherm_colors: array<vec4<f32>, {MAX_ISOTHERMS}>,
+};
+
+@group(0) @binding(0) var temperatures: texture_2d<f32>;
+@group(0) @binding(1) var gradient: texture_2d<f32>;
+@group(0) @binding(2) var<uniform> params: Params;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+// A single triangle covering the whole target, uv (0, 0) is the top left corner
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+// Temperatures are 32 bit floats, which can't be filtered by the sampler on every device
+fn temperature_at(pos: vec2<f32>) -> f32 {
+    let size = vec2<i32>(textureDimensions(temperatures));
+    let texel = clamp(vec2<i32>(pos), vec2<i32>(0, 0), size - vec2<i32>(1, 1));
+    return textureLoad(temperatures, texel, 0).r;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<f32>(textureDimensions(temperatures));
+    var kelvin: f32;
+    if params.range.z > 0.5 {
+        // pixel centers are at integer positions, as in the upscaling on the CPU
+        let pos = in.uv * size - 0.5;
+        let base = floor(pos);
+        let t = pos - base;
+        let top = mix(temperature_at(base), temperature_at(base + vec2<f32>(1.0, 0.0)), t.x);
+        let bottom = mix(
+            temperature_at(base + vec2<f32>(0.0, 1.0)),
+            temperature_at(base + vec2<f32>(1.0, 1.0)),
+            t.x
+        );
+        kelvin = mix(top, bottom, t.y);
+    } else {
+        kelvin = temperature_at(floor(in.uv * size));
+    }
+
+    for (var i = 0; i < {MAX_ISOTHERMS}; i++) {
+        let bounds = params.isotherm_bounds[i];
+        if bounds.w < 0.5 {
+            continue;
+        }
+        let above = kelvin >= bounds.x;
+        let below = kelvin <= bounds.y;
+        var inside = above && below;
+        if bounds.z < 0.5 {
+            inside = above;
+        } else if bounds.z < 1.5 {
+            inside = below;
+        }
+        if inside {
+            return params.isotherm_colors[i];
+        }
+    }
+
+    // nearest entry of the table, like GradientLut::color
+    let last = i32(textureDimensions(gradient).x) - 1;
+    let factor = (kelvin - params.range.x) * params.range.y;
+    let index = clamp(i32(factor * f32(last) + 0.5), 0, last);
+    return textureLoad(gradient, vec2<i32>(index, 0), 0);
+}
@@ -1,3 +1,8 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
 use eframe::{
     egui::{self, Grid, Response, Ui},
     epaint::{TextureHandle, Vec2},
@@ -7,23 +12,40 @@ use crate::thermal_gradient::{ThermalGradient, THERMAL_GRADIENTS};
 
 pub struct GradientSelectorView {
     preview_textures: Vec<TextureHandle>,
+    // Hash of the custom gradients the previews were created from, they can be edited
+    custom_gradients_hash: u64,
 }
 
 impl GradientSelectorView {
     pub fn new() -> Self {
         Self {
             preview_textures: vec![],
+            custom_gradients_hash: 0,
         }
     }
 
-    pub fn draw(&mut self, ui: &mut Ui, selected_gradient: &mut ThermalGradient) -> Response {
-        if self.preview_textures.len() != THERMAL_GRADIENTS.len() {
-            self.preview_textures = THERMAL_GRADIENTS
-                .iter()
+    ///
+    /// Draws the built-in gradients followed by `custom_gradients` (e.g. imported ones).
+    ///
+    pub fn draw(
+        &mut self,
+        ui: &mut Ui,
+        selected_gradient: &mut ThermalGradient,
+        custom_gradients: &[ThermalGradient],
+    ) -> Response {
+        let all_gradients = || THERMAL_GRADIENTS.iter().chain(custom_gradients.iter());
+        let mut hasher = DefaultHasher::new();
+        custom_gradients.hash(&mut hasher);
+        let custom_gradients_hash = hasher.finish();
+        if self.preview_textures.len() != THERMAL_GRADIENTS.len() + custom_gradients.len()
+            || self.custom_gradients_hash != custom_gradients_hash
+        {
+            self.custom_gradients_hash = custom_gradients_hash;
+            self.preview_textures = all_gradients()
                 .map(|gradient| {
                     let gradient_image = gradient.create_demo_image(256, 32);
                     ui.ctx().load_texture(
-                        format!("gradient_{}", gradient.name),
+                        format!("gradient_{}", gradient.uuid),
                         gradient_image,
                         Default::default(),
                     )
@@ -43,36 +65,33 @@ impl GradientSelectorView {
                     .striped(true)
                     .max_col_width(200.0)
                     .show(ui, |ui| {
-                        THERMAL_GRADIENTS
-                            .iter()
-                            .enumerate()
-                            .for_each(|(i, gradient)| {
-                                ui.radio_value(
-                                    &mut selected_gradient_uuid,
-                                    gradient.uuid,
-                                    gradient.name.clone(),
-                                );
-                                if ui
-                                    .add(
-                                        egui::Image::new(&self.preview_textures[i])
-                                            .fit_to_fraction(Vec2::new(1.0, 1.0)),
-                                    )
-                                    .clicked()
-                                {
-                                    selected_gradient_uuid = gradient.uuid;
-                                }
-                                ui.end_row();
-                            });
+                        all_gradients().enumerate().for_each(|(i, gradient)| {
+                            ui.radio_value(
+                                &mut selected_gradient_uuid,
+                                gradient.uuid,
+                                gradient.name.clone(),
+                            );
+                            if ui
+                                .add(
+                                    egui::Image::new(&self.preview_textures[i])
+                                        .fit_to_fraction(Vec2::new(1.0, 1.0)),
+                                )
+                                .clicked()
+                            {
+                                selected_gradient_uuid = gradient.uuid;
+                            }
+                            ui.end_row();
+                        });
                     });
             })
             .response;
 
-        *selected_gradient = THERMAL_GRADIENTS
-            .iter()
-            .find(|gradient| gradient.uuid == selected_gradient_uuid)
-            .unwrap()
-            .clone();
         if prev_selected_gradient_uuid != selected_gradient_uuid {
+            if let Some(gradient) =
+                all_gradients().find(|gradient| gradient.uuid == selected_gradient_uuid)
+            {
+                *selected_gradient = gradient.clone();
+            }
             resp.mark_changed();
         }
 
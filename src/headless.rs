@@ -0,0 +1,316 @@
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::Local;
+use nokhwa::utils::CameraIndex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    camera_enumerator::enumerate_cameras,
+    http_server::{HttpServer, HttpServerSettings},
+    overlay::{Overlay, OverlayOptions},
+    radiometric_recording::RADIOMETRIC_EXTENSION,
+    recorders::{
+        image_recorder::{save_result_image, ImageMetadata},
+        recorder::Recorder,
+        video_recorder::{VideoRecorder, VideoSettings},
+    },
+    settings_bundle::import_bundle,
+    temperature::TemperatureUnit,
+    thermal_capturer::{
+        ThermalCapturer, ThermalCapturerEvent, ThermalCapturerExt, ThermalCapturerResult,
+        ThermalCapturerSettings,
+    },
+    thermal_source::camera_source::CameraSource,
+    types::media_formats::ImageFormat,
+    user_preferences::UserPreferences,
+};
+
+pub const HEADLESS_FLAG: &str = "--headless";
+
+const USAGE: &str = "Usage: thermal-cat --headless <command> [--config <file>]
+
+Commands:
+  snapshot   Save a single image
+  interval   Save an image every `interval_secs`, `count` times (0 = until stopped)
+  record     Write a radiometric recording for `duration_secs`
+  video      Record a video of the colored image for `duration_secs`
+  stream     Serve the live image and temperatures over HTTP until stopped
+
+The config file is JSON, see HeadlessConfig for the keys. Missing keys use their defaults.";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum HeadlessCommand {
+    Snapshot,
+    Interval,
+    Record,
+    Video,
+    Stream,
+}
+
+///
+/// Configuration of a headless run, read from the file given with `--config`.
+///
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HeadlessConfig {
+    // Index of the camera to open, the first supported camera if not set
+    pub camera_index: Option<u32>,
+    // Open the synthetic demo device instead, for running without hardware
+    pub demo_camera: bool,
+    // Settings bundle exported from the UI, for the gradient, range, gizmos etc.
+    pub settings_bundle: Option<PathBuf>,
+    // Defaults to the captures directory of the user preferences
+    pub output_directory: Option<PathBuf>,
+    pub image_format: ImageFormat,
+    pub temperature_unit: TemperatureUnit,
+    pub draw_footer: bool,
+    // Burned into the saved images and videos
+    pub overlay: OverlayOptions,
+    pub video: VideoSettings,
+    // Frames skipped before the first image, so the auto range can settle
+    pub warmup_frames: usize,
+    pub interval_secs: f32,
+    pub count: usize,
+    pub duration_secs: f32,
+    pub http_server: HttpServerSettings,
+}
+
+impl Default for HeadlessConfig {
+    fn default() -> Self {
+        Self {
+            camera_index: None,
+            demo_camera: false,
+            settings_bundle: None,
+            output_directory: None,
+            image_format: ImageFormat::Png,
+            temperature_unit: TemperatureUnit::Celsius,
+            draw_footer: false,
+            overlay: OverlayOptions::default(),
+            video: VideoSettings::default(),
+            warmup_frames: 25,
+            interval_secs: 60.0,
+            count: 0,
+            duration_secs: 60.0,
+            http_server: HttpServerSettings::default(),
+        }
+    }
+}
+
+impl HeadlessConfig {
+    fn load(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open config file {}", path.display()))?;
+        Ok(serde_json::from_reader(BufReader::new(file))?)
+    }
+}
+
+///
+/// Runs a capture without the UI, with the arguments following `--headless`.
+///
+pub fn run_headless(args: &[String]) -> Result<()> {
+    let mut command = None;
+    let mut config = HeadlessConfig::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "snapshot" => command = Some(HeadlessCommand::Snapshot),
+            "interval" => command = Some(HeadlessCommand::Interval),
+            "record" => command = Some(HeadlessCommand::Record),
+            "video" => command = Some(HeadlessCommand::Video),
+            "stream" => command = Some(HeadlessCommand::Stream),
+            "--config" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| anyhow!("--config needs a file"))?;
+                config = HeadlessConfig::load(Path::new(path))?;
+            }
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            other => return Err(anyhow!("Unknown argument {}\n\n{}", other, USAGE)),
+        }
+    }
+    let command = command.ok_or_else(|| anyhow!("No command given\n\n{}", USAGE))?;
+
+    let settings = match &config.settings_bundle {
+        Some(path) => import_bundle(path)?.settings,
+        None => ThermalCapturerSettings::default(),
+    };
+    let output_directory = config.output_directory.clone().unwrap_or_else(|| {
+        PathBuf::from(
+            UserPreferences::load()
+                .unwrap_or_default()
+                .captures_directory,
+        )
+    });
+    std::fs::create_dir_all(&output_directory)?;
+
+    let mut capturer = open_capturer(&config, settings.clone())?;
+    let mut session = HeadlessSession {
+        capturer: &mut capturer,
+        config: &config,
+        settings: &settings,
+        output_directory: &output_directory,
+    };
+    session.skip_frames(config.warmup_frames)?;
+
+    match command {
+        HeadlessCommand::Snapshot => {
+            let result = session.next_result()?;
+            session.save_image(&result)?;
+        }
+        HeadlessCommand::Interval => {
+            let interval = Duration::from_secs_f32(config.interval_secs.max(0.1));
+            let mut saved = 0;
+            let mut next_time = Instant::now();
+            while config.count == 0 || saved < config.count {
+                let result = session.next_result()?;
+                if Instant::now() >= next_time {
+                    session.save_image(&result)?;
+                    saved += 1;
+                    next_time += interval;
+                }
+            }
+        }
+        HeadlessCommand::Record => {
+            let path = output_directory.join(format!(
+                "recording_{}.{}",
+                Local::now().format("%Y-%m-%d_%H-%M-%S"),
+                RADIOMETRIC_EXTENSION
+            ));
+            tracing::info!(path = %path.display(), "Recording");
+            session.capturer.start_radiometric_recording(path);
+            let end = Instant::now() + Duration::from_secs_f32(config.duration_secs.max(0.0));
+            while Instant::now() < end {
+                session.next_result()?;
+            }
+            session.capturer.stop_radiometric_recording();
+            // commands are handled between frames, wait until the recording is finished
+            session.skip_frames(2)?;
+        }
+        HeadlessCommand::Video => {
+            let recorder = Arc::new(Mutex::new(VideoRecorder::new(
+                output_directory.clone(),
+                "video".to_string(),
+                config.video,
+                Overlay::new(
+                    config.overlay,
+                    &settings,
+                    config.temperature_unit,
+                    config.temperature_unit.default_precision(),
+                ),
+            )));
+            let mut recording_settings = settings.clone();
+            recording_settings.recorders.push(recorder.clone());
+            session.capturer.set_settings(recording_settings);
+            let end = Instant::now() + Duration::from_secs_f32(config.duration_secs.max(0.0));
+            while Instant::now() < end {
+                session.next_result()?;
+            }
+            let mut recorder = recorder
+                .lock()
+                .map_err(|_| anyhow!("The video recorder panicked"))?;
+            recorder.finish()?;
+            for path in recorder.files_created() {
+                tracing::info!(path = %path.display(), "Saved");
+            }
+        }
+        HeadlessCommand::Stream => {
+            let mut server = HttpServer::start(&config.http_server)?;
+            loop {
+                let result = session.next_result()?;
+                server.update(&result, &settings.gizmo, config.temperature_unit);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn open_capturer(
+    config: &HeadlessConfig,
+    settings: ThermalCapturerSettings,
+) -> Result<ThermalCapturer> {
+    let cameras = enumerate_cameras()?;
+    let camera = cameras
+        .iter()
+        .filter(|camera| camera.adapter.is_some() && camera.is_demo() == config.demo_camera)
+        .find(|camera| {
+            config
+                .camera_index
+                .is_none_or(|index| camera.info.index() == &CameraIndex::Index(index))
+        })
+        .ok_or_else(|| anyhow!("No supported camera found"))?;
+    tracing::info!(camera = %camera.info.human_name(), "Opening camera");
+
+    let source = CameraSource::new(camera.info.index().clone(), camera.adapter.clone().unwrap());
+    // results are read blocking, no need to be notified
+    let mut capturer = ThermalCapturer::new(Box::new(source), settings, Arc::new(|| {}));
+    capturer.start();
+    Ok(capturer)
+}
+
+struct HeadlessSession<'a> {
+    capturer: &'a mut ThermalCapturer,
+    config: &'a HeadlessConfig,
+    settings: &'a ThermalCapturerSettings,
+    output_directory: &'a Path,
+}
+
+impl HeadlessSession<'_> {
+    fn next_result(&mut self) -> Result<Box<ThermalCapturerResult>> {
+        loop {
+            match self.capturer.recv_event() {
+                ThermalCapturerEvent::Frame(result) => return Ok(result),
+                ThermalCapturerEvent::Error(err) => return Err(err),
+                // keep waiting, the capturer reopens the camera
+                ThermalCapturerEvent::Disconnected(err) => {
+                    tracing::warn!(error = %format_args!("{:#}", err), "Camera disconnected")
+                }
+                ThermalCapturerEvent::Stopped(reason) => {
+                    return Err(reason.unwrap_or_else(|| anyhow!("The capturer stopped")))
+                }
+            }
+        }
+    }
+
+    fn skip_frames(&mut self, count: usize) -> Result<()> {
+        for _ in 0..count {
+            self.next_result()?;
+        }
+        Ok(())
+    }
+
+    fn save_image(&self, result: &ThermalCapturerResult) -> Result<()> {
+        let timestamp = Local::now();
+        let path = self.output_directory.join(format!(
+            "photo_{}.{}",
+            timestamp.format("%Y-%m-%d_%H-%M-%S%.3f"),
+            self.config.image_format.extension()
+        ));
+        let metadata = ImageMetadata::new(
+            self.settings,
+            self.config.temperature_unit,
+            1,
+            self.config.draw_footer,
+            true,
+            self.config.overlay,
+        );
+        save_result_image(
+            result,
+            &path,
+            self.config.image_format,
+            &metadata,
+            timestamp,
+        )?;
+        tracing::info!(path = %path.display(), "Saved");
+        Ok(())
+    }
+}
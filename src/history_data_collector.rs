@@ -1,19 +1,48 @@
-use std::{collections::HashMap, time::Instant};
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
 
+use anyhow::Result;
+use chrono::{DateTime, Local};
 use uuid::Uuid;
 
-use crate::{gizmos::GizmoResult, temperature::Temp};
+use crate::{
+    gizmos::GizmoResult,
+    temperature::{Temp, TemperatureUnit},
+};
+
+//
+// Maximum number of data points kept per gizmo, the oldest are dropped first.
+// About an hour at 25 frames per second.
+//
+const HISTORY_CAPACITY: usize = 90_000;
 
 pub struct DataPoint {
     pub temperature: Temp,
+    // Mean over the pixels of spot, line and area gizmos
+    pub mean: Option<Temp>,
     pub time: Instant,
 }
 
+impl DataPoint {
+    ///
+    /// The value charted for the gizmo: the mean of spots and areas,
+    /// or the temperature for gizmos without statistics (min/max).
+    ///
+    pub fn charted_temperature(&self) -> Temp {
+        self.mean.unwrap_or(self.temperature)
+    }
+}
+
 pub struct HistoryDataCollector {
     //
-    // Stores data points for each gizmo UUID
+    // Stores data points for each gizmo UUID, in ascending time order
     //
-    pub stored_data: HashMap<Uuid, Vec<DataPoint>>,
+    pub stored_data: HashMap<Uuid, VecDeque<DataPoint>>,
 }
 
 impl HistoryDataCollector {
@@ -31,12 +60,15 @@ impl HistoryDataCollector {
         for (gizmo_uuid, gizmo_result) in gizmo_results {
             let data_point = DataPoint {
                 temperature: gizmo_result.temperature,
+                mean: gizmo_result.stats.map(|s| s.mean),
                 time,
             };
 
             let data_points = self.stored_data.entry(*gizmo_uuid).or_default();
-
-            data_points.push(data_point);
+            if data_points.len() >= HISTORY_CAPACITY {
+                data_points.pop_front();
+            }
+            data_points.push_back(data_point);
         }
         Ok(())
     }
@@ -45,13 +77,77 @@ impl HistoryDataCollector {
     where
         F: FnMut(&DataPoint),
     {
-        // TODO: binary search
         self.stored_data.get(&gizmo_uuid).inspect(|data_points| {
-            for data_point in data_points.iter() {
-                if data_point.time >= from && data_point.time <= to {
-                    f(data_point);
+            let start = data_points.partition_point(|p| p.time < from);
+            for data_point in data_points.range(start..) {
+                if data_point.time > to {
+                    break;
                 }
+                f(data_point);
             }
         });
     }
+
+    ///
+    /// Writes the charted temperatures of the given gizmos between `from` and `to` as CSV,
+    /// one row per frame and one column per gizmo. Gizmos without a value in a frame
+    /// (e.g. added later) leave their cell empty.
+    ///
+    pub fn export_csv(
+        &self,
+        path: &Path,
+        gizmos: &[(Uuid, String)],
+        from: Instant,
+        to: Instant,
+        unit: TemperatureUnit,
+    ) -> Result<()> {
+        let mut rows: BTreeMap<Instant, Vec<Option<f32>>> = BTreeMap::new();
+        for (column, (uuid, _)) in gizmos.iter().enumerate() {
+            self.for_each_data_point(*uuid, from, to, |data_point| {
+                rows.entry(data_point.time)
+                    .or_insert_with(|| vec![None; gizmos.len()])[column] =
+                    Some(data_point.charted_temperature().to_unit(unit));
+            });
+        }
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        write!(writer, "timestamp,seconds")?;
+        for (_, name) in gizmos.iter() {
+            write!(
+                writer,
+                ",\"{} ({})\"",
+                name.replace('"', "\"\""),
+                unit.suffix()
+            )?;
+        }
+        writeln!(writer)?;
+
+        let now = Instant::now();
+        let now_wall: DateTime<Local> = Local::now();
+        let first_time = rows.keys().next().copied();
+        for (time, values) in rows.iter() {
+            let wall = now_wall
+                - chrono::Duration::from_std(now.saturating_duration_since(*time))
+                    .unwrap_or(chrono::Duration::zero());
+            let seconds = first_time
+                .map(|first| time.duration_since(first))
+                .unwrap_or(Duration::ZERO)
+                .as_secs_f64();
+            write!(
+                writer,
+                "{},{:.3}",
+                wall.format("%Y-%m-%dT%H:%M:%S%.3f"),
+                seconds
+            )?;
+            for value in values.iter() {
+                match value {
+                    Some(value) => write!(writer, ",{:.3}", value)?,
+                    None => write!(writer, ",")?,
+                }
+            }
+            writeln!(writer)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
 }
@@ -106,14 +106,14 @@ pub fn run_hotplug_detector<F: Fn(HotplugEvent) + Send + 'static>(
                 }),
             );
             if let Err(e) = hotplug_result {
-                log::error!("error registering hotplug handler: {:?}", e);
+                tracing::error!(error = ?e, "error registering hotplug handler");
                 return;
             }
             let reg: Box<rusb::Registration<Context>> = Box::new(hotplug_result.unwrap());
             loop {
                 let result = context.handle_events(None);
                 if result.is_err() {
-                    log::error!("error handling libusb events: {:?}", result.err());
+                    tracing::error!(error = ?result.err(), "error handling libusb events");
                     break;
                 }
             }
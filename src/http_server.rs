@@ -0,0 +1,442 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use eframe::epaint::ColorImage;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    gizmos::{Gizmo, GizmoKind, GizmoResult},
+    temperature::{reading_to_unit, TemperatureUnit},
+    thermal_capturer::ThermalCapturerResult,
+    thermal_data::ThermalData,
+    util::rgba8_to_rgb8,
+};
+
+// How often the accept loop checks whether the server was stopped
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+// MJPEG clients waiting longer than this for a frame get the last one again
+const FRAME_WAIT_TIMEOUT: Duration = Duration::from_secs(2);
+const JPEG_QUALITY: u8 = 85;
+// Clients which stop sending their request are dropped after this long
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpServerSettings {
+    pub enabled: bool,
+    // 127.0.0.1 only accepts connections from this computer, 0.0.0.0 from the whole network
+    pub bind_address: String,
+    pub port: u16,
+}
+
+impl Default for HttpServerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: "127.0.0.1".to_string(),
+            port: 8080,
+        }
+    }
+}
+
+struct GizmoReadout {
+    uuid: Uuid,
+    name: String,
+    result: GizmoResult,
+}
+
+///
+/// The latest result, as far as the server needs it.
+///
+struct LatestFrame {
+    // Incremented for every frame, so MJPEG clients can wait for the next one
+    sequence: u64,
+    // Only copied while a client is connected, None otherwise
+    image: Option<Arc<ColorImage>>,
+    thermal_data: Arc<ThermalData>,
+    gizmos: Vec<GizmoReadout>,
+    unit: TemperatureUnit,
+    // The values are temperature differences, see `reading_to_unit`
+    is_delta: bool,
+}
+
+struct SharedState {
+    // Cloned out of the lock by the connections, which encode it after releasing the lock
+    latest: Mutex<Option<Arc<LatestFrame>>>,
+    new_frame: Condvar,
+    stopped: AtomicBool,
+    // Open connections
+    clients: AtomicUsize,
+}
+
+///
+/// Counts a connection in `SharedState::clients` while it's open.
+///
+struct ClientGuard<'a>(&'a SharedState);
+
+impl<'a> ClientGuard<'a> {
+    fn new(state: &'a SharedState) -> Self {
+        state.clients.fetch_add(1, Ordering::Relaxed);
+        Self(state)
+    }
+}
+
+impl Drop for ClientGuard<'_> {
+    fn drop(&mut self) {
+        self.0.clients.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+///
+/// Embedded HTTP server exposing the live image and temperatures, so other tools
+/// can use the camera without access to its USB connection.
+///
+/// Endpoints:
+///   /stream.mjpg         the colored image as MJPEG stream
+///   /frame.jpg           the latest colored image
+///   /temperatures.json   the latest temperatures, row-major, in the preferred unit
+///   /temperatures.bin    u32 width, u32 height, then f32 Kelvin row-major, little-endian
+///   /gizmos              readouts of all gizmos as JSON
+///   /gizmos/<uuid>       readout of a single gizmo
+///
+/// In delta mode the values are differences to the reference frame, the JSON says so with "is_delta".
+///
+pub struct HttpServer {
+    state: Arc<SharedState>,
+    sequence: u64,
+    // Owns the listener, joined on drop so the port is free again afterwards
+    accept_thread: Option<JoinHandle<()>>,
+}
+
+impl HttpServer {
+    pub fn start(settings: &HttpServerSettings) -> Result<Self> {
+        let listener = TcpListener::bind((settings.bind_address.as_str(), settings.port))?;
+        listener.set_nonblocking(true)?;
+        tracing::info!(address = %listener.local_addr()?, "HTTP server listening");
+
+        let state = Arc::new(SharedState {
+            latest: Mutex::new(None),
+            new_frame: Condvar::new(),
+            stopped: AtomicBool::new(false),
+            clients: AtomicUsize::new(0),
+        });
+        let thread_state = state.clone();
+        let accept_thread = thread::spawn(move || {
+            while !thread_state.stopped.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        let connection_state = thread_state.clone();
+                        thread::spawn(move || {
+                            if let Err(err) = handle_connection(stream, &connection_state) {
+                                tracing::debug!(error = %err, "HTTP connection closed");
+                            }
+                        });
+                    }
+                    Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                        thread::sleep(ACCEPT_POLL_INTERVAL);
+                    }
+                    Err(err) => {
+                        tracing::error!(error = %err, "HTTP server stopped");
+                        break;
+                    }
+                }
+            }
+        });
+
+        Ok(Self {
+            state,
+            sequence: 0,
+            accept_thread: Some(accept_thread),
+        })
+    }
+
+    pub fn update(
+        &mut self,
+        result: &ThermalCapturerResult,
+        root_gizmo: &Gizmo,
+        unit: TemperatureUnit,
+    ) {
+        let GizmoKind::Root { children } = &root_gizmo.kind else {
+            return;
+        };
+        let gizmos = children
+            .iter()
+            .filter_map(|gizmo| {
+                Some(GizmoReadout {
+                    uuid: gizmo.uuid,
+                    name: gizmo.name.clone(),
+                    result: result.extension.gizmo_results.get(&gizmo.uuid)?.clone(),
+                })
+            })
+            .collect();
+
+        // the image is the only large copy, and nobody would see it
+        let image = (self.state.clients.load(Ordering::Relaxed) > 0)
+            .then(|| Arc::new(result.image.clone()));
+        self.sequence += 1;
+        let frame = Arc::new(LatestFrame {
+            sequence: self.sequence,
+            image,
+            thermal_data: result.thermal_data.clone(),
+            gizmos,
+            unit,
+            is_delta: result.is_delta,
+        });
+        *self.state.latest.lock().unwrap() = Some(frame);
+        self.state.new_frame.notify_all();
+    }
+}
+
+impl Drop for HttpServer {
+    fn drop(&mut self) {
+        self.state.stopped.store(true, Ordering::Relaxed);
+        self.state.new_frame.notify_all();
+        if let Some(accept_thread) = self.accept_thread.take() {
+            let _ = accept_thread.join();
+        }
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, state: &SharedState) -> Result<()> {
+    let _client = ClientGuard::new(state);
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // the headers are not needed, but have to be read before replying
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    if method != "GET" {
+        return write_response(&mut stream, "405 Method Not Allowed", "text/plain", b"");
+    }
+    let path = path.split('?').next().unwrap_or(path);
+
+    match path {
+        "/" => return write_response(&mut stream, "200 OK", "text/plain", INDEX_TEXT.as_bytes()),
+        "/stream.mjpg" => return stream_mjpeg(&mut stream, state),
+        _ => {}
+    }
+
+    let latest = if path == "/frame.jpg" {
+        next_frame_with_image(state, 0).filter(|frame| frame.image.is_some())
+    } else {
+        state.latest.lock().unwrap().clone()
+    };
+    let response = latest
+        .map(|frame| frame_response(&frame, path))
+        .transpose()?;
+    match response {
+        Some(Some((content_type, body))) => {
+            write_response(&mut stream, "200 OK", content_type, &body)
+        }
+        Some(None) => write_response(&mut stream, "404 Not Found", "text/plain", b""),
+        None => write_response(
+            &mut stream,
+            "503 Service Unavailable",
+            "text/plain",
+            b"No image yet",
+        ),
+    }
+}
+
+///
+/// Content type and body for a path which is answered from the latest frame,
+/// None for an unknown path.
+///
+fn frame_response(frame: &LatestFrame, path: &str) -> Result<Option<(&'static str, Vec<u8>)>> {
+    Ok(Some(match path {
+        "/frame.jpg" => match frame.image.as_ref() {
+            Some(image) => ("image/jpeg", encode_jpeg(image)?),
+            None => return Ok(None),
+        },
+        "/temperatures.json" => (
+            "application/json",
+            temperatures_json(frame).to_string().into_bytes(),
+        ),
+        "/temperatures.bin" => ("application/octet-stream", temperatures_bin(frame)),
+        "/gizmos" => (
+            "application/json",
+            serde_json::Value::Array(
+                frame
+                    .gizmos
+                    .iter()
+                    .map(|readout| gizmo_json(readout, frame.unit, frame.is_delta))
+                    .collect(),
+            )
+            .to_string()
+            .into_bytes(),
+        ),
+        _ => {
+            let readout = path
+                .strip_prefix("/gizmos/")
+                .and_then(|uuid| Uuid::parse_str(uuid).ok())
+                .and_then(|uuid| frame.gizmos.iter().find(|r| r.uuid == uuid));
+            let Some(readout) = readout else {
+                return Ok(None);
+            };
+            (
+                "application/json",
+                gizmo_json(readout, frame.unit, frame.is_delta)
+                    .to_string()
+                    .into_bytes(),
+            )
+        }
+    }))
+}
+
+const INDEX_TEXT: &str = "Thermal Cat\n\n\
+    /stream.mjpg\n/frame.jpg\n/temperatures.json\n/temperatures.bin\n/gizmos\n/gizmos/<uuid>\n";
+
+fn write_response(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+) -> Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    Ok(())
+}
+
+fn stream_mjpeg(stream: &mut TcpStream, state: &SharedState) -> Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: multipart/x-mixed-replace; boundary=frame\r\n\
+         Cache-Control: no-cache\r\nConnection: close\r\n\r\n"
+    )?;
+
+    let mut last_sequence = 0;
+    loop {
+        let Some(frame) = next_frame_with_image(state, last_sequence) else {
+            if state.stopped.load(Ordering::Relaxed) {
+                return Ok(());
+            }
+            continue;
+        };
+        last_sequence = frame.sequence;
+        let Some(image) = frame.image.as_ref() else {
+            continue;
+        };
+        let jpeg = encode_jpeg(image)?;
+
+        write!(
+            stream,
+            "--frame\r\nContent-Type: image/jpeg\r\nContent-Length: {}\r\n\r\n",
+            jpeg.len()
+        )?;
+        stream.write_all(&jpeg)?;
+        stream.write_all(b"\r\n")?;
+    }
+}
+
+///
+/// Waits for a frame newer than `last_sequence` which has an image, the image isn't copied
+/// until a client is connected. Returns the latest frame again after FRAME_WAIT_TIMEOUT,
+/// None if there is none or the server was stopped.
+///
+fn next_frame_with_image(state: &SharedState, last_sequence: u64) -> Option<Arc<LatestFrame>> {
+    let mut latest = state.latest.lock().unwrap();
+    while latest
+        .as_ref()
+        .is_none_or(|frame| frame.sequence == last_sequence || frame.image.is_none())
+        && !state.stopped.load(Ordering::Relaxed)
+    {
+        let (guard, timeout) = state
+            .new_frame
+            .wait_timeout(latest, FRAME_WAIT_TIMEOUT)
+            .unwrap();
+        latest = guard;
+        if timeout.timed_out() {
+            break;
+        }
+    }
+    if state.stopped.load(Ordering::Relaxed) {
+        return None;
+    }
+    latest.clone()
+}
+
+fn encode_jpeg(image: &ColorImage) -> Result<Vec<u8>> {
+    let rgba_img = image::RgbaImage::from_raw(
+        image.width() as u32,
+        image.height() as u32,
+        image.as_raw().into(),
+    )
+    .ok_or(anyhow!("Failed to create image for streaming"))?;
+    let mut jpeg = vec![];
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, JPEG_QUALITY)
+        .encode_image(&rgba8_to_rgb8(rgba_img))?;
+    Ok(jpeg)
+}
+
+fn temperatures_json(frame: &LatestFrame) -> serde_json::Value {
+    let data = &frame.thermal_data;
+    serde_json::json!({
+        "width": data.width,
+        "height": data.height,
+        "unit": frame.unit.suffix(),
+        "is_delta": frame.is_delta,
+        "data": data
+            .data
+            .iter()
+            .map(|t| reading_to_unit(*t, frame.unit, frame.is_delta))
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn temperatures_bin(frame: &LatestFrame) -> Vec<u8> {
+    let data = &frame.thermal_data;
+    let mut body = Vec::with_capacity(8 + data.data.len() * 4);
+    body.extend_from_slice(&(data.width as u32).to_le_bytes());
+    body.extend_from_slice(&(data.height as u32).to_le_bytes());
+    for temp in data.data.iter() {
+        let kelvin = reading_to_unit(*temp, TemperatureUnit::Kelvin, frame.is_delta);
+        body.extend_from_slice(&kelvin.to_le_bytes());
+    }
+    body
+}
+
+fn gizmo_json(readout: &GizmoReadout, unit: TemperatureUnit, is_delta: bool) -> serde_json::Value {
+    let result = &readout.result;
+    let to_unit = |value| reading_to_unit(value, unit, is_delta);
+    let mut json = serde_json::json!({
+        "uuid": readout.uuid.to_string(),
+        "name": readout.name,
+        "temperature": to_unit(result.temperature),
+        "unit": unit.suffix(),
+        "is_delta": is_delta,
+        "x": result.pos.x,
+        "y": result.pos.y,
+    });
+    if let Some(stats) = result.stats {
+        json["min"] = to_unit(stats.min).into();
+        json["max"] = to_unit(stats.max).into();
+        json["mean"] = to_unit(stats.mean).into();
+    }
+    json
+}
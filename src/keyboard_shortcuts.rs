@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+
+use eframe::egui::{self, Key};
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter)]
+pub enum ShortcutAction {
+    TogglePause,
+    Snapshot,
+    ToggleRawRecording,
+    ToggleAutoRange,
+    LockRange,
+    ToggleHoldRange,
+    NextGradient,
+    TriggerFfc,
+    CaptureReference,
+    Undo,
+    Redo,
+    ToggleHelp,
+}
+
+impl ShortcutAction {
+    ///
+    /// Shown in the shortcut help window and the preferences, so keep it short.
+    ///
+    pub fn description(&self) -> &'static str {
+        match self {
+            ShortcutAction::TogglePause => "Freeze/resume the live image",
+            ShortcutAction::Snapshot => "Save a snapshot",
+            ShortcutAction::ToggleRawRecording => "Start/stop a raw recording",
+            ShortcutAction::ToggleAutoRange => "Toggle the auto range",
+            ShortcutAction::LockRange => "Lock the current auto range",
+            ShortcutAction::ToggleHoldRange => "Hold/resume the auto range",
+            ShortcutAction::NextGradient => "Switch to the next gradient",
+            ShortcutAction::TriggerFfc => "Calibrate the shutter (FFC)",
+            ShortcutAction::CaptureReference => "Capture a reference, show the difference",
+            ShortcutAction::Undo => "Undo the last edit of gizmos, gradient or range",
+            ShortcutAction::Redo => "Redo",
+            ShortcutAction::ToggleHelp => "Show this list",
+        }
+    }
+
+    pub fn default_trigger(&self) -> ShortcutTrigger {
+        match self {
+            ShortcutAction::TogglePause => ShortcutTrigger::Key(Key::Space),
+            ShortcutAction::Snapshot => ShortcutTrigger::Key(Key::S),
+            ShortcutAction::ToggleRawRecording => ShortcutTrigger::Key(Key::C),
+            ShortcutAction::ToggleAutoRange => ShortcutTrigger::Key(Key::A),
+            ShortcutAction::LockRange => ShortcutTrigger::Key(Key::L),
+            ShortcutAction::ToggleHoldRange => ShortcutTrigger::Key(Key::H),
+            ShortcutAction::NextGradient => ShortcutTrigger::Key(Key::G),
+            ShortcutAction::TriggerFfc => ShortcutTrigger::Key(Key::F),
+            ShortcutAction::CaptureReference => ShortcutTrigger::Key(Key::R),
+            ShortcutAction::Undo => ShortcutTrigger::Command(Key::Z),
+            ShortcutAction::Redo => ShortcutTrigger::Command(Key::Y),
+            ShortcutAction::ToggleHelp => ShortcutTrigger::Text("?".to_string()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ShortcutTrigger {
+    Key(Key),
+    // The key with Ctrl (Cmd on macOS) held
+    Command(Key),
+    // Matched against typed text, for characters which depend on the keyboard layout
+    Text(String),
+}
+
+impl ShortcutTrigger {
+    pub fn name(&self) -> String {
+        match self {
+            ShortcutTrigger::Key(key) => key.name().to_string(),
+            ShortcutTrigger::Command(key) => format!("Ctrl+{}", key.name()),
+            ShortcutTrigger::Text(text) => text.clone(),
+        }
+    }
+
+    fn pressed(&self, i: &egui::InputState) -> bool {
+        match self {
+            ShortcutTrigger::Key(key) => !i.modifiers.command && i.key_pressed(*key),
+            ShortcutTrigger::Command(key) => {
+                i.modifiers.command && !i.modifiers.shift && i.key_pressed(*key)
+            }
+            ShortcutTrigger::Text(text) => {
+                !i.modifiers.command
+                    && i.events
+                        .iter()
+                        .any(|e| matches!(e, egui::Event::Text(t) if t == text))
+            }
+        }
+    }
+}
+
+///
+/// The keys of the shortcuts, stored in the user preferences.
+/// Only the keys changed by the user are stored, the other actions keep their default,
+/// so actions added later get a key without a migration.
+///
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct KeyBindings {
+    // None if the user removed the key of the action
+    overrides: HashMap<ShortcutAction, Option<ShortcutTrigger>>,
+}
+
+impl KeyBindings {
+    pub fn trigger(&self, action: ShortcutAction) -> Option<ShortcutTrigger> {
+        match self.overrides.get(&action) {
+            Some(trigger) => trigger.clone(),
+            None => Some(action.default_trigger()),
+        }
+    }
+
+    pub fn set_trigger(&mut self, action: ShortcutAction, trigger: Option<ShortcutTrigger>) {
+        if trigger.as_ref() == Some(&action.default_trigger()) {
+            self.overrides.remove(&action);
+        } else {
+            self.overrides.insert(action, trigger);
+        }
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.overrides.is_empty()
+    }
+
+    pub fn reset(&mut self) {
+        self.overrides.clear();
+    }
+
+    ///
+    /// The other actions bound to the same key as `action`.
+    ///
+    pub fn conflicts(&self, action: ShortcutAction) -> Vec<ShortcutAction> {
+        let Some(trigger) = self.trigger(action) else {
+            return vec![];
+        };
+        ShortcutAction::iter()
+            .filter(|other| *other != action && self.trigger(*other).as_ref() == Some(&trigger))
+            .collect()
+    }
+}
+
+///
+/// Returns the actions whose shortcuts were pressed this frame.
+/// Returns nothing while a widget (e.g. a text field) is consuming keyboard input.
+///
+pub fn pressed_shortcut_actions(
+    ctx: &egui::Context,
+    bindings: &KeyBindings,
+) -> Vec<ShortcutAction> {
+    if ctx.wants_keyboard_input() {
+        return vec![];
+    }
+    ctx.input(|i| {
+        if i.modifiers.alt {
+            return vec![];
+        }
+        ShortcutAction::iter()
+            .filter(|action| {
+                bindings
+                    .trigger(*action)
+                    .is_some_and(|trigger| trigger.pressed(i))
+            })
+            .collect()
+    })
+}
+
+///
+/// The key pressed this frame as a trigger, for binding it to an action.
+/// Escape is left out, it cancels the binding.
+///
+pub fn captured_trigger(ctx: &egui::Context) -> Option<ShortcutTrigger> {
+    ctx.input(|i| {
+        i.events.iter().find_map(|event| match event {
+            egui::Event::Key {
+                key,
+                pressed: true,
+                repeat: false,
+                modifiers,
+                ..
+            } if *key != Key::Escape && !modifiers.alt => Some(if modifiers.command {
+                ShortcutTrigger::Command(*key)
+            } else {
+                ShortcutTrigger::Key(*key)
+            }),
+            _ => None,
+        })
+    })
+}
+
+pub fn shortcuts_help_ui(ui: &mut egui::Ui, bindings: &KeyBindings) {
+    egui::Grid::new("shortcuts_help_grid")
+        .num_columns(2)
+        .spacing([20.0, 6.0])
+        .striped(true)
+        .show(ui, |ui| {
+            for action in ShortcutAction::iter() {
+                let Some(trigger) = bindings.trigger(action) else {
+                    continue;
+                };
+                ui.monospace(trigger.name());
+                ui.label(action.description());
+                ui.end_row();
+            }
+        });
+    ui.label("The keys can be changed in File > Preferences.");
+}
@@ -1,64 +1,156 @@
 #![deny(elided_lifetimes_in_paths)]
 
-use std::{cell::RefCell, collections::VecDeque, rc::Rc, time::SystemTime};
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
 
-use chart_pane::ChartPane;
-use dynamic_range_curve::DynamicRangeCurve;
-use egui_dock::{DockArea, DockState, NodeIndex};
-use gizmos::{Gizmo, GizmoKind};
+use alarms::{
+    alarms_path, load_alarm_rules, play_alarm_sound, save_alarm_rules, ActiveAlarm, AlarmCondition,
+};
+use annotations::{annotations_sidecar_path, load_annotations};
+use anyhow::{anyhow, Result};
+use app_events::AppEvent;
+use camera_adapter::CameraAdapter;
+use camera_manager::CameraManager;
+use camera_selection::CameraSelectionDialog;
+use chrono::Local;
+use egui_dock::{DockArea, DockState, NodeIndex, TabIndex};
+use gpu_colorizer::GpuColorizer;
 use history_data_collector::HistoryDataCollector;
 use hotplug_detector::{run_hotplug_detector, HotplugDetector};
-use log::error;
+use http_server::HttpServer;
+use keyboard_shortcuts::{
+    pressed_shortcut_actions, shortcuts_help_ui, KeyBindings, ShortcutAction,
+};
+use mqtt_publisher::MqttPublisher;
+use network_camera_dialog::{NetworkCameraDialog, NetworkCameraTarget, RawFrameReceiverDialog};
+use overlay::{Overlay, OverlayOptions};
 
-use nokhwa::native_api_backend;
+use nokhwa::{native_api_backend, utils::CameraIndex};
 
 use eframe::{
     egui::{self},
-    epaint::Color32,
     icon_data,
 };
-use pane_dispatcher::{Pane, PaneDispatcher};
+use pane_dispatcher::{Pane, PaneContext, PaneDispatcher, PaneKind};
 use panes::{
+    alarms_pane::AlarmsPane,
+    camera_pane::CameraPane,
+    camera_settings_pane::CameraSettingsPane,
     capture_pane::CapturePane,
     gallery_pane::{GalleryElement, GalleryPane},
+    gizmo_list_pane::GizmoListPane,
+    gradient_editor_pane::GradientEditorPane,
     histogram_pane::HistogramPane,
+    line_profile_pane::LineProfilePane,
+    log_pane::LogPane,
     measurements_pane::MeasurementsPane,
+    report_pane::ReportPane,
     setup_pane::SetupPane,
+    status_bar_pane::StatusBarPane,
+    temporal_plot_pane::TemporalPlotPane,
     thermal_display_pane::ThermalDisplayPane,
     user_preferences_pane::UserPreferencesPane,
 };
-use recorders::recorder::RecorderState;
-use temperature::{Temp, TempRange, TemperatureUnit};
-use thermal_capturer::{ThermalCapturer, ThermalCapturerResult, ThermalCapturerSettings};
-use types::image_rotation::ImageRotation;
-use user_preferences::UserPreferences;
+use persistence::{load_session, save_session, session_path};
+use profiles::{load_profiles, profiles_path, save_profiles, Profile};
+use radiometric_png::read_radiometric_png;
+use radiometric_recording::RADIOMETRIC_EXTENSION;
+use radiometric_tiff::save_radiometric_tiff;
+use recorders::{
+    image_recorder::{save_result_image, ImageMetadata, ImageRecorder},
+    recorder::RecorderState,
+};
+use report::ReportItem;
+use settings_bundle::{export_bundle, import_bundle};
+use settings_history::SettingsHistory;
+use temperature::{TempFormat, TemperatureUnit};
+use thermal_capturer::{
+    ThermalCapturer, ThermalCapturerEvent, ThermalCapturerExt, ThermalCapturerResult,
+    ThermalCapturerSettings,
+};
+use thermal_cat_core::{
+    auto_display_range_controller, camera_adapter, change_detection, contour_lines, denoise,
+    flat_field_calibration, grid_analysis, histogram_equalization, isotherm, lens_distortion,
+    pseudo_color, temperature, temperature_correction, temporal_average, thermal_data,
+    thermal_gradient, two_point_calibration, upscaling,
+};
+use thermal_gradient::{
+    custom_gradients_path, load_custom_gradients, save_custom_gradients, ThermalGradient,
+    THERMAL_GRADIENTS,
+};
+use thermal_source::{
+    camera_source::{camera_id, CameraSource},
+    network_source::{NetworkCameraConfig, NetworkSource},
+    playback_source::PlaybackSource,
+    raw_frame_source::{RawFrameReceiverConfig, RawFrameSource},
+    snapshot_source::SnapshotSource,
+    ThermalSource,
+};
+use types::{image_rotation::ImageRotation, media_formats::ImageFormat};
+use user_preferences::{CameraOrientation, UserPreferences};
 use video_thumbnail_loader::VideoThumbnailLoader;
+use visible_capturer::VisibleCapturer;
 
-mod auto_display_range_controller;
-mod camera_adapter;
+mod alarms;
+mod annotations;
+mod app_events;
+mod app_log;
 mod camera_enumerator;
-mod chart_pane;
+mod camera_manager;
+mod camera_selection;
 mod dynamic_range_curve;
+mod fusion;
 mod gizmos;
+mod gpu_colorizer;
 mod gradient_selector_widget;
+mod headless;
 mod history_data_collector;
 mod hotplug_detector;
+mod http_server;
+mod keyboard_shortcuts;
+mod measurement_log;
+mod mqtt_publisher;
+mod network_camera_dialog;
+mod overlay;
 mod pane_dispatcher;
 mod panes;
+mod persistence;
+mod profiles;
+mod radiometric_png;
+mod radiometric_recording;
+mod radiometric_tiff;
 mod recorders;
-mod temperature;
+mod report;
+mod settings_bundle;
+mod settings_history;
 mod temperature_edit_field;
 mod thermal_capturer;
-mod thermal_data;
-mod thermal_gradient;
+mod thermal_source;
+mod triggered_recording;
 mod types;
 mod user_preferences;
 mod util;
 mod video_thumbnail_loader;
+mod visible_capturer;
 mod widgets;
 
 fn main() -> Result<(), eframe::Error> {
-    env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
+    // Log to stderr (if you run with `RUST_LOG=debug`) and to the log pane
+    app_log::init_logging();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(pos) = args.iter().position(|arg| arg == headless::HEADLESS_FLAG) {
+        if let Err(err) = headless::run_headless(&args[pos + 1..]) {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([900.0, 600.0])
@@ -80,218 +172,1224 @@ fn main() -> Result<(), eframe::Error> {
             cc.egui_ctx
                 .add_image_loader(std::sync::Arc::new(VideoThumbnailLoader::default()));
 
-            Box::<ThermalViewerApp>::default()
+            let mut app = ThermalViewerApp::default();
+            app.global_state.gpu_colorizer = cc.wgpu_render_state.clone().map(GpuColorizer::new);
+            Box::new(app)
         }),
     )
 }
 
+///
+/// State of the app. The panes are drawn with it read-only, their changes are
+/// sent as `AppEvent`s and applied by the app once the frame is drawn.
+///
 pub struct AppGlobalState {
-    did_try_open_camera_at_startup: bool,
     should_try_open_camera_on_next_hotplug: bool,
 
     thermal_capturer_inst: Option<ThermalCapturer>,
     thermal_capturer_settings: ThermalCapturerSettings,
     last_thermal_capturer_result: Option<Box<ThermalCapturerResult>>,
+    // Why the capturer last failed, cleared when a new one is started
+    thermal_capturer_error: Option<String>,
+    // Where the enumeration found the camera the capturer reads, None for recordings and snapshots
+    thermal_camera_index: Option<CameraIndex>,
+    // Id of the camera the capturer reads, None for recordings and snapshots
+    thermal_camera_id: Option<String>,
+    // Model and serial number of that camera, for display
+    thermal_camera_name: Option<String>,
+    // Id of the camera opened last, kept after it's closed so it's reopened on the next start
+    last_camera_id: Option<String>,
+    // Cameras displayed next to the main one
+    camera_manager: CameraManager,
+    // Webcam whose image is fused with the thermal one
+    visible_capturer: Option<VisibleCapturer>,
+    // Colorizes the displayed frame when enabled in the preferences, None without wgpu
+    gpu_colorizer: Option<GpuColorizer>,
+    // Texture the GPU colorizer rendered the displayed frame into, see `colorize_on_gpu`
+    gpu_texture_id: Option<egui::TextureId>,
+
+    // When paused (frozen), new results are still received and alarms evaluated, but the displayed
+    // frame is not replaced. Only its gizmo results are, the capturer measures them on the frozen frame.
+    paused: bool,
 
     hotplug_detector: Option<HotplugDetector>,
     history_data_collector: HistoryDataCollector,
+    // Undo/redo of the edits of gizmos, annotations, gradient and range
+    settings_history: SettingsHistory,
+    // Set by the preferences while waiting for the key of a shortcut, which mustn't trigger it
+    capturing_shortcut: bool,
+    // The settings were edited since they were last given to the capturer
+    settings_changed: bool,
 
     prefs: Option<UserPreferences>,
+    // Running while enabled in the preferences
+    mqtt_publisher: Option<MqttPublisher>,
+    http_server: Option<HttpServer>,
 
     // Thumbnails shown in the "Capture tab"
     gallery: VecDeque<GalleryElement>,
     did_init_gallery: bool,
+
+    snapshot_format: ImageFormat,
+    // Burn the timestamp and range into a footer below snapshots
+    snapshot_footer: bool,
+    // Embed the temperatures into PNG snapshots
+    snapshot_radiometric: bool,
+    // Burned into snapshots, timelapse images and videos
+    export_overlay: OverlayOptions,
+
+    // Alarms of the latest result, also updated while paused
+    active_alarms: Vec<ActiveAlarm>,
+
+    // Gradients which are not built in, created in the gradient editor or imported.
+    // Persisted in the config directory.
+    custom_gradients: Vec<ThermalGradient>,
+
+    // Named measurement setups, persisted in the config directory
+    profiles: Vec<Profile>,
 }
 
 impl AppGlobalState {
+    ///
+    /// Edits the settings, the capturer gets them once all events of the frame are handled.
+    ///
+    fn edit_settings(&mut self, edit: impl FnOnce(&mut ThermalCapturerSettings)) {
+        self.settings_history
+            .begin_edit(&self.thermal_capturer_settings);
+        edit(&mut self.thermal_capturer_settings);
+        self.settings_changed = true;
+    }
+
+    ///
+    /// How temperatures are shown, from the preferences, or their defaults if they
+    /// couldn't be loaded.
+    ///
+    fn temp_format(&self) -> TempFormat {
+        match self.prefs.as_ref() {
+            Some(prefs) => TempFormat::new(prefs.temperature_unit, prefs.temperature_precision),
+            None => {
+                let defaults = UserPreferences::default();
+                TempFormat::new(defaults.temperature_unit, defaults.temperature_precision)
+            }
+        }
+    }
+
     fn preferred_temperature_unit(&self) -> TemperatureUnit {
+        self.temp_format().unit
+    }
+
+    ///
+    /// Starts or stops publishing to MQTT according to the preferences,
+    /// reconnecting with the new settings if already running.
+    ///
+    fn restart_mqtt_publisher(&mut self) {
+        self.mqtt_publisher = self
+            .prefs
+            .as_ref()
+            .filter(|prefs| prefs.mqtt.enabled)
+            .map(|prefs| MqttPublisher::start(prefs.mqtt.clone()));
+    }
+
+    fn restart_http_server(&mut self) {
+        // the old server has to release the port first
+        self.http_server = None;
+        self.http_server = self
+            .prefs
+            .as_ref()
+            .filter(|prefs| prefs.http_server.enabled)
+            .and_then(|prefs| {
+                HttpServer::start(&prefs.http_server)
+                    .inspect_err(|err| tracing::error!(error = %err, "Failed to start HTTP server"))
+                    .ok()
+            });
+    }
+
+    fn preferred_temperature_precision(&self) -> usize {
+        self.temp_format().precision
+    }
+
+    ///
+    /// Copies the range currently used for coloring the image into the manual range
+    /// and disables auto range, freezing the palette in place.
+    ///
+    fn lock_current_range(&mut self) {
+        let Some(range) = self
+            .last_thermal_capturer_result
+            .as_ref()
+            .map(|res| res.image_range)
+        else {
+            return;
+        };
+        self.edit_settings(|settings| {
+            settings.manual_range = range;
+            settings.auto_range = false;
+        });
+    }
+
+    ///
+    /// Stores the rotation and mirroring of the settings as the ones of the open camera.
+    ///
+    fn remember_camera_orientation(&mut self) {
+        let Some(camera_id) = self.thermal_camera_id.clone() else {
+            return;
+        };
+        let orientation = CameraOrientation::from_settings(&self.thermal_capturer_settings);
+        if let Some(prefs) = self.prefs.as_mut() {
+            prefs.camera_orientations.insert(camera_id, orientation);
+            if let Err(err) = prefs.save() {
+                tracing::error!(error = %err, "Failed to save preferences");
+            }
+        }
+    }
+
+    ///
+    /// Stores the lens distortion correction of the settings as the one of the open camera.
+    ///
+    fn remember_lens_profile(&mut self) {
+        let Some(camera_id) = self.thermal_camera_id.clone() else {
+            return;
+        };
+        let lens_distortion = self.thermal_capturer_settings.lens_distortion;
+        if let Some(prefs) = self.prefs.as_mut() {
+            prefs.lens_profiles.insert(camera_id, lens_distortion);
+            if let Err(err) = prefs.save() {
+                tracing::error!(error = %err, "Failed to save preferences");
+            }
+        }
+    }
+
+    ///
+    /// Stores the next frame as the reference and shows the differences to it.
+    ///
+    fn capture_delta_reference(&mut self) {
+        if let Some(thermal_capturer) = self.thermal_capturer_inst.as_mut() {
+            thermal_capturer.capture_delta_reference();
+        }
+        self.set_delta_mode(true);
+    }
+
+    fn set_delta_mode(&mut self, delta_mode: bool) {
+        self.edit_settings(|settings| settings.delta_mode = delta_mode);
+    }
+
+    ///
+    /// Freezes the display on the current frame, or resumes the live image.
+    ///
+    fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        let frozen_frame = self
+            .last_thermal_capturer_result
+            .as_ref()
+            .filter(|_| paused)
+            .map(|result| result.thermal_data.clone());
+        if let Some(thermal_capturer) = self.thermal_capturer_inst.as_mut() {
+            thermal_capturer.set_frozen_frame(frozen_frame);
+        }
+    }
+
+    fn key_bindings(&self) -> KeyBindings {
         self.prefs
             .as_ref()
-            .map(|p| p.temperature_unit)
+            .map(|prefs| prefs.keybindings.clone())
             .unwrap_or_default()
     }
+
+    fn toggle_auto_range(&mut self) {
+        self.edit_settings(|settings| settings.auto_range = !settings.auto_range);
+    }
+
+    ///
+    /// Switches to the gradient after the current one, the built in ones first.
+    ///
+    fn select_next_gradient(&mut self) {
+        let gradients: Vec<&ThermalGradient> = THERMAL_GRADIENTS
+            .iter()
+            .chain(self.custom_gradients.iter())
+            .collect();
+        let current = gradients
+            .iter()
+            .position(|g| g.uuid == self.thermal_capturer_settings.gradient.uuid);
+        let next = current.map_or(0, |i| (i + 1) % gradients.len());
+        let gradient = gradients[next].clone();
+        self.edit_settings(|settings| settings.gradient = gradient);
+    }
+
+    fn trigger_ffc(&mut self) {
+        if let Some(thermal_capturer) = self.thermal_capturer_inst.as_mut() {
+            thermal_capturer.trigger_ffc();
+        }
+    }
+
+    ///
+    /// Starts recording the temperatures of every frame into the captures directory.
+    ///
+    fn start_radiometric_recording(&mut self) {
+        let captures_dir = self
+            .prefs
+            .as_ref()
+            .map(|prefs| prefs.captures_directory.clone())
+            .unwrap_or("./".to_string());
+        let filename = format!(
+            "radiometric_{}.{}",
+            Local::now().format("%Y-%m-%d_%H-%M-%S"),
+            RADIOMETRIC_EXTENSION
+        );
+        if let Some(thermal_capturer) = self.thermal_capturer_inst.as_mut() {
+            thermal_capturer
+                .start_radiometric_recording(PathBuf::from(captures_dir).join(filename));
+        }
+    }
+
+    fn toggle_radiometric_recording(&mut self) {
+        let is_recording_radiometric = self
+            .last_thermal_capturer_result
+            .as_ref()
+            .is_some_and(|r| r.extension.is_recording_radiometric);
+        if is_recording_radiometric {
+            if let Some(thermal_capturer) = self.thermal_capturer_inst.as_mut() {
+                thermal_capturer.stop_radiometric_recording();
+            }
+        } else {
+            self.start_radiometric_recording();
+        }
+    }
+
+    fn undo(&mut self) {
+        if self
+            .settings_history
+            .undo(&mut self.thermal_capturer_settings)
+        {
+            self.settings_changed = true;
+        }
+    }
+
+    fn redo(&mut self) {
+        if self
+            .settings_history
+            .redo(&mut self.thermal_capturer_settings)
+        {
+            self.settings_changed = true;
+        }
+    }
+
+    fn toggle_hold_range(&mut self) {
+        self.edit_settings(|settings| settings.auto_range_held = !settings.auto_range_held);
+    }
+
+    ///
+    /// Metadata written into still images, describing the current settings.
+    ///
+    ///
+    /// Colorizes the displayed frame with the current settings, before the panes are drawn.
+    /// None if it's disabled or only the CPU can colorize the frame.
+    ///
+    fn colorize_on_gpu(&mut self) {
+        self.gpu_texture_id = match (
+            self.gpu_colorizer.as_mut(),
+            self.last_thermal_capturer_result.as_ref(),
+        ) {
+            (Some(colorizer), Some(res))
+                if self
+                    .prefs
+                    .as_ref()
+                    .is_some_and(|prefs| prefs.gpu_colorization) =>
+            {
+                colorizer.colorize(res, &self.thermal_capturer_settings)
+            }
+            _ => None,
+        };
+    }
+
+    fn image_metadata(&self) -> ImageMetadata {
+        ImageMetadata::new(
+            &self.thermal_capturer_settings,
+            self.preferred_temperature_unit(),
+            self.preferred_temperature_precision(),
+            self.snapshot_footer,
+            self.snapshot_radiometric,
+            self.export_overlay,
+        )
+    }
+
+    ///
+    /// What is burned into recorded videos, with the current settings.
+    ///
+    fn video_overlay(&self) -> Overlay {
+        Overlay::new(
+            self.export_overlay,
+            &self.thermal_capturer_settings,
+            self.preferred_temperature_unit(),
+            self.preferred_temperature_precision(),
+        )
+    }
+
+    fn take_snapshot(&mut self) {
+        if self.thermal_capturer_inst.is_none() {
+            return;
+        }
+        let captures_dir = self
+            .prefs
+            .as_ref()
+            .map(|prefs| prefs.captures_directory.clone())
+            .unwrap_or("./".to_string());
+        let recorder = ImageRecorder::new(
+            PathBuf::from(captures_dir),
+            "photo".to_string(),
+            self.snapshot_format,
+            self.image_metadata(),
+        );
+        self.edit_settings(|settings| settings.recorders.push(Arc::new(Mutex::new(recorder))));
+    }
+
+    ///
+    /// Saves the displayed frame as a radiometric snapshot into the captures directory,
+    /// with its readings for the inspection report.
+    ///
+    fn snapshot_for_report(&self) -> Result<ReportItem> {
+        let Some(result) = self.last_thermal_capturer_result.as_ref() else {
+            return Err(anyhow!("No frame to add"));
+        };
+        let captures_dir = self
+            .prefs
+            .as_ref()
+            .map(|prefs| prefs.captures_directory.clone())
+            .unwrap_or("./".to_string());
+        std::fs::create_dir_all(&captures_dir)?;
+
+        let timestamp = Local::now();
+        let filename = format!(
+            "report_{}.{}",
+            timestamp.format("%Y-%m-%d_%H-%M-%S"),
+            ImageFormat::Png.extension()
+        );
+        let path = PathBuf::from(captures_dir).join(filename);
+        let metadata = ImageMetadata {
+            embed_temperatures: true,
+            ..self.image_metadata()
+        };
+        save_result_image(result, &path, ImageFormat::Png, &metadata, timestamp)?;
+        Ok(ReportItem::from_result(
+            path,
+            result,
+            &self.thermal_capturer_settings,
+            self.preferred_temperature_unit(),
+            self.preferred_temperature_precision(),
+        ))
+    }
+
+    ///
+    /// Saves the temperatures of the displayed frame as a 16-bit radiometric TIFF
+    /// into the captures directory.
+    ///
+    fn export_radiometric_tiff(&self) -> Result<PathBuf> {
+        let Some(result) = self.last_thermal_capturer_result.as_ref() else {
+            return Err(anyhow!("No frame to export"));
+        };
+        let captures_dir = self
+            .prefs
+            .as_ref()
+            .map(|prefs| prefs.captures_directory.clone())
+            .unwrap_or("./".to_string());
+        std::fs::create_dir_all(&captures_dir)?;
+
+        let filename = format!(
+            "radiometric_{}.tiff",
+            Local::now().format("%Y-%m-%d_%H-%M-%S")
+        );
+        let path = PathBuf::from(captures_dir).join(filename);
+        save_radiometric_tiff(&result.thermal_data, &path)?;
+        Ok(path)
+    }
+
+    ///
+    /// Opens the camera found at `index`, replacing the camera or recording shown so far.
+    /// The camera is opened on the capturer thread, which reports if that fails.
+    ///
+    fn open_camera(
+        &mut self,
+        index: CameraIndex,
+        adapter: Arc<dyn CameraAdapter>,
+        ctx: &egui::Context,
+    ) {
+        let camera_id = camera_id(adapter.as_ref());
+        tracing::info!(adapter = %adapter.name(), %index, %camera_id, "Opening camera");
+        if let Some(prefs) = self.prefs.as_ref() {
+            prefs.apply_camera_profile(&camera_id, &mut self.thermal_capturer_settings);
+        }
+
+        let source = CameraSource::new(index.clone(), adapter.clone());
+        let cloned_ctx = ctx.clone();
+        let mut capturer = ThermalCapturer::new(
+            Box::new(source),
+            self.thermal_capturer_settings.clone(),
+            Arc::new(move || {
+                cloned_ctx.request_repaint(); // repaint so that the result can be read out
+            }),
+        );
+        capturer.start();
+        self.thermal_capturer_inst = Some(capturer);
+        self.thermal_capturer_error = None;
+        self.thermal_camera_index = Some(index);
+        self.thermal_camera_id = Some(camera_id.clone());
+        self.last_camera_id = Some(camera_id);
+        self.thermal_camera_name = Some(match adapter.serial_number() {
+            Some(serial) => format!("{} (S/N {})", adapter.name(), serial),
+            None => adapter.name(),
+        });
+        // the user wants this camera, open it again if it's replugged after closing
+        self.should_try_open_camera_on_next_hotplug = true;
+    }
+
+    ///
+    /// Replaces the camera with a playback of a radiometric recording,
+    /// with the annotations saved next to it.
+    ///
+    fn open_recording(&mut self, path: &Path, ctx: &egui::Context) -> Result<()> {
+        let source = PlaybackSource::open(path)?;
+        let sidecar = annotations_sidecar_path(path);
+        if sidecar.exists() {
+            match load_annotations(&sidecar) {
+                Ok(annotations) => self.thermal_capturer_settings.annotations = annotations,
+                Err(err) => tracing::error!(error = %err, ?sidecar, "Failed to load annotations"),
+            }
+        }
+        let cloned_ctx = ctx.clone();
+        let mut capturer = ThermalCapturer::new(
+            Box::new(source),
+            self.thermal_capturer_settings.clone(),
+            Arc::new(move || {
+                cloned_ctx.request_repaint(); // repaint so that the result can be read out
+            }),
+        );
+        capturer.start();
+        self.thermal_capturer_inst = Some(capturer);
+        self.thermal_capturer_error = None;
+        self.thermal_camera_index = None;
+        self.thermal_camera_id = None;
+        self.thermal_camera_name = None;
+        // don't switch back to the camera behind the user's back
+        self.should_try_open_camera_on_next_hotplug = false;
+        Ok(())
+    }
+
+    ///
+    /// Replaces the camera with a radiometric snapshot, so it can be measured again.
+    /// The gradient, range and annotations it was saved with are restored as well.
+    ///
+    fn open_snapshot(&mut self, path: &Path, ctx: &egui::Context) -> Result<()> {
+        let snapshot = read_radiometric_png(path)?;
+        if let Some(settings) = snapshot.settings {
+            self.thermal_capturer_settings.gradient = settings.gradient;
+            self.thermal_capturer_settings.auto_range = settings.auto_range;
+            self.thermal_capturer_settings.manual_range = settings.manual_range;
+            self.thermal_capturer_settings.dynamic_range_curve = settings.dynamic_range_curve;
+            self.thermal_capturer_settings.annotations = settings.annotations;
+        }
+        // the stored temperatures are already rotated and corrected
+        self.thermal_capturer_settings.rotation = ImageRotation::None;
+        self.thermal_capturer_settings.flip_horizontal = false;
+        self.thermal_capturer_settings.flip_vertical = false;
+        self.thermal_capturer_settings.lens_distortion.enabled = false;
+        self.thermal_capturer_settings.emissivity = 1.0;
+        self.thermal_capturer_settings
+            .gizmo
+            .clear_emissivity_overrides();
+
+        let cloned_ctx = ctx.clone();
+        let mut capturer = ThermalCapturer::new(
+            Box::new(SnapshotSource::new(snapshot.data)),
+            self.thermal_capturer_settings.clone(),
+            Arc::new(move || {
+                cloned_ctx.request_repaint(); // repaint so that the result can be read out
+            }),
+        );
+        capturer.start();
+        self.thermal_capturer_inst = Some(capturer);
+        self.thermal_capturer_error = None;
+        self.thermal_camera_index = None;
+        self.thermal_camera_id = None;
+        self.thermal_camera_name = None;
+        self.should_try_open_camera_on_next_hotplug = false;
+        Ok(())
+    }
+
+    ///
+    /// Opens a network camera, which is filled into the dialog the next time.
+    ///
+    fn open_network_camera(
+        &mut self,
+        config: NetworkCameraConfig,
+        target: NetworkCameraTarget,
+        ctx: &egui::Context,
+    ) {
+        if let Some(prefs) = self.prefs.as_mut() {
+            prefs.network_camera = config.clone();
+            if let Err(err) = prefs.save() {
+                tracing::error!(error = %err, "Failed to save preferences");
+            }
+        }
+        let (camera_id, name) = (config.camera_id(), config.display_url());
+        let source = Box::new(NetworkSource::new(config));
+        self.open_live_source(source, camera_id, name, target, ctx);
+    }
+
+    ///
+    /// Starts receiving raw frames from a sender on the network, e.g. a microcontroller.
+    ///
+    fn open_raw_frame_receiver(
+        &mut self,
+        config: RawFrameReceiverConfig,
+        target: NetworkCameraTarget,
+        ctx: &egui::Context,
+    ) {
+        if let Some(prefs) = self.prefs.as_mut() {
+            prefs.raw_frame_receiver = config.clone();
+            if let Err(err) = prefs.save() {
+                tracing::error!(error = %err, "Failed to save preferences");
+            }
+        }
+        let (camera_id, name) = (config.camera_id(), config.name());
+        let source = Box::new(RawFrameSource::new(config));
+        self.open_live_source(source, camera_id, name, target, ctx);
+    }
+
+    ///
+    /// Opens a live source other than a USB camera, either replacing the camera or recording
+    /// shown so far or in a pane of its own (see `CameraManager`).
+    ///
+    fn open_live_source(
+        &mut self,
+        source: Box<dyn ThermalSource>,
+        camera_id: String,
+        name: String,
+        target: NetworkCameraTarget,
+        ctx: &egui::Context,
+    ) {
+        tracing::info!(%name, %camera_id, "Opening camera");
+        if let NetworkCameraTarget::Alongside = target {
+            let settings = self.additional_camera_settings(Some(&camera_id));
+            self.camera_manager.open_source(source, name, settings, ctx);
+            return;
+        }
+
+        if let Some(prefs) = self.prefs.as_ref() {
+            prefs.apply_camera_profile(&camera_id, &mut self.thermal_capturer_settings);
+        }
+        let cloned_ctx = ctx.clone();
+        let mut capturer = ThermalCapturer::new(
+            source,
+            self.thermal_capturer_settings.clone(),
+            Arc::new(move || {
+                cloned_ctx.request_repaint(); // repaint so that the result can be read out
+            }),
+        );
+        capturer.start();
+        self.thermal_capturer_inst = Some(capturer);
+        self.thermal_capturer_error = None;
+        self.thermal_camera_index = None;
+        self.thermal_camera_id = Some(camera_id);
+        self.thermal_camera_name = Some(name);
+        self.should_try_open_camera_on_next_hotplug = false;
+    }
+
+    ///
+    /// Settings for a camera opened next to the main one: the look of the main camera,
+    /// but nothing it records or alarms on, turned the way the camera was last time.
+    ///
+    fn additional_camera_settings(&self, camera_id: Option<&str>) -> ThermalCapturerSettings {
+        let mut settings = ThermalCapturerSettings {
+            recorders: vec![],
+            alarm_rules: vec![],
+            visible_frames: None,
+            ..self.thermal_capturer_settings.clone()
+        };
+        if let (Some(camera_id), Some(prefs)) = (camera_id, self.prefs.as_ref()) {
+            prefs.apply_camera_profile(camera_id, &mut settings);
+        }
+        settings
+    }
+
+    fn export_settings(&self, path: &Path) -> Result<()> {
+        export_bundle(
+            path,
+            &self.thermal_capturer_settings,
+            self.preferred_temperature_unit(),
+        )
+    }
+
+    ///
+    /// Replaces the current settings with the ones from a bundle.
+    /// Recordings in progress are kept, and a gradient which is not known yet
+    /// is added to the custom gradients so it can be selected again later.
+    ///
+    fn import_settings(&mut self, path: &Path) -> Result<()> {
+        let bundle = import_bundle(path)?;
+        self.add_unknown_gradient(&bundle.settings.gradient);
+
+        self.edit_settings(|settings| {
+            *settings = ThermalCapturerSettings {
+                recorders: std::mem::take(&mut settings.recorders),
+                alarm_rules: std::mem::take(&mut settings.alarm_rules),
+                visible_frames: settings.visible_frames.take(),
+                ..bundle.settings
+            }
+        });
+
+        if let Some(prefs) = self.prefs.as_mut() {
+            prefs.temperature_unit = bundle.temperature_unit;
+            prefs.save()?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// Adds a gradient coming from a file to the custom gradients, unless it's already known.
+    ///
+    fn add_unknown_gradient(&mut self, gradient: &ThermalGradient) {
+        let is_known = THERMAL_GRADIENTS
+            .iter()
+            .chain(self.custom_gradients.iter())
+            .any(|g| g.uuid == gradient.uuid);
+        if !is_known {
+            self.custom_gradients.push(gradient.clone());
+            if let Err(err) = save_custom_gradients(&self.custom_gradients) {
+                tracing::error!(error = %err, "Failed to save custom gradients");
+            }
+        }
+    }
+
+    ///
+    /// Switches to the settings and alarms of the profile.
+    ///
+    fn apply_profile(&mut self, profile: &Profile) {
+        self.add_unknown_gradient(&profile.settings.gradient);
+
+        self.edit_settings(|settings| {
+            *settings = ThermalCapturerSettings {
+                recorders: std::mem::take(&mut settings.recorders),
+                alarm_rules: profile.alarm_rules.clone(),
+                visible_frames: settings.visible_frames.take(),
+                ..profile.settings.clone()
+            }
+        });
+        if let Err(err) = save_alarm_rules(&self.thermal_capturer_settings.alarm_rules) {
+            tracing::error!(error = %err, "Failed to save alarms");
+        }
+    }
+
+    ///
+    /// Adds the profile, replacing the one with the same name.
+    ///
+    fn save_profile(&mut self, profile: Profile) {
+        match self.profiles.iter_mut().find(|p| p.name == profile.name) {
+            Some(existing) => *existing = profile,
+            None => self.profiles.push(profile),
+        }
+        self.write_profiles();
+    }
+
+    fn delete_profile(&mut self, name: &str) {
+        self.profiles.retain(|p| p.name != name);
+        self.write_profiles();
+    }
+
+    fn write_profiles(&self) {
+        if let Err(err) = save_profiles(&self.profiles) {
+            tracing::error!(
+                error = %err,
+                path = %profiles_path().display(),
+                "Failed to save profiles"
+            );
+        }
+    }
 }
 
 struct ThermalViewerApp {
     did_init: bool,
+    show_shortcuts_help: bool,
+    camera_selection: CameraSelectionDialog,
+    network_camera_dialog: NetworkCameraDialog,
+    raw_frame_receiver_dialog: RawFrameReceiverDialog,
 
     dock_state: DockState<Box<dyn Pane>>,
 
-    global_state: Rc<RefCell<AppGlobalState>>,
+    global_state: AppGlobalState,
+    // Sent while the frame is drawn, handled at its end
+    events: Vec<AppEvent>,
 }
 
 impl ThermalViewerApp {
+    ///
+    /// Handles the events sent during the frame, in the order they were sent.
+    /// The settings are pushed to the capturer once, however many edits were made.
+    ///
+    fn handle_events(&mut self, ctx: &egui::Context) {
+        let events = std::mem::take(&mut self.events);
+        if !events.is_empty() {
+            ctx.request_repaint();
+        }
+        for event in events {
+            match event {
+                AppEvent::EditSettings(edit) => self.global_state.edit_settings(edit),
+                AppEvent::Update(update) => update(&mut self.global_state),
+                AppEvent::Action(action) => self.handle_action(action),
+            }
+        }
+
+        let global_state = &mut self.global_state;
+        if std::mem::take(&mut global_state.settings_changed) {
+            let settings_clone = global_state.thermal_capturer_settings.clone();
+            if let Some(thermal_capturer) = global_state.thermal_capturer_inst.as_mut() {
+                thermal_capturer.set_settings(settings_clone);
+            }
+        }
+    }
+
+    fn handle_action(&mut self, action: ShortcutAction) {
+        let global_state = &mut self.global_state;
+        match action {
+            ShortcutAction::TogglePause => {
+                let paused = !global_state.paused;
+                global_state.set_paused(paused);
+            }
+            ShortcutAction::Snapshot => global_state.take_snapshot(),
+            ShortcutAction::ToggleRawRecording => global_state.toggle_radiometric_recording(),
+            ShortcutAction::ToggleAutoRange => global_state.toggle_auto_range(),
+            ShortcutAction::LockRange => global_state.lock_current_range(),
+            ShortcutAction::ToggleHoldRange => global_state.toggle_hold_range(),
+            ShortcutAction::NextGradient => global_state.select_next_gradient(),
+            ShortcutAction::TriggerFfc => global_state.trigger_ffc(),
+            ShortcutAction::CaptureReference => global_state.capture_delta_reference(),
+            ShortcutAction::Undo => global_state.undo(),
+            ShortcutAction::Redo => global_state.redo(),
+            ShortcutAction::ToggleHelp => {
+                self.show_shortcuts_help = !self.show_shortcuts_help;
+            }
+        }
+    }
+
     fn set_default_dock_state(&mut self) {
-        self.dock_state = DockState::new(vec![Box::new(ThermalDisplayPane::new(
-            self.global_state.clone(),
-        ))]);
+        self.dock_state = DockState::new(vec![Box::new(ThermalDisplayPane::new())]);
         let [right, left] = self.dock_state.main_surface_mut().split_left(
             NodeIndex::root(),
             0.3,
-            vec![Box::new(SetupPane::new(self.global_state.clone()))],
+            vec![
+                Box::new(SetupPane::new()),
+                Box::new(GradientEditorPane::new()),
+                Box::new(CameraSettingsPane::new()),
+            ],
         );
 
-        self.dock_state.main_surface_mut().split_below(
+        let [display, _] = self.dock_state.main_surface_mut().split_below(
             right,
             0.7,
             vec![
-                Box::new(HistogramPane::new(self.global_state.clone())),
-                Box::new(ChartPane::new(self.global_state.clone())),
-                Box::new(GalleryPane::new(self.global_state.clone())),
+                Box::new(HistogramPane::new()),
+                Box::new(TemporalPlotPane::new()),
+                Box::new(LineProfilePane::new()),
+                Box::new(GalleryPane::new()),
             ],
         );
 
+        self.dock_state.main_surface_mut().split_below(
+            display,
+            0.92,
+            vec![Box::new(StatusBarPane::new())],
+        );
         self.dock_state.main_surface_mut().split_below(
             left,
             0.75,
-            vec![Box::new(CapturePane::new(self.global_state.clone()))],
+            vec![Box::new(CapturePane::new())],
         );
         self.dock_state.main_surface_mut().split_below(
             left,
             0.8,
-            vec![Box::new(MeasurementsPane::new(self.global_state.clone()))],
+            vec![
+                Box::new(MeasurementsPane::new()),
+                Box::new(GizmoListPane::new()),
+                Box::new(AlarmsPane::new()),
+            ],
         );
     }
+
+    fn create_pane(&self, kind: PaneKind) -> Box<dyn Pane> {
+        match kind {
+            PaneKind::ThermalDisplay => Box::new(ThermalDisplayPane::new()),
+            PaneKind::Setup => Box::new(SetupPane::new()),
+            PaneKind::GradientEditor => Box::new(GradientEditorPane::new()),
+            PaneKind::Histogram => Box::new(HistogramPane::new()),
+            PaneKind::TemporalPlot => Box::new(TemporalPlotPane::new()),
+            PaneKind::LineProfile => Box::new(LineProfilePane::new()),
+            PaneKind::Gallery => Box::new(GalleryPane::new()),
+            PaneKind::Capture => Box::new(CapturePane::new()),
+            PaneKind::Measurements => Box::new(MeasurementsPane::new()),
+            PaneKind::GizmoList => Box::new(GizmoListPane::new()),
+            PaneKind::Alarms => Box::new(AlarmsPane::new()),
+            PaneKind::Report => Box::new(ReportPane::new()),
+            PaneKind::StatusBar => Box::new(StatusBarPane::new()),
+            PaneKind::Log => Box::<LogPane>::default(),
+            PaneKind::CameraSettings => Box::new(CameraSettingsPane::new()),
+        }
+    }
+
+    ///
+    /// Brings the pane to the front, reopening it next to the focused one if it was closed.
+    ///
+    fn show_pane(&mut self, kind: PaneKind) {
+        // the panes can't be compared, so they're looked up by their kind
+        let location = self
+            .dock_state
+            .iter_all_tabs()
+            .find(|(_, tab)| tab.kind() == Some(kind))
+            .map(|(location, _)| location);
+        match location {
+            Some((surface_index, node_index)) => {
+                let tab_index = self.dock_state[surface_index][node_index]
+                    .tabs()
+                    .and_then(|tabs| tabs.iter().position(|tab| tab.kind() == Some(kind)))
+                    .unwrap_or_default();
+                self.dock_state
+                    .set_active_tab((surface_index, node_index, TabIndex(tab_index)));
+                self.dock_state
+                    .set_focused_node_and_surface((surface_index, node_index));
+            }
+            None => {
+                let pane = self.create_pane(kind);
+                self.dock_state.push_to_focused_leaf(pane);
+            }
+        }
+    }
+
+    ///
+    /// Writes the settings and the layout of the panes, restored on the next start.
+    ///
+    fn save_session(&self) {
+        let layout = self.dock_state.filter_map_tabs(|tab| tab.kind());
+        let global_state = &self.global_state;
+        if let Err(err) = save_session(
+            &global_state.thermal_capturer_settings,
+            layout,
+            global_state.last_camera_id.clone(),
+        ) {
+            tracing::error!(
+                error = %err,
+                path = %session_path().display(),
+                "Failed to save session"
+            );
+        }
+    }
+
+    ///
+    /// Flashing banner listing the alarms which are currently going off.
+    ///
+    fn alarm_banner_ui(&self, ctx: &egui::Context) {
+        let global_state = &self.global_state;
+        if global_state.active_alarms.is_empty() {
+            return;
+        }
+        let unit = global_state.preferred_temperature_unit();
+        let precision = global_state.preferred_temperature_precision();
+
+        // toggles twice a second
+        let flash_on = ((ctx.input(|i| i.time) * 2.0) as u64).is_multiple_of(2);
+        ctx.request_repaint_after(Duration::from_millis(250));
+
+        egui::TopBottomPanel::top("alarm_banner")
+            .frame(egui::Frame::default().inner_margin(6.0).fill(if flash_on {
+                egui::Color32::from_rgb(200, 0, 0)
+            } else {
+                egui::Color32::from_rgb(90, 0, 0)
+            }))
+            .show(ctx, |ui| {
+                for alarm in global_state.active_alarms.iter() {
+                    ui.label(
+                        egui::RichText::new(format!(
+                            "⚠ {}: {} ({} {})",
+                            alarm.name,
+                            alarm.format_value(alarm.temperature, unit, precision),
+                            match alarm.condition {
+                                AlarmCondition::Above => "above",
+                                AlarmCondition::Below => "below",
+                            },
+                            alarm.format_value(alarm.threshold, unit, precision),
+                        ))
+                        .color(egui::Color32::WHITE)
+                        .strong(),
+                    );
+                }
+            });
+    }
 }
 
 impl Default for ThermalViewerApp {
     fn default() -> Self {
         let _backend = native_api_backend().unwrap();
         let global_state = AppGlobalState {
-            did_try_open_camera_at_startup: false,
             should_try_open_camera_on_next_hotplug: true,
 
             prefs: None,
+            mqtt_publisher: None,
+            http_server: None,
             thermal_capturer_inst: None,
-            thermal_capturer_settings: ThermalCapturerSettings {
-                rotation: ImageRotation::None,
-                auto_range: true,
-                manual_range: TempRange::new(
-                    Temp::from_unit(TemperatureUnit::Celsius, 0.0),
-                    Temp::from_unit(TemperatureUnit::Celsius, 50.0),
-                ),
-                gradient: thermal_gradient::THERMAL_GRADIENTS[0].clone(),
-                gizmo: Gizmo::new_root(vec![
-                    Gizmo::new(GizmoKind::MaxTemp, "Max".to_string(), Color32::RED),
-                    Gizmo::new(
-                        GizmoKind::MinTemp,
-                        "Min".to_string(),
-                        Color32::from_rgb(72, 219, 251),
-                    ),
-                ]),
-                dynamic_range_curve: DynamicRangeCurve::default(),
-                recorders: vec![],
-            },
+            thermal_capturer_settings: ThermalCapturerSettings::default(),
             last_thermal_capturer_result: None,
+            thermal_capturer_error: None,
+            thermal_camera_index: None,
+            thermal_camera_id: None,
+            thermal_camera_name: None,
+            last_camera_id: None,
+            camera_manager: CameraManager::default(),
+            visible_capturer: None,
+            gpu_colorizer: None,
+            gpu_texture_id: None,
+            paused: false,
             hotplug_detector: None,
             history_data_collector: HistoryDataCollector::new(),
+            settings_history: SettingsHistory::new(),
+            capturing_shortcut: false,
+            settings_changed: false,
 
             gallery: VecDeque::new(),
             did_init_gallery: false,
+
+            snapshot_format: ImageFormat::Png,
+            snapshot_footer: false,
+            snapshot_radiometric: true,
+            export_overlay: OverlayOptions::default(),
+            active_alarms: vec![],
+            custom_gradients: vec![],
+            profiles: vec![],
         };
 
         ThermalViewerApp {
             dock_state: DockState::new(vec![]),
 
             did_init: false,
-            global_state: Rc::new(RefCell::new(global_state)),
+            show_shortcuts_help: false,
+            camera_selection: CameraSelectionDialog::default(),
+            network_camera_dialog: NetworkCameraDialog::default(),
+            raw_frame_receiver_dialog: RawFrameReceiverDialog::default(),
+            global_state,
+            events: vec![],
         }
     }
 }
 
 impl eframe::App for ThermalViewerApp {
+    // Called by eframe on exit and every now and then, the session is kept in its own file
+    fn save(&mut self, _storage: &mut dyn eframe::Storage) {
+        self.save_session();
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame_egui: &mut eframe::Frame) {
         if !self.did_init {
             self.did_init = true;
-            self.set_default_dock_state();
-            let mut borrowed_global_state = self.global_state.borrow_mut();
-            borrowed_global_state.prefs = Some(
+            let session = load_session()
+                .inspect_err(|err| {
+                    tracing::error!(
+                        error = %err,
+                        path = %session_path().display(),
+                        "Failed to load session"
+                    )
+                })
+                .ok()
+                .flatten();
+            match session.as_ref().and_then(|session| session.layout.as_ref()) {
+                Some(layout) => self.dock_state = layout.map_tabs(|kind| self.create_pane(*kind)),
+                None => self.set_default_dock_state(),
+            }
+            let global_state = &mut self.global_state;
+            let mut last_camera_id = None;
+            if let Some(session) = session {
+                global_state.thermal_capturer_settings = session.settings;
+                last_camera_id = session.last_camera_id;
+            }
+            global_state.last_camera_id = last_camera_id.clone();
+            global_state.prefs = Some(
                 UserPreferences::load()
                     .inspect_err(|err| {
-                        error!(
-                            "Failed to load user preferences from {}: {}",
-                            UserPreferences::preferences_path()
-                                .to_string_lossy()
-                                .to_string(),
-                            err
+                        tracing::error!(
+                            error = %err,
+                            path = %UserPreferences::preferences_path().display(),
+                            "Failed to load user preferences"
                         )
                     })
                     .unwrap_or_default(),
             );
+            global_state.custom_gradients = load_custom_gradients()
+                .inspect_err(|err| {
+                    tracing::error!(
+                        error = %err,
+                        path = %custom_gradients_path().display(),
+                        "Failed to load custom gradients"
+                    )
+                })
+                .unwrap_or_default();
+            global_state.profiles = load_profiles()
+                .inspect_err(|err| {
+                    tracing::error!(
+                        error = %err,
+                        path = %profiles_path().display(),
+                        "Failed to load profiles"
+                    )
+                })
+                .unwrap_or_default();
+            global_state.restart_mqtt_publisher();
+            global_state.restart_http_server();
+            global_state.thermal_capturer_settings.alarm_rules = load_alarm_rules()
+                .inspect_err(|err| {
+                    tracing::error!(
+                        error = %err,
+                        path = %alarms_path().display(),
+                        "Failed to load alarms"
+                    )
+                })
+                .unwrap_or_default();
             let cloned_ctx = ctx.clone();
 
-            borrowed_global_state.hotplug_detector = run_hotplug_detector(move |_| {
+            global_state.hotplug_detector = run_hotplug_detector(move |_| {
                 cloned_ctx.request_repaint();
             })
             .inspect_err(|e| {
-                error!("Failed to start hotplug detector: {}", e);
+                tracing::error!(error = %e, "Failed to start hotplug detector");
             })
             .ok();
-            borrowed_global_state.should_try_open_camera_on_next_hotplug = borrowed_global_state
+            let auto_open_camera = global_state
                 .prefs
                 .as_ref()
                 .map(|p| p.auto_open_camera)
                 .unwrap_or_default();
+            global_state.should_try_open_camera_on_next_hotplug = auto_open_camera;
+
+            if auto_open_camera {
+                self.camera_selection.start(last_camera_id);
+            }
         }
 
         {
-            let mut borrowed_global_state = self.global_state.borrow_mut();
+            let global_state = &mut self.global_state;
 
             // drain thermal capturer results
             while {
                 let mut had_result = false;
-                if let Some(capturer) = borrowed_global_state.thermal_capturer_inst.as_mut() {
+                if let Some(capturer) = global_state.thermal_capturer_inst.as_mut() {
                     // Handle thermal capturer commands
-                    if let Ok(r) = capturer.result_receiver.try_recv() {
-                        match r {
-                            Ok(result) => {
-                                borrowed_global_state
+                    if let Some(event) = capturer.try_recv_event() {
+                        match event {
+                            ThermalCapturerEvent::Frame(mut result) => {
+                                self.camera_selection.startup_camera_finished(false);
+                                global_state
                                     .history_data_collector
                                     .add_from_gizmo_results(
                                         result.capture_time,
-                                        &result.gizmo_results,
+                                        &result.extension.gizmo_results,
                                     )
                                     .unwrap();
                                 // Add image to gallery if needed
                                 // if let Some(saved_file) = result.created_capture_file.clone() {
-                                //     borrowed_global_state.gallery.push_front(GalleryElement {
+                                //     global_state.gallery.push_front(GalleryElement {
                                 //         path: saved_file,
                                 //         created_at: SystemTime::now(),
                                 //     });
 
-                                //     if borrowed_global_state.gallery.len() > 20 {
-                                //         borrowed_global_state.gallery.pop_back();
+                                //     if global_state.gallery.len() > 20 {
+                                //         global_state.gallery.pop_back();
                                 //     }
                                 // }
 
                                 let mut gallery_tmp = vec![];
-                                borrowed_global_state.thermal_capturer_settings.recorders =
-                                    borrowed_global_state
-                                        .thermal_capturer_settings
-                                        .recorders
-                                        .drain(..)
-                                        .filter(|recorder| {
-                                            let recorder = recorder.lock().unwrap();
-                                            if recorder.state() == RecorderState::Done {
-                                                for file in recorder.files_created() {
-                                                    gallery_tmp.push(GalleryElement {
-                                                        path: file,
-                                                        created_at: SystemTime::now(),
-                                                    });
-                                                }
-                                                return false;
+                                global_state.thermal_capturer_settings.recorders = global_state
+                                    .thermal_capturer_settings
+                                    .recorders
+                                    .drain(..)
+                                    .filter(|recorder| {
+                                        let recorder = recorder.lock().unwrap();
+                                        if recorder.state() == RecorderState::Done {
+                                            for file in recorder.files_created() {
+                                                gallery_tmp.push(GalleryElement {
+                                                    path: file,
+                                                    created_at: SystemTime::now(),
+                                                });
                                             }
-                                            true
-                                        })
-                                        .collect();
-                                borrowed_global_state.gallery.extend(gallery_tmp);
-                                borrowed_global_state.last_thermal_capturer_result = Some(result);
+                                            return false;
+                                        }
+                                        true
+                                    })
+                                    .collect();
+                                global_state.gallery.extend(gallery_tmp);
+
+                                let newly_sounding =
+                                    result.extension.active_alarms.iter().any(|alarm| {
+                                        alarm.sound
+                                            && !global_state
+                                                .active_alarms
+                                                .iter()
+                                                .any(|a| a.rule_uuid == alarm.rule_uuid)
+                                    });
+                                if newly_sounding {
+                                    play_alarm_sound();
+                                }
+                                global_state.active_alarms = result.extension.active_alarms.clone();
+
+                                let unit = global_state.preferred_temperature_unit();
+                                if let Some(publisher) = global_state.mqtt_publisher.as_mut() {
+                                    publisher.publish_result(
+                                        &global_state.thermal_capturer_settings.gizmo,
+                                        &result.extension.gizmo_results,
+                                        &result.extension.active_alarms,
+                                        unit,
+                                        result.is_delta,
+                                    );
+                                }
+                                if let Some(server) = global_state.http_server.as_mut() {
+                                    server.update(
+                                        &result,
+                                        &global_state.thermal_capturer_settings.gizmo,
+                                        unit,
+                                    );
+                                }
 
+                                // the result which is no longer shown goes back to the capturer
+                                let unused_result = if !global_state.paused {
+                                    global_state.last_thermal_capturer_result.replace(result)
+                                } else {
+                                    if let (Some(frozen), Some(frozen_gizmo_results)) = (
+                                        global_state.last_thermal_capturer_result.as_mut(),
+                                        result.extension.frozen_gizmo_results.take(),
+                                    ) {
+                                        frozen.extension.gizmo_results = frozen_gizmo_results;
+                                    }
+                                    Some(result)
+                                };
+                                if let (Some(unused_result), Some(capturer)) =
+                                    (unused_result, global_state.thermal_capturer_inst.as_mut())
+                                {
+                                    capturer.recycle_result(unused_result);
+                                }
+
+                                had_result = true;
+                            }
+                            ThermalCapturerEvent::Error(e) => {
+                                global_state.thermal_capturer_error = Some(format!("{:#}", e));
+                                had_result = true;
+                            }
+                            ThermalCapturerEvent::Disconnected(_) => {
+                                // the alarms can't be evaluated until the camera is back
+                                global_state.active_alarms.clear();
                                 had_result = true;
                             }
-                            Err(e) => {
-                                error!("Thermal capturer error: {}", e);
-                                borrowed_global_state.thermal_capturer_inst = None;
+                            ThermalCapturerEvent::Stopped(reason) => {
+                                self.camera_selection
+                                    .startup_camera_finished(reason.is_some());
+                                if let Some(e) = reason {
+                                    tracing::error!(
+                                        error = %format_args!("{:#}", e),
+                                        "Thermal capturer stopped"
+                                    );
+                                    global_state.thermal_capturer_error = Some(format!("{:#}", e));
+                                }
+                                global_state.thermal_capturer_inst = None;
+                                global_state.active_alarms.clear();
                             }
                         }
                     }
@@ -299,6 +1397,60 @@ impl eframe::App for ThermalViewerApp {
 
                 had_result
             } {}
+
+            global_state.camera_manager.update();
+        }
+
+        let opened_cameras = self.global_state.camera_manager.take_opened();
+        for camera_uuid in opened_cameras {
+            let pane = CameraPane::new(&self.global_state, camera_uuid);
+            self.dock_state.add_window(vec![Box::new(pane)]);
+        }
+
+        let (key_bindings, capturing_shortcut) = {
+            let global_state = &mut self.global_state;
+            (
+                global_state.key_bindings(),
+                std::mem::take(&mut global_state.capturing_shortcut),
+            )
+        };
+        let pressed_actions = if capturing_shortcut {
+            vec![]
+        } else {
+            pressed_shortcut_actions(ctx, &key_bindings)
+        };
+        self.events
+            .extend(pressed_actions.into_iter().map(AppEvent::Action));
+
+        egui::Window::new("Keyboard Shortcuts")
+            .open(&mut self.show_shortcuts_help)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| shortcuts_help_ui(ui, &key_bindings));
+
+        if let Some((index, adapter)) = self
+            .camera_selection
+            .poll_startup(ctx)
+            .or_else(|| self.camera_selection.show(ctx))
+        {
+            self.global_state.open_camera(index, adapter, ctx);
+        }
+
+        let network_camera = {
+            let global_state = &self.global_state;
+            let gradients: Vec<&ThermalGradient> = THERMAL_GRADIENTS
+                .iter()
+                .chain(global_state.custom_gradients.iter())
+                .collect();
+            self.network_camera_dialog
+                .show(ctx, global_state.temp_format(), &gradients)
+        };
+        if let Some((config, target)) = network_camera {
+            self.global_state.open_network_camera(config, target, ctx);
+        }
+        if let Some((config, target)) = self.raw_frame_receiver_dialog.show(ctx) {
+            self.global_state
+                .open_raw_frame_receiver(config, target, ctx);
         }
 
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
@@ -306,26 +1458,138 @@ impl eframe::App for ThermalViewerApp {
                 ui.menu_button("File", |ui| {
                     if ui.button("Preferences").clicked() {
                         // TODO: forbid opening multiple user preferences windows
-                        self.dock_state
-                            .add_window(vec![Box::new(UserPreferencesPane::new(
-                                self.global_state.clone(),
-                            ))]);
+                        let pane = UserPreferencesPane::new(&self.global_state);
+                        self.dock_state.add_window(vec![Box::new(pane)]);
+                    }
+                    ui.separator();
+                    if ui.button("Select Camera…").clicked() {
+                        ui.close_menu();
+                        self.camera_selection.refresh();
+                        self.camera_selection.open = true;
+                    }
+                    if ui.button("Open Network Camera…").clicked() {
+                        ui.close_menu();
+                        let config = self
+                            .global_state
+                            .prefs
+                            .as_ref()
+                            .map(|prefs| prefs.network_camera.clone())
+                            .unwrap_or_default();
+                        self.network_camera_dialog.show_with(config);
+                    }
+                    if ui.button("Receive Raw Frames…").clicked() {
+                        ui.close_menu();
+                        let config = self
+                            .global_state
+                            .prefs
+                            .as_ref()
+                            .map(|prefs| prefs.raw_frame_receiver.clone())
+                            .unwrap_or_default();
+                        self.raw_frame_receiver_dialog.show_with(config);
+                    }
+                    if ui.button("Open Recording…").clicked() {
+                        ui.close_menu();
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("Radiometric recording", &[RADIOMETRIC_EXTENSION])
+                            .pick_file()
+                        {
+                            if let Err(e) = self.global_state.open_recording(&path, ctx) {
+                                tracing::error!(error = %e, "Failed to open recording");
+                            }
+                        }
+                    }
+                    if ui.button("Open Snapshot…").clicked() {
+                        ui.close_menu();
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("Radiometric PNG", &["png"])
+                            .pick_file()
+                        {
+                            if let Err(e) = self.global_state.open_snapshot(&path, ctx) {
+                                tracing::error!(error = %e, "Failed to open snapshot");
+                            }
+                        }
+                    }
+                    ui.separator();
+                    if ui.button("Export Settings…").clicked() {
+                        ui.close_menu();
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("JSON", &["json"])
+                            .set_file_name("thermal-cat-settings.json")
+                            .save_file()
+                        {
+                            if let Err(e) = self.global_state.export_settings(&path) {
+                                tracing::error!(error = %e, "Failed to export settings");
+                            }
+                        }
+                    }
+                    if ui.button("Import Settings…").clicked() {
+                        ui.close_menu();
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("JSON", &["json"])
+                            .pick_file()
+                        {
+                            if let Err(e) = self.global_state.import_settings(&path) {
+                                tracing::error!(error = %e, "Failed to import settings");
+                            }
+                        }
                     }
                     ui.separator();
                     if ui.button("Quit").clicked() {
-                        self.global_state.borrow_mut().thermal_capturer_inst = None;
+                        self.save_session();
+                        self.global_state.thermal_capturer_inst = None;
                         std::process::exit(0);
                     }
                 });
+                ui.menu_button("Edit", |ui| {
+                    let global_state = &self.global_state;
+                    if ui
+                        .add_enabled(
+                            global_state.settings_history.can_undo(),
+                            egui::Button::new("Undo").shortcut_text("Ctrl+Z"),
+                        )
+                        .clicked()
+                    {
+                        self.events.push(AppEvent::Action(ShortcutAction::Undo));
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(
+                            global_state.settings_history.can_redo(),
+                            egui::Button::new("Redo").shortcut_text("Ctrl+Y"),
+                        )
+                        .clicked()
+                    {
+                        self.events.push(AppEvent::Action(ShortcutAction::Redo));
+                        ui.close_menu();
+                    }
+                });
                 ui.menu_button("Window", |ui| {
+                    for kind in PaneKind::ALL {
+                        if ui.button(kind.name()).clicked() {
+                            self.show_pane(kind);
+                            ui.close_menu();
+                        }
+                    }
+                    ui.separator();
                     if ui.button("Reset Layout").clicked() {
                         self.set_default_dock_state();
                     }
                 });
+                ui.menu_button("Help", |ui| {
+                    if ui.button("Keyboard Shortcuts").clicked() {
+                        self.show_shortcuts_help = true;
+                        ui.close_menu();
+                    }
+                });
             });
         });
 
+        self.alarm_banner_ui(ctx);
+
+        self.global_state.colorize_on_gpu();
         egui::CentralPanel::default().show(ctx, |ui| {
+            let mut app = PaneContext::new(&self.global_state, &mut self.events);
+
             // Either render a tab maximized, or render the egui_dock layout
             let fulscreen_tab = self
                 .dock_state
@@ -333,12 +1597,22 @@ impl eframe::App for ThermalViewerApp {
                 .find(|tab| tab.1.is_maximized());
 
             if let Some(tab) = fulscreen_tab {
-                tab.1.ui(ui);
+                tab.1.ui(ui, &mut app);
             } else {
                 DockArea::new(&mut self.dock_state)
                     .style(egui_dock::Style::from_egui(ui.style().as_ref()))
-                    .show_inside(ui, &mut PaneDispatcher {});
+                    .show_inside(ui, &mut PaneDispatcher { app });
             }
         });
+
+        self.handle_events(ctx);
+
+        // an edit in progress (e.g. a gizmo being dragged or a value typed) becomes one step
+        if !ctx.input(|i| i.pointer.any_down()) && !ctx.wants_keyboard_input() {
+            let global_state = &mut self.global_state;
+            global_state
+                .settings_history
+                .end_edit(&global_state.thermal_capturer_settings);
+        }
     }
 }
@@ -0,0 +1,189 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+    time::Instant,
+};
+
+use anyhow::{anyhow, Result};
+use chrono::Local;
+use uuid::Uuid;
+
+use crate::{
+    gizmos::{Gizmo, GizmoKind, GizmoResult},
+    temperature::{Temp, TempDelta, TemperatureUnit},
+};
+
+pub const MEASUREMENT_LOG_EXTENSION: &str = "csv";
+
+pub struct MeasurementLogParams {
+    // Path of the first file. Files are numbered after it, e.g. "log_000.csv", "log_001.csv"
+    pub path: PathBuf,
+    pub unit: TemperatureUnit,
+    // A new file is started once the current one grows past this size
+    pub max_file_size: u64,
+}
+
+///
+/// Owned by the capturer thread, writes the results of all gizmos of every frame as CSV.
+///
+/// Each row holds a timestamp and, for each gizmo, its value and the min/max/mean/standard
+/// deviation of its pixels (empty for min/max gizmos). A new file is started when the file
+/// size limit is reached, or when gizmos are added or removed, since the columns change.
+///
+pub struct MeasurementLogger {
+    params: Option<MeasurementLogParams>,
+    writer: Option<BufWriter<File>>,
+    file_index: usize,
+    bytes_written: u64,
+    // Gizmos of the columns in the current file
+    columns: Vec<Uuid>,
+    started: Instant,
+}
+
+impl MeasurementLogger {
+    pub fn new() -> Self {
+        Self {
+            params: None,
+            writer: None,
+            file_index: 0,
+            bytes_written: 0,
+            columns: vec![],
+            started: Instant::now(),
+        }
+    }
+
+    pub fn start(&mut self, params: MeasurementLogParams) -> Result<()> {
+        self.stop()?;
+        if let Some(dir) = params.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        self.params = Some(params);
+        self.file_index = 0;
+        self.started = Instant::now();
+        Ok(())
+    }
+
+    pub fn stop(&mut self) -> Result<()> {
+        self.params = None;
+        self.columns.clear();
+        if let Some(mut writer) = self.writer.take() {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn is_logging(&self) -> bool {
+        self.params.is_some()
+    }
+
+    ///
+    /// Writes a row if logging. On failure logging is stopped,
+    /// so that a full disk doesn't also stop the live image.
+    ///
+    pub fn log(&mut self, root_gizmo: &Gizmo, results: &HashMap<Uuid, GizmoResult>) {
+        if self.params.is_none() {
+            return;
+        }
+        if let Err(err) = self.write_row(root_gizmo, results) {
+            tracing::error!(error = %err, "Failed to write measurement log, stopping");
+            self.writer = None;
+            let _ = self.stop();
+        }
+    }
+
+    fn write_row(
+        &mut self,
+        root_gizmo: &Gizmo,
+        results: &HashMap<Uuid, GizmoResult>,
+    ) -> Result<()> {
+        let gizmos = match &root_gizmo.kind {
+            GizmoKind::Root { children } => children,
+            _ => return Err(anyhow!("Root gizmo has no children")),
+        };
+        let columns: Vec<Uuid> = gizmos.iter().map(|g| g.uuid).collect();
+        let max_file_size = self.params.as_ref().map_or(u64::MAX, |p| p.max_file_size);
+        if self.writer.is_none() || columns != self.columns || self.bytes_written >= max_file_size {
+            self.open_next_file(gizmos)?;
+            self.columns = columns;
+        }
+        let unit = self
+            .params
+            .as_ref()
+            .map_or(TemperatureUnit::Kelvin, |p| p.unit);
+
+        let mut row = format!(
+            "{},{:.3}",
+            Local::now().format("%Y-%m-%dT%H:%M:%S%.3f"),
+            self.started.elapsed().as_secs_f64()
+        );
+        let format_value = |temp: Temp| format!(",{:.3}", temp.to_unit(unit));
+        for gizmo in gizmos.iter() {
+            let result = results.get(&gizmo.uuid);
+            let stats = result.and_then(|r| r.stats);
+            match result {
+                Some(result) => row.push_str(&format_value(result.temperature)),
+                None => row.push(','),
+            }
+            match stats {
+                Some(stats) => {
+                    row.push_str(&format_value(stats.min));
+                    row.push_str(&format_value(stats.max));
+                    row.push_str(&format_value(stats.mean));
+                    row.push_str(&format!(
+                        ",{:.3}",
+                        TempDelta::new(stats.stddev_kelvin).to_unit(unit)
+                    ));
+                }
+                None => row.push_str(",,,,"),
+            }
+        }
+        row.push('\n');
+
+        let writer = self
+            .writer
+            .as_mut()
+            .ok_or_else(|| anyhow!("Measurement log file is not open"))?;
+        writer.write_all(row.as_bytes())?;
+        self.bytes_written += row.len() as u64;
+        Ok(())
+    }
+
+    fn open_next_file(&mut self, gizmos: &[Gizmo]) -> Result<()> {
+        if let Some(mut writer) = self.writer.take() {
+            writer.flush()?;
+        }
+        let params = self
+            .params
+            .as_ref()
+            .ok_or_else(|| anyhow!("Measurement log is not started"))?;
+        let stem = params
+            .path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or("measurements".to_string());
+        let path = params.path.with_file_name(format!(
+            "{}_{:03}.{}",
+            stem, self.file_index, MEASUREMENT_LOG_EXTENSION
+        ));
+        self.file_index += 1;
+
+        let mut header = "timestamp,seconds".to_string();
+        let suffix = params.unit.suffix();
+        for gizmo in gizmos.iter() {
+            let name = gizmo.name.replace('"', "\"\"");
+            for column in ["value", "min", "max", "mean", "stddev"] {
+                header.push_str(&format!(",\"{} {} ({})\"", name, column, suffix));
+            }
+        }
+        header.push('\n');
+
+        let mut writer = BufWriter::new(File::create(&path)?);
+        writer.write_all(header.as_bytes())?;
+        self.bytes_written = header.len() as u64;
+        self.writer = Some(writer);
+        tracing::info!(path = %path.display(), "Logging measurements");
+        Ok(())
+    }
+}
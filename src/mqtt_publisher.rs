@@ -0,0 +1,325 @@
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    alarms::ActiveAlarm,
+    gizmos::{Gizmo, GizmoKind, GizmoResult},
+    temperature::{reading_to_unit, TemperatureUnit},
+    util::pathify_string,
+};
+
+// Connection attempts are not repeated more often than this while the broker is unreachable
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(5);
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MqttSettings {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub client_id: String,
+    // Left empty if the broker doesn't require authentication
+    pub username: String,
+    pub password: String,
+    // Measurements are published to "<prefix>/gizmos/<name>", alarms to "<prefix>/alarms/<name>"
+    pub topic_prefix: String,
+    pub publish_interval_secs: f32,
+    // Ask the broker to keep the last measurement for new subscribers
+    pub retain: bool,
+}
+
+impl Default for MqttSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "localhost".to_string(),
+            port: 1883,
+            client_id: "thermal-cat".to_string(),
+            username: String::new(),
+            password: String::new(),
+            topic_prefix: "thermal-cat".to_string(),
+            publish_interval_secs: 5.0,
+            retain: false,
+        }
+    }
+}
+
+enum MqttCmd {
+    Publish {
+        topic: String,
+        payload: String,
+        retain: bool,
+    },
+    Stop,
+}
+
+///
+/// Publishes the gizmo temperatures and alarm events to an MQTT broker, e.g. for
+/// a Home Assistant dashboard. Only QoS 0 is used, so nothing is queued while the
+/// broker is unreachable.
+///
+/// The network is handled by a background thread, so a slow broker doesn't stall the UI.
+///
+pub struct MqttPublisher {
+    settings: MqttSettings,
+    cmd_sender: mpsc::Sender<MqttCmd>,
+    last_measurements_time: Option<Instant>,
+    // Names of the alarms which were reported as active, to report them cleared later
+    active_alarms: HashMap<Uuid, String>,
+}
+
+impl MqttPublisher {
+    pub fn start(settings: MqttSettings) -> Self {
+        let (cmd_sender, cmd_receiver) = mpsc::channel();
+        let thread_settings = settings.clone();
+        thread::spawn(move || run_connection(thread_settings, cmd_receiver));
+        Self {
+            settings,
+            cmd_sender,
+            last_measurements_time: None,
+            active_alarms: HashMap::new(),
+        }
+    }
+
+    pub fn publish_result(
+        &mut self,
+        root_gizmo: &Gizmo,
+        gizmo_results: &HashMap<Uuid, GizmoResult>,
+        alarms: &[ActiveAlarm],
+        unit: TemperatureUnit,
+        is_delta: bool,
+    ) {
+        let interval = Duration::from_secs_f32(self.settings.publish_interval_secs.max(0.1));
+        if self
+            .last_measurements_time
+            .is_none_or(|time| time.elapsed() >= interval)
+        {
+            self.last_measurements_time = Some(Instant::now());
+            self.publish_measurements(root_gizmo, gizmo_results, unit, is_delta);
+        }
+        self.publish_alarm_changes(alarms, unit);
+    }
+
+    fn publish_measurements(
+        &self,
+        root_gizmo: &Gizmo,
+        gizmo_results: &HashMap<Uuid, GizmoResult>,
+        unit: TemperatureUnit,
+        is_delta: bool,
+    ) {
+        let GizmoKind::Root { children } = &root_gizmo.kind else {
+            return;
+        };
+        // in delta mode the readings are differences to the reference frame
+        let to_unit = |value| reading_to_unit(value, unit, is_delta);
+        for gizmo in children.iter() {
+            let Some(result) = gizmo_results.get(&gizmo.uuid) else {
+                continue;
+            };
+            let mut payload = serde_json::json!({
+                "name": gizmo.name,
+                "temperature": to_unit(result.temperature),
+                "unit": unit.suffix(),
+                "is_delta": is_delta,
+            });
+            if let Some(stats) = result.stats {
+                payload["min"] = to_unit(stats.min).into();
+                payload["max"] = to_unit(stats.max).into();
+                payload["mean"] = to_unit(stats.mean).into();
+            }
+            self.send(
+                format!("gizmos/{}", pathify_string(gizmo.name.clone())),
+                payload.to_string(),
+                self.settings.retain,
+            );
+        }
+    }
+
+    fn publish_alarm_changes(&mut self, alarms: &[ActiveAlarm], unit: TemperatureUnit) {
+        for alarm in alarms {
+            if self.active_alarms.contains_key(&alarm.rule_uuid) {
+                continue;
+            }
+            self.active_alarms
+                .insert(alarm.rule_uuid, alarm.name.clone());
+            let payload = serde_json::json!({
+                "name": alarm.name,
+                "active": true,
+                "temperature": alarm.value_to_unit(alarm.temperature, unit),
+                "threshold": alarm.value_to_unit(alarm.threshold, unit),
+                "unit": unit.suffix(),
+            });
+            self.send(
+                format!("alarms/{}", pathify_string(alarm.name.clone())),
+                payload.to_string(),
+                true,
+            );
+        }
+
+        let cleared: Vec<(Uuid, String)> = self
+            .active_alarms
+            .iter()
+            .filter(|(uuid, _)| !alarms.iter().any(|alarm| alarm.rule_uuid == **uuid))
+            .map(|(uuid, name)| (*uuid, name.clone()))
+            .collect();
+        for (uuid, name) in cleared {
+            self.active_alarms.remove(&uuid);
+            let payload = serde_json::json!({ "name": name, "active": false });
+            self.send(
+                format!("alarms/{}", pathify_string(name)),
+                payload.to_string(),
+                true,
+            );
+        }
+    }
+
+    fn send(&self, subtopic: String, payload: String, retain: bool) {
+        let _ = self.cmd_sender.send(MqttCmd::Publish {
+            topic: format!(
+                "{}/{}",
+                self.settings.topic_prefix.trim_end_matches('/'),
+                subtopic
+            ),
+            payload,
+            retain,
+        });
+    }
+}
+
+impl Drop for MqttPublisher {
+    fn drop(&mut self) {
+        let _ = self.cmd_sender.send(MqttCmd::Stop);
+    }
+}
+
+fn run_connection(settings: MqttSettings, cmd_receiver: mpsc::Receiver<MqttCmd>) {
+    let mut stream: Option<TcpStream> = None;
+    let mut last_connect_attempt: Option<Instant> = None;
+
+    while let Ok(cmd) = cmd_receiver.recv() {
+        match cmd {
+            MqttCmd::Publish {
+                topic,
+                payload,
+                retain,
+            } => {
+                if stream.is_none()
+                    && last_connect_attempt.is_none_or(|t| t.elapsed() >= RECONNECT_INTERVAL)
+                {
+                    last_connect_attempt = Some(Instant::now());
+                    stream = connect(&settings)
+                        .inspect_err(|err| {
+                            tracing::warn!(
+                                error = %err,
+                                host = %settings.host,
+                                port = %settings.port,
+                                "Failed to connect to MQTT broker"
+                            )
+                        })
+                        .ok();
+                }
+                let Some(connection) = stream.as_mut() else {
+                    continue;
+                };
+                let packet = publish_packet(&topic, payload.as_bytes(), retain);
+                if let Err(err) = connection.write_all(&packet) {
+                    tracing::warn!(error = %err, "Lost connection to MQTT broker");
+                    stream = None;
+                }
+            }
+            MqttCmd::Stop => break,
+        }
+    }
+
+    if let Some(mut connection) = stream {
+        // DISCONNECT
+        let _ = connection.write_all(&[0xe0, 0x00]);
+    }
+}
+
+///
+/// Opens a MQTT 3.1.1 session. Keep alive is disabled, so no pings have to be sent.
+///
+fn connect(settings: &MqttSettings) -> Result<TcpStream> {
+    let addr = (settings.host.as_str(), settings.port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| anyhow!("Unknown host {}", settings.host))?;
+    let mut stream = TcpStream::connect_timeout(&addr, NETWORK_TIMEOUT)?;
+    stream.set_write_timeout(Some(NETWORK_TIMEOUT))?;
+    stream.set_read_timeout(Some(NETWORK_TIMEOUT))?;
+
+    // clean session
+    let mut flags = 0x02;
+    let mut payload = vec![];
+    write_string(&mut payload, &settings.client_id);
+    if !settings.username.is_empty() {
+        flags |= 0x80;
+        write_string(&mut payload, &settings.username);
+        if !settings.password.is_empty() {
+            flags |= 0x40;
+            write_string(&mut payload, &settings.password);
+        }
+    }
+
+    let mut body = vec![];
+    write_string(&mut body, "MQTT");
+    body.push(4); // protocol level 3.1.1
+    body.push(flags);
+    body.extend_from_slice(&0u16.to_be_bytes()); // keep alive
+    body.extend_from_slice(&payload);
+    stream.write_all(&packet(0x10, &body))?;
+
+    let mut connack = [0u8; 4];
+    stream.read_exact(&mut connack)?;
+    if connack[0] != 0x20 {
+        return Err(anyhow!("Unexpected reply from the broker"));
+    }
+    match connack[3] {
+        0 => Ok(stream),
+        4 | 5 => Err(anyhow!("The broker refused the username or password")),
+        code => Err(anyhow!("The broker refused the connection (code {})", code)),
+    }
+}
+
+fn publish_packet(topic: &str, payload: &[u8], retain: bool) -> Vec<u8> {
+    let mut body = vec![];
+    write_string(&mut body, topic);
+    body.extend_from_slice(payload);
+    packet(0x30 | retain as u8, &body)
+}
+
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![header];
+    // remaining length, 7 bits per byte with a continuation bit
+    let mut remaining = body.len();
+    loop {
+        let mut byte = (remaining % 128) as u8;
+        remaining /= 128;
+        if remaining > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if remaining == 0 {
+            break;
+        }
+    }
+    packet.extend_from_slice(body);
+    packet
+}
+
+fn write_string(buf: &mut Vec<u8>, s: &str) {
+    buf.extend_from_slice(&(s.len() as u16).to_be_bytes());
+    buf.extend_from_slice(s.as_bytes());
+}
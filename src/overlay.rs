@@ -0,0 +1,335 @@
+use ab_glyph::{FontRef, PxScale};
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use eframe::epaint::Color32;
+use image::{Rgb, RgbImage};
+use imageproc::{
+    drawing::{
+        draw_filled_rect_mut, draw_hollow_rect_mut, draw_line_segment_mut, draw_text_mut, text_size,
+    },
+    rect::Rect,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    annotations::AnnotationKind,
+    gizmos::GizmoKind,
+    temperature::{format_reading, TemperatureUnit},
+    thermal_capturer::{ThermalCapturerResult, ThermalCapturerSettings},
+    thermal_data::ThermalDataPos,
+    thermal_gradient::COLOR_SCALE_TICKS,
+};
+
+pub fn load_font() -> Result<FontRef<'static>> {
+    Ok(FontRef::try_from_slice(include_bytes!(
+        "fonts/DejaVuSansMono.ttf"
+    ))?)
+}
+
+///
+/// What is burned into exported images and videos.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OverlayOptions {
+    // Colors of the mapping range with their temperatures, at the right edge
+    pub color_scale: bool,
+    // Hottest and coldest pixel of the frame
+    pub min_max_markers: bool,
+    pub timestamp: bool,
+    // Names and readings of the visible gizmos, at their measured pixels
+    pub gizmo_labels: bool,
+    // Labels and drawings placed on the thermal view
+    pub annotations: bool,
+}
+
+impl Default for OverlayOptions {
+    fn default() -> Self {
+        Self {
+            color_scale: false,
+            min_max_markers: false,
+            timestamp: false,
+            gizmo_labels: false,
+            annotations: true,
+        }
+    }
+}
+
+impl OverlayOptions {
+    ///
+    /// Whether anything is drawn regardless of the annotations, which are often empty.
+    ///
+    pub fn any(&self) -> bool {
+        self.color_scale || self.min_max_markers || self.timestamp || self.gizmo_labels
+    }
+}
+
+///
+/// Draws the overlays onto the colored image of a capture result, without egui,
+/// so that exports look the same with and without the UI (e.g. headless).
+///
+#[derive(Clone)]
+pub struct Overlay {
+    options: OverlayOptions,
+    // The settings when the export was started, for the colors of the scale and the gizmos
+    settings: ThermalCapturerSettings,
+    temperature_unit: TemperatureUnit,
+    temperature_precision: usize,
+}
+
+impl Overlay {
+    pub fn new(
+        options: OverlayOptions,
+        settings: &ThermalCapturerSettings,
+        temperature_unit: TemperatureUnit,
+        temperature_precision: usize,
+    ) -> Self {
+        Self {
+            options,
+            // nothing that keeps the recorders alive
+            settings: ThermalCapturerSettings {
+                recorders: vec![],
+                visible_frames: None,
+                ..settings.clone()
+            },
+            temperature_unit,
+            temperature_precision,
+        }
+    }
+
+    ///
+    /// Draws the enabled overlays onto `img`, the image of `result`.
+    ///
+    pub fn burn_in(
+        &self,
+        img: &mut RgbImage,
+        result: &ThermalCapturerResult,
+        timestamp: DateTime<Local>,
+    ) -> Result<()> {
+        let draw_annotations = self.options.annotations && !self.settings.annotations.is_empty();
+        if !self.options.any() && !draw_annotations {
+            return Ok(());
+        }
+        let font = load_font()?;
+        let scale = PxScale::from((img.height() as f32 / 32.0).max(10.0));
+        // the image is upscaled from the thermal data
+        let zoom = img.width() as f32 / result.thermal_data.width.max(1) as f32;
+        let to_image =
+            |pos: ThermalDataPos| ((pos.x as f32 + 0.5) * zoom, (pos.y as f32 + 0.5) * zoom);
+        let format_value = |temp| {
+            format_reading(
+                temp,
+                self.temperature_unit,
+                self.temperature_precision,
+                result.is_delta,
+            )
+        };
+
+        if self.options.gizmo_labels {
+            if let GizmoKind::Root { children } = &self.settings.gizmo.kind {
+                for gizmo in children.iter().filter(|gizmo| !gizmo.hidden) {
+                    if let Some(gizmo_result) = result.extension.gizmo_results.get(&gizmo.uuid) {
+                        let (x, y) = to_image(gizmo_result.pos);
+                        let label = if gizmo.show_temperature_label {
+                            format!("{} {}", gizmo.name, format_value(gizmo_result.temperature))
+                        } else {
+                            gizmo.name.clone()
+                        };
+                        draw_marker(img, x, y, scale.y / 2.0, to_rgb(gizmo.color));
+                        draw_label(img, &font, scale, x + scale.y / 2.0, y, &label);
+                    }
+                }
+            }
+        }
+
+        if draw_annotations {
+            let to_image = |p: [f32; 2]| (p[0] * zoom, p[1] * zoom);
+            for annotation in self.settings.annotations.iter() {
+                let color = to_rgb(annotation.color);
+                match &annotation.kind {
+                    AnnotationKind::Text { pos, text } => {
+                        let (x, y) = to_image(*pos);
+                        draw_label(img, &font, scale, x, y, text);
+                    }
+                    AnnotationKind::Arrow { start, end } => {
+                        draw_arrow(img, to_image(*start), to_image(*end), scale.y / 2.0, color);
+                    }
+                    AnnotationKind::Freehand { points } => {
+                        for segment in points.windows(2) {
+                            draw_thick_line(img, to_image(segment[0]), to_image(segment[1]), color);
+                        }
+                    }
+                }
+            }
+        }
+
+        if self.options.min_max_markers {
+            for (pos, name, color) in [
+                (result.max_pos, "Max", Color32::RED),
+                (result.min_pos, "Min", Color32::from_rgb(72, 219, 251)),
+            ] {
+                let (x, y) = to_image(pos);
+                let temp = result.thermal_data.temperature_at(pos.x, pos.y);
+                draw_marker(img, x, y, scale.y / 2.0, to_rgb(color));
+                draw_label(
+                    img,
+                    &font,
+                    scale,
+                    x + scale.y / 2.0,
+                    y,
+                    &format!("{} {}", name, format_value(temp)),
+                );
+            }
+        }
+
+        if self.options.color_scale {
+            self.draw_color_scale(img, result, &font, scale);
+        }
+
+        if self.options.timestamp {
+            let text = timestamp.format("%Y-%m-%d %H:%M:%S").to_string();
+            draw_label(img, &font, scale, 2.0, scale.y / 2.0 + 2.0, &text);
+        }
+        Ok(())
+    }
+
+    ///
+    /// Draws the colors of the mapping range as a bar at the right edge, hottest at the top,
+    /// with the temperatures along it.
+    ///
+    fn draw_color_scale(
+        &self,
+        img: &mut RgbImage,
+        result: &ThermalCapturerResult,
+        font: &FontRef<'_>,
+        scale: PxScale,
+    ) {
+        let range = result.image_range;
+        let width = (img.width() / 40).max(6);
+        let margin = (img.width() / 80).max(4);
+        let top = img.height() / 10;
+        let height = img.height().saturating_sub(2 * top).max(2);
+        let left = img.width().saturating_sub(width + margin);
+
+        for y in 0..height {
+            let factor = 1.0 - (y as f32 + 0.5) / height as f32;
+            let color = self.settings.value_to_color(
+                range.factor_to_temp(factor),
+                range,
+                result.equalization.as_deref(),
+                result.is_delta,
+            );
+            draw_filled_rect_mut(
+                img,
+                Rect::at(left as i32, (top + y) as i32).of_size(width, 1),
+                to_rgb(color),
+            );
+        }
+        draw_hollow_rect_mut(
+            img,
+            Rect::at(left as i32, top as i32).of_size(width, height),
+            Rgb([255, 255, 255]),
+        );
+
+        for i in 0..COLOR_SCALE_TICKS {
+            let factor = i as f32 / (COLOR_SCALE_TICKS - 1) as f32;
+            let y = (top + height) as f32 - height as f32 * factor;
+            let text = format_reading(
+                range.factor_to_temp(factor),
+                self.temperature_unit,
+                self.temperature_precision,
+                result.is_delta,
+            );
+            let (text_width, _) = text_size(scale, font, &text);
+            draw_label(
+                img,
+                font,
+                scale,
+                left as f32 - text_width as f32 - 6.0,
+                y,
+                &text,
+            );
+        }
+    }
+}
+
+fn to_rgb(color: Color32) -> Rgb<u8> {
+    Rgb([color.r(), color.g(), color.b()])
+}
+
+///
+/// Draws a cross with a dark outline, readable over any color.
+///
+fn draw_marker(img: &mut RgbImage, x: f32, y: f32, radius: f32, color: Rgb<u8>) {
+    for (offset, color) in [(1.0, Rgb([0, 0, 0])), (0.0, color)] {
+        draw_line_segment_mut(
+            img,
+            (x - radius + offset, y + offset),
+            (x + radius + offset, y + offset),
+            color,
+        );
+        draw_line_segment_mut(
+            img,
+            (x + offset, y - radius + offset),
+            (x + offset, y + radius + offset),
+            color,
+        );
+    }
+}
+
+///
+/// Draws a line two pixels wide, so it stays visible in downscaled exports.
+///
+fn draw_thick_line(img: &mut RgbImage, start: (f32, f32), end: (f32, f32), color: Rgb<u8>) {
+    for offset in [0.0, 1.0] {
+        draw_line_segment_mut(
+            img,
+            (start.0 + offset, start.1),
+            (end.0 + offset, end.1),
+            color,
+        );
+        draw_line_segment_mut(
+            img,
+            (start.0, start.1 + offset),
+            (end.0, end.1 + offset),
+            color,
+        );
+    }
+}
+
+///
+/// Draws a line from `start` with an arrowhead of `head_length` at `end`.
+///
+fn draw_arrow(
+    img: &mut RgbImage,
+    start: (f32, f32),
+    end: (f32, f32),
+    head_length: f32,
+    color: Rgb<u8>,
+) {
+    draw_thick_line(img, start, end, color);
+    let angle = (end.1 - start.1).atan2(end.0 - start.0);
+    for side in [-1.0, 1.0] {
+        let head_angle = angle + std::f32::consts::PI + side * std::f32::consts::FRAC_PI_6;
+        let tip = (
+            end.0 + head_length * head_angle.cos(),
+            end.1 + head_length * head_angle.sin(),
+        );
+        draw_thick_line(img, end, tip, color);
+    }
+}
+
+///
+/// Draws white text on a black box, vertically centered on `y`.
+///
+fn draw_label(img: &mut RgbImage, font: &FontRef<'_>, scale: PxScale, x: f32, y: f32, text: &str) {
+    let (width, height) = text_size(scale, font, text);
+    let x = x.max(0.0) as i32;
+    let y = (y - scale.y / 2.0).max(0.0) as i32;
+    draw_filled_rect_mut(
+        img,
+        Rect::at(x, y).of_size(width + 2, (height + 2).max(scale.y as u32)),
+        Rgb([0, 0, 0]),
+    );
+    draw_text_mut(img, Rgb([255, 255, 255]), x + 1, y, scale, font, text);
+}
@@ -1,21 +1,146 @@
 use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_events::AppEvent,
+    keyboard_shortcuts::ShortcutAction,
+    thermal_capturer::{ThermalCapturer, ThermalCapturerSettings},
+    AppGlobalState,
+};
+
+///
+/// The panes which are restored with the layout.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PaneKind {
+    ThermalDisplay,
+    Setup,
+    GradientEditor,
+    Histogram,
+    TemporalPlot,
+    LineProfile,
+    Gallery,
+    Capture,
+    Measurements,
+    GizmoList,
+    Alarms,
+    Report,
+    StatusBar,
+    Log,
+    CameraSettings,
+}
+
+impl PaneKind {
+    pub const ALL: [PaneKind; 15] = [
+        PaneKind::ThermalDisplay,
+        PaneKind::Setup,
+        PaneKind::GradientEditor,
+        PaneKind::Histogram,
+        PaneKind::TemporalPlot,
+        PaneKind::LineProfile,
+        PaneKind::Gallery,
+        PaneKind::Capture,
+        PaneKind::Measurements,
+        PaneKind::GizmoList,
+        PaneKind::Alarms,
+        PaneKind::Report,
+        PaneKind::StatusBar,
+        PaneKind::Log,
+        PaneKind::CameraSettings,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PaneKind::ThermalDisplay => "Thermal Display",
+            PaneKind::Setup => "Setup",
+            PaneKind::GradientEditor => "Gradients",
+            PaneKind::Histogram => "Histogram",
+            PaneKind::TemporalPlot => "Chart",
+            PaneKind::LineProfile => "Line Profile",
+            PaneKind::Gallery => "Gallery",
+            PaneKind::Capture => "Capture",
+            PaneKind::Measurements => "Measurements",
+            PaneKind::GizmoList => "Gizmos",
+            PaneKind::Alarms => "Alarms",
+            PaneKind::Report => "Report",
+            PaneKind::StatusBar => "Status Bar",
+            PaneKind::Log => "Log",
+            PaneKind::CameraSettings => "Camera Settings",
+        }
+    }
+}
+
+///
+/// What a pane is drawn with: the state of the app as it was when the frame started,
+/// which can't be changed while drawing, and the events which change it afterwards.
+///
+pub struct PaneContext<'a> {
+    pub global_state: &'a AppGlobalState,
+    events: &'a mut Vec<AppEvent>,
+}
+
+impl<'a> PaneContext<'a> {
+    pub fn new(global_state: &'a AppGlobalState, events: &'a mut Vec<AppEvent>) -> Self {
+        Self {
+            global_state,
+            events,
+        }
+    }
+
+    pub fn send(&mut self, event: AppEvent) {
+        self.events.push(event);
+    }
+
+    pub fn action(&mut self, action: ShortcutAction) {
+        self.send(AppEvent::Action(action));
+    }
+
+    pub fn edit_settings(&mut self, edit: impl FnOnce(&mut ThermalCapturerSettings) + 'static) {
+        self.send(AppEvent::EditSettings(Box::new(edit)));
+    }
+
+    pub fn update(&mut self, update: impl FnOnce(&mut AppGlobalState) + 'static) {
+        self.send(AppEvent::Update(Box::new(update)));
+    }
+
+    ///
+    /// Calls the capturer of the open camera, nothing happens without one.
+    ///
+    pub fn with_capturer(&mut self, call: impl FnOnce(&mut ThermalCapturer) + 'static) {
+        self.update(|global_state| {
+            if let Some(thermal_capturer) = global_state.thermal_capturer_inst.as_mut() {
+                call(thermal_capturer);
+            }
+        });
+    }
+}
 
 pub trait Pane {
     fn title(&self) -> egui::WidgetText;
-    fn ui(&mut self, ui: &mut egui::Ui);
+    fn ui(&mut self, ui: &mut egui::Ui, app: &mut PaneContext<'_>);
     fn force_close(&mut self) -> bool {
         false
     }
 
+    // Called when the user closes the tab, return false to keep it open.
+    fn on_close(&mut self, _app: &mut PaneContext<'_>) -> bool {
+        true
+    }
+
     // If set to true, the pane will be rendered in maximized mode, hiding all other panes.
     fn is_maximized(&self) -> bool {
         false
     }
-}
 
-pub struct PaneDispatcher {}
+    // None for panes which are not saved with the layout, like the ones of additional cameras.
+    fn kind(&self) -> Option<PaneKind> {
+        None
+    }
+}
 
-impl PaneDispatcher {}
+pub struct PaneDispatcher<'a> {
+    pub app: PaneContext<'a>,
+}
 
 ///
 /// Adapter from egui_dock::TabViewer to Pane
@@ -23,7 +148,7 @@ impl PaneDispatcher {}
 /// Makes it so we can implement each tab in a separate struct,
 /// and dynamically dispatch to the correct code.\
 ///
-impl egui_dock::TabViewer for PaneDispatcher {
+impl egui_dock::TabViewer for PaneDispatcher<'_> {
     type Tab = Box<dyn Pane>;
 
     fn title(&mut self, tab: &mut Self::Tab) -> egui::WidgetText {
@@ -31,10 +156,14 @@ impl egui_dock::TabViewer for PaneDispatcher {
     }
 
     fn ui(&mut self, ui: &mut egui::Ui, tab: &mut Self::Tab) {
-        tab.ui(ui);
+        tab.ui(ui, &mut self.app);
     }
 
     fn force_close(&mut self, tab: &mut Self::Tab) -> bool {
         tab.force_close()
     }
+
+    fn on_close(&mut self, tab: &mut Self::Tab) -> bool {
+        tab.on_close(&mut self.app)
+    }
 }
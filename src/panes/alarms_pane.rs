@@ -0,0 +1,225 @@
+use eframe::egui::{
+    self, ComboBox, DragValue, Image, ImageButton, RichText, ScrollArea, TextEdit, Ui,
+};
+use uuid::Uuid;
+
+use crate::{
+    alarms::{save_alarm_rules, AlarmCondition, AlarmMetric, AlarmRule, AlarmSource},
+    change_detection::ChangeDetectionSettings,
+    pane_dispatcher::{Pane, PaneContext, PaneKind},
+    temperature::{Temp, TempDelta, TempFormat},
+    temperature_edit_field::temperature_edit_field,
+};
+
+pub struct AlarmsPane {}
+
+impl AlarmsPane {
+    pub fn new() -> AlarmsPane {
+        AlarmsPane {}
+    }
+
+    ///
+    /// Editor for a single rule, returns true if it was changed.
+    ///
+    fn rule_ui(
+        ui: &mut Ui,
+        rule: &mut AlarmRule,
+        gizmos: &[(Uuid, String)],
+        format: TempFormat,
+        is_active: bool,
+        remove: &mut bool,
+    ) -> bool {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            changed |= ui.checkbox(&mut rule.enabled, "").changed();
+            changed |= ui
+                .add(TextEdit::singleline(&mut rule.name).desired_width(120.0))
+                .changed();
+            if is_active {
+                ui.label(RichText::new("ALARM").color(egui::Color32::RED).strong());
+            }
+            if ui
+                .add(
+                    ImageButton::new(
+                        Image::new(egui::include_image!("../icons/trash.svg"))
+                            .tint(ui.style().visuals.widgets.active.fg_stroke.color),
+                    )
+                    .frame(false),
+                )
+                .on_hover_text("Remove alarm")
+                .clicked()
+            {
+                *remove = true;
+            }
+        });
+
+        ui.horizontal(|ui| {
+            let was_rate = rule.source.is_rate();
+            let source_name = match rule.source {
+                AlarmSource::AnyPixel => "Any pixel".to_string(),
+                AlarmSource::AnyPixelChange => "Any pixel change".to_string(),
+                AlarmSource::Gizmo { uuid, .. } => gizmos
+                    .iter()
+                    .find(|(gizmo_uuid, _)| *gizmo_uuid == uuid)
+                    .map(|(_, name)| name.clone())
+                    .unwrap_or_else(|| "Removed gizmo".to_string()),
+            };
+            ComboBox::from_id_source(("alarm_source", rule.uuid))
+                .selected_text(source_name)
+                .width(100.0)
+                .show_ui(ui, |ui| {
+                    changed |= ui
+                        .selectable_value(&mut rule.source, AlarmSource::AnyPixel, "Any pixel")
+                        .changed();
+                    changed |= ui
+                        .selectable_value(
+                            &mut rule.source,
+                            AlarmSource::AnyPixelChange,
+                            "Any pixel change",
+                        )
+                        .on_hover_text("Fastest rise or fall of any pixel, per second")
+                        .changed();
+                    for (uuid, name) in gizmos.iter() {
+                        let is_selected =
+                            matches!(rule.source, AlarmSource::Gizmo { uuid: u, .. } if u == *uuid);
+                        if ui.selectable_label(is_selected, name).clicked() && !is_selected {
+                            rule.source = AlarmSource::Gizmo {
+                                uuid: *uuid,
+                                metric: AlarmMetric::Value,
+                            };
+                            changed = true;
+                        }
+                    }
+                });
+            // a temperature makes no sense as a rate and vice versa
+            if rule.source.is_rate() != was_rate {
+                rule.threshold = if rule.source.is_rate() {
+                    Temp::new(ChangeDetectionSettings::default().threshold_kelvin_per_sec)
+                } else {
+                    AlarmRule::new(String::new()).threshold
+                };
+            }
+
+            if let AlarmSource::Gizmo { metric, .. } = &mut rule.source {
+                ComboBox::from_id_source(("alarm_metric", rule.uuid))
+                    .selected_text(metric.name())
+                    .width(60.0)
+                    .show_ui(ui, |ui| {
+                        for m in [
+                            AlarmMetric::Value,
+                            AlarmMetric::Min,
+                            AlarmMetric::Max,
+                            AlarmMetric::Mean,
+                        ] {
+                            changed |= ui.selectable_value(metric, m, m.name()).changed();
+                        }
+                    })
+                    .response
+                    .on_hover_text("Min, max and mean are only known for lines and areas");
+            }
+
+            ComboBox::from_id_source(("alarm_condition", rule.uuid))
+                .selected_text(rule.condition.name())
+                .width(60.0)
+                .show_ui(ui, |ui| {
+                    for condition in [AlarmCondition::Above, AlarmCondition::Below] {
+                        changed |= ui
+                            .selectable_value(&mut rule.condition, condition, condition.name())
+                            .changed();
+                    }
+                });
+            if rule.source.is_rate() {
+                let mut rate = rule.threshold.as_delta().to_unit(format.unit);
+                if ui
+                    .add(
+                        DragValue::new(&mut rate)
+                            .speed(0.1)
+                            .clamp_range(0.0..=1000.0)
+                            .max_decimals(format.precision.max(1))
+                            .suffix(format!(" {}/s", format.suffix())),
+                    )
+                    .changed()
+                {
+                    rule.threshold = Temp::from_delta(TempDelta::from_unit(format.unit, rate));
+                    changed = true;
+                }
+            } else {
+                changed |= temperature_edit_field(ui, format, &mut rule.threshold).changed();
+            }
+
+            ui.label("for");
+            changed |= ui
+                .add(
+                    DragValue::new(&mut rule.hold_secs)
+                        .speed(0.1)
+                        .clamp_range(0.0..=3600.0)
+                        .suffix(" s"),
+                )
+                .on_hover_text("How long the condition has to hold before the alarm goes off")
+                .changed();
+            changed |= ui.checkbox(&mut rule.sound, "Sound").changed();
+        });
+        changed
+    }
+}
+
+impl Pane for AlarmsPane {
+    fn title(&self) -> egui::WidgetText {
+        "Alarms".into()
+    }
+
+    fn kind(&self) -> Option<PaneKind> {
+        Some(PaneKind::Alarms)
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, app: &mut PaneContext<'_>) {
+        let global_state = app.global_state;
+        let temp_format = global_state.temp_format();
+
+        let gizmos: Vec<(Uuid, String)> = global_state
+            .thermal_capturer_settings
+            .gizmo
+            .children()
+            .unwrap()
+            .iter()
+            .map(|g| (g.uuid, g.name.clone()))
+            .collect();
+        let active_rules: Vec<Uuid> = global_state
+            .active_alarms
+            .iter()
+            .map(|alarm| alarm.rule_uuid)
+            .collect();
+
+        let mut alarm_rules = global_state.thermal_capturer_settings.alarm_rules.clone();
+        let mut changed = false;
+        if ui.button("Add Alarm").clicked() {
+            alarm_rules.push(AlarmRule::new(format!("Alarm {}", alarm_rules.len() + 1)));
+            changed = true;
+        }
+        ui.separator();
+
+        let mut rule_to_remove = None;
+        ScrollArea::vertical().show(ui, |ui| {
+            for rule in alarm_rules.iter_mut() {
+                let mut remove = false;
+                let is_active = active_rules.contains(&rule.uuid);
+                changed |= Self::rule_ui(ui, rule, &gizmos, temp_format, is_active, &mut remove);
+                if remove {
+                    rule_to_remove = Some(rule.uuid);
+                }
+                ui.separator();
+            }
+        });
+        if let Some(uuid) = rule_to_remove {
+            alarm_rules.retain(|rule| rule.uuid != uuid);
+            changed = true;
+        }
+
+        if changed {
+            if let Err(err) = save_alarm_rules(&alarm_rules) {
+                tracing::error!(error = %err, "Failed to save alarms");
+            }
+            app.edit_settings(move |settings| settings.alarm_rules = alarm_rules);
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use eframe::egui::{self, TextureOptions, WidgetText};
+use uuid::Uuid;
+
+use crate::{
+    pane_dispatcher::{Pane, PaneContext},
+    temperature_edit_field::temperature_range_edit_field,
+    thermal_gradient::THERMAL_GRADIENTS,
+    AppGlobalState,
+};
+
+///
+/// Displays one of the additional cameras of the CameraManager, with its own
+/// gradient, range and rotation. Closing the pane closes the camera.
+///
+pub struct CameraPane {
+    camera_uuid: Uuid,
+    title: String,
+    texture: Option<egui::TextureHandle>,
+    closed: bool,
+}
+
+impl CameraPane {
+    pub fn new(global_state: &AppGlobalState, camera_uuid: Uuid) -> CameraPane {
+        let title = global_state
+            .camera_manager
+            .get(camera_uuid)
+            .map(|camera| camera.name.clone())
+            .unwrap_or_else(|| "Camera".to_string());
+        CameraPane {
+            camera_uuid,
+            title,
+            texture: None,
+            closed: false,
+        }
+    }
+}
+
+impl Pane for CameraPane {
+    fn title(&self) -> WidgetText {
+        self.title.clone().into()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, app: &mut PaneContext<'_>) {
+        let global_state = app.global_state;
+        let temp_format = global_state.temp_format();
+        let custom_gradients = &global_state.custom_gradients;
+        let Some(camera) = global_state.camera_manager.get(self.camera_uuid) else {
+            self.closed = true;
+            return;
+        };
+
+        let mut settings = camera.settings.clone();
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source(("camera_pane_gradient", self.camera_uuid))
+                .selected_text(settings.gradient.name.clone())
+                .show_ui(ui, |ui| {
+                    for gradient in THERMAL_GRADIENTS.iter().chain(custom_gradients.iter()) {
+                        if ui
+                            .selectable_label(
+                                settings.gradient.uuid == gradient.uuid,
+                                gradient.name.clone(),
+                            )
+                            .clicked()
+                        {
+                            settings.gradient = gradient.clone();
+                            changed = true;
+                        }
+                    }
+                });
+            if ui.button("⟳").on_hover_text("Rotate").clicked() {
+                settings.rotation = settings.rotation.next();
+                changed = true;
+            }
+            changed |= ui
+                .checkbox(&mut settings.auto_range, "Auto range")
+                .changed();
+            if ui.button("Close Camera").clicked() {
+                self.closed = true;
+            }
+        });
+        if !settings.auto_range {
+            changed |= temperature_range_edit_field(
+                ui,
+                ("camera_pane_range", self.camera_uuid),
+                true,
+                temp_format,
+                &mut settings.manual_range,
+            )
+            .changed();
+        }
+        if changed {
+            let camera_uuid = self.camera_uuid;
+            app.update(move |global_state| {
+                if let Some(camera) = global_state.camera_manager.get_mut(camera_uuid) {
+                    camera.settings = settings;
+                    camera.apply_settings();
+                }
+            });
+        }
+
+        if let Some(error) = camera.error.as_ref() {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+        if camera.capturer.is_none() {
+            ui.label("The camera has stopped.");
+        }
+
+        if let Some(result) = camera.last_result.as_ref() {
+            self.texture = Some(ui.ctx().load_texture(
+                format!("camera_pane_{}", self.camera_uuid),
+                result.image.clone(),
+                TextureOptions {
+                    magnification: camera.settings.texture_filter,
+                    minification: camera.settings.texture_filter,
+                    ..Default::default()
+                },
+            ));
+            ui.label(format!(
+                "Min {}, max {}",
+                temp_format.format_reading(result.stats.min, result.is_delta),
+                temp_format.format_reading(result.stats.max, result.is_delta)
+            ));
+        }
+        if let Some(texture) = self.texture.as_ref() {
+            ui.add(egui::Image::new(texture).shrink_to_fit());
+        }
+
+        if self.closed {
+            let camera_uuid = self.camera_uuid;
+            app.update(move |global_state| global_state.camera_manager.close(camera_uuid));
+        }
+    }
+
+    fn force_close(&mut self) -> bool {
+        self.closed
+    }
+
+    fn on_close(&mut self, app: &mut PaneContext<'_>) -> bool {
+        let camera_uuid = self.camera_uuid;
+        app.update(move |global_state| global_state.camera_manager.close(camera_uuid));
+        true
+    }
+}
@@ -0,0 +1,131 @@
+use eframe::{
+    egui::{self, ComboBox, Slider},
+    epaint::Color32,
+};
+
+use crate::{
+    camera_adapter::{CameraControlKind, CameraControlValue},
+    pane_dispatcher::{Pane, PaneContext, PaneKind},
+};
+
+///
+/// The device-specific controls of the open camera, as listed by its adapter.
+/// The pane doesn't know what they do, it shows one widget per kind of control.
+///
+pub struct CameraSettingsPane {}
+
+impl CameraSettingsPane {
+    pub fn new() -> CameraSettingsPane {
+        CameraSettingsPane {}
+    }
+}
+
+impl Pane for CameraSettingsPane {
+    fn title(&self) -> egui::WidgetText {
+        "Camera Settings".into()
+    }
+
+    fn kind(&self) -> Option<PaneKind> {
+        Some(PaneKind::CameraSettings)
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, app: &mut PaneContext<'_>) {
+        let global_state = app.global_state;
+
+        let Some(result) = global_state
+            .last_thermal_capturer_result
+            .as_ref()
+            .filter(|_| global_state.thermal_capturer_inst.is_some())
+        else {
+            ui.weak("No camera open");
+            return;
+        };
+        let controls = result.camera_controls.clone();
+        let error = result.camera_control_error.clone();
+        if controls.is_empty() {
+            ui.weak("The camera has no settings of its own");
+            return;
+        }
+
+        let mut changed = None;
+        egui::Grid::new("camera_settings_grid")
+            .num_columns(2)
+            .show(ui, |ui| {
+                for state in controls.iter() {
+                    let control = &state.control;
+                    ui.label(control.name).on_hover_text(control.description);
+                    match (&control.kind, state.value) {
+                        (
+                            CameraControlKind::Choice { options, .. },
+                            Some(CameraControlValue::Choice(mut index)),
+                        ) => {
+                            ComboBox::from_id_source(("camera_control", control.id))
+                                .selected_text(options.get(index).copied().unwrap_or_default())
+                                .show_ui(ui, |ui| {
+                                    for (i, option) in options.iter().enumerate() {
+                                        if ui.selectable_value(&mut index, i, *option).changed() {
+                                            changed =
+                                                Some((control.id, CameraControlValue::Choice(i)));
+                                        }
+                                    }
+                                })
+                                .response
+                                .on_hover_text(control.description);
+                        }
+                        (
+                            CameraControlKind::Toggle { .. },
+                            Some(CameraControlValue::Toggle(mut on)),
+                        ) => {
+                            if ui
+                                .checkbox(&mut on, "")
+                                .on_hover_text(control.description)
+                                .changed()
+                            {
+                                changed = Some((control.id, CameraControlValue::Toggle(on)));
+                            }
+                        }
+                        (
+                            CameraControlKind::Range {
+                                min, max, suffix, ..
+                            },
+                            Some(CameraControlValue::Number(mut number)),
+                        ) => {
+                            if ui
+                                .add(Slider::new(&mut number, *min..=*max).suffix(*suffix))
+                                .on_hover_text(control.description)
+                                .changed()
+                            {
+                                changed = Some((control.id, CameraControlValue::Number(number)));
+                            }
+                        }
+                        (CameraControlKind::Action, _) => {
+                            if ui
+                                .button(control.name)
+                                .on_hover_text(control.description)
+                                .clicked()
+                            {
+                                changed = Some((control.id, CameraControlValue::Trigger));
+                            }
+                        }
+                        // the value doesn't fit the kind, the adapter is broken
+                        _ => {
+                            ui.colored_label(Color32::RED, "Invalid value");
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if let Some(error) = error {
+            ui.colored_label(Color32::RED, error);
+        }
+
+        if let Some((id, value)) = changed {
+            app.update(move |global_state| {
+                if let Some(capturer) = global_state.thermal_capturer_inst.as_mut() {
+                    capturer.set_camera_control(id, value);
+                }
+            });
+        }
+    }
+}
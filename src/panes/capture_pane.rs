@@ -1,33 +1,525 @@
 use std::{
-    any::{Any, TypeId},
-    borrow::Borrow,
-    cell::RefCell,
     path::PathBuf,
-    rc::Rc,
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
-use eframe::egui::{self, Align, Button, Color32, Layout, Vec2};
+use anyhow::anyhow;
+use chrono::{DateTime, Local, NaiveTime, TimeDelta};
+use eframe::egui::{
+    self, Align, Button, CollapsingHeader, Color32, ComboBox, DragValue, Layout, TextEdit, Vec2,
+};
+use uuid::Uuid;
 
 use crate::{
-    pane_dispatcher::Pane,
-    recorders::{image_recorder::ImageRecorder, video_recorder::VideoRecorder},
+    measurement_log::{MeasurementLogParams, MEASUREMENT_LOG_EXTENSION},
+    pane_dispatcher::{Pane, PaneContext, PaneKind},
+    radiometric_recording::RADIOMETRIC_EXTENSION,
+    recorders::{
+        auto_snapshot_recorder::{
+            AutoSnapshotRecorder, AutoSnapshotRule, DEFAULT_SNAPSHOT_PATTERN,
+        },
+        recorder::{Recorder, RecorderState},
+        timelapse_recorder::{TimelapseRecorder, TimelapseSchedule},
+        video_recorder::{
+            VideoRecorder, VideoSettings, MAX_VIDEO_BIT_RATE_KBPS, MAX_VIDEO_FRAME_RATE,
+            MIN_VIDEO_BIT_RATE_KBPS,
+        },
+    },
+    thermal_capturer::ThermalCapturerExt,
+    triggered_recording::{
+        TriggeredRecordingParams, TriggeredRecordingSettings, MAX_TRIGGER_BUFFER_MB,
+    },
     types::media_formats::{ImageFormat, VideoFormat},
-    AppGlobalState,
 };
 
+struct TimelapseOutputs {
+    images: bool,
+    video: bool,
+    radiometric: bool,
+}
+
+impl TimelapseOutputs {
+    fn any(&self) -> bool {
+        self.images || self.video || self.radiometric
+    }
+}
+
 pub struct CapturePane {
-    global_state: Rc<RefCell<AppGlobalState>>,
-    snapshot_format: ImageFormat,
-    video_format: VideoFormat,
+    video_settings: VideoSettings,
+
+    timelapse_interval: f32,
+    timelapse_interval_in_minutes: bool,
+    timelapse_outputs: TimelapseOutputs,
+    // Times of day as HH:MM, empty to start now / run until stopped
+    timelapse_start_at: String,
+    timelapse_stop_at: String,
+    // Empty means a "timelapse" folder in the captures directory
+    timelapse_directory: String,
+    timelapse_recorder: Option<Arc<Mutex<dyn Recorder>>>,
+    // Whether the running radiometric recording belongs to the timelapse
+    timelapse_radiometric: bool,
+    timelapse_error: Option<String>,
+
+    // None to only save on alarms
+    auto_snapshot_interval_secs: Option<f32>,
+    auto_snapshot_on_alarm: bool,
+    auto_snapshot_pattern: String,
+    auto_snapshot_gizmo: Option<Uuid>,
+    auto_snapshot_recorder: Option<Arc<Mutex<dyn Recorder>>>,
+
+    triggered_recording: TriggeredRecordingSettings,
+
+    // Size after which the measurement log continues in a new file
+    measurement_log_max_file_size_mb: u64,
 }
 
 impl CapturePane {
-    pub fn new(global_state: Rc<RefCell<AppGlobalState>>) -> CapturePane {
+    pub fn new() -> CapturePane {
         CapturePane {
-            global_state,
-            snapshot_format: ImageFormat::Png,
-            video_format: VideoFormat::MP4_H264,
+            video_settings: VideoSettings::default(),
+            timelapse_interval: 10.0,
+            timelapse_interval_in_minutes: false,
+            timelapse_outputs: TimelapseOutputs {
+                images: true,
+                video: false,
+                radiometric: false,
+            },
+            timelapse_start_at: String::new(),
+            timelapse_stop_at: String::new(),
+            timelapse_directory: String::new(),
+            timelapse_recorder: None,
+            timelapse_radiometric: false,
+            timelapse_error: None,
+            auto_snapshot_interval_secs: Some(60.0),
+            auto_snapshot_on_alarm: true,
+            auto_snapshot_pattern: DEFAULT_SNAPSHOT_PATTERN.to_string(),
+            auto_snapshot_gizmo: None,
+            auto_snapshot_recorder: None,
+            triggered_recording: TriggeredRecordingSettings::default(),
+            measurement_log_max_file_size_mb: 100,
+        }
+    }
+
+    ///
+    /// Whether the recorder runs in the background on its own schedule,
+    /// rather than being the video recording of the record button.
+    ///
+    fn is_background_recorder(&self, recorder: &Arc<Mutex<dyn Recorder>>) -> bool {
+        [&self.timelapse_recorder, &self.auto_snapshot_recorder]
+            .into_iter()
+            .flatten()
+            .any(|background| Arc::ptr_eq(background, recorder))
+    }
+
+    fn video_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Frame rate");
+            let mut camera_rate = self.video_settings.frame_rate.is_none();
+            if ui
+                .checkbox(&mut camera_rate, "Camera")
+                .on_hover_text("Use the frame rate of the camera")
+                .changed()
+            {
+                self.video_settings.frame_rate = if camera_rate { None } else { Some(25) };
+            }
+            if let Some(frame_rate) = self.video_settings.frame_rate.as_mut() {
+                ui.add(
+                    DragValue::new(frame_rate)
+                        .clamp_range(1..=MAX_VIDEO_FRAME_RATE)
+                        .suffix(" fps"),
+                );
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Bit rate");
+            ui.add(
+                DragValue::new(&mut self.video_settings.bit_rate_kbps)
+                    .speed(10.0)
+                    .clamp_range(MIN_VIDEO_BIT_RATE_KBPS..=MAX_VIDEO_BIT_RATE_KBPS)
+                    .suffix(" kbit/s"),
+            );
+        });
+        ui.label(
+            "Applies to the next recording. The burn in options of the snapshots apply as well.",
+        );
+    }
+
+    fn timelapse_ui(&mut self, ui: &mut egui::Ui, app: &mut PaneContext<'_>) {
+        let is_running = self
+            .timelapse_recorder
+            .as_ref()
+            .is_some_and(|rec| rec.lock().unwrap().state() != RecorderState::Done);
+        if !is_running {
+            // the capturer stops the raw recording at the same stop time
+            self.timelapse_radiometric = false;
+        }
+
+        ui.add_enabled_ui(!is_running, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Every");
+                ui.add(DragValue::new(&mut self.timelapse_interval).clamp_range(
+                    if self.timelapse_interval_in_minutes {
+                        1.0..=1440.0
+                    } else {
+                        1.0..=3600.0
+                    },
+                ));
+                ComboBox::from_id_source("capture_pane_timelapse_unit")
+                    .width(50.0)
+                    .selected_text(if self.timelapse_interval_in_minutes {
+                        "min"
+                    } else {
+                        "s"
+                    })
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.timelapse_interval_in_minutes, false, "s");
+                        ui.selectable_value(&mut self.timelapse_interval_in_minutes, true, "min");
+                    });
+            });
+            ui.horizontal(|ui| {
+                ui.label("Save");
+                ui.checkbox(&mut self.timelapse_outputs.images, "Images");
+                ui.checkbox(&mut self.timelapse_outputs.video, "Video")
+                    .on_hover_text("One video frame per capture, with the video settings above");
+                ui.checkbox(&mut self.timelapse_outputs.radiometric, "Raw")
+                    .on_hover_text(
+                        "The temperatures of every capture, in one radiometric recording",
+                    );
+            });
+            ui.horizontal(|ui| {
+                ui.label("From");
+                ui.add(
+                    TextEdit::singleline(&mut self.timelapse_start_at)
+                        .hint_text("now")
+                        .desired_width(50.0),
+                );
+                ui.label("until");
+                ui.add(
+                    TextEdit::singleline(&mut self.timelapse_stop_at)
+                        .hint_text("stopped")
+                        .desired_width(50.0),
+                );
+            })
+            .response
+            .on_hover_text("Times of day as HH:MM, the next time they occur");
+            ui.horizontal(|ui| {
+                ui.label("Folder");
+                ui.add(
+                    TextEdit::singleline(&mut self.timelapse_directory)
+                        .hint_text("<captures>/timelapse"),
+                );
+            });
+        });
+
+        if let Some(error) = &self.timelapse_error {
+            ui.colored_label(Color32::RED, error);
+        }
+
+        if is_running {
+            if ui.button("Stop timelapse").clicked() {
+                if let Some(rec) = self.timelapse_recorder.take() {
+                    let _ = rec.lock().unwrap().stop().inspect_err(|err| {
+                        tracing::error!(error = %err, "Failed to stop timelapse");
+                    });
+                }
+                if self.timelapse_radiometric {
+                    app.with_capturer(|thermal_capturer| {
+                        thermal_capturer.stop_radiometric_recording()
+                    });
+                }
+            }
+        } else if ui
+            .add_enabled(self.timelapse_outputs.any(), Button::new("Start timelapse"))
+            .clicked()
+        {
+            match self.timelapse_schedule() {
+                Ok(schedule) => {
+                    self.timelapse_error = None;
+                    self.start_timelapse(app, schedule);
+                }
+                Err(err) => self.timelapse_error = Some(err.to_string()),
+            }
+        }
+    }
+
+    fn timelapse_schedule(&self) -> anyhow::Result<TimelapseSchedule> {
+        let interval_secs = if self.timelapse_interval_in_minutes {
+            self.timelapse_interval * 60.0
+        } else {
+            self.timelapse_interval
+        };
+        let now = Local::now();
+        let start_at = parse_time_of_day(&self.timelapse_start_at, now)?;
+        let stop_at = parse_time_of_day(&self.timelapse_stop_at, start_at.unwrap_or(now))?;
+        Ok(TimelapseSchedule::new(
+            Duration::from_secs_f32(interval_secs),
+            start_at,
+            stop_at,
+        ))
+    }
+
+    fn start_timelapse(&mut self, app: &mut PaneContext<'_>, schedule: TimelapseSchedule) {
+        let global_state = app.global_state;
+        let destination = if self.timelapse_directory.trim().is_empty() {
+            let captures_dir = global_state
+                .prefs
+                .as_ref()
+                .map(|prefs| prefs.captures_directory.clone())
+                .unwrap_or("./".to_string());
+            PathBuf::from(captures_dir).join("timelapse")
+        } else {
+            PathBuf::from(self.timelapse_directory.trim())
+        };
+
+        let video = self.timelapse_outputs.video.then(|| {
+            VideoRecorder::new_timelapse(
+                destination.clone(),
+                "timelapse".to_string(),
+                self.video_settings,
+                global_state.video_overlay(),
+            )
+        });
+        // Also created for raw only, it tells the pane when the schedule is over
+        let recorder: Arc<Mutex<dyn Recorder>> = Arc::new(Mutex::new(TimelapseRecorder::new(
+            destination.clone(),
+            schedule,
+            global_state.image_metadata(),
+            self.timelapse_outputs.images,
+            video,
+        )));
+        self.timelapse_recorder = Some(recorder.clone());
+        app.edit_settings(move |settings| settings.recorders.push(recorder));
+        if global_state.thermal_capturer_inst.is_some() && self.timelapse_outputs.radiometric {
+            let filename = format!(
+                "timelapse_{}.{}",
+                Local::now().format("%Y-%m-%d_%H-%M-%S"),
+                RADIOMETRIC_EXTENSION
+            );
+            let path = destination.join(filename);
+            app.with_capturer(move |thermal_capturer| {
+                thermal_capturer.start_radiometric_timelapse(path, schedule)
+            });
+            self.timelapse_radiometric = true;
+        }
+    }
+
+    fn auto_snapshot_ui(&mut self, ui: &mut egui::Ui, app: &mut PaneContext<'_>) {
+        let global_state = app.global_state;
+        let is_running = self
+            .auto_snapshot_recorder
+            .as_ref()
+            .is_some_and(|rec| rec.lock().unwrap().state() != RecorderState::Done);
+        let gizmos: Vec<(Uuid, String)> = global_state
+            .thermal_capturer_settings
+            .gizmo
+            .children()
+            .unwrap()
+            .iter()
+            .map(|g| (g.uuid, g.name.clone()))
+            .collect();
+
+        ui.add_enabled_ui(!is_running, |ui| {
+            ui.horizontal(|ui| {
+                let mut use_interval = self.auto_snapshot_interval_secs.is_some();
+                if ui.checkbox(&mut use_interval, "Every").changed() {
+                    self.auto_snapshot_interval_secs = use_interval.then_some(60.0);
+                }
+                if let Some(interval) = self.auto_snapshot_interval_secs.as_mut() {
+                    ui.add(
+                        DragValue::new(interval)
+                            .clamp_range(1.0..=86400.0)
+                            .suffix(" s"),
+                    );
+                }
+            });
+            ui.checkbox(&mut self.auto_snapshot_on_alarm, "When an alarm goes off");
+            ui.horizontal(|ui| {
+                ui.label("Name");
+                ui.add(
+                    TextEdit::singleline(&mut self.auto_snapshot_pattern)
+                        .hint_text(DEFAULT_SNAPSHOT_PATTERN),
+                )
+                .on_hover_text(
+                    "{timestamp}, {trigger} (interval or the alarm), {gizmo} and {temp} are replaced",
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.label("{gizmo}");
+                let selected_name = self
+                    .auto_snapshot_gizmo
+                    .and_then(|uuid| gizmos.iter().find(|(u, _)| *u == uuid))
+                    .map_or("Hottest pixel".to_string(), |(_, name)| name.clone());
+                ComboBox::from_id_source("capture_pane_auto_snapshot_gizmo")
+                    .selected_text(selected_name)
+                    .width(100.0)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.auto_snapshot_gizmo, None, "Hottest pixel");
+                        for (uuid, name) in gizmos.iter() {
+                            ui.selectable_value(&mut self.auto_snapshot_gizmo, Some(*uuid), name);
+                        }
+                    });
+            });
+        });
+
+        if is_running {
+            if ui.button("Stop automatic snapshots").clicked() {
+                if let Some(rec) = self.auto_snapshot_recorder.take() {
+                    let _ = rec.lock().unwrap().stop().inspect_err(|err| {
+                        tracing::error!(error = %err, "Failed to stop automatic snapshots");
+                    });
+                }
+            }
+        } else if ui
+            .add_enabled(
+                self.auto_snapshot_interval_secs.is_some() || self.auto_snapshot_on_alarm,
+                Button::new("Start automatic snapshots"),
+            )
+            .on_hover_text("Radiometric PNGs in the \"auto\" folder of the captures directory")
+            .clicked()
+        {
+            let captures_dir = global_state
+                .prefs
+                .as_ref()
+                .map(|prefs| prefs.captures_directory.clone())
+                .unwrap_or("./".to_string());
+            let rule = AutoSnapshotRule {
+                interval: self
+                    .auto_snapshot_interval_secs
+                    .map(Duration::from_secs_f32),
+                on_alarm: self.auto_snapshot_on_alarm,
+                filename_pattern: self.auto_snapshot_pattern.clone(),
+                gizmo: self
+                    .auto_snapshot_gizmo
+                    .and_then(|uuid| gizmos.into_iter().find(|(u, _)| *u == uuid)),
+            };
+            let recorder: Arc<Mutex<dyn Recorder>> =
+                Arc::new(Mutex::new(AutoSnapshotRecorder::new(
+                    PathBuf::from(captures_dir).join("auto"),
+                    rule,
+                    global_state.image_metadata(),
+                )));
+            self.auto_snapshot_recorder = Some(recorder.clone());
+            app.edit_settings(move |settings| settings.recorders.push(recorder));
+        }
+    }
+
+    fn triggered_recording_ui(&mut self, ui: &mut egui::Ui, app: &mut PaneContext<'_>) {
+        let global_state = app.global_state;
+        let (is_armed, is_recording) =
+            global_state
+                .last_thermal_capturer_result
+                .as_ref()
+                .map_or((false, false), |r| {
+                    (
+                        r.extension.is_triggered_recording_armed,
+                        r.extension.is_triggered_recording_active,
+                    )
+                });
+
+        ui.add_enabled_ui(!is_armed, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Before");
+                ui.add(
+                    DragValue::new(&mut self.triggered_recording.pre_trigger_secs)
+                        .clamp_range(0.0..=300.0)
+                        .suffix(" s"),
+                );
+                ui.label("after");
+                ui.add(
+                    DragValue::new(&mut self.triggered_recording.post_trigger_secs)
+                        .clamp_range(0.0..=3600.0)
+                        .suffix(" s"),
+                )
+                .on_hover_text("Recording continues this long after the alarms cleared");
+            });
+            ui.horizontal(|ui| {
+                ui.label("Buffer up to");
+                ui.add(
+                    DragValue::new(&mut self.triggered_recording.max_buffer_mb)
+                        .clamp_range(16..=MAX_TRIGGER_BUFFER_MB)
+                        .suffix(" MB"),
+                )
+                .on_hover_text("Less time before the alarm is kept if the frames need more memory");
+            });
+        });
+
+        if is_armed {
+            if is_recording {
+                ui.colored_label(Color32::RED, "Recording alarm");
+            }
+            if ui.button("Disarm").clicked() {
+                app.with_capturer(|thermal_capturer| thermal_capturer.disarm_triggered_recording());
+            }
+        } else if ui
+            .button("Arm")
+            .on_hover_text(
+                "Record the raw temperatures around every alarm, in the \"alarms\" folder of the captures directory",
+            )
+            .clicked()
+        {
+            let captures_dir = global_state
+                .prefs
+                .as_ref()
+                .map(|prefs| prefs.captures_directory.clone())
+                .unwrap_or("./".to_string());
+            let params = TriggeredRecordingParams {
+                directory: PathBuf::from(captures_dir).join("alarms"),
+                settings: self.triggered_recording,
+            };
+            app.with_capturer(move |thermal_capturer| {
+                thermal_capturer.arm_triggered_recording(params)
+            });
+        }
+    }
+
+    fn measurement_log_ui(&mut self, ui: &mut egui::Ui, app: &mut PaneContext<'_>) {
+        let global_state = app.global_state;
+        let is_logging = global_state
+            .last_thermal_capturer_result
+            .as_ref()
+            .is_some_and(|r| r.extension.is_logging_measurements);
+
+        ui.add_enabled_ui(!is_logging, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("New file every");
+                ui.add(
+                    DragValue::new(&mut self.measurement_log_max_file_size_mb)
+                        .clamp_range(1..=10_000)
+                        .suffix(" MB"),
+                );
+            });
+        });
+
+        if is_logging {
+            if ui.button("Stop logging").clicked() {
+                app.with_capturer(|thermal_capturer| thermal_capturer.stop_measurement_log());
+            }
+        } else if ui
+            .button("Start logging")
+            .on_hover_text(
+                "Write the values and statistics of all gizmos of every frame to CSV files",
+            )
+            .clicked()
+        {
+            let captures_dir = global_state
+                .prefs
+                .as_ref()
+                .map(|prefs| prefs.captures_directory.clone())
+                .unwrap_or("./".to_string());
+            let filename = format!(
+                "measurements_{}.{}",
+                Local::now().format("%Y-%m-%d_%H-%M-%S"),
+                MEASUREMENT_LOG_EXTENSION
+            );
+            let params = MeasurementLogParams {
+                path: PathBuf::from(captures_dir).join(filename),
+                unit: global_state.preferred_temperature_unit(),
+                max_file_size: self.measurement_log_max_file_size_mb * 1024 * 1024,
+            };
+            app.with_capturer(move |thermal_capturer| {
+                thermal_capturer.start_measurement_log(params)
+            });
         }
     }
 }
@@ -37,21 +529,30 @@ impl Pane for CapturePane {
         "Capture".into()
     }
 
-    fn ui(&mut self, ui: &mut egui::Ui) {
-        let global_state_clone = self.global_state.clone();
-        let mut global_state = global_state_clone.as_ref().borrow_mut();
+    fn kind(&self) -> Option<PaneKind> {
+        Some(PaneKind::Capture)
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, app: &mut PaneContext<'_>) {
+        let global_state = app.global_state;
 
         let available_width = ui.available_width();
         ui.add_enabled_ui(global_state.thermal_capturer_inst.is_some(), |ui| {
             ui.with_layout(Layout::left_to_right(egui::Align::Min), |ui| {
                 ui.with_layout(Layout::top_down_justified(Align::Min), |ui| {
                     ui.set_max_width(available_width / 2.0 - 5.0);
+                    let mut snapshot_format = global_state.snapshot_format;
                     ImageFormat::egui_combo_box(
                         ui,
                         "capture_pane_snapshot_format",
-                        &mut self.snapshot_format,
+                        &mut snapshot_format,
                         available_width / 2.0 - 5.0,
                     );
+                    if snapshot_format != global_state.snapshot_format {
+                        app.update(move |global_state| {
+                            global_state.snapshot_format = snapshot_format
+                        });
+                    }
 
                     if ui
                         .add(
@@ -63,40 +564,93 @@ impl Pane for CapturePane {
                         )
                         .clicked()
                     {
-                        let captures_dir = global_state
-                            .prefs
-                            .as_ref()
-                            .map(|prefs| prefs.captures_directory.clone())
-                            .unwrap_or("./".to_string());
+                        app.update(|global_state| global_state.take_snapshot());
+                    }
 
-                        global_state
-                            .thermal_capturer_settings
-                            .recorders
-                            .push(Arc::new(Mutex::new(ImageRecorder::new(
-                                PathBuf::from(captures_dir),
-                                "photo".to_string(),
-                                self.snapshot_format,
-                            ))));
-
-                        let settings_clone = global_state.thermal_capturer_settings.clone();
-                        if let Some(thermal_capturer) = global_state.thermal_capturer_inst.as_mut()
-                        {
-                            thermal_capturer.set_settings(settings_clone);
+                    if ui
+                        .button("Export TIFF")
+                        .on_hover_text(
+                            "Save the temperatures as a 16-bit radiometric TIFF, e.g. for ImageJ",
+                        )
+                        .clicked()
+                    {
+                        match global_state.export_radiometric_tiff() {
+                            Ok(path) => tracing::info!(path = %path.display(), "Exported"),
+                            Err(err) => tracing::error!(error = %err, "Failed to export TIFF"),
                         }
                     }
+
+                    let is_recording_radiometric = global_state
+                        .last_thermal_capturer_result
+                        .as_ref()
+                        .is_some_and(|r| r.extension.is_recording_radiometric);
+                    if is_recording_radiometric {
+                        if ui.button("Stop raw recording").clicked() {
+                            app.with_capturer(|thermal_capturer| {
+                                thermal_capturer.stop_radiometric_recording()
+                            });
+                        }
+                    } else if ui
+                        .button("Record raw")
+                        .on_hover_text(
+                            "Record the temperatures of every frame, to analyze them again later",
+                        )
+                        .clicked()
+                    {
+                        app.update(|global_state| global_state.start_radiometric_recording());
+                    }
+
+                    let mut snapshot_radiometric = global_state.snapshot_radiometric;
+                    if ui
+                        .checkbox(&mut snapshot_radiometric, "Radiometric")
+                        .on_hover_text(
+                            "Store the temperatures in PNG snapshots, to measure them again later via File > Open Snapshot",
+                        )
+                        .changed()
+                    {
+                        app.update(move |global_state| {
+                            global_state.snapshot_radiometric = snapshot_radiometric
+                        });
+                    }
+                    let mut snapshot_footer = global_state.snapshot_footer;
+                    if ui
+                        .checkbox(&mut snapshot_footer, "Info footer")
+                        .on_hover_text(
+                            "Add a line with the time, range and resolution below the image",
+                        )
+                        .changed()
+                    {
+                        app.update(move |global_state| {
+                            global_state.snapshot_footer = snapshot_footer
+                        });
+                    }
+                    let mut overlay = global_state.export_overlay;
+                    ui.menu_button("Burn in", |ui| {
+                        ui.checkbox(&mut overlay.color_scale, "Color scale");
+                        ui.checkbox(&mut overlay.min_max_markers, "Min/max markers");
+                        ui.checkbox(&mut overlay.gizmo_labels, "Gizmo labels");
+                        ui.checkbox(&mut overlay.timestamp, "Timestamp");
+                        ui.checkbox(&mut overlay.annotations, "Annotations");
+                    })
+                    .response
+                    .on_hover_text("Drawn into snapshots, timelapse images and videos");
+                    if overlay != global_state.export_overlay {
+                        app.update(move |global_state| global_state.export_overlay = overlay);
+                    }
                 });
 
                 ui.with_layout(Layout::top_down_justified(Align::Min), |ui| {
                     VideoFormat::egui_combo_box(
                         ui,
                         "capture_pane_video_format",
-                        &mut self.video_format,
+                        &mut self.video_settings.format,
                         available_width / 2.0 - 5.0,
                     );
                     let is_recording = global_state
                         .thermal_capturer_settings
                         .recorders
-                        .iter_mut()
+                        .iter()
+                        .filter(|recorder| !self.is_background_recorder(recorder))
                         .any(|recorder| {
                             let recorder = recorder.lock().unwrap();
                             recorder.is_continuous()
@@ -126,6 +680,7 @@ impl Pane for CapturePane {
                                     .thermal_capturer_settings
                                     .recorders
                                     .iter()
+                                    .filter(|recorder| !self.is_background_recorder(recorder))
                                     .find(|recorder| {
                                         let recorder = recorder.lock().unwrap();
                                         recorder.is_continuous()
@@ -141,7 +696,10 @@ impl Pane for CapturePane {
                                     })
                                     .map(|mut rec| rec.stop())
                                     .inspect_err(|err| {
-                                        log::error!("Failed to stop video recording: {}", err)
+                                        tracing::error!(
+                                            error = %err,
+                                            "Failed to stop video recording"
+                                        )
                                     });
                             }
                         });
@@ -160,23 +718,62 @@ impl Pane for CapturePane {
                             .as_ref()
                             .map(|prefs| prefs.captures_directory.clone())
                             .unwrap_or("./".to_string());
-
-                        global_state
-                            .thermal_capturer_settings
-                            .recorders
-                            .push(Arc::new(Mutex::new(VideoRecorder::new(
+                        let recorder: Arc<Mutex<dyn Recorder>> =
+                            Arc::new(Mutex::new(VideoRecorder::new(
                                 PathBuf::from(captures_dir),
                                 "video".to_string(),
-                                self.video_format,
-                            ))));
-                        let settings_clone = global_state.thermal_capturer_settings.clone();
-                        if let Some(thermal_capturer) = global_state.thermal_capturer_inst.as_mut()
-                        {
-                            thermal_capturer.set_settings(settings_clone);
-                        }
+                                self.video_settings,
+                                global_state.video_overlay(),
+                            )));
+                        app.edit_settings(move |settings| settings.recorders.push(recorder));
                     }
                 });
             });
+
+            ui.separator();
+            CollapsingHeader::new("Video")
+                .id_source("capture_pane_video")
+                .show(ui, |ui| self.video_ui(ui));
+            CollapsingHeader::new("Timelapse")
+                .id_source("capture_pane_timelapse")
+                .show(ui, |ui| self.timelapse_ui(ui, app));
+            CollapsingHeader::new("Automatic snapshots")
+                .id_source("capture_pane_auto_snapshots")
+                .show(ui, |ui| self.auto_snapshot_ui(ui, app));
+            CollapsingHeader::new("Alarm recording")
+                .id_source("capture_pane_triggered_recording")
+                .show(ui, |ui| self.triggered_recording_ui(ui, app));
+            CollapsingHeader::new("Measurement log")
+                .id_source("capture_pane_measurement_log")
+                .show(ui, |ui| self.measurement_log_ui(ui, app));
         });
     }
 }
+
+///
+/// Parses a time of day as HH:MM into the next time it occurs after `after`.
+/// Empty text is None.
+///
+fn parse_time_of_day(
+    text: &str,
+    after: DateTime<Local>,
+) -> anyhow::Result<Option<DateTime<Local>>> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(None);
+    }
+    let time = NaiveTime::parse_from_str(text, "%H:%M")
+        .map_err(|_| anyhow!("\"{}\" is not a time of day like 18:30", text))?;
+    let mut date = after.date_naive();
+    loop {
+        let at = date
+            .and_time(time)
+            .and_local_timezone(Local)
+            .earliest()
+            .ok_or(anyhow!("{} does not exist in the local time zone", text))?;
+        if at > after {
+            return Ok(Some(at));
+        }
+        date += TimeDelta::days(1);
+    }
+}
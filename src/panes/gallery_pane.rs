@@ -1,14 +1,14 @@
 use std::{
-    cell::RefCell,
     collections::VecDeque,
     path::{Path, PathBuf},
-    rc::Rc,
 };
 
 use eframe::egui::{self, scroll_area::ScrollBarVisibility, Align, Image, Layout, Ui, Vec2};
 
 use crate::{
-    pane_dispatcher::Pane, types::media_formats::all_media_file_extensions, AppGlobalState,
+    pane_dispatcher::{Pane, PaneContext, PaneKind},
+    types::media_formats::all_media_file_extensions,
+    AppGlobalState,
 };
 
 #[derive(Debug, Clone)]
@@ -17,13 +17,11 @@ pub struct GalleryElement {
     pub created_at: std::time::SystemTime,
 }
 
-pub struct GalleryPane {
-    global_state: Rc<RefCell<AppGlobalState>>,
-}
+pub struct GalleryPane {}
 
 impl GalleryPane {
-    pub fn new(global_state: Rc<RefCell<AppGlobalState>>) -> GalleryPane {
-        GalleryPane { global_state }
+    pub fn new() -> GalleryPane {
+        GalleryPane {}
     }
 }
 
@@ -32,12 +30,19 @@ impl Pane for GalleryPane {
         "Gallery".into()
     }
 
-    fn ui(&mut self, ui: &mut egui::Ui) {
-        if let Err(err) = self.init_gallery() {
-            eprintln!("Failed to initialize gallery: {:?}", err);
+    fn kind(&self) -> Option<PaneKind> {
+        Some(PaneKind::Gallery)
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, app: &mut PaneContext<'_>) {
+        let global_state = app.global_state;
+        if !global_state.did_init_gallery {
+            app.update(|global_state| {
+                if let Err(err) = Self::init_gallery(global_state) {
+                    tracing::error!(error = ?err, "Failed to initialize gallery");
+                }
+            });
         }
-        let global_state_clone = self.global_state.clone();
-        let global_state = global_state_clone.as_ref().borrow_mut();
 
         // Width of each element in the gallery
         const ELEM_WIDTH: f32 = 150.0;
@@ -82,10 +87,7 @@ impl Pane for GalleryPane {
 
 impl GalleryPane {
     // Loads files from the captures directory and initializes the gallery
-    fn init_gallery(&mut self) -> Result<(), anyhow::Error> {
-        let global_state_clone = self.global_state.clone();
-        let mut global_state = global_state_clone.as_ref().borrow_mut();
-
+    fn init_gallery(global_state: &mut AppGlobalState) -> Result<(), anyhow::Error> {
         if global_state.did_init_gallery {
             return Ok(());
         }
@@ -132,7 +134,7 @@ impl GalleryPane {
             })
             .collect();
 
-        gallery_vec.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        gallery_vec.sort_by_key(|item| item.created_at);
 
         // Limit the vector to the last 20 items
         let last_items = gallery_vec.iter().rev().take(20).collect::<Vec<_>>();
@@ -0,0 +1,141 @@
+use eframe::egui::{self, color_picker::Alpha, CursorIcon, Id, Image, ImageButton, TextEdit};
+
+use crate::{
+    gizmos::GizmoKind,
+    pane_dispatcher::{Pane, PaneContext, PaneKind},
+    panes::measurements_pane::color_icon_rgb,
+    temperature::format_reading,
+};
+
+///
+/// All gizmos in one list, to rename, recolor, hide and reorder them without hunting
+/// for them on the image.
+///
+pub struct GizmoListPane {}
+
+impl GizmoListPane {
+    pub fn new() -> GizmoListPane {
+        GizmoListPane {}
+    }
+}
+
+impl Pane for GizmoListPane {
+    fn title(&self) -> egui::WidgetText {
+        "Gizmos".into()
+    }
+
+    fn kind(&self) -> Option<PaneKind> {
+        Some(PaneKind::GizmoList)
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, app: &mut PaneContext<'_>) {
+        let global_state = app.global_state;
+
+        let gizmo_results = global_state
+            .last_thermal_capturer_result
+            .as_ref()
+            .map(|r| r.extension.gizmo_results.clone());
+        let is_delta = global_state
+            .last_thermal_capturer_result
+            .as_ref()
+            .is_some_and(|r| r.is_delta);
+        let temp_unit = global_state.preferred_temperature_unit();
+        let temp_precision = global_state.preferred_temperature_precision();
+
+        let mut settings_changed = false;
+        // (from, to) indices of a row dropped onto another one
+        let mut moved = None;
+
+        let mut root_gizmo = global_state.thermal_capturer_settings.gizmo.clone();
+        let children = root_gizmo.children_mut().unwrap();
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (index, gizmo) in children.iter_mut().enumerate() {
+                let row = ui.horizontal(|ui| {
+                    ui.dnd_drag_source(Id::new(("gizmo_list_drag", gizmo.uuid)), index, |ui| {
+                        ui.label("☰");
+                    })
+                    .response
+                    .on_hover_cursor(CursorIcon::Grab)
+                    .on_hover_text("Drag to reorder");
+
+                    let mut visible = !gizmo.hidden;
+                    if ui
+                        .checkbox(&mut visible, "")
+                        .on_hover_text("Show on the image, hidden gizmos are still measured")
+                        .changed()
+                    {
+                        gizmo.hidden = !visible;
+                        settings_changed = true;
+                    }
+
+                    let icon = Image::new(match gizmo.kind {
+                        GizmoKind::MaxTemp => egui::include_image!("../icons/flame.svg"),
+                        GizmoKind::MinTemp => egui::include_image!("../icons/snowflake.svg"),
+                        GizmoKind::TempAt { .. } => {
+                            egui::include_image!("../icons/crosshair_center.svg")
+                        }
+                        GizmoKind::Rect { .. } => egui::include_image!("../icons/square.svg"),
+                        GizmoKind::Ellipse { .. } => egui::include_image!("../icons/circle.svg"),
+                        GizmoKind::Line { .. } => egui::include_image!("../icons/slash.svg"),
+                        GizmoKind::Root { .. } => egui::include_image!("../icons/flame.svg"),
+                    });
+                    settings_changed |= color_icon_rgb(
+                        ui,
+                        ImageButton::new(icon.tint(gizmo.color)).frame(false),
+                        &mut gizmo.color,
+                        Alpha::Opaque,
+                    )
+                    .changed();
+
+                    settings_changed |= ui
+                        .add(TextEdit::singleline(&mut gizmo.name).desired_width(100.0))
+                        .changed();
+
+                    let result = gizmo_results.as_ref().and_then(|r| r.get(&gizmo.uuid));
+                    match (result, result.and_then(|r| r.stats)) {
+                        (_, Some(stats)) if stats.pixel_count > 1 => {
+                            ui.label(format!(
+                                "{} / {} / {}",
+                                format_reading(stats.min, temp_unit, temp_precision, is_delta),
+                                format_reading(stats.mean, temp_unit, temp_precision, is_delta),
+                                format_reading(stats.max, temp_unit, temp_precision, is_delta),
+                            ))
+                            .on_hover_text(format!(
+                                "Min / mean / max of {} pixels",
+                                stats.pixel_count
+                            ));
+                        }
+                        (Some(result), _) => {
+                            ui.label(format_reading(
+                                result.temperature,
+                                temp_unit,
+                                temp_precision,
+                                is_delta,
+                            ));
+                        }
+                        (None, _) => {
+                            ui.label(" - ");
+                        }
+                    }
+                });
+
+                if let Some(from) = row.response.dnd_release_payload::<usize>() {
+                    moved = Some((*from, index));
+                }
+            }
+        });
+
+        if let Some((from, to)) = moved {
+            if from != to && from < children.len() {
+                let gizmo = children.remove(from);
+                children.insert(to.min(children.len()), gizmo);
+                settings_changed = true;
+            }
+        }
+
+        if settings_changed {
+            app.edit_settings(move |settings| settings.gizmo = root_gizmo);
+        }
+    }
+}
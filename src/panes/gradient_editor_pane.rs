@@ -0,0 +1,269 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+use eframe::{
+    egui::{self, Button, ComboBox, DragValue, Grid, Image, ImageButton, TextEdit, Ui},
+    epaint::{TextureHandle, Vec2},
+};
+use uuid::Uuid;
+
+use crate::{
+    pane_dispatcher::{Pane, PaneContext, PaneKind},
+    thermal_gradient::{
+        palette_import::{import_palette, PALETTE_EXTENSIONS},
+        save_custom_gradients, ThermalGradient, ThermalGradientPoint,
+    },
+    AppGlobalState,
+};
+
+pub struct GradientEditorPane {
+    // Working copy of the gradient being edited, only stored on save
+    editing: Option<ThermalGradient>,
+    // Preview of the working copy, with the hash of the gradient it was created from
+    preview: Option<(u64, TextureHandle)>,
+}
+
+impl GradientEditorPane {
+    pub fn new() -> GradientEditorPane {
+        GradientEditorPane {
+            editing: None,
+            preview: None,
+        }
+    }
+
+    fn preview_texture(&mut self, ui: &Ui, gradient: &ThermalGradient) -> TextureHandle {
+        let mut sorted = gradient.clone();
+        sorted.sort_points();
+        let mut hasher = DefaultHasher::new();
+        sorted.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        match &self.preview {
+            Some((preview_hash, texture)) if *preview_hash == hash => texture.clone(),
+            _ => {
+                let texture = ui.ctx().load_texture(
+                    "gradient_editor_preview",
+                    sorted.create_demo_image(256, 32),
+                    Default::default(),
+                );
+                self.preview = Some((hash, texture.clone()));
+                texture
+            }
+        }
+    }
+
+    ///
+    /// Stores the working copy in the custom gradients, replacing an earlier version of it.
+    /// If the gradient is currently used for the image, the new version is applied right away.
+    ///
+    fn save(global_state: &mut AppGlobalState, mut gradient: ThermalGradient) {
+        gradient.sort_points();
+
+        match global_state
+            .custom_gradients
+            .iter_mut()
+            .find(|g| g.uuid == gradient.uuid)
+        {
+            Some(existing) => *existing = gradient.clone(),
+            None => global_state.custom_gradients.push(gradient.clone()),
+        }
+        if let Err(err) = save_custom_gradients(&global_state.custom_gradients) {
+            tracing::error!(error = %err, "Failed to save custom gradients");
+        }
+
+        if global_state.thermal_capturer_settings.gradient.uuid == gradient.uuid {
+            global_state.edit_settings(|settings| settings.gradient = gradient);
+        }
+    }
+
+    fn delete(global_state: &mut AppGlobalState, uuid: Uuid) {
+        global_state.custom_gradients.retain(|g| g.uuid != uuid);
+        if let Err(err) = save_custom_gradients(&global_state.custom_gradients) {
+            tracing::error!(error = %err, "Failed to save custom gradients");
+        }
+    }
+
+    fn stops_ui(ui: &mut Ui, gradient: &mut ThermalGradient) {
+        let mut stop_to_remove = None;
+        let can_remove = gradient.points.len() > 2;
+        Grid::new("gradient_editor_stops")
+            .num_columns(3)
+            .spacing([10.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                for (i, point) in gradient.points.iter_mut().enumerate() {
+                    ui.color_edit_button_srgba(&mut point.color);
+                    ui.add(
+                        DragValue::new(&mut point.pos)
+                            .speed(0.005)
+                            .max_decimals(3)
+                            .clamp_range(0.0..=1.0),
+                    );
+                    if ui
+                        .add_enabled(
+                            can_remove,
+                            ImageButton::new(
+                                Image::new(egui::include_image!("../icons/trash.svg"))
+                                    .tint(ui.style().visuals.widgets.active.fg_stroke.color),
+                            )
+                            .frame(false),
+                        )
+                        .on_hover_text("Remove stop")
+                        .clicked()
+                    {
+                        stop_to_remove = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(i) = stop_to_remove {
+            gradient.points.remove(i);
+        }
+
+        if ui
+            .button("Add Stop")
+            .on_hover_text("Add a stop in the middle of the widest gap")
+            .clicked()
+        {
+            gradient.sort_points();
+            let (pos, _) = gradient
+                .points
+                .windows(2)
+                .map(|w| ((w[0].pos + w[1].pos) / 2.0, w[1].pos - w[0].pos))
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .unwrap_or((0.5, 0.0));
+            let color = gradient.get_color(pos);
+            gradient.points.push(ThermalGradientPoint {
+                color,
+                pos: (pos * 1000.0).round() / 1000.0,
+            });
+            gradient.sort_points();
+        }
+    }
+}
+
+impl Pane for GradientEditorPane {
+    fn title(&self) -> egui::WidgetText {
+        "Gradients".into()
+    }
+
+    fn kind(&self) -> Option<PaneKind> {
+        Some(PaneKind::GradientEditor)
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, app: &mut PaneContext<'_>) {
+        let global_state = app.global_state;
+
+        ui.horizontal(|ui| {
+            if ui.button("New").clicked() {
+                self.editing = Some(ThermalGradient::new(
+                    Uuid::new_v4(),
+                    "Custom gradient".to_string(),
+                    vec![
+                        ThermalGradientPoint::from_rgbv(0, 0, 0, 0.0),
+                        ThermalGradientPoint::from_rgbv(255, 255, 255, 1.0),
+                    ],
+                ));
+            }
+            if ui
+                .button("Copy Current")
+                .on_hover_text("Start a new gradient from the one used for the image")
+                .clicked()
+            {
+                let current = &global_state.thermal_capturer_settings.gradient;
+                self.editing = Some(ThermalGradient::new(
+                    Uuid::new_v4(),
+                    format!("{} (copy)", current.name),
+                    current.points.clone(),
+                ));
+            }
+            if ui
+                .button("Import…")
+                .on_hover_text("Import a GMT (.cpt), JASC (.pal) or FLIR (.pal) palette")
+                .clicked()
+            {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("Palettes", &PALETTE_EXTENSIONS)
+                    .pick_file()
+                {
+                    match import_palette(&path) {
+                        Ok(gradient) => {
+                            let saved = gradient.clone();
+                            app.update(move |global_state| Self::save(global_state, saved));
+                            self.editing = Some(gradient);
+                        }
+                        Err(err) => {
+                            tracing::error!(
+                                error = %err,
+                                path = %path.display(),
+                                "Failed to import palette"
+                            )
+                        }
+                    }
+                }
+            }
+
+            ComboBox::from_id_source("gradient_editor_edit")
+                .selected_text("Edit")
+                .show_ui(ui, |ui| {
+                    for gradient in global_state.custom_gradients.iter() {
+                        if ui.selectable_label(false, &gradient.name).clicked() {
+                            self.editing = Some(gradient.clone());
+                        }
+                    }
+                });
+        });
+
+        ui.separator();
+
+        let Some(mut gradient) = self.editing.take() else {
+            ui.label("Create a new gradient or pick a custom gradient to edit.");
+            return;
+        };
+
+        let is_saved = global_state
+            .custom_gradients
+            .iter()
+            .any(|g| g.uuid == gradient.uuid);
+        let mut keep_editing = true;
+
+        ui.horizontal(|ui| {
+            ui.label("Name");
+            ui.add(TextEdit::singleline(&mut gradient.name).desired_width(150.0));
+        });
+
+        let preview = self.preview_texture(ui, &gradient);
+        ui.add(Image::new(&preview).fit_to_exact_size(Vec2::new(256.0, 24.0)));
+
+        Self::stops_ui(ui, &mut gradient);
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(!gradient.name.trim().is_empty(), Button::new("Save"))
+                .clicked()
+            {
+                let saved = gradient.clone();
+                app.update(move |global_state| Self::save(global_state, saved));
+            }
+            if ui
+                .add_enabled(is_saved, Button::new("Delete"))
+                .on_hover_text("Remove the gradient from the custom gradients")
+                .clicked()
+            {
+                let uuid = gradient.uuid;
+                app.update(move |global_state| Self::delete(global_state, uuid));
+                keep_editing = false;
+            }
+            if ui.button("Close").clicked() {
+                keep_editing = false;
+            }
+        });
+
+        if keep_editing {
+            self.editing = Some(gradient);
+        }
+    }
+}
@@ -1,17 +1,40 @@
-use std::{cell::RefCell, rc::Rc};
+use eframe::{
+    egui::{self, CursorIcon, DragValue},
+    emath::Vec2b,
+    epaint::{Color32, Stroke},
+};
+use egui_plot::{Bar, BarChart, Legend, Line, Plot, VLine};
 
-use eframe::{egui, emath::Vec2b, epaint::Color32};
-use egui_plot::{Bar, BarChart, Plot, VLine};
+use crate::{
+    pane_dispatcher::{Pane, PaneContext, PaneKind},
+    temperature::{reading_to_unit, Temp, TempRange, TemperatureUnit},
+    thermal_data::{ThermalDataHistogramPoint, MAX_HISTOGRAM_BUCKETS, MIN_HISTOGRAM_BUCKETS},
+};
 
-use crate::{pane_dispatcher::Pane, temperature::TemperatureUnit, AppGlobalState};
+// How close to a handle the pointer grabs it, in points
+const HANDLE_GRAB_DISTANCE: f32 = 8.0;
+
+///
+/// An end of the mapping range, which can be dragged on the histogram.
+///
+#[derive(Clone, Copy, PartialEq)]
+enum RangeHandle {
+    Min,
+    Max,
+}
 
 pub struct HistogramPane {
-    global_state: Rc<RefCell<AppGlobalState>>,
+    // Handle under the pointer in the last frame, the plot isn't panned while one is
+    hovered_handle: Option<RangeHandle>,
+    dragged_handle: Option<RangeHandle>,
 }
 
 impl HistogramPane {
-    pub fn new(global_state: Rc<RefCell<AppGlobalState>>) -> HistogramPane {
-        HistogramPane { global_state }
+    pub fn new() -> HistogramPane {
+        HistogramPane {
+            hovered_handle: None,
+            dragged_handle: None,
+        }
     }
 }
 
@@ -20,9 +43,68 @@ impl Pane for HistogramPane {
         "Histogram".into()
     }
 
-    fn ui(&mut self, ui: &mut egui::Ui) {
-        let global_state_clone = self.global_state.clone();
-        let global_state = global_state_clone.as_ref().borrow_mut();
+    fn kind(&self) -> Option<PaneKind> {
+        Some(PaneKind::Histogram)
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, app: &mut PaneContext<'_>) {
+        let global_state = app.global_state;
+
+        ui.horizontal(|ui| {
+            if global_state
+                .thermal_capturer_settings
+                .histogram_region
+                .is_some()
+            {
+                if ui.button("Clear region").clicked() {
+                    app.edit_settings(|settings| settings.histogram_region = None);
+                }
+            } else {
+                ui.weak("Shift + drag on the image to show the histogram of a region");
+            }
+
+            ui.separator();
+            let mut histogram_buckets = global_state.thermal_capturer_settings.histogram_buckets;
+            ui.label("Bins");
+            if ui
+                .add(
+                    DragValue::new(&mut histogram_buckets)
+                        .clamp_range(MIN_HISTOGRAM_BUCKETS..=MAX_HISTOGRAM_BUCKETS),
+                )
+                .changed()
+            {
+                app.edit_settings(move |settings| settings.histogram_buckets = histogram_buckets);
+            }
+            let mut histogram_log_scale =
+                global_state.thermal_capturer_settings.histogram_log_scale;
+            if ui
+                .checkbox(&mut histogram_log_scale, "Log scale")
+                .on_hover_text(
+                    "Show pixel counts on a logarithmic scale, to see small hot or cold areas",
+                )
+                .changed()
+            {
+                app.edit_settings(move |settings| {
+                    settings.histogram_log_scale = histogram_log_scale
+                });
+            }
+
+            if let Some((stats, is_delta)) = global_state
+                .last_thermal_capturer_result
+                .as_ref()
+                .map(|r| (&r.stats, r.is_delta))
+            {
+                let temp_format = global_state.temp_format();
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    ui.label(format!(
+                        "Mean: {}  Median: {}  P95: {}",
+                        temp_format.format_reading(stats.mean, is_delta),
+                        temp_format.format_reading(stats.median, is_delta),
+                        temp_format.format_reading(stats.p95, is_delta),
+                    ));
+                });
+            }
+        });
 
         let default_vec = vec![];
         let temperature_points = global_state
@@ -37,72 +119,239 @@ impl Pane for HistogramPane {
             .map(|r| r.image_range)
             .unwrap_or_else(|| global_state.thermal_capturer_settings.manual_range);
 
+        let is_delta = global_state
+            .last_thermal_capturer_result
+            .as_ref()
+            .is_some_and(|r| r.is_delta);
+        // differences are always mapped on a range centered at zero
+        let handles_enabled = !is_delta && !color_mapping_range.is_default();
+        // while dragging, the lines follow the pointer rather than the range of the last frame
+        let handle_range = if global_state.thermal_capturer_settings.auto_range {
+            color_mapping_range
+        } else {
+            global_state.thermal_capturer_settings.manual_range
+        };
+        let unit = global_state.preferred_temperature_unit();
+        let equalization = global_state
+            .last_thermal_capturer_result
+            .as_ref()
+            .and_then(|res| res.equalization.clone());
+
         let mut bucket_width = 1.0;
         if temperature_points.len() > 1 {
             bucket_width = (temperature_points[1].temperature - temperature_points[0].temperature)
                 .to_unit(TemperatureUnit::Kelvin) as f64;
         }
 
+        let log_scale = global_state.thermal_capturer_settings.histogram_log_scale;
+        // height of a bar: % of the image, or log10 of the pixel count
+        let bar_height = move |p: &ThermalDataHistogramPoint| {
+            if log_scale {
+                (p.count as f64 + 1.0).log10()
+            } else {
+                p.factor as f64 * 100.0
+            }
+        };
+        // the gradient is shown as a strip below the bars
+        let gradient_strip_height = if log_scale { 0.15 } else { 1.5 };
+
+        let gradient_strip = BarChart::new(
+            temperature_points
+                .iter()
+                .map(|p| {
+                    Bar::new(
+                        reading_to_unit(p.temperature, unit, is_delta) as f64,
+                        -gradient_strip_height,
+                    )
+                    .width(bucket_width)
+                    .stroke(Stroke::NONE)
+                    .fill(
+                        global_state.thermal_capturer_settings.value_to_color(
+                            p.temperature,
+                            color_mapping_range,
+                            equalization.as_deref(),
+                            is_delta,
+                        ),
+                    )
+                })
+                .collect(),
+        );
+
         let chart = BarChart::new(
             temperature_points
                 .iter()
                 .map(|p| {
                     Bar::new(
-                        p.temperature
-                            .to_unit(global_state.preferred_temperature_unit())
-                            as f64,
-                        p.factor as f64 * 100.0,
+                        reading_to_unit(p.temperature, unit, is_delta) as f64,
+                        bar_height(p),
                     )
                     .width(bucket_width)
                     .fill(
-                        global_state.thermal_capturer_settings.temp_to_color(
+                        global_state.thermal_capturer_settings.value_to_color(
                             p.temperature,
-                            global_state
-                                .last_thermal_capturer_result
-                                .as_ref()
-                                .map(|res| res.image_range),
+                            color_mapping_range,
+                            equalization.as_deref(),
+                            is_delta,
                         ),
                     )
                 })
                 .collect(),
         );
-        let unit_suffix = global_state.preferred_temperature_unit().suffix();
+        let region_line = global_state
+            .last_thermal_capturer_result
+            .as_ref()
+            .and_then(|r| r.region_histogram.as_ref())
+            .map(|region_histogram| {
+                Line::new(
+                    region_histogram
+                        .points
+                        .iter()
+                        .map(|p| {
+                            [
+                                reading_to_unit(p.temperature, unit, is_delta) as f64,
+                                bar_height(p),
+                            ]
+                        })
+                        .collect::<Vec<_>>(),
+                )
+                .color(Color32::WHITE)
+                .name("Region")
+            });
+        let temp_format = global_state.temp_format();
+        let unit_suffix = temp_format.suffix();
 
-        Plot::new("Temperature distribution plot")
+        let plot_response = Plot::new("Temperature distribution plot")
             .auto_bounds(Vec2b::TRUE)
-            .y_axis_label("% of image")
+            .y_axis_label(if log_scale { "Pixels" } else { "% of image" })
             .x_axis_label(format!(
-                "Temperature ({})",
-                global_state.preferred_temperature_unit().suffix()
+                "{} ({})",
+                if is_delta {
+                    "Difference"
+                } else {
+                    "Temperature"
+                },
+                temp_format.suffix()
             ))
-            .include_y(0.0)
-            .include_y(30.0)
-            .y_axis_formatter(|grid_mark, _max_chars, _range| format!("{:.0}%", grid_mark.value))
+            .include_y(-gradient_strip_height)
+            .include_y(if log_scale { 1.0 } else { 30.0 })
+            .y_axis_formatter(move |grid_mark, _max_chars, _range| {
+                if grid_mark.value < 0.0 {
+                    String::new()
+                } else if log_scale {
+                    format!("{:.0}", 10f64.powf(grid_mark.value))
+                } else {
+                    format!("{:.0}%", grid_mark.value)
+                }
+            })
             .x_axis_formatter(move |grid_mark, _max_chars, _range| {
                 format!("{:.0} {}", grid_mark.value, unit_suffix)
             })
+            .label_formatter(move |_name, p| {
+                if p.y < 0.0 {
+                    // the gradient strip
+                    format!("{} {}", temp_format.format_value(p.x), temp_format.suffix())
+                } else if log_scale {
+                    format!(
+                        "{} {}\n{:.0} pixels",
+                        temp_format.format_value(p.x),
+                        temp_format.suffix(),
+                        10f64.powf(p.y)
+                    )
+                } else {
+                    format!(
+                        "{} {}\n{:.1}%",
+                        temp_format.format_value(p.x),
+                        temp_format.suffix(),
+                        p.y
+                    )
+                }
+            })
+            .legend(Legend::default())
+            .allow_drag(self.hovered_handle.is_none() && self.dragged_handle.is_none())
             .show(ui, |plot_ui| {
+                plot_ui.bar_chart(gradient_strip);
                 plot_ui.bar_chart(chart);
-                if !color_mapping_range.is_default() {
-                    plot_ui.vline(
-                        VLine::new(
-                            color_mapping_range
-                                .min
-                                .to_unit(global_state.preferred_temperature_unit())
-                                as f64,
-                        )
-                        .color(Color32::GRAY),
-                    );
+                if let Some(region_line) = region_line {
+                    plot_ui.line(region_line);
+                }
+                if color_mapping_range.is_default() {
+                    return None;
+                }
+
+                let handle_x = |handle| {
+                    let temp = match handle {
+                        RangeHandle::Min => handle_range.min,
+                        RangeHandle::Max => handle_range.max,
+                    };
+                    reading_to_unit(temp, unit, is_delta) as f64
+                };
+                let grab_distance = (HANDLE_GRAB_DISTANCE
+                    / plot_ui.transform().dpos_dvalue_x().abs() as f32)
+                    as f64;
+                self.hovered_handle = plot_ui
+                    .pointer_coordinate()
+                    .filter(|_| handles_enabled)
+                    .and_then(|pointer| {
+                        [RangeHandle::Min, RangeHandle::Max]
+                            .into_iter()
+                            .map(|handle| (handle, (handle_x(handle) - pointer.x).abs()))
+                            .filter(|(_, distance)| *distance < grab_distance)
+                            .min_by(|a, b| a.1.total_cmp(&b.1))
+                            .map(|(handle, _)| handle)
+                    });
+
+                let response = plot_ui.response();
+                if response.drag_started() {
+                    self.dragged_handle = self.hovered_handle;
+                } else if !response.dragged() {
+                    self.dragged_handle = None;
+                }
+                if self.hovered_handle.is_some() || self.dragged_handle.is_some() {
+                    plot_ui
+                        .ctx()
+                        .output_mut(|out| out.cursor_icon = CursorIcon::ResizeHorizontal);
+                }
+
+                for handle in [RangeHandle::Min, RangeHandle::Max] {
+                    let active = handles_enabled
+                        && (self.dragged_handle == Some(handle)
+                            || (self.dragged_handle.is_none()
+                                && self.hovered_handle == Some(handle)));
                     plot_ui.vline(
-                        VLine::new(
-                            color_mapping_range
-                                .max
-                                .to_unit(global_state.preferred_temperature_unit())
-                                as f64,
-                        )
-                        .color(Color32::GRAY),
+                        VLine::new(handle_x(handle))
+                            .color(if active {
+                                Color32::WHITE
+                            } else {
+                                Color32::GRAY
+                            })
+                            .width(if active { 2.0 } else { 1.0 }),
                     );
                 }
+
+                // the dragged end follows the pointer, without crossing the other one
+                let handle = self.dragged_handle?;
+                let temp = Temp::from_unit(unit, plot_ui.pointer_coordinate()?.x as f32);
+                Some(match handle {
+                    RangeHandle::Min => {
+                        TempRange::new(temp.min(handle_range.max), handle_range.max)
+                    }
+                    RangeHandle::Max => {
+                        TempRange::new(handle_range.min, temp.max(handle_range.min))
+                    }
+                })
             });
+
+        if let Some(range) = plot_response.inner {
+            let settings = &global_state.thermal_capturer_settings;
+            if settings.auto_range
+                || settings.manual_range.min != range.min
+                || settings.manual_range.max != range.max
+            {
+                app.edit_settings(move |settings| {
+                    settings.auto_range = false;
+                    settings.manual_range = range;
+                });
+            }
+        }
     }
 }
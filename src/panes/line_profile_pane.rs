@@ -0,0 +1,125 @@
+use eframe::{egui, emath::Vec2b};
+use egui_plot::{Line, Plot};
+use uuid::Uuid;
+
+use crate::{
+    gizmos::GizmoKind,
+    pane_dispatcher::{Pane, PaneContext, PaneKind},
+};
+
+pub struct LineProfilePane {
+    // Line gizmo whose profile is shown. Falls back to the first line if unset or removed.
+    selected_line: Option<Uuid>,
+}
+
+impl LineProfilePane {
+    pub fn new() -> LineProfilePane {
+        LineProfilePane {
+            selected_line: None,
+        }
+    }
+}
+
+impl Pane for LineProfilePane {
+    fn title(&self) -> egui::WidgetText {
+        "Line Profile".into()
+    }
+
+    fn kind(&self) -> Option<PaneKind> {
+        Some(PaneKind::LineProfile)
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, app: &mut PaneContext<'_>) {
+        let global_state = app.global_state;
+
+        let lines: Vec<_> = global_state
+            .thermal_capturer_settings
+            .gizmo
+            .children()
+            .unwrap()
+            .iter()
+            .filter_map(|g| match g.kind {
+                GizmoKind::Line { start, end } => {
+                    Some((g.uuid, g.name.clone(), g.color, start, end))
+                }
+                _ => None,
+            })
+            .collect();
+
+        if lines.is_empty() {
+            ui.weak("Select the line tool and drag on the image to draw a line");
+            return;
+        }
+
+        let selected = lines
+            .iter()
+            .find(|line| Some(line.0) == self.selected_line)
+            .unwrap_or(&lines[0]);
+        let (uuid, name, color, start, end) = selected.clone();
+
+        egui::ComboBox::from_id_source("line_profile_pane_line")
+            .selected_text(name.as_str())
+            .show_ui(ui, |ui| {
+                for line in lines.iter() {
+                    ui.selectable_value(&mut self.selected_line, Some(line.0), line.1.as_str());
+                }
+            });
+
+        let temp_format = global_state.temp_format();
+        let unit = temp_format.unit;
+        let points: Vec<[f64; 2]> = global_state
+            .last_thermal_capturer_result
+            .as_ref()
+            .map(|res| {
+                let start = start.clamped(res.thermal_data.width, res.thermal_data.height);
+                let end = end.clamped(res.thermal_data.width, res.thermal_data.height);
+                let length = ((end.x as f32 - start.x as f32).powi(2)
+                    + (end.y as f32 - start.y as f32).powi(2))
+                .sqrt();
+                // one sample per pixel of length
+                let count = length.ceil() as usize + 1;
+                res.thermal_data
+                    .sample_segment(
+                        [start.x as f32, start.y as f32],
+                        [end.x as f32, end.y as f32],
+                        count,
+                    )
+                    .iter()
+                    .enumerate()
+                    .map(|(i, temp)| {
+                        [
+                            length as f64 * i as f64 / (count - 1).max(1) as f64,
+                            temp.to_unit(unit) as f64,
+                        ]
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let unit_suffix = unit.suffix();
+        Plot::new("line_profile_plot")
+            .auto_bounds(Vec2b::TRUE)
+            .allow_scroll(false)
+            .allow_zoom(false)
+            .allow_drag(false)
+            .allow_boxed_zoom(false)
+            .x_axis_label("Distance (px)")
+            .y_axis_label(format!("Temperature ({})", unit_suffix))
+            .y_axis_formatter(move |grid_mark, _max_chars, _range| {
+                format!("{:.0} {}", grid_mark.value, unit_suffix)
+            })
+            .label_formatter(move |_name, p| {
+                format!(
+                    "{:.0} px\n{} {}",
+                    p.x,
+                    temp_format.format_value(p.y),
+                    temp_format.suffix()
+                )
+            })
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new(points).color(color).name(name.clone()));
+            });
+
+        self.selected_line = Some(uuid);
+    }
+}
@@ -0,0 +1,138 @@
+use eframe::{
+    egui::{self, ComboBox, RichText, ScrollArea, TextEdit, TextStyle},
+    epaint::Color32,
+};
+use log::Level;
+
+use crate::{
+    app_log::{
+        clear_log_entries, log_file_path, recent_log_entries, start_log_file, stop_log_file,
+        LogEntry,
+    },
+    pane_dispatcher::{Pane, PaneContext, PaneKind},
+};
+
+///
+/// The recent messages of the app, so problems can be looked into without running it
+/// from a terminal, and sent along with bug reports.
+///
+pub struct LogPane {
+    min_level: Level,
+    // Only messages containing it are shown, case insensitive
+    filter: String,
+    // Why the log file couldn't be opened
+    file_error: Option<String>,
+}
+
+impl Default for LogPane {
+    fn default() -> Self {
+        Self {
+            min_level: Level::Info,
+            filter: String::new(),
+            file_error: None,
+        }
+    }
+}
+
+fn level_color(level: Level) -> Color32 {
+    match level {
+        Level::Error => Color32::RED,
+        Level::Warn => Color32::YELLOW,
+        Level::Info => Color32::LIGHT_GRAY,
+        Level::Debug | Level::Trace => Color32::GRAY,
+    }
+}
+
+impl Pane for LogPane {
+    fn title(&self) -> egui::WidgetText {
+        "Log".into()
+    }
+
+    fn kind(&self) -> Option<PaneKind> {
+        Some(PaneKind::Log)
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, _app: &mut PaneContext<'_>) {
+        let filter = self.filter.to_lowercase();
+        let entries: Vec<LogEntry> = recent_log_entries(self.min_level)
+            .into_iter()
+            .filter(|entry| {
+                filter.is_empty()
+                    || entry.message.to_lowercase().contains(&filter)
+                    || entry.target.to_lowercase().contains(&filter)
+                    || entry.spans.to_lowercase().contains(&filter)
+            })
+            .collect();
+
+        ui.horizontal(|ui| {
+            ComboBox::from_id_source("log_pane_level")
+                .selected_text(self.min_level.as_str())
+                .show_ui(ui, |ui| {
+                    for level in [Level::Error, Level::Warn, Level::Info, Level::Debug] {
+                        ui.selectable_value(&mut self.min_level, level, level.as_str());
+                    }
+                });
+            ui.add(
+                TextEdit::singleline(&mut self.filter)
+                    .hint_text("Filter")
+                    .desired_width(160.0),
+            );
+            if ui.button("Clear").clicked() {
+                clear_log_entries();
+            }
+            if ui
+                .button("Copy")
+                .on_hover_text("Copy the shown messages, e.g. for a bug report")
+                .clicked()
+            {
+                let text = entries
+                    .iter()
+                    .map(|entry| entry.to_line())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                ui.output_mut(|out| out.copied_text = text);
+            }
+            ui.separator();
+            match log_file_path() {
+                Some(path) => {
+                    ui.label(format!("Writing to {}", path.display()));
+                    if ui.button("Stop").clicked() {
+                        stop_log_file();
+                    }
+                }
+                None => {
+                    if ui.button("Write to file...").clicked() {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .set_file_name("thermal-cat.log")
+                            .add_filter("Log", &["log", "txt"])
+                            .save_file()
+                        {
+                            self.file_error = start_log_file(&path).err().map(|err| {
+                                tracing::error!(error = %err, "Failed to open the log file");
+                                err.to_string()
+                            });
+                        }
+                    }
+                }
+            }
+            if let Some(err) = self.file_error.as_ref() {
+                ui.colored_label(Color32::RED, err);
+            }
+        });
+        ui.separator();
+
+        let row_height = ui.text_style_height(&TextStyle::Monospace);
+        ScrollArea::both()
+            .auto_shrink([false, false])
+            .stick_to_bottom(true)
+            .show_rows(ui, row_height, entries.len(), |ui, rows| {
+                for entry in &entries[rows] {
+                    ui.label(
+                        RichText::new(entry.to_line())
+                            .monospace()
+                            .color(level_color(entry.level)),
+                    );
+                }
+            });
+    }
+}
@@ -1,23 +1,96 @@
-use std::{cell::RefCell, rc::Rc};
-
 use eframe::{
     egui::{
         self,
         color_picker::{color_picker_color32, Alpha},
-        Area, Frame, Grid, Image, ImageButton, Key, Order, Response, TextEdit, Ui, Widget,
+        Area, Button, ComboBox, DragValue, Frame, Grid, Image, ImageButton, Key, Order, Response,
+        TextEdit, Ui, Widget,
     },
     epaint::Color32,
 };
 
-use crate::{gizmos::GizmoKind, pane_dispatcher::Pane, AppGlobalState};
+use crate::{
+    gizmos::{GizmoKind, GizmoLayout},
+    pane_dispatcher::{Pane, PaneContext, PaneKind},
+    temperature::{format_reading, TempDelta},
+    temperature_correction::MIN_EMISSIVITY,
+    thermal_data::{ThermalDataPos, ThermalDataRect},
+};
 
 pub struct MeasurementsPane {
-    global_state: Rc<RefCell<AppGlobalState>>,
+    layout_name: String,
+    saved_layouts: Vec<String>,
 }
 
 impl MeasurementsPane {
-    pub fn new(global_state: Rc<RefCell<AppGlobalState>>) -> MeasurementsPane {
-        MeasurementsPane { global_state }
+    pub fn new() -> MeasurementsPane {
+        MeasurementsPane {
+            layout_name: String::new(),
+            saved_layouts: Self::list_saved_layouts(),
+        }
+    }
+
+    fn list_saved_layouts() -> Vec<String> {
+        GizmoLayout::list_saved()
+            .inspect_err(|err| tracing::error!(error = %err, "Failed to list gizmo layouts"))
+            .unwrap_or_default()
+    }
+
+    fn layouts_ui(&mut self, ui: &mut Ui, app: &mut PaneContext<'_>) {
+        let global_state = app.global_state;
+        ui.horizontal(|ui| {
+            ui.add(
+                TextEdit::singleline(&mut self.layout_name)
+                    .hint_text("Layout name")
+                    .desired_width(100.0),
+            );
+            if ui
+                .add_enabled(
+                    !self.layout_name.trim().is_empty(),
+                    Button::new("Save Layout"),
+                )
+                .clicked()
+            {
+                let layout = GizmoLayout {
+                    root: global_state.thermal_capturer_settings.gizmo.clone(),
+                };
+                if let Err(err) = layout.save(self.layout_name.trim()) {
+                    tracing::error!(error = %err, "Failed to save gizmo layout");
+                }
+                self.saved_layouts = Self::list_saved_layouts();
+            }
+
+            let mut layout_to_load = None;
+            let combo_response = ComboBox::from_id_source("measurements_pane_load_layout")
+                .selected_text("Load Layout")
+                .show_ui(ui, |ui| {
+                    for name in self.saved_layouts.iter() {
+                        if ui.selectable_label(false, name).clicked() {
+                            layout_to_load = Some(name.clone());
+                        }
+                    }
+                });
+            if combo_response.response.clicked() {
+                self.saved_layouts = Self::list_saved_layouts();
+            }
+
+            if let Some(name) = layout_to_load {
+                match GizmoLayout::load(&name) {
+                    Ok(mut layout) => {
+                        // clamp rather than reject gizmos placed outside of the current image
+                        if let Some(res) = global_state.last_thermal_capturer_result.as_ref() {
+                            layout
+                                .root
+                                .clamp_to_bounds(res.thermal_data.width, res.thermal_data.height);
+                        }
+                        app.edit_settings(move |settings| settings.gizmo = layout.root);
+                        self.layout_name = name;
+                    }
+                    Err(err) => {
+                        tracing::error!(error = %err, %name, "Failed to load gizmo layout")
+                    }
+                }
+            }
+        });
     }
 }
 
@@ -26,33 +99,71 @@ impl Pane for MeasurementsPane {
         "Measurements".into()
     }
 
-    fn ui(&mut self, ui: &mut egui::Ui) {
-        let global_state_clone = self.global_state.clone();
-        let mut global_state = global_state_clone.as_ref().borrow_mut();
+    fn kind(&self) -> Option<PaneKind> {
+        Some(PaneKind::Measurements)
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, app: &mut PaneContext<'_>) {
+        let global_state = app.global_state;
+
+        self.layouts_ui(ui, app);
+        ui.separator();
+
+        let mut root_gizmo = global_state.thermal_capturer_settings.gizmo.clone();
+        let mut settings_changed = false;
+
+        if ui
+            .button("Add Region")
+            .on_hover_text("Adds a rectangular region, which can have its own emissivity. Covers the histogram region if one is selected.")
+            .clicked()
+        {
+            if let Some(res) = global_state.last_thermal_capturer_result.as_ref() {
+                let (width, height) = (res.thermal_data.width, res.thermal_data.height);
+                let rect = global_state
+                    .thermal_capturer_settings
+                    .histogram_region
+                    .unwrap_or(ThermalDataRect::from_corners(
+                        ThermalDataPos::new(width / 4, height / 4),
+                        ThermalDataPos::new(width * 3 / 4, height * 3 / 4),
+                    ));
+                root_gizmo.push_child(
+                    GizmoKind::Rect {
+                        rect,
+                        emissivity: None,
+                    },
+                    "Region".to_string(),
+                );
+                settings_changed = true;
+            }
+        }
 
         Grid::new("measurements_pane_grid")
             .striped(true)
-            .num_columns(5)
+            .num_columns(7)
             .min_col_width(40.0)
             .show(ui, |ui| {
                 ui.label("");
                 ui.label("Value");
-                ui.label("Value");
+                ui.label("Mean");
+                ui.label("Name");
                 ui.end_row();
 
                 let gizmo_results = global_state
                     .last_thermal_capturer_result
                     .as_ref()
-                    .map(|r| r.gizmo_results.clone())
+                    .map(|r| r.extension.gizmo_results.clone())
                     .clone();
+                let is_delta = global_state
+                    .last_thermal_capturer_result
+                    .as_ref()
+                    .is_some_and(|r| r.is_delta);
 
                 let temp_unit = global_state.preferred_temperature_unit();
+                let temp_precision = global_state.preferred_temperature_precision();
 
                 let mut gizmo_uuid_to_remove = Option::None;
 
-                global_state
-                    .thermal_capturer_settings
-                    .gizmo
+                root_gizmo
                     .children_mut()
                     .unwrap()
                     .iter_mut()
@@ -63,34 +174,94 @@ impl Pane for MeasurementsPane {
                             GizmoKind::TempAt { pos: _ } => {
                                 egui::include_image!("../icons/crosshair_center.svg")
                             }
+                            GizmoKind::Rect { .. } => egui::include_image!("../icons/square.svg"),
+                            GizmoKind::Ellipse { .. } => {
+                                egui::include_image!("../icons/circle.svg")
+                            }
+                            GizmoKind::Line { .. } => egui::include_image!("../icons/slash.svg"),
                             _ => egui::include_image!("../icons/flame.svg"),
                         });
 
-                        color_icon_rgb(
+                        settings_changed |= color_icon_rgb(
                             ui,
                             ImageButton::new(icon.tint(gizmo.color)).frame(false),
                             &mut gizmo.color,
                             Alpha::Opaque,
-                        );
+                        )
+                        .changed();
 
+                        let result = gizmo_results.as_ref().and_then(|gr| gr.get(&gizmo.uuid));
                         ui.label(
-                            gizmo_results
-                                .as_ref()
-                                .and_then(|gr| gr.get(&gizmo.uuid))
+                            result
                                 .map(|r| {
-                                    format!(
-                                        "{:.1} {}",
-                                        r.temperature.to_unit(temp_unit),
-                                        temp_unit.suffix()
+                                    format_reading(
+                                        r.temperature,
+                                        temp_unit,
+                                        temp_precision,
+                                        is_delta,
                                     )
                                 })
                                 .unwrap_or(" - ".to_string()),
                         );
 
-                        ui.add_sized(
-                            [100.0, 20.0],
-                            TextEdit::singleline(&mut gizmo.name).desired_width(100.0),
-                        );
+                        match result.and_then(|r| r.stats) {
+                            // a single pixel has no spread worth showing
+                            Some(stats) if stats.pixel_count > 1 => {
+                                ui.label(format!(
+                                    "{} ± {}",
+                                    format_reading(stats.mean, temp_unit, temp_precision, is_delta),
+                                    TempDelta::new(stats.stddev_kelvin)
+                                        .format(temp_unit, temp_precision)
+                                ))
+                                .on_hover_text(format!(
+                                    "Min: {}\nMax: {}\nMean: {}\nStd. dev.: {}\nPixels: {}",
+                                    format_reading(stats.min, temp_unit, temp_precision, is_delta),
+                                    format_reading(stats.max, temp_unit, temp_precision, is_delta),
+                                    format_reading(stats.mean, temp_unit, temp_precision, is_delta),
+                                    TempDelta::new(stats.stddev_kelvin)
+                                        .format(temp_unit, temp_precision),
+                                    stats.pixel_count
+                                ));
+                            }
+                            _ => {
+                                ui.label("");
+                            }
+                        }
+
+                        settings_changed |= ui
+                            .add_sized(
+                                [100.0, 20.0],
+                                TextEdit::singleline(&mut gizmo.name).desired_width(100.0),
+                            )
+                            .changed();
+
+                        if let GizmoKind::Rect { emissivity, .. } = &mut gizmo.kind {
+                            ui.horizontal(|ui| {
+                                let mut has_override = emissivity.is_some();
+                                if ui
+                                    .checkbox(&mut has_override, "ε")
+                                    .on_hover_text(
+                                        "Use a different emissivity inside of this region",
+                                    )
+                                    .changed()
+                                {
+                                    *emissivity = has_override.then_some(1.0);
+                                    settings_changed = true;
+                                }
+                                if let Some(emissivity) = emissivity.as_mut() {
+                                    settings_changed |= ui
+                                        .add(
+                                            DragValue::new(emissivity)
+                                                .speed(0.01)
+                                                .max_decimals(2)
+                                                .clamp_range(MIN_EMISSIVITY..=1.0),
+                                        )
+                                        .changed();
+                                }
+                            });
+                        } else {
+                            ui.label("");
+                        }
 
                         match gizmo.kind {
                             GizmoKind::MaxTemp => {
@@ -138,20 +309,24 @@ impl Pane for MeasurementsPane {
                             .clicked()
                         {
                             gizmo.show_temperature_label = !gizmo.show_temperature_label;
+                            settings_changed = true;
                         }
 
                         ui.end_row();
                     });
 
                 gizmo_uuid_to_remove.inspect(|uuid| {
-                    global_state
-                        .thermal_capturer_settings
-                        .gizmo
+                    root_gizmo
                         .children_mut()
                         .unwrap()
                         .retain(|gizmo| gizmo.uuid != *uuid);
+                    settings_changed = true;
                 });
             });
+
+        if settings_changed {
+            app.edit_settings(move |settings| settings.gizmo = root_gizmo);
+        }
     }
 }
 
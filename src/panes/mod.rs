@@ -1,7 +1,17 @@
+pub mod alarms_pane;
+pub mod camera_pane;
+pub mod camera_settings_pane;
 pub mod capture_pane;
 pub mod gallery_pane;
+pub mod gizmo_list_pane;
+pub mod gradient_editor_pane;
 pub mod histogram_pane;
+pub mod line_profile_pane;
+pub mod log_pane;
 pub mod measurements_pane;
+pub mod report_pane;
 pub mod setup_pane;
+pub mod status_bar_pane;
+pub mod temporal_plot_pane;
 pub mod thermal_display_pane;
 pub mod user_preferences_pane;
@@ -0,0 +1,213 @@
+use std::path::PathBuf;
+
+use eframe::egui::{self, Button, ComboBox, Grid, Image, ScrollArea, TextEdit, Ui, Vec2};
+
+use crate::{
+    pane_dispatcher::{Pane, PaneContext, PaneKind},
+    report::{InspectionReport, ReportItem},
+    types::media_formats::ImageFormat,
+};
+use strum::IntoEnumIterator;
+
+pub struct ReportPane {
+    report: InspectionReport,
+    // Page written by the last export, or why it failed
+    export_result: Option<Result<PathBuf, String>>,
+}
+
+// Width of the image previews of the items
+const PREVIEW_WIDTH: f32 = 160.0;
+
+impl ReportPane {
+    pub fn new() -> ReportPane {
+        ReportPane {
+            report: InspectionReport::default(),
+            export_result: None,
+        }
+    }
+
+    fn header_ui(&mut self, ui: &mut Ui) {
+        Grid::new("report_header").num_columns(2).show(ui, |ui| {
+            ui.label("Title");
+            ui.add(
+                TextEdit::singleline(&mut self.report.title).hint_text("Thermal inspection report"),
+            );
+            ui.end_row();
+            ui.label("Inspector");
+            ui.text_edit_singleline(&mut self.report.inspector);
+            ui.end_row();
+            ui.label("Location");
+            ui.text_edit_singleline(&mut self.report.location);
+            ui.end_row();
+            ui.label("Notes");
+            ui.add(TextEdit::multiline(&mut self.report.notes).desired_rows(2));
+            ui.end_row();
+        });
+    }
+
+    ///
+    /// Editor for a single item, returns whether it should be moved or removed.
+    ///
+    fn item_ui(ui: &mut Ui, index: usize, count: usize, item: &mut ReportItem) -> ItemAction {
+        let mut action = ItemAction::None;
+        ui.horizontal(|ui| {
+            ui.add(
+                Image::new(format!("file://{}", item.image.display()))
+                    .fit_to_exact_size(Vec2::new(PREVIEW_WIDTH, PREVIEW_WIDTH * 0.75))
+                    .maintain_aspect_ratio(true),
+            );
+            ui.vertical(|ui| {
+                ui.horizontal(|ui| {
+                    ui.add(
+                        TextEdit::singleline(&mut item.caption)
+                            .hint_text(format!("Image {}", index + 1))
+                            .desired_width(160.0),
+                    );
+                    if ui.add_enabled(index > 0, Button::new("⏶")).clicked() {
+                        action = ItemAction::MoveUp;
+                    }
+                    if ui
+                        .add_enabled(index + 1 < count, Button::new("⏷"))
+                        .clicked()
+                    {
+                        action = ItemAction::MoveDown;
+                    }
+                    if ui.button("Remove").clicked() {
+                        action = ItemAction::Remove;
+                    }
+                });
+                ui.label(item.captured_at.format("%Y-%m-%d %H:%M:%S").to_string());
+                for (name, reading) in item.readings.iter() {
+                    ui.label(format!("{}: {}", name, reading));
+                }
+                ui.add(
+                    TextEdit::multiline(&mut item.notes)
+                        .hint_text("Findings")
+                        .desired_rows(2),
+                );
+            });
+        });
+        action
+    }
+}
+
+enum ItemAction {
+    None,
+    MoveUp,
+    MoveDown,
+    Remove,
+}
+
+impl Pane for ReportPane {
+    fn title(&self) -> egui::WidgetText {
+        "Report".into()
+    }
+
+    fn kind(&self) -> Option<PaneKind> {
+        Some(PaneKind::Report)
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, app: &mut PaneContext<'_>) {
+        let global_state = app.global_state;
+
+        self.header_ui(ui);
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    global_state.last_thermal_capturer_result.is_some(),
+                    Button::new("Add Current Frame"),
+                )
+                .on_hover_text("Save a snapshot with the readings of all gizmos")
+                .clicked()
+            {
+                match global_state.snapshot_for_report() {
+                    Ok(item) => self.report.items.push(item),
+                    Err(err) => tracing::error!(error = %err, "Failed to add frame to the report"),
+                }
+            }
+
+            let image_extensions: Vec<&str> = ImageFormat::iter()
+                .map(|format| format.extension())
+                .collect();
+            ComboBox::from_id_source("report_add_from_gallery")
+                .selected_text("Add From Gallery")
+                .show_ui(ui, |ui| {
+                    for elem in global_state.gallery.iter().filter(|elem| {
+                        elem.path.extension().is_some_and(|ext| {
+                            image_extensions.contains(&ext.to_string_lossy().as_ref())
+                        })
+                    }) {
+                        let name = elem
+                            .path
+                            .file_name()
+                            .map(|name| name.to_string_lossy().to_string())
+                            .unwrap_or_default();
+                        if ui.selectable_label(false, name).clicked() {
+                            self.report
+                                .items
+                                .push(ReportItem::from_image(elem.path.clone()));
+                        }
+                    }
+                });
+        });
+        ui.separator();
+
+        let count = self.report.items.len();
+        let mut item_action = None;
+        ScrollArea::vertical()
+            .max_height(ui.available_height() - 60.0)
+            .show(ui, |ui| {
+                for (i, item) in self.report.items.iter_mut().enumerate() {
+                    match Self::item_ui(ui, i, count, item) {
+                        ItemAction::None => {}
+                        action => item_action = Some((i, action)),
+                    }
+                    ui.separator();
+                }
+                if count == 0 {
+                    ui.label("Add frames or snapshots to include them in the report.");
+                }
+            });
+        match item_action {
+            Some((i, ItemAction::MoveUp)) => self.report.items.swap(i, i - 1),
+            Some((i, ItemAction::MoveDown)) => self.report.items.swap(i, i + 1),
+            Some((i, ItemAction::Remove)) => {
+                self.report.items.remove(i);
+            }
+            _ => {}
+        }
+
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(count > 0, Button::new("Export HTML…"))
+                .on_hover_text(
+                    "Write the report and its images into a folder, print it from a browser for a PDF",
+                )
+                .clicked()
+            {
+                if let Some(directory) = rfd::FileDialog::new()
+                    .set_title("Choose a folder for the report")
+                    .pick_folder()
+                {
+                    self.export_result = Some(
+                        self.report
+                            .write_html(&directory)
+                            .map_err(|err| err.to_string()),
+                    );
+                }
+            }
+            match &self.export_result {
+                Some(Ok(path)) if ui.link(path.display().to_string()).clicked() => {
+                    ui.ctx()
+                        .open_url(egui::OpenUrl::new_tab(format!("file://{}", path.display())));
+                }
+                Some(Err(err)) => {
+                    ui.colored_label(egui::Color32::RED, err);
+                }
+                _ => {}
+            }
+        });
+    }
+}
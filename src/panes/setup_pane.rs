@@ -1,55 +1,91 @@
-use std::cell::RefCell;
-use std::rc::Rc;
-use std::sync::Arc;
-
 use anyhow::Error;
-use eframe::egui::{self, Button, CollapsingHeader};
-use eframe::egui::{RichText, WidgetText};
+use eframe::egui::{self, Button, CollapsingHeader, DragValue};
+use eframe::egui::{RichText, TextEdit, WidgetText};
 use eframe::epaint::text::LayoutJob;
 use nokhwa::utils::CameraIndex;
-use nokhwa::Camera;
+use uuid::Uuid;
 
+use crate::auto_display_range_controller::{
+    RangeLock, SmoothingMode, MAX_CLIP_PERCENT, MAX_LOCKED_SPAN_KELVIN, MIN_LOCKED_SPAN_KELVIN,
+};
 use crate::camera_enumerator::{enumerate_cameras, EnumeratedCamera};
+use crate::contour_lines::MAX_CONTOUR_LEVELS;
+use crate::denoise::{DenoiseFilter, MAX_DENOISE_SIGMA, MIN_DENOISE_SIGMA};
 use crate::dynamic_range_curve::dynamic_curve_editor;
+use crate::flat_field_calibration::FlatFieldStep;
+use crate::fusion::{FusionMode, MAX_EDGE_STRENGTH, MAX_FUSION_SCALE, MIN_FUSION_SCALE};
+use crate::gizmos::GizmoKind;
 use crate::gradient_selector_widget::GradientSelectorView;
-use crate::pane_dispatcher::Pane;
+use crate::isotherm::{Isotherm, IsothermMode, MAX_ISOTHERMS};
+use crate::lens_distortion::MAX_DISTORTION_COEFFICIENT;
+use crate::pane_dispatcher::{Pane, PaneContext, PaneKind};
+use crate::profiles::{export_profile, import_profile, Profile};
 
-use crate::temperature_edit_field::temperature_range_edit_field;
-use crate::thermal_capturer::ThermalCapturer;
+use crate::temperature::{Temp, TempDelta, TemperatureUnit};
+use crate::temperature_correction::MIN_EMISSIVITY;
+use crate::temperature_edit_field::{
+    temperature_delta_edit_field, temperature_edit_field, temperature_range_edit_field,
+};
+use crate::temporal_average::{MAX_AVERAGE_FRAMES, MIN_AVERAGE_FRAMES};
+use crate::thermal_capturer::{
+    ThermalCapturerStatus, MAX_AUTO_FFC_INTERVAL_SECS, MAX_PROCESSING_THREADS,
+    MIN_AUTO_FFC_INTERVAL_SECS,
+};
+use crate::thermal_source::camera_source::camera_id;
 use crate::types::image_rotation::ImageRotation;
-use crate::AppGlobalState;
+use crate::types::mapping_mode::MappingMode;
+use crate::upscaling::{UpscaleFilter, MAX_UPSCALE_FACTOR, MIN_UPSCALE_FACTOR};
+use crate::visible_capturer::VisibleCapturer;
 
 use anyhow::{Context, Result};
 
 pub struct SetupPane {
-    global_state: Rc<RefCell<AppGlobalState>>,
     cameras: Result<Vec<EnumeratedCamera>, Error>,
     selected_camera_index: CameraIndex,
+    // Camera the app had open when last shown, see AppGlobalState::thermal_camera_index
+    opened_camera_index: Option<CameraIndex>,
     open_camera_error: Option<String>,
     gradient_selector: GradientSelectorView,
+    // Temperatures of the flat field references, the first one only if it is known
+    flat_field_use_reference: bool,
+    flat_field_reference: Temp,
+    flat_field_second_reference: Temp,
+    // Webcam selected for the fusion with the thermal image
+    visible_camera_index: Option<CameraIndex>,
+    visible_camera_error: Option<String>,
+    // Name the current settings are saved as, the last loaded profile by default
+    profile_name: String,
+    profile_error: Option<String>,
 }
 
 impl SetupPane {
-    pub fn new(global_state: Rc<RefCell<AppGlobalState>>) -> SetupPane {
+    pub fn new() -> SetupPane {
         let cameras = enumerate_cameras().inspect_err(|err| {
-            eprintln!("Failed to enumerate cameras: {:#}", err);
+            tracing::error!(error = %format_args!("{:#}", err), "Failed to enumerate cameras");
         });
 
         SetupPane {
-            global_state,
             selected_camera_index: cameras
                 .as_ref()
                 .ok()
                 .and_then(|cameras| {
                     cameras
                         .iter()
-                        .find(|camera| camera.adapter.is_some())
+                        .find(|camera| camera.adapter.is_some() && !camera.is_demo())
                         .map(|camera| camera.info.index().clone())
                 })
                 .unwrap_or(CameraIndex::Index(0)),
             cameras,
+            opened_camera_index: None,
             open_camera_error: None,
             gradient_selector: GradientSelectorView::new(),
+            flat_field_use_reference: false,
+            flat_field_reference: Temp::from_celsius(35.0),
+            flat_field_second_reference: Temp::from_celsius(60.0),
+            visible_camera_index: None,
+            visible_camera_error: None,
+            profile_name: String::new(),
+            profile_error: None,
         }
     }
 
@@ -64,40 +100,155 @@ impl SetupPane {
     fn open_selected_camera(
         &mut self,
         ctx: &egui::Context,
-        global_state: &mut AppGlobalState,
+        app: &mut PaneContext<'_>,
     ) -> Result<()> {
         let adapter = self
             .selected_camera_info()
-            .and_then(|i| i.adapter.as_ref())
+            .and_then(|i| i.adapter.clone())
             .context("No camera selected")?;
-        let cloned_ctx = ctx.clone();
-        let cloned_adapter = adapter.clone();
+        let index = self.selected_camera_index.clone();
+        let ctx = ctx.clone();
+        app.update(move |global_state| global_state.open_camera(index, adapter, &ctx));
+        self.open_camera_error = None;
+        Ok(())
+    }
 
-        Camera::new(
-            self.selected_camera_index.clone(),
-            adapter.requested_format(),
-        )
-        .map(|cam| {
-            // Create thermal capturer
-
-            global_state.thermal_capturer_inst = Some(ThermalCapturer::new(
-                cam,
-                cloned_adapter,
-                global_state.thermal_capturer_settings.clone(),
-                Arc::new(move || {
-                    cloned_ctx.request_repaint(); // repaint so that the result can be read out
-                }),
-            ))
-            .map(|mut capturer| {
-                capturer.start();
-                capturer
+    fn profiles_ui(&mut self, ui: &mut egui::Ui, app: &mut PaneContext<'_>) {
+        let global_state = app.global_state;
+        CollapsingHeader::new("Profiles")
+            .id_source("setup_pane_profiles")
+            .show(ui, |ui| {
+                let mut loaded_profile = None;
+                let mut deleted_profile = None;
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_source("setup_pane_profile")
+                        .selected_text(
+                            if global_state
+                                .profiles
+                                .iter()
+                                .any(|p| p.name == self.profile_name)
+                            {
+                                self.profile_name.clone()
+                            } else {
+                                "Select a profile".to_string()
+                            },
+                        )
+                        .show_ui(ui, |ui| {
+                            for profile in global_state.profiles.iter() {
+                                if ui
+                                    .selectable_label(
+                                        profile.name == self.profile_name,
+                                        &profile.name,
+                                    )
+                                    .clicked()
+                                {
+                                    loaded_profile = Some(profile.clone());
+                                }
+                            }
+                        })
+                        .response
+                        .on_hover_text("Switch to the settings and alarms of a profile");
+
+                    let selected = global_state
+                        .profiles
+                        .iter()
+                        .find(|p| p.name == self.profile_name);
+                    if ui
+                        .add_enabled(selected.is_some(), Button::new("Export…"))
+                        .clicked()
+                    {
+                        if let (Some(profile), Some(path)) = (
+                            selected,
+                            rfd::FileDialog::new()
+                                .add_filter("JSON", &["json"])
+                                .set_file_name(format!("{}.json", self.profile_name))
+                                .save_file(),
+                        ) {
+                            self.profile_error = export_profile(&path, profile)
+                                .err()
+                                .map(|err| format!("Failed to export profile: {}", err));
+                        }
+                    }
+                    if ui
+                        .add_enabled(selected.is_some(), Button::new("Delete"))
+                        .clicked()
+                    {
+                        deleted_profile = Some(self.profile_name.clone());
+                    }
+                });
+                if let Some(profile) = loaded_profile {
+                    self.profile_name = profile.name.clone();
+                    app.update(move |global_state| global_state.apply_profile(&profile));
+                }
+                if let Some(name) = deleted_profile {
+                    app.update(move |global_state| global_state.delete_profile(&name));
+                }
+
+                ui.horizontal(|ui| {
+                    ui.add(
+                        TextEdit::singleline(&mut self.profile_name)
+                            .hint_text("Profile name")
+                            .desired_width(120.0),
+                    );
+                    let name = self.profile_name.trim().to_string();
+                    if ui
+                        .add_enabled(!name.is_empty(), Button::new("Save"))
+                        .on_hover_text(
+                            "Save the current settings and alarms, replacing the profile with the same name",
+                        )
+                        .clicked()
+                    {
+                        let profile = Profile::new(name, &global_state.thermal_capturer_settings);
+                        app.update(move |global_state| global_state.save_profile(profile));
+                    }
+                    if ui.button("Import…").clicked() {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("JSON", &["json"])
+                            .pick_file()
+                        {
+                            match import_profile(&path) {
+                                Ok(profile) => {
+                                    self.profile_error = None;
+                                    self.profile_name = profile.name.clone();
+                                    app.update(move |global_state| {
+                                        global_state.save_profile(profile)
+                                    });
+                                }
+                                Err(err) => {
+                                    self.profile_error =
+                                        Some(format!("Failed to import profile: {}", err));
+                                }
+                            }
+                        }
+                    }
+                });
+                if let Some(error) = &self.profile_error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
             });
-            self.open_camera_error = None;
-        })
-        .inspect_err(|err| {
-            self.open_camera_error = Some(format!("Failed to open camera: {}", err));
-        })
-        .context("Failed to open camera")
+    }
+
+    fn open_additional_camera(&mut self, ctx: &egui::Context, app: &mut PaneContext<'_>) {
+        let Some(camera) = self.selected_camera_info() else {
+            return;
+        };
+        let Some(id) = camera
+            .adapter
+            .as_ref()
+            .map(|adapter| camera_id(adapter.as_ref()))
+        else {
+            self.open_camera_error = Some("Failed to open camera: it is not supported".to_string());
+            return;
+        };
+        let camera = camera.clone();
+        let ctx = ctx.clone();
+        app.update(move |global_state| {
+            let settings = global_state.additional_camera_settings(Some(&id));
+            if let Err(err) = global_state.camera_manager.open(&camera, settings, &ctx) {
+                tracing::error!(error = %err, "Failed to open camera");
+            }
+        });
+        self.open_camera_error = None;
     }
 }
 
@@ -106,32 +257,36 @@ impl Pane for SetupPane {
         "Setup".into()
     }
 
-    fn ui(&mut self, ui: &mut egui::Ui) {
-        let global_state_clone = self.global_state.clone();
-        let mut global_state = global_state_clone.as_ref().borrow_mut();
-        if !global_state.did_try_open_camera_at_startup {
-            global_state.did_try_open_camera_at_startup = true;
-            if global_state.prefs.as_ref().unwrap().auto_open_camera {
-                let _ = self.open_selected_camera(ui.ctx(), &mut global_state);
+    fn kind(&self) -> Option<PaneKind> {
+        Some(PaneKind::Setup)
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, app: &mut PaneContext<'_>) {
+        let global_state = app.global_state;
+        // follow cameras opened elsewhere, e.g. from the camera selection dialog
+        if global_state.thermal_camera_index != self.opened_camera_index {
+            self.opened_camera_index = global_state.thermal_camera_index.clone();
+            if let Some(index) = self.opened_camera_index.clone() {
+                self.selected_camera_index = index;
             }
         }
 
         if let Some(_evt) = global_state
             .hotplug_detector
-            .as_mut()
+            .as_ref()
             .and_then(|r| r.receiver.try_recv().ok())
         {
             self.cameras = enumerate_cameras().inspect_err(|err| {
-                eprintln!("Failed to enumerate cameras: {:#}", err);
+                tracing::error!(error = %format_args!("{:#}", err), "Failed to enumerate cameras");
             });
             if global_state.should_try_open_camera_on_next_hotplug
                 && global_state.thermal_capturer_inst.is_none()
             {
-                // select a camera with an adapter if possible
+                // select a connected camera with an adapter if possible
                 if !self
                     .selected_camera_info()
                     .as_ref()
-                    .map(|i| i.adapter.is_some())
+                    .map(|i| i.adapter.is_some() && !i.is_demo())
                     .unwrap_or(false)
                 {
                     self.selected_camera_index = self
@@ -141,14 +296,14 @@ impl Pane for SetupPane {
                         .and_then(|cameras| {
                             cameras
                                 .iter()
-                                .find(|camera| camera.adapter.is_some())
+                                .find(|camera| camera.adapter.is_some() && !camera.is_demo())
                                 .map(|camera| camera.info.index().clone())
                         })
                         .unwrap_or(CameraIndex::Index(0));
                 }
 
                 // try to open the camera
-                let _ = self.open_selected_camera(ui.ctx(), &mut global_state);
+                let _ = self.open_selected_camera(ui.ctx(), app);
             }
         }
 
@@ -217,90 +372,515 @@ impl Pane for SetupPane {
                 )
                 .clicked()
             {
-                let _ = self.open_selected_camera(ui.ctx(), &mut global_state);
-                global_state.should_try_open_camera_on_next_hotplug = true;
+                let _ = self.open_selected_camera(ui.ctx(), app);
+                app.update(|global_state| {
+                    global_state.should_try_open_camera_on_next_hotplug = true
+                });
             }
-        } else if ui.button("Close Camera").clicked() {
-            global_state.thermal_capturer_inst = None;
-            global_state.should_try_open_camera_on_next_hotplug = false;
+        } else {
+            ui.horizontal(|ui| {
+                if ui.button("Close Camera").clicked() {
+                    app.update(|global_state| {
+                        global_state.thermal_capturer_inst = None;
+                        global_state.should_try_open_camera_on_next_hotplug = false;
+                    });
+                }
+                if ui
+                    .add_enabled(
+                        self.selected_camera_info()
+                            .is_some_and(|i| i.adapter.is_some())
+                            && global_state.thermal_camera_index.as_ref()
+                                != Some(&self.selected_camera_index),
+                        Button::new("Switch Camera"),
+                    )
+                    .on_hover_text("Replace the open camera with the selected one")
+                    .clicked()
+                {
+                    let _ = self.open_selected_camera(ui.ctx(), app);
+                }
+                if ui
+                    .add_enabled(
+                        self.selected_camera_info()
+                            .and_then(|i| i.adapter.as_ref())
+                            .is_some(),
+                        Button::new("Open as Additional Camera"),
+                    )
+                    .on_hover_text(
+                        "Shows the selected camera in a window of its own, next to this one",
+                    )
+                    .clicked()
+                {
+                    self.open_additional_camera(ui.ctx(), app);
+                }
+            });
+        }
+
+        if let Some(ThermalCapturerStatus::Reconnecting { error }) = global_state
+            .thermal_capturer_inst
+            .as_ref()
+            .map(|capturer| capturer.status().clone())
+        {
+            ui.colored_label(
+                egui::Color32::YELLOW,
+                format!("Camera lost ({}), waiting for it to come back...", error),
+            );
         }
 
         if let Some(error) = &self.open_camera_error {
             ui.colored_label(egui::Color32::RED, error);
         }
+        if let Some(error) = &global_state.thermal_capturer_error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+        ui.separator();
+        self.profiles_ui(ui, app);
         ui.separator();
         ui.label("Rotation");
         ui.horizontal(|ui| {
+            let mut rotation = global_state.thermal_capturer_settings.rotation;
             if ui
-                .selectable_value(
-                    &mut global_state.thermal_capturer_settings.rotation,
-                    ImageRotation::None,
-                    "None",
-                )
+                .selectable_value(&mut rotation, ImageRotation::None, "None")
                 .changed()
                 || ui
-                    .selectable_value(
-                        &mut global_state.thermal_capturer_settings.rotation,
-                        ImageRotation::Clockwise90,
-                        "90°",
-                    )
+                    .selectable_value(&mut rotation, ImageRotation::Clockwise90, "90°")
                     .changed()
                 || ui
-                    .selectable_value(
-                        &mut global_state.thermal_capturer_settings.rotation,
-                        ImageRotation::Clockwise180,
-                        "180°",
-                    )
+                    .selectable_value(&mut rotation, ImageRotation::Clockwise180, "180°")
                     .changed()
                 || ui
-                    .selectable_value(
-                        &mut global_state.thermal_capturer_settings.rotation,
-                        ImageRotation::Clockwise270,
-                        "270°",
-                    )
+                    .selectable_value(&mut rotation, ImageRotation::Clockwise270, "270°")
                     .changed()
             {
-                let settings_clone = global_state.thermal_capturer_settings.clone();
-                if let Some(thermal_capturer) = global_state.thermal_capturer_inst.as_mut() {
-                    thermal_capturer.set_settings(settings_clone);
-                }
+                app.update(move |global_state| {
+                    global_state.edit_settings(|settings| settings.rotation = rotation);
+                    global_state.remember_camera_orientation();
+                });
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Mirror");
+            let mut flip_horizontal = global_state.thermal_capturer_settings.flip_horizontal;
+            let mut flip_vertical = global_state.thermal_capturer_settings.flip_vertical;
+            if ui.checkbox(&mut flip_horizontal, "Horizontal").changed()
+                || ui.checkbox(&mut flip_vertical, "Vertical").changed()
+            {
+                app.update(move |global_state| {
+                    global_state.edit_settings(|settings| {
+                        settings.flip_horizontal = flip_horizontal;
+                        settings.flip_vertical = flip_vertical;
+                    });
+                    global_state.remember_camera_orientation();
+                });
             }
         });
         ui.separator();
 
-        if ui
-            .checkbox(
-                &mut global_state.thermal_capturer_settings.auto_range,
-                "Auto Range",
-            )
-            .changed()
+        if self
+            .selected_camera_info()
+            .is_some_and(|i| i.adapter.is_some())
         {
-            // auto range has been disabled, copy the current range to the manual range
-            if !global_state.thermal_capturer_settings.auto_range {
-                let range_to_copy = global_state
-                    .last_thermal_capturer_result
-                    .as_ref()
-                    .map(|res| res.image_range);
-                if let Some(range) = range_to_copy {
-                    global_state.thermal_capturer_settings.manual_range = range;
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(
+                        global_state.thermal_capturer_inst.is_some(),
+                        Button::new("Calibrate Shutter"),
+                    )
+                    .on_hover_text("Run the flat-field correction (FFC) of the camera now")
+                    .clicked()
+                {
+                    app.with_capturer(|thermal_capturer| thermal_capturer.trigger_ffc());
+                }
+
+                let settings = &global_state.thermal_capturer_settings;
+                let mut auto_ffc = settings.auto_ffc_interval_secs.is_some();
+                let mut changed = ui.checkbox(&mut auto_ffc, "Every").changed();
+                let mut interval = settings.auto_ffc_interval_secs.unwrap_or(60);
+                ui.add_enabled_ui(auto_ffc, |ui| {
+                    changed |= ui
+                        .add(
+                            DragValue::new(&mut interval)
+                                .clamp_range(
+                                    MIN_AUTO_FFC_INTERVAL_SECS..=MAX_AUTO_FFC_INTERVAL_SECS,
+                                )
+                                .suffix(" s"),
+                        )
+                        .changed();
+                });
+                if changed {
+                    app.edit_settings(move |settings| {
+                        settings.auto_ffc_interval_secs = auto_ffc.then_some(interval)
+                    });
                 }
+            });
+            if let Some(error) = global_state
+                .last_thermal_capturer_result
+                .as_ref()
+                .and_then(|r| r.ffc_error.as_ref())
+            {
+                ui.colored_label(egui::Color32::RED, error);
             }
-            let settings_clone = global_state.thermal_capturer_settings.clone();
-            if let Some(thermal_capturer) = global_state.thermal_capturer_inst.as_mut() {
-                thermal_capturer.set_settings(settings_clone);
+            ui.separator();
+        }
+
+        CollapsingHeader::new("Non-uniformity Correction")
+            .id_source("nuc_header")
+            .show(ui, |ui| {
+                let mut nuc_enabled = global_state.thermal_capturer_settings.nuc_enabled;
+                if ui.checkbox(&mut nuc_enabled, "Enable NUC").changed() {
+                    app.edit_settings(move |settings| settings.nuc_enabled = nuc_enabled);
+                }
+
+                let temp_format = global_state.temp_format();
+                let result = global_state.last_thermal_capturer_result.as_ref();
+                let has_flat_field = result.is_some_and(|r| r.has_flat_field);
+                let progress = result.and_then(|r| r.flat_field_capture_progress);
+                let error = result.and_then(|r| r.flat_field_error.clone());
+                ui.label(match (has_flat_field, result.is_some_and(|r| r.flat_field_has_gains)) {
+                    (true, true) => "Flat field calibration with gains stored.",
+                    (true, false) => "Flat field calibration stored.",
+                    _ => "No flat field calibration.",
+                });
+                if let Some(progress) = progress {
+                    ui.add(egui::ProgressBar::new(progress).text("Capturing reference..."));
+                }
+                if let Some(error) = error {
+                    ui.label(RichText::new(error).color(egui::Color32::RED));
+                }
+
+                let mut step = None;
+                ui.add_enabled_ui(
+                    global_state.thermal_capturer_inst.is_some() && progress.is_none(),
+                    |ui| {
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut self.flat_field_use_reference, "Reference at")
+                                .on_hover_text(
+                                    "Temperature of the reference, e.g. a blackbody. Without it, only the differences between the pixels are corrected.",
+                                );
+                            ui.add_enabled_ui(self.flat_field_use_reference, |ui| {
+                                temperature_edit_field(ui, temp_format, &mut self.flat_field_reference);
+                            });
+                        });
+                        ui.horizontal(|ui| {
+                            if ui
+                                .button("Capture Flat Field")
+                                .on_hover_text(
+                                    "Point the camera at a surface of uniform temperature first",
+                                )
+                                .clicked()
+                            {
+                                step = Some(FlatFieldStep::Reference(
+                                    self.flat_field_use_reference
+                                        .then_some(self.flat_field_reference),
+                                ));
+                            }
+                            if ui
+                                .add_enabled(has_flat_field, Button::new("Clear Calibration"))
+                                .clicked()
+                            {
+                                app.with_capturer(|thermal_capturer| {
+                                    thermal_capturer.clear_flat_field()
+                                });
+                            }
+                        });
+                        ui.add_enabled_ui(has_flat_field && self.flat_field_use_reference, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.label("Second reference at");
+                                temperature_edit_field(
+                                    ui,
+                                    temp_format,
+                                    &mut self.flat_field_second_reference,
+                                );
+                                if ui
+                                    .button("Capture Gains")
+                                    .on_hover_text(
+                                        "Corrects the sensitivity of the pixels, using a reference at another temperature",
+                                    )
+                                    .clicked()
+                                {
+                                    step = Some(FlatFieldStep::SecondReference(
+                                        self.flat_field_second_reference,
+                                    ));
+                                }
+                            });
+                        });
+                    },
+                );
+                if let Some(step) = step {
+                    app.with_capturer(move |thermal_capturer| {
+                        thermal_capturer.capture_flat_field(step)
+                    });
+                }
+            });
+
+        CollapsingHeader::new("Bad Pixels")
+            .id_source("bad_pixels_header")
+            .show(ui, |ui| {
+                let mut bad_pixel_correction =
+                    global_state.thermal_capturer_settings.bad_pixel_correction;
+                if ui
+                    .checkbox(&mut bad_pixel_correction, "Correct bad pixels")
+                    .on_hover_text("Replace hot, dead and stuck pixels by their neighbours")
+                    .changed()
+                {
+                    app.edit_settings(move |settings| {
+                        settings.bad_pixel_correction = bad_pixel_correction
+                    });
+                }
+
+                let result = global_state.last_thermal_capturer_result.as_ref();
+                let bad_pixel_count = result.and_then(|r| r.bad_pixel_count);
+                let progress = result.and_then(|r| r.bad_pixel_calibration_progress);
+                if let Some(progress) = progress {
+                    ui.add(egui::ProgressBar::new(progress).text("Detecting bad pixels..."));
+                } else {
+                    ui.label(match bad_pixel_count {
+                        Some(count) => format!("{} bad pixels stored.", count),
+                        None => "No bad pixel map.".to_string(),
+                    });
+                }
+
+                ui.add_enabled_ui(
+                    global_state.thermal_capturer_inst.is_some() && progress.is_none(),
+                    |ui| {
+                        ui.horizontal(|ui| {
+                            if ui
+                                .button("Detect Bad Pixels")
+                                .on_hover_text(
+                                    "Takes a few seconds, point the camera at a scene without small hot or cold objects",
+                                )
+                                .clicked()
+                            {
+                                app.with_capturer(|thermal_capturer| {
+                                    thermal_capturer.detect_bad_pixels()
+                                });
+                            }
+                            if ui
+                                .add_enabled(bad_pixel_count.is_some(), Button::new("Clear Map"))
+                                .clicked()
+                            {
+                                app.with_capturer(|thermal_capturer| {
+                                    thermal_capturer.clear_bad_pixels()
+                                });
+                            }
+                        });
+                    },
+                );
+            });
+
+        ui.separator();
+
+        let mut auto_range = global_state.thermal_capturer_settings.auto_range;
+        let mut auto_range_changed = false;
+        ui.horizontal(|ui| {
+            auto_range_changed = ui.checkbox(&mut auto_range, "Auto Range").changed();
+            if ui
+                .add_enabled(
+                    auto_range && global_state.last_thermal_capturer_result.is_some(),
+                    Button::new("Lock Range"),
+                )
+                .on_hover_text("Switch to manual range, keeping the current auto range")
+                .clicked()
+            {
+                app.update(|global_state| global_state.lock_current_range());
+            }
+            let mut auto_range_held = global_state.thermal_capturer_settings.auto_range_held;
+            if ui
+                .add_enabled(
+                    auto_range,
+                    egui::Checkbox::new(&mut auto_range_held, "Hold"),
+                )
+                .on_hover_text("Stop the auto range from adapting to the scene (H)")
+                .changed()
+            {
+                app.edit_settings(move |settings| settings.auto_range_held = auto_range_held);
+            }
+            let mut clip_percent = global_state.thermal_capturer_settings.auto_range_clip_percent;
+            if ui
+                .add_enabled(
+                    auto_range,
+                    DragValue::new(&mut clip_percent)
+                    .speed(0.05)
+                    .max_decimals(2)
+                    .clamp_range(0.0..=MAX_CLIP_PERCENT)
+                    .prefix("Clip ")
+                    .suffix(" %"),
+                )
+                .on_hover_text(
+                    "Ignore this percentage of the coldest and hottest pixels, so that a few hot or dead pixels don't stretch the range",
+                )
+                .changed()
+            {
+                app.edit_settings(move |settings| {
+                    settings.auto_range_clip_percent = clip_percent
+                });
             }
+        });
+        if auto_range_changed {
+            let range_to_copy = global_state
+                .last_thermal_capturer_result
+                .as_ref()
+                .map(|res| res.image_range);
+            app.edit_settings(move |settings| {
+                settings.auto_range = auto_range;
+                // auto range has been disabled, copy the current range to the manual range
+                if let (false, Some(range)) = (auto_range, range_to_copy) {
+                    settings.manual_range = range;
+                }
+            });
         }
+        ui.horizontal(|ui| {
+            let settings = &global_state.thermal_capturer_settings;
+            let rect_gizmos: Vec<(Uuid, String)> = match &settings.gizmo.kind {
+                GizmoKind::Root { children } => children
+                    .iter()
+                    .filter(|gizmo| matches!(gizmo.kind, GizmoKind::Rect { .. }))
+                    .map(|gizmo| (gizmo.uuid, gizmo.name.clone()))
+                    .collect(),
+                _ => vec![],
+            };
+            let selected_name = settings
+                .auto_range_roi
+                .and_then(|uuid| rect_gizmos.iter().find(|(gizmo, _)| *gizmo == uuid))
+                .map_or("Whole image", |(_, name)| name.as_str());
+            let mut auto_range_roi = settings.auto_range_roi;
+
+            ui.add_enabled_ui(settings.auto_range, |ui| {
+                ui.label("Range ROI");
+                egui::ComboBox::from_id_source("setup_pane_auto_range_roi")
+                    .selected_text(selected_name)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut auto_range_roi, None, "Whole image");
+                        for (uuid, name) in rect_gizmos.iter() {
+                            ui.selectable_value(&mut auto_range_roi, Some(*uuid), name);
+                        }
+                    })
+                    .response
+                    .on_hover_text(
+                        "Compute the auto range from the pixels of a rectangle gizmo, stretching the colors over the object of interest",
+                    );
+            });
+            if auto_range_roi != settings.auto_range_roi {
+                app.edit_settings(move |settings| settings.auto_range_roi = auto_range_roi);
+            }
+        });
+        ui.horizontal(|ui| {
+            let temp_format = global_state.temp_format();
+            // a new lock starts from the range currently shown
+            let current_range = global_state
+                .last_thermal_capturer_result
+                .as_ref()
+                .map(|res| res.image_range)
+                .unwrap_or(global_state.thermal_capturer_settings.manual_range);
+            let settings = &global_state.thermal_capturer_settings;
+            let mut auto_range_lock = settings.auto_range_lock;
+
+            ui.add_enabled_ui(settings.auto_range, |ui| {
+                ui.label("Lock");
+                egui::ComboBox::from_id_source("setup_pane_auto_range_lock")
+                    .selected_text(auto_range_lock.name())
+                    .show_ui(ui, |ui| {
+                        for lock in [
+                            RangeLock::Off,
+                            RangeLock::Span(current_range.span()),
+                            RangeLock::Level(current_range.center()),
+                        ] {
+                            let selected = std::mem::discriminant(&lock)
+                                == std::mem::discriminant(&auto_range_lock);
+                            if ui.selectable_label(selected, lock.name()).clicked() && !selected {
+                                auto_range_lock = lock;
+                            }
+                        }
+                    })
+                    .response
+                    .on_hover_text(
+                        "Keep the span or the level (center) of the auto range fixed, while the other one follows the scene",
+                    );
+                match &mut auto_range_lock {
+                    RangeLock::Off => {}
+                    RangeLock::Span(span) => {
+                        let mut span_kelvin = span.to_unit(TemperatureUnit::Kelvin);
+                        if temperature_delta_edit_field(
+                            ui,
+                            temp_format,
+                            &mut span_kelvin,
+                            MIN_LOCKED_SPAN_KELVIN..=MAX_LOCKED_SPAN_KELVIN,
+                        )
+                        .changed()
+                        {
+                            *span = TempDelta::new(span_kelvin);
+                        }
+                    }
+                    RangeLock::Level(level) => {
+                        temperature_edit_field(ui, temp_format, level);
+                    }
+                }
+            });
+            if auto_range_lock != settings.auto_range_lock {
+                app.edit_settings(move |settings| settings.auto_range_lock = auto_range_lock);
+            }
+        });
+        CollapsingHeader::new("Auto Range Smoothing")
+            .id_source("setup_pane_auto_range_smoothing")
+            .show(ui, |ui| {
+                let temp_format = global_state.temp_format();
+                let mut smoothing = global_state.thermal_capturer_settings.auto_range_smoothing;
+                let mut changed = false;
+                ui.horizontal(|ui| {
+                    ui.label("Mode");
+                    egui::ComboBox::from_id_source("setup_pane_smoothing_mode")
+                        .selected_text(smoothing.mode.name())
+                        .show_ui(ui, |ui| {
+                            for mode in [SmoothingMode::Stepped, SmoothingMode::Exponential] {
+                                changed |= ui
+                                    .selectable_value(&mut smoothing.mode, mode, mode.name())
+                                    .changed();
+                            }
+                        });
+                });
+                ui.add_enabled_ui(smoothing.mode == SmoothingMode::Exponential, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Time constant");
+                        changed |= ui
+                            .add(
+                                DragValue::new(&mut smoothing.time_constant_secs)
+                                    .speed(0.05)
+                                    .clamp_range(0.05..=30.0)
+                                    .suffix(" s"),
+                            )
+                            .on_hover_text("Longer is steadier, but slower to follow the scene")
+                            .changed();
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Hysteresis");
+                        changed |= temperature_delta_edit_field(
+                            ui,
+                            temp_format,
+                            &mut smoothing.hysteresis_kelvin,
+                            0.0..=20.0,
+                        )
+                        .on_hover_text(
+                            "Changes of the scene smaller than this don't move the range",
+                        )
+                        .changed();
+                    });
+                });
+                if changed {
+                    app.edit_settings(move |settings| settings.auto_range_smoothing = smoothing);
+                }
+            });
+
         // copy of the range to pass to the edit field
         // (it will not be modified if auto_range is enabled, because the field is disabled)
         let mut range_copy = global_state
             .last_thermal_capturer_result
             .as_ref()
             .map(|r| r.image_range);
+        let mut manual_range = global_state.thermal_capturer_settings.manual_range;
         if temperature_range_edit_field(
             ui,
             "range",
             !global_state.thermal_capturer_settings.auto_range,
-            global_state.preferred_temperature_unit(),
+            global_state.temp_format(),
             range_copy
                 .as_mut()
                 .and_then(|r| {
@@ -310,16 +890,621 @@ impl Pane for SetupPane {
                         None
                     }
                 })
-                .unwrap_or(&mut global_state.thermal_capturer_settings.manual_range),
+                .unwrap_or(&mut manual_range),
         )
         .changed()
         {
-            let settings_clone = global_state.thermal_capturer_settings.clone();
-            if let Some(thermal_capturer) = global_state.thermal_capturer_inst.as_mut() {
-                thermal_capturer.set_settings(settings_clone);
-            }
+            app.edit_settings(move |settings| settings.manual_range = manual_range);
         }
 
+        ui.horizontal(|ui| {
+            ui.label("Mapping");
+            let mut mapping_mode = global_state.thermal_capturer_settings.mapping_mode;
+            egui::ComboBox::from_id_source("setup_pane_mapping_mode")
+                .selected_text(mapping_mode.name())
+                .show_ui(ui, |ui| {
+                    for mode in [MappingMode::Linear, MappingMode::HistogramEqualization] {
+                        ui.selectable_value(&mut mapping_mode, mode, mode.name());
+                    }
+                })
+                .response
+                .on_hover_text(
+                    "Histogram equalization spreads the colors by how many pixels have each temperature, showing detail in large uniform areas",
+                );
+            if mapping_mode != global_state.thermal_capturer_settings.mapping_mode {
+                app.edit_settings(move |settings| settings.mapping_mode = mapping_mode);
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Upscaling");
+            let mut upscale_filter = global_state.thermal_capturer_settings.upscale_filter;
+            let mut upscale_factor = global_state.thermal_capturer_settings.upscale_factor;
+            let mut changed = false;
+            egui::ComboBox::from_id_source("setup_pane_upscale_filter")
+                .selected_text(upscale_filter.name())
+                .show_ui(ui, |ui| {
+                    for filter in UpscaleFilter::ALL {
+                        changed |= ui
+                            .selectable_value(&mut upscale_filter, filter, filter.name())
+                            .changed();
+                    }
+                })
+                .response
+                .on_hover_text(
+                    "Interpolates the temperatures, so the image is smooth when enlarged",
+                );
+            if upscale_filter != UpscaleFilter::Off {
+                changed |= ui
+                    .add(
+                        DragValue::new(&mut upscale_factor)
+                            .clamp_range(MIN_UPSCALE_FACTOR..=MAX_UPSCALE_FACTOR)
+                            .prefix("×"),
+                    )
+                    .changed();
+            }
+            if changed {
+                app.edit_settings(move |settings| {
+                    settings.upscale_filter = upscale_filter;
+                    settings.upscale_factor = upscale_factor;
+                });
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Processing threads");
+            let mut processing_threads = global_state.thermal_capturer_settings.processing_threads;
+            if ui
+                .add(
+                    DragValue::new(&mut processing_threads)
+                        .clamp_range(0..=MAX_PROCESSING_THREADS)
+                        .custom_formatter(|n, _| {
+                            if n == 0.0 {
+                                "Auto".to_string()
+                            } else {
+                                format!("{}", n)
+                            }
+                        }),
+                )
+                .on_hover_text(
+                    "Threads processing each frame, Auto uses all cores. Fewer leave more to other programs.",
+                )
+                .changed()
+            {
+                app.edit_settings(move |settings| {
+                    settings.processing_threads = processing_threads
+                });
+            }
+        });
+
+        ui.separator();
+
+        CollapsingHeader::new("Noise Reduction")
+            .id_source("setup_pane_denoise")
+            .show(ui, |ui| {
+                let temp_format = global_state.temp_format();
+                let mut denoise = global_state.thermal_capturer_settings.denoise;
+                let mut changed = false;
+                ui.horizontal(|ui| {
+                    ui.label("Filter");
+                    egui::ComboBox::from_id_source("setup_pane_denoise_filter")
+                        .selected_text(denoise.filter.name())
+                        .show_ui(ui, |ui| {
+                            for filter in DenoiseFilter::ALL {
+                                changed |= ui
+                                    .selectable_value(&mut denoise.filter, filter, filter.name())
+                                    .changed();
+                            }
+                        });
+                });
+                ui.add_enabled_ui(
+                    matches!(
+                        denoise.filter,
+                        DenoiseFilter::Gaussian | DenoiseFilter::Bilateral
+                    ),
+                    |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Radius");
+                            changed |= ui
+                                .add(
+                                    DragValue::new(&mut denoise.sigma_px)
+                                        .speed(0.05)
+                                        .clamp_range(MIN_DENOISE_SIGMA..=MAX_DENOISE_SIGMA)
+                                        .suffix(" px"),
+                                )
+                                .on_hover_text("Standard deviation of the blur")
+                                .changed();
+                        });
+                    },
+                );
+                ui.add_enabled_ui(denoise.filter == DenoiseFilter::Bilateral, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Edge threshold");
+                        changed |= temperature_delta_edit_field(
+                            ui,
+                            temp_format,
+                            &mut denoise.range_sigma_kelvin,
+                            0.05..=20.0,
+                        )
+                            .on_hover_text(
+                                "Pixels differing more than this from their neighbours are not smoothed",
+                            )
+                            .changed();
+                    });
+                });
+                if changed {
+                    app.edit_settings(move |settings| settings.denoise = denoise);
+                }
+            });
+
+        CollapsingHeader::new("Lens Correction")
+            .id_source("setup_pane_lens_distortion")
+            .show(ui, |ui| {
+                let mut lens_distortion = global_state.thermal_capturer_settings.lens_distortion;
+                let mut changed = ui
+                    .checkbox(&mut lens_distortion.enabled, "Correct lens distortion")
+                    .on_hover_text("Straighten edges bent by the lens before measuring")
+                    .changed();
+                ui.add_enabled_ui(lens_distortion.enabled, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("k1");
+                        changed |= ui
+                            .add(
+                                DragValue::new(&mut lens_distortion.k1)
+                                    .speed(0.002)
+                                    .clamp_range(
+                                        -MAX_DISTORTION_COEFFICIENT..=MAX_DISTORTION_COEFFICIENT,
+                                    ),
+                            )
+                            .on_hover_text("Negative for barrel distortion (wide-angle lenses)")
+                            .changed();
+                        ui.label("k2");
+                        changed |= ui
+                            .add(
+                                DragValue::new(&mut lens_distortion.k2)
+                                    .speed(0.002)
+                                    .clamp_range(
+                                        -MAX_DISTORTION_COEFFICIENT..=MAX_DISTORTION_COEFFICIENT,
+                                    ),
+                            )
+                            .on_hover_text("Correction towards the corners")
+                            .changed();
+                    });
+                });
+                if changed {
+                    app.update(move |global_state| {
+                        global_state
+                            .edit_settings(|settings| settings.lens_distortion = lens_distortion);
+                        global_state.remember_lens_profile();
+                    });
+                }
+            });
+
+        CollapsingHeader::new("Temporal Averaging")
+            .id_source("setup_pane_temporal_average")
+            .show(ui, |ui| {
+                let temp_format = global_state.temp_format();
+                let instantaneous_max = global_state
+                    .last_thermal_capturer_result
+                    .as_ref()
+                    .map(|res| res.instantaneous_range.max);
+                let mut average = global_state.thermal_capturer_settings.temporal_average;
+                let mut changed = false;
+                changed |= ui
+                    .checkbox(&mut average.enabled, "Average frames")
+                    .on_hover_text("Reduces the noise of static scenes")
+                    .changed();
+                ui.add_enabled_ui(average.enabled, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Frames");
+                        changed |= ui
+                            .add(
+                                DragValue::new(&mut average.frames)
+                                    .clamp_range(MIN_AVERAGE_FRAMES..=MAX_AVERAGE_FRAMES),
+                            )
+                            .changed();
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Motion threshold");
+                        changed |= temperature_delta_edit_field(
+                            ui,
+                            temp_format,
+                            &mut average.motion_threshold_kelvin,
+                            0.05..=20.0,
+                        )
+                        .on_hover_text(
+                            "The average starts over when the scene changes by more than this",
+                        )
+                        .changed();
+                    });
+                    if let Some(max) = instantaneous_max {
+                        ui.label(format!("Instantaneous max: {}", temp_format.format(max)));
+                    }
+                });
+                if changed {
+                    app.edit_settings(move |settings| settings.temporal_average = average);
+                }
+            });
+
+        CollapsingHeader::new("Emissivity")
+            .id_source("emissivity_header")
+            .show(ui, |ui| {
+                let temp_format = global_state.temp_format();
+                let mut emissivity = global_state.thermal_capturer_settings.emissivity;
+                let mut reflected_temperature =
+                    global_state.thermal_capturer_settings.reflected_temperature;
+                let mut changed = false;
+                ui.horizontal(|ui| {
+                    ui.label("Emissivity");
+                    changed |= ui
+                        .add(
+                            DragValue::new(&mut emissivity)
+                                .speed(0.01)
+                                .clamp_range(MIN_EMISSIVITY..=1.0)
+                                .max_decimals(2),
+                        )
+                        .on_hover_text("e.g. 0.95 for paint or skin, 0.1 or less for bare metal")
+                        .changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Reflected temperature");
+                    changed |= temperature_edit_field(ui, temp_format, &mut reflected_temperature)
+                        .on_hover_text("Temperature of the surroundings, usually the room")
+                        .changed();
+                });
+                if changed {
+                    app.edit_settings(move |settings| {
+                        settings.emissivity = emissivity;
+                        settings.reflected_temperature = reflected_temperature;
+                    });
+                }
+            });
+
+        CollapsingHeader::new("Temperature Calibration")
+            .id_source("two_point_calibration_header")
+            .show(ui, |ui| {
+                let temp_format = global_state.temp_format();
+                // current readings of the gizmos, to take a reference from the image
+                let mut readings: Vec<(String, Temp)> = vec![];
+                if let (Some(result), GizmoKind::Root { children }) = (
+                    global_state.last_thermal_capturer_result.as_ref(),
+                    &global_state.thermal_capturer_settings.gizmo.kind,
+                ) {
+                    for gizmo in children.iter() {
+                        if let Some(reading) = result.extension.gizmo_results.get(&gizmo.uuid) {
+                            readings.push((gizmo.name.clone(), reading.temperature));
+                        }
+                    }
+                }
+
+                let mut calibration = global_state.thermal_capturer_settings.two_point_calibration;
+                let mut changed = ui
+                    .checkbox(&mut calibration.enabled, "Correct readings")
+                    .on_hover_text(
+                        "Measure two spots with a contact thermometer and enter the real temperatures",
+                    )
+                    .changed();
+
+                let current = calibration;
+                for (i, point) in calibration.points.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Spot {} reads", i + 1));
+                        changed |= temperature_edit_field(ui, temp_format, &mut point.measured).changed();
+                        ui.menu_button("Take", |ui| {
+                            if readings.is_empty() {
+                                ui.label("No readings");
+                            }
+                            for (name, temperature) in readings.iter() {
+                                let label = format!(
+                                    "{}: {}",
+                                    name,
+                                    temp_format.format(*temperature)
+                                );
+                                if ui.button(label).clicked() {
+                                    // the readings are already corrected while it's enabled
+                                    point.measured = current.uncorrected(*temperature);
+                                    changed = true;
+                                    ui.close_menu();
+                                }
+                            }
+                        })
+                        .response
+                        .on_hover_text("Use the current reading of a gizmo");
+                        ui.label("actually");
+                        changed |= temperature_edit_field(ui, temp_format, &mut point.actual).changed();
+                    });
+                }
+                if calibration.enabled && calibration.correction().is_none() {
+                    ui.label(
+                        RichText::new("The readings of the spots are too close")
+                            .color(egui::Color32::RED),
+                    );
+                }
+
+                if changed {
+                    app.edit_settings(move |settings| {
+                        settings.two_point_calibration = calibration
+                    });
+                }
+            });
+
+        ui.separator();
+
+        CollapsingHeader::new("Isotherms")
+            .id_source("isotherms_header")
+            .show(ui, |ui| {
+                let temp_format = global_state.temp_format();
+                let mut isotherms = global_state.thermal_capturer_settings.isotherms.clone();
+                let mut changed = false;
+
+                let mut isotherm_to_remove = None;
+                for (i, isotherm) in isotherms.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        changed |= ui.checkbox(&mut isotherm.enabled, "").changed();
+                        egui::ComboBox::from_id_source(("isotherm_mode", i))
+                            .selected_text(isotherm.mode.name())
+                            .width(80.0)
+                            .show_ui(ui, |ui| {
+                                for mode in [
+                                    IsothermMode::Above,
+                                    IsothermMode::Below,
+                                    IsothermMode::Between,
+                                ] {
+                                    changed |= ui
+                                        .selectable_value(&mut isotherm.mode, mode, mode.name())
+                                        .changed();
+                                }
+                            });
+                        if isotherm.mode != IsothermMode::Below {
+                            changed |= temperature_edit_field(ui, temp_format, &mut isotherm.lower)
+                                .changed();
+                        }
+                        if isotherm.mode == IsothermMode::Between {
+                            ui.label("and");
+                        }
+                        if isotherm.mode != IsothermMode::Above {
+                            changed |= temperature_edit_field(ui, temp_format, &mut isotherm.upper)
+                                .changed();
+                        }
+                        changed |= ui.color_edit_button_srgba(&mut isotherm.color).changed();
+                        if ui.small_button("Remove").clicked() {
+                            isotherm_to_remove = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = isotherm_to_remove {
+                    isotherms.remove(i);
+                    changed = true;
+                }
+
+                if ui
+                    .add_enabled(isotherms.len() < MAX_ISOTHERMS, Button::new("Add isotherm"))
+                    .on_hover_text("Draw a temperature band in a solid color")
+                    .clicked()
+                {
+                    isotherms.push(Isotherm::new(
+                        IsothermMode::Above,
+                        Temp::from_celsius(60.0),
+                        Temp::from_celsius(80.0),
+                        egui::Color32::RED,
+                    ));
+                    changed = true;
+                }
+
+                if changed {
+                    app.edit_settings(move |settings| settings.isotherms = isotherms);
+                }
+            });
+
+        ui.separator();
+
+        CollapsingHeader::new("Contour Lines")
+            .id_source("contour_lines_header")
+            .show(ui, |ui| {
+                let temp_format = global_state.temp_format();
+                let mut show_contours = global_state.thermal_capturer_settings.show_contours;
+                let mut contour_levels = global_state
+                    .thermal_capturer_settings
+                    .contour_levels
+                    .clone();
+                let mut changed = ui
+                    .checkbox(&mut show_contours, "Show contour lines")
+                    .changed();
+
+                let mut level_to_remove = None;
+                for (i, level) in contour_levels.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        changed |= temperature_edit_field(ui, temp_format, level).changed();
+                        if ui.small_button("Remove").clicked() {
+                            level_to_remove = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = level_to_remove {
+                    contour_levels.remove(i);
+                    changed = true;
+                }
+
+                if ui
+                    .add_enabled(
+                        contour_levels.len() < MAX_CONTOUR_LEVELS,
+                        Button::new("Add level"),
+                    )
+                    .clicked()
+                {
+                    let new_level = contour_levels
+                        .last()
+                        .map(|l| *l + TempDelta::new(10.0))
+                        .unwrap_or(Temp::from_celsius(30.0));
+                    contour_levels.push(new_level);
+                    changed = true;
+                }
+
+                if changed {
+                    app.edit_settings(move |settings| {
+                        settings.show_contours = show_contours;
+                        settings.contour_levels = contour_levels;
+                    });
+                }
+            });
+
+        CollapsingHeader::new("Visible Camera Fusion")
+            .id_source("fusion_header")
+            .show(ui, |ui| {
+                let mut changed = false;
+                let visible_camera_name = global_state
+                    .visible_capturer
+                    .as_ref()
+                    .map(|visible_capturer| visible_capturer.name.clone());
+                if let Some(name) = visible_camera_name {
+                    ui.horizontal(|ui| {
+                        ui.label(name);
+                        if ui.button("Close").clicked() {
+                            app.update(|global_state| {
+                                global_state.visible_capturer = None;
+                                global_state
+                                    .edit_settings(|settings| settings.visible_frames = None);
+                            });
+                        }
+                    });
+                } else {
+                    // regular webcams don't have an adapter
+                    let cameras: Vec<(CameraIndex, String)> = self
+                        .cameras
+                        .as_ref()
+                        .map(|cameras| {
+                            cameras
+                                .iter()
+                                .filter(|camera| camera.adapter.is_none())
+                                .map(|camera| {
+                                    (camera.info.index().clone(), camera.info.human_name())
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default();
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_source("visible_camera")
+                            .selected_text(
+                                cameras
+                                    .iter()
+                                    .find(|(index, _)| {
+                                        Some(index) == self.visible_camera_index.as_ref()
+                                    })
+                                    .map(|(_, name)| name.as_str())
+                                    .unwrap_or("No camera selected"),
+                            )
+                            .width(160.0)
+                            .show_ui(ui, |ui| {
+                                for (index, name) in cameras.iter() {
+                                    ui.selectable_value(
+                                        &mut self.visible_camera_index,
+                                        Some(index.clone()),
+                                        name,
+                                    );
+                                }
+                            });
+                        if ui
+                            .add_enabled(self.visible_camera_index.is_some(), Button::new("Open"))
+                            .clicked()
+                        {
+                            let index = self.visible_camera_index.clone().unwrap();
+                            let name = cameras
+                                .iter()
+                                .find(|(i, _)| *i == index)
+                                .map(|(_, name)| name.clone())
+                                .unwrap_or_default();
+                            match VisibleCapturer::open(index, name) {
+                                Ok(visible_capturer) => {
+                                    app.update(move |global_state| {
+                                        let frames = visible_capturer.frames();
+                                        global_state.edit_settings(|settings| {
+                                            settings.visible_frames = Some(frames)
+                                        });
+                                        global_state.visible_capturer = Some(visible_capturer);
+                                    });
+                                    self.visible_camera_error = None;
+                                }
+                                Err(err) => {
+                                    self.visible_camera_error =
+                                        Some(format!("Failed to open camera: {}", err));
+                                }
+                            }
+                        }
+                    });
+                }
+                if let Some(error) = global_state
+                    .visible_capturer
+                    .as_ref()
+                    .and_then(|visible_capturer| visible_capturer.error())
+                    .or(self.visible_camera_error.clone())
+                {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+
+                let mut fusion = global_state.thermal_capturer_settings.fusion;
+                ui.horizontal(|ui| {
+                    ui.label("Mode");
+                    for mode in FusionMode::ALL {
+                        changed |= ui
+                            .selectable_value(&mut fusion.mode, mode, mode.name())
+                            .changed();
+                    }
+                });
+                match fusion.mode {
+                    FusionMode::Blend => {
+                        changed |= ui
+                            .add(
+                                egui::Slider::new(&mut fusion.opacity, 0.0..=1.0)
+                                    .text("Thermal opacity"),
+                            )
+                            .changed();
+                    }
+                    FusionMode::Edges => {
+                        changed |= ui
+                            .add(
+                                egui::Slider::new(
+                                    &mut fusion.edge_strength,
+                                    0.0..=MAX_EDGE_STRENGTH,
+                                )
+                                .text("Edge strength"),
+                            )
+                            .changed();
+                    }
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Offset");
+                    changed |= ui
+                        .add(
+                            DragValue::new(&mut fusion.offset.0)
+                                .speed(0.002)
+                                .clamp_range(-0.5..=0.5)
+                                .prefix("x: "),
+                        )
+                        .changed();
+                    changed |= ui
+                        .add(
+                            DragValue::new(&mut fusion.offset.1)
+                                .speed(0.002)
+                                .clamp_range(-0.5..=0.5)
+                                .prefix("y: "),
+                        )
+                        .changed();
+                    ui.label("Scale");
+                    changed |= ui
+                        .add(
+                            DragValue::new(&mut fusion.scale)
+                                .speed(0.005)
+                                .clamp_range(MIN_FUSION_SCALE..=MAX_FUSION_SCALE),
+                        )
+                        .on_hover_text("Width of the thermal image relative to the visible one")
+                        .changed();
+                });
+
+                if changed {
+                    app.edit_settings(move |settings| settings.fusion = fusion);
+                }
+            });
+
         ui.separator();
 
         // Curve editor
@@ -345,33 +1530,33 @@ impl Pane for SetupPane {
                     .map(|r| r.image_range)
                     .unwrap_or(manual_range);
                 let unit = global_state.preferred_temperature_unit();
+                let mut curve = global_state
+                    .thermal_capturer_settings
+                    .dynamic_range_curve
+                    .clone();
                 if dynamic_curve_editor(
                     ui,
                     "main_curve_editor",
-                    &mut global_state.thermal_capturer_settings,
+                    &mut curve,
+                    &global_state.thermal_capturer_settings.gradient,
                     curr_range,
                     unit,
                 )
                 .changed()
                 {
-                    let settings_clone = global_state.thermal_capturer_settings.clone();
-                    if let Some(thermal_capturer) = global_state.thermal_capturer_inst.as_mut() {
-                        thermal_capturer.set_settings(settings_clone);
-                    }
+                    app.edit_settings(move |settings| settings.dynamic_range_curve = curve);
                 }
             });
 
         ui.separator();
 
+        let mut gradient = global_state.thermal_capturer_settings.gradient.clone();
         if self
             .gradient_selector
-            .draw(ui, &mut global_state.thermal_capturer_settings.gradient)
+            .draw(ui, &mut gradient, &global_state.custom_gradients)
             .changed()
         {
-            let settings_clone = global_state.thermal_capturer_settings.clone();
-            if let Some(thermal_capturer) = global_state.thermal_capturer_inst.as_mut() {
-                thermal_capturer.set_settings(settings_clone.clone());
-            }
+            app.edit_settings(move |settings| settings.gradient = gradient);
         }
     }
 }
@@ -0,0 +1,114 @@
+use eframe::{egui, epaint::Color32};
+
+use crate::{
+    pane_dispatcher::{Pane, PaneContext, PaneKind},
+    recorders::recorder::RecorderState,
+    thermal_capturer::ThermalCapturerStatus,
+};
+
+// The real frame rate is shown in yellow below this fraction of the reported one
+const LOW_FPS_FRACTION: f32 = 0.8;
+
+///
+/// One line telling whether the capture is healthy: the camera and its connection,
+/// the frame rates, the frames which were dropped or late, and what is being recorded.
+///
+pub struct StatusBarPane {}
+
+impl StatusBarPane {
+    pub fn new() -> StatusBarPane {
+        StatusBarPane {}
+    }
+}
+
+impl Pane for StatusBarPane {
+    fn title(&self) -> egui::WidgetText {
+        "Status".into()
+    }
+
+    fn kind(&self) -> Option<PaneKind> {
+        Some(PaneKind::StatusBar)
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, app: &mut PaneContext<'_>) {
+        let global_state = app.global_state;
+
+        ui.horizontal_wrapped(|ui| {
+            let Some(capturer) = global_state.thermal_capturer_inst.as_ref() else {
+                ui.weak("No camera open");
+                return;
+            };
+
+            match global_state.thermal_camera_name.as_deref() {
+                Some(name) => {
+                    ui.label(name);
+                    match capturer.status() {
+                        ThermalCapturerStatus::Running => {
+                            ui.colored_label(Color32::GREEN, "● Connected");
+                        }
+                        ThermalCapturerStatus::Reconnecting { error } => {
+                            ui.colored_label(Color32::YELLOW, "● Reconnecting")
+                                .on_hover_text(error);
+                        }
+                    }
+                }
+                None => {
+                    ui.label("Playing back a file");
+                }
+            }
+            ui.separator();
+
+            if let Some(res) = global_state.last_thermal_capturer_result.as_ref() {
+                let fps_text = format!(
+                    "{:.1} / {:.1} FPS",
+                    res.real_fps, res.reported_fps
+                );
+                if res.reported_fps > 0.0 && res.real_fps < res.reported_fps * LOW_FPS_FRACTION
+                {
+                    ui.colored_label(Color32::YELLOW, fps_text)
+                } else {
+                    ui.label(fps_text)
+                }
+                .on_hover_text("Real frame rate / frame rate reported by the camera");
+                ui.separator();
+
+                ui.label(format!("Late: {}", res.late_frames))
+                    .on_hover_text("Frames which arrived later than expected from the frame rate");
+            }
+            ui.label(format!("Dropped: {}", capturer.dropped_frames()))
+                .on_hover_text(
+                    "Frames which weren't shown, because the display was behind or processing them failed",
+                );
+            ui.separator();
+
+            let mut recording = vec![];
+            if let Some(res) = global_state.last_thermal_capturer_result.as_ref() {
+                if res.extension.is_recording_radiometric {
+                    recording.push("radiometric");
+                }
+                if res.extension.is_logging_measurements {
+                    recording.push("measurement log");
+                }
+                if res.extension.is_triggered_recording_active {
+                    recording.push("alarm");
+                }
+            }
+            if global_state
+                .thermal_capturer_settings
+                .recorders
+                .iter()
+                .any(|recorder| recorder.lock().unwrap().state() == RecorderState::Recording)
+            {
+                recording.push("video");
+            }
+            if recording.is_empty() {
+                ui.weak("Not recording");
+            } else {
+                ui.colored_label(
+                    Color32::RED,
+                    format!("● Recording {}", recording.join(", ")),
+                );
+            }
+        });
+    }
+}
@@ -0,0 +1,196 @@
+use std::{
+    collections::HashSet,
+    time::{Duration, Instant},
+};
+
+use eframe::{egui, emath::Vec2b, epaint::Vec2};
+use egui_plot::{Legend, Line, Plot, PlotPoints};
+use uuid::Uuid;
+
+use crate::pane_dispatcher::{Pane, PaneContext, PaneKind};
+
+///
+/// Charts the temperatures of the gizmos over time, from the history kept by
+/// `HistoryDataCollector`. Spots and areas are charted by their mean.
+///
+pub struct TemporalPlotPane {
+    display_duration: Duration,
+
+    // Gizmos unchecked by the user, charted gizmos are the rest
+    hidden_gizmos: HashSet<Uuid>,
+}
+
+impl TemporalPlotPane {
+    const POSSIBLE_DURATIONS: [Duration; 4] = [
+        Duration::from_secs(60 * 30),
+        Duration::from_secs(60 * 15),
+        Duration::from_secs(60 * 5),
+        Duration::from_secs(60),
+    ];
+    pub fn new() -> TemporalPlotPane {
+        TemporalPlotPane {
+            display_duration: Self::POSSIBLE_DURATIONS[3],
+            hidden_gizmos: HashSet::new(),
+        }
+    }
+
+    fn duration_to_string(duration: Duration) -> String {
+        let minutes = duration.as_secs() / 60;
+        let seconds = duration.as_secs() % 60;
+        let mut str = "".to_string();
+        if minutes > 0 {
+            str.push_str(&format!("{}m", minutes));
+        }
+        if seconds > 0 {
+            str.push_str(&format!("{}s", seconds));
+        }
+        str
+    }
+}
+
+impl Pane for TemporalPlotPane {
+    fn title(&self) -> egui::WidgetText {
+        "Chart".into()
+    }
+
+    fn kind(&self) -> Option<PaneKind> {
+        Some(PaneKind::TemporalPlot)
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, app: &mut PaneContext<'_>) {
+        let global_state = app.global_state;
+
+        let gizmos = global_state
+            .thermal_capturer_settings
+            .gizmo
+            .children()
+            .unwrap();
+
+        let now = global_state
+            .last_thermal_capturer_result
+            .as_ref()
+            .map(|cr| cr.capture_time)
+            .unwrap_or(Instant::now());
+        let start_of_range = now - self.display_duration;
+
+        let temp_format = global_state.temp_format();
+        let unit = temp_format.unit;
+        let unit_suffix = unit.suffix();
+        egui::menu::bar(ui, |ui| {
+            ui.menu_button("Gizmos", |ui| {
+                for gizmo in gizmos.iter() {
+                    let mut shown = !self.hidden_gizmos.contains(&gizmo.uuid);
+                    if ui.checkbox(&mut shown, gizmo.name.as_str()).changed() {
+                        if shown {
+                            self.hidden_gizmos.remove(&gizmo.uuid);
+                        } else {
+                            self.hidden_gizmos.insert(gizmo.uuid);
+                        }
+                    }
+                }
+            });
+
+            if ui
+                .button("Export CSV…")
+                .on_hover_text("Save the charted values in the shown time window")
+                .clicked()
+            {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("CSV", &["csv"])
+                    .set_file_name("thermal-cat-chart.csv")
+                    .save_file()
+                {
+                    let columns: Vec<(Uuid, String)> = gizmos
+                        .iter()
+                        .filter(|g| !self.hidden_gizmos.contains(&g.uuid))
+                        .map(|g| (g.uuid, g.name.clone()))
+                        .collect();
+                    if let Err(err) = global_state.history_data_collector.export_csv(
+                        &path,
+                        &columns,
+                        start_of_range,
+                        now,
+                        unit,
+                    ) {
+                        tracing::error!(
+                            error = %err,
+                            path = %path.display(),
+                            "Failed to export chart"
+                        );
+                    }
+                }
+            }
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
+                Self::POSSIBLE_DURATIONS.iter().for_each(|&duration| {
+                    ui.selectable_value(
+                        &mut self.display_duration,
+                        duration,
+                        Self::duration_to_string(duration),
+                    );
+                });
+            });
+        });
+
+        let plot_ret = Plot::new("Chart")
+            .auto_bounds(Vec2b::TRUE)
+            .set_margin_fraction(Vec2::new(0.0, 0.1))
+            .include_x(0.0)
+            .include_x(-self.display_duration.as_secs_f64())
+            .allow_scroll(false)
+            .allow_zoom(false)
+            .allow_drag(false)
+            .allow_boxed_zoom(false)
+            .allow_double_click_reset(false)
+            .legend(Legend::default())
+            .y_axis_label(format!("Temperature ({})", unit_suffix))
+            .y_axis_formatter(move |grid_mark, _max_chars, _range| {
+                format!("{:.0} {}", grid_mark.value, unit_suffix)
+            })
+            .x_axis_formatter(move |grid_mark, _max_chars, _range| {
+                let dur = Duration::from_secs_f64(grid_mark.value.abs());
+                TemporalPlotPane::duration_to_string(dur)
+            })
+            .label_formatter(move |lbl: &str, p| {
+                format!(
+                    "{} {} {}",
+                    temp_format.format_value(p.y),
+                    temp_format.suffix(),
+                    lbl
+                )
+            })
+            .show(ui, |plot_ui| {
+                gizmos
+                    .iter()
+                    .filter(|gizmo| !self.hidden_gizmos.contains(&gizmo.uuid))
+                    .for_each(|gizmo| {
+                        let mut points = vec![];
+                        global_state.history_data_collector.for_each_data_point(
+                            gizmo.uuid,
+                            start_of_range,
+                            now,
+                            |data_point| {
+                                points.push([
+                                    -(now - data_point.time).as_secs_f64(),
+                                    data_point.charted_temperature().to_unit(unit) as f64,
+                                ]);
+                            },
+                        );
+                        let line = Line::new(PlotPoints::new(points))
+                            .color(gizmo.color)
+                            .name(gizmo.name.clone());
+                        plot_ui.line(line);
+                    })
+            });
+
+        if plot_ret.response.hovered() {
+            let scroll_delta_y = ui.input(|i: &egui::InputState| i.smooth_scroll_delta.y);
+            if scroll_delta_y != 0.0 {
+                let duration_secs = self.display_duration.as_secs() as f64;
+                let new_duration_secs: f64 = duration_secs - (scroll_delta_y as f64 / 3.0);
+                let new_duration_secs = new_duration_secs.max(5.0);
+                self.display_duration = Duration::from_secs_f64(new_duration_secs);
+            }
+        }
+    }
+}
@@ -1,27 +1,42 @@
-use std::{cell::RefCell, rc::Rc};
+use std::borrow::Cow;
 
 use eframe::{
     egui::{
         self,
         load::{TextureLoadResult, TexturePoll},
-        Button, DragValue, Image, Layout, Response, RichText, SizeHint, Slider, TextureOptions, Ui,
-        Widget,
+        Button, CursorIcon, DragValue, Image, Key, Layout, Response, RichText, Sense, SizeHint,
+        Slider, TextEdit, TextureFilter, TextureId, TextureOptions, Ui, Widget,
     },
-    emath::Align2,
-    epaint::{Color32, TextureHandle, Vec2},
+    emath::{Align2, Pos2, Rect},
+    epaint::{Color32, ColorImage, FontId, Stroke, TextureHandle, Vec2},
 };
-use egui_plot::{MarkerShape, Plot, PlotBounds, PlotImage, PlotPoint, Points, Text};
+use egui_plot::{Arrows, Line, MarkerShape, Plot, PlotBounds, PlotImage, PlotPoint, Points, Text};
+use uuid::Uuid;
 
 use crate::{
-    gizmos::GizmoKind, pane_dispatcher::Pane, thermal_data::ThermalDataPos,
-    widgets::selectable_image_label::SelectableImageLabel, AppGlobalState,
+    annotations::{Annotation, AnnotationKind, DEFAULT_ANNOTATION_COLOR},
+    change_detection::{MAX_CHANGE_BASELINE_SECS, MIN_CHANGE_BASELINE_SECS},
+    contour_lines::{contour_segments, CONTOUR_COLORS},
+    fusion::{DisplayMode, FusionMode, MIN_PIP_SIZE},
+    gizmos::{GizmoHandle, GizmoKind},
+    grid_analysis::{GridAnalysis, GridMetric, MAX_GRID_CELLS, MIN_GRID_CELLS},
+    keyboard_shortcuts::ShortcutAction,
+    pane_dispatcher::{Pane, PaneContext, PaneKind},
+    temperature::{format_reading, format_temp, Temp, TempDelta, TemperatureUnit},
+    thermal_capturer::{ThermalCapturerResult, ThermalCapturerSettings, ThermalCapturerStatus},
+    thermal_data::{ThermalDataPos, ThermalDataRect},
+    thermal_gradient::{create_scale_image, COLOR_SCALE_TICKS},
+    widgets::selectable_image_label::SelectableImageLabel,
 };
 
 pub struct ThermalDisplayPane {
-    global_state: Rc<RefCell<AppGlobalState>>,
-
     camera_texture: Option<egui::TextureHandle>,
+    // Shown texture, camera_texture or the one of the GPU colorizer
+    camera_texture_id: Option<TextureId>,
     camera_image_size: Option<(usize, usize)>,
+    visible_texture: Option<egui::TextureHandle>,
+    // Pixels changing faster than the threshold, drawn over the camera image
+    change_mask_texture: Option<egui::TextureHandle>,
 
     crosshair_texture_load_result: Option<TextureLoadResult>,
     crosshair_texture: Option<egui::TextureHandle>,
@@ -31,24 +46,401 @@ pub struct ThermalDisplayPane {
     external_zoom_factor_changed: bool,
 
     maximized: bool,
+
+    // Gizmo handle currently being dragged with the mouse, with the last pointer position
+    dragged_gizmo: Option<(Uuid, GizmoHandle, ThermalDataPos)>,
+
+    // Gizmo being dragged out with the mouse, with the position where the drag started
+    new_gizmo_start: Option<(Uuid, ThermalDataPos)>,
+
+    // Kind of gizmo or annotation added by clicking or dragging on the image
+    measurement_tool: MeasurementTool,
+
+    // Annotation being dragged out with the mouse
+    new_annotation: Option<Uuid>,
+    // Text and color of the next annotation
+    annotation_text: String,
+    annotation_color: Color32,
+
+    // Corner where the user started dragging out the histogram region (with Shift held)
+    region_selection_start: Option<ThermalDataPos>,
+
+    color_scale_texture: Option<egui::TextureHandle>,
+}
+
+// Distance in screen pixels within which a gizmo can be grabbed with the mouse
+const GIZMO_GRAB_RADIUS: f32 = 12.0;
+
+// Size of the color scale on screen, in points
+const COLOR_SCALE_WIDTH: f32 = 14.0;
+const COLOR_SCALE_MARGIN: f32 = 8.0;
+// Resolution of the color scale texture
+const COLOR_SCALE_STEPS: usize = 256;
+// Tint of the pixels changing faster than the threshold
+const CHANGE_HIGHLIGHT_COLOR: Color32 = Color32::from_rgba_premultiplied(120, 0, 120, 120);
+
+#[derive(Clone, Copy, PartialEq)]
+enum MeasurementTool {
+    // Added with a click
+    Spot,
+    // Dragged out from one point to another
+    Line,
+    Rect,
+    Ellipse,
+    // Annotations, text is added with a click, arrows and freehand drawings are dragged out
+    Text,
+    Arrow,
+    Freehand,
+}
+
+impl MeasurementTool {
+    fn is_annotation(&self) -> bool {
+        matches!(
+            self,
+            MeasurementTool::Text | MeasurementTool::Arrow | MeasurementTool::Freehand
+        )
+    }
+
+    ///
+    /// Returns the annotation to start dragging out at `pos`, see `new_gizmo`.
+    ///
+    fn new_annotation(&self, pos: [f32; 2]) -> Option<AnnotationKind> {
+        match self {
+            MeasurementTool::Arrow => Some(AnnotationKind::Arrow {
+                start: pos,
+                end: pos,
+            }),
+            MeasurementTool::Freehand => Some(AnnotationKind::Freehand { points: vec![pos] }),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Returns the gizmo to start dragging out at `pos`, or None for tools which add gizmos by clicking.
+    ///
+    fn new_gizmo(&self, pos: ThermalDataPos) -> Option<(GizmoKind, &'static str)> {
+        match self {
+            MeasurementTool::Spot
+            | MeasurementTool::Text
+            | MeasurementTool::Arrow
+            | MeasurementTool::Freehand => None,
+            MeasurementTool::Line => Some((
+                GizmoKind::Line {
+                    start: pos,
+                    end: pos,
+                },
+                "Line",
+            )),
+            MeasurementTool::Rect => Some((
+                GizmoKind::Rect {
+                    rect: ThermalDataRect::from_corners(pos, pos),
+                    emissivity: None,
+                },
+                "Area",
+            )),
+            MeasurementTool::Ellipse => Some((
+                GizmoKind::Ellipse {
+                    rect: ThermalDataRect::from_corners(pos, pos),
+                },
+                "Ellipse",
+            )),
+        }
+    }
 }
 
 impl ThermalDisplayPane {
-    pub fn new(global_state: Rc<RefCell<AppGlobalState>>) -> ThermalDisplayPane {
+    pub fn new() -> ThermalDisplayPane {
         ThermalDisplayPane {
-            global_state,
             camera_texture: None,
+            camera_texture_id: None,
             crosshair_texture_load_result: None,
             crosshair_texture: None,
             camera_image_size: None,
+            visible_texture: None,
+            change_mask_texture: None,
             zoom_to_fit: true,
             external_zoom_factor: 1.0,
             external_zoom_factor_changed: false,
             maximized: false,
+            dragged_gizmo: None,
+            new_gizmo_start: None,
+            measurement_tool: MeasurementTool::Spot,
+            new_annotation: None,
+            annotation_text: "Note".to_string(),
+            annotation_color: DEFAULT_ANNOTATION_COLOR,
+            region_selection_start: None,
+            color_scale_texture: None,
+        }
+    }
+
+    ///
+    /// Draws the colors of the mapping range as a bar at the right edge of `rect`,
+    /// with the temperatures along it.
+    ///
+    fn draw_color_scale(
+        &mut self,
+        ui: &Ui,
+        rect: Rect,
+        settings: &ThermalCapturerSettings,
+        res: &ThermalCapturerResult,
+        temp_unit: TemperatureUnit,
+        temp_precision: usize,
+    ) {
+        let range = res.image_range;
+        let texture = self.color_scale_texture.insert(ui.ctx().load_texture(
+            "color_scale",
+            create_scale_image(1, COLOR_SCALE_STEPS, |factor| {
+                settings.value_to_color(
+                    range.factor_to_temp(factor),
+                    range,
+                    res.equalization.as_deref(),
+                    res.is_delta,
+                )
+            }),
+            TextureOptions::LINEAR,
+        ));
+
+        let bar = Rect::from_min_max(
+            Pos2::new(
+                rect.right() - COLOR_SCALE_MARGIN - COLOR_SCALE_WIDTH,
+                rect.top() + rect.height() * 0.1,
+            ),
+            Pos2::new(
+                rect.right() - COLOR_SCALE_MARGIN,
+                rect.bottom() - rect.height() * 0.1,
+            ),
+        );
+        let painter = ui.painter_at(rect);
+        painter.image(
+            texture.id(),
+            bar,
+            Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0)),
+            Color32::WHITE,
+        );
+        painter.rect_stroke(bar, 0.0, Stroke::new(1.0, Color32::WHITE));
+
+        for i in 0..COLOR_SCALE_TICKS {
+            let factor = i as f32 / (COLOR_SCALE_TICKS - 1) as f32;
+            let y = bar.bottom() - bar.height() * factor;
+            painter.line_segment(
+                [Pos2::new(bar.left() - 4.0, y), Pos2::new(bar.left(), y)],
+                Stroke::new(1.0, Color32::WHITE),
+            );
+            let text = format_reading(
+                range.factor_to_temp(factor),
+                temp_unit,
+                temp_precision,
+                res.is_delta,
+            );
+            // outlined by a shadow to be readable over any part of the image
+            for (offset, color) in [
+                (Vec2::splat(1.0), Color32::BLACK),
+                (Vec2::ZERO, Color32::WHITE),
+            ] {
+                painter.text(
+                    Pos2::new(bar.left() - 6.0, y) + offset,
+                    Align2::RIGHT_CENTER,
+                    &text,
+                    FontId::proportional(12.0),
+                    color,
+                );
+            }
+        }
+    }
+
+    ///
+    /// Play/pause button and timeline slider, shown while playing back a recording.
+    ///
+    fn build_playback_ui(&mut self, ui: &mut egui::Ui, app: &mut PaneContext<'_>) {
+        let Some(position) = app
+            .global_state
+            .last_thermal_capturer_result
+            .as_ref()
+            .and_then(|r| r.playback_position)
+        else {
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            if ui
+                .button(if position.paused { "▶" } else { "⏸" })
+                .on_hover_text(if position.paused { "Play" } else { "Pause" })
+                .clicked()
+            {
+                app.with_capturer(move |thermal_capturer| {
+                    thermal_capturer.set_playback_paused(!position.paused)
+                });
+            }
+
+            let mut frame_index = position.frame_index;
+            ui.spacing_mut().slider_width = (ui.available_width() - 120.0).max(50.0);
+            if Slider::new(&mut frame_index, 0..=position.frame_count.max(1) - 1)
+                .show_value(false)
+                .ui(ui)
+                .changed()
+            {
+                app.with_capturer(move |thermal_capturer| {
+                    thermal_capturer.seek_playback(frame_index)
+                });
+            }
+
+            let format_time = |us: u64| {
+                let secs = us / 1_000_000;
+                format!("{:02}:{:02}", secs / 60, secs % 60)
+            };
+            ui.label(format!(
+                "{} / {}",
+                format_time(position.timestamp_us),
+                format_time(position.duration_us)
+            ));
+        });
+    }
+
+    ///
+    /// Shows the visible image with the thermal one in an insert, which can be moved
+    /// by dragging it and resized by dragging its bottom right corner.
+    ///
+    fn build_picture_in_picture_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        app: &mut PaneContext<'_>,
+        visible: &ColorImage,
+    ) {
+        let (Some(thermal_texture_id), Some(img_size)) =
+            (self.camera_texture_id, self.camera_image_size)
+        else {
+            return;
+        };
+        let visible_texture = self.visible_texture.insert(ui.ctx().load_texture(
+            "visible_ctx",
+            visible.clone(),
+            TextureOptions::LINEAR,
+        ));
+        let area = ui.add(Image::new(&*visible_texture).shrink_to_fit()).rect;
+
+        let mut fusion = app.global_state.thermal_capturer_settings.fusion;
+        let width = fusion.pip_size.clamp(MIN_PIP_SIZE, 1.0) * area.width();
+        let height = (width * img_size.1 as f32 / img_size.0 as f32).min(area.height());
+        let insert = Rect::from_min_size(
+            area.min + Vec2::new(fusion.pip_position.0, fusion.pip_position.1) * area.size(),
+            Vec2::new(width, height),
+        );
+        ui.painter().image(
+            thermal_texture_id,
+            insert,
+            Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0)),
+            Color32::WHITE,
+        );
+        ui.painter()
+            .rect_stroke(insert, 0.0, Stroke::new(1.0, Color32::WHITE));
+
+        // the corner handle is added last so it's on top of the insert
+        let move_response = ui
+            .interact(insert, ui.id().with("pip_insert"), Sense::drag())
+            .on_hover_cursor(CursorIcon::Grab);
+        let resize_response = ui
+            .interact(
+                Rect::from_center_size(insert.right_bottom(), Vec2::splat(12.0)),
+                ui.id().with("pip_resize"),
+                Sense::drag(),
+            )
+            .on_hover_cursor(CursorIcon::ResizeNwSe);
+
+        let mut changed = false;
+        if move_response.dragged() {
+            let delta = move_response.drag_delta() / area.size();
+            fusion.pip_position.0 += delta.x;
+            fusion.pip_position.1 += delta.y;
+            changed = true;
+        }
+        if resize_response.dragged() {
+            fusion.pip_size += resize_response.drag_delta().x / area.width();
+            changed = true;
+        }
+        if changed {
+            fusion.pip_size = fusion.pip_size.clamp(MIN_PIP_SIZE, 1.0);
+            // keep the whole insert within the visible image
+            let height = fusion.pip_size * area.width() * img_size.1 as f32
+                / img_size.0 as f32
+                / area.height();
+            fusion.pip_position.0 = fusion.pip_position.0.clamp(0.0, 1.0 - fusion.pip_size);
+            fusion.pip_position.1 = fusion.pip_position.1.clamp(0.0, (1.0 - height).max(0.0));
+
+            app.edit_settings(move |settings| settings.fusion = fusion);
         }
     }
 
-    fn build_toolbar_ui(&mut self, ui: &mut egui::Ui, global_state: &mut AppGlobalState) {
+    fn build_grid_menu(&mut self, ui: &mut egui::Ui, app: &mut PaneContext<'_>) {
+        let global_state = app.global_state;
+        let mut grid = global_state.thermal_capturer_settings.grid_analysis;
+        let mut changed = false;
+        let mut export = false;
+        ui.menu_button(if grid.enabled { "Grid ●" } else { "Grid" }, |ui| {
+            changed |= ui.checkbox(&mut grid.enabled, "Show grid").changed();
+            ui.horizontal(|ui| {
+                changed |= ui
+                    .add(
+                        DragValue::new(&mut grid.columns)
+                            .clamp_range(MIN_GRID_CELLS..=MAX_GRID_CELLS)
+                            .suffix(" columns"),
+                    )
+                    .changed();
+                ui.label("×");
+                changed |= ui
+                    .add(
+                        DragValue::new(&mut grid.rows)
+                            .clamp_range(MIN_GRID_CELLS..=MAX_GRID_CELLS)
+                            .suffix(" rows"),
+                    )
+                    .changed();
+            });
+            ui.horizontal(|ui| {
+                for metric in [GridMetric::Mean, GridMetric::Max] {
+                    changed |= ui
+                        .selectable_value(&mut grid.metric, metric, metric.name())
+                        .changed();
+                }
+            });
+            if ui.button("Export CSV…").clicked() {
+                export = true;
+                ui.close_menu();
+            }
+        })
+        .response
+        .on_hover_text(
+            "Show the mean or max temperature of each cell of a grid, e.g. for panel surveys",
+        );
+
+        if changed {
+            app.edit_settings(move |settings| settings.grid_analysis = grid);
+        }
+        if export {
+            let Some(res) = global_state.last_thermal_capturer_result.as_ref() else {
+                return;
+            };
+            let settings = global_state.thermal_capturer_settings.grid_analysis;
+            // the values of the frame shown when the export was clicked
+            let grid = GridAnalysis::from_thermal_data(&res.thermal_data, &settings);
+            let is_delta = res.is_delta;
+            if let Some(path) = rfd::FileDialog::new()
+                .add_filter("CSV", &["csv"])
+                .set_file_name("thermal-cat-grid.csv")
+                .save_file()
+            {
+                if let Err(err) = grid.export_csv(
+                    &path,
+                    settings.metric,
+                    global_state.preferred_temperature_unit(),
+                    is_delta,
+                ) {
+                    tracing::error!(error = %err, path = %path.display(), "Failed to export grid");
+                }
+            }
+        }
+    }
+
+    fn build_toolbar_ui(&mut self, ui: &mut egui::Ui, app: &mut PaneContext<'_>) {
+        let global_state = app.global_state;
         ui.with_layout(
             Layout::left_to_right(egui::Align::Min)
                 .with_main_align(egui::Align::Min)
@@ -92,12 +484,11 @@ impl ThermalDisplayPane {
                     ))
                     .clicked()
                 {
-                    global_state.thermal_capturer_settings.rotation =
-                        global_state.thermal_capturer_settings.rotation.next();
-                    let settings_clone = global_state.thermal_capturer_settings.clone();
-                    if let Some(thermal_capturer) = global_state.thermal_capturer_inst.as_mut() {
-                        thermal_capturer.set_settings(settings_clone);
-                    }
+                    app.update(|global_state| {
+                        global_state
+                            .edit_settings(|settings| settings.rotation = settings.rotation.next());
+                        global_state.remember_camera_orientation();
+                    });
                 }
 
                 if ui
@@ -109,11 +500,281 @@ impl ThermalDisplayPane {
                     ))
                     .clicked()
                 {
-                    global_state.thermal_capturer_settings.rotation =
-                        global_state.thermal_capturer_settings.rotation.prev();
-                    let settings_clone = global_state.thermal_capturer_settings.clone();
-                    if let Some(thermal_capturer) = global_state.thermal_capturer_inst.as_mut() {
-                        thermal_capturer.set_settings(settings_clone);
+                    app.update(|global_state| {
+                        global_state
+                            .edit_settings(|settings| settings.rotation = settings.rotation.prev());
+                        global_state.remember_camera_orientation();
+                    });
+                }
+
+                ui.add_space(8.0);
+
+                let mut paused = global_state.paused;
+                if ui
+                    .toggle_value(&mut paused, "Freeze")
+                    .on_hover_text(
+                        "Freeze the image on the current frame (Space), gizmos still measure it. Capturing, recording and alarms go on.",
+                    )
+                    .changed()
+                {
+                    app.action(ShortcutAction::TogglePause);
+                }
+
+                let mut smooth =
+                    global_state.thermal_capturer_settings.texture_filter == TextureFilter::Linear;
+                if ui
+                    .toggle_value(&mut smooth, "Smooth")
+                    .on_hover_text("Smooth the image when scaled, instead of showing crisp pixels")
+                    .changed()
+                {
+                    let texture_filter = if smooth {
+                        TextureFilter::Linear
+                    } else {
+                        TextureFilter::Nearest
+                    };
+                    app.edit_settings(move |settings| settings.texture_filter = texture_filter);
+                }
+
+                ui.menu_button("Markers", |ui| {
+                    let settings = &global_state.thermal_capturer_settings;
+                    let mut show_max_marker = settings.show_max_marker;
+                    let mut show_min_marker = settings.show_min_marker;
+                    let mut show_center_marker = settings.show_center_marker;
+                    let mut changed = false;
+                    changed |= ui
+                        .checkbox(&mut show_max_marker, "Hottest pixel")
+                        .changed();
+                    changed |= ui
+                        .checkbox(&mut show_min_marker, "Coldest pixel")
+                        .changed();
+                    changed |= ui.checkbox(&mut show_center_marker, "Center").changed();
+                    if changed {
+                        app.edit_settings(move |settings| {
+                            settings.show_max_marker = show_max_marker;
+                            settings.show_min_marker = show_min_marker;
+                            settings.show_center_marker = show_center_marker;
+                        });
+                    }
+                })
+                .response
+                .on_hover_text("Mark the hottest and coldest pixels and the center of the image");
+
+                let mut show_color_scale = global_state.thermal_capturer_settings.show_color_scale;
+                if ui
+                    .toggle_value(&mut show_color_scale, "Scale")
+                    .on_hover_text("Show the temperatures of the colors next to the image")
+                    .changed()
+                {
+                    app.edit_settings(move |settings| settings.show_color_scale = show_color_scale);
+                }
+
+                let (is_delta, has_reference) = global_state
+                    .last_thermal_capturer_result
+                    .as_ref()
+                    .map_or((false, false), |r| (r.is_delta, r.has_delta_reference));
+                ui.menu_button(if is_delta { "Delta ●" } else { "Delta" }, |ui| {
+                    if ui.button("Capture reference (R)").clicked() {
+                        app.update(|global_state| global_state.capture_delta_reference());
+                        ui.close_menu();
+                    }
+                    let mut delta_mode = global_state.thermal_capturer_settings.delta_mode;
+                    if ui
+                        .add_enabled(
+                            has_reference,
+                            egui::Checkbox::new(&mut delta_mode, "Show difference"),
+                        )
+                        .changed()
+                    {
+                        app.update(move |global_state| global_state.set_delta_mode(delta_mode));
+                    }
+                    if ui
+                        .add_enabled(has_reference, egui::Button::new("Clear reference"))
+                        .clicked()
+                    {
+                        app.update(|global_state| global_state.set_delta_mode(false));
+                        app.with_capturer(|thermal_capturer| {
+                            thermal_capturer.clear_delta_reference()
+                        });
+                        ui.close_menu();
+                    }
+                })
+                .response
+                .on_hover_text(
+                    "Show how much each pixel changed since a reference frame, e.g. after powering a board",
+                );
+
+                let unit = global_state.preferred_temperature_unit();
+                let precision = global_state.preferred_temperature_precision();
+                let max_rate = global_state
+                    .last_thermal_capturer_result
+                    .as_ref()
+                    .and_then(|r| r.change_detection.as_ref())
+                    .map(|change| change.max_rate_kelvin_per_sec);
+                let mut change_detection = global_state.thermal_capturer_settings.change_detection;
+                let mut changed = false;
+                ui.menu_button(
+                    if change_detection.highlight {
+                        "Changes ●"
+                    } else {
+                        "Changes"
+                    },
+                    |ui| {
+                        changed |= ui
+                            .checkbox(&mut change_detection.highlight, "Highlight fast changes")
+                            .changed();
+                        ui.horizontal(|ui| {
+                            ui.label("Faster than");
+                            let mut threshold =
+                                TempDelta::new(change_detection.threshold_kelvin_per_sec)
+                                    .to_unit(unit);
+                            if ui
+                                .add(
+                                    DragValue::new(&mut threshold)
+                                        .speed(0.1)
+                                        .clamp_range(0.1..=1000.0)
+                                        .suffix(format!(" {}/s", unit.suffix())),
+                                )
+                                .changed()
+                            {
+                                change_detection.threshold_kelvin_per_sec =
+                                    TempDelta::from_unit(unit, threshold)
+                                        .to_unit(TemperatureUnit::Kelvin);
+                                changed = true;
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Measured over");
+                            changed |= ui
+                                .add(
+                                    DragValue::new(&mut change_detection.baseline_secs)
+                                        .speed(0.1)
+                                        .clamp_range(
+                                            MIN_CHANGE_BASELINE_SECS..=MAX_CHANGE_BASELINE_SECS,
+                                        )
+                                        .suffix(" s"),
+                                )
+                                .on_hover_text("Longer is less noisy but slower to react")
+                                .changed();
+                        });
+                        if let Some(max_rate) = max_rate {
+                            ui.label(format!(
+                                "Fastest change: {}/s",
+                                TempDelta::new(max_rate).format(unit, precision)
+                            ));
+                        }
+                    },
+                )
+                .response
+                .on_hover_text(
+                    "Highlight the pixels whose temperature changes quickly, e.g. a part overheating",
+                );
+                if changed {
+                    app.edit_settings(move |settings| settings.change_detection = change_detection);
+                }
+
+                self.build_grid_menu(ui, app);
+
+                if global_state.visible_capturer.is_some() {
+                    ui.add_space(8.0);
+
+                    let mut fusion = global_state.thermal_capturer_settings.fusion;
+                    let mut changed = false;
+                    egui::ComboBox::from_id_source("display_mode")
+                        .selected_text(fusion.display.name())
+                        .show_ui(ui, |ui| {
+                            for mode in DisplayMode::ALL {
+                                changed |= ui
+                                    .selectable_value(&mut fusion.display, mode, mode.name())
+                                    .changed();
+                            }
+                        });
+                    if fusion.display == DisplayMode::Fused && fusion.mode == FusionMode::Blend {
+                        changed |= ui
+                            .add(Slider::new(&mut fusion.opacity, 0.0..=1.0).show_value(false))
+                            .on_hover_text("Visible on the left, thermal on the right")
+                            .changed();
+                    }
+                    if changed {
+                        app.edit_settings(move |settings| settings.fusion = fusion);
+                    }
+                }
+
+                ui.add_space(8.0);
+
+                for (tool, icon, hint) in [
+                    (
+                        MeasurementTool::Spot,
+                        egui::include_image!("../icons/crosshair_center.svg"),
+                        "Click to add a spot",
+                    ),
+                    (
+                        MeasurementTool::Line,
+                        egui::include_image!("../icons/slash.svg"),
+                        "Drag to add a line",
+                    ),
+                    (
+                        MeasurementTool::Rect,
+                        egui::include_image!("../icons/square.svg"),
+                        "Drag to add a rectangle",
+                    ),
+                    (
+                        MeasurementTool::Ellipse,
+                        egui::include_image!("../icons/circle.svg"),
+                        "Drag to add an ellipse",
+                    ),
+                    (
+                        MeasurementTool::Text,
+                        egui::include_image!("../icons/type.svg"),
+                        "Click to add a text label",
+                    ),
+                    (
+                        MeasurementTool::Arrow,
+                        egui::include_image!("../icons/move-up-right.svg"),
+                        "Drag to add an arrow",
+                    ),
+                    (
+                        MeasurementTool::Freehand,
+                        egui::include_image!("../icons/pencil.svg"),
+                        "Drag to draw",
+                    ),
+                ] {
+                    if tool == MeasurementTool::Text {
+                        ui.separator();
+                    }
+                    if ui
+                        .add(SelectableImageLabel::new(
+                            self.measurement_tool == tool,
+                            Image::new(icon)
+                                .max_height(14.0)
+                                .tint(ui.style().visuals.widgets.active.fg_stroke.color),
+                        ))
+                        .on_hover_text(if tool.is_annotation() {
+                            format!("{}. Right click or Delete to remove.", hint)
+                        } else {
+                            format!(
+                                "{}. Drag the handles to move or resize, right click or Delete to remove.",
+                                hint
+                            )
+                        })
+                        .clicked()
+                    {
+                        self.measurement_tool = tool;
+                    }
+                }
+                if self.measurement_tool.is_annotation() {
+                    if self.measurement_tool == MeasurementTool::Text {
+                        ui.add(TextEdit::singleline(&mut self.annotation_text).desired_width(80.0));
+                    }
+                    ui.color_edit_button_srgba(&mut self.annotation_color);
+                    if ui
+                        .add_enabled(
+                            !global_state.thermal_capturer_settings.annotations.is_empty(),
+                            Button::new("Clear"),
+                        )
+                        .on_hover_text("Remove all annotations")
+                        .clicked()
+                    {
+                        app.edit_settings(|settings| settings.annotations.clear());
                     }
                 }
 
@@ -149,28 +810,86 @@ impl Pane for ThermalDisplayPane {
         "Thermal Display".into()
     }
 
-    fn ui(&mut self, ui: &mut egui::Ui) {
-        let global_state_clone = self.global_state.clone();
-        let mut global_state = global_state_clone.as_ref().borrow_mut();
+    fn kind(&self) -> Option<PaneKind> {
+        Some(PaneKind::ThermalDisplay)
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, app: &mut PaneContext<'_>) {
+        let global_state = app.global_state;
 
         ui.centered_and_justified(|ui| {
             if let Some(res) = global_state.last_thermal_capturer_result.as_ref() {
-                self.camera_texture = Some(ui.ctx().load_texture(
-                    "cam_ctx",
-                    res.image.clone(),
-                    TextureOptions {
-                        magnification: egui::TextureFilter::Nearest,
-                        ..Default::default()
-                    },
-                ));
-                self.camera_image_size = Some((res.image.width(), res.image.height()));
+                self.camera_texture_id = Some(match global_state.gpu_texture_id {
+                    Some(texture_id) => texture_id,
+                    None => self
+                        .camera_texture
+                        .insert(ui.ctx().load_texture(
+                            "cam_ctx",
+                            res.image.clone(),
+                            TextureOptions {
+                                magnification:
+                                    global_state.thermal_capturer_settings.texture_filter,
+                                minification: global_state.thermal_capturer_settings.texture_filter,
+                                ..Default::default()
+                            },
+                        ))
+                        .id(),
+                });
+                // in sensor pixels, the image may be upscaled
+                self.camera_image_size = Some((res.thermal_data.width, res.thermal_data.height));
+
+                self.change_mask_texture = res
+                    .change_detection
+                    .as_ref()
+                    .filter(|_| {
+                        global_state
+                            .thermal_capturer_settings
+                            .change_detection
+                            .highlight
+                    })
+                    .map(|change| {
+                        let pixels = change
+                            .changed
+                            .iter()
+                            .map(|changed| {
+                                if *changed {
+                                    CHANGE_HIGHLIGHT_COLOR
+                                } else {
+                                    Color32::TRANSPARENT
+                                }
+                            })
+                            .collect();
+                        ui.ctx().load_texture(
+                            "change_mask",
+                            ColorImage {
+                                size: [change.width, change.height],
+                                pixels,
+                            },
+                            TextureOptions::NEAREST,
+                        )
+                    });
             }
 
             let gizmo_results = global_state
                 .last_thermal_capturer_result
                 .as_ref()
-                .map(|r| r.gizmo_results.clone())
+                .map(|r| r.extension.gizmo_results.clone())
                 .clone();
+            let is_delta = global_state
+                .last_thermal_capturer_result
+                .as_ref()
+                .is_some_and(|r| r.is_delta);
+            let markers = global_state
+                .last_thermal_capturer_result
+                .as_ref()
+                .map(|r| frame_markers(r, &global_state.thermal_capturer_settings))
+                .unwrap_or_default();
+            let grid_settings = global_state.thermal_capturer_settings.grid_analysis;
+            let grid = global_state
+                .last_thermal_capturer_result
+                .as_ref()
+                .filter(|_| grid_settings.enabled)
+                .map(|r| GridAnalysis::from_thermal_data(&r.thermal_data, &grid_settings));
 
             self.crosshair_texture_load_result.get_or_insert_with(|| {
                 egui::include_image!("../icons/crosshair_center.svg").load(
@@ -192,10 +911,36 @@ impl Pane for ThermalDisplayPane {
                 }
             }
             ui.vertical(|ui| {
-                self.build_toolbar_ui(ui, &mut global_state);
-                if let Some(texture) = self.camera_texture.as_ref() {
+                self.build_toolbar_ui(ui, app);
+                self.build_playback_ui(ui, app);
+                if global_state
+                    .thermal_capturer_inst
+                    .as_ref()
+                    .is_some_and(|capturer| capturer.status() != &ThermalCapturerStatus::Running)
+                {
+                    ui.colored_label(Color32::YELLOW, "Camera disconnected, reconnecting...");
+                }
+                let pip_visible = global_state
+                    .visible_capturer
+                    .as_ref()
+                    .filter(|_| {
+                        global_state.thermal_capturer_settings.fusion.display
+                            == DisplayMode::PictureInPicture
+                            && self.camera_texture_id.is_some()
+                    })
+                    .and_then(|capturer| capturer.frames().latest());
+                if let Some(visible) = pip_visible {
+                    self.build_picture_in_picture_ui(ui, app, &visible);
+                } else if let Some(texture) = self.camera_texture_id {
                     let img_size = self.camera_image_size.unwrap();
 
+                    // only copied once they are edited in the plot
+                    let settings = &global_state.thermal_capturer_settings;
+                    let mut root_gizmo = Cow::Borrowed(&settings.gizmo);
+                    let mut annotations = Cow::Borrowed(settings.annotations.as_slice());
+                    let mut histogram_region = settings.histogram_region;
+                    let mut histogram_region_changed = false;
+
                     let plot_response = Plot::new("thermal_display_plot")
                         .show_grid(false)
                         .show_axes(false)
@@ -243,15 +988,45 @@ impl Pane for ThermalDisplayPane {
                                 PlotPoint::new(img_size.0 as f64 / 2.0, img_size.1 as f64 / 2.0),
                                 Vec2::new(img_size.0 as f32, img_size.1 as f32),
                             ));
+                            if let Some(mask) = self.change_mask_texture.as_ref() {
+                                plot_ui.image(PlotImage::new(
+                                    mask,
+                                    PlotPoint::new(
+                                        img_size.0 as f64 / 2.0,
+                                        img_size.1 as f64 / 2.0,
+                                    ),
+                                    Vec2::new(img_size.0 as f32, img_size.1 as f32),
+                                ));
+                            }
 
                             let temp_unit = global_state.preferred_temperature_unit();
-                            global_state
-                                .thermal_capturer_settings
-                                .gizmo
-                                .children_mut()
+                            let temp_precision = global_state.preferred_temperature_precision();
+                            root_gizmo
+                                .children()
                                 .unwrap()
                                 .iter()
+                                .filter(|c| !c.hidden)
                                 .for_each(|c| {
+                                    match c.kind {
+                                        GizmoKind::Rect { rect, .. } => plot_ui.line(
+                                            Line::new(rect_outline(rect, img_size)).color(c.color),
+                                        ),
+                                        GizmoKind::Ellipse { rect } => plot_ui.line(
+                                            Line::new(ellipse_outline(rect, img_size))
+                                                .color(c.color),
+                                        ),
+                                        GizmoKind::Line { start, end } => {
+                                            let start = handle_plot_point(start, img_size);
+                                            let end = handle_plot_point(end, img_size);
+                                            plot_ui.line(
+                                                Line::new(vec![[start.x, start.y], [end.x, end.y]])
+                                                    .color(c.color)
+                                                    .width(2.0),
+                                            )
+                                        }
+                                        _ => {}
+                                    }
+
                                     let result =
                                         gizmo_results.as_ref().and_then(|r| r.get(&c.uuid));
                                     if let Some(result) = result {
@@ -288,10 +1063,11 @@ impl Pane for ThermalDisplayPane {
                                             plot_ui.text(
                                                 Text::new(
                                                     PlotPoint::new(x + 4.0, y),
-                                                    RichText::new(format!(
-                                                        "{:.1} {}",
-                                                        result.temperature.to_unit(temp_unit),
-                                                        temp_unit.suffix()
+                                                    RichText::new(format_reading(
+                                                        result.temperature,
+                                                        temp_unit,
+                                                        temp_precision,
+                                                        is_delta,
                                                     ))
                                                     .size(16.0)
                                                     .background_color(
@@ -305,25 +1081,371 @@ impl Pane for ThermalDisplayPane {
                                     }
                                 });
 
-                            if plot_ui.response().clicked() {
+                            for cell in grid.iter().flat_map(|grid| grid.cells.iter()) {
+                                let outline = rect_outline(cell.rect, img_size);
+                                plot_ui.line(
+                                    Line::new(outline.clone())
+                                        .color(Color32::WHITE.gamma_multiply(0.6)),
+                                );
+                                plot_ui.text(
+                                    Text::new(
+                                        PlotPoint::new(
+                                            (outline[0][0] + outline[2][0]) / 2.0,
+                                            (outline[0][1] + outline[2][1]) / 2.0,
+                                        ),
+                                        RichText::new(format_reading(
+                                            cell.value(grid_settings.metric),
+                                            temp_unit,
+                                            temp_precision,
+                                            is_delta,
+                                        ))
+                                        .size(12.0)
+                                        .background_color(Color32::BLACK.gamma_multiply(0.5))
+                                        .color(Color32::WHITE),
+                                    )
+                                    .anchor(Align2::CENTER_CENTER),
+                                );
+                            }
+
+                            let to_plot =
+                                |p: [f32; 2]| [p[0] as f64, img_size.1 as f64 - p[1] as f64];
+                            for annotation in annotations.iter() {
+                                match &annotation.kind {
+                                    AnnotationKind::Text { pos, text } => {
+                                        let [x, y] = to_plot(*pos);
+                                        plot_ui.text(
+                                            Text::new(
+                                                PlotPoint::new(x, y),
+                                                RichText::new(text)
+                                                    .size(16.0)
+                                                    .background_color(
+                                                        Color32::BLACK.gamma_multiply(0.5),
+                                                    )
+                                                    .color(annotation.color),
+                                            )
+                                            .anchor(Align2::LEFT_CENTER),
+                                        );
+                                    }
+                                    AnnotationKind::Arrow { start, end } => plot_ui.arrows(
+                                        Arrows::new(vec![to_plot(*start)], vec![to_plot(*end)])
+                                            .color(annotation.color),
+                                    ),
+                                    AnnotationKind::Freehand { points } => plot_ui.line(
+                                        Line::new(
+                                            points.iter().map(|p| to_plot(*p)).collect::<Vec<_>>(),
+                                        )
+                                        .color(annotation.color)
+                                        .width(2.0),
+                                    ),
+                                }
+                            }
+
+                            for (pos, temperature, shape, color) in markers.iter() {
+                                let PlotPoint { x, y } = handle_plot_point(*pos, img_size);
+                                plot_ui.points(
+                                    Points::new(vec![[x, y]])
+                                        .shape(*shape)
+                                        .radius(7.0)
+                                        .filled(true)
+                                        .color(Color32::BLACK.gamma_multiply(0.5)),
+                                );
+                                plot_ui.points(
+                                    Points::new(vec![[x, y]])
+                                        .shape(*shape)
+                                        .radius(5.0)
+                                        .filled(true)
+                                        .color(*color),
+                                );
+                                plot_ui.text(
+                                    Text::new(
+                                        PlotPoint::new(x + 4.0, y),
+                                        RichText::new(format_reading(
+                                            *temperature,
+                                            temp_unit,
+                                            temp_precision,
+                                            is_delta,
+                                        ))
+                                        .size(14.0)
+                                        .background_color(Color32::BLACK.gamma_multiply(0.5))
+                                        .color(Color32::WHITE),
+                                    )
+                                    .anchor(Align2::LEFT_CENTER),
+                                );
+                            }
+
+                            // find the gizmo handle closest to the pointer
+                            let pointer_screen_pos = plot_ui
+                                .response()
+                                .hover_pos()
+                                .or(plot_ui.response().interact_pointer_pos());
+                            let hovered_handle = pointer_screen_pos.and_then(|pointer| {
+                                root_gizmo
+                                    .children()
+                                    .unwrap()
+                                    .iter()
+                                    .filter(|g| !g.hidden)
+                                    .flat_map(|g| {
+                                        g.kind
+                                            .handles()
+                                            .into_iter()
+                                            .map(move |(handle, pos)| (g.uuid, handle, pos))
+                                    })
+                                    .map(|(uuid, handle, pos)| {
+                                        let screen_pos = plot_ui
+                                            .transform()
+                                            .position_from_point(&handle_plot_point(pos, img_size));
+                                        (uuid, handle, screen_pos.distance(pointer))
+                                    })
+                                    .filter(|(_, _, dist)| *dist <= GIZMO_GRAB_RADIUS)
+                                    .min_by(|a, b| a.2.total_cmp(&b.2))
+                                    .map(|(uuid, handle, _)| (uuid, handle))
+                            });
+
+                            // show the handles of the gizmo under the mouse
+                            let active_gizmo = self
+                                .dragged_gizmo
+                                .map(|(uuid, _, _)| uuid)
+                                .or(hovered_handle.map(|(uuid, _)| uuid));
+                            let active_handle_points = active_gizmo.and_then(|uuid| {
+                                root_gizmo
+                                    .children()
+                                    .unwrap()
+                                    .iter()
+                                    .find(|g| g.uuid == uuid)
+                                    .filter(|g| !matches!(g.kind, GizmoKind::TempAt { .. }))
+                                    .map(|g| {
+                                        g.kind
+                                            .handles()
+                                            .iter()
+                                            .map(|(_, pos)| {
+                                                let point = handle_plot_point(*pos, img_size);
+                                                [point.x, point.y]
+                                            })
+                                            .collect::<Vec<_>>()
+                                    })
+                            });
+                            if let Some(points) = active_handle_points {
+                                plot_ui.points(
+                                    Points::new(points)
+                                        .shape(MarkerShape::Square)
+                                        .radius(4.0)
+                                        .filled(true)
+                                        .color(Color32::WHITE),
+                                );
+                            }
+
+                            let grabbed_handle = self
+                                .dragged_gizmo
+                                .map(|(_, handle, _)| handle)
+                                .or(hovered_handle.map(|(_, handle)| handle));
+                            if let Some(handle) = grabbed_handle {
+                                let cursor_icon = match handle {
+                                    GizmoHandle::Move if self.dragged_gizmo.is_some() => {
+                                        CursorIcon::Grabbing
+                                    }
+                                    GizmoHandle::Move => CursorIcon::Grab,
+                                    GizmoHandle::LineStart | GizmoHandle::LineEnd => {
+                                        CursorIcon::Crosshair
+                                    }
+                                    GizmoHandle::Corner { left, top } if left == top => {
+                                        CursorIcon::ResizeNwSe
+                                    }
+                                    GizmoHandle::Corner { .. } => CursorIcon::ResizeNeSw,
+                                };
+                                plot_ui
+                                    .ctx()
+                                    .output_mut(|out| out.cursor_icon = cursor_icon);
+                            }
+
+                            if plot_ui
+                                .response()
+                                .drag_started_by(egui::PointerButton::Primary)
+                            {
+                                let pointer_pos = plot_ui
+                                    .pointer_coordinate()
+                                    .map(|coord| plot_point_to_data_pos(coord, img_size));
+                                if plot_ui.ctx().input(|i| i.modifiers.shift) {
+                                    self.region_selection_start = pointer_pos;
+                                } else if let Some((uuid, handle)) = hovered_handle {
+                                    self.dragged_gizmo = pointer_pos.map(|pos| (uuid, handle, pos));
+                                } else if let Some(kind) =
+                                    plot_ui.pointer_coordinate().and_then(|coord| {
+                                        self.measurement_tool.new_annotation(
+                                            plot_point_to_annotation_pos(coord, img_size),
+                                        )
+                                    })
+                                {
+                                    let annotation = Annotation::new(kind, self.annotation_color);
+                                    self.new_annotation = Some(annotation.uuid);
+                                    annotations.to_mut().push(annotation);
+                                } else if let Some(pos) = pointer_pos {
+                                    // drag out a new line or area with the selected tool
+                                    if let Some((kind, name)) = self.measurement_tool.new_gizmo(pos)
+                                    {
+                                        let uuid =
+                                            root_gizmo.to_mut().push_child(kind, name.to_string());
+                                        self.new_gizmo_start = Some((uuid, pos));
+                                    }
+                                }
+                            }
+
+                            if let Some(start) = self.region_selection_start {
+                                if plot_ui.response().dragged_by(egui::PointerButton::Primary) {
+                                    if let Some(coord) = plot_ui.pointer_coordinate() {
+                                        histogram_region = Some(ThermalDataRect::from_corners(
+                                            start,
+                                            plot_point_to_data_pos(coord, img_size),
+                                        ));
+                                        histogram_region_changed = true;
+                                    }
+                                } else {
+                                    self.region_selection_start = None;
+                                }
+                            }
+
+                            if let Some(rect) = histogram_region {
+                                plot_ui.line(
+                                    Line::new(rect_outline(rect, img_size))
+                                        .color(Color32::WHITE)
+                                        .style(egui_plot::LineStyle::dashed_dense()),
+                                );
+                            }
+
+                            if let Some((uuid, handle, last_pos)) = self.dragged_gizmo {
+                                if plot_ui.response().dragged_by(egui::PointerButton::Primary) {
+                                    if let Some(coord) = plot_ui.pointer_coordinate() {
+                                        let new_pos = plot_point_to_data_pos(coord, img_size);
+                                        if let Some(gizmo) = root_gizmo
+                                            .to_mut()
+                                            .children_mut()
+                                            .unwrap()
+                                            .iter_mut()
+                                            .find(|g| g.uuid == uuid)
+                                        {
+                                            gizmo.kind.drag_handle(
+                                                handle, last_pos, new_pos, img_size.0, img_size.1,
+                                            );
+                                        }
+                                        self.dragged_gizmo = Some((uuid, handle, new_pos));
+                                    }
+                                } else {
+                                    self.dragged_gizmo = None;
+                                }
+                            }
+
+                            if let Some((uuid, start)) = self.new_gizmo_start {
+                                if plot_ui.response().dragged_by(egui::PointerButton::Primary) {
+                                    if let Some(coord) = plot_ui.pointer_coordinate() {
+                                        let end = plot_point_to_data_pos(coord, img_size);
+                                        if let Some(gizmo) = root_gizmo
+                                            .to_mut()
+                                            .children_mut()
+                                            .unwrap()
+                                            .iter_mut()
+                                            .find(|g| g.uuid == uuid)
+                                        {
+                                            gizmo.kind.set_extent(start, end);
+                                        }
+                                    }
+                                } else {
+                                    self.new_gizmo_start = None;
+                                }
+                            }
+
+                            if let Some(uuid) = self.new_annotation {
+                                if plot_ui.response().dragged_by(egui::PointerButton::Primary) {
+                                    if let Some(coord) = plot_ui.pointer_coordinate() {
+                                        let pos = plot_point_to_annotation_pos(coord, img_size);
+                                        if let Some(annotation) =
+                                            annotations.to_mut().iter_mut().find(|a| a.uuid == uuid)
+                                        {
+                                            match &mut annotation.kind {
+                                                AnnotationKind::Arrow { end, .. } => *end = pos,
+                                                // skip points closer than half a pixel
+                                                AnnotationKind::Freehand { points } => {
+                                                    if points.last().is_none_or(|last| {
+                                                        (last[0] - pos[0]).hypot(last[1] - pos[1])
+                                                            >= 0.5
+                                                    }) {
+                                                        points.push(pos)
+                                                    }
+                                                }
+                                                AnnotationKind::Text { .. } => {}
+                                            }
+                                        }
+                                    }
+                                } else {
+                                    self.new_annotation = None;
+                                }
+                            }
+
+                            // right click or Delete removes the gizmo or annotation under the mouse
+                            let delete_requested = plot_ui.response().secondary_clicked()
+                                || (plot_ui.response().hovered()
+                                    && plot_ui.ctx().input(|i| i.key_pressed(Key::Delete)));
+                            if let (true, Some((uuid, _))) = (delete_requested, hovered_handle) {
+                                root_gizmo
+                                    .to_mut()
+                                    .children_mut()
+                                    .unwrap()
+                                    .retain(|g| g.uuid != uuid);
+                            } else if let (true, Some(pointer)) =
+                                (delete_requested, pointer_screen_pos)
+                            {
+                                let hovered_annotation = annotations
+                                    .iter()
+                                    .filter_map(|annotation| {
+                                        annotation
+                                            .points()
+                                            .iter()
+                                            .map(|p| {
+                                                let [x, y] = to_plot(*p);
+                                                plot_ui
+                                                    .transform()
+                                                    .position_from_point(&PlotPoint::new(x, y))
+                                                    .distance(pointer)
+                                            })
+                                            .min_by(|a, b| a.total_cmp(b))
+                                            .map(|dist| (annotation.uuid, dist))
+                                    })
+                                    .filter(|(_, dist)| *dist <= GIZMO_GRAB_RADIUS)
+                                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                                    .map(|(uuid, _)| uuid);
+                                if let Some(uuid) = hovered_annotation {
+                                    annotations.to_mut().retain(|a| a.uuid != uuid);
+                                }
+                            }
+
+                            if plot_ui.response().clicked()
+                                && hovered_handle.is_none()
+                                && self.measurement_tool == MeasurementTool::Text
+                                && !self.annotation_text.trim().is_empty()
+                            {
+                                if let Some(coord) = plot_ui.pointer_coordinate() {
+                                    annotations.to_mut().push(Annotation::new(
+                                        AnnotationKind::Text {
+                                            pos: plot_point_to_annotation_pos(coord, img_size),
+                                            text: self.annotation_text.trim().to_string(),
+                                        },
+                                        self.annotation_color,
+                                    ));
+                                }
+                            }
+
+                            if plot_ui.response().clicked()
+                                && hovered_handle.is_none()
+                                && self.measurement_tool == MeasurementTool::Spot
+                            {
                                 let pos = plot_ui.pointer_coordinate().unwrap();
                                 let x = pos.x as usize;
                                 let y = pos.y as usize;
                                 if x > 0 && y > 0 && x < img_size.0 && y < img_size.1 {
-                                    global_state.thermal_capturer_settings.gizmo.push_child(
+                                    root_gizmo.to_mut().push_child(
                                         GizmoKind::TempAt {
                                             pos: ThermalDataPos::new(x, img_size.1 - y),
                                         },
                                         "Custom".to_string(),
                                     );
-
-                                    let settings_clone =
-                                        global_state.thermal_capturer_settings.clone();
-                                    if let Some(thermal_capturer) =
-                                        global_state.thermal_capturer_inst.as_mut()
-                                    {
-                                        thermal_capturer.set_settings(settings_clone);
-                                    }
                                 }
                             }
 
@@ -368,6 +1490,109 @@ impl Pane for ThermalDisplayPane {
                             }
                         });
 
+                    if let Cow::Owned(root_gizmo) = root_gizmo {
+                        app.edit_settings(move |settings| settings.gizmo = root_gizmo);
+                    }
+                    if let Cow::Owned(annotations) = annotations {
+                        app.edit_settings(move |settings| settings.annotations = annotations);
+                    }
+                    if histogram_region_changed {
+                        app.edit_settings(move |settings| {
+                            settings.histogram_region = histogram_region
+                        });
+                    }
+
+                    // temperature under the pointer, except while dragging gizmos around
+                    let hovered_point = plot_response
+                        .response
+                        .hover_pos()
+                        .filter(|_| self.dragged_gizmo.is_none() && self.new_gizmo_start.is_none())
+                        .map(|pos| plot_response.transform.value_from_position(pos))
+                        .filter(|point| {
+                            (0.0..img_size.0 as f64).contains(&point.x)
+                                && point.y > 0.0
+                                && point.y <= img_size.1 as f64
+                        });
+                    if let (Some(point), Some(res)) = (
+                        hovered_point,
+                        global_state.last_thermal_capturer_result.as_ref(),
+                    ) {
+                        let pos = plot_point_to_data_pos(point, img_size);
+                        plot_response
+                            .response
+                            .clone()
+                            .on_hover_text_at_pointer(format!(
+                                "{} at ({}, {})",
+                                format_reading(
+                                    res.thermal_data.temperature_at(pos.x, pos.y),
+                                    global_state.preferred_temperature_unit(),
+                                    global_state.preferred_temperature_precision(),
+                                    res.is_delta,
+                                ),
+                                pos.x,
+                                pos.y
+                            ));
+                    }
+
+                    if global_state.thermal_capturer_settings.show_contours {
+                        // the levels are temperatures, differences have none
+                        if let Some(res) = global_state
+                            .last_thermal_capturer_result
+                            .as_ref()
+                            .filter(|res| !res.is_delta)
+                        {
+                            let painter = ui.painter_at(plot_response.response.rect);
+                            let height = res.thermal_data.height as f64;
+                            // pixel centers are offset by half a pixel from the plot grid
+                            let to_screen = |p: [f32; 2]| {
+                                plot_response.transform.position_from_point(&PlotPoint::new(
+                                    p[0] as f64 + 0.5,
+                                    height - p[1] as f64 - 0.5,
+                                ))
+                            };
+                            for (level, color) in global_state
+                                .thermal_capturer_settings
+                                .contour_levels
+                                .iter()
+                                .zip(CONTOUR_COLORS.iter())
+                            {
+                                let segments = contour_segments(&res.thermal_data, *level);
+                                for segment in segments.iter() {
+                                    painter.line_segment(
+                                        [to_screen(segment[0]), to_screen(segment[1])],
+                                        Stroke::new(1.5, *color),
+                                    );
+                                }
+                                if let Some(segment) = segments.get(segments.len() / 2) {
+                                    painter.text(
+                                        to_screen(segment[0]),
+                                        Align2::LEFT_BOTTOM,
+                                        format_temp(
+                                            *level,
+                                            global_state.preferred_temperature_unit(),
+                                            global_state.preferred_temperature_precision(),
+                                        ),
+                                        FontId::proportional(12.0),
+                                        *color,
+                                    );
+                                }
+                            }
+                        }
+                    }
+
+                    if global_state.thermal_capturer_settings.show_color_scale {
+                        if let Some(res) = global_state.last_thermal_capturer_result.as_ref() {
+                            self.draw_color_scale(
+                                ui,
+                                plot_response.response.rect,
+                                &global_state.thermal_capturer_settings,
+                                res,
+                                global_state.preferred_temperature_unit(),
+                                global_state.preferred_temperature_precision(),
+                            );
+                        }
+                    }
+
                     self.external_zoom_factor = (img_size.0 as f64
                         / plot_response.transform.bounds().width())
                     .max(img_size.1 as f64 / plot_response.transform.bounds().height())
@@ -381,6 +1606,108 @@ impl Pane for ThermalDisplayPane {
     }
 }
 
+///
+/// Converts a point on the display plot to a pixel position in the thermal data,
+/// clamped to the image bounds. The plot has its y axis flipped relative to the image.
+///
+fn plot_point_to_data_pos(point: PlotPoint, img_size: (usize, usize)) -> ThermalDataPos {
+    let x = (point.x.max(0.0) as usize).min(img_size.0 - 1);
+    let y = img_size
+        .1
+        .saturating_sub(point.y.max(0.0) as usize)
+        .min(img_size.1 - 1);
+    ThermalDataPos::new(x, y)
+}
+
+///
+/// Position of an annotation, in continuous pixels of the thermal data.
+///
+fn plot_point_to_annotation_pos(point: PlotPoint, img_size: (usize, usize)) -> [f32; 2] {
+    [
+        (point.x as f32).clamp(0.0, img_size.0 as f32),
+        (img_size.1 as f64 - point.y).clamp(0.0, img_size.1 as f64) as f32,
+    ]
+}
+
+///
+/// Returns the closed outline around the pixels of `rect`, in plot coordinates.
+///
+fn rect_outline(rect: ThermalDataRect, img_size: (usize, usize)) -> Vec<[f64; 2]> {
+    let left = rect.min.x as f64;
+    let right = rect.max.x as f64 + 1.0;
+    let top = img_size.1 as f64 - rect.min.y as f64 + 1.0;
+    let bottom = img_size.1 as f64 - rect.max.y as f64;
+    vec![
+        [left, top],
+        [right, top],
+        [right, bottom],
+        [left, bottom],
+        [left, top],
+    ]
+}
+
+///
+/// Returns the closed outline of the ellipse inscribed in `rect`, in plot coordinates.
+///
+fn ellipse_outline(rect: ThermalDataRect, img_size: (usize, usize)) -> Vec<[f64; 2]> {
+    let outline = rect_outline(rect, img_size);
+    let (left, top) = (outline[0][0], outline[0][1]);
+    let (right, bottom) = (outline[2][0], outline[2][1]);
+    let center = [(left + right) / 2.0, (top + bottom) / 2.0];
+    let radius = [(right - left) / 2.0, (top - bottom) / 2.0];
+    (0..=64)
+        .map(|i| {
+            let angle = i as f64 / 64.0 * std::f64::consts::TAU;
+            [
+                center[0] + radius[0] * angle.cos(),
+                center[1] + radius[1] * angle.sin(),
+            ]
+        })
+        .collect()
+}
+
+///
+/// Returns the markers enabled in the settings as (position, temperature, shape, color).
+///
+fn frame_markers(
+    result: &ThermalCapturerResult,
+    settings: &ThermalCapturerSettings,
+) -> Vec<(ThermalDataPos, Temp, MarkerShape, Color32)> {
+    let data = &result.thermal_data;
+    let center = ThermalDataPos::new(data.width / 2, data.height / 2);
+    [
+        (
+            settings.show_max_marker,
+            result.max_pos,
+            MarkerShape::Up,
+            Color32::RED,
+        ),
+        (
+            settings.show_min_marker,
+            result.min_pos,
+            MarkerShape::Down,
+            Color32::from_rgb(72, 219, 251),
+        ),
+        (
+            settings.show_center_marker,
+            center,
+            MarkerShape::Diamond,
+            Color32::WHITE,
+        ),
+    ]
+    .into_iter()
+    .filter(|(enabled, ..)| *enabled)
+    .map(|(_, pos, shape, color)| (pos, data.temperature_at(pos.x, pos.y), shape, color))
+    .collect()
+}
+
+///
+/// Returns the center of a pixel in plot coordinates, where its handle is drawn.
+///
+fn handle_plot_point(pos: ThermalDataPos, img_size: (usize, usize)) -> PlotPoint {
+    PlotPoint::new(pos.x as f64 + 0.5, img_size.1 as f64 - pos.y as f64 + 0.5)
+}
+
 pub fn zoom_edit_field(ui: &mut Ui, zoom_value: &mut f64) -> Response {
     let mut tmp_value = *zoom_value * 100.0;
     let res = ui.add(
@@ -1,25 +1,94 @@
-use std::{cell::RefCell, rc::Rc};
-
 use crate::{
-    pane_dispatcher::Pane, temperature::TemperatureUnit, user_preferences::UserPreferences,
+    keyboard_shortcuts::{captured_trigger, KeyBindings, ShortcutAction},
+    pane_dispatcher::{Pane, PaneContext},
+    temperature::TemperatureUnit,
+    user_preferences::UserPreferences,
     AppGlobalState,
 };
 use anyhow::Context;
-use eframe::egui::{self, Grid};
-use log::error;
+use eframe::egui::{self, Button, Color32, DragValue, Grid, TextEdit};
 use strum::IntoEnumIterator;
 
 pub struct UserPreferencesPane {
     local_user_preferences: Option<UserPreferences>,
-    global_state: Rc<RefCell<AppGlobalState>>,
+    // Action waiting for its new key
+    rebinding: Option<ShortcutAction>,
 }
 
 impl UserPreferencesPane {
-    pub fn new(global_state: Rc<RefCell<AppGlobalState>>) -> Self {
-        let copied_prefs = global_state.as_ref().borrow().prefs.clone();
+    pub fn new(global_state: &AppGlobalState) -> Self {
         Self {
-            local_user_preferences: copied_prefs,
-            global_state,
+            local_user_preferences: global_state.prefs.clone(),
+            rebinding: None,
+        }
+    }
+
+    ///
+    /// Lists the shortcuts, clicking the key of one waits for a new key to bind to it.
+    ///
+    fn keybindings_ui(&mut self, ui: &mut egui::Ui, keybindings: &mut KeyBindings) {
+        // the key is taken before the buttons, a key like Space would click the focused button again
+        let mut just_bound = false;
+        if let Some(action) = self.rebinding {
+            if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                self.rebinding = None;
+                just_bound = true;
+            } else if let Some(trigger) = captured_trigger(ui.ctx()) {
+                keybindings.set_trigger(action, Some(trigger));
+                self.rebinding = None;
+                just_bound = true;
+            }
+        }
+
+        Grid::new("keybindings_grid")
+            .num_columns(3)
+            .spacing([10.0, 6.0])
+            .striped(true)
+            .show(ui, |ui| {
+                for action in ShortcutAction::iter() {
+                    ui.label(action.description());
+                    let key_name = if self.rebinding == Some(action) {
+                        "Press a key…".to_string()
+                    } else {
+                        keybindings
+                            .trigger(action)
+                            .map_or("None".to_string(), |trigger| trigger.name())
+                    };
+                    ui.horizontal(|ui| {
+                        if ui
+                            .add(Button::new(key_name).min_size(egui::vec2(90.0, 0.0)))
+                            .on_hover_text("Click and press the new key, Escape cancels")
+                            .clicked()
+                            && !just_bound
+                        {
+                            self.rebinding = Some(action);
+                        }
+                        if ui
+                            .add_enabled(keybindings.trigger(action).is_some(), Button::new("✖"))
+                            .on_hover_text("Remove the key")
+                            .clicked()
+                        {
+                            keybindings.set_trigger(action, None);
+                        }
+                    });
+                    let conflicts = keybindings.conflicts(action);
+                    if conflicts.is_empty() {
+                        ui.label("");
+                    } else {
+                        ui.colored_label(
+                            Color32::RED,
+                            format!("Also used for \"{}\"", conflicts[0].description()),
+                        );
+                    }
+                    ui.end_row();
+                }
+            });
+        if ui
+            .add_enabled(!keybindings.is_default(), Button::new("Reset to defaults"))
+            .clicked()
+        {
+            keybindings.reset();
+            self.rebinding = None;
         }
     }
 }
@@ -28,9 +97,8 @@ impl Pane for UserPreferencesPane {
     fn title(&self) -> egui::WidgetText {
         "User Preferences".into()
     }
-    fn ui(&mut self, ui: &mut egui::Ui) {
-        let global_state_clone = self.global_state.clone();
-        let mut global_state = global_state_clone.as_ref().borrow_mut();
+    fn ui(&mut self, ui: &mut egui::Ui, app: &mut PaneContext<'_>) {
+        let global_state = app.global_state;
         if self.local_user_preferences.is_some() {
             ui.heading("User Preferences");
             ui.separator();
@@ -49,15 +117,29 @@ impl Pane for UserPreferencesPane {
                         .selected_text(edited_prefs.temperature_unit.to_string())
                         .show_ui(ui, |ui| {
                             for unit in TemperatureUnit::iter() {
-                                ui.selectable_value(
-                                    &mut edited_prefs.temperature_unit,
-                                    unit,
-                                    unit.to_string(),
-                                );
+                                if ui
+                                    .selectable_value(
+                                        &mut edited_prefs.temperature_unit,
+                                        unit,
+                                        format!("{} ({})", unit, unit.suffix()),
+                                    )
+                                    .changed()
+                                {
+                                    edited_prefs.temperature_precision = unit.default_precision();
+                                }
                             }
                         });
                     ui.end_row();
 
+                    ui.label("Temperature Precision")
+                        .on_hover_text("Set to the usual one when the unit is changed");
+                    ui.add(
+                        DragValue::new(&mut edited_prefs.temperature_precision)
+                            .clamp_range(0..=3)
+                            .suffix(" decimals"),
+                    );
+                    ui.end_row();
+
                     ui.label("Auto Open Camera");
                     ui.checkbox(&mut edited_prefs.auto_open_camera, "");
                     ui.end_row();
@@ -66,25 +148,136 @@ impl Pane for UserPreferencesPane {
                     ui.checkbox(&mut edited_prefs.show_unsupported_cameras, "");
                     ui.end_row();
 
+                    ui.label("GPU colorization")
+                        .on_hover_text("Only for the display, equalization, fusion and sharper upscaling still use the CPU");
+                    ui.checkbox(&mut edited_prefs.gpu_colorization, "");
+                    ui.end_row();
+
                     ui.label("Captures directory");
                     ui.text_edit_singleline(&mut edited_prefs.captures_directory);
                     ui.end_row();
                 });
 
+            ui.add_space(10.0);
+            ui.heading("MQTT");
+            ui.separator();
+            Grid::new("mqtt_grid")
+                .num_columns(2)
+                .spacing([10.0, 10.0])
+                .striped(true)
+                .show(ui, |ui| {
+                    let mqtt = &mut self.local_user_preferences.as_mut().unwrap().mqtt;
+
+                    ui.label("Publish to MQTT");
+                    ui.checkbox(&mut mqtt.enabled, "");
+                    ui.end_row();
+
+                    ui.label("Broker");
+                    ui.horizontal(|ui| {
+                        ui.add(TextEdit::singleline(&mut mqtt.host).desired_width(150.0));
+                        ui.add(DragValue::new(&mut mqtt.port).prefix("Port "));
+                    });
+                    ui.end_row();
+
+                    ui.label("Client ID");
+                    ui.text_edit_singleline(&mut mqtt.client_id);
+                    ui.end_row();
+
+                    ui.label("Username");
+                    ui.text_edit_singleline(&mut mqtt.username);
+                    ui.end_row();
+
+                    ui.label("Password");
+                    ui.add(TextEdit::singleline(&mut mqtt.password).password(true));
+                    ui.end_row();
+
+                    ui.label("Topic prefix");
+                    ui.text_edit_singleline(&mut mqtt.topic_prefix)
+                        .on_hover_text(
+                            "Topics are <prefix>/gizmos/<name> and <prefix>/alarms/<name>",
+                        );
+                    ui.end_row();
+
+                    ui.label("Publish interval");
+                    ui.add(
+                        DragValue::new(&mut mqtt.publish_interval_secs)
+                            .speed(0.1)
+                            .clamp_range(0.1..=3600.0)
+                            .suffix(" s"),
+                    );
+                    ui.end_row();
+
+                    ui.label("Retain measurements");
+                    ui.checkbox(&mut mqtt.retain, "");
+                    ui.end_row();
+                });
+
+            ui.add_space(10.0);
+            ui.heading("HTTP Server");
+            ui.separator();
+            Grid::new("http_server_grid")
+                .num_columns(2)
+                .spacing([10.0, 10.0])
+                .striped(true)
+                .show(ui, |ui| {
+                    let http_server =
+                        &mut self.local_user_preferences.as_mut().unwrap().http_server;
+
+                    ui.label("Serve image and temperatures");
+                    ui.checkbox(&mut http_server.enabled, "")
+                        .on_hover_text("MJPEG stream, temperatures and gizmo readouts over HTTP");
+                    ui.end_row();
+
+                    ui.label("Address");
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            TextEdit::singleline(&mut http_server.bind_address)
+                                .desired_width(150.0),
+                        )
+                        .on_hover_text("0.0.0.0 makes the server reachable from the network");
+                        ui.add(DragValue::new(&mut http_server.port).prefix("Port "));
+                    });
+                    ui.end_row();
+                });
+
+            ui.add_space(10.0);
+            ui.heading("Keyboard Shortcuts");
+            ui.separator();
+            let mut keybindings = self
+                .local_user_preferences
+                .as_ref()
+                .unwrap()
+                .keybindings
+                .clone();
+            self.keybindings_ui(ui, &mut keybindings);
+            self.local_user_preferences.as_mut().unwrap().keybindings = keybindings;
+            // keeps the key from triggering the shortcut it's already bound to
+            let capturing_shortcut = self.rebinding.is_some();
+            if capturing_shortcut != global_state.capturing_shortcut {
+                app.update(move |global_state| {
+                    global_state.capturing_shortcut = capturing_shortcut
+                });
+            }
+
             ui.add_space(10.0);
             ui.separator();
 
             ui.horizontal(|ui| {
                 if ui.button("Save").clicked() {
-                    global_state.prefs =
-                        Some(self.local_user_preferences.as_ref().unwrap().clone());
-                    self.local_user_preferences = None;
-                    let _ = global_state
-                        .prefs
-                        .as_ref()
-                        .context("Failed to get user preferences")
-                        .map(|prefs| prefs.save())
-                        .inspect_err(|err| error!("Failed to save user preferences: {}", err));
+                    let prefs = self.local_user_preferences.take();
+                    app.update(move |global_state| {
+                        global_state.prefs = prefs;
+                        let _ = global_state
+                            .prefs
+                            .as_ref()
+                            .context("Failed to get user preferences")
+                            .map(|prefs| prefs.save())
+                            .inspect_err(|err| {
+                                tracing::error!(error = %err, "Failed to save user preferences")
+                            });
+                        global_state.restart_mqtt_publisher();
+                        global_state.restart_http_server();
+                    });
                 }
                 if ui.button("Cancel").clicked() {
                     self.local_user_preferences = None;
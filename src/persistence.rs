@@ -0,0 +1,90 @@
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::PathBuf,
+};
+
+use anyhow::{anyhow, Result};
+use egui_dock::DockState;
+use serde::{Deserialize, Serialize};
+
+use crate::{pane_dispatcher::PaneKind, thermal_capturer::ThermalCapturerSettings};
+
+/// Denotes the maximum known version of the session file for this version of the application.
+///
+/// Version 1: Initial version.
+/// Version 2: Added `last_camera_id`.
+const SESSION_VERSION: u32 = 2;
+
+///
+/// What is restored on the next start: the image settings, the arrangement of the panes
+/// and the camera which was used.
+/// The size and position of the window are restored by eframe.
+///
+#[derive(Serialize, Deserialize)]
+pub struct Session {
+    pub session_version: u32,
+    pub settings: ThermalCapturerSettings,
+    // None to use the default layout
+    pub layout: Option<DockState<PaneKind>>,
+    // See `camera_id`, reopened on the next start
+    #[serde(default)]
+    pub last_camera_id: Option<String>,
+}
+
+// Only the version is parsed first, so that a newer session is refused with
+// a clear message instead of a confusing deserialization error
+#[derive(Deserialize)]
+struct SessionHeader {
+    session_version: u32,
+}
+
+pub fn session_path() -> PathBuf {
+    let mut path = dirs::config_dir().unwrap();
+    path.push("thermal-viewer");
+    path.push("session.json");
+    path
+}
+
+pub fn load_session() -> Result<Option<Session>> {
+    let path = session_path();
+    if !path.exists() {
+        return Ok(None);
+    }
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+    let value: serde_json::Value = serde_json::from_reader(reader)?;
+
+    let header: SessionHeader = serde_json::from_value(value.clone())?;
+    if header.session_version > SESSION_VERSION {
+        return Err(anyhow!(
+            "Unknown session version {}. Please update the application.",
+            header.session_version
+        ));
+    }
+
+    Ok(Some(serde_json::from_value(value)?))
+}
+
+pub fn save_session(
+    settings: &ThermalCapturerSettings,
+    layout: DockState<PaneKind>,
+    last_camera_id: Option<String>,
+) -> Result<()> {
+    let path = session_path();
+    let dir_path = path.parent().unwrap();
+    if !dir_path.exists() {
+        fs::create_dir_all(dir_path)?;
+    }
+
+    let session = Session {
+        session_version: SESSION_VERSION,
+        settings: settings.clone(),
+        layout: Some(layout),
+        last_camera_id,
+    };
+    let file = File::create(path)?;
+    let writer = BufWriter::new(file);
+    serde_json::to_writer(writer, &session)?;
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{alarms::AlarmRule, thermal_capturer::ThermalCapturerSettings};
+
+// Bump when the profiles change in a way older versions cannot read
+const PROFILES_VERSION: u32 = 1;
+
+///
+/// A named measurement setup, e.g. "PCB rework": the image settings (gradient, range,
+/// emissivity, gizmos, ...) together with the alarms, so it can be switched to in one go.
+///
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    pub settings: ThermalCapturerSettings,
+    pub alarm_rules: Vec<AlarmRule>,
+}
+
+impl Profile {
+    pub fn new(name: String, settings: &ThermalCapturerSettings) -> Self {
+        Self {
+            name,
+            alarm_rules: settings.alarm_rules.clone(),
+            // nothing that only lives as long as the session
+            settings: ThermalCapturerSettings {
+                recorders: vec![],
+                alarm_rules: vec![],
+                visible_frames: None,
+                ..settings.clone()
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ProfilesFile {
+    version: u32,
+    profiles: Vec<Profile>,
+}
+
+// A single profile exported to be shared
+#[derive(Serialize, Deserialize)]
+struct ProfileFile {
+    version: u32,
+    profile: Profile,
+}
+
+pub fn profiles_path() -> PathBuf {
+    let mut path = dirs::config_dir().unwrap();
+    path.push("thermal-viewer");
+    path.push("profiles.json");
+    path
+}
+
+pub fn load_profiles() -> Result<Vec<Profile>> {
+    let path = profiles_path();
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+    let file: ProfilesFile = serde_json::from_reader(reader)?;
+    if file.version > PROFILES_VERSION {
+        return Err(anyhow!(
+            "Unknown profiles version {}. Please update the application.",
+            file.version
+        ));
+    }
+    Ok(file.profiles)
+}
+
+pub fn save_profiles(profiles: &[Profile]) -> Result<()> {
+    let path = profiles_path();
+    let dir_path = path.parent().unwrap();
+    if !dir_path.exists() {
+        fs::create_dir_all(dir_path)?;
+    }
+
+    let file = File::create(path)?;
+    let writer = BufWriter::new(file);
+    serde_json::to_writer_pretty(
+        writer,
+        &ProfilesFile {
+            version: PROFILES_VERSION,
+            profiles: profiles.to_vec(),
+        },
+    )?;
+    Ok(())
+}
+
+pub fn export_profile(path: &Path, profile: &Profile) -> Result<()> {
+    let file = File::create(path)?;
+    let writer = BufWriter::new(file);
+    serde_json::to_writer_pretty(
+        writer,
+        &ProfileFile {
+            version: PROFILES_VERSION,
+            profile: profile.clone(),
+        },
+    )?;
+    Ok(())
+}
+
+pub fn import_profile(path: &Path) -> Result<Profile> {
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+    let file: ProfileFile = serde_json::from_reader(reader)
+        .map_err(|err| anyhow!("{} is not a profile: {}", path.display(), err))?;
+    if file.version > PROFILES_VERSION {
+        return Err(anyhow!(
+            "Profile version {} is newer than the supported version {}. Please update the application.",
+            file.version,
+            PROFILES_VERSION
+        ));
+    }
+    Ok(file.profile)
+}
@@ -0,0 +1,189 @@
+use std::{
+    fs::File,
+    io::{BufReader, Read, Write},
+    path::Path,
+};
+
+use anyhow::{anyhow, Result};
+
+use crate::{
+    temperature::{Temp, TemperatureUnit},
+    thermal_capturer::ThermalCapturerSettings,
+    thermal_data::ThermalData,
+};
+
+//
+// Radiometric PNG snapshots carry the temperatures next to the colored image,
+// in a private ancillary chunk which other software ignores.
+//
+// "tcRd" chunk, all values little-endian:
+//   u32      format version (RADIOMETRIC_CHUNK_VERSION)
+//   u32      width in pixels
+//   u32      height in pixels
+//   f32 * width * height
+//            temperatures in Kelvin, row-major, matching the image pixels
+//
+// The settings used for coloring are stored as JSON in the "Settings" iTXt chunk,
+// see ImageMetadata.
+//
+const RADIOMETRIC_CHUNK_TYPE: [u8; 4] = *b"tcRd";
+const RADIOMETRIC_CHUNK_VERSION: u32 = 1;
+const SETTINGS_KEYWORD: &str = "Settings";
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+// Largest chunk read into memory, enough for the temperatures of a 4096×4096 image,
+// so a corrupted length can't make us allocate gigabytes
+const MAX_CHUNK_LENGTH: usize = 12 + 4096 * 4096 * 4;
+
+pub struct RadiometricSnapshot {
+    pub data: ThermalData,
+    // Present if the snapshot was saved with its settings
+    pub settings: Option<ThermalCapturerSettings>,
+}
+
+pub fn write_radiometric_chunk<W: Write>(
+    writer: &mut png::Writer<W>,
+    data: &ThermalData,
+) -> Result<()> {
+    let mut payload = Vec::with_capacity(12 + data.data.len() * 4);
+    payload.extend_from_slice(&RADIOMETRIC_CHUNK_VERSION.to_le_bytes());
+    payload.extend_from_slice(&(data.width as u32).to_le_bytes());
+    payload.extend_from_slice(&(data.height as u32).to_le_bytes());
+    for temp in data.data.iter() {
+        payload.extend_from_slice(&temp.to_unit(TemperatureUnit::Kelvin).to_le_bytes());
+    }
+    writer.write_chunk(png::chunk::ChunkType(RADIOMETRIC_CHUNK_TYPE), &payload)?;
+    Ok(())
+}
+
+///
+/// Reads the temperatures (and settings, if present) back from a radiometric PNG.
+/// The chunks are walked directly, the image itself doesn't need to be decoded.
+///
+pub fn read_radiometric_png(path: &Path) -> Result<RadiometricSnapshot> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut signature = [0u8; 8];
+    reader.read_exact(&mut signature)?;
+    if signature != PNG_SIGNATURE {
+        return Err(anyhow!("{} is not a PNG file", path.display()));
+    }
+
+    let mut data = None;
+    let mut settings = None;
+    loop {
+        let mut header = [0u8; 8];
+        if reader.read_exact(&mut header).is_err() {
+            break;
+        }
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let chunk_type = [header[4], header[5], header[6], header[7]];
+
+        match &chunk_type {
+            b"IEND" => break,
+            &RADIOMETRIC_CHUNK_TYPE | b"iTXt" => {}
+            _ => {
+                // skip the data and CRC of the chunks we don't need, e.g. the image
+                reader.seek_relative(length as i64 + 4)?;
+                continue;
+            }
+        }
+        if length > MAX_CHUNK_LENGTH {
+            return Err(anyhow!(
+                "{} is corrupted, its {} chunk is {} bytes long",
+                path.display(),
+                String::from_utf8_lossy(&chunk_type),
+                length
+            ));
+        }
+        let mut chunk = vec![0u8; length];
+        reader.read_exact(&mut chunk)?;
+        let mut crc = [0u8; 4];
+        reader.read_exact(&mut crc)?;
+
+        match &chunk_type {
+            &RADIOMETRIC_CHUNK_TYPE => {
+                let mut hasher = crc32fast::Hasher::new();
+                hasher.update(&chunk_type);
+                hasher.update(&chunk);
+                if hasher.finalize() != u32::from_be_bytes(crc) {
+                    return Err(anyhow!(
+                        "The temperature data of {} is corrupted",
+                        path.display()
+                    ));
+                }
+                data = Some(parse_radiometric_chunk(&chunk)?);
+            }
+            b"iTXt" => {
+                if let Some(json) = parse_itxt(&chunk, SETTINGS_KEYWORD) {
+                    settings = serde_json::from_str(&json)
+                        .inspect_err(|err| {
+                            tracing::warn!(
+                                error = %err,
+                                "Ignoring unreadable settings in snapshot"
+                            )
+                        })
+                        .ok();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let data = data.ok_or_else(|| {
+        anyhow!(
+            "{} does not contain temperature data, it was not saved as a radiometric snapshot",
+            path.display()
+        )
+    })?;
+    Ok(RadiometricSnapshot { data, settings })
+}
+
+fn parse_radiometric_chunk(chunk: &[u8]) -> Result<ThermalData> {
+    let read_u32 = |offset: usize| -> Result<u32> {
+        let bytes = chunk
+            .get(offset..offset + 4)
+            .ok_or_else(|| anyhow!("Truncated temperature data"))?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    };
+    let version = read_u32(0)?;
+    if version > RADIOMETRIC_CHUNK_VERSION {
+        return Err(anyhow!(
+            "Temperature data version {} is not supported. Please update the application.",
+            version
+        ));
+    }
+    let width = read_u32(4)? as usize;
+    let height = read_u32(8)? as usize;
+    let temperatures = chunk
+        .get(12..12 + width * height * 4)
+        .ok_or_else(|| anyhow!("Truncated temperature data"))?;
+
+    Ok(ThermalData::new(
+        width,
+        height,
+        temperatures
+            .chunks_exact(4)
+            .map(|b| Temp::new(f32::from_le_bytes([b[0], b[1], b[2], b[3]])))
+            .collect(),
+    ))
+}
+
+///
+/// Returns the text of an uncompressed iTXt chunk with the given keyword.
+///
+fn parse_itxt(chunk: &[u8], keyword: &str) -> Option<String> {
+    // keyword \0 compression_flag compression_method language \0 translated_keyword \0 text
+    let mut parts = chunk.splitn(2, |b| *b == 0);
+    if parts.next()? != keyword.as_bytes() {
+        return None;
+    }
+    let rest = parts.next()?;
+    let (&compressed, rest) = rest.split_first()?;
+    if compressed != 0 {
+        return None;
+    }
+    let rest = rest.get(1..)?;
+    let mut parts = rest.splitn(3, |b| *b == 0);
+    let _language = parts.next()?;
+    let _translated_keyword = parts.next()?;
+    String::from_utf8(parts.next()?.to_vec()).ok()
+}
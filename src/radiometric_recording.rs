@@ -0,0 +1,265 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+
+use crate::{
+    recorders::timelapse_recorder::{TimelapseSchedule, TimelapseStep},
+    temperature::{Temp, TemperatureUnit},
+    thermal_data::ThermalData,
+};
+
+//
+// Radiometric recording file format (.tcrad)
+//
+// All integers and floats are little-endian.
+//
+// Header:
+//   [u8; 8]  magic "TCATRAD\0"
+//   u32      format version (RADIOMETRIC_FORMAT_VERSION)
+//   u64      wall-clock start time, milliseconds since the unix epoch
+//
+// Followed by any number of frames:
+//   u64      timestamp, microseconds since the start of the recording
+//   u32      width in pixels
+//   u32      height in pixels
+//   f32 * width * height
+//            temperatures in Kelvin, row-major, as delivered by the camera
+//            (before flat field correction and rotation)
+//
+// Frames carry their own size, so a file may be cut at any frame boundary.
+//
+pub const RADIOMETRIC_EXTENSION: &str = "tcrad";
+const RADIOMETRIC_MAGIC: &[u8; 8] = b"TCATRAD\0";
+const RADIOMETRIC_FORMAT_VERSION: u32 = 1;
+
+pub struct RadiometricWriter {
+    writer: BufWriter<File>,
+    started: Instant,
+}
+
+impl RadiometricWriter {
+    pub fn create(path: &Path) -> Result<Self> {
+        Self::create_started_at(path, Instant::now())
+    }
+
+    ///
+    /// A recording which started at `started`, e.g. with frames that were buffered before.
+    ///
+    pub fn create_started_at(path: &Path, started: Instant) -> Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        let start_ms = SystemTime::now()
+            .checked_sub(started.elapsed())
+            .unwrap_or_else(SystemTime::now)
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+
+        writer.write_all(RADIOMETRIC_MAGIC)?;
+        writer.write_all(&RADIOMETRIC_FORMAT_VERSION.to_le_bytes())?;
+        writer.write_all(&start_ms.to_le_bytes())?;
+
+        Ok(Self { writer, started })
+    }
+
+    pub fn write_frame(&mut self, data: &ThermalData) -> Result<()> {
+        self.write_frame_at(data, Instant::now())
+    }
+
+    pub fn write_frame_at(&mut self, data: &ThermalData, captured: Instant) -> Result<()> {
+        let timestamp_us = captured.saturating_duration_since(self.started).as_micros() as u64;
+        self.writer.write_all(&timestamp_us.to_le_bytes())?;
+        self.writer.write_all(&(data.width as u32).to_le_bytes())?;
+        self.writer.write_all(&(data.height as u32).to_le_bytes())?;
+        for temp in data.data.iter() {
+            self.writer
+                .write_all(&temp.to_unit(TemperatureUnit::Kelvin).to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+// Size of the per-frame header: timestamp, width and height
+const FRAME_HEADER_SIZE: u64 = 8 + 4 + 4;
+
+#[derive(Clone, Copy)]
+struct FrameIndexEntry {
+    offset: u64,
+    timestamp_us: u64,
+}
+
+///
+/// Random access to the frames of a recording.
+/// The file is scanned once when opened to find where each frame starts.
+///
+pub struct RadiometricReader {
+    reader: BufReader<File>,
+    frames: Vec<FrameIndexEntry>,
+}
+
+impl RadiometricReader {
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != RADIOMETRIC_MAGIC {
+            return Err(anyhow!("{} is not a radiometric recording", path.display()));
+        }
+        let version = read_u32(&mut reader)?;
+        if version > RADIOMETRIC_FORMAT_VERSION {
+            return Err(anyhow!(
+                "Radiometric recording version {} is not supported. Please update the application.",
+                version
+            ));
+        }
+        let _start_time_ms = read_u64(&mut reader)?;
+
+        let file_len = reader.get_ref().metadata()?.len();
+        let mut frames = vec![];
+        let mut offset = reader.stream_position()?;
+        while offset + FRAME_HEADER_SIZE <= file_len {
+            let timestamp_us = read_u64(&mut reader)?;
+            let width = read_u32(&mut reader)? as u64;
+            let height = read_u32(&mut reader)? as u64;
+            let next_offset = offset + FRAME_HEADER_SIZE + width * height * 4;
+            // a recording cut off in the middle of a frame is still readable up to that frame
+            if next_offset > file_len {
+                break;
+            }
+            frames.push(FrameIndexEntry {
+                offset,
+                timestamp_us,
+            });
+            reader.seek(SeekFrom::Start(next_offset))?;
+            offset = next_offset;
+        }
+        if frames.is_empty() {
+            return Err(anyhow!("{} contains no frames", path.display()));
+        }
+
+        Ok(Self { reader, frames })
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn timestamp_us(&self, frame_index: usize) -> u64 {
+        self.frames[frame_index].timestamp_us
+    }
+
+    pub fn duration_us(&self) -> u64 {
+        self.frames[self.frames.len() - 1].timestamp_us
+    }
+
+    pub fn read_frame(&mut self, frame_index: usize) -> Result<ThermalData> {
+        let entry = self.frames[frame_index];
+        self.reader
+            .seek(SeekFrom::Start(entry.offset + FRAME_HEADER_SIZE - 8))?;
+        let width = read_u32(&mut self.reader)? as usize;
+        let height = read_u32(&mut self.reader)? as usize;
+
+        let mut buf = vec![0u8; width * height * 4];
+        self.reader.read_exact(&mut buf)?;
+        let data = buf
+            .chunks_exact(4)
+            .map(|b| Temp::new(f32::from_le_bytes([b[0], b[1], b[2], b[3]])))
+            .collect();
+        Ok(ThermalData::new(width, height, data))
+    }
+}
+
+fn read_u32(reader: &mut impl Read) -> std::io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(reader: &mut impl Read) -> std::io::Result<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+///
+/// Owned by the capturer thread, writes every captured frame while a recording is active,
+/// or only the frames due on the schedule of a timelapse.
+///
+pub struct RecordingController {
+    writer: Option<RadiometricWriter>,
+    path: Option<PathBuf>,
+    schedule: Option<TimelapseSchedule>,
+}
+
+impl RecordingController {
+    pub fn new() -> Self {
+        Self {
+            writer: None,
+            path: None,
+            schedule: None,
+        }
+    }
+
+    pub fn start(&mut self, path: PathBuf, schedule: Option<TimelapseSchedule>) -> Result<()> {
+        self.stop()?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        self.writer = Some(RadiometricWriter::create(&path)?);
+        self.path = Some(path);
+        self.schedule = schedule;
+        Ok(())
+    }
+
+    pub fn stop(&mut self) -> Result<()> {
+        self.path = None;
+        self.schedule = None;
+        if let Some(writer) = self.writer.take() {
+            writer.finish()?;
+        }
+        Ok(())
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.writer.is_some()
+    }
+
+    ///
+    /// Writes a frame if recording. On failure the recording is stopped,
+    /// so that a full disk doesn't also stop the live image.
+    ///
+    pub fn record(&mut self, data: &ThermalData) {
+        let Some(writer) = self.writer.as_mut() else {
+            return;
+        };
+        match self.schedule.as_mut().map(|schedule| schedule.next_step()) {
+            Some(TimelapseStep::Wait) => return,
+            Some(TimelapseStep::Finished) => {
+                if let Err(err) = self.stop() {
+                    tracing::error!(error = %err, "Failed to finish radiometric timelapse");
+                }
+                return;
+            }
+            Some(TimelapseStep::Capture) | None => {}
+        }
+        if let Err(err) = writer.write_frame(data) {
+            tracing::error!(
+                error = %err,
+                path = ?self.path,
+                "Failed to write radiometric frame, stopping"
+            );
+            self.writer = None;
+            self.path = None;
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use std::{fs::File, io::BufWriter, path::Path};
+
+use anyhow::Result;
+use tiff::{
+    encoder::{colortype::Gray16, TiffEncoder},
+    tags::Tag,
+};
+
+use crate::{temperature::TempRange, thermal_data::ThermalData};
+
+///
+/// Saves the raw temperatures as a single 16-bit grayscale TIFF.
+///
+/// The pixel values map linearly to Kelvin as `kelvin = c0 + value * c1`.
+/// The coefficients are written into the ImageDescription tag in the format ImageJ
+/// uses for density calibration, so ImageJ shows real temperatures right away,
+/// and other tools can parse `c0` and `c1` from the same text.
+///
+pub fn save_radiometric_tiff(data: &ThermalData, path: &Path) -> Result<()> {
+    let (min_pos, max_pos) = data.get_min_max_pos();
+    // Use the range of the frame itself, for the best precision
+    let range = TempRange::new(
+        data.temperature_at(min_pos.x, min_pos.y),
+        data.temperature_at(max_pos.x, max_pos.y),
+    );
+    let (values, scale, offset) = data.to_gray16(range);
+
+    // cf=0 is ImageJ's straight line calibration function
+    let description = format!("ImageJ=1.11a\ncf=0\nc0={}\nc1={}\nvunit=K\n", offset, scale);
+
+    let file = File::create(path)?;
+    let mut encoder = TiffEncoder::new(BufWriter::new(file))?;
+    let mut image = encoder.new_image::<Gray16>(data.width as u32, data.height as u32)?;
+    image
+        .encoder()
+        .write_tag(Tag::ImageDescription, description.as_str())?;
+    image.encoder().write_tag(Tag::Software, "Thermal Cat")?;
+    image.write_data(&values)?;
+    Ok(())
+}
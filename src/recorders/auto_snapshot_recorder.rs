@@ -0,0 +1,212 @@
+use std::{
+    collections::HashSet,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Local};
+use uuid::Uuid;
+
+use crate::{
+    temperature::format_reading, thermal_capturer::ThermalCapturerResult,
+    types::media_formats::ImageFormat, util::pathify_string,
+};
+
+use super::{
+    image_recorder::{save_result_image, ImageMetadata},
+    recorder::{Recorder, RecorderState, RecorderStreamParams},
+};
+
+pub const DEFAULT_SNAPSHOT_PATTERN: &str = "{timestamp}_{trigger}_{gizmo}_{temp}";
+
+///
+/// When snapshots are saved automatically, and how they are named.
+///
+#[derive(Clone)]
+pub struct AutoSnapshotRule {
+    // None to only save on alarms
+    pub interval: Option<Duration>,
+    // Save when any alarm goes off
+    pub on_alarm: bool,
+    // Without extension, see expand_filename_pattern for the placeholders
+    pub filename_pattern: String,
+    // Gizmo for {gizmo} and {temp}, None for the hottest pixel of the image
+    pub gizmo: Option<(Uuid, String)>,
+}
+
+///
+/// Saves radiometric snapshots while running, every interval and/or whenever an alarm goes off,
+/// e.g. to document a process without watching it.
+///
+pub struct AutoSnapshotRecorder {
+    // Params
+    destination_folder: PathBuf,
+    rule: AutoSnapshotRule,
+    metadata: ImageMetadata,
+
+    // State
+    last_interval_snapshot: Option<Instant>,
+    // Alarms seen in the previous frame, so an alarm saves once when it goes off
+    active_alarms: HashSet<Uuid>,
+
+    // Output info
+    last_output_file: Option<PathBuf>,
+    curr_state: RecorderState,
+}
+
+impl AutoSnapshotRecorder {
+    pub fn new(
+        destination_folder: PathBuf,
+        rule: AutoSnapshotRule,
+        metadata: ImageMetadata,
+    ) -> AutoSnapshotRecorder {
+        AutoSnapshotRecorder {
+            destination_folder,
+            rule,
+            // the temperatures are what makes the snapshots worth keeping
+            metadata: ImageMetadata {
+                embed_temperatures: true,
+                ..metadata
+            },
+            last_interval_snapshot: None,
+            active_alarms: HashSet::new(),
+            last_output_file: None,
+            curr_state: RecorderState::Initial,
+        }
+    }
+
+    ///
+    /// Replaces the placeholders of the filename pattern:
+    /// {timestamp}, {trigger} ("interval" or the name of the alarm), {gizmo} and {temp}.
+    ///
+    fn expand_filename_pattern(
+        &self,
+        result: &ThermalCapturerResult,
+        trigger: &str,
+        timestamp: DateTime<Local>,
+    ) -> String {
+        let (gizmo_name, temp) = match &self.rule.gizmo {
+            Some((uuid, name)) => (
+                name.clone(),
+                result
+                    .extension
+                    .gizmo_results
+                    .get(uuid)
+                    .map(|r| r.temperature),
+            ),
+            None => ("image".to_string(), Some(result.image_range.max)),
+        };
+        let temp_text = temp.map_or("-".to_string(), |temp| {
+            format_reading(
+                temp,
+                self.metadata.temperature_unit,
+                self.metadata.temperature_precision,
+                result.is_delta,
+            )
+        });
+
+        let pattern = if self.rule.filename_pattern.trim().is_empty() {
+            DEFAULT_SNAPSHOT_PATTERN
+        } else {
+            self.rule.filename_pattern.trim()
+        };
+        pattern
+            .replace(
+                "{timestamp}",
+                &timestamp.format("%Y-%m-%d_%H-%M-%S").to_string(),
+            )
+            .replace("{trigger}", &pathify_string(trigger.to_string()))
+            .replace("{gizmo}", &pathify_string(gizmo_name))
+            .replace("{temp}", &pathify_string(temp_text))
+            // the pattern must not leave the destination folder
+            .replace(['/', '\\'], "_")
+    }
+
+    fn save_snapshot(
+        &mut self,
+        result: &ThermalCapturerResult,
+        trigger: &str,
+    ) -> Result<(), anyhow::Error> {
+        let current_local: DateTime<Local> = Local::now();
+        let name = self.expand_filename_pattern(result, trigger, current_local);
+        let mut save_path =
+            self.destination_folder
+                .join(format!("{}.{}", name, ImageFormat::Png.extension()));
+        // e.g. an alarm and the interval in the same second
+        let mut n = 1;
+        while save_path.exists() {
+            save_path = self.destination_folder.join(format!(
+                "{}_{}.{}",
+                name,
+                n,
+                ImageFormat::Png.extension()
+            ));
+            n += 1;
+        }
+        save_result_image(
+            result,
+            &save_path,
+            ImageFormat::Png,
+            &self.metadata,
+            current_local,
+        )?;
+        self.last_output_file = Some(save_path);
+        Ok(())
+    }
+}
+
+impl Recorder for AutoSnapshotRecorder {
+    fn start(&mut self, _params: RecorderStreamParams) -> Result<(), anyhow::Error> {
+        std::fs::create_dir_all(self.destination_folder.clone())?;
+        self.curr_state = RecorderState::Recording;
+        Ok(())
+    }
+
+    fn process_result(&mut self, result: &ThermalCapturerResult) -> Result<(), anyhow::Error> {
+        if self.rule.on_alarm {
+            let new_alarm = result
+                .extension
+                .active_alarms
+                .iter()
+                .find(|alarm| !self.active_alarms.contains(&alarm.rule_uuid))
+                .map(|alarm| alarm.name.clone());
+            self.active_alarms = result
+                .extension
+                .active_alarms
+                .iter()
+                .map(|alarm| alarm.rule_uuid)
+                .collect();
+            if let Some(alarm_name) = new_alarm {
+                self.save_snapshot(result, &alarm_name)?;
+            }
+        }
+
+        if let Some(interval) = self.rule.interval {
+            if self
+                .last_interval_snapshot
+                .is_none_or(|last| last.elapsed() >= interval)
+            {
+                self.last_interval_snapshot = Some(Instant::now());
+                self.save_snapshot(result, "interval")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn state(&self) -> RecorderState {
+        self.curr_state
+    }
+
+    fn files_created(&self) -> Vec<PathBuf> {
+        self.last_output_file.iter().cloned().collect()
+    }
+
+    fn stop(&mut self) -> Result<(), anyhow::Error> {
+        self.curr_state = RecorderState::Done;
+        Ok(())
+    }
+
+    fn is_continuous(&self) -> bool {
+        true
+    }
+}
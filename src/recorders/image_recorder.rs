@@ -1,21 +1,125 @@
-use std::path::PathBuf;
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
 
+use ab_glyph::PxScale;
 use anyhow::anyhow;
 use chrono::{DateTime, Local};
+use image::{Rgb, RgbImage};
+use imageproc::drawing::draw_text_mut;
 
 use crate::{
-    thermal_capturer::ThermalCapturerResult,
+    overlay::{load_font, Overlay, OverlayOptions},
+    radiometric_png::write_radiometric_chunk,
+    temperature::{format_reading, TemperatureUnit},
+    thermal_capturer::{ThermalCapturerResult, ThermalCapturerSettings},
+    thermal_data::ThermalData,
     types::media_formats::ImageFormat,
     util::{pathify_string, rgba8_to_rgb8},
 };
 
 use super::recorder::{Recorder, RecorderState};
 
+const FOOTER_HEIGHT: u32 = 14;
+
+///
+/// Describes how a snapshot was taken, so that saved images document themselves.
+/// Written as text chunks into PNG files, and optionally burned into a footer below the image.
+/// Also carries what is burned into the image itself.
+///
+#[derive(Clone)]
+pub struct ImageMetadata {
+    pub temperature_unit: TemperatureUnit,
+    pub temperature_precision: usize,
+    pub gradient_name: String,
+    pub emissivity: f32,
+    // All of the capture settings serialized as JSON
+    pub settings_json: String,
+    // Append a line with the timestamp, range and resolution below the image
+    pub draw_footer: bool,
+    // Store the raw temperatures in PNG files, so they can be measured again later
+    pub embed_temperatures: bool,
+    pub overlay: Overlay,
+}
+
+impl ImageMetadata {
+    pub fn new(
+        settings: &ThermalCapturerSettings,
+        temperature_unit: TemperatureUnit,
+        temperature_precision: usize,
+        draw_footer: bool,
+        embed_temperatures: bool,
+        overlay: OverlayOptions,
+    ) -> Self {
+        Self {
+            temperature_unit,
+            temperature_precision,
+            gradient_name: settings.gradient.name.clone(),
+            emissivity: settings.emissivity,
+            settings_json: serde_json::to_string(settings).unwrap_or_default(),
+            draw_footer,
+            embed_temperatures,
+            overlay: Overlay::new(overlay, settings, temperature_unit, temperature_precision),
+        }
+    }
+
+    fn metadata_entries(
+        &self,
+        result: &ThermalCapturerResult,
+        timestamp: DateTime<Local>,
+    ) -> Vec<(&'static str, String)> {
+        let unit = self.temperature_unit;
+        let precision = self.temperature_precision;
+        vec![
+            ("Software", "Thermal Cat".to_string()),
+            ("Creation Time", timestamp.to_rfc3339()),
+            ("Gradient", self.gradient_name.clone()),
+            (
+                "Range Min",
+                format_reading(result.image_range.min, unit, precision, result.is_delta),
+            ),
+            (
+                "Range Max",
+                format_reading(result.image_range.max, unit, precision, result.is_delta),
+            ),
+            ("Temperature Unit", unit.to_string()),
+            ("Emissivity", format!("{:.2}", self.emissivity)),
+            (
+                "Resolution",
+                format!(
+                    "{}x{}",
+                    result.thermal_data.width, result.thermal_data.height
+                ),
+            ),
+            ("Reported FPS", format!("{:.1}", result.reported_fps)),
+            ("Settings", self.settings_json.clone()),
+        ]
+    }
+
+    fn footer_text(&self, result: &ThermalCapturerResult, timestamp: DateTime<Local>) -> String {
+        let unit = self.temperature_unit;
+        let precision = self.temperature_precision;
+        format!(
+            "{}  {} - {}  e={:.2}  {}x{}  {:.0} fps",
+            timestamp.format("%Y-%m-%d %H:%M:%S"),
+            format_reading(result.image_range.min, unit, precision, result.is_delta),
+            format_reading(result.image_range.max, unit, precision, result.is_delta),
+            self.emissivity,
+            result.thermal_data.width,
+            result.thermal_data.height,
+            result.reported_fps
+        )
+    }
+}
+
 pub struct ImageRecorder {
     // Params
     destination_folder: PathBuf,
     name_prefix: String,
     image_format: ImageFormat,
+    metadata: ImageMetadata,
 
     // Output info
     output_file: Option<PathBuf>,
@@ -27,17 +131,115 @@ impl ImageRecorder {
         destination_folder: PathBuf,
         name_prefix: String,
         image_format: ImageFormat,
+        metadata: ImageMetadata,
     ) -> ImageRecorder {
         ImageRecorder {
             destination_folder,
             name_prefix,
             image_format,
+            metadata,
             output_file: None,
             curr_state: RecorderState::Initial,
         }
     }
 }
 
+///
+/// Returns a copy of the image extended with a black strip at the bottom containing `text`,
+/// so that the thermal pixels themselves are left untouched.
+///
+fn with_footer(img: &RgbImage, text: &str) -> Result<RgbImage, anyhow::Error> {
+    let font = load_font()?;
+
+    let mut out = RgbImage::new(img.width(), img.height() + FOOTER_HEIGHT);
+    image::imageops::replace(&mut out, img, 0, 0);
+
+    // Monospace glyphs advance by ~0.6 em, shrink the text to fit narrow sensors
+    let chars = text.chars().count().max(1) as f32;
+    let scale = ((img.width() as f32 - 4.0) / (chars * 0.6)).min(FOOTER_HEIGHT as f32 - 2.0);
+    draw_text_mut(
+        &mut out,
+        Rgb([255, 255, 255]),
+        2,
+        img.height() as i32 + 1,
+        PxScale::from(scale),
+        &font,
+        text,
+    );
+    Ok(out)
+}
+
+///
+/// Saves the colored image of a capture result, along with its metadata.
+/// Shared by all of the recorders which write still images.
+///
+pub fn save_result_image(
+    result: &ThermalCapturerResult,
+    path: &Path,
+    image_format: ImageFormat,
+    metadata: &ImageMetadata,
+    timestamp: DateTime<Local>,
+) -> Result<(), anyhow::Error> {
+    let image = &result.image;
+    let rgba_img = image::RgbaImage::from_raw(
+        image.width() as u32,
+        image.height() as u32,
+        image.as_raw().into(),
+    )
+    .ok_or(anyhow!("Failed to create image when saving snapshot"))?;
+
+    // Convert to Rgb8, we don't need the alpha channel
+    let mut img = rgba8_to_rgb8(rgba_img);
+    metadata.overlay.burn_in(&mut img, result, timestamp)?;
+
+    if metadata.draw_footer {
+        img = with_footer(&img, &metadata.footer_text(result, timestamp))?;
+    }
+
+    match image_format {
+        ImageFormat::Png => {
+            let temperatures = metadata
+                .embed_temperatures
+                .then_some(result.thermal_data.as_ref());
+            save_png_with_text(
+                &img,
+                path,
+                metadata.metadata_entries(result, timestamp),
+                temperatures,
+            )?
+        }
+        ImageFormat::Jpeg => img.save(path)?,
+    }
+    Ok(())
+}
+
+///
+/// Writes an RGB image as PNG with the given text chunks,
+/// and the temperatures in a radiometric chunk if given.
+///
+fn save_png_with_text(
+    img: &RgbImage,
+    path: &Path,
+    entries: Vec<(&'static str, String)>,
+    temperatures: Option<&ThermalData>,
+) -> Result<(), anyhow::Error> {
+    let file = File::create(path)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), img.width(), img.height());
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    for (keyword, text) in entries {
+        // iTXt chunks are UTF-8, needed for the degree sign
+        encoder.add_itxt_chunk(keyword.to_string(), text)?;
+    }
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(img.as_raw())?;
+    if let Some(temperatures) = temperatures {
+        write_radiometric_chunk(&mut writer, temperatures)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
 impl Recorder for ImageRecorder {
     fn start(
         &mut self,
@@ -49,17 +251,6 @@ impl Recorder for ImageRecorder {
     }
 
     fn process_result(&mut self, result: &ThermalCapturerResult) -> Result<(), anyhow::Error> {
-        let image = &result.image;
-        let rgba_img = image::RgbaImage::from_raw(
-            image.width() as u32,
-            image.height() as u32,
-            image.as_raw().into(),
-        )
-        .ok_or(anyhow!("Failed to create image when saving snapshot"))?;
-
-        // Convert to Rgb8, we don't need the alpha channel
-        let img = rgba8_to_rgb8(rgba_img);
-
         std::fs::create_dir_all(self.destination_folder.clone())?;
         let current_local: DateTime<Local> = Local::now();
 
@@ -71,7 +262,13 @@ impl Recorder for ImageRecorder {
         );
 
         let save_path = self.destination_folder.join(PathBuf::from(filename));
-        img.save(save_path.clone())?;
+        save_result_image(
+            result,
+            &save_path,
+            self.image_format,
+            &self.metadata,
+            current_local,
+        )?;
         self.output_file = Some(save_path);
         self.curr_state = RecorderState::Done;
         Ok(())
@@ -1,3 +1,5 @@
+pub mod auto_snapshot_recorder;
 pub mod image_recorder;
 pub mod recorder;
+pub mod timelapse_recorder;
 pub mod video_recorder;
@@ -0,0 +1,183 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Local};
+
+use crate::{thermal_capturer::ThermalCapturerResult, types::media_formats::ImageFormat};
+
+use super::{
+    image_recorder::{save_result_image, ImageMetadata},
+    recorder::{Recorder, RecorderState, RecorderStreamParams},
+    video_recorder::VideoRecorder,
+};
+
+///
+/// When the frames of a timelapse are taken: one every `interval`,
+/// between the optional start and stop times.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct TimelapseSchedule {
+    interval: Duration,
+    // Now if not set
+    start_at: Option<DateTime<Local>>,
+    // Until stopped if not set
+    stop_at: Option<DateTime<Local>>,
+    last_capture: Option<Instant>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimelapseStep {
+    Wait,
+    Capture,
+    // The stop time has passed
+    Finished,
+}
+
+impl TimelapseSchedule {
+    pub fn new(
+        interval: Duration,
+        start_at: Option<DateTime<Local>>,
+        stop_at: Option<DateTime<Local>>,
+    ) -> Self {
+        Self {
+            interval,
+            start_at,
+            stop_at,
+            last_capture: None,
+        }
+    }
+
+    ///
+    /// Decides what to do with the current frame. Wall-clock based,
+    /// so the interval holds regardless of the frame rate.
+    ///
+    pub fn next_step(&mut self) -> TimelapseStep {
+        let now = Local::now();
+        if self.stop_at.is_some_and(|stop_at| now >= stop_at) {
+            return TimelapseStep::Finished;
+        }
+        if self.start_at.is_some_and(|start_at| now < start_at)
+            || self
+                .last_capture
+                .is_some_and(|last| last.elapsed() < self.interval)
+        {
+            return TimelapseStep::Wait;
+        }
+        self.last_capture = Some(Instant::now());
+        TimelapseStep::Capture
+    }
+}
+
+///
+/// Saves a still image and/or a video frame on the TimelapseSchedule, for monitoring
+/// over long periods without recording a full video. Frames are numbered so they sort
+/// in capture order.
+///
+pub struct TimelapseRecorder {
+    // Params
+    destination_folder: PathBuf,
+    schedule: TimelapseSchedule,
+    metadata: ImageMetadata,
+    save_images: bool,
+    // Gets one frame per capture, so the video plays the timelapse
+    video: Option<VideoRecorder>,
+
+    // State
+    frame_index: usize,
+
+    // Output info
+    last_output_file: Option<PathBuf>,
+    curr_state: RecorderState,
+}
+
+impl TimelapseRecorder {
+    pub fn new(
+        destination_folder: PathBuf,
+        schedule: TimelapseSchedule,
+        metadata: ImageMetadata,
+        save_images: bool,
+        video: Option<VideoRecorder>,
+    ) -> TimelapseRecorder {
+        TimelapseRecorder {
+            destination_folder,
+            schedule,
+            metadata,
+            save_images,
+            video,
+            frame_index: 0,
+            last_output_file: None,
+            curr_state: RecorderState::Initial,
+        }
+    }
+}
+
+impl Recorder for TimelapseRecorder {
+    fn start(&mut self, params: RecorderStreamParams) -> Result<(), anyhow::Error> {
+        std::fs::create_dir_all(self.destination_folder.clone())?;
+        if let Some(video) = self.video.as_mut() {
+            video.start(params)?;
+        }
+        self.curr_state = RecorderState::Recording;
+        Ok(())
+    }
+
+    fn process_result(&mut self, result: &ThermalCapturerResult) -> Result<(), anyhow::Error> {
+        match self.schedule.next_step() {
+            TimelapseStep::Wait => return Ok(()),
+            TimelapseStep::Finished => return self.stop(),
+            TimelapseStep::Capture => {}
+        }
+
+        if self.save_images {
+            let current_local: DateTime<Local> = Local::now();
+            let filename = format!(
+                "timelapse_{:06}_{}.{}",
+                self.frame_index,
+                current_local.format("%Y-%m-%d_%H-%M-%S"),
+                ImageFormat::Png.extension()
+            );
+            let save_path = self.destination_folder.join(filename);
+            save_result_image(
+                result,
+                &save_path,
+                ImageFormat::Png,
+                &self.metadata,
+                current_local,
+            )?;
+            self.last_output_file = Some(save_path);
+        }
+        if let Some(video) = self.video.as_mut() {
+            video.process_result(result)?;
+        }
+
+        self.frame_index += 1;
+        Ok(())
+    }
+
+    fn state(&self) -> RecorderState {
+        self.curr_state
+    }
+
+    fn files_created(&self) -> Vec<PathBuf> {
+        // Only the latest frame, a long timelapse would flood the gallery otherwise
+        self.last_output_file
+            .iter()
+            .cloned()
+            .chain(self.video.iter().flat_map(|video| video.files_created()))
+            .collect()
+    }
+
+    fn stop(&mut self) -> Result<(), anyhow::Error> {
+        self.curr_state = RecorderState::Done;
+        if let Some(video) = self.video.as_mut() {
+            video.stop()?;
+        }
+        Ok(())
+    }
+
+    fn is_continuous(&self) -> bool {
+        true
+    }
+}
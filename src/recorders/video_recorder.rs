@@ -1,8 +1,11 @@
 extern crate ffmpeg_next as ffmpeg;
 use std::sync::mpsc::Sender;
 use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::Instant;
 use std::{path::PathBuf, sync::mpsc::channel, thread};
 
+use crate::overlay::Overlay;
 use crate::util::rgba8_to_rgb8;
 use crate::{types::media_formats::VideoFormat, util::pathify_string};
 use anyhow::anyhow;
@@ -13,18 +16,54 @@ use ffmpeg::rescale::TIME_BASE;
 use ffmpeg::software::scaling::Flags;
 use ffmpeg::{codec, encoder, format, frame, picture, Dictionary, Packet, Rational};
 use image::RgbImage;
+use serde::{Deserialize, Serialize};
 use std::borrow::BorrowMut;
 
 use super::recorder::{Recorder, RecorderState};
 
+pub const MAX_VIDEO_FRAME_RATE: u32 = 60;
+pub const MIN_VIDEO_BIT_RATE_KBPS: u32 = 100;
+pub const MAX_VIDEO_BIT_RATE_KBPS: u32 = 20_000;
+
+///
+/// How videos are encoded, chosen in the capture pane or the headless config.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VideoSettings {
+    pub format: VideoFormat,
+    // Frames per second of the video (1..=MAX_VIDEO_FRAME_RATE), None for the rate of the camera.
+    // Frames are dropped or repeated so the video plays in real time.
+    pub frame_rate: Option<u32>,
+    pub bit_rate_kbps: u32,
+}
+
+impl Default for VideoSettings {
+    fn default() -> Self {
+        Self {
+            format: VideoFormat::MP4_H264,
+            frame_rate: None,
+            bit_rate_kbps: 1000,
+        }
+    }
+}
+
 pub struct VideoRecorder {
     // Params
     destination_folder: PathBuf,
     name_prefix: String,
-    video_format: VideoFormat,
+    settings: VideoSettings,
+    overlay: Overlay,
+    // Repeats or drops frames to play in real time, otherwise every frame is one video frame
+    real_time: bool,
 
     // State
     tx_frames: Option<Sender<RgbImage>>,
+    encoder_thread: Option<JoinHandle<()>>,
+    // Frame rate of the video, known once recording starts
+    frame_rate: usize,
+    started_at: Option<Instant>,
+    frames_sent: u64,
 
     // Output info
     output_file: Option<PathBuf>,
@@ -35,17 +74,54 @@ impl VideoRecorder {
     pub fn new(
         destination_folder: PathBuf,
         name_prefix: String,
-        video_format: VideoFormat,
+        settings: VideoSettings,
+        overlay: Overlay,
     ) -> VideoRecorder {
         VideoRecorder {
             destination_folder,
             name_prefix,
-            video_format,
+            settings,
+            overlay,
+            real_time: true,
             tx_frames: None,
+            encoder_thread: None,
+            frame_rate: 1,
+            started_at: None,
+            frames_sent: 0,
             output_file: None,
             curr_state: RecorderState::Initial,
         }
     }
+
+    ///
+    /// A video with one frame per given result, e.g. for a timelapse that is
+    /// sped up by the interval between its captures.
+    ///
+    pub fn new_timelapse(
+        destination_folder: PathBuf,
+        name_prefix: String,
+        settings: VideoSettings,
+        overlay: Overlay,
+    ) -> VideoRecorder {
+        VideoRecorder {
+            real_time: false,
+            ..Self::new(destination_folder, name_prefix, settings, overlay)
+        }
+    }
+
+    ///
+    /// Stops the recording and waits until the encoder has written the end of the file,
+    /// e.g. before exiting.
+    ///
+    pub fn finish(&mut self) -> Result<(), anyhow::Error> {
+        self.stop()?;
+        if let Some(encoder_thread) = self.encoder_thread.take() {
+            encoder_thread
+                .join()
+                .map_err(|_| anyhow!("The video encoder panicked"))?;
+        }
+        Ok(())
+    }
 }
 
 impl Recorder for VideoRecorder {
@@ -64,7 +140,7 @@ impl Recorder for VideoRecorder {
             "{}_{}.{}",
             pathify_string(self.name_prefix.clone()),
             current_local.format("%Y-%m-%d_%H-%M-%S"),
-            self.video_format.extension()
+            self.settings.format.extension()
         );
 
         let (tx_frames, rx_frames) = channel();
@@ -87,18 +163,32 @@ impl Recorder for VideoRecorder {
             .encoder()
             .video()?;
 
+        let framerate = self
+            .settings
+            .frame_rate
+            .map_or(params.framerate, |rate| {
+                rate.clamp(1, MAX_VIDEO_FRAME_RATE) as usize
+            })
+            .max(1);
+        self.frame_rate = framerate;
+
         encoder.set_height(params.height as u32);
         encoder.set_width(params.width as u32);
-        encoder.set_frame_rate(Some(Rational::new(params.framerate as i32, 1)));
+        encoder.set_frame_rate(Some(Rational::new(framerate as i32, 1)));
         encoder.set_format(Pixel::YUV420P);
 
         encoder.set_qmin(10);
         encoder.set_qmax(51);
-        encoder.set_bit_rate(1_000_000);
+        encoder.set_bit_rate(
+            self.settings
+                .bit_rate_kbps
+                .clamp(MIN_VIDEO_BIT_RATE_KBPS, MAX_VIDEO_BIT_RATE_KBPS) as usize
+                * 1000,
+        );
         encoder.set_me_range(16);
         encoder.set_i_quant_factor(0.71);
 
-        encoder.set_time_base(Rational::new(1, params.framerate as i32)); // todo change this?
+        encoder.set_time_base(Rational::new(1, framerate as i32)); // todo change this?
         if global_header {
             encoder.set_flags(ffmpeg::codec::flag::Flags::GLOBAL_HEADER);
         }
@@ -115,7 +205,7 @@ impl Recorder for VideoRecorder {
         octx.write_header()?;
         let mutexed_octx = Mutex::new(octx);
 
-        thread::spawn(move || {
+        self.encoder_thread = Some(thread::spawn(move || {
             let mut scaler = ffmpeg::software::scaling::context::Context::get(
                 Pixel::RGB24,
                 params.width as u32,
@@ -135,11 +225,11 @@ impl Recorder for VideoRecorder {
 
                 video_frame.set_kind(picture::Type::None);
                 scaler.run(&video_frame, &mut yuv_frame).unwrap();
-                yuv_frame.set_pts(Some((i as i64) * (1_000_000 / params.framerate as i64)));
+                yuv_frame.set_pts(Some((i as i64) * (1_000_000 / framerate as i64)));
                 match encoder.send_frame(&yuv_frame) {
                     Ok(_) => {}
                     Err(err) => {
-                        log::error!("failed to send frame: {}", err);
+                        tracing::error!(error = %err, "failed to send frame");
                         break;
                     }
                 }
@@ -155,13 +245,13 @@ impl Recorder for VideoRecorder {
             }
 
             let _ = encoder.send_eof().inspect_err(|err| {
-                log::error!("failed to finish encoding: {}", err);
+                tracing::error!(error = %err, "failed to finish encoding");
             });
 
             if let Err(err) = mutexed_octx.lock().unwrap().write_trailer() {
-                log::error!("failed to write trailer: {}", err);
+                tracing::error!(error = %err, "failed to write trailer");
             }
-        });
+        }));
         self.curr_state = RecorderState::Recording;
         Ok(())
     }
@@ -171,7 +261,7 @@ impl Recorder for VideoRecorder {
         result: &crate::thermal_capturer::ThermalCapturerResult,
     ) -> Result<(), anyhow::Error> {
         if let Some(tx_frames) = &self.tx_frames {
-            let rgb_img = rgba8_to_rgb8(
+            let mut rgb_img = rgba8_to_rgb8(
                 image::RgbaImage::from_raw(
                     result.image.width() as u32,
                     result.image.height() as u32,
@@ -179,8 +269,26 @@ impl Recorder for VideoRecorder {
                 )
                 .ok_or(anyhow!("Failed to create image when copying frame"))?,
             );
+            self.overlay.burn_in(&mut rgb_img, result, Local::now())?;
 
-            tx_frames.send(rgb_img)?;
+            if !self.real_time {
+                tx_frames.send(rgb_img)?;
+                self.frames_sent += 1;
+                return Ok(());
+            }
+
+            // the frames due by now, to keep the frame rate of the video
+            let started_at = *self.started_at.get_or_insert(result.capture_time);
+            let due = (result
+                .capture_time
+                .saturating_duration_since(started_at)
+                .as_secs_f64()
+                * self.frame_rate as f64) as u64
+                + 1;
+            for _ in self.frames_sent..due {
+                tx_frames.send(rgb_img.clone())?;
+            }
+            self.frames_sent = self.frames_sent.max(due);
         }
         Ok(())
     }
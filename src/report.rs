@@ -0,0 +1,257 @@
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local};
+
+use crate::{
+    gizmos::GizmoKind,
+    temperature::{format_reading, format_temp, TemperatureUnit},
+    thermal_capturer::{ThermalCapturerResult, ThermalCapturerSettings},
+    util::pathify_string,
+};
+
+///
+/// A snapshot in a report, with the readings and settings at the time it was taken.
+///
+pub struct ReportItem {
+    pub image: PathBuf,
+    pub captured_at: DateTime<Local>,
+    pub caption: String,
+    pub notes: String,
+    // Name of each gizmo with its formatted reading
+    pub readings: Vec<(String, String)>,
+    // e.g. ("Emissivity", "0.95")
+    pub settings: Vec<(String, String)>,
+}
+
+impl ReportItem {
+    ///
+    /// Describes a frame whose image was saved to `image`.
+    ///
+    pub fn from_result(
+        image: PathBuf,
+        result: &ThermalCapturerResult,
+        settings: &ThermalCapturerSettings,
+        unit: TemperatureUnit,
+        precision: usize,
+    ) -> Self {
+        let format_value = |value| format_reading(value, unit, precision, result.is_delta);
+        let readings = match &settings.gizmo.kind {
+            GizmoKind::Root { children } => children
+                .iter()
+                .filter_map(|gizmo| {
+                    let gizmo_result = result.extension.gizmo_results.get(&gizmo.uuid)?;
+                    let reading = match &gizmo_result.stats {
+                        Some(stats) => format!(
+                            "max {}, mean {}, min {}",
+                            format_value(stats.max),
+                            format_value(stats.mean),
+                            format_value(stats.min)
+                        ),
+                        None => format_value(gizmo_result.temperature),
+                    };
+                    Some((gizmo.name.clone(), reading))
+                })
+                .collect(),
+            _ => vec![],
+        };
+        let settings = vec![
+            (
+                "Range".to_string(),
+                format!(
+                    "{} – {}",
+                    format_value(result.image_range.min),
+                    format_value(result.image_range.max)
+                ),
+            ),
+            (
+                "Emissivity".to_string(),
+                format!("{:.2}", settings.emissivity),
+            ),
+            (
+                "Reflected temperature".to_string(),
+                format_temp(settings.reflected_temperature, unit, precision),
+            ),
+            ("Gradient".to_string(), settings.gradient.name.clone()),
+            (
+                "Resolution".to_string(),
+                format!(
+                    "{}x{}",
+                    result.thermal_data.width, result.thermal_data.height
+                ),
+            ),
+        ];
+        Self {
+            image,
+            captured_at: Local::now(),
+            caption: String::new(),
+            notes: String::new(),
+            readings,
+            settings,
+        }
+    }
+
+    ///
+    /// An image saved earlier (e.g. from the gallery), whose readings aren't known.
+    ///
+    pub fn from_image(image: PathBuf) -> Self {
+        let captured_at = fs::metadata(&image)
+            .and_then(|metadata| metadata.modified())
+            .map(DateTime::<Local>::from)
+            .unwrap_or_else(|_| Local::now());
+        Self {
+            image,
+            captured_at,
+            caption: String::new(),
+            notes: String::new(),
+            readings: vec![],
+            settings: vec![],
+        }
+    }
+}
+
+///
+/// An inspection report, built up in the report pane and written as a self-contained folder
+/// with an HTML page and the images. The page has print styles, so it can be saved as PDF
+/// from any browser.
+///
+#[derive(Default)]
+pub struct InspectionReport {
+    pub title: String,
+    pub inspector: String,
+    pub location: String,
+    pub notes: String,
+    pub items: Vec<ReportItem>,
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Keeps the line breaks of notes typed into multiline fields
+fn escape_html_multiline(text: &str) -> String {
+    escape_html(text).replace('\n', "<br>\n")
+}
+
+const REPORT_STYLE: &str =
+    "body { font-family: sans-serif; max-width: 900px; margin: 2em auto; color: #222; }
+h1 { margin-bottom: 0.2em; }
+.meta td { padding-right: 1.5em; }
+.item { page-break-inside: avoid; border-top: 1px solid #ccc; margin-top: 1.5em; padding-top: 1em; }
+.item img { max-width: 100%; }
+table.values { border-collapse: collapse; margin: 0.5em 0; }
+table.values td, table.values th { border: 1px solid #ccc; padding: 0.2em 0.6em; text-align: left; }
+@media print { body { margin: 0; max-width: none; } }
+";
+
+impl InspectionReport {
+    ///
+    /// Writes `report.html` into `directory` and copies the images next to it,
+    /// so the folder can be handed over as is. Returns the path of the page.
+    ///
+    pub fn write_html(&self, directory: &Path) -> Result<PathBuf> {
+        fs::create_dir_all(directory.join("images"))?;
+
+        let title = if self.title.trim().is_empty() {
+            "Thermal inspection report"
+        } else {
+            self.title.trim()
+        };
+        let path = directory.join("report.html");
+        let mut writer = BufWriter::new(File::create(&path)?);
+        writeln!(writer, "<!DOCTYPE html>")?;
+        writeln!(writer, "<html><head><meta charset=\"utf-8\">")?;
+        writeln!(writer, "<title>{}</title>", escape_html(title))?;
+        writeln!(writer, "<style>\n{}</style></head><body>", REPORT_STYLE)?;
+        writeln!(writer, "<h1>{}</h1>", escape_html(title))?;
+
+        writeln!(writer, "<table class=\"meta\">")?;
+        let meta = [
+            ("Date", Local::now().format("%Y-%m-%d %H:%M").to_string()),
+            ("Inspector", self.inspector.clone()),
+            ("Location", self.location.clone()),
+        ];
+        for (name, value) in meta.iter().filter(|(_, value)| !value.trim().is_empty()) {
+            writeln!(
+                writer,
+                "<tr><td><b>{}</b></td><td>{}</td></tr>",
+                name,
+                escape_html(value)
+            )?;
+        }
+        writeln!(writer, "</table>")?;
+        if !self.notes.trim().is_empty() {
+            writeln!(writer, "<p>{}</p>", escape_html_multiline(&self.notes))?;
+        }
+
+        for (i, item) in self.items.iter().enumerate() {
+            let extension = item
+                .image
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_string())
+                .unwrap_or_else(|| "png".to_string());
+            let stem = item
+                .image
+                .file_stem()
+                .map(|stem| pathify_string(stem.to_string_lossy().to_string()))
+                .unwrap_or_default();
+            // numbered, different snapshots may share a name
+            let image_name = format!("{:03}_{}.{}", i + 1, stem, extension);
+            fs::copy(&item.image, directory.join("images").join(&image_name))
+                .map_err(|err| anyhow!("Failed to copy {}: {}", item.image.display(), err))?;
+
+            writeln!(writer, "<div class=\"item\">")?;
+            let caption = if item.caption.trim().is_empty() {
+                format!("Image {}", i + 1)
+            } else {
+                item.caption.trim().to_string()
+            };
+            writeln!(writer, "<h2>{}</h2>", escape_html(&caption))?;
+            writeln!(
+                writer,
+                "<p>{}</p>",
+                item.captured_at.format("%Y-%m-%d %H:%M:%S")
+            )?;
+            writeln!(
+                writer,
+                "<img src=\"images/{}\" alt=\"{}\">",
+                escape_html(&image_name),
+                escape_html(&caption)
+            )?;
+            for (heading, values) in [("Reading", &item.readings), ("Setting", &item.settings)] {
+                if values.is_empty() {
+                    continue;
+                }
+                writeln!(
+                    writer,
+                    "<table class=\"values\"><tr><th>{}</th><th>Value</th></tr>",
+                    heading
+                )?;
+                for (name, value) in values.iter() {
+                    writeln!(
+                        writer,
+                        "<tr><td>{}</td><td>{}</td></tr>",
+                        escape_html(name),
+                        escape_html(value)
+                    )?;
+                }
+                writeln!(writer, "</table>")?;
+            }
+            if !item.notes.trim().is_empty() {
+                writeln!(writer, "<p>{}</p>", escape_html_multiline(&item.notes))?;
+            }
+            writeln!(writer, "</div>")?;
+        }
+
+        writeln!(writer, "</body></html>")?;
+        writer.flush()?;
+        Ok(path)
+    }
+}
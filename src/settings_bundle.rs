@@ -0,0 +1,67 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{temperature::TemperatureUnit, thermal_capturer::ThermalCapturerSettings};
+
+// Bump when the bundle layout changes in a way older versions cannot read
+const SETTINGS_BUNDLE_VERSION: u32 = 1;
+
+///
+/// All of the image settings in a single file, so that a setup can be shared with
+/// other users. The selected gradient is embedded in the settings, so custom gradients
+/// travel along with the bundle.
+///
+#[derive(Serialize, Deserialize)]
+pub struct SettingsBundle {
+    pub bundle_version: u32,
+    pub temperature_unit: TemperatureUnit,
+    pub settings: ThermalCapturerSettings,
+}
+
+// Only the version is parsed first, so that a newer bundle is refused with
+// a clear message instead of a confusing deserialization error
+#[derive(Deserialize)]
+struct SettingsBundleHeader {
+    bundle_version: u32,
+}
+
+pub fn export_bundle(
+    path: &Path,
+    settings: &ThermalCapturerSettings,
+    temperature_unit: TemperatureUnit,
+) -> Result<()> {
+    let bundle = SettingsBundle {
+        bundle_version: SETTINGS_BUNDLE_VERSION,
+        temperature_unit,
+        settings: settings.clone(),
+    };
+    let file = File::create(path)?;
+    let writer = BufWriter::new(file);
+    serde_json::to_writer_pretty(writer, &bundle)?;
+    Ok(())
+}
+
+pub fn import_bundle(path: &Path) -> Result<SettingsBundle> {
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+    let value: serde_json::Value = serde_json::from_reader(reader)?;
+
+    let header: SettingsBundleHeader = serde_json::from_value(value.clone())
+        .map_err(|_| anyhow!("{} is not a settings bundle", path.display()))?;
+    if header.bundle_version > SETTINGS_BUNDLE_VERSION {
+        return Err(anyhow!(
+            "Settings bundle version {} is newer than the supported version {}. Please update the application.",
+            header.bundle_version,
+            SETTINGS_BUNDLE_VERSION
+        ));
+    }
+
+    let bundle: SettingsBundle = serde_json::from_value(value)?;
+    Ok(bundle)
+}
@@ -0,0 +1,129 @@
+use std::collections::VecDeque;
+
+use crate::{
+    annotations::Annotation, gizmos::Gizmo, temperature::TempRange,
+    thermal_capturer::ThermalCapturerSettings, thermal_gradient::ThermalGradient,
+};
+
+// Older steps are forgotten
+pub const MAX_UNDO_STEPS: usize = 100;
+
+///
+/// The part of the settings covered by undo/redo: what is edited on the image
+/// (gizmos, annotations, gradient and range), not the setup of the camera.
+///
+struct UndoableSettings {
+    gizmo: Gizmo,
+    annotations: Vec<Annotation>,
+    gradient: ThermalGradient,
+    auto_range: bool,
+    manual_range: TempRange,
+}
+
+impl UndoableSettings {
+    fn from_settings(settings: &ThermalCapturerSettings) -> Self {
+        Self {
+            gizmo: settings.gizmo.clone(),
+            annotations: settings.annotations.clone(),
+            gradient: settings.gradient.clone(),
+            auto_range: settings.auto_range,
+            manual_range: settings.manual_range,
+        }
+    }
+
+    fn apply_to(self, settings: &mut ThermalCapturerSettings) {
+        settings.gizmo = self.gizmo;
+        settings.annotations = self.annotations;
+        settings.gradient = self.gradient;
+        settings.auto_range = self.auto_range;
+        settings.manual_range = self.manual_range;
+    }
+
+    fn matches(&self, settings: &ThermalCapturerSettings) -> bool {
+        self.gizmo == settings.gizmo
+            && self.annotations == settings.annotations
+            && self.gradient == settings.gradient
+            && self.auto_range == settings.auto_range
+            && self.manual_range == settings.manual_range
+    }
+}
+
+///
+/// Undo and redo of the settings edits. The state before an edit is remembered when
+/// the settings are edited (see `begin_edit`), and becomes a step once the edit ends,
+/// so e.g. dragging a gizmo over many frames is one step.
+///
+pub struct SettingsHistory {
+    // State before the edit in progress
+    editing: Option<UndoableSettings>,
+    undo_stack: VecDeque<UndoableSettings>,
+    redo_stack: Vec<UndoableSettings>,
+}
+
+impl SettingsHistory {
+    pub fn new() -> Self {
+        Self {
+            editing: None,
+            undo_stack: VecDeque::new(),
+            redo_stack: vec![],
+        }
+    }
+
+    ///
+    /// Called before the settings are edited, the first edit of a step remembers the state before it.
+    ///
+    pub fn begin_edit(&mut self, settings: &ThermalCapturerSettings) {
+        if self.editing.is_none() {
+            self.editing = Some(UndoableSettings::from_settings(settings));
+        }
+    }
+
+    ///
+    /// Ends the edit in progress, which becomes a step unless it left the undoable part unchanged.
+    ///
+    pub fn end_edit(&mut self, settings: &ThermalCapturerSettings) {
+        match self.editing.take() {
+            Some(previous) if !previous.matches(settings) => {
+                self.undo_stack.push_back(previous);
+                if self.undo_stack.len() > MAX_UNDO_STEPS {
+                    self.undo_stack.pop_front();
+                }
+                self.redo_stack.clear();
+            }
+            _ => {}
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    ///
+    /// Restores the state before the last step, returns false if there is none.
+    ///
+    pub fn undo(&mut self, settings: &mut ThermalCapturerSettings) -> bool {
+        self.end_edit(settings);
+        let Some(state) = self.undo_stack.pop_back() else {
+            return false;
+        };
+        self.redo_stack
+            .push(UndoableSettings::from_settings(settings));
+        state.apply_to(settings);
+        true
+    }
+
+    pub fn redo(&mut self, settings: &mut ThermalCapturerSettings) -> bool {
+        self.end_edit(settings);
+        let Some(state) = self.redo_stack.pop() else {
+            return false;
+        };
+        self.undo_stack
+            .push_back(UndoableSettings::from_settings(settings));
+        state.apply_to(settings);
+        true
+    }
+}
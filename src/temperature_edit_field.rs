@@ -1,16 +1,44 @@
+use std::ops::RangeInclusive;
+
 use eframe::egui::{self, DragValue, Response, Ui};
 
-use crate::temperature::{Temp, TempRange, TemperatureUnit};
+use crate::temperature::{Temp, TempDelta, TempFormat, TempRange, TemperatureUnit};
 
-pub fn temperature_edit_field(ui: &mut Ui, unit: TemperatureUnit, value: &mut Temp) -> Response {
-    let mut tmp_value = value.to_unit(unit);
+pub fn temperature_edit_field(ui: &mut Ui, format: TempFormat, value: &mut Temp) -> Response {
+    let mut tmp_value = value.to_unit(format.unit);
     let res = ui.add(
         DragValue::new(&mut tmp_value)
             .speed(0.5)
-            .max_decimals(1)
-            .suffix(unit.suffix()),
+            .max_decimals(format.precision)
+            .suffix(format!(" {}", format.suffix())),
+    );
+    *value = Temp::from_unit(format.unit, tmp_value);
+    res
+}
+
+///
+/// Edits a temperature difference stored in Kelvin (e.g. a threshold), shown in the unit
+/// of `format`. `clamp_kelvin` is the allowed range in Kelvin.
+///
+pub fn temperature_delta_edit_field(
+    ui: &mut Ui,
+    format: TempFormat,
+    delta_kelvin: &mut f32,
+    clamp_kelvin: RangeInclusive<f32>,
+) -> Response {
+    let to_unit = |kelvin: f32| TempDelta::new(kelvin).to_unit(format.unit);
+    let mut tmp_value = to_unit(*delta_kelvin);
+    let res = ui.add(
+        DragValue::new(&mut tmp_value)
+            .speed(0.05)
+            .clamp_range(to_unit(*clamp_kelvin.start())..=to_unit(*clamp_kelvin.end()))
+            .max_decimals(format.precision.max(2))
+            .suffix(format!(" {}", format.suffix())),
     );
-    *value = Temp::from_unit(unit, tmp_value);
+    if res.changed() {
+        *delta_kelvin =
+            TempDelta::from_unit(format.unit, tmp_value).to_unit(TemperatureUnit::Kelvin);
+    }
     res
 }
 
@@ -18,7 +46,7 @@ pub fn temperature_range_edit_field(
     ui: &mut Ui,
     id_source: impl std::hash::Hash,
     enabled: bool,
-    unit: TemperatureUnit,
+    format: TempFormat,
     value: &mut TempRange,
 ) -> Response {
     let mut did_change = false;
@@ -28,13 +56,13 @@ pub fn temperature_range_edit_field(
             ui.label("Min");
             ui.label("Max");
             ui.end_row();
-            if temperature_edit_field(ui, unit, &mut value.min).changed() {
+            if temperature_edit_field(ui, format, &mut value.min).changed() {
                 did_change = true;
                 if value.min > value.max {
                     value.max = value.min;
                 }
             }
-            if temperature_edit_field(ui, unit, &mut value.max).changed() {
+            if temperature_edit_field(ui, format, &mut value.max).changed() {
                 did_change = true;
                 if value.max < value.min {
                     value.min = value.max;
@@ -1,49 +1,229 @@
 use std::{
     collections::HashMap,
     path::PathBuf,
-    sync::{mpsc, Arc, Mutex},
-    thread,
+    sync::{Arc, Mutex},
 };
 
 use anyhow::{anyhow, Error};
-use chrono::{DateTime, Local};
-use eframe::epaint::{Color32, ColorImage};
-use image::RgbImage;
-use nokhwa::Camera;
+use eframe::epaint::{textures::TextureFilter, Color32};
+use serde::{Deserialize, Serialize};
+use thermal_cat_core::thermal_capturer::{CapturerExtension, ProcessedFrame, ProcessingSettings};
 use uuid::Uuid;
 
 use crate::{
-    auto_display_range_controller::AutoDisplayRangeController,
-    camera_adapter::CameraAdapter,
+    alarms::{ActiveAlarm, AlarmEvaluator, AlarmRule},
+    annotations::{annotations_sidecar_path, save_annotations, Annotation},
+    auto_display_range_controller::{AutoRangeSmoothing, RangeLock},
+    change_detection::ChangeDetectionSettings,
+    denoise::DenoiseSettings,
     dynamic_range_curve::DynamicRangeCurve,
-    gizmos::{Gizmo, GizmoKind, GizmoResult},
-    recorders::recorder::{Recorder, RecorderState, RecorderStreamParams},
-    temperature::{Temp, TempRange},
-    thermal_data::ThermalDataHistogram,
-    thermal_gradient::ThermalGradient,
-    types::{image_rotation::ImageRotation, media_formats::VideoFormat},
-    util::{pathify_string, rgba8_to_rgb8},
+    fusion::FusionSettings,
+    gizmos::{Gizmo, GizmoKind, GizmoResult, GizmoStats},
+    grid_analysis::GridAnalysisSettings,
+    histogram_equalization::HistogramEqualization,
+    isotherm::{isotherm_color, Isotherm},
+    lens_distortion::LensDistortion,
+    measurement_log::{MeasurementLogParams, MeasurementLogger},
+    radiometric_recording::RecordingController,
+    recorders::{
+        recorder::{Recorder, RecorderState, RecorderStreamParams},
+        timelapse_recorder::TimelapseSchedule,
+    },
+    temperature::{Temp, TempRange, TemperatureUnit},
+    temporal_average::TemporalAverageSettings,
+    thermal_data::{ThermalData, ThermalDataPos, ThermalDataRect},
+    thermal_gradient::{GradientLut, ThermalGradient, DELTA_GRADIENT, THERMAL_GRADIENTS},
+    triggered_recording::{TriggeredRecorder, TriggeredRecordingParams},
+    two_point_calibration::TwoPointCalibration,
+    types::{image_rotation::ImageRotation, mapping_mode::MappingMode},
+    upscaling::UpscaleFilter,
+    visible_capturer::VisibleFrames,
 };
 
-pub struct ThermalCapturerResult {
-    pub image: ColorImage,
-    pub image_range: TempRange,
-    pub real_fps: f32,
-    pub reported_fps: f32,
-    pub histogram: ThermalDataHistogram,
+pub use thermal_cat_core::thermal_capturer::{
+    ThermalCapturerStatus, MAX_AUTO_FFC_INTERVAL_SECS, MAX_PROCESSING_THREADS,
+    MIN_AUTO_FFC_INTERVAL_SECS,
+};
+
+///
+/// The capturer of thermal-cat: the pipeline of the core crate, with the gizmos, alarms
+/// and recordings added by `AppCapturerExtension`.
+///
+pub type ThermalCapturer =
+    thermal_cat_core::thermal_capturer::ThermalCapturer<AppCapturerExtension>;
+pub type ThermalCapturerResult =
+    thermal_cat_core::thermal_capturer::ThermalCapturerResult<AppFrameResults>;
+pub type ThermalCapturerEvent =
+    thermal_cat_core::thermal_capturer::ThermalCapturerEvent<AppFrameResults>;
+
+///
+/// What `AppCapturerExtension` adds to every result, in `ThermalCapturerResult::extension`.
+///
+pub struct AppFrameResults {
     pub gizmo_results: HashMap<Uuid, GizmoResult>,
-    pub capture_time: std::time::Instant,
+    // Results of the gizmos on the frozen frame, while the display is frozen
+    pub frozen_gizmo_results: Option<HashMap<Uuid, GizmoResult>>,
+    // Alarms whose condition has held for long enough
+    pub active_alarms: Vec<ActiveAlarm>,
+    pub is_recording_radiometric: bool,
+    pub is_logging_measurements: bool,
+    // Buffering frames to record when an alarm goes off
+    pub is_triggered_recording_armed: bool,
+    // Recording after an alarm went off
+    pub is_triggered_recording_active: bool,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ThermalCapturerSettings {
     pub auto_range: bool,
+    // Stop the auto range from adapting, keeping its last computed range
+    pub auto_range_held: bool,
+    // Percentage of the coldest and hottest pixels ignored by the auto range
+    pub auto_range_clip_percent: f32,
+    pub auto_range_smoothing: AutoRangeSmoothing,
+    // Span or level of the auto range kept fixed
+    pub auto_range_lock: RangeLock,
+    // Rectangle gizmo the auto range is computed from, the whole image if None or it was removed
+    pub auto_range_roi: Option<Uuid>,
     pub manual_range: TempRange,
     pub gradient: ThermalGradient,
     pub rotation: ImageRotation,
+    // Mirroring applied after the rotation, e.g. for a camera looking through a mirror
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
     pub gizmo: Gizmo,
     pub dynamic_range_curve: DynamicRangeCurve,
+    pub mapping_mode: MappingMode,
+    #[serde(skip)]
     pub recorders: Vec<Arc<Mutex<dyn Recorder>>>,
+    // Persisted on their own, so that they are not replaced when settings are imported
+    #[serde(skip)]
+    pub alarm_rules: Vec<AlarmRule>,
+
+    // Apply the flat field (non-uniformity) correction, if one has been captured
+    pub nuc_enabled: bool,
+    // Interpolate the pixels of the bad pixel map, if one has been detected
+    pub bad_pixel_correction: bool,
+    // Spatial noise reduction, before anything is measured
+    pub denoise: DenoiseSettings,
+    pub lens_distortion: LensDistortion,
+    pub temporal_average: TemporalAverageSettings,
+
+    // Area of the image for which a separate histogram is computed
+    pub histogram_region: Option<ThermalDataRect>,
+    // Number of histogram buckets (MIN_HISTOGRAM_BUCKETS..=MAX_HISTOGRAM_BUCKETS)
+    pub histogram_buckets: usize,
+    // Show the pixel counts of the histogram on a logarithmic scale, display only
+    pub histogram_log_scale: bool,
+
+    // Markers drawn over the image at the hottest and coldest pixels and at the center,
+    // display only
+    pub show_max_marker: bool,
+    pub show_min_marker: bool,
+    pub show_center_marker: bool,
+    // Color scale with the temperatures of the mapping range next to the image, display only
+    pub show_color_scale: bool,
+
+    // Temperature bands drawn in a solid color over the gradient (at most MAX_ISOTHERMS)
+    pub isotherms: Vec<Isotherm>,
+
+    // Isotherm contour lines drawn over the image (at most MAX_CONTOUR_LEVELS)
+    pub show_contours: bool,
+    pub contour_levels: Vec<Temp>,
+    // Mean or max of each cell of a grid, shown over the image
+    pub grid_analysis: GridAnalysisSettings,
+    // Labels and drawings on the image, saved with snapshots and recordings
+    pub annotations: Vec<Annotation>,
+
+    // Filter used when the image is scaled on screen, does not affect recordings
+    pub texture_filter: TextureFilter,
+    // Interpolation of the temperatures before colorizing, affects recordings as well
+    pub upscale_filter: UpscaleFilter,
+    // MIN_UPSCALE_FACTOR..=MAX_UPSCALE_FACTOR
+    pub upscale_factor: usize,
+    // Threads processing each frame in parallel (at most MAX_PROCESSING_THREADS), 0 uses one per core
+    pub processing_threads: usize,
+
+    // Run the shutter calibration (FFC) every so many seconds, None leaves it to the camera
+    pub auto_ffc_interval_secs: Option<u32>,
+
+    // Show the difference of each pixel to the reference frame, once one was captured
+    pub delta_mode: bool,
+    pub change_detection: ChangeDetectionSettings,
+
+    // Emissivity of the measured surface, 1.0 disables the correction
+    pub emissivity: f32,
+    // Apparent temperature of the surroundings reflected by the surface
+    pub reflected_temperature: Temp,
+    // Linear correction of the readings, from two references of known temperature
+    pub two_point_calibration: TwoPointCalibration,
+    pub fusion: FusionSettings,
+    // Frames of the visible-light camera, blended with the thermal image while it's open
+    #[serde(skip)]
+    pub visible_frames: Option<VisibleFrames>,
+}
+
+impl Default for ThermalCapturerSettings {
+    fn default() -> Self {
+        Self {
+            rotation: ImageRotation::None,
+            flip_horizontal: false,
+            flip_vertical: false,
+            auto_range: true,
+            auto_range_held: false,
+            auto_range_clip_percent: 0.0,
+            auto_range_smoothing: AutoRangeSmoothing::default(),
+            auto_range_lock: RangeLock::Off,
+            auto_range_roi: None,
+            manual_range: TempRange::new(
+                Temp::from_unit(TemperatureUnit::Celsius, 0.0),
+                Temp::from_unit(TemperatureUnit::Celsius, 50.0),
+            ),
+            gradient: THERMAL_GRADIENTS[0].clone(),
+            gizmo: Gizmo::new_root(vec![
+                Gizmo::new(GizmoKind::MaxTemp, "Max".to_string(), Color32::RED),
+                Gizmo::new(
+                    GizmoKind::MinTemp,
+                    "Min".to_string(),
+                    Color32::from_rgb(72, 219, 251),
+                ),
+            ]),
+            dynamic_range_curve: DynamicRangeCurve::default(),
+            mapping_mode: MappingMode::default(),
+            recorders: vec![],
+            alarm_rules: vec![],
+            nuc_enabled: true,
+            bad_pixel_correction: true,
+            denoise: DenoiseSettings::default(),
+            lens_distortion: LensDistortion::default(),
+            temporal_average: TemporalAverageSettings::default(),
+            histogram_region: None,
+            histogram_buckets: 100,
+            histogram_log_scale: false,
+            show_max_marker: false,
+            show_min_marker: false,
+            show_center_marker: false,
+            show_color_scale: true,
+            isotherms: vec![],
+            show_contours: false,
+            contour_levels: vec![Temp::from_celsius(30.0)],
+            grid_analysis: GridAnalysisSettings::default(),
+            annotations: vec![],
+            texture_filter: TextureFilter::Nearest,
+            upscale_filter: UpscaleFilter::default(),
+            upscale_factor: 4,
+            processing_threads: 0,
+            auto_ffc_interval_secs: None,
+            delta_mode: false,
+            change_detection: ChangeDetectionSettings::default(),
+            emissivity: 1.0,
+            reflected_temperature: Temp::from_celsius(20.0),
+            two_point_calibration: TwoPointCalibration::default(),
+            fusion: FusionSettings::default(),
+            visible_frames: None,
+        }
+    }
 }
 
 impl ThermalCapturerSettings {
@@ -52,211 +232,438 @@ impl ThermalCapturerSettings {
     // applying all necessary transformations (dynamic range curve, gradient)
     //
     // override_range should be the actual range of the image. If not available, pass None.
+    // equalization should be the one computed for the image when using histogram equalization,
+    // it's ignored otherwise.
+    // Temperatures inside of an enabled isotherm get its color instead of the gradient.
     //
-    pub fn temp_to_color(&self, temp: Temp, override_range: Option<TempRange>) -> Color32 {
-        let mut fac = override_range.unwrap_or(self.manual_range).factor(temp);
+    pub fn temp_to_color(
+        &self,
+        temp: Temp,
+        override_range: Option<TempRange>,
+        equalization: Option<&HistogramEqualization>,
+    ) -> Color32 {
+        if let Some(color) = isotherm_color(&self.isotherms, temp) {
+            return color;
+        }
+        let mut fac = match (self.mapping_mode, equalization) {
+            (MappingMode::HistogramEqualization, Some(equalization)) => equalization.factor(temp),
+            _ => override_range.unwrap_or(self.manual_range).factor(temp),
+        };
         fac = self.dynamic_range_curve.get_value(fac);
         self.gradient.get_color(fac)
     }
-}
 
+    ///
+    /// The gradient with the dynamic range curve applied, as a table for colorizing frames.
+    ///
+    pub fn gradient_lut(&self) -> GradientLut {
+        GradientLut::new(|factor| {
+            self.gradient
+                .get_color(self.dynamic_range_curve.get_value(factor))
+        })
+    }
 
-
-pub type ThermalCapturerCallback = Arc<dyn Fn() + Send + Sync>;
-
-enum ThermalCapturerCmd {
-    SetSettings(ThermalCapturerSettings),
-    Stop,
+    ///
+    /// Color of a value of a frame, which is a difference to the reference frame if `is_delta`.
+    /// Differences are mapped with the diverging DELTA_GRADIENT, ignoring the curve and isotherms
+    /// which are set up for temperatures.
+    ///
+    pub fn value_to_color(
+        &self,
+        value: Temp,
+        range: TempRange,
+        equalization: Option<&HistogramEqualization>,
+        is_delta: bool,
+    ) -> Color32 {
+        if is_delta {
+            DELTA_GRADIENT.get_color(range.factor(value))
+        } else {
+            self.temp_to_color(value, Some(range), equalization)
+        }
+    }
 }
 
-struct ThermalCapturerCtx {
-    camera: Camera,
-    callback: ThermalCapturerCallback,
-    cmd_receiver: mpsc::Receiver<ThermalCapturerCmd>,
-    result_sender: mpsc::Sender<Result<Box<ThermalCapturerResult>, Error>>,
-    adapter: Arc<dyn CameraAdapter>,
-    settings: ThermalCapturerSettings,
-    auto_range_controller: AutoDisplayRangeController,
-    last_frame_time: std::time::Instant,
+///
+/// Measures the gizmos, evaluates the alarms and runs the recorders of thermal-cat,
+/// on the capturer thread.
+///
+pub struct AppCapturerExtension {
+    recording_controller: RecordingController,
+    measurement_logger: MeasurementLogger,
+    triggered_recorder: TriggeredRecorder,
+    alarm_evaluator: AlarmEvaluator,
+    // Frame shown while the display is frozen, its gizmos are measured along with the live ones
+    frozen_frame: Option<Arc<ThermalData>>,
+    // Pixels of the gizmo being measured, kept between the frames
+    gizmo_pixels: Vec<ThermalDataPos>,
 }
 
-pub struct ThermalCapturer {
-    ctx: Option<ThermalCapturerCtx>,
-    cmd_sender: mpsc::Sender<ThermalCapturerCmd>,
+impl Default for AppCapturerExtension {
+    fn default() -> Self {
+        Self {
+            recording_controller: RecordingController::new(),
+            measurement_logger: MeasurementLogger::new(),
+            triggered_recorder: TriggeredRecorder::new(),
+            alarm_evaluator: AlarmEvaluator::new(),
+            frozen_frame: None,
+            gizmo_pixels: vec![],
+        }
+    }
+}
 
-    pub result_receiver: mpsc::Receiver<Result<Box<ThermalCapturerResult>, Error>>,
+pub enum AppCapturerCmd {
+    StartRadiometricRecording(PathBuf, Option<TimelapseSchedule>),
+    StopRadiometricRecording,
+    StartMeasurementLog(MeasurementLogParams),
+    StopMeasurementLog,
+    SetFrozenFrame(Option<Arc<ThermalData>>),
+    ArmTriggeredRecording(TriggeredRecordingParams),
+    DisarmTriggeredRecording,
 }
 
 ///
-/// ThermalCapturer runs in a background thread continuously capturing images from the camera,
-/// And calling the callback function with the captured image.
-impl ThermalCapturer {
-    pub fn new(
-        camera: Camera,
-        adapter: Arc<dyn CameraAdapter>,
-        default_settings: ThermalCapturerSettings,
-        callback: ThermalCapturerCallback,
-    ) -> Self {
-        let (cmd_sender, cmd_receiver) = mpsc::channel();
-        let (result_sender, result_receiver) = mpsc::channel();
-        Self {
-            ctx: Some(ThermalCapturerCtx {
-                camera,
-                adapter,
-                callback,
-                cmd_receiver,
-                result_sender,
-                settings: default_settings,
-                auto_range_controller: AutoDisplayRangeController::new(),
-                last_frame_time: std::time::Instant::now(),
-            }),
-            cmd_sender,
-            result_receiver,
+/// Measures the gizmos on a frame into `gizmo_results`, `min_pos` and `max_pos` are its
+/// coldest and hottest pixels. The pixels of each gizmo are listed in `pixels`.
+///
+fn measure_gizmos(
+    root: &mut Gizmo,
+    thermal_data: &ThermalData,
+    min_pos: ThermalDataPos,
+    max_pos: ThermalDataPos,
+    gizmo_results: &mut HashMap<Uuid, GizmoResult>,
+    pixels: &mut Vec<ThermalDataPos>,
+) -> Result<(), Error> {
+    gizmo_results.clear();
+    for g in root
+        .children_mut()
+        .ok_or(anyhow!("Root gizmo has no children"))?
+        .iter()
+    {
+        match &g.kind {
+            GizmoKind::MaxTemp => {
+                gizmo_results.insert(
+                    g.uuid,
+                    GizmoResult {
+                        temperature: thermal_data.temperature_at(max_pos.x, max_pos.y),
+                        pos: max_pos,
+                        stats: None,
+                    },
+                );
+            }
+            GizmoKind::MinTemp => {
+                gizmo_results.insert(
+                    g.uuid,
+                    GizmoResult {
+                        temperature: thermal_data.temperature_at(min_pos.x, min_pos.y),
+                        pos: min_pos,
+                        stats: None,
+                    },
+                );
+            }
+            GizmoKind::Root { .. } => return Err(anyhow!("Nested root gizmos are not supported")),
+            kind => {
+                // spots, lines and areas report their hottest pixel
+                kind.measured_pixels_into(thermal_data.width, thermal_data.height, pixels);
+                if let Some((stats, pos)) = GizmoStats::from_pixels(thermal_data, pixels) {
+                    gizmo_results.insert(
+                        g.uuid,
+                        GizmoResult {
+                            temperature: stats.max,
+                            pos,
+                            stats: Some(stats),
+                        },
+                    );
+                }
+            }
         }
     }
 
-    //
-    pub fn start(&mut self) {
-        // move the camera out of self so we can use it into the thread
-        let mut ctx = self.ctx.take().unwrap();
-        thread::spawn(move || {
-            ctx.camera.open_stream().unwrap();
-
-            fn produce_result(
-                ctx: &mut ThermalCapturerCtx,
-            ) -> Result<Box<ThermalCapturerResult>, Error> {
-                ctx.last_frame_time = std::time::Instant::now();
-
-                let thermal_data = ctx
-                    .adapter
-                    .capture_thermal_data(&mut ctx.camera)?
-                    .rotated(ctx.settings.rotation);
-                let capture_time = std::time::Instant::now();
-
-                let (mintemp_pos, maxtemp_pos) = thermal_data.get_min_max_pos();
-
-                let captured_range = TempRange::new(
-                    thermal_data.temperature_at(mintemp_pos.x, mintemp_pos.y),
-                    thermal_data.temperature_at(maxtemp_pos.x, maxtemp_pos.y),
-                );
+    Ok(())
+}
 
-                let mut mapping_range = ctx.auto_range_controller.compute(captured_range);
+impl CapturerExtension for AppCapturerExtension {
+    type Settings = ThermalCapturerSettings;
+    type Output = AppFrameResults;
+    type Command = AppCapturerCmd;
 
-                if !ctx.settings.auto_range {
-                    mapping_range = ctx.settings.manual_range;
-                }
+    fn processing_settings(settings: &ThermalCapturerSettings) -> ProcessingSettings {
+        ProcessingSettings {
+            auto_range: settings.auto_range,
+            auto_range_held: settings.auto_range_held,
+            auto_range_clip_percent: settings.auto_range_clip_percent,
+            auto_range_smoothing: settings.auto_range_smoothing,
+            auto_range_lock: settings.auto_range_lock,
+            auto_range_roi: settings
+                .auto_range_roi
+                .and_then(|uuid| settings.gizmo.find_rect(uuid)),
+            manual_range: settings.manual_range,
+            mapping_mode: settings.mapping_mode,
+            gradient_lut: settings.gradient_lut(),
+            isotherms: settings.isotherms.clone(),
+            rotation: settings.rotation,
+            flip_horizontal: settings.flip_horizontal,
+            flip_vertical: settings.flip_vertical,
+            nuc_enabled: settings.nuc_enabled,
+            bad_pixel_correction: settings.bad_pixel_correction,
+            denoise: settings.denoise,
+            lens_distortion: settings.lens_distortion,
+            temporal_average: settings.temporal_average,
+            histogram_region: settings.histogram_region,
+            histogram_buckets: settings.histogram_buckets,
+            upscale_filter: settings.upscale_filter,
+            upscale_factor: settings.upscale_factor,
+            processing_threads: settings.processing_threads,
+            auto_ffc_interval_secs: settings.auto_ffc_interval_secs,
+            delta_mode: settings.delta_mode,
+            change_detection: settings.change_detection,
+            // rate alarms need the changes even while they aren't highlighted
+            watch_changes: settings
+                .alarm_rules
+                .iter()
+                .any(|rule| rule.enabled && rule.source.is_rate()),
+            emissivity: settings.emissivity,
+            emissivity_overrides: settings.gizmo.emissivity_overrides(),
+            reflected_temperature: settings.reflected_temperature,
+            two_point_calibration: settings.two_point_calibration,
+        }
+    }
 
-                let image = thermal_data
-                    .map_to_image(|t| ctx.settings.temp_to_color(t, Some(mapping_range)));
-
-                let mut gizmo_results = HashMap::default();
-                ctx.settings
-                    .gizmo
-                    .children_mut()
-                    .ok_or(anyhow!("Root gizmo has no children"))?
-                    .iter()
-                    .for_each(|g| match g.kind {
-                        GizmoKind::MaxTemp => {
-                            gizmo_results.insert(
-                                g.uuid,
-                                GizmoResult {
-                                    uuid: g.uuid,
-                                    temperature: captured_range.max,
-                                    pos: maxtemp_pos,
-                                },
-                            );
-                        }
-                        GizmoKind::MinTemp => {
-                            gizmo_results.insert(
-                                g.uuid,
-                                GizmoResult {
-                                    uuid: g.uuid,
-                                    temperature: captured_range.min,
-                                    pos: mintemp_pos,
-                                },
-                            );
-                        }
-                        GizmoKind::TempAt { pos } => {
-                            gizmo_results.insert(
-                                g.uuid,
-                                GizmoResult {
-                                    uuid: g.uuid,
-                                    temperature: thermal_data.temperature_at(pos.x, pos.y),
-                                    pos,
-                                },
-                            );
-                        }
-                        _ => panic!("Unimplemented gizmo kind"),
-                    });
-
-                let result = Box::new(ThermalCapturerResult {
-                    image,
-                    real_fps: 1.0 / ctx.last_frame_time.elapsed().as_secs_f32(),
-                    reported_fps: ctx.camera.frame_rate() as f32,
-                    image_range: mapping_range,
-                    histogram: ThermalDataHistogram::from_thermal_data(
-                        &thermal_data,
-                        captured_range.join(mapping_range),
-                        100,
-                    ),
-                    gizmo_results,
-                    capture_time,
-                });
-
-                for recorder in ctx.settings.recorders.iter() {
-                    let recorder = &mut recorder.lock().unwrap();
-                    if recorder.state() == RecorderState::Initial {
-                        recorder.start(RecorderStreamParams {
-                            width: result.image.size[0],
-                            height: result.image.size[1],
-                            framerate: ctx.camera.frame_rate() as usize,
-                        })?;
-                    }
-                    if recorder.state() != RecorderState::Done {
-                        recorder.process_result(&result)?;
-                    }
-                }
+    fn raw_frame(&mut self, _settings: &ThermalCapturerSettings, thermal_data: &ThermalData) {
+        self.recording_controller.record(thermal_data);
+        self.triggered_recorder.push_frame(thermal_data);
+    }
 
-                Ok(result)
+    fn process_frame(
+        &mut self,
+        settings: &mut ThermalCapturerSettings,
+        frame: ProcessedFrame<'_>,
+        recycled: Option<&mut AppFrameResults>,
+    ) -> Result<AppFrameResults, Error> {
+        // blended before anything is drawn onto it or recorded
+        if let Some(visible) = settings
+            .visible_frames
+            .as_ref()
+            .and_then(|frames| frames.latest())
+        {
+            let image = std::mem::take(&mut *frame.image);
+            *frame.image = settings.fusion.compose(image, &visible);
+        }
+
+        // the maps of a result the UI is done with are filled again
+        let (mut gizmo_results, recycled_frozen_results) = match recycled {
+            Some(recycled) => (
+                std::mem::take(&mut recycled.gizmo_results),
+                recycled.frozen_gizmo_results.take(),
+            ),
+            None => (HashMap::default(), None),
+        };
+        measure_gizmos(
+            &mut settings.gizmo,
+            frame.thermal_data,
+            frame.min_pos,
+            frame.max_pos,
+            &mut gizmo_results,
+            &mut self.gizmo_pixels,
+        )?;
+        // the frozen frame is measured again, so gizmos can still be placed on it
+        let frozen_gizmo_results = match self.frozen_frame.as_ref() {
+            Some(frozen) => {
+                let (min_pos, max_pos) = frozen.get_min_max_pos();
+                let mut frozen_gizmo_results = recycled_frozen_results.unwrap_or_default();
+                measure_gizmos(
+                    &mut settings.gizmo,
+                    frozen,
+                    min_pos,
+                    max_pos,
+                    &mut frozen_gizmo_results,
+                    &mut self.gizmo_pixels,
+                )?;
+                Some(frozen_gizmo_results)
             }
-            loop {
-                let result = produce_result(&mut ctx);
-                let last_image_size = match result.as_ref() {
-                    Ok(res) => res.image.size,
-                    Err(_) => [0, 0],
-                };
-                if let Err(err) = ctx.result_sender.send(result) {
-                    log::error!("Error sending result: {}", err);
-                    break;
-                }
+            None => None,
+        };
 
-                (ctx.callback)();
+        self.measurement_logger.log(&settings.gizmo, &gizmo_results);
+        // any pixel alarms look at the current frame, so they aren't delayed by the average.
+        // Their thresholds are temperatures, so they pause while showing differences.
+        let active_alarms = if frame.is_delta {
+            vec![]
+        } else {
+            self.alarm_evaluator.evaluate(
+                &settings.alarm_rules,
+                frame.instantaneous_range,
+                &gizmo_results,
+                frame
+                    .change_detection
+                    .map(|change| change.max_rate_kelvin_per_sec),
+            )
+        };
+        self.triggered_recorder.update(!active_alarms.is_empty());
 
-                // drain the command queue
+        Ok(AppFrameResults {
+            gizmo_results,
+            frozen_gizmo_results,
+            active_alarms,
+            is_recording_radiometric: self.recording_controller.is_recording(),
+            is_logging_measurements: self.measurement_logger.is_logging(),
+            is_triggered_recording_armed: self.triggered_recorder.is_armed(),
+            is_triggered_recording_active: self.triggered_recorder.is_recording(),
+        })
+    }
 
-                while let Ok(cmd) = ctx.cmd_receiver.try_recv() {
-                    match cmd {
-                        ThermalCapturerCmd::Stop => {
-                            ctx.camera.stop_stream().unwrap();
-                            break;
-                        }
-                        ThermalCapturerCmd::SetSettings(range_settings) => {
-                            ctx.settings = range_settings;
-                        }
+    fn result_ready(
+        &mut self,
+        settings: &ThermalCapturerSettings,
+        result: &ThermalCapturerResult,
+    ) -> Result<(), Error> {
+        for recorder in settings.recorders.iter() {
+            let recorder = &mut recorder
+                .lock()
+                .map_err(|_| anyhow!("A recorder panicked while recording"))?;
+            if recorder.state() == RecorderState::Initial {
+                recorder.start(RecorderStreamParams {
+                    width: result.image.size[0],
+                    height: result.image.size[1],
+                    framerate: result.reported_fps.round() as usize,
+                })?;
+            }
+            if recorder.state() != RecorderState::Done {
+                recorder.process_result(result)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_command(&mut self, settings: &ThermalCapturerSettings, command: AppCapturerCmd) {
+        match command {
+            AppCapturerCmd::StartRadiometricRecording(path, schedule) => {
+                if !settings.annotations.is_empty() {
+                    let sidecar = annotations_sidecar_path(&path);
+                    if let Err(err) = save_annotations(&sidecar, &settings.annotations) {
+                        tracing::error!(path = ?sidecar, error = %err, "Failed to save annotations");
                     }
                 }
+                if let Err(err) = self.recording_controller.start(path, schedule) {
+                    tracing::error!(error = %err, "Failed to start radiometric recording");
+                }
+            }
+            AppCapturerCmd::StopRadiometricRecording => {
+                if let Err(err) = self.recording_controller.stop() {
+                    tracing::error!(error = %err, "Failed to finish radiometric recording");
+                }
+            }
+            AppCapturerCmd::StartMeasurementLog(params) => {
+                if let Err(err) = self.measurement_logger.start(params) {
+                    tracing::error!(error = %err, "Failed to start measurement log");
+                }
+            }
+            AppCapturerCmd::StopMeasurementLog => {
+                if let Err(err) = self.measurement_logger.stop() {
+                    tracing::error!(error = %err, "Failed to finish measurement log");
+                }
+            }
+            AppCapturerCmd::SetFrozenFrame(frame) => {
+                self.frozen_frame = frame;
             }
-        });
+            AppCapturerCmd::ArmTriggeredRecording(params) => {
+                if let Err(err) = self.triggered_recorder.arm(params) {
+                    tracing::error!(error = %err, "Failed to arm triggered recording");
+                }
+            }
+            AppCapturerCmd::DisarmTriggeredRecording => {
+                if let Err(err) = self.triggered_recorder.disarm() {
+                    tracing::error!(error = %err, "Failed to finish triggered recording");
+                }
+            }
+        }
     }
-    pub fn set_settings(&mut self, settings: ThermalCapturerSettings) {
-        self.cmd_sender
-            .send(ThermalCapturerCmd::SetSettings(settings))
-            .unwrap();
+
+    fn stop(&mut self) {
+        if let Err(err) = self.recording_controller.stop() {
+            tracing::error!(error = %err, "Failed to finish radiometric recording");
+        }
+        if let Err(err) = self.measurement_logger.stop() {
+            tracing::error!(error = %err, "Failed to finish measurement log");
+        }
+        if let Err(err) = self.triggered_recorder.disarm() {
+            tracing::error!(error = %err, "Failed to finish triggered recording");
+        }
     }
 }
 
-impl Drop for ThermalCapturer {
-    fn drop(&mut self) {
-        self.cmd_sender.send(ThermalCapturerCmd::Stop).unwrap();
+///
+/// The commands of `AppCapturerExtension`, on the capturer.
+///
+pub trait ThermalCapturerExt {
+    ///
+    /// Start writing the raw temperatures of every frame to `path`,
+    /// see radiometric_recording.rs for the file format.
+    ///
+    fn start_radiometric_recording(&mut self, path: PathBuf);
+
+    ///
+    /// Like start_radiometric_recording, but only writes the frames due on `schedule`.
+    /// Stops by itself at the stop time of the schedule.
+    ///
+    fn start_radiometric_timelapse(&mut self, path: PathBuf, schedule: TimelapseSchedule);
+
+    fn stop_radiometric_recording(&mut self);
+
+    ///
+    /// Start writing the gizmo results of every frame as CSV, see measurement_log.rs.
+    ///
+    fn start_measurement_log(&mut self, params: MeasurementLogParams);
+
+    fn stop_measurement_log(&mut self);
+
+    ///
+    /// Keeps measuring the gizmos on `frame` while the display is frozen on it,
+    /// see `AppFrameResults::frozen_gizmo_results`. None ends the freeze.
+    ///
+    fn set_frozen_frame(&mut self, frame: Option<Arc<ThermalData>>);
+
+    ///
+    /// Keep the raw frames of the last seconds, and record them along with the following
+    /// frames when an alarm goes off, see triggered_recording.rs.
+    ///
+    fn arm_triggered_recording(&mut self, params: TriggeredRecordingParams);
+
+    fn disarm_triggered_recording(&mut self);
+}
+
+impl ThermalCapturerExt for ThermalCapturer {
+    fn start_radiometric_recording(&mut self, path: PathBuf) {
+        self.send_extension_command(AppCapturerCmd::StartRadiometricRecording(path, None));
+    }
+
+    fn start_radiometric_timelapse(&mut self, path: PathBuf, schedule: TimelapseSchedule) {
+        self.send_extension_command(AppCapturerCmd::StartRadiometricRecording(
+            path,
+            Some(schedule),
+        ));
+    }
+
+    fn stop_radiometric_recording(&mut self) {
+        self.send_extension_command(AppCapturerCmd::StopRadiometricRecording);
+    }
+
+    fn start_measurement_log(&mut self, params: MeasurementLogParams) {
+        self.send_extension_command(AppCapturerCmd::StartMeasurementLog(params));
+    }
+
+    fn stop_measurement_log(&mut self) {
+        self.send_extension_command(AppCapturerCmd::StopMeasurementLog);
+    }
+
+    fn set_frozen_frame(&mut self, frame: Option<Arc<ThermalData>>) {
+        self.send_extension_command(AppCapturerCmd::SetFrozenFrame(frame));
+    }
+
+    fn arm_triggered_recording(&mut self, params: TriggeredRecordingParams) {
+        self.send_extension_command(AppCapturerCmd::ArmTriggeredRecording(params));
+    }
+
+    fn disarm_triggered_recording(&mut self) {
+        self.send_extension_command(AppCapturerCmd::DisarmTriggeredRecording);
     }
 }
@@ -0,0 +1,132 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use nokhwa::utils::CameraIndex;
+
+use crate::{
+    camera_adapter::{CameraAdapter, CameraControl, CameraControlValue, CameraStream},
+    camera_enumerator::enumerate_cameras,
+    thermal_data::ThermalData,
+    util::pathify_string,
+};
+
+use super::ThermalSource;
+
+///
+/// Live frames from a camera, read through the stream its adapter opens.
+///
+pub struct CameraSource {
+    // None until opened on the capturer thread
+    stream: Option<Box<dyn CameraStream>>,
+    adapter: Arc<dyn CameraAdapter>,
+    // Model and serial number, e.g. "p2_pro_0123456789"
+    camera_id: String,
+    // Where the camera was opened, reconnecting prefers the same index
+    index: CameraIndex,
+}
+
+impl CameraSource {
+    ///
+    /// The camera found at `index` by the enumeration, opened once the capturer starts.
+    ///
+    pub fn new(index: CameraIndex, adapter: Arc<dyn CameraAdapter>) -> Self {
+        let camera_id = camera_id(adapter.as_ref());
+        Self {
+            stream: None,
+            adapter,
+            camera_id,
+            index,
+        }
+    }
+}
+
+///
+/// Model and serial number of the camera, which files and preferences of the camera are stored by.
+///
+pub fn camera_id(adapter: &dyn CameraAdapter) -> String {
+    pathify_string(match adapter.serial_number() {
+        Some(serial) => format!("{}_{}", adapter.short_name(), serial),
+        None => adapter.short_name(),
+    })
+}
+
+impl ThermalSource for CameraSource {
+    fn open(&mut self) -> Result<()> {
+        let _span =
+            tracing::info_span!("adapter", name = %self.adapter.name(), index = %self.index)
+                .entered();
+        self.stream = Some(self.adapter.open(self.index.clone())?);
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.stream = None;
+        Ok(())
+    }
+
+    fn capture(&mut self) -> Result<ThermalData> {
+        self.stream
+            .as_mut()
+            .ok_or_else(|| anyhow!("The camera is not open"))?
+            .next_frame()
+    }
+
+    fn reconnect(&mut self) -> Result<()> {
+        // the index may change when the camera is plugged back in,
+        // look it up by the adapter matched to its USB descriptor
+        let cameras = enumerate_cameras()?;
+        let mut indices = cameras
+            .iter()
+            .filter(|camera| {
+                camera
+                    .adapter
+                    .as_ref()
+                    .is_some_and(|adapter| adapter.name() == self.adapter.name())
+            })
+            .map(|camera| camera.info.index().clone());
+        let index = match indices.clone().find(|index| *index == self.index) {
+            Some(index) => index,
+            None => indices
+                .next()
+                .ok_or_else(|| anyhow!("{} is not connected", self.adapter.name()))?,
+        };
+
+        let _span =
+            tracing::info_span!("adapter", name = %self.adapter.name(), index = %index).entered();
+        // some transports can only be claimed once, release the old one first
+        self.stream = None;
+        self.stream = Some(self.adapter.open(index.clone())?);
+        self.index = index;
+        Ok(())
+    }
+
+    fn can_reconnect(&self) -> bool {
+        true
+    }
+
+    fn frame_rate(&self) -> f32 {
+        self.stream
+            .as_ref()
+            .map(|stream| stream.frame_rate())
+            .unwrap_or_default()
+    }
+
+    fn camera_id(&self) -> Option<String> {
+        Some(self.camera_id.clone())
+    }
+
+    fn controls(&self) -> Vec<CameraControl> {
+        self.adapter.controls()
+    }
+
+    fn set_control(&mut self, id: &str, value: CameraControlValue) -> Result<()> {
+        self.stream
+            .as_mut()
+            .ok_or_else(|| anyhow!("The camera is not open"))?
+            .set_control(id, value)
+    }
+
+    fn trigger_ffc(&mut self) -> Result<()> {
+        self.adapter.trigger_ffc()
+    }
+}
@@ -0,0 +1,7 @@
+pub use thermal_cat_core::thermal_source::{PlaybackPosition, ThermalSource};
+
+pub mod camera_source;
+pub mod network_source;
+pub mod playback_source;
+pub mod raw_frame_source;
+pub mod snapshot_source;
@@ -0,0 +1,110 @@
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+
+use crate::{radiometric_recording::RadiometricReader, thermal_data::ThermalData};
+
+use super::{PlaybackPosition, ThermalSource};
+
+// Used while paused, so a paused playback doesn't spin
+const PAUSED_FRAME_INTERVAL: Duration = Duration::from_millis(40);
+
+///
+/// Plays a radiometric recording back at its original speed, looping at the end.
+///
+pub struct PlaybackSource {
+    reader: RadiometricReader,
+    frame_index: usize,
+    paused: bool,
+
+    // Wall-clock time at which the current frame is due, playback is paced against it
+    frame_due_at: Instant,
+    // Set by seek, so the seeked-to frame is shown immediately
+    show_frame_now: bool,
+}
+
+impl PlaybackSource {
+    pub fn open(path: &Path) -> Result<Self> {
+        Ok(Self {
+            reader: RadiometricReader::open(path)?,
+            frame_index: 0,
+            paused: false,
+            frame_due_at: Instant::now(),
+            show_frame_now: true,
+        })
+    }
+
+    fn next_frame_index(&self) -> usize {
+        (self.frame_index + 1) % self.reader.frame_count()
+    }
+}
+
+impl ThermalSource for PlaybackSource {
+    fn open(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn capture(&mut self) -> Result<ThermalData> {
+        if self.show_frame_now {
+            self.show_frame_now = false;
+            self.frame_due_at = Instant::now();
+        } else if self.paused {
+            std::thread::sleep(PAUSED_FRAME_INTERVAL);
+        } else {
+            let next_index = self.next_frame_index();
+            let frame_gap_us = if next_index == 0 {
+                // looping around, wait one average frame
+                (1_000_000.0 / self.frame_rate()) as u64
+            } else {
+                self.reader.timestamp_us(next_index) - self.reader.timestamp_us(self.frame_index)
+            };
+            self.frame_due_at += Duration::from_micros(frame_gap_us);
+            let now = Instant::now();
+            if self.frame_due_at > now {
+                std::thread::sleep(self.frame_due_at - now);
+            } else {
+                // fell behind (e.g. slow disk), don't try to catch up
+                self.frame_due_at = now;
+            }
+            self.frame_index = next_index;
+        }
+
+        self.reader.read_frame(self.frame_index)
+    }
+
+    fn frame_rate(&self) -> f32 {
+        let frame_count = self.reader.frame_count();
+        let duration_us = self.reader.duration_us();
+        if frame_count < 2 || duration_us == 0 {
+            return 25.0;
+        }
+        (frame_count - 1) as f32 / (duration_us as f32 / 1_000_000.0)
+    }
+
+    fn playback_position(&self) -> Option<PlaybackPosition> {
+        Some(PlaybackPosition {
+            frame_index: self.frame_index,
+            frame_count: self.reader.frame_count(),
+            timestamp_us: self.reader.timestamp_us(self.frame_index),
+            duration_us: self.reader.duration_us(),
+            paused: self.paused,
+        })
+    }
+
+    fn seek(&mut self, frame_index: usize) {
+        self.frame_index = frame_index.min(self.reader.frame_count() - 1);
+        self.show_frame_now = true;
+    }
+
+    fn set_playback_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.show_frame_now = true;
+    }
+}
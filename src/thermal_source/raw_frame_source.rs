@@ -0,0 +1,570 @@
+use std::{
+    io::{self, Read},
+    net::{Ipv4Addr, TcpListener, TcpStream, UdpSocket},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use strum_macros::EnumIter;
+
+use crate::{
+    temperature::{Temp, TemperatureUnit},
+    thermal_data::ThermalData,
+    util::pathify_string,
+};
+
+use super::ThermalSource;
+
+// The packet format is described in docs/raw-frame-protocol.md,
+// examples/raw_frame_sender.rs sends frames in it
+const MAGIC: &[u8; 4] = b"TCRF";
+const PROTOCOL_VERSION: u8 = 1;
+const HEADER_SIZE: usize = 20;
+// Larger frames are refused, as a sanity check of the header
+const MAX_FRAME_DIMENSION: usize = 1024;
+// Largest UDP payload
+const MAX_DATAGRAM_SIZE: usize = 65507;
+
+pub const DEFAULT_RAW_FRAME_PORT: u16 = 5510;
+// Without a frame for this long the sender is considered gone and the capture fails,
+// so the capturer can handle its commands while waiting for the sender to come back
+const FRAME_TIMEOUT: Duration = Duration::from_secs(3);
+// How long reconnecting waits for data before giving up for this attempt
+const RECONNECT_WAIT: Duration = Duration::from_millis(500);
+// Interval of checking for incoming TCP connections
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+// Reported until two frames were received, the highest refresh rate of an MLX90640 in practice
+const DEFAULT_FRAME_RATE: f32 = 8.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, Serialize, Deserialize)]
+pub enum RawFrameTransport {
+    Udp,
+    Tcp,
+}
+
+impl RawFrameTransport {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RawFrameTransport::Udp => "UDP",
+            RawFrameTransport::Tcp => "TCP",
+        }
+    }
+}
+
+///
+/// Where the raw frames are received, the sender connects to (TCP) or sends to (UDP) this port.
+///
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RawFrameReceiverConfig {
+    pub transport: RawFrameTransport,
+    pub port: u16,
+}
+
+impl Default for RawFrameReceiverConfig {
+    fn default() -> Self {
+        Self {
+            transport: RawFrameTransport::Udp,
+            port: DEFAULT_RAW_FRAME_PORT,
+        }
+    }
+}
+
+impl RawFrameReceiverConfig {
+    pub fn name(&self) -> String {
+        format!("Raw frames on {} port {}", self.transport.name(), self.port)
+    }
+
+    pub fn camera_id(&self) -> String {
+        pathify_string(format!("raw_{}_{}", self.transport.name(), self.port))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum PixelFormat {
+    // u16, hundredths of a kelvin
+    CentiKelvin,
+    // f32, degrees Celsius as read from an MLX90640
+    Celsius,
+}
+
+impl PixelFormat {
+    fn from_byte(byte: u8) -> Result<Self> {
+        match byte {
+            1 => Ok(Self::CentiKelvin),
+            2 => Ok(Self::Celsius),
+            _ => Err(anyhow!("Unknown pixel format {}", byte)),
+        }
+    }
+
+    fn bytes_per_pixel(&self) -> usize {
+        match self {
+            Self::CentiKelvin => 2,
+            Self::Celsius => 4,
+        }
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Temp {
+        match self {
+            Self::CentiKelvin => Temp::new(u16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 100.0),
+            Self::Celsius => Temp::from_unit(
+                TemperatureUnit::Celsius,
+                f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            ),
+        }
+    }
+}
+
+///
+/// Header of a packet, which carries `row_count` rows of a frame starting at `first_row`.
+///
+struct PacketHeader {
+    pixel_format: PixelFormat,
+    width: usize,
+    height: usize,
+    frame_number: u32,
+    first_row: usize,
+    row_count: usize,
+}
+
+impl PacketHeader {
+    fn parse(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < HEADER_SIZE {
+            return Err(anyhow!("Packet too short: {} bytes", bytes.len()));
+        }
+        if &bytes[0..4] != MAGIC {
+            return Err(anyhow!("Not a raw frame packet"));
+        }
+        if bytes[4] != PROTOCOL_VERSION {
+            return Err(anyhow!("Unsupported protocol version {}", bytes[4]));
+        }
+        let u16_at =
+            |offset: usize| u16::from_le_bytes([bytes[offset], bytes[offset + 1]]) as usize;
+        let header = Self {
+            pixel_format: PixelFormat::from_byte(bytes[5])?,
+            width: u16_at(6),
+            height: u16_at(8),
+            frame_number: u32::from_le_bytes([bytes[10], bytes[11], bytes[12], bytes[13]]),
+            first_row: u16_at(14),
+            row_count: u16_at(16),
+        };
+        if header.width == 0
+            || header.height == 0
+            || header.width > MAX_FRAME_DIMENSION
+            || header.height > MAX_FRAME_DIMENSION
+        {
+            return Err(anyhow!(
+                "Invalid frame size {}×{}",
+                header.width,
+                header.height
+            ));
+        }
+        if header.row_count == 0 || header.first_row + header.row_count > header.height {
+            return Err(anyhow!(
+                "Invalid rows {}..{} of a frame {} rows high",
+                header.first_row,
+                header.first_row + header.row_count,
+                header.height
+            ));
+        }
+        Ok(header)
+    }
+
+    ///
+    /// Parses a UDP datagram, which has to hold exactly the rows its header announces.
+    ///
+    fn parse_datagram(bytes: &[u8]) -> Result<Self> {
+        let header = Self::parse(bytes)?;
+        let expected_size = HEADER_SIZE + header.payload_size();
+        if bytes.len() != expected_size {
+            return Err(anyhow!(
+                "Packet of {} bytes, its header announces {}",
+                bytes.len(),
+                expected_size
+            ));
+        }
+        Ok(header)
+    }
+
+    fn payload_size(&self) -> usize {
+        self.row_count * self.width * self.pixel_format.bytes_per_pixel()
+    }
+}
+
+///
+/// Puts the frames together from their packets. A frame is complete once all its rows arrived,
+/// a frame missing rows (lost UDP datagrams) is dropped when the next one starts.
+///
+#[derive(Default)]
+struct FrameAssembler {
+    frame_number: u32,
+    width: usize,
+    height: usize,
+    data: Vec<Temp>,
+    received_rows: Vec<bool>,
+    missing_rows: usize,
+}
+
+impl FrameAssembler {
+    fn add(&mut self, header: &PacketHeader, payload: &[u8]) -> Option<ThermalData> {
+        if self.missing_rows == 0
+            || header.frame_number != self.frame_number
+            || header.width != self.width
+            || header.height != self.height
+        {
+            self.frame_number = header.frame_number;
+            self.width = header.width;
+            self.height = header.height;
+            self.data = vec![Temp::default(); header.width * header.height];
+            self.received_rows = vec![false; header.height];
+            self.missing_rows = header.height;
+        }
+
+        let bytes_per_pixel = header.pixel_format.bytes_per_pixel();
+        let offset = header.first_row * header.width;
+        for (temp, bytes) in self.data[offset..offset + header.row_count * header.width]
+            .iter_mut()
+            .zip(payload.chunks_exact(bytes_per_pixel))
+        {
+            *temp = header.pixel_format.decode(bytes);
+        }
+        for received in
+            &mut self.received_rows[header.first_row..header.first_row + header.row_count]
+        {
+            if !*received {
+                *received = true;
+                self.missing_rows -= 1;
+            }
+        }
+
+        (self.missing_rows == 0).then(|| ThermalData {
+            width: self.width,
+            height: self.height,
+            data: std::mem::take(&mut self.data),
+        })
+    }
+}
+
+enum Receiver {
+    Udp(UdpSocket),
+    Tcp {
+        listener: TcpListener,
+        // The sender connected last, None while waiting for one
+        connection: Option<TcpStream>,
+    },
+}
+
+///
+/// Receives temperature frames streamed by other devices, e.g. a microcontroller reading
+/// an MLX90640, in the raw frame protocol.
+///
+pub struct RawFrameSource {
+    config: RawFrameReceiverConfig,
+    // None until opened on the capturer thread
+    receiver: Option<Receiver>,
+    assembler: FrameAssembler,
+    buffer: Vec<u8>,
+    last_frame_time: Option<Instant>,
+    frame_rate: f32,
+}
+
+impl RawFrameSource {
+    pub fn new(config: RawFrameReceiverConfig) -> Self {
+        Self {
+            config,
+            receiver: None,
+            assembler: FrameAssembler::default(),
+            buffer: vec![0; MAX_DATAGRAM_SIZE],
+            last_frame_time: None,
+            frame_rate: DEFAULT_FRAME_RATE,
+        }
+    }
+
+    ///
+    /// Waits for the next packet until `deadline`, returns the frame if it completed one.
+    ///
+    fn receive_packet(&mut self, deadline: Instant) -> Result<Option<ThermalData>> {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(anyhow!(
+                "No frame received for {} s",
+                FRAME_TIMEOUT.as_secs()
+            ));
+        }
+        let receiver = self
+            .receiver
+            .as_mut()
+            .ok_or_else(|| anyhow!("The receiver is not open"))?;
+        match receiver {
+            Receiver::Udp(socket) => {
+                socket.set_read_timeout(Some(remaining))?;
+                let size = match socket.recv(&mut self.buffer) {
+                    Ok(size) => size,
+                    Err(err) if is_timeout(&err) => return Ok(None),
+                    Err(err) => return Err(err.into()),
+                };
+                let packet = &self.buffer[..size];
+                let header = match PacketHeader::parse_datagram(packet) {
+                    Ok(header) => header,
+                    Err(err) => {
+                        // anyone can send to the port, don't give up on the sender
+                        tracing::debug!(error = %err, "Ignoring a packet");
+                        return Ok(None);
+                    }
+                };
+                Ok(self.assembler.add(&header, &packet[HEADER_SIZE..]))
+            }
+            Receiver::Tcp {
+                listener,
+                connection,
+            } => {
+                let stream = match connection {
+                    Some(stream) => stream,
+                    None => match accept(listener)? {
+                        Some(stream) => connection.insert(stream),
+                        None => {
+                            std::thread::sleep(ACCEPT_POLL_INTERVAL.min(remaining));
+                            return Ok(None);
+                        }
+                    },
+                };
+                stream.set_read_timeout(Some(remaining))?;
+                // a stream can't be resynchronized after a partial packet, drop the connection
+                // on any error and let the sender connect again
+                let result = read_tcp_packet(stream, &mut self.buffer);
+                if result.is_err() {
+                    *connection = None;
+                }
+                let header = result?;
+                Ok(self
+                    .assembler
+                    .add(&header, &self.buffer[..header.payload_size()]))
+            }
+        }
+    }
+}
+
+fn is_timeout(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+///
+/// Takes a pending connection of a sender without blocking.
+///
+fn accept(listener: &TcpListener) -> Result<Option<TcpStream>> {
+    match listener.accept() {
+        Ok((stream, address)) => {
+            tracing::info!(%address, "Raw frame sender connected");
+            // the accepted socket may inherit the non-blocking mode of the listener
+            stream.set_nonblocking(false)?;
+            stream.set_nodelay(true)?;
+            Ok(Some(stream))
+        }
+        Err(err) if err.kind() == io::ErrorKind::WouldBlock => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+///
+/// Reads a whole packet from the stream, the payload into `buffer`.
+///
+fn read_tcp_packet(stream: &mut TcpStream, buffer: &mut [u8]) -> Result<PacketHeader> {
+    let mut header_bytes = [0; HEADER_SIZE];
+    stream
+        .read_exact(&mut header_bytes)
+        .map_err(|err| anyhow!("The sender disconnected: {}", err))?;
+    let header = PacketHeader::parse(&header_bytes)?;
+    if header.payload_size() > buffer.len() {
+        return Err(anyhow!("Packet too large: {} bytes", header.payload_size()));
+    }
+    stream
+        .read_exact(&mut buffer[..header.payload_size()])
+        .map_err(|err| anyhow!("The sender disconnected: {}", err))?;
+    Ok(header)
+}
+
+impl ThermalSource for RawFrameSource {
+    fn open(&mut self) -> Result<()> {
+        let address = (Ipv4Addr::UNSPECIFIED, self.config.port);
+        self.receiver = Some(match self.config.transport {
+            RawFrameTransport::Udp => Receiver::Udp(UdpSocket::bind(address)?),
+            RawFrameTransport::Tcp => {
+                let listener = TcpListener::bind(address)?;
+                listener.set_nonblocking(true)?;
+                Receiver::Tcp {
+                    listener,
+                    connection: None,
+                }
+            }
+        });
+        tracing::info!(name = %self.config.name(), "Listening for raw frames");
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.receiver = None;
+        Ok(())
+    }
+
+    fn capture(&mut self) -> Result<ThermalData> {
+        let deadline = Instant::now() + FRAME_TIMEOUT;
+        loop {
+            if let Some(frame) = self.receive_packet(deadline)? {
+                let now = Instant::now();
+                if let Some(last_frame_time) = self.last_frame_time.replace(now) {
+                    let rate = 1.0 / (now - last_frame_time).as_secs_f32().max(0.001);
+                    self.frame_rate = self.frame_rate * 0.9 + rate * 0.1;
+                }
+                return Ok(frame);
+            }
+        }
+    }
+
+    fn reconnect(&mut self) -> Result<()> {
+        // the receiver stays open, the sender is back once there's something to read
+        let waiting = || anyhow!("Waiting for the sender");
+        match self.receiver.as_mut().ok_or_else(waiting)? {
+            Receiver::Udp(socket) => {
+                socket.set_read_timeout(Some(RECONNECT_WAIT))?;
+                socket.peek(&mut self.buffer).map_err(|_| waiting())?;
+            }
+            Receiver::Tcp {
+                listener,
+                connection,
+            } => {
+                if connection.is_none() {
+                    *connection = Some(accept(listener)?.ok_or_else(waiting)?);
+                }
+            }
+        }
+        self.last_frame_time = None;
+        Ok(())
+    }
+
+    fn can_reconnect(&self) -> bool {
+        true
+    }
+
+    fn frame_rate(&self) -> f32 {
+        self.frame_rate
+    }
+
+    fn camera_id(&self) -> Option<String> {
+        Some(self.config.camera_id())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A packet of centikelvin pixels, `row_count` follows from the number of pixels
+    fn packet(
+        width: u16,
+        height: u16,
+        frame_number: u32,
+        first_row: u16,
+        pixels: &[u16],
+    ) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(PROTOCOL_VERSION);
+        bytes.push(1);
+        bytes.extend_from_slice(&width.to_le_bytes());
+        bytes.extend_from_slice(&height.to_le_bytes());
+        bytes.extend_from_slice(&frame_number.to_le_bytes());
+        bytes.extend_from_slice(&first_row.to_le_bytes());
+        bytes.extend_from_slice(&(pixels.len() as u16 / width).to_le_bytes());
+        bytes.extend_from_slice(&[0, 0]);
+        for pixel in pixels {
+            bytes.extend_from_slice(&pixel.to_le_bytes());
+        }
+        bytes
+    }
+
+    fn add(assembler: &mut FrameAssembler, packet: &[u8]) -> Option<ThermalData> {
+        let header = PacketHeader::parse_datagram(packet).unwrap();
+        assembler.add(&header, &packet[HEADER_SIZE..])
+    }
+
+    fn kelvin(frame: &ThermalData) -> Vec<f32> {
+        frame
+            .data
+            .iter()
+            .map(|temp| (temp.to_unit(TemperatureUnit::Kelvin) * 100.0).round() / 100.0)
+            .collect()
+    }
+
+    #[test]
+    fn parse_header() {
+        let header = PacketHeader::parse(&packet(32, 24, 7, 4, &[0; 64])).unwrap();
+        assert!(header.pixel_format == PixelFormat::CentiKelvin);
+        assert_eq!((header.width, header.height), (32, 24));
+        assert_eq!(header.frame_number, 7);
+        assert_eq!((header.first_row, header.row_count), (4, 2));
+        assert_eq!(header.payload_size(), 128);
+    }
+
+    #[test]
+    fn parse_invalid_header() {
+        let valid = packet(4, 2, 0, 0, &[0; 8]);
+        assert!(PacketHeader::parse(&valid[..HEADER_SIZE - 1]).is_err());
+        let mut magic = valid.clone();
+        magic[0] = b'X';
+        assert!(PacketHeader::parse(&magic).is_err());
+        let mut version = valid.clone();
+        version[4] = PROTOCOL_VERSION + 1;
+        assert!(PacketHeader::parse(&version).is_err());
+        let mut format = valid.clone();
+        format[5] = 9;
+        assert!(PacketHeader::parse(&format).is_err());
+        // rows past the end of the frame
+        assert!(PacketHeader::parse(&packet(4, 2, 0, 1, &[0; 8])).is_err());
+        let mut width = valid.clone();
+        width[6..8].copy_from_slice(&0u16.to_le_bytes());
+        assert!(PacketHeader::parse(&width).is_err());
+        width[6..8].copy_from_slice(&(MAX_FRAME_DIMENSION as u16 + 1).to_le_bytes());
+        assert!(PacketHeader::parse(&width).is_err());
+    }
+
+    #[test]
+    fn size_mismatch() {
+        let mut packet = packet(4, 2, 0, 0, &[0; 8]);
+        assert!(PacketHeader::parse_datagram(&packet).is_ok());
+        packet.push(0);
+        assert!(PacketHeader::parse_datagram(&packet).is_err());
+        packet.truncate(packet.len() - 3);
+        assert!(PacketHeader::parse_datagram(&packet).is_err());
+    }
+
+    #[test]
+    fn assemble_multiple_packets() {
+        let mut assembler = FrameAssembler::default();
+        // the rows may arrive in any order
+        assert!(add(&mut assembler, &packet(2, 3, 1, 2, &[500, 600])).is_none());
+        assert!(add(&mut assembler, &packet(2, 3, 1, 0, &[100, 200])).is_none());
+        let frame = add(&mut assembler, &packet(2, 3, 1, 1, &[300, 400])).unwrap();
+        assert_eq!((frame.width, frame.height), (2, 3));
+        assert_eq!(kelvin(&frame), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+
+        // the next frame starts over
+        assert!(add(&mut assembler, &packet(2, 3, 2, 0, &[100, 100, 100, 100])).is_none());
+        let frame = add(&mut assembler, &packet(2, 3, 2, 2, &[200, 200])).unwrap();
+        assert_eq!(kelvin(&frame), vec![1.0, 1.0, 1.0, 1.0, 2.0, 2.0]);
+    }
+
+    #[test]
+    fn dropped_row() {
+        let mut assembler = FrameAssembler::default();
+        // the second row of frame 1 is lost
+        assert!(add(&mut assembler, &packet(2, 2, 1, 0, &[100, 100])).is_none());
+        // frame 2 replaces it, none of frame 1 ends up in it
+        assert!(add(&mut assembler, &packet(2, 2, 2, 1, &[400, 400])).is_none());
+        let frame = add(&mut assembler, &packet(2, 2, 2, 0, &[300, 300])).unwrap();
+        assert_eq!(kelvin(&frame), vec![3.0, 3.0, 4.0, 4.0]);
+        // a late row of frame 1 doesn't complete anything
+        assert!(add(&mut assembler, &packet(2, 2, 1, 1, &[200, 200])).is_none());
+    }
+}
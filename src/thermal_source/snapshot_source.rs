@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::thermal_data::ThermalData;
+
+use super::ThermalSource;
+
+const SNAPSHOT_FRAME_RATE: f32 = 10.0;
+
+///
+/// Repeats a single stored frame, e.g. from a radiometric snapshot,
+/// so that it can be measured with the live tools.
+///
+pub struct SnapshotSource {
+    data: ThermalData,
+    did_capture: bool,
+}
+
+impl SnapshotSource {
+    pub fn new(data: ThermalData) -> Self {
+        Self {
+            data,
+            did_capture: false,
+        }
+    }
+}
+
+impl ThermalSource for SnapshotSource {
+    fn open(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn capture(&mut self) -> Result<ThermalData> {
+        // the first frame is returned right away, then settings changes are picked up at a slow pace
+        if self.did_capture {
+            std::thread::sleep(Duration::from_secs_f32(1.0 / SNAPSHOT_FRAME_RATE));
+        }
+        self.did_capture = true;
+        Ok(self.data.clone())
+    }
+
+    fn frame_rate(&self) -> f32 {
+        SNAPSHOT_FRAME_RATE
+    }
+}
@@ -0,0 +1,183 @@
+use std::{
+    collections::VecDeque,
+    mem::size_of,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    radiometric_recording::{RadiometricWriter, RADIOMETRIC_EXTENSION},
+    temperature::Temp,
+    thermal_data::ThermalData,
+};
+
+pub const MAX_TRIGGER_BUFFER_MB: u32 = 4096;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TriggeredRecordingSettings {
+    // How much of the time before the alarm is included
+    pub pre_trigger_secs: f32,
+    // How long recording continues after the last alarm cleared
+    pub post_trigger_secs: f32,
+    // Older frames are dropped from the pre-trigger buffer to stay below this
+    pub max_buffer_mb: u32,
+}
+
+impl Default for TriggeredRecordingSettings {
+    fn default() -> Self {
+        Self {
+            pre_trigger_secs: 10.0,
+            post_trigger_secs: 10.0,
+            max_buffer_mb: 256,
+        }
+    }
+}
+
+pub struct TriggeredRecordingParams {
+    // Recordings are named after the time of their alarm
+    pub directory: PathBuf,
+    pub settings: TriggeredRecordingSettings,
+}
+
+///
+/// Owned by the capturer thread. While armed, keeps the raw frames of the last seconds
+/// in a ring buffer, and when an alarm goes off writes them to a radiometric recording
+/// which continues until the alarms have been clear for the post-trigger duration.
+/// Stays armed afterwards, so every alarm gets its own recording.
+///
+pub struct TriggeredRecorder {
+    params: Option<TriggeredRecordingParams>,
+    buffer: VecDeque<(Instant, ThermalData)>,
+    buffer_bytes: usize,
+    writer: Option<RadiometricWriter>,
+    last_alarm: Instant,
+}
+
+fn frame_bytes(data: &ThermalData) -> usize {
+    data.data.len() * size_of::<Temp>()
+}
+
+impl TriggeredRecorder {
+    pub fn new() -> Self {
+        Self {
+            params: None,
+            buffer: VecDeque::new(),
+            buffer_bytes: 0,
+            writer: None,
+            last_alarm: Instant::now(),
+        }
+    }
+
+    pub fn arm(&mut self, params: TriggeredRecordingParams) -> Result<()> {
+        self.disarm()?;
+        std::fs::create_dir_all(&params.directory)?;
+        self.params = Some(params);
+        Ok(())
+    }
+
+    pub fn disarm(&mut self) -> Result<()> {
+        self.params = None;
+        self.clear_buffer();
+        if let Some(writer) = self.writer.take() {
+            writer.finish()?;
+        }
+        Ok(())
+    }
+
+    pub fn is_armed(&self) -> bool {
+        self.params.is_some()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.writer.is_some()
+    }
+
+    fn clear_buffer(&mut self) {
+        self.buffer.clear();
+        self.buffer_bytes = 0;
+    }
+
+    ///
+    /// Takes a raw frame, before any corrections like the regular radiometric recordings.
+    ///
+    pub fn push_frame(&mut self, data: &ThermalData) {
+        let Some(settings) = self.params.as_ref().map(|params| params.settings) else {
+            return;
+        };
+        let now = Instant::now();
+        if let Some(writer) = self.writer.as_mut() {
+            if let Err(err) = writer.write_frame_at(data, now) {
+                tracing::error!(error = %err, "Failed to write triggered recording, stopping");
+                self.writer = None;
+            }
+            return;
+        }
+
+        let max_bytes = settings.max_buffer_mb as usize * 1024 * 1024;
+        let max_age = Duration::from_secs_f32(settings.pre_trigger_secs.max(0.0));
+        self.buffer.push_back((now, data.clone()));
+        self.buffer_bytes += frame_bytes(data);
+        while let Some((captured, front)) = self.buffer.front() {
+            if self.buffer_bytes <= max_bytes && now.duration_since(*captured) <= max_age {
+                break;
+            }
+            self.buffer_bytes -= frame_bytes(front);
+            self.buffer.pop_front();
+        }
+    }
+
+    ///
+    /// Starts a recording with the buffered frames when an alarm goes off,
+    /// and finishes it once the alarms were clear for long enough.
+    ///
+    pub fn update(&mut self, alarm_active: bool) {
+        let Some((directory, settings)) = self
+            .params
+            .as_ref()
+            .map(|params| (params.directory.clone(), params.settings))
+        else {
+            return;
+        };
+        if alarm_active {
+            self.last_alarm = Instant::now();
+            if self.writer.is_none() {
+                let path = directory.join(format!(
+                    "alarm_{}.{}",
+                    Local::now().format("%Y-%m-%d_%H-%M-%S"),
+                    RADIOMETRIC_EXTENSION
+                ));
+                if let Err(err) = self.start_writer(path.clone()) {
+                    tracing::error!(error = %err, ?path, "Failed to start triggered recording");
+                }
+            }
+        } else if self.writer.is_some()
+            && self.last_alarm.elapsed().as_secs_f32() > settings.post_trigger_secs
+        {
+            if let Some(writer) = self.writer.take() {
+                if let Err(err) = writer.finish() {
+                    tracing::error!(error = %err, "Failed to finish triggered recording");
+                }
+            }
+        }
+    }
+
+    fn start_writer(&mut self, path: PathBuf) -> Result<()> {
+        let started = self
+            .buffer
+            .front()
+            .map_or_else(Instant::now, |(captured, _)| *captured);
+        let mut writer = RadiometricWriter::create_started_at(&path, started)?;
+        for (captured, data) in self.buffer.iter() {
+            writer.write_frame_at(data, *captured)?;
+        }
+        self.clear_buffer();
+        self.writer = Some(writer);
+        tracing::info!(?path, "Alarm, recording");
+        Ok(())
+    }
+}
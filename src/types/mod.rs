@@ -1,2 +1,3 @@
-pub mod image_rotation;
+pub use thermal_cat_core::types::{image_rotation, mapping_mode};
+
 pub mod media_formats;
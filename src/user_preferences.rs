@@ -1,4 +1,5 @@
 use std::{
+    collections::HashMap,
     fs::{self, File},
     io::{BufReader, BufWriter},
     path::PathBuf,
@@ -8,22 +9,86 @@ use serde::{Deserialize, Serialize};
 
 use anyhow::Result;
 
-use crate::temperature::TemperatureUnit;
+use crate::{
+    http_server::HttpServerSettings,
+    keyboard_shortcuts::KeyBindings,
+    lens_distortion::LensDistortion,
+    mqtt_publisher::MqttSettings,
+    temperature::TemperatureUnit,
+    thermal_capturer::ThermalCapturerSettings,
+    thermal_source::{
+        network_source::NetworkCameraConfig, raw_frame_source::RawFrameReceiverConfig,
+    },
+    types::image_rotation::ImageRotation,
+};
 
 /// Denotes the maximum known version of the preferences file for this version of the application.
 ///
 /// Version 1: Initial version.
 /// Version 2: Added `captures_directory`.
-const MAX_KNOWN_PREFERENCES_VERSION: u32 = 2;
+/// Version 3: Added `temperature_precision`.
+/// Version 4: Added `mqtt`.
+/// Version 5: Added `http_server`.
+/// Version 6: Added `camera_orientations`.
+/// Version 7: Added `lens_profiles`.
+/// Version 8: Added `keybindings`.
+/// Version 9: Added `gpu_colorization`.
+/// Version 10: Added `network_camera`.
+/// Version 11: Added `raw_frame_receiver`.
+const MAX_KNOWN_PREFERENCES_VERSION: u32 = 11;
+
+///
+/// How the image of a camera is turned, remembered for each camera as it depends on how it's mounted.
+///
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CameraOrientation {
+    pub rotation: ImageRotation,
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+}
+
+impl CameraOrientation {
+    pub fn from_settings(settings: &ThermalCapturerSettings) -> Self {
+        Self {
+            rotation: settings.rotation,
+            flip_horizontal: settings.flip_horizontal,
+            flip_vertical: settings.flip_vertical,
+        }
+    }
+
+    pub fn apply_to(&self, settings: &mut ThermalCapturerSettings) {
+        settings.rotation = self.rotation;
+        settings.flip_horizontal = self.flip_horizontal;
+        settings.flip_vertical = self.flip_vertical;
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct UserPreferences {
     pub preferences_version: u32,
     pub temperature_unit: TemperatureUnit,
+    // Number of decimal places shown in temperature readouts
+    pub temperature_precision: usize,
     pub auto_open_camera: bool,
     pub show_unsupported_cameras: bool,
     pub captures_directory: String,
+    // Publishing of measurements and alarms to an MQTT broker
+    pub mqtt: MqttSettings,
+    // Serving the live image and temperatures to other tools
+    pub http_server: HttpServerSettings,
+    // By camera id (see `ThermalSource::camera_id`)
+    pub camera_orientations: HashMap<String, CameraOrientation>,
+    // Lens distortion correction by camera id, as every lens is a bit different
+    pub lens_profiles: HashMap<String, LensDistortion>,
+    // Keys of the shortcuts changed from their defaults
+    pub keybindings: KeyBindings,
+    // Colorize the displayed frame on the GPU instead of the CPU, where supported
+    pub gpu_colorization: bool,
+    // Last network camera opened, filled into the dialog
+    pub network_camera: NetworkCameraConfig,
+    // Last transport and port raw frames were received on
+    pub raw_frame_receiver: RawFrameReceiverConfig,
 }
 
 impl Default for UserPreferences {
@@ -31,6 +96,7 @@ impl Default for UserPreferences {
         Self {
             preferences_version: 1,
             temperature_unit: TemperatureUnit::Celsius,
+            temperature_precision: TemperatureUnit::Celsius.default_precision(),
             auto_open_camera: true,
             show_unsupported_cameras: false,
             captures_directory: dirs::picture_dir()
@@ -38,6 +104,14 @@ impl Default for UserPreferences {
                 .join("Thermal Cat")
                 .to_string_lossy()
                 .to_string(),
+            mqtt: MqttSettings::default(),
+            http_server: HttpServerSettings::default(),
+            camera_orientations: HashMap::new(),
+            lens_profiles: HashMap::new(),
+            keybindings: KeyBindings::default(),
+            gpu_colorization: false,
+            network_camera: NetworkCameraConfig::default(),
+            raw_frame_receiver: RawFrameReceiverConfig::default(),
         }
     }
 }
@@ -68,7 +142,7 @@ impl UserPreferences {
 
         let prefs = if prefs.preferences_version < 2 {
             did_migration = true;
-            log::info!("Migrating preferences to version 2");
+            tracing::info!(version = 2, "Migrating preferences");
             UserPreferences {
                 preferences_version: 2,
                 captures_directory: Self::default().captures_directory,
@@ -78,6 +152,114 @@ impl UserPreferences {
             prefs
         };
 
+        let prefs = if prefs.preferences_version < 3 {
+            did_migration = true;
+            tracing::info!(version = 3, "Migrating preferences");
+            UserPreferences {
+                preferences_version: 3,
+                temperature_precision: Self::default().temperature_precision,
+                ..prefs
+            }
+        } else {
+            prefs
+        };
+
+        let prefs = if prefs.preferences_version < 4 {
+            did_migration = true;
+            tracing::info!(version = 4, "Migrating preferences");
+            UserPreferences {
+                preferences_version: 4,
+                mqtt: MqttSettings::default(),
+                ..prefs
+            }
+        } else {
+            prefs
+        };
+
+        let prefs = if prefs.preferences_version < 5 {
+            did_migration = true;
+            tracing::info!(version = 5, "Migrating preferences");
+            UserPreferences {
+                preferences_version: 5,
+                http_server: HttpServerSettings::default(),
+                ..prefs
+            }
+        } else {
+            prefs
+        };
+
+        let prefs = if prefs.preferences_version < 6 {
+            did_migration = true;
+            tracing::info!(version = 6, "Migrating preferences");
+            UserPreferences {
+                preferences_version: 6,
+                camera_orientations: HashMap::new(),
+                ..prefs
+            }
+        } else {
+            prefs
+        };
+
+        let prefs = if prefs.preferences_version < 7 {
+            did_migration = true;
+            tracing::info!(version = 7, "Migrating preferences");
+            UserPreferences {
+                preferences_version: 7,
+                lens_profiles: HashMap::new(),
+                ..prefs
+            }
+        } else {
+            prefs
+        };
+
+        let prefs = if prefs.preferences_version < 8 {
+            did_migration = true;
+            tracing::info!(version = 8, "Migrating preferences");
+            UserPreferences {
+                preferences_version: 8,
+                keybindings: KeyBindings::default(),
+                ..prefs
+            }
+        } else {
+            prefs
+        };
+
+        let prefs = if prefs.preferences_version < 9 {
+            did_migration = true;
+            tracing::info!(version = 9, "Migrating preferences");
+            UserPreferences {
+                preferences_version: 9,
+                gpu_colorization: false,
+                ..prefs
+            }
+        } else {
+            prefs
+        };
+
+        let prefs = if prefs.preferences_version < 10 {
+            did_migration = true;
+            tracing::info!(version = 10, "Migrating preferences");
+            UserPreferences {
+                preferences_version: 10,
+                network_camera: NetworkCameraConfig::default(),
+                ..prefs
+            }
+        } else {
+            prefs
+        };
+
+        let prefs = if prefs.preferences_version < 11 {
+            did_migration = true;
+            tracing::info!(version = 11, "Migrating preferences");
+            UserPreferences {
+                preferences_version: 11,
+                raw_frame_receiver: RawFrameReceiverConfig::default(),
+                ..prefs
+            }
+        } else {
+            prefs
+        };
+
         // More migrations here...
 
         if did_migration {
@@ -87,6 +269,18 @@ impl UserPreferences {
         Ok(prefs)
     }
 
+    ///
+    /// Turns the image and corrects the lens the way it was last done for the camera.
+    ///
+    pub fn apply_camera_profile(&self, camera_id: &str, settings: &mut ThermalCapturerSettings) {
+        if let Some(orientation) = self.camera_orientations.get(camera_id) {
+            orientation.apply_to(settings);
+        }
+        if let Some(lens_distortion) = self.lens_profiles.get(camera_id) {
+            settings.lens_distortion = *lens_distortion;
+        }
+    }
+
     pub fn save(&self) -> Result<()> {
         let path = Self::preferences_path();
         let dir_path = path.parent().unwrap();
@@ -50,7 +50,7 @@ impl ImageLoader for VideoThumbnailLoader {
         if Path::new(uri)
             .extension()
             .and_then(|ext| ext.to_str())
-            .map_or(false, |ext| ext != "mp4")
+            .is_some_and(|ext| ext != "mp4")
         {
             return Err(LoadError::NotSupported);
         }
@@ -73,8 +73,8 @@ impl ImageLoader for VideoThumbnailLoader {
                     let uri = uri.to_owned();
                     move || {
                         // extract the thing here
-                        log::trace!("generating thumbnail {uri:?}");
-                        log::trace!("path: {path:?}");
+                        tracing::trace!(?uri, "generating thumbnail");
+                        tracing::trace!(?path, "thumbnail source");
                         fn do_extraction(path: &Path) -> Result<Arc<ColorImage>, ffmpeg::Error> {
                             let mut ictx = input(path)?;
                             let input = ictx
@@ -101,7 +101,7 @@ impl ImageLoader for VideoThumbnailLoader {
                             let mut rgb_frame = None;
                             for (stream, packet) in ictx.packets() {
                                 if stream.index() == video_stream_index {
-                                    log::trace!("decoding frame {frame_idx}");
+                                    tracing::trace!(%frame_idx, "decoding frame");
                                     decoder.send_packet(&packet)?;
                                     let mut decoded = Video::empty();
                                     while decoder.receive_frame(&mut decoded).is_ok()
@@ -152,7 +152,7 @@ impl ImageLoader for VideoThumbnailLoader {
                         drop(cache);
 
                         ctx.request_repaint();
-                        log::trace!("finished generating thumbnail {uri:?}");
+                        tracing::trace!(?uri, "finished generating thumbnail");
                     }
                 })
                 .expect("failed to spawn thread");
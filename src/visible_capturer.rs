@@ -0,0 +1,106 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use anyhow::Result;
+use eframe::epaint::ColorImage;
+use nokhwa::{
+    pixel_format::RgbFormat,
+    utils::{CameraIndex, RequestedFormat, RequestedFormatType},
+    Camera,
+};
+
+///
+/// The latest frame of the visible-light camera, shared with the thermal capturer
+/// which blends it with the thermal image.
+///
+#[derive(Clone, Default)]
+pub struct VisibleFrames(Arc<Mutex<Option<Arc<ColorImage>>>>);
+
+impl VisibleFrames {
+    pub fn latest(&self) -> Option<Arc<ColorImage>> {
+        self.0.lock().ok()?.clone()
+    }
+
+    fn set(&self, frame: ColorImage) {
+        if let Ok(mut latest) = self.0.lock() {
+            *latest = Some(Arc::new(frame));
+        }
+    }
+}
+
+///
+/// Captures a regular webcam on a thread of its own, next to the thermal camera.
+/// Only the latest frame is kept, the thermal capturer picks it up at its own pace.
+///
+pub struct VisibleCapturer {
+    pub name: String,
+    frames: VisibleFrames,
+    stop: Arc<AtomicBool>,
+    // Why the capture thread ended
+    error: Arc<Mutex<Option<String>>>,
+}
+
+impl VisibleCapturer {
+    pub fn open(index: CameraIndex, name: String) -> Result<Self> {
+        let mut camera = Camera::new(
+            index,
+            RequestedFormat::new::<RgbFormat>(RequestedFormatType::AbsoluteHighestFrameRate),
+        )?;
+        camera.open_stream()?;
+
+        let frames = VisibleFrames::default();
+        let stop = Arc::new(AtomicBool::new(false));
+        let error = Arc::new(Mutex::new(None));
+        let (thread_frames, thread_stop, thread_error) =
+            (frames.clone(), stop.clone(), error.clone());
+        thread::spawn(move || {
+            while !thread_stop.load(Ordering::Relaxed) {
+                match camera
+                    .frame()
+                    .and_then(|buffer| buffer.decode_image::<RgbFormat>())
+                {
+                    Ok(image) => thread_frames.set(ColorImage::from_rgb(
+                        [image.width() as usize, image.height() as usize],
+                        image.as_raw(),
+                    )),
+                    Err(err) => {
+                        tracing::error!(error = %err, "Visible camera failed");
+                        if let Ok(mut error) = thread_error.lock() {
+                            *error = Some(err.to_string());
+                        }
+                        break;
+                    }
+                }
+            }
+            if let Err(err) = camera.stop_stream() {
+                tracing::error!(error = %err, "Failed to close the visible camera");
+            }
+        });
+
+        Ok(Self {
+            name,
+            frames,
+            stop,
+            error,
+        })
+    }
+
+    pub fn frames(&self) -> VisibleFrames {
+        self.frames.clone()
+    }
+
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().ok()?.clone()
+    }
+}
+
+impl Drop for VisibleCapturer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,50 @@
+//!
+//! Colorization of a frame of the size of the common 256×192 sensors, as done for every
+//! displayed frame. Run with `cargo bench -p thermal-cat-core`.
+//!
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use thermal_cat_core::{
+    temperature::{Temp, TempRange},
+    thermal_data::ThermalData,
+    thermal_gradient::{GradientLut, THERMAL_GRADIENTS},
+};
+
+const WIDTH: usize = 256;
+const HEIGHT: usize = 192;
+
+// A warm spot on a gradient background, so the whole range of the gradient is used
+fn test_frame() -> ThermalData {
+    let data = (0..WIDTH * HEIGHT)
+        .map(|i| {
+            let x = (i % WIDTH) as f32 / WIDTH as f32;
+            let y = (i / WIDTH) as f32 / HEIGHT as f32;
+            let spot = (-((x - 0.6).powi(2) + (y - 0.4).powi(2)) * 20.0).exp();
+            Temp::from_celsius(20.0 + x * 5.0 + spot * 40.0)
+        })
+        .collect();
+    ThermalData::new(WIDTH, HEIGHT, data)
+}
+
+fn colorization(c: &mut Criterion) {
+    let frame = test_frame();
+    let range = TempRange::new(Temp::from_celsius(20.0), Temp::from_celsius(65.0));
+    let gradient = &THERMAL_GRADIENTS[0];
+    let lut = GradientLut::from_gradient(gradient);
+
+    c.bench_function("map_to_image gradient per pixel", |b| {
+        b.iter(|| black_box(&frame).map_to_image(|t| gradient.get_color(range.factor(t))))
+    });
+    c.bench_function("map_to_image lut per pixel", |b| {
+        b.iter(|| black_box(&frame).map_to_image(|t| lut.color(range.factor(t))))
+    });
+    c.bench_function("map_range_to_image", |b| {
+        b.iter(|| black_box(&frame).map_range_to_image(black_box(range), &lut))
+    });
+    c.bench_function("build lut", |b| {
+        b.iter(|| GradientLut::from_gradient(black_box(gradient)))
+    });
+}
+
+criterion_group!(benches, colorization);
+criterion_main!(benches);
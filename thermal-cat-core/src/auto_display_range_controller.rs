@@ -0,0 +1,324 @@
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    temperature::{Temp, TempDelta, TempRange},
+    thermal_data::{ThermalData, ThermalDataRect, ThermalDataStats},
+};
+
+// Upper limit of the clipped percentage at each end, so that the range still covers most of the image
+pub const MAX_CLIP_PERCENT: f32 = 10.0;
+
+// Limits of the span kept by RangeLock::Span
+pub const MIN_LOCKED_SPAN_KELVIN: f32 = 0.5;
+pub const MAX_LOCKED_SPAN_KELVIN: f32 = 500.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SmoothingMode {
+    // Keeps the range until the scene leaves it for a while, then animates to the new range
+    #[default]
+    Stepped,
+    // Continuously follows the scene with an exponential moving average
+    Exponential,
+}
+
+impl SmoothingMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SmoothingMode::Stepped => "Stepped",
+            SmoothingMode::Exponential => "Exponential",
+        }
+    }
+}
+
+///
+/// Keeps one of the two parameters of the auto range fixed, while the other follows the scene.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum RangeLock {
+    #[default]
+    Off,
+    // The width of the range is fixed, it's centered on the scene
+    Span(TempDelta),
+    // The center of the range is fixed, its width fits the scene
+    Level(Temp),
+}
+
+impl RangeLock {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RangeLock::Off => "Off",
+            RangeLock::Span(_) => "Span",
+            RangeLock::Level(_) => "Level",
+        }
+    }
+
+    fn apply(&self, range: TempRange) -> TempRange {
+        match *self {
+            RangeLock::Off => range,
+            RangeLock::Span(span) => {
+                let half_span = span.abs() * 0.5;
+                TempRange::new(range.center() - half_span, range.center() + half_span)
+            }
+            RangeLock::Level(level) => {
+                let half_span = range.span() * 0.5;
+                TempRange::new(level - half_span, level + half_span)
+            }
+        }
+    }
+}
+
+///
+/// How the auto range follows changes of the scene.
+///
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoRangeSmoothing {
+    pub mode: SmoothingMode,
+    // Exponential mode: time for the range to cover ~63% of a change
+    pub time_constant_secs: f32,
+    // Exponential mode: changes smaller than this (in Kelvin) are ignored, so the range stays still
+    pub hysteresis_kelvin: f32,
+}
+
+impl Default for AutoRangeSmoothing {
+    fn default() -> Self {
+        Self {
+            mode: SmoothingMode::Stepped,
+            time_constant_secs: 1.0,
+            hysteresis_kelvin: 1.0,
+        }
+    }
+}
+
+//
+// Struct holding the state of the auto temp range algorithm.
+//
+pub struct AutoDisplayRangeController {
+    current: TempRange,
+
+    last_compute_call: Option<Instant>,
+
+    anim_target_range: Option<TempRange>,
+    anim_progress: Duration,
+
+    clipping_time: Duration,
+
+    // Settings
+    clipping_time_threshold: Duration,
+    anim_duration: Duration,
+
+    // headroom to add to captured range when setting new range
+    new_range_max_headroom: TempDelta,
+    new_range_min_headroom: TempDelta,
+
+    shrink_range_max_headroom: TempDelta,
+    shrink_range_min_headroom: TempDelta,
+
+    min_separation: TempDelta,
+
+    // Percentage of pixels at each end of the distribution which is ignored,
+    // so that a few hot or dead pixels don't stretch the range
+    clip_percent: f32,
+
+    smoothing: AutoRangeSmoothing,
+
+    lock: RangeLock,
+
+    // While held, the last computed range is returned without adapting to the scene
+    held: bool,
+    last_result: TempRange,
+}
+
+impl Default for AutoDisplayRangeController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AutoDisplayRangeController {
+    pub fn new() -> AutoDisplayRangeController {
+        AutoDisplayRangeController {
+            current: TempRange::new(Temp::from_celsius(0.0), Temp::from_celsius(50.0)),
+            last_compute_call: None,
+            anim_target_range: None,
+            anim_progress: Duration::from_secs(0),
+            clipping_time: Duration::from_secs(0),
+
+            clipping_time_threshold: Duration::from_millis(900),
+            anim_duration: Duration::from_millis(500),
+
+            new_range_max_headroom: TempDelta::new(3.0),
+            new_range_min_headroom: TempDelta::new(3.0),
+
+            shrink_range_max_headroom: TempDelta::new(8.0),
+            shrink_range_min_headroom: TempDelta::new(8.0),
+            min_separation: TempDelta::new(30.0),
+
+            clip_percent: 0.0,
+
+            smoothing: AutoRangeSmoothing::default(),
+
+            lock: RangeLock::Off,
+
+            held: false,
+            last_result: TempRange::new(Temp::from_celsius(0.0), Temp::from_celsius(50.0)),
+        }
+    }
+
+    pub fn set_held(&mut self, held: bool) {
+        if self.held && !held {
+            // resume adapting from the next frame, without accounting for the time spent held
+            self.last_compute_call = None;
+            self.clipping_time = Duration::from_secs(0);
+            self.anim_target_range = None;
+        }
+        self.held = held;
+    }
+
+    pub fn set_clip_percent(&mut self, clip_percent: f32) {
+        self.clip_percent = clip_percent.clamp(0.0, MAX_CLIP_PERCENT);
+    }
+
+    pub fn set_smoothing(&mut self, smoothing: AutoRangeSmoothing) {
+        if smoothing.mode != self.smoothing.mode {
+            self.clipping_time = Duration::from_secs(0);
+            self.anim_target_range = None;
+        }
+        self.smoothing = smoothing;
+    }
+
+    pub fn set_lock(&mut self, lock: RangeLock) {
+        self.lock = lock;
+    }
+
+    ///
+    /// Computes the range from the distribution of the captured temperatures,
+    /// leaving out the clipped percentage of pixels at both ends.
+    /// Only the pixels inside of `roi` are considered when set, so the palette is stretched
+    /// over the object of interest rather than the whole scene.
+    /// `stats` are the ones of the whole frame, used without a region.
+    ///
+    pub fn compute_from_data(
+        &mut self,
+        data: &ThermalData,
+        stats: &ThermalDataStats,
+        roi: Option<ThermalDataRect>,
+    ) -> TempRange {
+        let roi = roi.map(|rect| rect.clamped(data.width, data.height));
+        let captured_range = if self.clip_percent > 0.0 {
+            let percents = [self.clip_percent, 100.0 - self.clip_percent];
+            let clipped = match roi {
+                Some(rect) => data.region_percentiles(rect, &percents),
+                None => data.percentiles(&percents),
+            };
+            TempRange::new(clipped[0], clipped[1])
+        } else {
+            let stats = match roi {
+                Some(rect) => data.region_stats(rect),
+                None => *stats,
+            };
+            TempRange::new(stats.min, stats.max)
+        };
+        self.compute(captured_range)
+    }
+
+    fn compute(&mut self, captured_range: TempRange) -> TempRange {
+        if self.held {
+            return self.last_result;
+        }
+
+        let now = Instant::now();
+        let last_compute_call = self.last_compute_call.unwrap_or(now);
+        let delta = now - last_compute_call;
+        self.last_compute_call = Some(now);
+
+        match self.smoothing.mode {
+            SmoothingMode::Stepped => self.step_range(captured_range, delta),
+            SmoothingMode::Exponential => self.follow_range(captured_range, delta),
+        }
+
+        // at the end apply min separation
+        let range = if self.current.span() < self.min_separation {
+            TempRange::new(
+                self.current.min,
+                self.current.max + (self.min_separation - self.current.span()),
+            )
+        } else {
+            self.current
+        };
+        // the lock replaces the span or level, the smoothing still applies to the other one
+        self.last_result = self.lock.apply(range);
+        self.last_result
+    }
+
+    ///
+    /// Moves each end of the range towards the captured range (with headroom) by an exponential
+    /// moving average, unless it's already within the hysteresis.
+    ///
+    fn follow_range(&mut self, captured_range: TempRange, delta: Duration) {
+        let target = TempRange::new(
+            captured_range.min - self.new_range_min_headroom,
+            captured_range.max + self.new_range_max_headroom,
+        );
+        let time_constant = self.smoothing.time_constant_secs.max(0.01);
+        let factor = 1.0 - (-delta.as_secs_f32() / time_constant).exp();
+        let hysteresis = TempDelta::new(self.smoothing.hysteresis_kelvin).max(TempDelta::ZERO);
+
+        let follow = |current: Temp, target: Temp| {
+            if target.delta_from(current).abs() <= hysteresis {
+                current
+            } else {
+                current.lerp(target, factor)
+            }
+        };
+        self.current = TempRange::new(
+            follow(self.current.min, target.min),
+            follow(self.current.max, target.max),
+        );
+    }
+
+    fn step_range(&mut self, captured_range: TempRange, delta: Duration) {
+        // if the max point or min point of the captured range is in the shrinking range, start shrinking the current range
+        let shrinking_range = TempRange::new(
+            self.current.min + self.shrink_range_min_headroom,
+            self.current.max - self.shrink_range_max_headroom,
+        );
+
+        // check clipping
+        if !self.current.contains_range(captured_range)
+            || captured_range.max < shrinking_range.max
+            || captured_range.min > shrinking_range.min
+        {
+            self.clipping_time += delta;
+            // if we are in the shrinking range, reset the clipping time
+            self.clipping_time += delta;
+        } else {
+            self.clipping_time = Duration::from_secs(0);
+        }
+
+        if self.clipping_time > self.clipping_time_threshold {
+            if self.anim_target_range.is_none() {
+                let target = TempRange::new(
+                    captured_range.min - self.new_range_min_headroom,
+                    captured_range.max + self.new_range_max_headroom,
+                );
+                self.anim_target_range = Some(target);
+
+                self.anim_progress = Duration::from_secs(0);
+            }
+        } else {
+            self.anim_target_range = None;
+        }
+
+        if let Some(target_range) = self.anim_target_range {
+            self.anim_progress += delta;
+            let factor = self.anim_progress.as_secs_f32() / self.anim_duration.as_secs_f32();
+            self.current = self.current.animate(target_range, factor);
+            if factor >= 1.0 {
+                self.anim_target_range = None;
+            }
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::PathBuf,
+};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    temperature::{Temp, TemperatureUnit},
+    thermal_data::ThermalData,
+};
+
+// Number of frames the detector looks at before deciding which pixels are bad
+pub const BAD_PIXEL_CALIBRATION_FRAMES: usize = 50;
+// Pixels differing from their neighbours by more than this on average (in Kelvin) are hot or dead
+const MIN_DEVIATION_KELVIN: f64 = 3.0;
+// ... or by more than this many times the typical deviation of the sensor, if that is larger
+const DEVIATION_FACTOR: f64 = 8.0;
+// Pixels whose noise is below this fraction of the typical noise are stuck
+const STUCK_NOISE_FACTOR: f64 = 0.05;
+
+///
+/// Sensor pixels which are hot, dead or stuck. They are replaced by the mean of their
+/// good neighbours on every frame, so a single broken pixel doesn't ruin the auto range.
+///
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BadPixelMap {
+    pub width: usize,
+    pub height: usize,
+
+    // Sorted indices into ThermalData::data
+    indices: Vec<usize>,
+}
+
+impl BadPixelMap {
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    fn is_bad(&self, index: usize) -> bool {
+        self.indices.binary_search(&index).is_ok()
+    }
+
+    pub fn apply(&self, data: &mut ThermalData) {
+        if self.width != data.width || self.height != data.height {
+            return;
+        }
+        for &index in self.indices.iter() {
+            let (x, y) = (index % data.width, index / data.width);
+            let mut sum = 0.0;
+            let mut count = 0;
+            for ny in y.saturating_sub(1)..=(y + 1).min(data.height - 1) {
+                for nx in x.saturating_sub(1)..=(x + 1).min(data.width - 1) {
+                    let neighbour = ny * data.width + nx;
+                    if !self.is_bad(neighbour) {
+                        sum += data.data[neighbour].to_unit(TemperatureUnit::Kelvin);
+                        count += 1;
+                    }
+                }
+            }
+            // a cluster of bad pixels keeps its values, there is nothing to interpolate from
+            if count > 0 {
+                data.data[index] = Temp::new(sum / count as f32);
+            }
+        }
+    }
+
+    ///
+    /// `camera_id` identifies the camera model, as maps of different cameras with the same
+    /// resolution are not interchangeable.
+    ///
+    pub fn map_path(camera_id: &str, width: usize, height: usize) -> PathBuf {
+        let mut path = dirs::config_dir().unwrap();
+        path.push("thermal-viewer");
+        path.push(format!(
+            "bad_pixels_{}_{}x{}.json",
+            camera_id, width, height
+        ));
+        path
+    }
+
+    pub fn load(camera_id: &str, width: usize, height: usize) -> Result<Option<Self>> {
+        let path = Self::map_path(camera_id, width, height);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let file = File::open(path)?;
+        let reader = BufReader::new(file);
+        let map: BadPixelMap = serde_json::from_reader(reader)?;
+        if map.width != width
+            || map.height != height
+            || map.indices.iter().any(|&i| i >= width * height)
+        {
+            return Err(anyhow!(
+                "Stored bad pixel map does not match the {}x{} resolution",
+                width,
+                height
+            ));
+        }
+        Ok(Some(map))
+    }
+
+    pub fn save(&self, camera_id: &str) -> Result<()> {
+        let path = Self::map_path(camera_id, self.width, self.height);
+        let dir_path = path.parent().unwrap();
+        if !dir_path.exists() {
+            fs::create_dir_all(dir_path)?;
+        }
+
+        let file = File::create(path)?;
+        let writer = BufWriter::new(file);
+        serde_json::to_writer(writer, self)?;
+        Ok(())
+    }
+
+    pub fn remove(camera_id: &str, width: usize, height: usize) -> Result<()> {
+        let path = Self::map_path(camera_id, width, height);
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+///
+/// Collects statistics over several frames to find the bad pixels:
+/// hot and dead pixels keep differing from their neighbours,
+/// stuck pixels don't show the noise every other pixel has.
+///
+pub struct BadPixelDetector {
+    width: usize,
+    height: usize,
+    frames: usize,
+    // Per pixel, in Kelvin: sum of the deviations from the neighbourhood median,
+    // sum of the values and sum of the squared values
+    deviation_sums: Vec<f64>,
+    sums: Vec<f64>,
+    square_sums: Vec<f64>,
+}
+
+impl BadPixelDetector {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            frames: 0,
+            deviation_sums: vec![0.0; width * height],
+            sums: vec![0.0; width * height],
+            square_sums: vec![0.0; width * height],
+        }
+    }
+
+    ///
+    /// Fraction of the frames collected, between 0 and 1.
+    ///
+    pub fn progress(&self) -> f32 {
+        self.frames as f32 / BAD_PIXEL_CALIBRATION_FRAMES as f32
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.frames >= BAD_PIXEL_CALIBRATION_FRAMES
+    }
+
+    ///
+    /// Adds a raw frame, frames of another resolution are ignored.
+    ///
+    pub fn add_frame(&mut self, data: &ThermalData) {
+        if data.width != self.width || data.height != self.height {
+            return;
+        }
+        let mut window = Vec::with_capacity(9);
+        for y in 0..data.height {
+            for x in 0..data.width {
+                window.clear();
+                for ny in y.saturating_sub(1)..=(y + 1).min(data.height - 1) {
+                    for nx in x.saturating_sub(1)..=(x + 1).min(data.width - 1) {
+                        window.push(data.temperature_at(nx, ny).to_unit(TemperatureUnit::Kelvin));
+                    }
+                }
+                let middle = window.len() / 2;
+                let (_, median, _) = window.select_nth_unstable_by(middle, f32::total_cmp);
+
+                let index = y * data.width + x;
+                let value = data.data[index].to_unit(TemperatureUnit::Kelvin) as f64;
+                self.deviation_sums[index] += (value - *median as f64).abs();
+                self.sums[index] += value;
+                self.square_sums[index] += value * value;
+            }
+        }
+        self.frames += 1;
+    }
+
+    pub fn finish(&self) -> BadPixelMap {
+        let frames = self.frames.max(1) as f64;
+        let deviations: Vec<f64> = self.deviation_sums.iter().map(|d| d / frames).collect();
+        let noise: Vec<f64> = self
+            .sums
+            .iter()
+            .zip(self.square_sums.iter())
+            .map(|(sum, square_sum)| {
+                let mean = sum / frames;
+                (square_sum / frames - mean * mean).max(0.0).sqrt()
+            })
+            .collect();
+
+        let deviation_threshold = MIN_DEVIATION_KELVIN.max(median(&deviations) * DEVIATION_FACTOR);
+        let stuck_threshold = median(&noise) * STUCK_NOISE_FACTOR;
+
+        BadPixelMap {
+            width: self.width,
+            height: self.height,
+            indices: (0..deviations.len())
+                .filter(|&i| deviations[i] > deviation_threshold || noise[i] < stuck_threshold)
+                .collect(),
+        }
+    }
+}
+
+fn median(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = values.to_vec();
+    let middle = sorted.len() / 2;
+    *sorted.select_nth_unstable_by(middle, f64::total_cmp).1
+}
@@ -0,0 +1,58 @@
+use anyhow::anyhow;
+use nokhwa::utils::CameraIndex;
+
+use super::{CameraAdapter, CameraCapabilities, CameraStream, FrameLayout};
+
+const IMAGE_WIDTH: u32 = 160;
+const IMAGE_HEIGHT: u32 = 120;
+
+pub struct FlirLeptonAdapter {}
+
+//
+// Camera adapter for the FLIR Lepton 3.5 on a PureThermal 2/3 board
+// See: https://groupgets.com/products/purethermal-2-flir-lepton-smart-i-o-module
+// The board presents the 160x120 sensor as a UVC camera with several formats,
+// only the 16-bit grayscale (Y16) one carries the radiometric data.
+//
+// With radiometry and TLinear enabled (the PureThermal firmware default for the 3.5),
+// each pixel is a little-endian uint16 of the temperature in 1/100th's Kelvin.
+//
+// The gain is switched over the Lepton CCI, which the board does not expose over UVC,
+// so the sensor is left in its automatic gain mode.
+//
+// Not listed in CAMERA_ADAPTERS: nokhwa 0.10 can't request the Y16 format, its GRAY format
+// is 8 bits per pixel, so the frames are half the size the 16-bit thermal data needs.
+//
+impl CameraAdapter for FlirLeptonAdapter {
+    fn name(&self) -> String {
+        "FLIR Lepton 3.5 (PureThermal)".to_string()
+    }
+
+    fn short_name(&self) -> String {
+        "Lepton".to_string()
+    }
+
+    fn open(&self, _index: CameraIndex) -> Result<Box<dyn CameraStream>, anyhow::Error> {
+        Err(anyhow!(
+            "The {} needs the Y16 format, which nokhwa can't request",
+            self.name()
+        ))
+    }
+
+    fn capabilities(&self) -> CameraCapabilities {
+        CameraCapabilities {
+            resolution: (IMAGE_WIDTH as usize, IMAGE_HEIGHT as usize),
+            frame_layout: FrameLayout::ThermalOnly,
+        }
+    }
+
+    fn temperature_range(&self) -> (f32, f32) {
+        // high gain mode, low gain goes up to 673.15
+        (263.15, 413.15)
+    }
+
+    fn usb_vid_pid(&self) -> (u16, u16) {
+        // GroupGets PureThermal
+        (0x1e4e, 0x0100)
+    }
+}
@@ -0,0 +1,94 @@
+use nokhwa::{
+    pixel_format::RgbFormat,
+    utils::{
+        CameraFormat, CameraIndex, FrameFormat, RequestedFormat, RequestedFormatType, Resolution,
+    },
+};
+
+use super::{uvc::UvcStream, CameraAdapter, CameraCapabilities, CameraStream, FrameLayout};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum HtiModel {
+    // Hti HT-301, 384x288
+    Ht301,
+    // Uni-T UTi260B, a 256x192 camera on the same board family
+    Uti260B,
+}
+
+pub struct HtiAdapter {
+    pub model: HtiModel,
+}
+
+impl HtiAdapter {
+    fn sensor_size(&self) -> (u32, u32) {
+        match self.model {
+            HtiModel::Ht301 => (384, 288),
+            HtiModel::Uti260B => (256, 192),
+        }
+    }
+}
+
+//
+// Camera adapter for the HT-301 / UTi260B family of UVC thermal cameras
+// The cameras present a single 16-bit per pixel stream twice the height of the sensor:
+// - the greyscale image rows first
+// - the same number of temperature rows after them, uint16 in 1/10th's Kelvin
+//
+// Both models use the same USB PID/VID, the UTi260B is told apart by its product string.
+//
+impl CameraAdapter for HtiAdapter {
+    fn name(&self) -> String {
+        match self.model {
+            HtiModel::Ht301 => "Hti HT-301".to_string(),
+            HtiModel::Uti260B => "Uni-T UTi260B".to_string(),
+        }
+    }
+
+    fn short_name(&self) -> String {
+        match self.model {
+            HtiModel::Ht301 => "HT-301".to_string(),
+            HtiModel::Uti260B => "UTi260B".to_string(),
+        }
+    }
+
+    fn open(&self, index: CameraIndex) -> Result<Box<dyn CameraStream>, anyhow::Error> {
+        let (width, height) = self.sensor_size();
+        Ok(Box::new(UvcStream::open(
+            index,
+            RequestedFormat::new::<RgbFormat>(RequestedFormatType::Closest(CameraFormat::new(
+                Resolution::new(width, height * 2),
+                FrameFormat::YUYV,
+                25,
+            ))),
+            self.capabilities(),
+            1.0 / 10.0,
+        )?))
+    }
+
+    fn capabilities(&self) -> CameraCapabilities {
+        let (width, height) = self.sensor_size();
+        CameraCapabilities {
+            resolution: (width as usize, height as usize),
+            frame_layout: FrameLayout::ThermalAfterRows(height as usize),
+        }
+    }
+
+    fn temperature_range(&self) -> (f32, f32) {
+        match self.model {
+            HtiModel::Ht301 => (253.15, 673.15),
+            HtiModel::Uti260B => (253.15, 823.15),
+        }
+    }
+
+    fn matches_usb_product(&self, product: Option<&str>) -> bool {
+        let is_uti = product.is_some_and(|product| product.contains("UTi"));
+        match self.model {
+            HtiModel::Ht301 => !is_uti,
+            HtiModel::Uti260B => is_uti,
+        }
+    }
+
+    fn usb_vid_pid(&self) -> (u16, u16) {
+        (0x1514, 0x0001)
+    }
+}
@@ -0,0 +1,225 @@
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+use nokhwa::{
+    pixel_format::RgbFormat,
+    utils::{
+        CameraFormat, CameraIndex, FrameFormat, RequestedFormat, RequestedFormatType, Resolution,
+    },
+};
+use rusb::{DeviceHandle, GlobalContext};
+
+use crate::{thermal_data::ThermalData, types::gain_mode::GainMode};
+
+use super::{
+    uvc::UvcStream, CameraAdapter, CameraCapabilities, CameraControl, CameraControlKind,
+    CameraControlValue, CameraStream, FrameLayout,
+};
+
+const IMAGE_WIDTH: u32 = 256;
+const IMAGE_HEIGHT: u32 = 192;
+// Options of the gain control, in this order
+const GAIN_MODES: [GainMode; 2] = [GainMode::High, GainMode::Low];
+const CONTROL_GAIN: &str = "gain";
+// The raw values are 1/64th Kelvin in both gain modes, as decoded by P2Pro-Viewer (see below)
+// for either gain. 16 bits of that reach 1024 K, enough for the 550 °C of low gain.
+const KELVIN_PER_UNIT: f32 = 1.0 / 64.0;
+
+// Vendor specific control transfers, see https://github.com/LeoDJ/P2Pro-Viewer
+const CMD_REQUEST_TYPE_OUT: u8 = 0x41;
+const CMD_REQUEST_TYPE_IN: u8 = 0xc1;
+const CMD_REQUEST_WRITE: u8 = 0x45;
+const CMD_REQUEST_STATUS: u8 = 0x44;
+const CMD_VALUE: u16 = 0x78;
+const CMD_INDEX_HEADER: u16 = 0x9d00;
+const CMD_INDEX_DATA: u16 = 0x1d08;
+const CMD_INDEX_STATUS: u16 = 0x0200;
+const CMD_TIMEOUT: Duration = Duration::from_millis(1000);
+
+// prop_tpd_params | SET
+const CMD_SET_TPD_PARAMS: u16 = 0x8514 | 0x4000;
+const TPD_PARAM_GAIN_SEL: u32 = 5;
+
+pub struct InfirayP2ProAdapter {}
+
+//
+// Camera adapter for the Infiray P2 Pro
+// See: https://www.infiray.com/p2-pro-thermal-camera-for-smartphone/
+// The camera presents two video streams:
+// - A 256x192 YUYV stream with greyscale representation of the thermal data (the scale changes depending on the temperature range)
+// - A 256x348 YUYV stream with the same greyscale thermal data on top, and 256x156 uint16 thermal data underneath
+//
+// We are interested in the bottom part of the second stream, which contains the raw thermal data.
+//
+// The uint16 thermal data is a 256x192 array of 16-bit unsigned integers, representing the temperature in 1/64th's Kelvin
+//
+impl CameraAdapter for InfirayP2ProAdapter {
+    fn name(&self) -> String {
+        "Infiray P2 Pro".to_string()
+    }
+
+    fn short_name(&self) -> String {
+        "P2 Pro".to_string()
+    }
+
+    fn open(&self, index: CameraIndex) -> Result<Box<dyn CameraStream>, anyhow::Error> {
+        Ok(Box::new(InfirayP2ProStream {
+            uvc: UvcStream::open(
+                index,
+                RequestedFormat::new::<RgbFormat>(RequestedFormatType::Closest(CameraFormat::new(
+                    Resolution::new(IMAGE_WIDTH, IMAGE_HEIGHT * 2),
+                    FrameFormat::YUYV,
+                    25,
+                ))),
+                self.capabilities(),
+                KELVIN_PER_UNIT,
+            )?,
+            usb_vid_pid: self.usb_vid_pid(),
+        }))
+    }
+
+    fn capabilities(&self) -> CameraCapabilities {
+        CameraCapabilities {
+            resolution: (IMAGE_WIDTH as usize, IMAGE_HEIGHT as usize),
+            // the bottom half of the frame contains the thermal data
+            frame_layout: FrameLayout::ThermalAfterRows(IMAGE_HEIGHT as usize),
+        }
+    }
+
+    fn temperature_range(&self) -> (f32, f32) {
+        (253.15, 873.15)
+    }
+
+    ///
+    /// High gain covers about -20 to 150 °C, low gain about 100 to 550 °C.
+    /// The camera starts up in high gain.
+    ///
+    fn controls(&self) -> Vec<CameraControl> {
+        vec![CameraControl {
+            id: CONTROL_GAIN,
+            name: "Gain",
+            description: "High gain is more precise, low gain measures hotter objects",
+            kind: CameraControlKind::Choice {
+                options: vec!["High (-20 to 150 °C)", "Low (100 to 550 °C)"],
+                default: 0,
+            },
+        }]
+    }
+
+    fn usb_vid_pid(&self) -> (u16, u16) {
+        // Bus 001 Device 061: ID 0bda:5830 Realtek Semiconductor Corp. USB Camera
+        (0x0bda, 0x5840)
+    }
+}
+
+///
+/// The UVC stream of the camera, with the gain switched over the vendor commands.
+/// Switching only changes the measurable range and the noise, not the scale of the frames.
+///
+struct InfirayP2ProStream {
+    uvc: UvcStream,
+    usb_vid_pid: (u16, u16),
+}
+
+impl InfirayP2ProStream {
+    fn set_gain_mode(&mut self, gain_mode: GainMode) -> Result<(), anyhow::Error> {
+        let (vid, pid) = self.usb_vid_pid;
+        let handle = rusb::open_device_with_vid_pid(vid, pid)
+            .ok_or_else(|| anyhow!("Failed to open the USB device to switch gain"))?;
+
+        let value: u16 = match gain_mode {
+            GainMode::Low => 0,
+            GainMode::High => 1,
+        };
+        write_command(
+            &handle,
+            CMD_SET_TPD_PARAMS,
+            TPD_PARAM_GAIN_SEL,
+            &value.to_be_bytes(),
+        )?;
+        Ok(())
+    }
+}
+
+impl CameraStream for InfirayP2ProStream {
+    fn next_frame(&mut self) -> Result<ThermalData, anyhow::Error> {
+        self.uvc.next_frame()
+    }
+
+    fn frame_rate(&self) -> f32 {
+        self.uvc.frame_rate()
+    }
+
+    fn set_control(&mut self, id: &str, value: CameraControlValue) -> Result<(), anyhow::Error> {
+        match (id, value) {
+            (CONTROL_GAIN, CameraControlValue::Choice(index)) => {
+                let gain_mode = GAIN_MODES
+                    .get(index)
+                    .ok_or_else(|| anyhow!("Invalid gain mode {}", index))?;
+                self.set_gain_mode(*gain_mode)
+            }
+            _ => Err(anyhow!("Invalid P2 Pro control {}: {:?}", id, value)),
+        }
+    }
+}
+
+///
+/// Sends a command with a short payload (at most 8 bytes) and waits for the camera to process it.
+///
+fn write_command(
+    handle: &DeviceHandle<GlobalContext>,
+    cmd: u16,
+    param: u32,
+    data: &[u8],
+) -> Result<(), anyhow::Error> {
+    let mut header = Vec::with_capacity(10);
+    header.extend_from_slice(&cmd.to_le_bytes());
+    header.extend_from_slice(&param.to_le_bytes());
+    header.extend_from_slice(&(data.len() as u32).to_be_bytes());
+
+    handle.write_control(
+        CMD_REQUEST_TYPE_OUT,
+        CMD_REQUEST_WRITE,
+        CMD_VALUE,
+        CMD_INDEX_HEADER,
+        &header,
+        CMD_TIMEOUT,
+    )?;
+    wait_until_ready(handle)?;
+    handle.write_control(
+        CMD_REQUEST_TYPE_OUT,
+        CMD_REQUEST_WRITE,
+        CMD_VALUE,
+        CMD_INDEX_DATA,
+        data,
+        CMD_TIMEOUT,
+    )?;
+    wait_until_ready(handle)
+}
+
+fn wait_until_ready(handle: &DeviceHandle<GlobalContext>) -> Result<(), anyhow::Error> {
+    let started = Instant::now();
+    let mut status = [0u8; 1];
+    while started.elapsed() < CMD_TIMEOUT {
+        handle.read_control(
+            CMD_REQUEST_TYPE_IN,
+            CMD_REQUEST_STATUS,
+            CMD_VALUE,
+            CMD_INDEX_STATUS,
+            &mut status,
+            CMD_TIMEOUT,
+        )?;
+        if status[0] & 0xfc != 0 {
+            return Err(anyhow!(
+                "Camera rejected command, status {:#04x}",
+                status[0]
+            ));
+        }
+        // bits 0 and 1 are set while the camera is busy
+        if status[0] & 0x03 == 0 {
+            return Ok(());
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    Err(anyhow!("Timed out waiting for the camera"))
+}
@@ -0,0 +1,93 @@
+use nokhwa::{
+    pixel_format::RgbFormat,
+    utils::{
+        CameraFormat, CameraIndex, FrameFormat, RequestedFormat, RequestedFormatType, Resolution,
+    },
+};
+
+use super::{uvc::UvcStream, CameraAdapter, CameraCapabilities, CameraStream, FrameLayout};
+
+const IMAGE_WIDTH: u32 = 256;
+const IMAGE_HEIGHT: u32 = 192;
+// Rows of camera parameters between the greyscale image and the temperatures
+const PARAM_ROWS: u32 = 4;
+// The temperature block is padded to the same height as the image and the parameters
+const FRAME_HEIGHT: u32 = (IMAGE_HEIGHT + PARAM_ROWS) * 2;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum InfirayT2Model {
+    T2SPlus,
+    T2L,
+}
+
+pub struct InfirayT2Adapter {
+    pub model: InfirayT2Model,
+}
+
+//
+// Camera adapter for the Infiray T2S+ and T2L phone modules
+// See: https://www.infiray.com/t2s-thermal-camera-for-smartphone/
+// Both use the same USB bridge (and USB PID/VID) and differ only in the product string.
+// Unlike the P2 Pro, the single 256x392 YUYV stream has a few rows of camera parameters
+// between the greyscale image and the temperatures:
+// - 256x192 greyscale image
+// - 256x4 camera parameters
+// - 256x192 uint16 temperatures in 1/64th's Kelvin, followed by 4 rows of padding
+//
+impl CameraAdapter for InfirayT2Adapter {
+    fn name(&self) -> String {
+        match self.model {
+            InfirayT2Model::T2SPlus => "Infiray T2S+".to_string(),
+            InfirayT2Model::T2L => "Infiray T2L".to_string(),
+        }
+    }
+
+    fn short_name(&self) -> String {
+        match self.model {
+            InfirayT2Model::T2SPlus => "T2S+".to_string(),
+            InfirayT2Model::T2L => "T2L".to_string(),
+        }
+    }
+
+    fn open(&self, index: CameraIndex) -> Result<Box<dyn CameraStream>, anyhow::Error> {
+        Ok(Box::new(UvcStream::open(
+            index,
+            RequestedFormat::new::<RgbFormat>(RequestedFormatType::Closest(CameraFormat::new(
+                Resolution::new(IMAGE_WIDTH, FRAME_HEIGHT),
+                FrameFormat::YUYV,
+                25,
+            ))),
+            self.capabilities(),
+            1.0 / 64.0,
+        )?))
+    }
+
+    fn capabilities(&self) -> CameraCapabilities {
+        CameraCapabilities {
+            resolution: (IMAGE_WIDTH as usize, IMAGE_HEIGHT as usize),
+            frame_layout: FrameLayout::ThermalAfterRows((IMAGE_HEIGHT + PARAM_ROWS) as usize),
+        }
+    }
+
+    fn temperature_range(&self) -> (f32, f32) {
+        match self.model {
+            InfirayT2Model::T2SPlus => (253.15, 823.15),
+            InfirayT2Model::T2L => (253.15, 393.15),
+        }
+    }
+
+    ///
+    /// The T2L has "T2L" in its product string, the T2S+ is the bridge's generic "USB Camera".
+    ///
+    fn matches_usb_product(&self, product: Option<&str>) -> bool {
+        let is_t2l = product.is_some_and(|product| product.contains("T2L"));
+        match self.model {
+            InfirayT2Model::T2SPlus => !is_t2l,
+            InfirayT2Model::T2L => is_t2l,
+        }
+    }
+
+    fn usb_vid_pid(&self) -> (u16, u16) {
+        (0x0bda, 0x5830)
+    }
+}
@@ -0,0 +1,293 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use nokhwa::utils::CameraIndex;
+use once_cell::sync::Lazy;
+
+use crate::{temperature::Temp, thermal_data::ThermalData};
+
+pub mod flir_lepton;
+pub mod hti;
+pub mod infiray_p2_pro;
+pub mod infiray_t2;
+pub mod seek_compact;
+pub mod synthetic;
+pub mod uvc;
+
+pub static CAMERA_ADAPTERS: Lazy<Vec<Arc<dyn CameraAdapter>>> = Lazy::new(|| {
+    vec![
+        Arc::new(infiray_p2_pro::InfirayP2ProAdapter {}),
+        Arc::new(infiray_t2::InfirayT2Adapter {
+            model: infiray_t2::InfirayT2Model::T2L,
+        }),
+        Arc::new(infiray_t2::InfirayT2Adapter {
+            model: infiray_t2::InfirayT2Model::T2SPlus,
+        }),
+        Arc::new(hti::HtiAdapter {
+            model: hti::HtiModel::Uti260B,
+        }),
+        Arc::new(hti::HtiAdapter {
+            model: hti::HtiModel::Ht301,
+        }),
+        Arc::new(seek_compact::SeekCompactAdapter {
+            model: seek_compact::SeekModel::CompactPro,
+        }),
+        Arc::new(seek_compact::SeekCompactAdapter {
+            model: seek_compact::SeekModel::Compact,
+        }),
+        Arc::new(synthetic::SyntheticCameraAdapter {
+            scene: synthetic::SyntheticScene::default(),
+        }),
+    ]
+});
+
+///
+/// What a camera delivers, so the frames can be decoded and the camera described
+/// without knowing its model.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct CameraCapabilities {
+    // Width and height of the thermal data
+    pub resolution: (usize, usize),
+    pub frame_layout: FrameLayout,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameLayout {
+    // The whole frame is 16-bit thermal data
+    ThermalOnly,
+    // A 16-bit per pixel image and other rows come first, the thermal rows follow them
+    ThermalAfterRows(usize),
+    // Decoded by the adapter itself, e.g. raw counts referenced to a shutter frame
+    Custom,
+}
+
+impl CameraCapabilities {
+    ///
+    /// Reads the little-endian 16-bit thermal rows out of a raw frame,
+    /// `kelvin_per_unit` being the temperature step of the camera.
+    ///
+    pub fn decode_frame(
+        &self,
+        frame: &[u8],
+        kelvin_per_unit: f32,
+    ) -> Result<ThermalData, anyhow::Error> {
+        let (width, height) = self.resolution;
+        let start = match self.frame_layout {
+            FrameLayout::ThermalOnly => 0,
+            FrameLayout::ThermalAfterRows(rows) => rows * width * 2,
+            FrameLayout::Custom => {
+                return Err(anyhow!(
+                    "The frames of this camera can't be decoded generically"
+                ))
+            }
+        };
+        let end = start + width * height * 2;
+        if frame.len() < end {
+            return Err(anyhow!(
+                "The frame has {} bytes, expected at least {}. Is the right format selected?",
+                frame.len(),
+                end
+            ));
+        }
+
+        Ok(ThermalData::new(
+            width,
+            height,
+            frame[start..end]
+                .chunks_exact(2)
+                .map(|px| Temp::new(u16::from_le_bytes([px[0], px[1]]) as f32 * kelvin_per_unit))
+                .collect(),
+        ))
+    }
+}
+
+///
+/// How the frames of a camera are read, which also decides how the camera is found.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CameraTransport {
+    // Listed by the camera enumeration of nokhwa
+    Uvc,
+    // Looked for by the USB PID/VID of the adapter
+    Usb,
+    // Frames are generated, always listed as a demo device
+    Synthetic,
+}
+
+///
+/// Product string from the USB descriptor of the first device with the given PID/VID.
+///
+pub fn usb_product_string(vid: u16, pid: u16) -> Option<String> {
+    let handle = rusb::open_device_with_vid_pid(vid, pid)?;
+    let descriptor = handle.device().device_descriptor().ok()?;
+    handle.read_product_string_ascii(&descriptor).ok()
+}
+
+///
+/// A device-specific setting of a camera, like the noise of the demo camera,
+/// which the camera settings pane shows without knowing what it does.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct CameraControl {
+    // Identifies the control when it's set, unique within the adapter
+    pub id: &'static str,
+    pub name: &'static str,
+    // Shown as the tooltip
+    pub description: &'static str,
+    pub kind: CameraControlKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CameraControlKind {
+    // One of the named options, set with CameraControlValue::Choice
+    Choice {
+        options: Vec<&'static str>,
+        default: usize,
+    },
+    // Set with CameraControlValue::Toggle
+    Toggle {
+        default: bool,
+    },
+    // A number between min and max, set with CameraControlValue::Number
+    Range {
+        min: f32,
+        max: f32,
+        suffix: &'static str,
+        default: f32,
+    },
+    // Does something once, like closing the shutter, set with CameraControlValue::Trigger
+    Action,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CameraControlValue {
+    Choice(usize),
+    Toggle(bool),
+    Number(f32),
+    Trigger,
+}
+
+impl CameraControl {
+    ///
+    /// Value the camera starts up with, None for actions which have no value.
+    ///
+    pub fn default_value(&self) -> Option<CameraControlValue> {
+        match &self.kind {
+            CameraControlKind::Choice { default, .. } => Some(CameraControlValue::Choice(*default)),
+            CameraControlKind::Toggle { default } => Some(CameraControlValue::Toggle(*default)),
+            CameraControlKind::Range { default, .. } => Some(CameraControlValue::Number(*default)),
+            CameraControlKind::Action => None,
+        }
+    }
+}
+
+///
+/// An opened camera, owning whatever its frames are read through (UVC, libusb, ...).
+/// Created by `CameraAdapter::open`, the camera is closed when it's dropped.
+///
+pub trait CameraStream: Send {
+    ///
+    /// Blocks until the next frame is available and decodes it.
+    ///
+    fn next_frame(&mut self) -> Result<ThermalData, anyhow::Error>;
+
+    ///
+    /// Nominal frame rate of the camera
+    ///
+    fn frame_rate(&self) -> f32;
+
+    ///
+    /// Apply one of the controls listed by `CameraAdapter::controls`.
+    /// Fails for cameras without controls, which is the default.
+    ///
+    fn set_control(&mut self, id: &str, _value: CameraControlValue) -> Result<(), anyhow::Error> {
+        Err(anyhow!("The camera has no control {}", id))
+    }
+}
+
+///
+/// Describes a camera model and opens it. The adapters are shared, everything
+/// that belongs to one opened camera lives in its `CameraStream`.
+///
+pub trait CameraAdapter: Send + Sync {
+    ///
+    /// Get friendly name of the camera model
+    ///
+    fn name(&self) -> String;
+
+    ///
+    /// Shorter name of the camera model for use in filenames
+    ///
+    fn short_name(&self) -> String;
+
+    ///
+    /// Resolution and frame layout of the camera
+    ///
+    fn capabilities(&self) -> CameraCapabilities;
+
+    ///
+    /// Get the USB PID/VID of the camera to match against
+    ///
+    fn usb_vid_pid(&self) -> (u16, u16);
+
+    ///
+    /// Whether the USB product string belongs to this camera model, for models sharing a PID/VID.
+    /// `product` is None if the string can't be read. Accepts any product by default.
+    ///
+    fn matches_usb_product(&self, _product: Option<&str>) -> bool {
+        true
+    }
+
+    ///
+    /// Serial number from the USB descriptor, None if the camera has none or it can't be read.
+    /// With several cameras of the same model connected, this is the serial of the first one.
+    ///
+    fn serial_number(&self) -> Option<String> {
+        let (vid, pid) = self.usb_vid_pid();
+        let handle = rusb::open_device_with_vid_pid(vid, pid)?;
+        let descriptor = handle.device().device_descriptor().ok()?;
+        handle
+            .read_serial_number_string_ascii(&descriptor)
+            .ok()
+            .filter(|serial| !serial.trim().is_empty())
+    }
+
+    ///
+    /// How the camera is connected, UVC by default.
+    ///
+    fn transport(&self) -> CameraTransport {
+        CameraTransport::Uvc
+    }
+
+    ///
+    /// Open the camera and start its stream. `index` is where the enumeration found the camera.
+    ///
+    fn open(&self, index: CameraIndex) -> Result<Box<dyn CameraStream>, anyhow::Error>;
+
+    ///
+    /// Get the advertised temperature range of the camera
+    /// (min, max)
+    ///
+    #[allow(dead_code)]
+    fn temperature_range(&self) -> (f32, f32);
+
+    ///
+    /// Device-specific settings of the camera, changed on the opened camera
+    /// with `CameraStream::set_control`. Empty by default.
+    ///
+    fn controls(&self) -> Vec<CameraControl> {
+        vec![]
+    }
+
+    ///
+    /// Run the flat-field correction of the sensor (closing the shutter on cameras which have one).
+    /// Fails for cameras which can't be asked to, which is the default.
+    ///
+    fn trigger_ffc(&self) -> Result<(), anyhow::Error> {
+        Err(anyhow!(
+            "{} doesn't support triggering the shutter calibration",
+            self.name()
+        ))
+    }
+}
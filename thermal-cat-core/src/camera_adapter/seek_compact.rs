@@ -0,0 +1,270 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use nokhwa::utils::CameraIndex;
+use rusb::{DeviceHandle, GlobalContext};
+
+use crate::{temperature::Temp, thermal_data::ThermalData};
+
+use super::{CameraAdapter, CameraCapabilities, CameraStream, CameraTransport, FrameLayout};
+
+const USB_VID: u16 = 0x289d;
+
+// Vendor specific control transfers, see https://github.com/OpenThermal/libseek-thermal
+const REQUEST_TYPE_OUT: u8 = 0x41;
+const REQUEST_TYPE_IN: u8 = 0xc1;
+const START_GET_IMAGE_TRANSFER: u8 = 83;
+const TARGET_PLATFORM: u8 = 84;
+const SET_OPERATION_MODE: u8 = 60;
+const SET_IMAGE_PROCESSING_MODE: u8 = 62;
+const SET_FACTORY_SETTINGS_FEATURES: u8 = 86;
+const GET_FACTORY_SETTINGS: u8 = 88;
+const SET_FIRMWARE_INFO_FEATURES: u8 = 85;
+const GET_FIRMWARE_INFO: u8 = 78;
+const READ_CHIP_ID: u8 = 54;
+const TIMEOUT: Duration = Duration::from_millis(1000);
+
+const BULK_ENDPOINT: u8 = 0x81;
+const BULK_CHUNK_SIZE: usize = 13680;
+
+// Frame types, in the header word at ModelParams::frame_type_index
+const FRAME_TYPE_IMAGE: u16 = 3;
+
+// The sensor isn't radiometric: readings are raw counts relative to the shutter frame,
+// converted with a typical sensitivity around an assumed shutter temperature.
+// Use the two-point calibration to correct them.
+const COUNTS_PER_KELVIN: f32 = 33.0;
+const SHUTTER_TEMP_KELVIN: f32 = 293.15;
+// About what the cameras deliver, they don't report it
+const FRAME_RATE: f32 = 9.0;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SeekModel {
+    Compact,
+    CompactPro,
+}
+
+struct ModelParams {
+    raw_width: usize,
+    raw_height: usize,
+    // Usable area of the raw frame, (x, y, width, height)
+    crop: (usize, usize, usize, usize),
+    // Header word holding the type of the frame
+    frame_type_index: usize,
+    // Frames taken with the shutter closed, the reference of the following images
+    shutter_frame_type: u16,
+}
+
+impl SeekModel {
+    fn usb_vid_pid(&self) -> (u16, u16) {
+        match self {
+            SeekModel::Compact => (USB_VID, 0x0010),
+            SeekModel::CompactPro => (USB_VID, 0x0011),
+        }
+    }
+
+    fn params(&self) -> ModelParams {
+        match self {
+            SeekModel::Compact => ModelParams {
+                raw_width: 208,
+                raw_height: 156,
+                crop: (0, 0, 206, 156),
+                frame_type_index: 10,
+                shutter_frame_type: 1,
+            },
+            SeekModel::CompactPro => ModelParams {
+                raw_width: 342,
+                raw_height: 260,
+                crop: (1, 4, 320, 240),
+                frame_type_index: 2,
+                shutter_frame_type: 4,
+            },
+        }
+    }
+}
+
+pub struct SeekCompactAdapter {
+    pub model: SeekModel,
+}
+
+//
+// Camera adapter for the Seek Thermal Compact and CompactPRO
+// See: https://www.thermal.com/compact-series.html
+// These are not UVC cameras: frames are requested with vendor control transfers
+// and read from a bulk endpoint, so the adapter talks to the camera over libusb.
+//
+impl CameraAdapter for SeekCompactAdapter {
+    fn name(&self) -> String {
+        match self.model {
+            SeekModel::Compact => "Seek Thermal Compact".to_string(),
+            SeekModel::CompactPro => "Seek Thermal CompactPRO".to_string(),
+        }
+    }
+
+    fn short_name(&self) -> String {
+        match self.model {
+            SeekModel::Compact => "Seek Compact".to_string(),
+            SeekModel::CompactPro => "Seek CompactPRO".to_string(),
+        }
+    }
+
+    fn usb_vid_pid(&self) -> (u16, u16) {
+        self.model.usb_vid_pid()
+    }
+
+    fn transport(&self) -> CameraTransport {
+        CameraTransport::Usb
+    }
+
+    ///
+    /// Opens the first connected camera of the model, there is no index to tell several of them apart.
+    ///
+    fn open(&self, _index: CameraIndex) -> Result<Box<dyn CameraStream>> {
+        Ok(Box::new(SeekCompactStream::open(self.model)?))
+    }
+
+    fn capabilities(&self) -> CameraCapabilities {
+        let (_, _, width, height) = self.model.params().crop;
+        CameraCapabilities {
+            resolution: (width, height),
+            frame_layout: FrameLayout::Custom,
+        }
+    }
+
+    fn temperature_range(&self) -> (f32, f32) {
+        (233.15, 603.15)
+    }
+}
+
+///
+/// An opened Seek camera.
+///
+/// The camera closes its shutter every now and then and sends the frame taken with it,
+/// which is subtracted from the following images to cancel the pixel offsets.
+///
+struct SeekCompactStream {
+    handle: DeviceHandle<GlobalContext>,
+    model: SeekModel,
+    // Last shutter frame, images are dropped until the first one arrives
+    shutter_frame: Option<Vec<u16>>,
+}
+
+impl SeekCompactStream {
+    fn open(model: SeekModel) -> Result<Self> {
+        let (vid, pid) = model.usb_vid_pid();
+        let handle = rusb::open_device_with_vid_pid(vid, pid)
+            .ok_or_else(|| anyhow!("No Seek Thermal camera is connected"))?;
+        handle.set_active_configuration(1)?;
+        handle.claim_interface(0)?;
+
+        let stream = Self {
+            handle,
+            model,
+            shutter_frame: None,
+        };
+        stream.init()?;
+        Ok(stream)
+    }
+
+    fn init(&self) -> Result<()> {
+        // the first command fails if the camera wasn't closed properly, it works on the retry
+        if self.write(TARGET_PLATFORM, &[0x01]).is_err() {
+            self.write(SET_OPERATION_MODE, &[0x00, 0x00])?;
+            self.write(TARGET_PLATFORM, &[0x01])?;
+        }
+        self.write(SET_OPERATION_MODE, &[0x00, 0x00])?;
+        self.read(GET_FIRMWARE_INFO, 4)?;
+        self.read(READ_CHIP_ID, 12)?;
+        self.write(
+            SET_FACTORY_SETTINGS_FEATURES,
+            &[0x20, 0x00, 0x30, 0x00, 0x00, 0x00],
+        )?;
+        self.read(GET_FACTORY_SETTINGS, 64)?;
+        self.write(SET_FIRMWARE_INFO_FEATURES, &[0x17, 0x00])?;
+        self.read(GET_FIRMWARE_INFO, 64)?;
+        self.write(SET_IMAGE_PROCESSING_MODE, &[0x08, 0x00])?;
+        self.write(SET_OPERATION_MODE, &[0x01, 0x00])
+    }
+
+    fn read_raw_frame(&self, params: &ModelParams) -> Result<Vec<u16>> {
+        let words = (params.raw_width * params.raw_height) as u32;
+        self.write(START_GET_IMAGE_TRANSFER, &words.to_le_bytes())?;
+
+        let mut buffer = vec![0u8; words as usize * 2];
+        let mut received = 0;
+        while received < buffer.len() {
+            let end = (received + BULK_CHUNK_SIZE).min(buffer.len());
+            let read = self
+                .handle
+                .read_bulk(BULK_ENDPOINT, &mut buffer[received..end], TIMEOUT)?;
+            if read == 0 {
+                return Err(anyhow!("The camera stopped sending the frame"));
+            }
+            received += read;
+        }
+
+        Ok(buffer
+            .chunks_exact(2)
+            .map(|word| u16::from_le_bytes([word[0], word[1]]))
+            .collect())
+    }
+
+    fn to_thermal_data(params: &ModelParams, frame: &[u16], shutter_frame: &[u16]) -> ThermalData {
+        let (crop_x, crop_y, width, height) = params.crop;
+        let mut data = Vec::with_capacity(width * height);
+        for y in crop_y..crop_y + height {
+            for x in crop_x..crop_x + width {
+                let i = y * params.raw_width + x;
+                let counts = frame[i] as f32 - shutter_frame[i] as f32;
+                data.push(Temp::new(SHUTTER_TEMP_KELVIN + counts / COUNTS_PER_KELVIN));
+            }
+        }
+        ThermalData::new(width, height, data)
+    }
+
+    fn write(&self, request: u8, data: &[u8]) -> Result<()> {
+        self.handle
+            .write_control(REQUEST_TYPE_OUT, request, 0, 0, data, TIMEOUT)?;
+        Ok(())
+    }
+
+    // the replies are only read because the camera expects it
+    fn read(&self, request: u8, len: usize) -> Result<()> {
+        let mut data = vec![0u8; len];
+        self.handle
+            .read_control(REQUEST_TYPE_IN, request, 0, 0, &mut data, TIMEOUT)?;
+        Ok(())
+    }
+}
+
+impl CameraStream for SeekCompactStream {
+    ///
+    /// Blocks until the next image, handling the shutter frames in between.
+    ///
+    fn next_frame(&mut self) -> Result<ThermalData> {
+        let params = self.model.params();
+        loop {
+            let frame = self.read_raw_frame(&params)?;
+            let frame_type = frame[params.frame_type_index];
+            if frame_type == params.shutter_frame_type {
+                self.shutter_frame = Some(frame);
+            } else if frame_type == FRAME_TYPE_IMAGE {
+                if let Some(shutter_frame) = self.shutter_frame.as_ref() {
+                    return Ok(Self::to_thermal_data(&params, &frame, shutter_frame));
+                }
+            }
+        }
+    }
+
+    fn frame_rate(&self) -> f32 {
+        FRAME_RATE
+    }
+}
+
+impl Drop for SeekCompactStream {
+    fn drop(&mut self) {
+        // stop the sensor, so the next open doesn't need the retry
+        let _ = self.write(SET_OPERATION_MODE, &[0x00, 0x00]);
+        let _ = self.handle.release_interface(0);
+    }
+}
@@ -0,0 +1,417 @@
+use std::{
+    f32::consts::TAU,
+    time::{Duration, Instant},
+};
+
+use anyhow::anyhow;
+use nokhwa::utils::CameraIndex;
+
+use crate::{
+    temperature::{Temp, TemperatureUnit},
+    thermal_data::ThermalData,
+};
+
+use super::{
+    CameraAdapter, CameraCapabilities, CameraControl, CameraControlKind, CameraControlValue,
+    CameraStream, CameraTransport, FrameLayout,
+};
+
+// Controls of the demo camera, see SyntheticCameraAdapter::controls
+const CONTROL_NOISE: &str = "noise";
+const CONTROL_NOISE_SIGMA: &str = "noise_sigma";
+const CONTROL_MOTION: &str = "motion";
+const CONTROL_RESEED: &str = "reseed";
+// Offsets of the fixed pattern noise when it's picked with the noise control
+const FIXED_PATTERN_OFFSET_SIGMA_KELVIN: f32 = 0.5;
+const DEFAULT_NOISE_SIGMA_KELVIN: f32 = 0.1;
+const MAX_NOISE_SIGMA_KELVIN: f32 = 2.0;
+
+#[derive(Clone, Debug)]
+pub enum Background {
+    // The same temperature everywhere
+    Uniform(Temp),
+    // From `left` to `right`, swinging sideways once every `period_secs`
+    Gradient {
+        left: Temp,
+        right: Temp,
+        period_secs: f32,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct HotSpot {
+    // Temperature at the center, fading into the background over `radius`
+    pub temperature: Temp,
+    // In pixels
+    pub radius: f32,
+    // Center of the elliptic path, relative to the image size (0.0 - 1.0)
+    pub center: (f32, f32),
+    // Half axes of the path, relative to the image size
+    pub orbit: (f32, f32),
+    // Seconds for one round, 0.0 for a spot that stays put
+    pub period_secs: f32,
+}
+
+#[derive(Clone, Debug)]
+pub enum NoiseModel {
+    None,
+    // Gaussian noise, different in every frame
+    Temporal {
+        sigma_kelvin: f32,
+    },
+    // Temporal noise on top of a fixed offset per pixel, like an uncorrected sensor
+    FixedPattern {
+        sigma_kelvin: f32,
+        offset_sigma_kelvin: f32,
+    },
+}
+
+impl NoiseModel {
+    const NAMES: [&'static str; 3] = ["None", "Temporal", "Fixed pattern"];
+
+    fn index(&self) -> usize {
+        match self {
+            NoiseModel::None => 0,
+            NoiseModel::Temporal { .. } => 1,
+            NoiseModel::FixedPattern { .. } => 2,
+        }
+    }
+
+    // Level of the noise control, which is kept for when the noise is switched on
+    fn sigma_kelvin(&self) -> f32 {
+        match self {
+            NoiseModel::None => DEFAULT_NOISE_SIGMA_KELVIN,
+            NoiseModel::Temporal { sigma_kelvin }
+            | NoiseModel::FixedPattern { sigma_kelvin, .. } => *sigma_kelvin,
+        }
+    }
+
+    fn from_index(index: usize, sigma_kelvin: f32) -> Self {
+        match index {
+            1 => NoiseModel::Temporal { sigma_kelvin },
+            2 => NoiseModel::FixedPattern {
+                sigma_kelvin,
+                offset_sigma_kelvin: FIXED_PATTERN_OFFSET_SIGMA_KELVIN,
+            },
+            _ => NoiseModel::None,
+        }
+    }
+}
+
+///
+/// What the synthetic camera films.
+///
+#[derive(Clone, Debug)]
+pub struct SyntheticScene {
+    pub resolution: (usize, usize),
+    pub frame_rate: f32,
+    pub background: Background,
+    pub hot_spots: Vec<HotSpot>,
+    pub noise: NoiseModel,
+    // Seed of the noise, the same seed gives the same frames
+    pub seed: u64,
+}
+
+impl Default for SyntheticScene {
+    fn default() -> Self {
+        Self {
+            resolution: (256, 192),
+            frame_rate: 25.0,
+            background: Background::Gradient {
+                left: Temp::from_celsius(18.0),
+                right: Temp::from_celsius(26.0),
+                period_secs: 20.0,
+            },
+            hot_spots: vec![
+                HotSpot {
+                    temperature: Temp::from_celsius(36.5),
+                    radius: 24.0,
+                    center: (0.4, 0.5),
+                    orbit: (0.25, 0.2),
+                    period_secs: 8.0,
+                },
+                HotSpot {
+                    temperature: Temp::from_celsius(70.0),
+                    radius: 8.0,
+                    center: (0.75, 0.3),
+                    orbit: (0.1, 0.15),
+                    period_secs: 3.0,
+                },
+            ],
+            noise: NoiseModel::Temporal {
+                sigma_kelvin: DEFAULT_NOISE_SIGMA_KELVIN,
+            },
+            seed: 1,
+        }
+    }
+}
+
+impl SyntheticScene {
+    fn render(&self, time_secs: f32, rng: &mut Rng, offsets: &[f32]) -> ThermalData {
+        let (width, height) = self.resolution;
+        let spots: Vec<(f32, f32, &HotSpot)> = self
+            .hot_spots
+            .iter()
+            .map(|spot| {
+                let phase = if spot.period_secs > 0.0 {
+                    time_secs / spot.period_secs * TAU
+                } else {
+                    0.0
+                };
+                (
+                    (spot.center.0 + spot.orbit.0 * phase.cos()) * width as f32,
+                    (spot.center.1 + spot.orbit.1 * phase.sin()) * height as f32,
+                    spot,
+                )
+            })
+            .collect();
+
+        let mut data = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let mut kelvin = match &self.background {
+                    Background::Uniform(temp) => temp.to_unit(TemperatureUnit::Kelvin),
+                    Background::Gradient {
+                        left,
+                        right,
+                        period_secs,
+                    } => {
+                        let shift = if *period_secs > 0.0 {
+                            0.25 * (time_secs / period_secs * TAU).sin()
+                        } else {
+                            0.0
+                        };
+                        let factor = (x as f32 / width as f32 + shift).clamp(0.0, 1.0);
+                        let left = left.to_unit(TemperatureUnit::Kelvin);
+                        left + (right.to_unit(TemperatureUnit::Kelvin) - left) * factor
+                    }
+                };
+                for (spot_x, spot_y, spot) in spots.iter() {
+                    let distance = ((x as f32 - spot_x).powi(2) + (y as f32 - spot_y).powi(2))
+                        .sqrt()
+                        / spot.radius.max(1.0);
+                    if distance < 1.0 {
+                        // smooth falloff, flat at the center
+                        let weight = 0.5 + 0.5 * (distance * TAU / 2.0).cos();
+                        let spot_kelvin = spot.temperature.to_unit(TemperatureUnit::Kelvin);
+                        kelvin += (spot_kelvin - kelvin) * weight;
+                    }
+                }
+                kelvin += match self.noise {
+                    NoiseModel::None => 0.0,
+                    NoiseModel::Temporal { sigma_kelvin } => rng.gaussian() * sigma_kelvin,
+                    NoiseModel::FixedPattern { sigma_kelvin, .. } => {
+                        rng.gaussian() * sigma_kelvin + offsets[y * width + x]
+                    }
+                };
+                data.push(Temp::new(kelvin));
+            }
+        }
+        ThermalData::new(width, height, data)
+    }
+}
+
+///
+/// A camera which doesn't exist: it renders a scene of moving hot spots,
+/// for trying the app out and for tests which shouldn't need hardware.
+///
+pub struct SyntheticCameraAdapter {
+    pub scene: SyntheticScene,
+}
+
+impl CameraAdapter for SyntheticCameraAdapter {
+    fn name(&self) -> String {
+        "Demo Device (synthetic)".to_string()
+    }
+
+    fn short_name(&self) -> String {
+        "Demo".to_string()
+    }
+
+    fn capabilities(&self) -> CameraCapabilities {
+        CameraCapabilities {
+            resolution: self.scene.resolution,
+            frame_layout: FrameLayout::Custom,
+        }
+    }
+
+    // not a USB device
+    fn usb_vid_pid(&self) -> (u16, u16) {
+        (0, 0)
+    }
+
+    fn serial_number(&self) -> Option<String> {
+        None
+    }
+
+    fn transport(&self) -> CameraTransport {
+        CameraTransport::Synthetic
+    }
+
+    fn open(&self, _index: CameraIndex) -> Result<Box<dyn CameraStream>, anyhow::Error> {
+        Ok(Box::new(SyntheticStream::new(self.scene.clone())))
+    }
+
+    fn temperature_range(&self) -> (f32, f32) {
+        (233.15, 673.15)
+    }
+
+    fn controls(&self) -> Vec<CameraControl> {
+        vec![
+            CameraControl {
+                id: CONTROL_NOISE,
+                name: "Noise",
+                description:
+                    "Noise added to the frames, fixed pattern noise also differs per pixel",
+                kind: CameraControlKind::Choice {
+                    options: NoiseModel::NAMES.to_vec(),
+                    default: self.scene.noise.index(),
+                },
+            },
+            CameraControl {
+                id: CONTROL_NOISE_SIGMA,
+                name: "Noise level",
+                description: "Standard deviation of the noise which changes in every frame",
+                kind: CameraControlKind::Range {
+                    min: 0.0,
+                    max: MAX_NOISE_SIGMA_KELVIN,
+                    suffix: " K",
+                    default: self.scene.noise.sigma_kelvin(),
+                },
+            },
+            CameraControl {
+                id: CONTROL_MOTION,
+                name: "Moving scene",
+                description: "Move the hot spots and the background, or hold them still",
+                kind: CameraControlKind::Toggle { default: true },
+            },
+            CameraControl {
+                id: CONTROL_RESEED,
+                name: "New noise pattern",
+                description: "Draw new per pixel offsets for the fixed pattern noise",
+                kind: CameraControlKind::Action,
+            },
+        ]
+    }
+}
+
+struct SyntheticStream {
+    scene: SyntheticScene,
+    rng: Rng,
+    // Per pixel offsets of the fixed pattern noise, empty for the other noise models
+    offsets: Vec<f32>,
+    // Level of the noise control, kept while the noise is off
+    noise_sigma_kelvin: f32,
+    started: Instant,
+    frame_count: u32,
+    // Time of the scene, which only advances while it's moving
+    moving: bool,
+    scene_secs: f32,
+    last_frame_secs: f32,
+}
+
+impl SyntheticStream {
+    fn new(scene: SyntheticScene) -> Self {
+        let mut stream = Self {
+            rng: Rng::new(scene.seed),
+            offsets: vec![],
+            noise_sigma_kelvin: scene.noise.sigma_kelvin(),
+            scene,
+            started: Instant::now(),
+            frame_count: 0,
+            moving: true,
+            scene_secs: 0.0,
+            last_frame_secs: 0.0,
+        };
+        stream.generate_offsets();
+        stream
+    }
+
+    fn generate_offsets(&mut self) {
+        self.offsets = match self.scene.noise {
+            NoiseModel::FixedPattern {
+                offset_sigma_kelvin,
+                ..
+            } => (0..self.scene.resolution.0 * self.scene.resolution.1)
+                .map(|_| self.rng.gaussian() * offset_sigma_kelvin)
+                .collect(),
+            _ => vec![],
+        };
+    }
+}
+
+impl CameraStream for SyntheticStream {
+    ///
+    /// Waits for the time of the next frame, like a real camera would.
+    ///
+    fn next_frame(&mut self) -> Result<ThermalData, anyhow::Error> {
+        let frame_time =
+            Duration::from_secs_f32(self.frame_count as f32 / self.scene.frame_rate.max(1.0));
+        if let Some(wait) = frame_time.checked_sub(self.started.elapsed()) {
+            std::thread::sleep(wait);
+        }
+        self.frame_count += 1;
+        if self.moving {
+            self.scene_secs += frame_time.as_secs_f32() - self.last_frame_secs;
+        }
+        self.last_frame_secs = frame_time.as_secs_f32();
+        Ok(self
+            .scene
+            .render(self.scene_secs, &mut self.rng, &self.offsets))
+    }
+
+    fn frame_rate(&self) -> f32 {
+        self.scene.frame_rate
+    }
+
+    fn set_control(&mut self, id: &str, value: CameraControlValue) -> Result<(), anyhow::Error> {
+        match (id, value) {
+            (CONTROL_NOISE, CameraControlValue::Choice(index)) => {
+                self.scene.noise = NoiseModel::from_index(index, self.noise_sigma_kelvin);
+                self.generate_offsets();
+            }
+            (CONTROL_NOISE_SIGMA, CameraControlValue::Number(sigma)) => {
+                self.noise_sigma_kelvin = sigma.clamp(0.0, MAX_NOISE_SIGMA_KELVIN);
+                match &mut self.scene.noise {
+                    NoiseModel::None => {}
+                    NoiseModel::Temporal { sigma_kelvin }
+                    | NoiseModel::FixedPattern { sigma_kelvin, .. } => {
+                        *sigma_kelvin = self.noise_sigma_kelvin
+                    }
+                }
+            }
+            (CONTROL_MOTION, CameraControlValue::Toggle(moving)) => self.moving = moving,
+            (CONTROL_RESEED, CameraControlValue::Trigger) => self.generate_offsets(),
+            _ => return Err(anyhow!("Invalid demo camera control {}: {:?}", id, value)),
+        }
+        Ok(())
+    }
+}
+
+///
+/// Small xorshift generator, the noise doesn't need anything better.
+///
+struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // xorshift gets stuck at zero
+        Self { state: seed.max(1) }
+    }
+
+    fn uniform(&mut self) -> f32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        (self.state >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    // Box-Muller transform, standard deviation of 1
+    fn gaussian(&mut self) -> f32 {
+        let u1 = self.uniform().max(f32::MIN_POSITIVE);
+        let u2 = self.uniform();
+        (-2.0 * u1.ln()).sqrt() * (TAU * u2).cos()
+    }
+}
@@ -0,0 +1,62 @@
+use nokhwa::{
+    utils::{CameraIndex, RequestedFormat},
+    Camera,
+};
+
+use crate::thermal_data::ThermalData;
+
+use super::{CameraCapabilities, CameraStream};
+
+///
+/// Frames of a UVC camera read with nokhwa, decoded according to the capabilities of its adapter.
+///
+pub struct UvcStream {
+    camera: Camera,
+    capabilities: CameraCapabilities,
+    kelvin_per_unit: f32,
+}
+
+impl UvcStream {
+    ///
+    /// Opens the camera at `index` in `format` and starts its stream.
+    ///
+    pub fn open(
+        index: CameraIndex,
+        format: RequestedFormat<'static>,
+        capabilities: CameraCapabilities,
+        kelvin_per_unit: f32,
+    ) -> Result<Self, anyhow::Error> {
+        let mut camera = Camera::new(index.clone(), format)?;
+        camera.open_stream()?;
+        tracing::info!(
+            %index,
+            format = %camera.camera_format(),
+            "Opened the stream of the UVC camera"
+        );
+        Ok(Self {
+            camera,
+            capabilities,
+            kelvin_per_unit,
+        })
+    }
+}
+
+impl CameraStream for UvcStream {
+    fn next_frame(&mut self) -> Result<ThermalData, anyhow::Error> {
+        let frame_data: std::borrow::Cow<'_, [u8]> = self.camera.frame_raw()?;
+        self.capabilities
+            .decode_frame(&frame_data, self.kelvin_per_unit)
+    }
+
+    fn frame_rate(&self) -> f32 {
+        self.camera.frame_rate() as f32
+    }
+}
+
+impl Drop for UvcStream {
+    fn drop(&mut self) {
+        if let Err(err) = self.camera.stop_stream() {
+            tracing::error!(error = %err, "Failed to stop the camera stream");
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use std::{collections::VecDeque, time::Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    temperature::TemperatureUnit,
+    thermal_data::{ThermalData, ThermalDataPos},
+};
+
+pub const MIN_CHANGE_BASELINE_SECS: f32 = 0.2;
+pub const MAX_CHANGE_BASELINE_SECS: f32 = 10.0;
+
+// Frames kept per baseline, the rates are measured against a frame between one baseline
+// and one baseline plus this fraction ago
+const BASELINE_STEPS: f32 = 10.0;
+
+///
+/// Finds the pixels whose temperature changes faster than a threshold, e.g. a component
+/// heating up rapidly right before it fails.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChangeDetectionSettings {
+    // Highlight the pixels changing faster than the threshold on the image
+    pub highlight: bool,
+    // Rising or falling, in Kelvin (= °C) per second
+    pub threshold_kelvin_per_sec: f32,
+    // Rates are measured over this long (MIN_CHANGE_BASELINE_SECS..=MAX_CHANGE_BASELINE_SECS),
+    // longer is less noisy but slower to react
+    pub baseline_secs: f32,
+}
+
+impl Default for ChangeDetectionSettings {
+    fn default() -> Self {
+        Self {
+            highlight: false,
+            threshold_kelvin_per_sec: 2.0,
+            baseline_secs: 1.0,
+        }
+    }
+}
+
+pub struct ChangeDetection {
+    // Fastest change of any pixel, rising or falling, in Kelvin per second
+    pub max_rate_kelvin_per_sec: f32,
+    pub max_rate_pos: ThermalDataPos,
+    pub width: usize,
+    pub height: usize,
+    // Whether each pixel changed faster than the threshold, row-major
+    pub changed: Vec<bool>,
+}
+
+impl ChangeDetection {
+    pub fn changed_pixel_count(&self) -> usize {
+        self.changed.iter().filter(|changed| **changed).count()
+    }
+}
+
+///
+/// Owned by the capturer thread, keeps a few frames of the last baseline.
+///
+#[derive(Default)]
+pub struct ChangeDetector {
+    history: VecDeque<(Instant, ThermalData)>,
+}
+
+impl ChangeDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn reset(&mut self) {
+        self.history.clear();
+    }
+
+    ///
+    /// Adds the frame captured at `now` and compares it to the one a baseline ago.
+    /// Returns None until a baseline worth of frames has been seen.
+    ///
+    pub fn process(
+        &mut self,
+        settings: &ChangeDetectionSettings,
+        data: &ThermalData,
+        now: Instant,
+    ) -> Option<ChangeDetection> {
+        if self
+            .history
+            .back()
+            .is_some_and(|(_, last)| last.width != data.width || last.height != data.height)
+        {
+            self.reset();
+        }
+        let baseline_secs = settings
+            .baseline_secs
+            .clamp(MIN_CHANGE_BASELINE_SECS, MAX_CHANGE_BASELINE_SECS);
+
+        // a sparse history is enough, the rates only need a frame about a baseline ago
+        if self.history.back().is_none_or(|(taken, _)| {
+            now.duration_since(*taken).as_secs_f32() >= baseline_secs / BASELINE_STEPS
+        }) {
+            self.history.push_back((now, data.clone()));
+        }
+        while self.history.len() > 1
+            && now.duration_since(self.history[1].0).as_secs_f32() >= baseline_secs
+        {
+            self.history.pop_front();
+        }
+
+        let (taken, baseline) = self.history.front()?;
+        let elapsed = now.duration_since(*taken).as_secs_f32();
+        if elapsed < baseline_secs {
+            return None;
+        }
+
+        let mut max_rate = 0.0;
+        let mut max_index = 0;
+        let changed = data
+            .data
+            .iter()
+            .zip(baseline.data.iter())
+            .enumerate()
+            .map(|(i, (temp, baseline_temp))| {
+                let rate =
+                    ((*temp - *baseline_temp).to_unit(TemperatureUnit::Kelvin) / elapsed).abs();
+                if rate > max_rate {
+                    max_rate = rate;
+                    max_index = i;
+                }
+                rate > settings.threshold_kelvin_per_sec
+            })
+            .collect();
+
+        Some(ChangeDetection {
+            max_rate_kelvin_per_sec: max_rate,
+            max_rate_pos: ThermalDataPos::new(max_index % data.width, max_index / data.width),
+            width: data.width,
+            height: data.height,
+            changed,
+        })
+    }
+}
@@ -0,0 +1,104 @@
+use ecolor::Color32;
+
+use crate::{
+    temperature::{Temp, TemperatureUnit},
+    thermal_data::ThermalData,
+};
+
+// Each level is a full pass over the image, so keep the number bounded
+pub const MAX_CONTOUR_LEVELS: usize = 8;
+
+pub const CONTOUR_COLORS: [Color32; MAX_CONTOUR_LEVELS] = [
+    Color32::WHITE,
+    Color32::from_rgb(255, 230, 0),
+    Color32::from_rgb(0, 230, 255),
+    Color32::from_rgb(255, 80, 200),
+    Color32::from_rgb(120, 255, 80),
+    Color32::from_rgb(255, 140, 0),
+    Color32::from_rgb(160, 120, 255),
+    Color32::BLACK,
+];
+
+///
+/// A line segment of a contour, in pixel coordinates of the thermal data.
+/// Pixel centers lie on integer coordinates.
+///
+pub type ContourSegment = [[f32; 2]; 2];
+
+///
+/// Computes the line segments where the temperature field crosses `level`,
+/// using the marching squares algorithm over the grid of pixel centers.
+///
+pub fn contour_segments(data: &ThermalData, level: Temp) -> Vec<ContourSegment> {
+    let mut segments = vec![];
+    if data.width < 2 || data.height < 2 {
+        return segments;
+    }
+
+    let level = level.to_unit(TemperatureUnit::Kelvin);
+    let value = |x: usize, y: usize| data.temperature_at(x, y).to_unit(TemperatureUnit::Kelvin);
+    // position of the level crossing between two values, as a fraction from a to b
+    let crossing = |a: f32, b: f32| {
+        if (b - a).abs() <= f32::EPSILON {
+            0.5
+        } else {
+            ((level - a) / (b - a)).clamp(0.0, 1.0)
+        }
+    };
+
+    for y in 0..data.height - 1 {
+        for x in 0..data.width - 1 {
+            let tl = value(x, y);
+            let tr = value(x + 1, y);
+            let br = value(x + 1, y + 1);
+            let bl = value(x, y + 1);
+
+            let case = ((tl > level) as u8) << 3
+                | ((tr > level) as u8) << 2
+                | ((br > level) as u8) << 1
+                | (bl > level) as u8;
+            if case == 0 || case == 15 {
+                continue;
+            }
+
+            let (xf, yf) = (x as f32, y as f32);
+            let top = [xf + crossing(tl, tr), yf];
+            let right = [xf + 1.0, yf + crossing(tr, br)];
+            let bottom = [xf + crossing(bl, br), yf + 1.0];
+            let left = [xf, yf + crossing(tl, bl)];
+
+            // saddle points are resolved using the average of the cell
+            let center_above = (tl + tr + br + bl) / 4.0 > level;
+
+            match case {
+                1 | 14 => segments.push([left, bottom]),
+                2 | 13 => segments.push([bottom, right]),
+                3 | 12 => segments.push([left, right]),
+                4 | 11 => segments.push([top, right]),
+                6 | 9 => segments.push([top, bottom]),
+                7 | 8 => segments.push([top, left]),
+                5 => {
+                    if center_above {
+                        segments.push([top, left]);
+                        segments.push([bottom, right]);
+                    } else {
+                        segments.push([top, right]);
+                        segments.push([left, bottom]);
+                    }
+                }
+                10 => {
+                    if center_above {
+                        segments.push([top, right]);
+                        segments.push([left, bottom]);
+                    } else {
+                        segments.push([top, left]);
+                        segments.push([bottom, right]);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    segments
+}
@@ -0,0 +1,178 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    temperature::{Temp, TemperatureUnit},
+    thermal_data::ThermalData,
+};
+
+pub const MIN_DENOISE_SIGMA: f32 = 0.3;
+pub const MAX_DENOISE_SIGMA: f32 = 3.0;
+
+// Widest kernels, for MAX_DENOISE_SIGMA, so they fit on the stack
+const MAX_GAUSSIAN_TAPS: usize = (MAX_DENOISE_SIGMA * 3.0) as usize * 2 + 1;
+const MAX_BILATERAL_SIZE: usize = (MAX_DENOISE_SIGMA * 2.0) as usize * 2 + 1;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DenoiseFilter {
+    #[default]
+    Off,
+    // Median of the 3×3 neighbourhood, removes single speckles without blurring edges much
+    Median,
+    Gaussian,
+    // Gaussian which only averages pixels of similar temperature, so edges stay sharp
+    Bilateral,
+}
+
+impl DenoiseFilter {
+    pub const ALL: [DenoiseFilter; 4] = [
+        DenoiseFilter::Off,
+        DenoiseFilter::Median,
+        DenoiseFilter::Gaussian,
+        DenoiseFilter::Bilateral,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DenoiseFilter::Off => "Off",
+            DenoiseFilter::Median => "Median 3×3",
+            DenoiseFilter::Gaussian => "Gaussian",
+            DenoiseFilter::Bilateral => "Bilateral",
+        }
+    }
+}
+
+///
+/// Spatial noise reduction, applied to the temperatures before anything is measured.
+///
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DenoiseSettings {
+    pub filter: DenoiseFilter,
+    // Gaussian and bilateral: standard deviation of the blur in pixels
+    pub sigma_px: f32,
+    // Bilateral: temperature differences much larger than this (in Kelvin) are treated as edges
+    pub range_sigma_kelvin: f32,
+}
+
+impl Default for DenoiseSettings {
+    fn default() -> Self {
+        Self {
+            filter: DenoiseFilter::Off,
+            sigma_px: 1.0,
+            range_sigma_kelvin: 1.0,
+        }
+    }
+}
+
+impl DenoiseSettings {
+    ///
+    /// Filters the temperatures through `scratch`, e.g. kept between frames,
+    /// which only allocates when the frames grew.
+    ///
+    pub fn apply(&self, data: &mut ThermalData, scratch: &mut Vec<Temp>) {
+        if data.width == 0 || data.height == 0 {
+            return;
+        }
+        let sigma = self.sigma_px.clamp(MIN_DENOISE_SIGMA, MAX_DENOISE_SIGMA);
+        match self.filter {
+            DenoiseFilter::Off => {}
+            DenoiseFilter::Median => median_3x3(data, scratch),
+            DenoiseFilter::Gaussian => gaussian(data, sigma, scratch),
+            DenoiseFilter::Bilateral => {
+                bilateral(data, sigma, self.range_sigma_kelvin.max(0.01), scratch)
+            }
+        }
+    }
+}
+
+///
+/// Pixel at the given offset from (x, y), repeating the border pixels outside of the image.
+///
+#[inline(always)]
+fn clamped_at(data: &ThermalData, x: usize, y: usize, dx: isize, dy: isize) -> Temp {
+    let x = (x as isize + dx).clamp(0, data.width as isize - 1) as usize;
+    let y = (y as isize + dy).clamp(0, data.height as isize - 1) as usize;
+    data.temperature_at(x, y)
+}
+
+fn median_3x3(data: &mut ThermalData, scratch: &mut Vec<Temp>) {
+    data.map_positions_into(scratch, |x, y| {
+        let mut window = [Temp::new(0.0); 9];
+        for (i, pixel) in window.iter_mut().enumerate() {
+            *pixel = clamped_at(data, x, y, i as isize % 3 - 1, i as isize / 3 - 1);
+        }
+        let (_, median, _) = window.select_nth_unstable_by(4, |a, b| {
+            a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal)
+        });
+        *median
+    });
+    std::mem::swap(&mut data.data, scratch);
+}
+
+///
+/// Normalized weights of a gaussian, from -radius to radius, at the start of `kernel`.
+/// Returns the number of weights.
+///
+fn gaussian_kernel(sigma: f32, kernel: &mut [f32; MAX_GAUSSIAN_TAPS]) -> usize {
+    let radius = (sigma * 3.0).ceil() as isize;
+    let len = (radius * 2 + 1) as usize;
+    for (i, weight) in (-radius..=radius).zip(kernel.iter_mut()) {
+        *weight = (-(i * i) as f32 / (2.0 * sigma * sigma)).exp();
+    }
+    let sum: f32 = kernel[..len].iter().sum();
+    kernel[..len].iter_mut().for_each(|w| *w /= sum);
+    len
+}
+
+fn gaussian(data: &mut ThermalData, sigma: f32, scratch: &mut Vec<Temp>) {
+    let mut weights = [0.0; MAX_GAUSSIAN_TAPS];
+    let len = gaussian_kernel(sigma, &mut weights);
+    let kernel = &weights[..len];
+    let radius = (kernel.len() / 2) as isize;
+
+    // separable, blur the rows and then the columns
+    for horizontal in [true, false] {
+        data.map_positions_into(scratch, |x, y| {
+            let mut sum = 0.0;
+            for (i, weight) in kernel.iter().enumerate() {
+                let offset = i as isize - radius;
+                let (dx, dy) = if horizontal { (offset, 0) } else { (0, offset) };
+                sum += clamped_at(data, x, y, dx, dy).to_unit(TemperatureUnit::Kelvin) * weight;
+            }
+            Temp::new(sum)
+        });
+        std::mem::swap(&mut data.data, scratch);
+    }
+}
+
+fn bilateral(data: &mut ThermalData, sigma: f32, range_sigma_kelvin: f32, scratch: &mut Vec<Temp>) {
+    let radius = (sigma * 2.0).ceil() as isize;
+    let size = (radius * 2 + 1) as usize;
+    let mut weights = [0.0; MAX_BILATERAL_SIZE * MAX_BILATERAL_SIZE];
+    let spatial_weights = &mut weights[..size * size];
+    for (i, weight) in spatial_weights.iter_mut().enumerate() {
+        let dx = (i % size) as isize - radius;
+        let dy = (i / size) as isize - radius;
+        *weight = (-((dx * dx + dy * dy) as f32) / (2.0 * sigma * sigma)).exp();
+    }
+    let spatial_weights = &*spatial_weights;
+    let range_factor = -1.0 / (2.0 * range_sigma_kelvin * range_sigma_kelvin);
+
+    data.map_positions_into(scratch, |x, y| {
+        let center = data.temperature_at(x, y);
+        let mut sum = 0.0;
+        let mut weight_sum = 0.0;
+        for (i, spatial_weight) in spatial_weights.iter().enumerate() {
+            let dx = (i % size) as isize - radius;
+            let dy = (i / size) as isize - radius;
+            let pixel = clamped_at(data, x, y, dx, dy);
+            let difference = (pixel - center).to_unit(TemperatureUnit::Kelvin);
+            let weight = spatial_weight * (difference * difference * range_factor).exp();
+            sum += pixel.to_unit(TemperatureUnit::Kelvin) * weight;
+            weight_sum += weight;
+        }
+        // the center pixel has weight 1, so the sum is never zero
+        Temp::new(sum / weight_sum)
+    });
+    std::mem::swap(&mut data.data, scratch);
+}
@@ -0,0 +1,419 @@
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::PathBuf,
+};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    temperature::{Temp, TempDelta, TemperatureUnit},
+    thermal_data::ThermalData,
+};
+
+// Number of frames averaged for each reference, so the sensor noise doesn't end up in the calibration
+pub const FLAT_FIELD_FRAMES: usize = 25;
+// The two references of a gain calibration have to differ at least this much (in Kelvin)
+const MIN_REFERENCE_DIFFERENCE_KELVIN: f32 = 5.0;
+
+///
+/// What is captured while the camera is pointed at the uniform reference.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FlatFieldStep {
+    // Per-pixel offsets. With a temperature, the pixels are corrected to it (e.g. a blackbody),
+    // without one only the differences between the pixels are removed.
+    Reference(Option<Temp>),
+    // Adds per-pixel gains to the stored calibration, using a second reference at another temperature
+    SecondReference(Temp),
+}
+
+///
+/// Averages the frames captured for one reference.
+///
+pub struct FlatFieldAccumulator {
+    step: FlatFieldStep,
+    width: usize,
+    height: usize,
+    frames: usize,
+    // Per pixel sums in Kelvin
+    sums: Vec<f64>,
+}
+
+impl FlatFieldAccumulator {
+    pub fn new(step: FlatFieldStep, width: usize, height: usize) -> Self {
+        Self {
+            step,
+            width,
+            height,
+            frames: 0,
+            sums: vec![0.0; width * height],
+        }
+    }
+
+    ///
+    /// Adds a frame, frames of another resolution are ignored.
+    ///
+    pub fn add_frame(&mut self, data: &ThermalData) {
+        if data.width != self.width || data.height != self.height {
+            return;
+        }
+        for (sum, temp) in self.sums.iter_mut().zip(data.data.iter()) {
+            *sum += temp.to_unit(TemperatureUnit::Kelvin) as f64;
+        }
+        self.frames += 1;
+    }
+
+    pub fn progress(&self) -> f32 {
+        self.frames as f32 / FLAT_FIELD_FRAMES as f32
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.frames >= FLAT_FIELD_FRAMES
+    }
+
+    fn means(&self) -> Vec<f32> {
+        let frames = self.frames.max(1) as f64;
+        self.sums.iter().map(|sum| (sum / frames) as f32).collect()
+    }
+
+    ///
+    /// Computes the calibration from the averaged frames. `previous` is the stored calibration,
+    /// which a second reference adds the gains to.
+    ///
+    pub fn finish(&self, previous: Option<&FlatFieldCalibration>) -> Result<FlatFieldCalibration> {
+        let means = self.means();
+        match self.step {
+            FlatFieldStep::Reference(temperature) => Ok(FlatFieldCalibration::from_reference(
+                self.width,
+                self.height,
+                means,
+                temperature,
+            )),
+            FlatFieldStep::SecondReference(temperature) => previous
+                .ok_or_else(|| anyhow!("Capture the first reference before the second one"))?
+                .with_second_reference(&means, temperature),
+        }
+    }
+}
+
+///
+/// Per-pixel correction used for flat-field (non-uniformity) correction.
+///
+/// Captured while the camera is pointed at a surface of uniform temperature,
+/// each offset is the difference between the reference temperature (or the frame mean,
+/// if it isn't known) and the pixel value, so adding it back to a frame cancels the
+/// fixed-pattern noise of the sensor. A second reference at another temperature
+/// additionally corrects the differing sensitivity of the pixels.
+///
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FlatFieldCalibration {
+    pub width: usize,
+    pub height: usize,
+
+    // Offsets in Kelvin, row-major, same layout as ThermalData::data
+    offsets: Vec<f32>,
+    // Gains, applied before the offsets. None if only one reference was captured.
+    #[serde(default)]
+    gains: Option<Vec<f32>>,
+
+    // The first reference, kept to add a second one later
+    #[serde(default)]
+    reference_temperature: Option<Temp>,
+    #[serde(default)]
+    reference_means: Vec<f32>,
+}
+
+impl FlatFieldCalibration {
+    fn from_reference(
+        width: usize,
+        height: usize,
+        means: Vec<f32>,
+        temperature: Option<Temp>,
+    ) -> Self {
+        let target = temperature
+            .map(|t| t.to_unit(TemperatureUnit::Kelvin) as f64)
+            .unwrap_or_else(|| {
+                means.iter().map(|m| *m as f64).sum::<f64>() / means.len().max(1) as f64
+            });
+
+        Self {
+            width,
+            height,
+            offsets: means.iter().map(|m| (target - *m as f64) as f32).collect(),
+            gains: None,
+            reference_temperature: temperature,
+            reference_means: means,
+        }
+    }
+
+    fn with_second_reference(&self, means: &[f32], temperature: Temp) -> Result<Self> {
+        let first_temperature = self.reference_temperature.ok_or_else(|| {
+            anyhow!("The first reference needs a known temperature to compute gains")
+        })?;
+        if self.reference_means.len() != means.len() {
+            return Err(anyhow!(
+                "The references were captured at different resolutions"
+            ));
+        }
+        let t1 = first_temperature.to_unit(TemperatureUnit::Kelvin);
+        let t2 = temperature.to_unit(TemperatureUnit::Kelvin);
+        if (t2 - t1).abs() < MIN_REFERENCE_DIFFERENCE_KELVIN {
+            return Err(anyhow!(
+                "The references have to differ by at least {} K",
+                MIN_REFERENCE_DIFFERENCE_KELVIN
+            ));
+        }
+
+        let mut gains = Vec::with_capacity(means.len());
+        let mut offsets = Vec::with_capacity(means.len());
+        for (m1, m2) in self.reference_means.iter().zip(means.iter()) {
+            // a pixel which didn't respond to the change keeps the offset only
+            let gain = if (m2 - m1).abs() > f32::EPSILON {
+                (t2 - t1) / (m2 - m1)
+            } else {
+                1.0
+            };
+            gains.push(gain);
+            offsets.push(t1 - m1 * gain);
+        }
+
+        Ok(Self {
+            width: self.width,
+            height: self.height,
+            offsets,
+            gains: Some(gains),
+            reference_temperature: self.reference_temperature,
+            reference_means: self.reference_means.clone(),
+        })
+    }
+
+    pub fn has_gains(&self) -> bool {
+        self.gains.is_some()
+    }
+
+    ///
+    /// Returns true if this calibration was captured with a sensor of the same resolution.
+    ///
+    pub fn matches(&self, data: &ThermalData) -> bool {
+        self.width == data.width
+            && self.height == data.height
+            && self.offsets.len() == data.data.len()
+    }
+
+    pub fn apply(&self, data: &mut ThermalData) {
+        if !self.matches(data) {
+            return;
+        }
+        match self.gains.as_ref() {
+            Some(gains) => {
+                for ((temp, offset), gain) in data
+                    .data
+                    .iter_mut()
+                    .zip(self.offsets.iter())
+                    .zip(gains.iter())
+                {
+                    *temp = Temp::new(temp.to_unit(TemperatureUnit::Kelvin) * gain + offset);
+                }
+            }
+            None => {
+                for (temp, offset) in data.data.iter_mut().zip(self.offsets.iter()) {
+                    *temp = *temp + TempDelta::new(*offset);
+                }
+            }
+        }
+    }
+
+    ///
+    /// `camera_id` identifies the camera (see `ThermalSource::camera_id`),
+    /// without it the calibration is shared by all sources of the resolution.
+    ///
+    pub fn calibration_path(camera_id: Option<&str>, width: usize, height: usize) -> PathBuf {
+        let mut path = dirs::config_dir().unwrap();
+        path.push("thermal-viewer");
+        path.push(match camera_id {
+            Some(camera_id) => format!("flat_field_{}_{}x{}.json", camera_id, width, height),
+            None => format!("flat_field_{}x{}.json", width, height),
+        });
+        path
+    }
+
+    ///
+    /// Loads a stored calibration for the given camera and resolution, if there is one.
+    /// Falls back to the calibration shared by the resolution, which older versions stored.
+    ///
+    pub fn load(camera_id: Option<&str>, width: usize, height: usize) -> Result<Option<Self>> {
+        let mut path = Self::calibration_path(camera_id, width, height);
+        if !path.exists() {
+            path = Self::calibration_path(None, width, height);
+        }
+        if !path.exists() {
+            return Ok(None);
+        }
+        let file = File::open(path)?;
+        let reader = BufReader::new(file);
+        let calibration: FlatFieldCalibration = serde_json::from_reader(reader)?;
+        if calibration.width != width
+            || calibration.height != height
+            || calibration.offsets.len() != width * height
+            || calibration
+                .gains
+                .as_ref()
+                .is_some_and(|gains| gains.len() != width * height)
+        {
+            return Err(anyhow!(
+                "Stored flat field calibration does not match the {}x{} resolution",
+                width,
+                height
+            ));
+        }
+        Ok(Some(calibration))
+    }
+
+    pub fn save(&self, camera_id: Option<&str>) -> Result<()> {
+        let path = Self::calibration_path(camera_id, self.width, self.height);
+        let dir_path = path.parent().unwrap();
+        if !dir_path.exists() {
+            fs::create_dir_all(dir_path)?;
+        }
+
+        let file = File::create(path)?;
+        let writer = BufWriter::new(file);
+        serde_json::to_writer(writer, self)?;
+        Ok(())
+    }
+
+    ///
+    /// Removes the calibration of the camera and the shared one of the resolution,
+    /// so that the latter isn't loaded in its place.
+    ///
+    pub fn remove(camera_id: Option<&str>, width: usize, height: usize) -> Result<()> {
+        for path in [
+            Self::calibration_path(camera_id, width, height),
+            Self::calibration_path(None, width, height),
+        ] {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WIDTH: usize = 4;
+    const HEIGHT: usize = 3;
+
+    // Fixed-pattern offsets of the sensor in Kelvin, a column pattern plus single hot and cold pixels
+    fn pattern() -> Vec<f32> {
+        (0..WIDTH * HEIGHT)
+            .map(|i| match i {
+                5 => 2.5,
+                10 => -1.75,
+                _ => (i % WIDTH) as f32 * 0.5 - 0.75,
+            })
+            .collect()
+    }
+
+    // A uniform scene at `kelvin` as seen through the pattern, with `gains` scaling the response
+    fn frame(kelvin: f32, gains: Option<&[f32]>) -> ThermalData {
+        let data = pattern()
+            .iter()
+            .enumerate()
+            .map(|(i, offset)| {
+                let gain = gains.map_or(1.0, |gains| gains[i]);
+                Temp::new(kelvin * gain + offset)
+            })
+            .collect();
+        ThermalData::new(WIDTH, HEIGHT, data)
+    }
+
+    fn capture(
+        step: FlatFieldStep,
+        reference: &ThermalData,
+        previous: Option<&FlatFieldCalibration>,
+    ) -> FlatFieldCalibration {
+        let mut accumulator = FlatFieldAccumulator::new(step, WIDTH, HEIGHT);
+        while !accumulator.is_done() {
+            accumulator.add_frame(reference);
+        }
+        accumulator.finish(previous).unwrap()
+    }
+
+    fn assert_uniform(data: &ThermalData, kelvin: f32) {
+        for (i, temp) in data.data.iter().enumerate() {
+            let value = temp.to_unit(TemperatureUnit::Kelvin);
+            assert!(
+                (value - kelvin).abs() < 1e-3,
+                "pixel {} is {} K instead of {} K",
+                i,
+                value,
+                kelvin
+            );
+        }
+    }
+
+    #[test]
+    fn offsets_cancel_the_pattern() {
+        let calibration = capture(
+            FlatFieldStep::Reference(Some(Temp::new(300.0))),
+            &frame(300.0, None),
+            None,
+        );
+        assert!(!calibration.has_gains());
+
+        // a scene at another temperature, the pattern is gone and the reading is unchanged
+        let mut data = frame(310.0, None);
+        calibration.apply(&mut data);
+        assert_uniform(&data, 310.0);
+    }
+
+    #[test]
+    fn offsets_without_reference_temperature_keep_the_mean() {
+        let reference = frame(300.0, None);
+        let mean = pattern().iter().sum::<f32>() / (WIDTH * HEIGHT) as f32;
+        let calibration = capture(FlatFieldStep::Reference(None), &reference, None);
+
+        let mut data = reference.clone();
+        calibration.apply(&mut data);
+        assert_uniform(&data, 300.0 + mean);
+    }
+
+    #[test]
+    fn gains_cancel_the_differing_sensitivity() {
+        let gains: Vec<f32> = (0..WIDTH * HEIGHT)
+            .map(|i| 0.95 + (i % 5) as f32 * 0.025)
+            .collect();
+        let first = capture(
+            FlatFieldStep::Reference(Some(Temp::new(293.0))),
+            &frame(293.0, Some(&gains)),
+            None,
+        );
+        let calibration = capture(
+            FlatFieldStep::SecondReference(Temp::new(313.0)),
+            &frame(313.0, Some(&gains)),
+            Some(&first),
+        );
+        assert!(calibration.has_gains());
+
+        let mut data = frame(330.0, Some(&gains));
+        calibration.apply(&mut data);
+        assert_uniform(&data, 330.0);
+    }
+
+    #[test]
+    fn other_resolutions_are_left_alone() {
+        let calibration = capture(
+            FlatFieldStep::Reference(Some(Temp::new(300.0))),
+            &frame(300.0, None),
+            None,
+        );
+        let mut data = ThermalData::new(2, 2, vec![Temp::new(300.0); 4]);
+        calibration.apply(&mut data);
+        assert_uniform(&data, 300.0);
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    temperature::{reading_to_unit, Temp, TemperatureUnit},
+    thermal_data::{ThermalData, ThermalDataPos, ThermalDataRect, ThermalDataStats},
+};
+
+pub const MIN_GRID_CELLS: usize = 1;
+pub const MAX_GRID_CELLS: usize = 32;
+
+///
+/// Which temperature of each cell is shown and exported.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GridMetric {
+    Mean,
+    Max,
+}
+
+impl GridMetric {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GridMetric::Mean => "Mean",
+            GridMetric::Max => "Max",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GridAnalysisSettings {
+    pub enabled: bool,
+    // Number of cells across and down (MIN_GRID_CELLS..=MAX_GRID_CELLS)
+    pub columns: usize,
+    pub rows: usize,
+    pub metric: GridMetric,
+}
+
+impl Default for GridAnalysisSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            columns: 4,
+            rows: 3,
+            metric: GridMetric::Max,
+        }
+    }
+}
+
+pub struct GridCell {
+    pub rect: ThermalDataRect,
+    pub stats: ThermalDataStats,
+}
+
+impl GridCell {
+    pub fn value(&self, metric: GridMetric) -> Temp {
+        match metric {
+            GridMetric::Mean => self.stats.mean,
+            GridMetric::Max => self.stats.max,
+        }
+    }
+}
+
+///
+/// Statistics of the frame divided into a grid of equally sized cells,
+/// e.g. one per breaker of an electrical panel.
+///
+pub struct GridAnalysis {
+    pub columns: usize,
+    pub rows: usize,
+    // Row-major, starting at the top left cell
+    pub cells: Vec<GridCell>,
+}
+
+impl GridAnalysis {
+    ///
+    /// Divides the frame into the grid of the settings. The number of cells is limited
+    /// to the pixels of the frame, so no cell is empty.
+    ///
+    pub fn from_thermal_data(data: &ThermalData, settings: &GridAnalysisSettings) -> Self {
+        let columns = settings
+            .columns
+            .clamp(MIN_GRID_CELLS, MAX_GRID_CELLS)
+            .min(data.width.max(1));
+        let rows = settings
+            .rows
+            .clamp(MIN_GRID_CELLS, MAX_GRID_CELLS)
+            .min(data.height.max(1));
+        // bounds of the cells along one axis, the remainder is spread over the cells
+        let edges = |cells: usize, size: usize| -> Vec<usize> {
+            (0..=cells).map(|i| i * size / cells).collect()
+        };
+        let x_edges = edges(columns, data.width);
+        let y_edges = edges(rows, data.height);
+
+        let mut cells = Vec::with_capacity(columns * rows);
+        for y in 0..rows {
+            for x in 0..columns {
+                let (x0, x1) = (x_edges[x], x_edges[x + 1]);
+                let (y0, y1) = (y_edges[y], y_edges[y + 1]);
+                let stats = ThermalDataStats::from_temperatures(
+                    (y0..y1).flat_map(|py| (x0..x1).map(move |px| data.temperature_at(px, py))),
+                );
+                cells.push(GridCell {
+                    rect: ThermalDataRect {
+                        min: ThermalDataPos::new(x0, y0),
+                        max: ThermalDataPos::new(x1.saturating_sub(1), y1.saturating_sub(1)),
+                    },
+                    stats,
+                });
+            }
+        }
+        Self {
+            columns,
+            rows,
+            cells,
+        }
+    }
+
+    ///
+    /// Writes the values of the cells as a CSV table laid out like the grid,
+    /// with the columns labeled A, B, … and the rows 1, 2, … as in survey reports.
+    /// The values are differences if `is_delta`, see `reading_to_unit`.
+    ///
+    pub fn export_csv(
+        &self,
+        path: &Path,
+        metric: GridMetric,
+        unit: TemperatureUnit,
+        is_delta: bool,
+    ) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        write!(writer, "\"{} ({})\"", metric.name(), unit.suffix())?;
+        for x in 0..self.columns {
+            write!(writer, ",{}", column_label(x))?;
+        }
+        writeln!(writer)?;
+        for (y, row) in self.cells.chunks(self.columns).enumerate() {
+            write!(writer, "{}", y + 1)?;
+            for cell in row {
+                write!(
+                    writer,
+                    ",{:.3}",
+                    reading_to_unit(cell.value(metric), unit, is_delta)
+                )?;
+            }
+            writeln!(writer)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+///
+/// Spreadsheet style label of a column: A, B, …, Z, AA, AB, …
+///
+pub fn column_label(index: usize) -> String {
+    let mut label = String::new();
+    let mut n = index + 1;
+    while n > 0 {
+        n -= 1;
+        label.insert(0, (b'A' + (n % 26) as u8) as char);
+        n /= 26;
+    }
+    label
+}
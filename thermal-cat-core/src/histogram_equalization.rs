@@ -0,0 +1,59 @@
+use crate::{
+    temperature::{Temp, TempRange},
+    thermal_data::ThermalData,
+};
+
+// Resolution of the lookup table over the display range
+const EQUALIZATION_BINS: usize = 1024;
+
+// Bins are capped at this multiple of the average bin count before accumulating
+// (plateau equalization), so that a large uniform area doesn't stretch its sensor noise
+// over most of the gradient
+const PLATEAU_FACTOR: f32 = 8.0;
+
+///
+/// Maps temperatures to gradient positions by the cumulative distribution of the pixels,
+/// instead of linearly over the range.
+///
+pub struct HistogramEqualization {
+    range: TempRange,
+    // Cumulative fraction of pixels at the upper edge of each bin, from 0 to 1
+    cdf: Vec<f32>,
+}
+
+impl HistogramEqualization {
+    pub fn from_thermal_data(data: &ThermalData, range: TempRange) -> Self {
+        let mut bins = vec![0.0f32; EQUALIZATION_BINS];
+        for temp in data.data.iter() {
+            let bin = (range.factor(*temp).clamp(0.0, 1.0) * (EQUALIZATION_BINS - 1) as f32).round()
+                as usize;
+            bins[bin] += 1.0;
+        }
+
+        let plateau = (data.data.len() as f32 / EQUALIZATION_BINS as f32 * PLATEAU_FACTOR).max(1.0);
+        let mut cdf = Vec::with_capacity(EQUALIZATION_BINS);
+        let mut total = 0.0;
+        for count in bins.iter() {
+            total += count.min(plateau);
+            cdf.push(total);
+        }
+        if total > 0.0 {
+            for value in cdf.iter_mut() {
+                *value /= total;
+            }
+        }
+
+        Self { range, cdf }
+    }
+
+    ///
+    /// Position of the temperature on the gradient, from 0 to 1.
+    ///
+    pub fn factor(&self, temp: Temp) -> f32 {
+        let position = self.range.factor(temp).clamp(0.0, 1.0) * (EQUALIZATION_BINS - 1) as f32;
+        let lower = position.floor() as usize;
+        let upper = (lower + 1).min(EQUALIZATION_BINS - 1);
+        let t = position - lower as f32;
+        self.cdf[lower] * (1.0 - t) + self.cdf[upper] * t
+    }
+}
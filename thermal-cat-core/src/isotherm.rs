@@ -0,0 +1,69 @@
+use ecolor::Color32;
+use serde::{Deserialize, Serialize};
+
+use crate::temperature::Temp;
+
+// Every isotherm is checked for every pixel, so keep the number bounded
+pub const MAX_ISOTHERMS: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IsothermMode {
+    Above,
+    Below,
+    Between,
+}
+
+impl IsothermMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            IsothermMode::Above => "Above",
+            IsothermMode::Below => "Below",
+            IsothermMode::Between => "Between",
+        }
+    }
+}
+
+///
+/// A temperature band which is drawn in a solid color instead of the gradient,
+/// e.g. everything above 60 °C in red.
+///
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Isotherm {
+    pub enabled: bool,
+    pub mode: IsothermMode,
+    // Used by Above and Between
+    pub lower: Temp,
+    // Used by Below and Between
+    pub upper: Temp,
+    pub color: Color32,
+}
+
+impl Isotherm {
+    pub fn new(mode: IsothermMode, lower: Temp, upper: Temp, color: Color32) -> Self {
+        Self {
+            enabled: true,
+            mode,
+            lower,
+            upper,
+            color,
+        }
+    }
+
+    pub fn contains(&self, temp: Temp) -> bool {
+        match self.mode {
+            IsothermMode::Above => temp >= self.lower,
+            IsothermMode::Below => temp <= self.upper,
+            IsothermMode::Between => temp >= self.lower && temp <= self.upper,
+        }
+    }
+}
+
+///
+/// Returns the color of the first enabled isotherm containing `temp`, if any.
+///
+pub fn isotherm_color(isotherms: &[Isotherm], temp: Temp) -> Option<Color32> {
+    isotherms
+        .iter()
+        .find(|isotherm| isotherm.enabled && isotherm.contains(temp))
+        .map(|isotherm| isotherm.color)
+}
@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{temperature::Temp, thermal_data::ThermalData};
+
+pub const MAX_DISTORTION_COEFFICIENT: f32 = 1.0;
+
+///
+/// Correction of the radial distortion of the lens, with the k1/k2 model:
+/// a point at the normalized radius r from the image center is seen at r * (1 + k1 r² + k2 r⁴).
+/// The radius is normalized to half the image diagonal, so the coefficients don't depend
+/// on the resolution. Wide-angle lenses bulge straight edges outwards (barrel distortion),
+/// which is corrected with a negative k1.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LensDistortion {
+    pub enabled: bool,
+    pub k1: f32,
+    pub k2: f32,
+}
+
+impl Default for LensDistortion {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            k1: 0.0,
+            k2: 0.0,
+        }
+    }
+}
+
+impl LensDistortion {
+    ///
+    /// Resamples the temperatures so straight edges in the scene are straight in the data.
+    /// Pixels seen outside of the sensor repeat its border. Resampled into `scratch`,
+    /// e.g. kept between frames, which is swapped with the temperatures.
+    ///
+    pub fn apply(&self, data: &mut ThermalData, scratch: &mut Vec<Temp>) {
+        if !self.enabled || (self.k1 == 0.0 && self.k2 == 0.0) {
+            return;
+        }
+        let (width, height) = (data.width, data.height);
+        if width < 2 || height < 2 {
+            return;
+        }
+        let k1 = self
+            .k1
+            .clamp(-MAX_DISTORTION_COEFFICIENT, MAX_DISTORTION_COEFFICIENT);
+        let k2 = self
+            .k2
+            .clamp(-MAX_DISTORTION_COEFFICIENT, MAX_DISTORTION_COEFFICIENT);
+        let center_x = (width - 1) as f32 / 2.0;
+        let center_y = (height - 1) as f32 / 2.0;
+        let norm = (center_x * center_x + center_y * center_y).sqrt();
+
+        scratch.clear();
+        for y in 0..height {
+            for x in 0..width {
+                let dx = (x as f32 - center_x) / norm;
+                let dy = (y as f32 - center_y) / norm;
+                let r2 = dx * dx + dy * dy;
+                let factor = 1.0 + k1 * r2 + k2 * r2 * r2;
+                scratch.push(sample_bilinear(
+                    data,
+                    center_x + dx * factor * norm,
+                    center_y + dy * factor * norm,
+                ));
+            }
+        }
+        std::mem::swap(&mut data.data, scratch);
+    }
+}
+
+fn sample_bilinear(data: &ThermalData, x: f32, y: f32) -> Temp {
+    let x = x.clamp(0.0, (data.width - 1) as f32);
+    let y = y.clamp(0.0, (data.height - 1) as f32);
+    let x0 = (x.floor() as usize).min(data.width - 2);
+    let y0 = (y.floor() as usize).min(data.height - 2);
+    let fx = x - x0 as f32;
+    let fy = y - y0 as f32;
+    let at = |x: usize, y: usize| data.data[y * data.width + x];
+
+    let top = at(x0, y0).lerp(at(x0 + 1, y0), fx);
+    let bottom = at(x0, y0 + 1).lerp(at(x0 + 1, y0 + 1), fx);
+    top.lerp(bottom, fy)
+}
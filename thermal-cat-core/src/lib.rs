@@ -0,0 +1,33 @@
+//!
+//! Capture and processing pipeline of Thermal Cat: camera adapters decoding the raw
+//! sensor data into `ThermalData`, temperature corrections, display range control
+//! and the gradients used to colorize it. `ThermalCapturer` runs the pipeline on the
+//! frames of a `ThermalSource`, applications add their own processing to it with a
+//! `CapturerExtension`.
+//!
+//! Rendering into `epaint::ColorImage` is behind the `color-image` feature (on by default),
+//! so the crate can be used without pulling in egui.
+//!
+
+pub mod auto_display_range_controller;
+pub mod bad_pixel_map;
+pub mod camera_adapter;
+pub mod change_detection;
+pub mod contour_lines;
+pub mod denoise;
+pub mod flat_field_calibration;
+pub mod grid_analysis;
+pub mod histogram_equalization;
+pub mod isotherm;
+pub mod lens_distortion;
+pub mod pseudo_color;
+pub mod temperature;
+pub mod temperature_correction;
+pub mod temporal_average;
+pub mod thermal_capturer;
+pub mod thermal_data;
+pub mod thermal_gradient;
+pub mod thermal_source;
+pub mod two_point_calibration;
+pub mod types;
+pub mod upscaling;
@@ -0,0 +1,97 @@
+use rayon::prelude::*;
+
+use crate::{
+    temperature::{Temp, TempRange},
+    thermal_data::ThermalData,
+    thermal_gradient::ThermalGradient,
+};
+
+// Bits kept of every color channel when looking colors up, 5 bits make a table of 32K entries
+const CHANNEL_BITS: u32 = 5;
+const CHANNEL_LEVELS: usize = 1 << CHANNEL_BITS;
+// Points sampled along the gradient, the resolution of the decoded temperatures
+const GRADIENT_SAMPLES: usize = 256;
+
+///
+/// Estimates temperatures from a colorized image, e.g. the video of a camera which only streams
+/// its false-color picture. Every pixel gets the temperature of the nearest color of the gradient
+/// within the range the camera maps to it.
+///
+/// The result is only as good as the image: the resolution is limited by the gradient and the
+/// compression of the video, and overlays burnt into the picture decode to bogus temperatures.
+///
+pub struct PseudoColorDecoder {
+    // Factor along the gradient for every color, indexed by the quantized channels
+    factors: Vec<f32>,
+    range: TempRange,
+}
+
+impl PseudoColorDecoder {
+    pub fn new(gradient: &ThermalGradient, range: TempRange) -> Self {
+        let last = (GRADIENT_SAMPLES - 1) as f32;
+        let samples: Vec<(f32, [f32; 3])> = (0..GRADIENT_SAMPLES)
+            .map(|i| {
+                let factor = i as f32 / last;
+                let color = gradient.get_color(factor);
+                (
+                    factor,
+                    [color.r() as f32, color.g() as f32, color.b() as f32],
+                )
+            })
+            .collect();
+
+        let step = 256.0 / CHANNEL_LEVELS as f32;
+        let factors = (0..CHANNEL_LEVELS * CHANNEL_LEVELS * CHANNEL_LEVELS)
+            .into_par_iter()
+            .map(|index| {
+                // the middle of the colors falling into this entry
+                let channel = |shift: u32| {
+                    ((index >> shift) & (CHANNEL_LEVELS - 1)) as f32 * step + step / 2.0
+                };
+                let color = [channel(2 * CHANNEL_BITS), channel(CHANNEL_BITS), channel(0)];
+                samples
+                    .iter()
+                    .map(|(factor, sample)| {
+                        let distance: f32 = sample
+                            .iter()
+                            .zip(color.iter())
+                            .map(|(a, b)| (a - b) * (a - b))
+                            .sum();
+                        (distance, *factor)
+                    })
+                    .min_by(|a, b| a.0.total_cmp(&b.0))
+                    .map(|(_, factor)| factor)
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        Self { factors, range }
+    }
+
+    #[inline(always)]
+    fn temperature(&self, r: u8, g: u8, b: u8) -> Temp {
+        let shift = 8 - CHANNEL_BITS;
+        let index = (((r >> shift) as usize) << (2 * CHANNEL_BITS))
+            | (((g >> shift) as usize) << CHANNEL_BITS)
+            | (b >> shift) as usize;
+        self.range.factor_to_temp(self.factors[index])
+    }
+
+    ///
+    /// Decodes tightly packed RGB pixels, rows may be padded to `stride` bytes.
+    ///
+    pub fn decode(&self, rgb: &[u8], width: usize, height: usize, stride: usize) -> ThermalData {
+        let mut data = vec![Temp::default(); width * height];
+        data.par_chunks_mut(width).enumerate().for_each(|(y, row)| {
+            let pixels = &rgb[y * stride..y * stride + width * 3];
+            for (temp, pixel) in row.iter_mut().zip(pixels.chunks_exact(3)) {
+                *temp = self.temperature(pixel[0], pixel[1], pixel[2]);
+            }
+        });
+        ThermalData {
+            width,
+            height,
+            data,
+        }
+    }
+}
@@ -0,0 +1,627 @@
+use serde::{Deserialize, Serialize};
+// 0.17.1
+use strum_macros::{Display, EnumIter}; // 0.17.1
+
+use std::{
+    fmt::{self, Debug},
+    ops,
+};
+
+//
+// Represents a temperature in Kelvin.
+//
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, PartialOrd)]
+pub struct Temp {
+    value_kelvin: f32,
+}
+
+impl Temp {
+    pub const MAX: Temp = Temp {
+        value_kelvin: f32::MAX,
+    };
+    pub const MIN: Temp = Temp {
+        value_kelvin: f32::MIN,
+    };
+    pub fn new(value_kelvin: f32) -> Self {
+        Self { value_kelvin }
+    }
+
+    pub fn from_celsius(value: f32) -> Self {
+        Temp::from_unit(TemperatureUnit::Celsius, value)
+    }
+
+    pub fn from_unit(unit: TemperatureUnit, value: f32) -> Self {
+        Self {
+            value_kelvin: match unit {
+                TemperatureUnit::Kelvin => value,
+                TemperatureUnit::Celsius => value + 273.15,
+                TemperatureUnit::Fahrenheit => (value - 32.0) / 1.8 + 273.15,
+                TemperatureUnit::Rankine => value / 1.8,
+            },
+        }
+    }
+    pub fn to_unit(self, unit: TemperatureUnit) -> f32 {
+        match unit {
+            TemperatureUnit::Kelvin => self.value_kelvin,
+            TemperatureUnit::Celsius => self.value_kelvin - 273.15,
+            TemperatureUnit::Fahrenheit => (self.value_kelvin - 273.15) * 1.8 + 32.0,
+            TemperatureUnit::Rankine => self.value_kelvin * 1.8,
+        }
+    }
+
+    ///
+    /// How much warmer this temperature is than `base`, negative if it's colder.
+    ///
+    pub fn delta_from(self, base: Temp) -> TempDelta {
+        TempDelta::new(self.value_kelvin - base.value_kelvin)
+    }
+
+    ///
+    /// Stores a difference where a temperature is expected, as frames in delta mode hold the
+    /// differences to their reference frame. Read it back with `as_delta` or `reading_to_unit`.
+    ///
+    pub fn from_delta(delta: TempDelta) -> Temp {
+        Temp::new(delta.value_kelvin)
+    }
+
+    ///
+    /// The difference stored by `from_delta`.
+    ///
+    pub fn as_delta(self) -> TempDelta {
+        TempDelta::new(self.value_kelvin)
+    }
+
+    pub fn min(self, other: Temp) -> Temp {
+        Temp::new(self.value_kelvin.min(other.value_kelvin))
+    }
+
+    pub fn max(self, other: Temp) -> Temp {
+        Temp::new(self.value_kelvin.max(other.value_kelvin))
+    }
+
+    pub fn clamp(self, min: Temp, max: Temp) -> Temp {
+        self.max(min).min(max)
+    }
+
+    ///
+    /// Linear interpolation, `factor` 0 gives this temperature and 1 gives `other`.
+    ///
+    pub fn lerp(self, other: Temp, factor: f32) -> Temp {
+        Temp::new(self.value_kelvin + (other.value_kelvin - self.value_kelvin) * factor)
+    }
+}
+
+impl Default for Temp {
+    fn default() -> Self {
+        Self { value_kelvin: 0.0 }
+    }
+}
+
+impl Debug for Temp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let debug_unit = TemperatureUnit::Celsius;
+        write!(f, "{} {}", self.to_unit(debug_unit), debug_unit.suffix())
+    }
+}
+
+impl ops::Sub for Temp {
+    type Output = TempDelta;
+
+    fn sub(self, rhs: Temp) -> Self::Output {
+        self.delta_from(rhs)
+    }
+}
+
+impl ops::Div<Temp> for Temp {
+    // Dividing two temperatures gives a ratio, which is a unitless value.
+    type Output = f32;
+
+    fn div(self, rhs: Temp) -> Self::Output {
+        self.value_kelvin / rhs.value_kelvin
+    }
+}
+
+impl ops::Add<TempDelta> for Temp {
+    type Output = Temp;
+
+    fn add(self, rhs: TempDelta) -> Self::Output {
+        Temp::new(self.value_kelvin + rhs.value_kelvin)
+    }
+}
+
+impl ops::Sub<TempDelta> for Temp {
+    type Output = Temp;
+
+    fn sub(self, rhs: TempDelta) -> Self::Output {
+        Temp::new(self.value_kelvin - rhs.value_kelvin)
+    }
+}
+
+///
+/// A difference between temperatures, e.g. a threshold, a headroom or a standard deviation.
+/// Kept apart from `Temp`, as converting it to a unit must not apply the offset of the unit:
+/// a difference of 10 K is 10 °C and 18 °F, while a temperature of 10 K is -263.15 °C.
+///
+#[derive(Clone, Copy, Default, Serialize, Deserialize, PartialEq, PartialOrd)]
+pub struct TempDelta {
+    value_kelvin: f32,
+}
+
+impl TempDelta {
+    pub const ZERO: TempDelta = TempDelta { value_kelvin: 0.0 };
+
+    pub fn new(value_kelvin: f32) -> Self {
+        Self { value_kelvin }
+    }
+
+    pub fn from_unit(unit: TemperatureUnit, value: f32) -> Self {
+        Self {
+            value_kelvin: value / unit.degree_kelvin(),
+        }
+    }
+
+    ///
+    /// Unlike `Temp::to_unit`, no offset is applied.
+    ///
+    pub fn to_unit(self, unit: TemperatureUnit) -> f32 {
+        self.value_kelvin * unit.degree_kelvin()
+    }
+
+    pub fn abs(self) -> TempDelta {
+        TempDelta::new(self.value_kelvin.abs())
+    }
+
+    pub fn max(self, other: TempDelta) -> TempDelta {
+        TempDelta::new(self.value_kelvin.max(other.value_kelvin))
+    }
+
+    ///
+    /// e.g. "1.5 °C", or "-1.5 °C" for a drop.
+    ///
+    pub fn format(self, unit: TemperatureUnit, precision: usize) -> String {
+        format!("{:.*} {}", precision, self.to_unit(unit), unit.suffix())
+    }
+}
+
+impl Debug for TempDelta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} K", self.value_kelvin)
+    }
+}
+
+impl ops::Add for TempDelta {
+    type Output = TempDelta;
+
+    fn add(self, rhs: TempDelta) -> Self::Output {
+        TempDelta::new(self.value_kelvin + rhs.value_kelvin)
+    }
+}
+
+impl ops::Sub for TempDelta {
+    type Output = TempDelta;
+
+    fn sub(self, rhs: TempDelta) -> Self::Output {
+        TempDelta::new(self.value_kelvin - rhs.value_kelvin)
+    }
+}
+
+impl ops::Neg for TempDelta {
+    type Output = TempDelta;
+
+    fn neg(self) -> Self::Output {
+        TempDelta::new(-self.value_kelvin)
+    }
+}
+
+impl ops::Mul<f32> for TempDelta {
+    type Output = TempDelta;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        TempDelta::new(self.value_kelvin * rhs)
+    }
+}
+
+impl ops::Div<f32> for TempDelta {
+    type Output = TempDelta;
+
+    fn div(self, rhs: f32) -> Self::Output {
+        TempDelta::new(self.value_kelvin / rhs)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TempRange {
+    pub min: Temp,
+    pub max: Temp,
+}
+
+impl Default for TempRange {
+    fn default() -> Self {
+        Self {
+            min: Temp::MIN,
+            max: Temp::MAX,
+        }
+    }
+}
+
+impl TempRange {
+    pub fn new(min: Temp, max: Temp) -> Self {
+        Self { min, max }
+    }
+
+    pub fn factor(&self, temp: Temp) -> f32 {
+        (temp.value_kelvin - self.min.value_kelvin)
+            / (self.max.value_kelvin - self.min.value_kelvin)
+    }
+
+    pub fn factor_to_temp(&self, factor: f32) -> Temp {
+        self.min.lerp(self.max, factor)
+    }
+
+    pub fn contains(&self, temp: Temp) -> bool {
+        temp.value_kelvin >= self.min.value_kelvin && temp.value_kelvin <= self.max.value_kelvin
+    }
+
+    pub fn contains_range(&self, range: TempRange) -> bool {
+        self.contains(range.min) && self.contains(range.max)
+    }
+
+    pub fn animate(&self, target: TempRange, factor: f32) -> TempRange {
+        TempRange {
+            min: self.min.lerp(target.min, factor),
+            max: self.max.lerp(target.max, factor),
+        }
+    }
+
+    ///
+    /// Width of the range.
+    ///
+    pub fn span(&self) -> TempDelta {
+        self.max.delta_from(self.min)
+    }
+
+    pub fn center(&self) -> Temp {
+        self.min.lerp(self.max, 0.5)
+    }
+
+    ///
+    /// The temperature of the range closest to `temp`.
+    ///
+    pub fn clamp(&self, temp: Temp) -> Temp {
+        temp.clamp(self.min, self.max)
+    }
+
+    ///
+    /// Widens the range by `delta` at each end, or narrows it if negative.
+    ///
+    pub fn expand_by(&self, delta: TempDelta) -> TempRange {
+        TempRange::new(self.min - delta, self.max + delta)
+    }
+
+    pub fn join(&self, other: TempRange) -> TempRange {
+        TempRange {
+            min: Temp::new(self.min.value_kelvin.min(other.min.value_kelvin)),
+            max: Temp::new(self.max.value_kelvin.max(other.max.value_kelvin)),
+        }
+    }
+
+    ///
+    /// Returns true if this range is the default range, which is the entire range of possible
+    /// temperatures.
+    ///
+    pub fn is_default(&self) -> bool {
+        self.min == Temp::MIN && self.max == Temp::MAX
+    }
+}
+
+impl Debug for TempRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{:?} - {:?}]", self.min, self.max)
+    }
+}
+
+#[derive(EnumIter, Display, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum TemperatureUnit {
+    #[default]
+    Kelvin,
+    Celsius,
+    Fahrenheit,
+    // Absolute scale with the degrees of Fahrenheit, used in US engineering
+    Rankine,
+}
+
+impl TemperatureUnit {
+    pub fn suffix(&self) -> String {
+        match self {
+            TemperatureUnit::Kelvin => "K".to_string(),
+            TemperatureUnit::Celsius => "°C".to_string(),
+            TemperatureUnit::Fahrenheit => "°F".to_string(),
+            TemperatureUnit::Rankine => "°R".to_string(),
+        }
+    }
+
+    ///
+    /// Decimal places suggested when the unit is picked. Kelvin is mostly used for
+    /// scientific work, the other units for readings where tenths are enough.
+    ///
+    pub fn default_precision(&self) -> usize {
+        match self {
+            TemperatureUnit::Kelvin => 2,
+            TemperatureUnit::Celsius | TemperatureUnit::Fahrenheit | TemperatureUnit::Rankine => 1,
+        }
+    }
+
+    ///
+    /// Size of a degree of this unit in Kelvin, what differences are scaled by.
+    ///
+    fn degree_kelvin(&self) -> f32 {
+        match self {
+            TemperatureUnit::Kelvin | TemperatureUnit::Celsius => 1.0,
+            TemperatureUnit::Fahrenheit | TemperatureUnit::Rankine => 1.8,
+        }
+    }
+
+    ///
+    /// Formats a temperature in this unit with a fixed number of decimal places,
+    /// followed by the unit suffix, e.g. "-4.5 °C".
+    ///
+    pub fn format(&self, temp: Temp, precision: usize) -> String {
+        let mut value = temp.to_unit(*self);
+        // avoid printing "-0.0" for values which round to zero
+        if format!("{:.*}", precision, value.abs())
+            .chars()
+            .all(|c| c == '0' || c == '.')
+        {
+            value = 0.0;
+        }
+        format!("{:.*} {}", precision, value, self.suffix())
+    }
+}
+
+///
+/// See `TemperatureUnit::format`.
+///
+pub fn format_temp(temp: Temp, unit: TemperatureUnit, precision: usize) -> String {
+    unit.format(temp, precision)
+}
+
+///
+/// Converts a value of a thermal frame to the unit, see `format_reading`.
+///
+pub fn reading_to_unit(value: Temp, unit: TemperatureUnit, is_delta: bool) -> f32 {
+    if is_delta {
+        value.as_delta().to_unit(unit)
+    } else {
+        value.to_unit(unit)
+    }
+}
+
+///
+/// Formats a value of a thermal frame, which holds temperature differences in Kelvin
+/// instead of temperatures in delta mode. Differences are signed, e.g. "+1.5 °C".
+///
+pub fn format_reading(
+    value: Temp,
+    unit: TemperatureUnit,
+    precision: usize,
+    is_delta: bool,
+) -> String {
+    if !is_delta {
+        return format_temp(value, unit, precision);
+    }
+    let delta = reading_to_unit(value, unit, true);
+    if format!("{:.*}", precision, delta.abs())
+        .chars()
+        .all(|c| c == '0' || c == '.')
+    {
+        return format!("{:.*} {}", precision, 0.0, unit.suffix());
+    }
+    format!("{:+.*} {}", precision, delta, unit.suffix())
+}
+
+///
+/// How temperatures are shown to the user: the unit and the number of decimal places.
+/// Every readout, edit field and export of the UI goes through the one of the preferences,
+/// so changing them applies everywhere at once.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TempFormat {
+    pub unit: TemperatureUnit,
+    pub precision: usize,
+}
+
+impl TempFormat {
+    pub fn new(unit: TemperatureUnit, precision: usize) -> Self {
+        Self { unit, precision }
+    }
+
+    pub fn suffix(&self) -> String {
+        self.unit.suffix()
+    }
+
+    ///
+    /// e.g. "-4.5 °C", see `format_temp`.
+    ///
+    pub fn format(&self, temp: Temp) -> String {
+        self.unit.format(temp, self.precision)
+    }
+
+    ///
+    /// e.g. "1.5 °C", see `TempDelta::format`.
+    ///
+    pub fn format_delta(&self, delta: TempDelta) -> String {
+        delta.format(self.unit, self.precision)
+    }
+
+    ///
+    /// A value of a thermal frame, see `format_reading`.
+    ///
+    pub fn format_reading(&self, value: Temp, is_delta: bool) -> String {
+        format_reading(value, self.unit, self.precision, is_delta)
+    }
+
+    ///
+    /// A plain number in the unit, e.g. for plot labels which carry the unit elsewhere.
+    ///
+    pub fn format_value(&self, value: f64) -> String {
+        format!("{:.*}", self.precision, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn delta_to_unit_applies_no_offset() {
+        let delta = TempDelta::new(10.0);
+        assert_close(delta.to_unit(TemperatureUnit::Kelvin), 10.0);
+        assert_close(delta.to_unit(TemperatureUnit::Celsius), 10.0);
+        assert_close(delta.to_unit(TemperatureUnit::Fahrenheit), 18.0);
+        assert_close(delta.to_unit(TemperatureUnit::Rankine), 18.0);
+        // unlike a temperature of 10 K
+        assert_close(Temp::new(10.0).to_unit(TemperatureUnit::Celsius), -263.15);
+        assert_close(
+            TempDelta::from_unit(TemperatureUnit::Fahrenheit, 18.0)
+                .to_unit(TemperatureUnit::Kelvin),
+            10.0,
+        );
+    }
+
+    #[test]
+    fn delta_from() {
+        let warm = Temp::from_celsius(30.0);
+        let cold = Temp::from_celsius(20.0);
+        assert_close(
+            warm.delta_from(cold).to_unit(TemperatureUnit::Celsius),
+            10.0,
+        );
+        assert_close(
+            cold.delta_from(warm).to_unit(TemperatureUnit::Fahrenheit),
+            -18.0,
+        );
+        assert_close((warm - cold).to_unit(TemperatureUnit::Fahrenheit), 18.0);
+        assert_eq!(
+            (cold - warm).format(TemperatureUnit::Fahrenheit, 1),
+            "-18.0 °F"
+        );
+        assert_close(
+            (cold + warm.delta_from(cold)).to_unit(TemperatureUnit::Celsius),
+            30.0,
+        );
+    }
+
+    #[test]
+    fn delta_mode_values() {
+        let value = Temp::from_delta(Temp::from_celsius(25.0) - Temp::from_celsius(30.0));
+        assert_close(reading_to_unit(value, TemperatureUnit::Celsius, true), -5.0);
+        assert_close(
+            reading_to_unit(value, TemperatureUnit::Fahrenheit, true),
+            -9.0,
+        );
+        assert_close(value.as_delta().to_unit(TemperatureUnit::Kelvin), -5.0);
+    }
+
+    #[test]
+    fn format_temp_per_unit() {
+        let temp = Temp::from_celsius(21.456);
+        assert_eq!(format_temp(temp, TemperatureUnit::Celsius, 1), "21.5 °C");
+        assert_eq!(format_temp(temp, TemperatureUnit::Celsius, 0), "21 °C");
+        assert_eq!(format_temp(temp, TemperatureUnit::Kelvin, 2), "294.61 K");
+        assert_eq!(format_temp(temp, TemperatureUnit::Fahrenheit, 1), "70.6 °F");
+        assert_eq!(format_temp(temp, TemperatureUnit::Rankine, 1), "530.3 °R");
+    }
+
+    #[test]
+    fn format_temp_below_zero() {
+        let temp = Temp::from_celsius(-4.5);
+        assert_eq!(format_temp(temp, TemperatureUnit::Celsius, 1), "-4.5 °C");
+        assert_eq!(format_temp(temp, TemperatureUnit::Celsius, 3), "-4.500 °C");
+        assert_eq!(format_temp(temp, TemperatureUnit::Fahrenheit, 1), "23.9 °F");
+        assert_eq!(
+            format_temp(Temp::from_celsius(-40.0), TemperatureUnit::Fahrenheit, 0),
+            "-40 °F"
+        );
+        // values rounding to zero don't keep their sign
+        assert_eq!(
+            format_temp(Temp::from_celsius(-0.04), TemperatureUnit::Celsius, 1),
+            "0.0 °C"
+        );
+        assert_eq!(
+            format_temp(Temp::from_celsius(-0.04), TemperatureUnit::Celsius, 2),
+            "-0.04 °C"
+        );
+    }
+
+    #[test]
+    fn lerp() {
+        let a = Temp::from_celsius(10.0);
+        let b = Temp::from_celsius(30.0);
+        assert_close(a.lerp(b, 0.0).to_unit(TemperatureUnit::Celsius), 10.0);
+        assert_close(a.lerp(b, 0.25).to_unit(TemperatureUnit::Celsius), 15.0);
+        assert_close(a.lerp(b, 1.0).to_unit(TemperatureUnit::Celsius), 30.0);
+        assert_close(b.lerp(a, 0.25).to_unit(TemperatureUnit::Celsius), 25.0);
+    }
+
+    #[test]
+    fn clamp() {
+        let min = Temp::from_celsius(0.0);
+        let max = Temp::from_celsius(100.0);
+        let clamp = |celsius: f32| {
+            Temp::from_celsius(celsius)
+                .clamp(min, max)
+                .to_unit(TemperatureUnit::Celsius)
+        };
+        assert_close(clamp(-20.0), 0.0);
+        assert_close(clamp(50.0), 50.0);
+        assert_close(clamp(120.0), 100.0);
+
+        let range = TempRange::new(min, max);
+        assert_close(
+            range
+                .clamp(Temp::from_celsius(-5.0))
+                .to_unit(TemperatureUnit::Celsius),
+            0.0,
+        );
+    }
+
+    #[test]
+    fn contains() {
+        let range = TempRange::new(Temp::from_celsius(20.0), Temp::from_celsius(40.0));
+        assert!(range.contains(Temp::from_celsius(20.0)));
+        assert!(range.contains(Temp::from_celsius(30.0)));
+        assert!(range.contains(Temp::from_celsius(40.0)));
+        assert!(!range.contains(Temp::from_celsius(19.9)));
+        assert!(!range.contains(Temp::from_celsius(40.1)));
+        assert!(range.contains_range(TempRange::new(
+            Temp::from_celsius(25.0),
+            Temp::from_celsius(35.0)
+        )));
+        assert!(!range.contains_range(TempRange::new(
+            Temp::from_celsius(25.0),
+            Temp::from_celsius(45.0)
+        )));
+    }
+
+    #[test]
+    fn expand_by() {
+        let range = TempRange::new(Temp::from_celsius(20.0), Temp::from_celsius(40.0));
+        let wider = range.expand_by(TempDelta::new(5.0));
+        assert_close(wider.min.to_unit(TemperatureUnit::Celsius), 15.0);
+        assert_close(wider.max.to_unit(TemperatureUnit::Celsius), 45.0);
+        assert_close(wider.span().to_unit(TemperatureUnit::Kelvin), 30.0);
+
+        let narrower = range.expand_by(-TempDelta::new(5.0));
+        assert_close(narrower.min.to_unit(TemperatureUnit::Celsius), 25.0);
+        assert_close(narrower.max.to_unit(TemperatureUnit::Celsius), 35.0);
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{
+    temperature::{Temp, TemperatureUnit},
+    thermal_data::{ThermalData, ThermalDataRect},
+};
+
+pub const MIN_EMISSIVITY: f32 = 0.01;
+
+///
+/// Converts the apparent temperature reported by the camera (calibrated for a black body)
+/// into the temperature of a surface with the given emissivity.
+///
+/// By Stefan-Boltzmann, the radiation reaching the sensor is emitted by the object
+/// plus reflected from its surroundings:
+///
+/// `T_app^4 = e * T_obj^4 + (1 - e) * T_refl^4`
+///
+/// so `T_obj = ((T_app^4 - (1 - e) * T_refl^4) / e)^(1/4)`.
+/// All temperatures are absolute (Kelvin).
+///
+pub fn correct_emissivity(apparent: Temp, emissivity: f32, reflected: Temp) -> Temp {
+    let emissivity = emissivity.clamp(MIN_EMISSIVITY, 1.0);
+    let apparent = apparent.to_unit(TemperatureUnit::Kelvin) as f64;
+    let reflected = reflected.to_unit(TemperatureUnit::Kelvin) as f64;
+    let emissivity = emissivity as f64;
+
+    let object_4 = (apparent.powi(4) - (1.0 - emissivity) * reflected.powi(4)) / emissivity;
+    // the surroundings can't be reflecting more than was measured, clamp instead of NaN
+    Temp::new(object_4.max(0.0).powf(0.25) as f32)
+}
+
+///
+/// Per-pixel emissivity of the scene, so that regions made of different materials
+/// can each be corrected with their own value.
+///
+#[derive(Default)]
+pub struct EmissivityMap {
+    width: usize,
+    height: usize,
+    values: Vec<f32>,
+}
+
+impl EmissivityMap {
+    ///
+    /// Fills the map for an image of the given size with the global emissivity,
+    /// with the regions overriding it in order. The values of the last frame are overwritten.
+    ///
+    pub fn update(
+        &mut self,
+        width: usize,
+        height: usize,
+        emissivity: f32,
+        overrides: &[(ThermalDataRect, f32)],
+    ) {
+        self.width = width;
+        self.height = height;
+        self.values.clear();
+        self.values.resize(width * height, emissivity);
+        for (rect, emissivity) in overrides.iter() {
+            self.set_region(rect.clamped(width, height), *emissivity);
+        }
+    }
+
+    pub fn set_region(&mut self, rect: ThermalDataRect, emissivity: f32) {
+        for y in rect.min.y..=rect.max.y.min(self.height.saturating_sub(1)) {
+            for x in rect.min.x..=rect.max.x.min(self.width.saturating_sub(1)) {
+                self.values[y * self.width + x] = emissivity;
+            }
+        }
+    }
+
+    ///
+    /// Corrects every pixel with its own emissivity. Pixels with an emissivity of 1 are left as is.
+    ///
+    pub fn apply(&self, data: &mut ThermalData, reflected: Temp) {
+        if data.width != self.width || data.height != self.height {
+            tracing::warn!(
+                map_width = self.width,
+                map_height = self.height,
+                width = data.width,
+                height = data.height,
+                "Emissivity map doesn't match the image, skipping correction"
+            );
+            return;
+        }
+        for (temp, emissivity) in data.data.iter_mut().zip(self.values.iter()) {
+            if *emissivity < 1.0 {
+                *temp = correct_emissivity(*temp, *emissivity, reflected);
+            }
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    temperature::{Temp, TemperatureUnit},
+    thermal_data::ThermalData,
+};
+
+pub const MIN_AVERAGE_FRAMES: usize = 2;
+pub const MAX_AVERAGE_FRAMES: usize = 64;
+
+///
+/// Rolling average over the last frames, which reduces the temporal noise of static scenes.
+///
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TemporalAverageSettings {
+    pub enabled: bool,
+    // MIN_AVERAGE_FRAMES..=MAX_AVERAGE_FRAMES
+    pub frames: usize,
+    // The average starts over when a frame differs from it by more than this on average (in Kelvin),
+    // so moving the camera doesn't leave a trail
+    pub motion_threshold_kelvin: f32,
+}
+
+impl Default for TemporalAverageSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            frames: 8,
+            motion_threshold_kelvin: 1.0,
+        }
+    }
+}
+
+///
+/// Owned by the capturer thread, keeps the frames inside of the averaging window.
+///
+#[derive(Default)]
+pub struct TemporalAverager {
+    frames: VecDeque<Vec<Temp>>,
+    // Sum of the frames in Kelvin, in f64 so that adding and removing frames doesn't drift
+    sum: Vec<f64>,
+    resolution: (usize, usize),
+}
+
+impl TemporalAverager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn reset(&mut self) {
+        self.frames.clear();
+        self.sum.clear();
+    }
+
+    ///
+    /// Adds the frame to the window and replaces its temperatures with the average.
+    ///
+    pub fn process(&mut self, settings: &TemporalAverageSettings, data: &mut ThermalData) {
+        if !settings.enabled {
+            self.reset();
+            return;
+        }
+        let resolution = (data.width, data.height);
+        if resolution != self.resolution || self.motion_detected(settings, data) {
+            self.reset();
+            self.resolution = resolution;
+        }
+        if self.sum.is_empty() {
+            self.sum.resize(data.data.len(), 0.0);
+        }
+
+        for (sum, temp) in self.sum.iter_mut().zip(data.data.iter()) {
+            *sum += temp.to_unit(TemperatureUnit::Kelvin) as f64;
+        }
+        let max_frames = settings
+            .frames
+            .clamp(MIN_AVERAGE_FRAMES, MAX_AVERAGE_FRAMES);
+        // the buffer of the oldest frame is reused for the new one once the window is full
+        let mut oldest = None;
+        while self.frames.len() >= max_frames {
+            let frame = self.frames.pop_front().unwrap();
+            for (sum, temp) in self.sum.iter_mut().zip(frame.iter()) {
+                *sum -= temp.to_unit(TemperatureUnit::Kelvin) as f64;
+            }
+            oldest = Some(frame);
+        }
+        let mut frame = oldest.unwrap_or_default();
+        frame.clear();
+        frame.extend_from_slice(&data.data);
+        self.frames.push_back(frame);
+
+        let count = self.frames.len() as f64;
+        for (temp, sum) in data.data.iter_mut().zip(self.sum.iter()) {
+            *temp = Temp::new((sum / count) as f32);
+        }
+    }
+
+    fn motion_detected(&self, settings: &TemporalAverageSettings, data: &ThermalData) -> bool {
+        if self.frames.is_empty() || data.data.is_empty() {
+            return false;
+        }
+        let count = self.frames.len() as f64;
+        let total_difference: f64 = self
+            .sum
+            .iter()
+            .zip(data.data.iter())
+            .map(|(sum, temp)| (temp.to_unit(TemperatureUnit::Kelvin) as f64 - sum / count).abs())
+            .sum();
+        total_difference / data.data.len() as f64 > settings.motion_threshold_kelvin as f64
+    }
+}
@@ -0,0 +1,1499 @@
+use std::{
+    collections::VecDeque,
+    sync::{mpsc, Arc, Condvar, Mutex},
+    thread,
+};
+
+use anyhow::{anyhow, Error};
+#[cfg(feature = "color-image")]
+use epaint::ColorImage;
+
+use crate::{
+    auto_display_range_controller::{AutoDisplayRangeController, AutoRangeSmoothing, RangeLock},
+    bad_pixel_map::{BadPixelDetector, BadPixelMap},
+    camera_adapter::{CameraControl, CameraControlValue},
+    change_detection::{ChangeDetection, ChangeDetectionSettings, ChangeDetector},
+    denoise::DenoiseSettings,
+    flat_field_calibration::{FlatFieldAccumulator, FlatFieldCalibration, FlatFieldStep},
+    histogram_equalization::HistogramEqualization,
+    isotherm::Isotherm,
+    lens_distortion::LensDistortion,
+    temperature::{Temp, TempRange, TemperatureUnit},
+    temperature_correction::EmissivityMap,
+    temporal_average::{TemporalAverageSettings, TemporalAverager},
+    thermal_data::{
+        ThermalData, ThermalDataHistogram, ThermalDataPos, ThermalDataRect, ThermalDataStats,
+        MAX_HISTOGRAM_BUCKETS, MIN_HISTOGRAM_BUCKETS,
+    },
+    thermal_gradient::{GradientLut, THERMAL_GRADIENTS},
+    thermal_source::{PlaybackPosition, ThermalSource},
+    two_point_calibration::TwoPointCalibration,
+    types::{image_rotation::ImageRotation, mapping_mode::MappingMode},
+    upscaling::UpscaleFilter,
+};
+#[cfg(feature = "color-image")]
+use crate::{
+    isotherm::isotherm_color,
+    thermal_gradient::DELTA_GRADIENT,
+    upscaling::{Upscaler, MAX_UPSCALE_FACTOR, MIN_UPSCALE_FACTOR},
+};
+
+///
+/// A device-specific setting of the camera and the value it was last set to.
+///
+#[derive(Clone, Debug)]
+pub struct CameraControlState {
+    pub control: CameraControl,
+    // None for actions
+    pub value: Option<CameraControlValue>,
+}
+
+///
+/// A processed frame, `extension` holds what the `CapturerExtension` added to it.
+///
+pub struct ThermalCapturerResult<O> {
+    #[cfg(feature = "color-image")]
+    pub image: ColorImage,
+    // Raw temperatures the image was generated from, after rotation
+    pub thermal_data: Arc<ThermalData>,
+    pub image_range: TempRange,
+    // Set when the image was mapped with histogram equalization
+    pub equalization: Option<Arc<HistogramEqualization>>,
+    pub real_fps: f32,
+    pub reported_fps: f32,
+    pub histogram: ThermalDataHistogram,
+    // Histogram of the pixels inside ProcessingSettings::histogram_region
+    pub region_histogram: Option<ThermalDataHistogram>,
+    pub stats: ThermalDataStats,
+    // Range of the current frame, before the temporal average,
+    // equal to the range of thermal_data while averaging is off
+    pub instantaneous_range: TempRange,
+    // Coldest and hottest pixels of thermal_data
+    pub min_pos: ThermalDataPos,
+    pub max_pos: ThermalDataPos,
+    // Set while changes are highlighted or watched, after the first baseline
+    pub change_detection: Option<Arc<ChangeDetection>>,
+    pub capture_time: std::time::Instant,
+    pub has_flat_field: bool,
+    pub flat_field_has_gains: bool,
+    // Fraction of the reference frames collected while capturing a flat field
+    pub flat_field_capture_progress: Option<f32>,
+    pub flat_field_error: Option<String>,
+    // Why the last shutter calibration (FFC) failed
+    pub ffc_error: Option<String>,
+    // Device-specific settings of the camera, empty for other sources
+    pub camera_controls: Arc<Vec<CameraControlState>>,
+    // Why the last camera control couldn't be set
+    pub camera_control_error: Option<String>,
+    // Number of pixels in the bad pixel map, None if the camera has no map
+    pub bad_pixel_count: Option<usize>,
+    // Fraction of the frames collected while detecting bad pixels
+    pub bad_pixel_calibration_progress: Option<f32>,
+    // thermal_data holds the differences to the reference frame, in Kelvin
+    pub is_delta: bool,
+    pub has_delta_reference: bool,
+    // Set when playing back a recording
+    pub playback_position: Option<PlaybackPosition>,
+    // Frames of the camera which arrived late (see LATE_FRAME_INTERVALS) since the capturer started
+    pub late_frames: u64,
+    pub extension: O,
+}
+
+///
+/// Settings of the processing pipeline, from the correction of the raw frames to the colorized
+/// image. Derived from the settings of the `CapturerExtension` whenever they change.
+///
+#[derive(Clone)]
+pub struct ProcessingSettings {
+    pub auto_range: bool,
+    // Stop the auto range from adapting, keeping its last computed range
+    pub auto_range_held: bool,
+    // Percentage of the coldest and hottest pixels ignored by the auto range
+    pub auto_range_clip_percent: f32,
+    pub auto_range_smoothing: AutoRangeSmoothing,
+    // Span or level of the auto range kept fixed
+    pub auto_range_lock: RangeLock,
+    // Area the auto range is computed from, the whole image if None
+    pub auto_range_roi: Option<ThermalDataRect>,
+    pub manual_range: TempRange,
+    pub mapping_mode: MappingMode,
+    // The gradient the frames are colorized with, including anything applied to the factor
+    pub gradient_lut: GradientLut,
+    // Temperatures inside of an enabled isotherm get its color instead of the gradient
+    pub isotherms: Vec<Isotherm>,
+    pub rotation: ImageRotation,
+    // Mirroring applied after the rotation, e.g. for a camera looking through a mirror
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+
+    // Apply the flat field (non-uniformity) correction, if one has been captured
+    pub nuc_enabled: bool,
+    // Interpolate the pixels of the bad pixel map, if one has been detected
+    pub bad_pixel_correction: bool,
+    // Spatial noise reduction, before anything is measured
+    pub denoise: DenoiseSettings,
+    pub lens_distortion: LensDistortion,
+    pub temporal_average: TemporalAverageSettings,
+
+    // Area of the image for which a separate histogram is computed
+    pub histogram_region: Option<ThermalDataRect>,
+    // Number of histogram buckets (MIN_HISTOGRAM_BUCKETS..=MAX_HISTOGRAM_BUCKETS)
+    pub histogram_buckets: usize,
+
+    // Interpolation of the temperatures before colorizing
+    pub upscale_filter: UpscaleFilter,
+    // MIN_UPSCALE_FACTOR..=MAX_UPSCALE_FACTOR
+    pub upscale_factor: usize,
+    // Threads processing each frame in parallel (at most MAX_PROCESSING_THREADS), 0 uses one per core
+    pub processing_threads: usize,
+
+    // Run the shutter calibration (FFC) every so many seconds, None leaves it to the camera
+    pub auto_ffc_interval_secs: Option<u32>,
+
+    // Show the difference of each pixel to the reference frame, once one was captured
+    pub delta_mode: bool,
+    pub change_detection: ChangeDetectionSettings,
+    // Detect changes even while they aren't highlighted, e.g. for alarms on the rate of change
+    pub watch_changes: bool,
+
+    // Emissivity of the measured surface, 1.0 disables the correction
+    pub emissivity: f32,
+    // Regions of the image with an emissivity of their own, applied in order
+    pub emissivity_overrides: Vec<(ThermalDataRect, f32)>,
+    // Apparent temperature of the surroundings reflected by the surface
+    pub reflected_temperature: Temp,
+    // Linear correction of the readings, from two references of known temperature
+    pub two_point_calibration: TwoPointCalibration,
+}
+
+impl Default for ProcessingSettings {
+    fn default() -> Self {
+        Self {
+            auto_range: true,
+            auto_range_held: false,
+            auto_range_clip_percent: 0.0,
+            auto_range_smoothing: AutoRangeSmoothing::default(),
+            auto_range_lock: RangeLock::Off,
+            auto_range_roi: None,
+            manual_range: TempRange::new(Temp::from_celsius(0.0), Temp::from_celsius(50.0)),
+            mapping_mode: MappingMode::default(),
+            gradient_lut: GradientLut::from_gradient(&THERMAL_GRADIENTS[0]),
+            isotherms: vec![],
+            rotation: ImageRotation::None,
+            flip_horizontal: false,
+            flip_vertical: false,
+            nuc_enabled: true,
+            bad_pixel_correction: true,
+            denoise: DenoiseSettings::default(),
+            lens_distortion: LensDistortion::default(),
+            temporal_average: TemporalAverageSettings::default(),
+            histogram_region: None,
+            histogram_buckets: 100,
+            upscale_filter: UpscaleFilter::default(),
+            upscale_factor: 4,
+            processing_threads: 0,
+            auto_ffc_interval_secs: None,
+            delta_mode: false,
+            change_detection: ChangeDetectionSettings::default(),
+            watch_changes: false,
+            emissivity: 1.0,
+            emissivity_overrides: vec![],
+            reflected_temperature: Temp::from_celsius(20.0),
+            two_point_calibration: TwoPointCalibration::default(),
+        }
+    }
+}
+
+///
+/// A frame the pipeline is done with, handed to `CapturerExtension::process_frame`.
+///
+pub struct ProcessedFrame<'a> {
+    pub thermal_data: &'a Arc<ThermalData>,
+    // Coldest and hottest pixels of thermal_data
+    pub min_pos: ThermalDataPos,
+    pub max_pos: ThermalDataPos,
+    // Range of the current frame, before the temporal average
+    pub instantaneous_range: TempRange,
+    pub is_delta: bool,
+    pub change_detection: Option<&'a ChangeDetection>,
+    // The colorized frame, which may still be drawn onto or replaced
+    #[cfg(feature = "color-image")]
+    pub image: &'a mut ColorImage,
+}
+
+///
+/// What an application adds to the capturer, e.g. measurements, alarms and recordings.
+/// Runs on the capturer thread: it sees every frame and the results it returns travel
+/// with the frame in `ThermalCapturerResult::extension`.
+///
+pub trait CapturerExtension: Send + 'static {
+    // Settings of the application, the processing settings are derived from them
+    type Settings: Send + 'static;
+    // Added to every result
+    type Output: Send + 'static;
+    // Sent with `ThermalCapturer::send_extension_command`
+    type Command: Send + 'static;
+
+    ///
+    /// Called whenever the settings change.
+    ///
+    fn processing_settings(settings: &Self::Settings) -> ProcessingSettings;
+
+    ///
+    /// Sees every frame as it was captured, before any correction, e.g. to record it.
+    ///
+    fn raw_frame(&mut self, _settings: &Self::Settings, _thermal_data: &ThermalData) {}
+
+    ///
+    /// Called for every frame once it's processed and colorized, returns what's added to its result.
+    /// `recycled` is the output of a result the UI is done with, so its buffers can be filled again.
+    /// An error drops the frame.
+    ///
+    fn process_frame(
+        &mut self,
+        settings: &mut Self::Settings,
+        frame: ProcessedFrame<'_>,
+        recycled: Option<&mut Self::Output>,
+    ) -> Result<Self::Output, Error>;
+
+    ///
+    /// Sees the finished result before it's handed out, e.g. to encode it.
+    /// An error drops the frame.
+    ///
+    fn result_ready(
+        &mut self,
+        _settings: &Self::Settings,
+        _result: &ThermalCapturerResult<Self::Output>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn handle_command(&mut self, settings: &Self::Settings, command: Self::Command);
+
+    ///
+    /// Called when the capturer stops, to finish anything still being written.
+    ///
+    fn stop(&mut self) {}
+}
+
+///
+/// The pipeline on its own, without anything added to the frames.
+///
+impl CapturerExtension for () {
+    type Settings = ProcessingSettings;
+    type Output = ();
+    type Command = ();
+
+    fn processing_settings(settings: &ProcessingSettings) -> ProcessingSettings {
+        settings.clone()
+    }
+
+    fn process_frame(
+        &mut self,
+        _settings: &mut ProcessingSettings,
+        _frame: ProcessedFrame<'_>,
+        _recycled: Option<&mut ()>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn handle_command(&mut self, _settings: &ProcessingSettings, _command: ()) {}
+}
+
+pub type ThermalCapturerCallback = Arc<dyn Fn() + Send + Sync>;
+
+// Smallest half width of the range in delta mode, so sensor noise doesn't fill the gradient
+const MIN_DELTA_RANGE_KELVIN: f32 = 0.5;
+
+// How often a lost source is tried to be reopened
+const RECONNECT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+pub const MIN_AUTO_FFC_INTERVAL_SECS: u32 = 10;
+pub const MAX_AUTO_FFC_INTERVAL_SECS: u32 = 3600;
+
+pub const MAX_PROCESSING_THREADS: usize = 64;
+
+// Results kept for reuse: one being displayed while the next is filled is enough
+// for a steady stream of frames, more would only hold memory
+const MAX_RECYCLED_RESULTS: usize = 2;
+
+// A frame of a camera is late when it arrives this many nominal frame intervals after the previous one
+const LATE_FRAME_INTERVALS: f32 = 1.5;
+
+///
+/// Pool for the parallel passes over the frames (filters, statistics, colorization),
+/// separate from the global one so the number of threads can be changed at any time.
+/// None if the threads couldn't be started, the frames are then processed on the capturer thread.
+///
+fn build_processing_pool(threads: usize) -> Option<Arc<rayon::ThreadPool>> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads.min(MAX_PROCESSING_THREADS))
+        .thread_name(|i| format!("thermal-processing-{}", i))
+        .build()
+        .inspect_err(|err| tracing::error!(error = %err, "Failed to start the processing threads"))
+        .ok()
+        .map(Arc::new)
+}
+
+///
+/// What the capturer thread reports through its EventQueue.
+///
+pub enum ThermalCapturerEvent<O> {
+    Frame(Box<ThermalCapturerResult<O>>),
+    // Processing a frame failed, capturing goes on with the next one
+    Error(Error),
+    // Capturing failed, e.g. because the camera was unplugged, and the source is being reopened.
+    // Frames follow once it is back.
+    Disconnected(Error),
+    // The thread has exited, with the reason unless it was asked to stop
+    Stopped(Option<Error>),
+}
+
+impl<O> ThermalCapturerEvent<O> {
+    // The thread always says when it stops, unless it panicked
+    fn thread_gone() -> Self {
+        ThermalCapturerEvent::Stopped(Some(anyhow!("The capturer thread exited unexpectedly")))
+    }
+}
+
+// Events kept for the UI at most, the oldest are dropped first
+const MAX_QUEUED_EVENTS: usize = 16;
+
+///
+/// Events of the capturer thread waiting to be received. A new frame replaces the previous
+/// one if that hasn't been received yet, so a UI which can't keep up skips to the latest
+/// frame instead of falling further behind while the frames pile up in memory.
+/// The queue is bounded, e.g. for a failing camera while the window is minimized.
+///
+struct EventQueue<O> {
+    state: Mutex<EventQueueState<O>>,
+    changed: Condvar,
+}
+
+struct EventQueueState<O> {
+    events: VecDeque<ThermalCapturerEvent<O>>,
+    // Frames replaced by a newer one before the UI received them
+    skipped_frames: u64,
+    // Set once the thread is gone, the events left in the queue can still be received
+    closed: bool,
+}
+
+impl<O> EventQueue<O> {
+    fn new() -> Self {
+        Self {
+            state: Mutex::new(EventQueueState {
+                events: VecDeque::new(),
+                skipped_frames: 0,
+                closed: false,
+            }),
+            changed: Condvar::new(),
+        }
+    }
+
+    fn push(&self, event: ThermalCapturerEvent<O>) {
+        let mut state = self.state.lock().unwrap();
+        if matches!(event, ThermalCapturerEvent::Frame(_))
+            && matches!(state.events.back(), Some(ThermalCapturerEvent::Frame(_)))
+        {
+            state.events.pop_back();
+            state.skipped_frames += 1;
+        }
+        state.events.push_back(event);
+        while state.events.len() > MAX_QUEUED_EVENTS {
+            state.events.pop_front();
+        }
+        self.changed.notify_all();
+    }
+
+    fn skipped_frames(&self) -> u64 {
+        self.state.lock().unwrap().skipped_frames
+    }
+
+    fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.changed.notify_all();
+    }
+
+    ///
+    /// Returns the oldest event, waiting for one if `wait` is set.
+    /// None if there is no event and `wait` isn't set.
+    ///
+    fn pop(&self, wait: bool) -> Option<ThermalCapturerEvent<O>> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(event) = state.events.pop_front() {
+                return Some(event);
+            }
+            if state.closed {
+                return Some(ThermalCapturerEvent::thread_gone());
+            }
+            if !wait {
+                return None;
+            }
+            state = self.changed.wait(state).unwrap();
+        }
+    }
+}
+
+///
+/// The capturer thread's end of the EventQueue, closes it when the thread is gone,
+/// even if it panicked.
+///
+struct EventSender<O>(Arc<EventQueue<O>>);
+
+impl<O> EventSender<O> {
+    ///
+    /// Returns false if the ThermalCapturer has been dropped, so nobody receives the event.
+    ///
+    fn send(&self, event: ThermalCapturerEvent<O>) -> bool {
+        self.0.push(event);
+        Arc::strong_count(&self.0) > 1
+    }
+}
+
+impl<O> Drop for EventSender<O> {
+    fn drop(&mut self) {
+        self.0.close();
+    }
+}
+
+///
+/// State of the capturer thread, shown by the UI.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum ThermalCapturerStatus {
+    Running,
+    // See ThermalCapturerEvent::Disconnected
+    Reconnecting { error: String },
+}
+
+enum ThermalCapturerCmd<E: CapturerExtension> {
+    SetSettings(E::Settings),
+    CaptureFlatField(FlatFieldStep),
+    ClearFlatField,
+    DetectBadPixels,
+    ClearBadPixels,
+    TriggerFFC,
+    SetCameraControl(&'static str, CameraControlValue),
+    CaptureDeltaReference,
+    ClearDeltaReference,
+    RecycleResult(Box<ThermalCapturerResult<E::Output>>),
+    SeekPlayback(usize),
+    SetPlaybackPaused(bool),
+    Extension(E::Command),
+    Stop,
+}
+
+struct ThermalCapturerCtx<E: CapturerExtension> {
+    source: Box<dyn ThermalSource>,
+    callback: ThermalCapturerCallback,
+    cmd_receiver: mpsc::Receiver<ThermalCapturerCmd<E>>,
+    event_sender: EventSender<E::Output>,
+    extension: E,
+    settings: E::Settings,
+    // Derived from `settings` whenever they change, instead of for every frame
+    processing: ProcessingSettings,
+    auto_range_controller: AutoDisplayRangeController,
+    last_frame_time: std::time::Instant,
+    // When the last frame of a camera was captured, None after (re)connecting
+    last_camera_frame_time: Option<std::time::Instant>,
+    late_frames: u64,
+
+    flat_field: Option<FlatFieldCalibration>,
+    // Sensor resolution the calibrations were loaded for
+    calibration_resolution: Option<(usize, usize)>,
+    // Set while the frames of a reference are collected
+    flat_field_accumulator: Option<FlatFieldAccumulator>,
+    // Why the last flat field capture failed
+    flat_field_error: Option<String>,
+    bad_pixel_map: Option<BadPixelMap>,
+    // Set while bad pixels are being detected
+    bad_pixel_detector: Option<BadPixelDetector>,
+
+    last_ffc_time: std::time::Instant,
+    // Why the last shutter calibration failed, pauses the automatic one
+    ffc_error: Option<String>,
+    // Listed by the source once it's open, with the values set since
+    camera_controls: Arc<Vec<CameraControlState>>,
+    camera_control_error: Option<String>,
+
+    // Corrected frame which delta mode subtracts
+    delta_reference: Option<ThermalData>,
+    // Set until the next frame is stored as the reference
+    delta_reference_pending: bool,
+    // Whether the last frame was a delta frame, the temporal average restarts on changes
+    delta_active: bool,
+    #[cfg(feature = "color-image")]
+    delta_gradient_lut: GradientLut,
+    processing_pool: Option<Arc<rayon::ThreadPool>>,
+    // Results handed back by the UI, reused for the next frames
+    recycled_results: Vec<Box<ThermalCapturerResult<E::Output>>>,
+    temporal_averager: TemporalAverager,
+    change_detector: ChangeDetector,
+
+    // Buffers of the processing steps, kept so the frames don't allocate
+    emissivity_map: EmissivityMap,
+    // Temperatures of the filters, swapped with those of the frame
+    filter_scratch: Vec<Temp>,
+    // Temperatures sorted for the statistics
+    stats_scratch: Vec<f32>,
+    #[cfg(feature = "color-image")]
+    upscaler: Upscaler,
+}
+
+///
+/// ThermalCapturer runs in a background thread continuously capturing frames from a source,
+/// processing them and calling the callback once a result can be received.
+/// What the application adds to the frames is done by the `CapturerExtension`.
+///
+pub struct ThermalCapturer<E: CapturerExtension> {
+    ctx: Option<ThermalCapturerCtx<E>>,
+    cmd_sender: mpsc::Sender<ThermalCapturerCmd<E>>,
+
+    events: Arc<EventQueue<E::Output>>,
+    // Updated from the received events
+    status: ThermalCapturerStatus,
+    // Frames whose processing failed
+    failed_frames: u64,
+}
+
+impl<E: CapturerExtension + Default> ThermalCapturer<E> {
+    pub fn new(
+        source: Box<dyn ThermalSource>,
+        default_settings: E::Settings,
+        callback: ThermalCapturerCallback,
+    ) -> Self {
+        Self::with_extension(source, E::default(), default_settings, callback)
+    }
+}
+
+impl<E: CapturerExtension> ThermalCapturer<E> {
+    pub fn with_extension(
+        source: Box<dyn ThermalSource>,
+        extension: E,
+        default_settings: E::Settings,
+        callback: ThermalCapturerCallback,
+    ) -> Self {
+        let (cmd_sender, cmd_receiver) = mpsc::channel();
+        let events = Arc::new(EventQueue::new());
+        let processing = E::processing_settings(&default_settings);
+        Self {
+            ctx: Some(ThermalCapturerCtx {
+                source,
+                callback,
+                cmd_receiver,
+                event_sender: EventSender(events.clone()),
+                extension,
+                settings: default_settings,
+                #[cfg(feature = "color-image")]
+                delta_gradient_lut: GradientLut::from_gradient(&DELTA_GRADIENT),
+                processing_pool: build_processing_pool(processing.processing_threads),
+                recycled_results: vec![],
+                processing,
+                auto_range_controller: AutoDisplayRangeController::new(),
+                last_frame_time: std::time::Instant::now(),
+                last_camera_frame_time: None,
+                late_frames: 0,
+                flat_field: None,
+                calibration_resolution: None,
+                flat_field_accumulator: None,
+                flat_field_error: None,
+                bad_pixel_map: None,
+                bad_pixel_detector: None,
+                last_ffc_time: std::time::Instant::now(),
+                ffc_error: None,
+                camera_controls: Arc::default(),
+                camera_control_error: None,
+                delta_reference: None,
+                delta_reference_pending: false,
+                delta_active: false,
+                temporal_averager: TemporalAverager::new(),
+                change_detector: ChangeDetector::new(),
+                emissivity_map: EmissivityMap::default(),
+                filter_scratch: vec![],
+                stats_scratch: vec![],
+                #[cfg(feature = "color-image")]
+                upscaler: Upscaler::default(),
+            }),
+            cmd_sender,
+            events,
+            status: ThermalCapturerStatus::Running,
+            failed_frames: 0,
+        }
+    }
+
+    //
+    pub fn start(&mut self) {
+        // move the source out of self so we can use it into the thread
+        let mut ctx = self.ctx.take().unwrap();
+        thread::spawn(move || {
+            // the messages of the thread, and of the source it drives, tell which camera they're about
+            let _span = tracing::info_span!("capturer", camera = ctx.source.camera_id().as_deref())
+                .entered();
+            if let Err(err) = ctx.source.open() {
+                tracing::error!(error = %format!("{:#}", err), "Failed to open the thermal source");
+                ctx.event_sender
+                    .send(ThermalCapturerEvent::Stopped(Some(err)));
+                (ctx.callback)();
+                return;
+            }
+            ctx.camera_controls = Arc::new(
+                ctx.source
+                    .controls()
+                    .into_iter()
+                    .map(|control| CameraControlState {
+                        value: control.default_value(),
+                        control,
+                    })
+                    .collect(),
+            );
+
+            fn capture_frame<E: CapturerExtension>(
+                ctx: &mut ThermalCapturerCtx<E>,
+            ) -> Result<ThermalData, Error> {
+                ctx.last_frame_time = std::time::Instant::now();
+
+                if let Some(interval) = ctx.processing.auto_ffc_interval_secs {
+                    if ctx.ffc_error.is_none()
+                        && ctx.last_ffc_time.elapsed()
+                            >= std::time::Duration::from_secs(interval as u64)
+                    {
+                        trigger_ffc(ctx);
+                    }
+                }
+
+                let thermal_data = ctx.source.capture()?;
+                // recordings and snapshots are read at their own pace
+                if ctx.source.camera_id().is_some() {
+                    let now = std::time::Instant::now();
+                    if let Some(last) = ctx.last_camera_frame_time {
+                        let interval = now.duration_since(last).as_secs_f32();
+                        if interval * ctx.source.frame_rate() > LATE_FRAME_INTERVALS {
+                            ctx.late_frames += 1;
+                        }
+                    }
+                    ctx.last_camera_frame_time = Some(now);
+                }
+                Ok(thermal_data)
+            }
+
+            fn trigger_ffc<E: CapturerExtension>(ctx: &mut ThermalCapturerCtx<E>) {
+                ctx.last_ffc_time = std::time::Instant::now();
+                match ctx.source.trigger_ffc() {
+                    Ok(()) => {
+                        ctx.ffc_error = None;
+                        // the frames before the calibration would smear into the new ones
+                        ctx.temporal_averager.reset();
+                    }
+                    Err(err) => {
+                        tracing::error!(error = %err, "Failed to trigger the shutter calibration");
+                        ctx.ffc_error = Some(err.to_string());
+                    }
+                }
+            }
+
+            fn set_camera_control<E: CapturerExtension>(
+                ctx: &mut ThermalCapturerCtx<E>,
+                id: &'static str,
+                value: CameraControlValue,
+            ) {
+                match ctx.source.set_control(id, value) {
+                    Ok(()) => {
+                        ctx.camera_control_error = None;
+                        // e.g. the gain changed, so the smoothed range is meaningless now
+                        ctx.auto_range_controller = AutoDisplayRangeController::new();
+                        if let Some(state) = Arc::make_mut(&mut ctx.camera_controls)
+                            .iter_mut()
+                            .find(|state| state.control.id == id)
+                        {
+                            // actions have no value to remember
+                            if state.value.is_some() {
+                                state.value = Some(value);
+                            }
+                        }
+                    }
+                    Err(err) => {
+                        tracing::error!(control = id, ?value, error = %err, "Failed to set the camera control");
+                        ctx.camera_control_error = Some(err.to_string());
+                    }
+                }
+            }
+
+            fn produce_result<E: CapturerExtension>(
+                ctx: &mut ThermalCapturerCtx<E>,
+                mut thermal_data: ThermalData,
+            ) -> Result<Box<ThermalCapturerResult<E::Output>>, Error> {
+                ctx.extension.raw_frame(&ctx.settings, &thermal_data);
+
+                let resolution = (thermal_data.width, thermal_data.height);
+                if ctx.calibration_resolution != Some(resolution) {
+                    // e.g. a range of 0 K means the frames aren't decoded right
+                    let (min_pos, max_pos) = thermal_data.get_min_max_pos();
+                    tracing::info!(
+                        width = resolution.0,
+                        height = resolution.1,
+                        fps = ctx.source.frame_rate(),
+                        min = ?thermal_data.temperature_at(min_pos.x, min_pos.y),
+                        max = ?thermal_data.temperature_at(max_pos.x, max_pos.y),
+                        "Receiving frames"
+                    );
+                    ctx.calibration_resolution = Some(resolution);
+                    let camera_id = ctx.source.camera_id();
+                    ctx.flat_field = FlatFieldCalibration::load(
+                        camera_id.as_deref(),
+                        resolution.0,
+                        resolution.1,
+                    )
+                    .inspect_err(|err| {
+                        tracing::error!(error = %err, "Failed to load flat field calibration")
+                    })
+                    .ok()
+                    .flatten();
+                    ctx.bad_pixel_map = camera_id.and_then(|camera_id| {
+                        BadPixelMap::load(&camera_id, resolution.0, resolution.1)
+                            .inspect_err(
+                                |err| tracing::error!(error = %err, "Failed to load bad pixel map"),
+                            )
+                            .ok()
+                            .flatten()
+                    });
+                }
+                // detected on the raw frames, the map is applied before any other correction
+                if let Some(detector) = ctx.bad_pixel_detector.as_mut() {
+                    detector.add_frame(&thermal_data);
+                    if detector.is_done() {
+                        let map = detector.finish();
+                        tracing::info!(count = map.len(), "Detected bad pixels");
+                        if let Some(camera_id) = ctx.source.camera_id() {
+                            if let Err(err) = map.save(&camera_id) {
+                                tracing::error!(error = %err, "Failed to save bad pixel map");
+                            }
+                        }
+                        ctx.bad_pixel_map = Some(map);
+                        ctx.bad_pixel_detector = None;
+                    }
+                }
+                if ctx.processing.bad_pixel_correction {
+                    if let Some(map) = ctx.bad_pixel_map.as_ref() {
+                        map.apply(&mut thermal_data);
+                    }
+                }
+                if let Some(accumulator) = ctx.flat_field_accumulator.as_mut() {
+                    accumulator.add_frame(&thermal_data);
+                    if accumulator.is_done() {
+                        match accumulator.finish(ctx.flat_field.as_ref()) {
+                            Ok(calibration) => {
+                                if let Err(err) =
+                                    calibration.save(ctx.source.camera_id().as_deref())
+                                {
+                                    tracing::error!(error = %err, "Failed to save flat field calibration");
+                                }
+                                ctx.flat_field = Some(calibration);
+                            }
+                            Err(err) => {
+                                tracing::error!(error = %err, "Failed to compute flat field calibration");
+                                ctx.flat_field_error = Some(err.to_string());
+                            }
+                        }
+                        ctx.flat_field_accumulator = None;
+                    }
+                }
+                if ctx.processing.nuc_enabled {
+                    if let Some(flat_field) = ctx.flat_field.as_ref() {
+                        flat_field.apply(&mut thermal_data);
+                    }
+                }
+                ctx.processing
+                    .denoise
+                    .apply(&mut thermal_data, &mut ctx.filter_scratch);
+                // in sensor coordinates, before the image is turned
+                ctx.processing
+                    .lens_distortion
+                    .apply(&mut thermal_data, &mut ctx.filter_scratch);
+
+                // a result the UI is done with, its box and buffers are filled again
+                let mut recycled = ctx.recycled_results.pop();
+
+                // corrected after rotating, the regions are placed on the displayed image
+                let mut oriented = recycled
+                    .as_mut()
+                    .and_then(|result| Arc::get_mut(&mut result.thermal_data))
+                    .map(std::mem::take)
+                    .unwrap_or_default();
+                thermal_data.oriented_into(
+                    ctx.processing.rotation,
+                    ctx.processing.flip_horizontal,
+                    ctx.processing.flip_vertical,
+                    &mut oriented,
+                );
+                let mut thermal_data = oriented;
+                ctx.emissivity_map.update(
+                    thermal_data.width,
+                    thermal_data.height,
+                    ctx.processing.emissivity,
+                    &ctx.processing.emissivity_overrides,
+                );
+                ctx.emissivity_map
+                    .apply(&mut thermal_data, ctx.processing.reflected_temperature);
+                ctx.processing
+                    .two_point_calibration
+                    .apply(&mut thermal_data);
+
+                if std::mem::take(&mut ctx.delta_reference_pending) {
+                    ctx.delta_reference = Some(thermal_data.clone());
+                }
+                let delta_reference = ctx.delta_reference.as_ref().filter(|reference| {
+                    ctx.processing.delta_mode
+                        && reference.width == thermal_data.width
+                        && reference.height == thermal_data.height
+                });
+                let is_delta = delta_reference.is_some();
+                if let Some(reference) = delta_reference {
+                    for (value, reference) in
+                        thermal_data.data.iter_mut().zip(reference.data.iter())
+                    {
+                        *value = Temp::from_delta(value.delta_from(*reference));
+                    }
+                }
+                if is_delta != ctx.delta_active {
+                    ctx.delta_active = is_delta;
+                    // temperatures and differences must not be averaged or compared together
+                    ctx.temporal_averager.reset();
+                    ctx.change_detector.reset();
+                }
+
+                let (mintemp_pos, maxtemp_pos) = thermal_data.get_min_max_pos();
+                let instantaneous_range = TempRange::new(
+                    thermal_data.temperature_at(mintemp_pos.x, mintemp_pos.y),
+                    thermal_data.temperature_at(maxtemp_pos.x, maxtemp_pos.y),
+                );
+                ctx.temporal_averager
+                    .process(&ctx.processing.temporal_average, &mut thermal_data);
+
+                let thermal_data = Arc::new(thermal_data);
+                let capture_time = std::time::Instant::now();
+
+                // only while needed, it keeps copies of the frames of the last baseline
+                let change_detection =
+                    if ctx.processing.change_detection.highlight || ctx.processing.watch_changes {
+                        ctx.change_detector
+                            .process(
+                                &ctx.processing.change_detection,
+                                &thermal_data,
+                                capture_time,
+                            )
+                            .map(Arc::new)
+                    } else {
+                        ctx.change_detector.reset();
+                        None
+                    };
+
+                let (mintemp_pos, maxtemp_pos) = thermal_data.get_min_max_pos();
+
+                let captured_range = TempRange::new(
+                    thermal_data.temperature_at(mintemp_pos.x, mintemp_pos.y),
+                    thermal_data.temperature_at(maxtemp_pos.x, maxtemp_pos.y),
+                );
+
+                let stats = thermal_data.stats_with(&mut ctx.stats_scratch);
+
+                ctx.auto_range_controller
+                    .set_held(ctx.processing.auto_range_held);
+                ctx.auto_range_controller
+                    .set_clip_percent(ctx.processing.auto_range_clip_percent);
+                ctx.auto_range_controller
+                    .set_smoothing(ctx.processing.auto_range_smoothing);
+                ctx.auto_range_controller
+                    .set_lock(ctx.processing.auto_range_lock);
+                let mut mapping_range = ctx.auto_range_controller.compute_from_data(
+                    &thermal_data,
+                    &stats,
+                    ctx.processing.auto_range_roi,
+                );
+
+                if is_delta {
+                    // centered at zero, so the diverging gradient is white where nothing changed
+                    let max_abs = mapping_range
+                        .min
+                        .to_unit(TemperatureUnit::Kelvin)
+                        .abs()
+                        .max(mapping_range.max.to_unit(TemperatureUnit::Kelvin).abs())
+                        .max(MIN_DELTA_RANGE_KELVIN);
+                    mapping_range = TempRange::new(Temp::new(-max_abs), Temp::new(max_abs));
+                } else if !ctx.processing.auto_range {
+                    mapping_range = ctx.processing.manual_range;
+                }
+
+                let equalization = (ctx.processing.mapping_mode
+                    == MappingMode::HistogramEqualization)
+                    .then(|| {
+                        Arc::new(HistogramEqualization::from_thermal_data(
+                            &thermal_data,
+                            mapping_range,
+                        ))
+                    });
+                #[cfg(feature = "color-image")]
+                let mut image = colorize(
+                    ctx,
+                    &thermal_data,
+                    mapping_range,
+                    equalization.as_deref(),
+                    is_delta,
+                    recycled.as_mut(),
+                );
+
+                let extension = ctx.extension.process_frame(
+                    &mut ctx.settings,
+                    ProcessedFrame {
+                        thermal_data: &thermal_data,
+                        min_pos: mintemp_pos,
+                        max_pos: maxtemp_pos,
+                        instantaneous_range,
+                        is_delta,
+                        change_detection: change_detection.as_deref(),
+                        #[cfg(feature = "color-image")]
+                        image: &mut image,
+                    },
+                    recycled.as_mut().map(|result| &mut result.extension),
+                )?;
+
+                let histogram_range = captured_range.join(mapping_range);
+                let histogram_buckets = ctx
+                    .processing
+                    .histogram_buckets
+                    .clamp(MIN_HISTOGRAM_BUCKETS, MAX_HISTOGRAM_BUCKETS);
+                let mut histogram = recycled
+                    .as_mut()
+                    .map(|result| std::mem::take(&mut result.histogram))
+                    .unwrap_or_default();
+                histogram.update_from_thermal_data(
+                    &thermal_data,
+                    histogram_range,
+                    histogram_buckets,
+                );
+                let region_histogram = ctx.processing.histogram_region.map(|rect| {
+                    let mut histogram = recycled
+                        .as_mut()
+                        .and_then(|result| result.region_histogram.take())
+                        .unwrap_or_default();
+                    histogram.update_from_thermal_data_region(
+                        &thermal_data,
+                        rect,
+                        histogram_range,
+                        histogram_buckets,
+                    );
+                    histogram
+                });
+                let result = ThermalCapturerResult {
+                    #[cfg(feature = "color-image")]
+                    image,
+                    thermal_data: thermal_data.clone(),
+                    real_fps: 1.0 / ctx.last_frame_time.elapsed().as_secs_f32(),
+                    reported_fps: ctx.source.frame_rate(),
+                    image_range: mapping_range,
+                    equalization,
+                    histogram,
+                    region_histogram,
+                    stats,
+                    instantaneous_range,
+                    min_pos: mintemp_pos,
+                    max_pos: maxtemp_pos,
+                    change_detection,
+                    capture_time,
+                    has_flat_field: ctx.flat_field.is_some(),
+                    flat_field_has_gains: ctx
+                        .flat_field
+                        .as_ref()
+                        .is_some_and(|flat_field| flat_field.has_gains()),
+                    flat_field_capture_progress: ctx
+                        .flat_field_accumulator
+                        .as_ref()
+                        .map(|accumulator| accumulator.progress()),
+                    flat_field_error: ctx.flat_field_error.clone(),
+                    ffc_error: ctx.ffc_error.clone(),
+                    camera_controls: ctx.camera_controls.clone(),
+                    camera_control_error: ctx.camera_control_error.clone(),
+                    bad_pixel_count: ctx.bad_pixel_map.as_ref().map(|map| map.len()),
+                    bad_pixel_calibration_progress: ctx
+                        .bad_pixel_detector
+                        .as_ref()
+                        .map(|detector| detector.progress()),
+                    is_delta,
+                    has_delta_reference: ctx.delta_reference.is_some(),
+                    playback_position: ctx.source.playback_position(),
+                    late_frames: ctx.late_frames,
+                    extension,
+                };
+                let result = match recycled {
+                    Some(mut boxed) => {
+                        *boxed = result;
+                        boxed
+                    }
+                    None => Box::new(result),
+                };
+
+                ctx.extension.result_ready(&ctx.settings, &result)?;
+
+                Ok(result)
+            }
+
+            ///
+            /// Colorizes the frame, into the pixels of `recycled` if there is one.
+            ///
+            #[cfg(feature = "color-image")]
+            fn colorize<E: CapturerExtension>(
+                ctx: &mut ThermalCapturerCtx<E>,
+                thermal_data: &ThermalData,
+                mapping_range: TempRange,
+                equalization: Option<&HistogramEqualization>,
+                is_delta: bool,
+                recycled: Option<&mut Box<ThermalCapturerResult<E::Output>>>,
+            ) -> ColorImage {
+                // only the image is upscaled, the measurements use the sensor pixels
+                let upscaled = ctx.upscaler.upscale(
+                    thermal_data,
+                    ctx.processing.upscale_filter,
+                    ctx.processing
+                        .upscale_factor
+                        .clamp(MIN_UPSCALE_FACTOR, MAX_UPSCALE_FACTOR),
+                );
+                let mut image = recycled
+                    .map(|result| std::mem::take(&mut result.image))
+                    .unwrap_or_default();
+                let processing = &ctx.processing;
+                // same colors as `value_to_color` of the app's settings, from the tables
+                if is_delta {
+                    upscaled.map_range_to_image_into(
+                        &mut image,
+                        mapping_range,
+                        &ctx.delta_gradient_lut,
+                    );
+                } else if equalization.is_none()
+                    && !processing.isotherms.iter().any(|isotherm| isotherm.enabled)
+                {
+                    upscaled.map_range_to_image_into(
+                        &mut image,
+                        mapping_range,
+                        &processing.gradient_lut,
+                    );
+                } else {
+                    upscaled.map_to_image_into(&mut image, |t| {
+                        if let Some(color) = isotherm_color(&processing.isotherms, t) {
+                            return color;
+                        }
+                        let factor = match equalization {
+                            Some(equalization) => equalization.factor(t),
+                            None => mapping_range.factor(t),
+                        };
+                        processing.gradient_lut.color(factor)
+                    });
+                }
+                image
+            }
+
+            ///
+            /// Drains the command queue, returns true if the capturer was stopped.
+            ///
+            fn handle_commands<E: CapturerExtension>(ctx: &mut ThermalCapturerCtx<E>) -> bool {
+                while let Ok(cmd) = ctx.cmd_receiver.try_recv() {
+                    match cmd {
+                        ThermalCapturerCmd::Stop => return true,
+                        ThermalCapturerCmd::SetSettings(settings) => {
+                            let processing = E::processing_settings(&settings);
+                            if processing.processing_threads != ctx.processing.processing_threads {
+                                ctx.processing_pool =
+                                    build_processing_pool(processing.processing_threads);
+                            }
+                            ctx.processing = processing;
+                            ctx.settings = settings;
+                        }
+                        ThermalCapturerCmd::CaptureDeltaReference => {
+                            ctx.delta_reference_pending = true;
+                        }
+                        ThermalCapturerCmd::ClearDeltaReference => {
+                            ctx.delta_reference = None;
+                        }
+                        ThermalCapturerCmd::RecycleResult(result) => {
+                            if ctx.recycled_results.len() < MAX_RECYCLED_RESULTS {
+                                ctx.recycled_results.push(result);
+                            }
+                        }
+                        ThermalCapturerCmd::SeekPlayback(frame_index) => {
+                            ctx.source.seek(frame_index);
+                        }
+                        ThermalCapturerCmd::SetPlaybackPaused(paused) => {
+                            ctx.source.set_playback_paused(paused);
+                        }
+                        ThermalCapturerCmd::CaptureFlatField(step) => {
+                            ctx.flat_field_error = None;
+                            ctx.flat_field_accumulator =
+                                ctx.calibration_resolution.map(|(width, height)| {
+                                    FlatFieldAccumulator::new(step, width, height)
+                                });
+                        }
+                        ThermalCapturerCmd::ClearFlatField => {
+                            ctx.flat_field = None;
+                            if let Some((width, height)) = ctx.calibration_resolution {
+                                if let Err(err) = FlatFieldCalibration::remove(
+                                    ctx.source.camera_id().as_deref(),
+                                    width,
+                                    height,
+                                ) {
+                                    tracing::error!(error = %err, "Failed to remove flat field calibration");
+                                }
+                            }
+                        }
+                        ThermalCapturerCmd::TriggerFFC => trigger_ffc(ctx),
+                        ThermalCapturerCmd::SetCameraControl(id, value) => {
+                            set_camera_control(ctx, id, value)
+                        }
+                        ThermalCapturerCmd::DetectBadPixels => {
+                            ctx.bad_pixel_detector = ctx
+                                .calibration_resolution
+                                .map(|(width, height)| BadPixelDetector::new(width, height));
+                        }
+                        ThermalCapturerCmd::ClearBadPixels => {
+                            ctx.bad_pixel_map = None;
+                            if let (Some((width, height)), Some(camera_id)) =
+                                (ctx.calibration_resolution, ctx.source.camera_id())
+                            {
+                                if let Err(err) = BadPixelMap::remove(&camera_id, width, height) {
+                                    tracing::error!(error = %err, "Failed to remove bad pixel map");
+                                }
+                            }
+                        }
+                        ThermalCapturerCmd::Extension(command) => {
+                            ctx.extension.handle_command(&ctx.settings, command);
+                        }
+                    }
+                }
+                false
+            }
+
+            ///
+            /// Waits for the source to come back after a failed capture,
+            /// handling the commands meanwhile. Returns false if the capturer was stopped.
+            ///
+            fn reconnect<E: CapturerExtension>(
+                ctx: &mut ThermalCapturerCtx<E>,
+                error: Error,
+            ) -> bool {
+                tracing::warn!(error = %format!("{:#}", error), "Thermal source failed, reconnecting");
+                let _span = tracing::info_span!("reconnect").entered();
+                ctx.event_sender
+                    .send(ThermalCapturerEvent::Disconnected(error));
+                (ctx.callback)();
+                loop {
+                    thread::sleep(RECONNECT_INTERVAL);
+                    if handle_commands(ctx) {
+                        return false;
+                    }
+                    match ctx.source.reconnect() {
+                        Ok(()) => {
+                            tracing::info!("Thermal source reconnected");
+                            ctx.last_camera_frame_time = None;
+                            ctx.temporal_averager.reset();
+                            // the camera starts up with the default controls too, set the changed ones again
+                            let changed: Vec<(&'static str, CameraControlValue)> = ctx
+                                .camera_controls
+                                .iter()
+                                .filter(|state| state.value != state.control.default_value())
+                                .filter_map(|state| Some((state.control.id, state.value?)))
+                                .collect();
+                            for (id, value) in changed {
+                                set_camera_control(ctx, id, value);
+                            }
+                            return true;
+                        }
+                        Err(err) => tracing::debug!(error = %err, "Reconnecting failed"),
+                    }
+                }
+            }
+
+            // None when asked to stop
+            let stop_reason = loop {
+                let event = match capture_frame(&mut ctx) {
+                    Ok(thermal_data) => {
+                        let span = tracing::debug_span!(
+                            "frame",
+                            width = thermal_data.width,
+                            height = thermal_data.height
+                        );
+                        // the parallel passes of the processing run on the pool it's installed in,
+                        // the span is entered on the thread of the pool running it
+                        let result = match ctx.processing_pool.clone() {
+                            Some(pool) => pool.install(|| {
+                                span.in_scope(|| produce_result(&mut ctx, thermal_data))
+                            }),
+                            None => span.in_scope(|| produce_result(&mut ctx, thermal_data)),
+                        };
+                        match result {
+                            Ok(result) => ThermalCapturerEvent::Frame(result),
+                            Err(err) => {
+                                tracing::error!(error = %format!("{:#}", err), "Failed to process frame");
+                                ThermalCapturerEvent::Error(err)
+                            }
+                        }
+                    }
+                    Err(err) if ctx.source.can_reconnect() => {
+                        if !reconnect(&mut ctx, err) {
+                            break None;
+                        }
+                        continue;
+                    }
+                    Err(err) => break Some(err),
+                };
+                if !ctx.event_sender.send(event) {
+                    // the capturer was dropped without stopping the thread
+                    break None;
+                }
+
+                (ctx.callback)();
+
+                if handle_commands(&mut ctx) {
+                    break None;
+                }
+            };
+
+            ctx.extension.stop();
+            if let Err(err) = ctx.source.close() {
+                tracing::error!(error = %err, "Failed to close the thermal source");
+            }
+            match stop_reason.as_ref() {
+                Some(err) => tracing::error!(error = %format!("{:#}", err), "Capturing stopped"),
+                None => tracing::info!("Capturing stopped"),
+            }
+            ctx.event_sender
+                .send(ThermalCapturerEvent::Stopped(stop_reason));
+            (ctx.callback)();
+        });
+    }
+
+    ///
+    /// Returns the next event of the capturer thread without blocking.
+    ///
+    pub fn try_recv_event(&mut self) -> Option<ThermalCapturerEvent<E::Output>> {
+        let event = self.events.pop(false)?;
+        self.update_status(&event);
+        Some(event)
+    }
+
+    ///
+    /// Blocks until the capturer thread reports the next event.
+    ///
+    pub fn recv_event(&mut self) -> ThermalCapturerEvent<E::Output> {
+        // only None when not waiting
+        let event = self.events.pop(true).unwrap();
+        self.update_status(&event);
+        event
+    }
+
+    fn update_status(&mut self, event: &ThermalCapturerEvent<E::Output>) {
+        match event {
+            ThermalCapturerEvent::Frame(_) => self.status = ThermalCapturerStatus::Running,
+            ThermalCapturerEvent::Disconnected(err) => {
+                self.status = ThermalCapturerStatus::Reconnecting {
+                    error: err.to_string(),
+                }
+            }
+            ThermalCapturerEvent::Error(_) => self.failed_frames += 1,
+            ThermalCapturerEvent::Stopped(_) => {}
+        }
+    }
+
+    ///
+    /// Frames which never reached the UI: skipped because the UI was behind, or failed to process.
+    ///
+    pub fn dropped_frames(&self) -> u64 {
+        self.events.skipped_frames() + self.failed_frames
+    }
+
+    ///
+    /// Latest state reported by the capturer thread.
+    ///
+    pub fn status(&self) -> &ThermalCapturerStatus {
+        &self.status
+    }
+
+    fn send_cmd(&self, cmd: ThermalCapturerCmd<E>) {
+        // the thread has already reported why it stopped
+        if self.cmd_sender.send(cmd).is_err() {
+            tracing::warn!("Ignoring a command, the capturer thread has stopped");
+        }
+    }
+
+    pub fn set_settings(&mut self, settings: E::Settings) {
+        self.send_cmd(ThermalCapturerCmd::SetSettings(settings));
+    }
+
+    ///
+    /// Hands a command to the `CapturerExtension`, handled between frames.
+    ///
+    pub fn send_extension_command(&mut self, command: E::Command) {
+        self.send_cmd(ThermalCapturerCmd::Extension(command));
+    }
+
+    ///
+    /// Average the next FLAT_FIELD_FRAMES frames as a flat field reference.
+    /// The camera should be pointed at a surface of uniform temperature.
+    ///
+    pub fn capture_flat_field(&mut self, step: FlatFieldStep) {
+        self.send_cmd(ThermalCapturerCmd::CaptureFlatField(step));
+    }
+
+    ///
+    /// Store the next frame as the reference which delta mode subtracts.
+    ///
+    pub fn capture_delta_reference(&mut self) {
+        self.send_cmd(ThermalCapturerCmd::CaptureDeltaReference);
+    }
+
+    pub fn clear_delta_reference(&mut self) {
+        self.send_cmd(ThermalCapturerCmd::ClearDeltaReference);
+    }
+
+    ///
+    /// Hands back a result which is no longer displayed, so its buffers are reused
+    /// for a later frame instead of allocating new ones.
+    ///
+    pub fn recycle_result(&mut self, result: Box<ThermalCapturerResult<E::Output>>) {
+        self.send_cmd(ThermalCapturerCmd::RecycleResult(result));
+    }
+
+    pub fn seek_playback(&mut self, frame_index: usize) {
+        self.send_cmd(ThermalCapturerCmd::SeekPlayback(frame_index));
+    }
+
+    pub fn set_playback_paused(&mut self, paused: bool) {
+        self.send_cmd(ThermalCapturerCmd::SetPlaybackPaused(paused));
+    }
+
+    pub fn clear_flat_field(&mut self) {
+        self.send_cmd(ThermalCapturerCmd::ClearFlatField);
+    }
+
+    ///
+    /// Look for hot, dead and stuck pixels over the next BAD_PIXEL_CALIBRATION_FRAMES frames.
+    /// The camera should see a scene without small hot or cold objects.
+    ///
+    pub fn detect_bad_pixels(&mut self) {
+        self.send_cmd(ThermalCapturerCmd::DetectBadPixels);
+    }
+
+    pub fn clear_bad_pixels(&mut self) {
+        self.send_cmd(ThermalCapturerCmd::ClearBadPixels);
+    }
+
+    ///
+    /// Run the flat-field correction of the camera now, e.g. after the image has drifted.
+    ///
+    pub fn trigger_ffc(&mut self) {
+        self.send_cmd(ThermalCapturerCmd::TriggerFFC);
+    }
+
+    ///
+    /// Set one of the controls in `ThermalCapturerResult::camera_controls`.
+    ///
+    pub fn set_camera_control(&mut self, id: &'static str, value: CameraControlValue) {
+        self.send_cmd(ThermalCapturerCmd::SetCameraControl(id, value));
+    }
+}
+
+impl<E: CapturerExtension> Drop for ThermalCapturer<E> {
+    fn drop(&mut self) {
+        // fails if the thread has stopped on its own already
+        let _ = self.cmd_sender.send(ThermalCapturerCmd::Stop);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nokhwa::utils::CameraIndex;
+
+    use super::*;
+    use crate::camera_adapter::{
+        synthetic::{Background, HotSpot, NoiseModel, SyntheticCameraAdapter, SyntheticScene},
+        CameraAdapter, CameraStream,
+    };
+
+    // Captures from a camera stream, like the camera source of the app
+    struct StreamSource(Box<dyn CameraStream>);
+
+    impl ThermalSource for StreamSource {
+        fn open(&mut self) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn close(&mut self) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn capture(&mut self) -> anyhow::Result<ThermalData> {
+            self.0.next_frame()
+        }
+
+        fn frame_rate(&self) -> f32 {
+            self.0.frame_rate()
+        }
+    }
+
+    fn assert_celsius(temp: Temp, expected: f32) {
+        let actual = temp.to_unit(TemperatureUnit::Celsius);
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "expected {} °C, got {} °C",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn synthetic_frame_comes_through() {
+        // a still hot spot on a uniform background, without noise
+        let adapter = SyntheticCameraAdapter {
+            scene: SyntheticScene {
+                resolution: (32, 24),
+                frame_rate: 100.0,
+                background: Background::Uniform(Temp::from_celsius(20.0)),
+                hot_spots: vec![HotSpot {
+                    temperature: Temp::from_celsius(60.0),
+                    radius: 4.0,
+                    center: (0.25, 0.5),
+                    orbit: (0.0, 0.0),
+                    period_secs: 0.0,
+                }],
+                noise: NoiseModel::None,
+                seed: 1,
+            },
+        };
+        let stream = adapter.open(CameraIndex::Index(0)).unwrap();
+        let mut capturer = ThermalCapturer::<()>::new(
+            Box::new(StreamSource(stream)),
+            ProcessingSettings::default(),
+            Arc::new(|| {}),
+        );
+        capturer.start();
+
+        let result = match capturer.recv_event() {
+            ThermalCapturerEvent::Frame(result) => result,
+            ThermalCapturerEvent::Error(err)
+            | ThermalCapturerEvent::Disconnected(err)
+            | ThermalCapturerEvent::Stopped(Some(err)) => panic!("No frame: {:#}", err),
+            ThermalCapturerEvent::Stopped(None) => panic!("The capturer stopped"),
+        };
+        assert_eq!(
+            (result.thermal_data.width, result.thermal_data.height),
+            (32, 24)
+        );
+        assert!(!result.is_delta);
+        assert_celsius(result.stats.min, 20.0);
+        assert_celsius(result.stats.max, 60.0);
+        assert_eq!(result.max_pos, ThermalDataPos::new(8, 12));
+        assert_celsius(
+            result
+                .thermal_data
+                .temperature_at(result.min_pos.x, result.min_pos.y),
+            20.0,
+        );
+    }
+}
@@ -0,0 +1,962 @@
+#[cfg(feature = "color-image")]
+use epaint::{Color32, ColorImage};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "color-image")]
+use crate::thermal_gradient::GradientLut;
+use crate::{
+    temperature::{Temp, TempRange, TemperatureUnit},
+    types::image_rotation::ImageRotation,
+};
+
+#[derive(Clone, Default)]
+pub struct ThermalData {
+    // Width in pixels
+    pub width: usize,
+
+    // Height in pixels
+    pub height: usize,
+
+    // Temperature data in degrees Kelvin
+    pub data: Vec<Temp>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ThermalDataPos {
+    pub x: usize,
+    pub y: usize,
+}
+
+impl ThermalDataPos {
+    pub fn new(x: usize, y: usize) -> Self {
+        Self { x, y }
+    }
+
+    ///
+    /// Returns the position moved inside of an image of the given size.
+    ///
+    pub fn clamped(&self, width: usize, height: usize) -> Self {
+        Self::new(
+            self.x.min(width.saturating_sub(1)),
+            self.y.min(height.saturating_sub(1)),
+        )
+    }
+}
+
+///
+/// Rectangular area of thermal data, with both corners inclusive.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ThermalDataRect {
+    pub min: ThermalDataPos,
+    pub max: ThermalDataPos,
+}
+
+impl ThermalDataRect {
+    pub fn from_corners(a: ThermalDataPos, b: ThermalDataPos) -> Self {
+        Self {
+            min: ThermalDataPos::new(a.x.min(b.x), a.y.min(b.y)),
+            max: ThermalDataPos::new(a.x.max(b.x), a.y.max(b.y)),
+        }
+    }
+
+    #[inline(always)]
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.min.x && x <= self.max.x && y >= self.min.y && y <= self.max.y
+    }
+
+    ///
+    /// Returns the rect moved and shrunk to fit inside of an image of the given size.
+    ///
+    pub fn clamped(&self, width: usize, height: usize) -> Self {
+        Self {
+            min: self.min.clamped(width, height),
+            max: self.max.clamped(width, height),
+        }
+    }
+}
+
+impl ThermalData {
+    pub fn new(width: usize, height: usize, data: Vec<Temp>) -> Self {
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+
+    #[inline(always)]
+    pub fn temperature_at(&self, x: usize, y: usize) -> Temp {
+        self.data[y * self.width + x]
+    }
+
+    ///
+    /// Samples the temperature at a fractional pixel position, bilinearly interpolating
+    /// between the four surrounding pixels. Integer coordinates are pixel centers,
+    /// so they return the same value as `temperature_at`.
+    /// Positions outside of the image are clamped to the border.
+    ///
+    pub fn temperature_at_subpixel(&self, x: f32, y: f32) -> Temp {
+        let x = x.clamp(0.0, (self.width - 1) as f32);
+        let y = y.clamp(0.0, (self.height - 1) as f32);
+
+        let x0 = x.floor() as usize;
+        let y0 = y.floor() as usize;
+        let x1 = (x0 + 1).min(self.width - 1);
+        let y1 = (y0 + 1).min(self.height - 1);
+        let tx = x - x0 as f32;
+        let ty = y - y0 as f32;
+
+        let top = self
+            .temperature_at(x0, y0)
+            .lerp(self.temperature_at(x1, y0), tx);
+        let bottom = self
+            .temperature_at(x0, y1)
+            .lerp(self.temperature_at(x1, y1), tx);
+        top.lerp(bottom, ty)
+    }
+
+    ///
+    /// Samples `count` evenly spaced temperatures along the segment from `start` to `end`
+    /// (both in pixel coordinates, both inclusive), interpolating between pixels.
+    ///
+    pub fn sample_segment(&self, start: [f32; 2], end: [f32; 2], count: usize) -> Vec<Temp> {
+        if count < 2 {
+            return vec![self.temperature_at_subpixel(start[0], start[1]); count];
+        }
+        let step_x = (end[0] - start[0]) / (count - 1) as f32;
+        let step_y = (end[1] - start[1]) / (count - 1) as f32;
+        (0..count)
+            .map(|i| {
+                self.temperature_at_subpixel(
+                    start[0] + step_x * i as f32,
+                    start[1] + step_y * i as f32,
+                )
+            })
+            .collect()
+    }
+
+    ///
+    /// Colorizes every pixel with `callback`, in parallel over the rows.
+    ///
+    #[cfg(feature = "color-image")]
+    pub fn map_to_image<F: Fn(Temp) -> Color32 + Sync>(&self, callback: F) -> ColorImage {
+        let mut img = ColorImage::default();
+        self.map_to_image_into(&mut img, callback);
+        img
+    }
+
+    ///
+    /// Same as `map_to_image`, but reuses the pixels of `img`, e.g. of a previous frame,
+    /// which only allocates when the image grew.
+    ///
+    #[cfg(feature = "color-image")]
+    pub fn map_to_image_into<F: Fn(Temp) -> Color32 + Sync>(
+        &self,
+        img: &mut ColorImage,
+        callback: F,
+    ) {
+        self.resize_image(img);
+        img.pixels
+            .par_chunks_mut(self.row_len())
+            .zip(self.data.par_chunks(self.row_len()))
+            .for_each(|(pixels, temps)| {
+                for (pixel, temp) in pixels.iter_mut().zip(temps) {
+                    *pixel = callback(*temp);
+                }
+            });
+    }
+
+    ///
+    /// Colorizes the temperatures linearly across `range`, the fast path of `map_to_image`
+    /// for when nothing but the gradient decides the colors.
+    /// The indices into the table are computed a block at a time, without branches,
+    /// so the compiler can vectorize that part; only the lookups are done per pixel.
+    ///
+    #[cfg(feature = "color-image")]
+    pub fn map_range_to_image(&self, range: TempRange, lut: &GradientLut) -> ColorImage {
+        let mut img = ColorImage::default();
+        self.map_range_to_image_into(&mut img, range, lut);
+        img
+    }
+
+    #[cfg(feature = "color-image")]
+    pub fn map_range_to_image_into(
+        &self,
+        img: &mut ColorImage,
+        range: TempRange,
+        lut: &GradientLut,
+    ) {
+        const BLOCK: usize = 64;
+
+        let colors = lut.colors();
+        let last_index = (colors.len() - 1) as f32;
+        let min = range.min.to_unit(TemperatureUnit::Kelvin);
+        let span = (range.max - range.min).to_unit(TemperatureUnit::Kelvin);
+        // a uniform range gets the colors of its ends instead of dividing by zero,
+        // an inverted one maps in reverse like `TempRange::factor`
+        let span = if span.abs() < f32::EPSILON {
+            f32::EPSILON
+        } else {
+            span
+        };
+        let scale = last_index / span;
+        // rounds to the nearest entry when truncated
+        let offset = 0.5 - min * scale;
+
+        self.resize_image(img);
+        img.pixels
+            .par_chunks_mut(self.row_len())
+            .zip(self.data.par_chunks(self.row_len()))
+            .for_each(|(row_pixels, row_temps)| {
+                let mut indices = [0u16; BLOCK];
+                for (temps, pixels) in row_temps.chunks(BLOCK).zip(row_pixels.chunks_mut(BLOCK)) {
+                    for (index, temp) in indices.iter_mut().zip(temps) {
+                        let kelvin = temp.to_unit(TemperatureUnit::Kelvin);
+                        *index = (kelvin * scale + offset).clamp(0.0, last_index) as u16;
+                    }
+                    for (pixel, index) in pixels.iter_mut().zip(indices.iter()) {
+                        *pixel = colors[*index as usize];
+                    }
+                }
+            });
+    }
+
+    // Every pixel is overwritten, so the previous contents are kept rather than cleared
+    #[cfg(feature = "color-image")]
+    fn resize_image(&self, img: &mut ColorImage) {
+        img.size = [self.width, self.height];
+        img.pixels.resize(self.width * self.height, Color32::BLACK);
+    }
+
+    ///
+    /// Computes a new value for every pixel from its position into `output`, in parallel over the rows.
+    /// Used by filters which read the neighbourhood of each pixel, which swap `output`
+    /// with the temperatures afterwards.
+    ///
+    pub(crate) fn map_positions_into(
+        &self,
+        output: &mut Vec<Temp>,
+        f: impl Fn(usize, usize) -> Temp + Sync,
+    ) {
+        output.resize(self.data.len(), Temp::new(0.0));
+        output
+            .par_chunks_mut(self.row_len())
+            .enumerate()
+            .for_each(|(y, row)| {
+                for (x, pixel) in row.iter_mut().enumerate() {
+                    *pixel = f(x, y);
+                }
+            });
+    }
+
+    // Length of the rows processed in parallel, never zero so the data can be chunked
+    #[inline(always)]
+    fn row_len(&self) -> usize {
+        self.width.max(1)
+    }
+
+    pub fn get_min_max_pos(&self) -> (ThermalDataPos, ThermalDataPos) {
+        // (index, temperature) of the coldest and hottest pixel of each row, then of all rows.
+        // Ties keep the first pixel, as the rows are combined in order.
+        let ((min_index, _), (max_index, _)) = self
+            .data
+            .par_chunks(self.row_len())
+            .enumerate()
+            .map(|(y, row)| {
+                let mut min = (y * self.row_len(), Temp::MAX);
+                let mut max = (y * self.row_len(), Temp::MIN);
+                for (x, temp) in row.iter().enumerate() {
+                    if *temp < min.1 {
+                        min = (y * self.row_len() + x, *temp);
+                    }
+                    if *temp > max.1 {
+                        max = (y * self.row_len() + x, *temp);
+                    }
+                }
+                (min, max)
+            })
+            .reduce(
+                || ((0, Temp::MAX), (0, Temp::MIN)),
+                |a, b| {
+                    (
+                        if b.0 .1 < a.0 .1 { b.0 } else { a.0 },
+                        if b.1 .1 > a.1 .1 { b.1 } else { a.1 },
+                    )
+                },
+            );
+        (
+            ThermalDataPos::new(min_index % self.row_len(), min_index / self.row_len()),
+            ThermalDataPos::new(max_index % self.row_len(), max_index / self.row_len()),
+        )
+    }
+
+    ///
+    /// Maps the temperatures linearly onto the full 16-bit range, for radiometric export.
+    /// Returns the pixel values and the `(scale, offset)` of the mapping, both in Kelvin:
+    ///
+    /// `kelvin = offset + value * scale`
+    ///
+    /// `offset` is `range.min` and `scale` is `(range.max - range.min) / 65535`,
+    /// so the quantization error is at most `scale / 2`.
+    /// Temperatures outside of `range` are clamped.
+    ///
+    pub fn to_gray16(&self, range: TempRange) -> (Vec<u16>, f32, f32) {
+        let offset = range.min.to_unit(TemperatureUnit::Kelvin);
+        let span = (range.max - range.min).to_unit(TemperatureUnit::Kelvin);
+        // a uniform frame still needs a non-zero scale to be decodable
+        let scale = span.max(f32::EPSILON) / u16::MAX as f32;
+
+        let values = self
+            .data
+            .iter()
+            .map(|t| {
+                let value = (t.to_unit(TemperatureUnit::Kelvin) - offset) / scale;
+                value.round().clamp(0.0, u16::MAX as f32) as u16
+            })
+            .collect();
+        (values, scale, offset)
+    }
+
+    pub fn stats(&self) -> ThermalDataStats {
+        ThermalDataStats::from_temperatures(self.data.iter().copied())
+    }
+
+    ///
+    /// Same as `stats`, but sorts the temperatures in `scratch` instead of a new buffer.
+    ///
+    pub fn stats_with(&self, scratch: &mut Vec<f32>) -> ThermalDataStats {
+        ThermalDataStats::from_temperatures_with(self.data.iter().copied(), scratch)
+    }
+
+    ///
+    /// Same as `stats`, but only of the pixels inside of `rect`.
+    ///
+    pub fn region_stats(&self, rect: ThermalDataRect) -> ThermalDataStats {
+        ThermalDataStats::from_temperatures(self.region_temperatures(rect))
+    }
+
+    ///
+    /// The temperatures below which `percents` (0-100) of the pixels fall, see `percentiles`.
+    ///
+    pub fn percentiles(&self, percents: &[f32]) -> Vec<Temp> {
+        percentiles(self.data.iter().copied(), percents)
+    }
+
+    ///
+    /// Same as `percentiles`, but only of the pixels inside of `rect`.
+    ///
+    pub fn region_percentiles(&self, rect: ThermalDataRect, percents: &[f32]) -> Vec<Temp> {
+        percentiles(self.region_temperatures(rect), percents)
+    }
+
+    fn region_temperatures(&self, rect: ThermalDataRect) -> impl Iterator<Item = Temp> + '_ {
+        self.data
+            .iter()
+            .enumerate()
+            .filter(move |(i, _)| rect.contains(i % self.row_len(), i / self.row_len()))
+            .map(|(_, temp)| *temp)
+    }
+
+    pub fn rotated(&self, rotation: ImageRotation) -> Self {
+        if rotation == ImageRotation::None {
+            return self.clone();
+        }
+        let (width, height) = match rotation {
+            ImageRotation::None => (self.width, self.height),
+            ImageRotation::Clockwise90 => (self.height, self.width),
+            ImageRotation::Clockwise180 => (self.width, self.height),
+            ImageRotation::Clockwise270 => (self.height, self.width),
+        };
+
+        let mut data: Vec<Temp> = vec![Temp::new(0.0); width * height];
+        for (i, pixel) in self.data.iter().enumerate() {
+            let x = i % self.width;
+            let y = i / self.width;
+            let (x, y) = match rotation {
+                ImageRotation::None => (x, y),
+                ImageRotation::Clockwise90 => (y, self.width - x - 1),
+                ImageRotation::Clockwise180 => (self.width - x - 1, self.height - y - 1),
+                ImageRotation::Clockwise270 => (self.height - y - 1, x),
+            };
+            data[y * width + x] = *pixel;
+        }
+
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+
+    ///
+    /// Mirrored left to right and/or top to bottom.
+    ///
+    pub fn mirrored(&self, horizontal: bool, vertical: bool) -> Self {
+        if !horizontal && !vertical {
+            return self.clone();
+        }
+        let mut data = Vec::with_capacity(self.data.len());
+        for y in 0..self.height {
+            let y = if vertical { self.height - y - 1 } else { y };
+            let row = &self.data[y * self.width..(y + 1) * self.width];
+            if horizontal {
+                data.extend(row.iter().rev());
+            } else {
+                data.extend_from_slice(row);
+            }
+        }
+
+        Self {
+            width: self.width,
+            height: self.height,
+            data,
+        }
+    }
+
+    ///
+    /// Same as `rotated` followed by `mirrored`, but written into the temperatures of `output`,
+    /// e.g. of a previous frame, which only allocates when the frame grew.
+    ///
+    pub fn oriented_into(
+        &self,
+        rotation: ImageRotation,
+        horizontal: bool,
+        vertical: bool,
+        output: &mut ThermalData,
+    ) {
+        let (width, height) = match rotation {
+            ImageRotation::None | ImageRotation::Clockwise180 => (self.width, self.height),
+            ImageRotation::Clockwise90 | ImageRotation::Clockwise270 => (self.height, self.width),
+        };
+        output.width = width;
+        output.height = height;
+        // every pixel is overwritten, like the pixels of `resize_image`
+        output.data.resize(width * height, Temp::new(0.0));
+        for (i, pixel) in self.data.iter().enumerate() {
+            let x = i % self.width;
+            let y = i / self.width;
+            let (x, y) = match rotation {
+                ImageRotation::None => (x, y),
+                ImageRotation::Clockwise90 => (y, self.width - x - 1),
+                ImageRotation::Clockwise180 => (self.width - x - 1, self.height - y - 1),
+                ImageRotation::Clockwise270 => (self.height - y - 1, x),
+            };
+            let x = if horizontal { width - x - 1 } else { x };
+            let y = if vertical { height - y - 1 } else { y };
+            output.data[y * width + x] = *pixel;
+        }
+    }
+}
+
+///
+/// Distribution statistics of a set of temperatures.
+/// All values are kept in Kelvin and only converted when displayed.
+///
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ThermalDataStats {
+    pub min: Temp,
+    pub max: Temp,
+    pub mean: Temp,
+    pub median: Temp,
+    // 95th percentile, the hot end of the scene without the few hottest pixels
+    pub p95: Temp,
+}
+
+impl ThermalDataStats {
+    pub fn from_temperatures(temperatures: impl Iterator<Item = Temp>) -> Self {
+        Self::from_temperatures_with(temperatures, &mut vec![])
+    }
+
+    ///
+    /// Same as `from_temperatures`, the percentiles are selected in `scratch`,
+    /// e.g. kept between frames, which only allocates when there are more temperatures.
+    ///
+    pub fn from_temperatures_with(
+        temperatures: impl Iterator<Item = Temp>,
+        scratch: &mut Vec<f32>,
+    ) -> Self {
+        scratch.clear();
+        scratch.extend(temperatures.map(|t| t.to_unit(TemperatureUnit::Kelvin)));
+        let kelvin = scratch;
+        if kelvin.is_empty() {
+            return Self::default();
+        }
+
+        // summed in f64, a frame has enough pixels for f32 sums to lose precision
+        let (min, max, sum) = kelvin
+            .iter()
+            .fold((f32::MAX, f32::MIN, 0.0f64), |(min, max, sum), t| {
+                (min.min(*t), max.max(*t), sum + *t as f64)
+            });
+        let mean = sum / kelvin.len() as f64;
+        let median = percentile_of(kelvin, 50.0);
+        let p95 = percentile_of(kelvin, 95.0);
+
+        Self {
+            min: Temp::new(min),
+            max: Temp::new(max),
+            mean: Temp::new(mean as f32),
+            median,
+            p95,
+        }
+    }
+}
+
+///
+/// Returns the temperatures below which `percents` (0-100) of the values fall,
+/// linearly interpolating between the two closest ranks.
+///
+/// Computed on demand by partially sorting a copy of the values, so only the percentiles
+/// which are needed cost anything.
+///
+pub fn percentiles(temperatures: impl Iterator<Item = Temp>, percents: &[f32]) -> Vec<Temp> {
+    let mut kelvin: Vec<f32> = temperatures
+        .map(|t| t.to_unit(TemperatureUnit::Kelvin))
+        .collect();
+    percents
+        .iter()
+        .map(|percent| percentile_of(&mut kelvin, *percent))
+        .collect()
+}
+
+// Reorders `kelvin`: selects the lower rank in O(n) instead of sorting all values,
+// the upper rank is then the smallest of the values above it
+fn percentile_of(kelvin: &mut [f32], percent: f32) -> Temp {
+    if kelvin.is_empty() {
+        return Temp::default();
+    }
+    let rank = (percent.clamp(0.0, 100.0) / 100.0) * (kelvin.len() - 1) as f32;
+    let lower = rank.floor() as usize;
+    let t = rank - lower as f32;
+    let (_, lower_value, above) = kelvin.select_nth_unstable_by(lower, f32::total_cmp);
+    let lower_value = *lower_value;
+    if t == 0.0 || above.is_empty() {
+        return Temp::new(lower_value);
+    }
+    let upper_value = above.iter().copied().fold(f32::MAX, f32::min);
+    Temp::new(lower_value * (1.0 - t) + upper_value * t)
+}
+
+pub const MIN_HISTOGRAM_BUCKETS: usize = 10;
+pub const MAX_HISTOGRAM_BUCKETS: usize = 500;
+
+#[derive(Clone, Debug)]
+pub struct ThermalDataHistogramPoint {
+    pub temperature: Temp,
+    // Fraction of all pixels falling into this bucket
+    pub factor: f32,
+    // Number of pixels falling into this bucket
+    pub count: usize,
+}
+
+#[derive(Default)]
+pub struct ThermalDataHistogram {
+    pub points: Vec<ThermalDataHistogramPoint>,
+}
+
+impl ThermalDataHistogram {
+    pub fn from_thermal_data(data: &ThermalData, range: TempRange, num_buckets: usize) -> Self {
+        let mut histogram = Self::default();
+        histogram.update_from_thermal_data(data, range, num_buckets);
+        histogram
+    }
+
+    ///
+    /// Same as `from_thermal_data`, but reuses the points of `self`, e.g. of a previous frame.
+    ///
+    pub fn update_from_thermal_data(
+        &mut self,
+        data: &ThermalData,
+        range: TempRange,
+        num_buckets: usize,
+    ) {
+        // counted per row in parallel, then summed
+        let buckets = data
+            .data
+            .par_chunks(data.row_len())
+            .fold(
+                || vec![0; num_buckets],
+                |mut buckets, row| {
+                    for temp in row {
+                        if let Some(bucket) = Self::bucket_of(*temp, range, num_buckets) {
+                            buckets[bucket] += 1;
+                        }
+                    }
+                    buckets
+                },
+            )
+            .reduce(
+                || vec![0; num_buckets],
+                |mut a, b| {
+                    a.iter_mut().zip(b).for_each(|(a, b)| *a += b);
+                    a
+                },
+            );
+        self.set_buckets(&buckets, data.data.len(), range);
+    }
+
+    ///
+    /// Same as `from_thermal_data`, but only counts the pixels inside of `rect`.
+    ///
+    pub fn from_thermal_data_region(
+        data: &ThermalData,
+        rect: ThermalDataRect,
+        range: TempRange,
+        num_buckets: usize,
+    ) -> Self {
+        let mut histogram = Self::default();
+        histogram.update_from_thermal_data_region(data, rect, range, num_buckets);
+        histogram
+    }
+
+    ///
+    /// Same as `from_thermal_data_region`, but reuses the points of `self`.
+    ///
+    pub fn update_from_thermal_data_region(
+        &mut self,
+        data: &ThermalData,
+        rect: ThermalDataRect,
+        range: TempRange,
+        num_buckets: usize,
+    ) {
+        self.set_temperatures(
+            data.data
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| rect.contains(i % data.width, i / data.width))
+                .map(|(_, temp)| *temp),
+            range,
+            num_buckets,
+        );
+    }
+
+    fn set_temperatures(
+        &mut self,
+        temperatures: impl Iterator<Item = Temp>,
+        range: TempRange,
+        num_buckets: usize,
+    ) {
+        let mut buckets = vec![0; num_buckets];
+        let mut total_pixels = 0;
+
+        for temp in temperatures {
+            total_pixels += 1;
+            if let Some(bucket) = Self::bucket_of(temp, range, num_buckets) {
+                buckets[bucket] += 1;
+            }
+        }
+        self.set_buckets(&buckets, total_pixels, range);
+    }
+
+    // None for temperatures above the range, which aren't counted
+    #[inline(always)]
+    fn bucket_of(temp: Temp, range: TempRange, num_buckets: usize) -> Option<usize> {
+        let bucket = (range.factor(temp) * (num_buckets as f32)) as usize;
+        (bucket < num_buckets).then_some(bucket)
+    }
+
+    fn set_buckets(&mut self, buckets: &[usize], total_pixels: usize, range: TempRange) {
+        let num_buckets = buckets.len();
+        self.points.clear();
+        for (i, bucket) in buckets.iter().enumerate() {
+            let factor = *bucket as f32 / total_pixels.max(1) as f32;
+            let temperature =
+                range.min + (range.max - range.min) * ((i as f32 + 0.5) / num_buckets as f32);
+            self.points.push(ThermalDataHistogramPoint {
+                temperature,
+                factor,
+                count: *bucket,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_kelvin(temp: Temp, expected: f32) {
+        let kelvin = temp.to_unit(TemperatureUnit::Kelvin);
+        assert!(
+            (kelvin - expected).abs() < 1e-3,
+            "expected {} K, got {} K",
+            expected,
+            kelvin
+        );
+    }
+
+    // 3×2 frame, in Kelvin:
+    // 300 310 320
+    // 330 340 350
+    fn grid() -> ThermalData {
+        ThermalData::new(
+            3,
+            2,
+            [300.0, 310.0, 320.0, 330.0, 340.0, 350.0]
+                .into_iter()
+                .map(Temp::new)
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn subpixel_is_exact_at_pixel_centers() {
+        let data = grid();
+        for y in 0..data.height {
+            for x in 0..data.width {
+                assert_kelvin(
+                    data.temperature_at_subpixel(x as f32, y as f32),
+                    data.temperature_at(x, y).to_unit(TemperatureUnit::Kelvin),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn subpixel_midpoints_average_the_neighbors() {
+        let data = grid();
+        assert_kelvin(data.temperature_at_subpixel(0.5, 0.0), 305.0);
+        assert_kelvin(data.temperature_at_subpixel(1.5, 1.0), 345.0);
+        assert_kelvin(data.temperature_at_subpixel(2.0, 0.5), 335.0);
+        // between four pixels
+        assert_kelvin(data.temperature_at_subpixel(0.5, 0.5), 320.0);
+        assert_kelvin(data.temperature_at_subpixel(1.25, 0.5), 327.5);
+    }
+
+    #[test]
+    fn subpixel_clamps_at_the_borders() {
+        let data = grid();
+        assert_kelvin(data.temperature_at_subpixel(-3.0, -0.5), 300.0);
+        assert_kelvin(data.temperature_at_subpixel(7.0, 0.0), 320.0);
+        assert_kelvin(data.temperature_at_subpixel(2.0, 4.0), 350.0);
+        assert_kelvin(data.temperature_at_subpixel(0.5, 9.0), 335.0);
+    }
+
+    #[test]
+    fn stats_of_a_known_grid() {
+        // 1 to 10 K, out of order
+        let data = ThermalData::new(
+            5,
+            2,
+            [7.0, 2.0, 10.0, 4.0, 1.0, 9.0, 5.0, 3.0, 8.0, 6.0]
+                .into_iter()
+                .map(Temp::new)
+                .collect(),
+        );
+        let stats = data.stats();
+        assert_kelvin(stats.min, 1.0);
+        assert_kelvin(stats.max, 10.0);
+        assert_kelvin(stats.mean, 5.5);
+        // even number of pixels, between 5 and 6
+        assert_kelvin(stats.median, 5.5);
+        // rank 0.95 * 9 = 8.55, between 9 and 10
+        assert_kelvin(stats.p95, 9.55);
+
+        // the left column: 7 and 9
+        let region = data.region_stats(ThermalDataRect::from_corners(
+            ThermalDataPos::new(0, 0),
+            ThermalDataPos::new(0, 1),
+        ));
+        assert_kelvin(region.min, 7.0);
+        assert_kelvin(region.max, 9.0);
+        assert_kelvin(region.median, 8.0);
+    }
+
+    #[test]
+    fn median_of_even_and_odd_pixel_counts() {
+        let data = grid();
+        let stats = data.stats();
+        // 300 to 350, the median is between 320 and 330
+        assert_kelvin(stats.median, 325.0);
+        assert_kelvin(stats.mean, 325.0);
+
+        let data = ThermalData::new(3, 1, vec![Temp::new(5.0), Temp::new(1.0), Temp::new(3.0)]);
+        assert_kelvin(data.stats().median, 3.0);
+    }
+
+    #[test]
+    fn p50_is_the_median() {
+        let data = ThermalData::new(
+            4,
+            3,
+            (0..12)
+                .map(|i| Temp::new(290.0 + ((i * 7) % 12) as f32 * 1.5))
+                .collect(),
+        );
+        let stats = data.stats();
+        let percentiles = data.percentiles(&[0.0, 50.0, 95.0, 100.0]);
+        assert_kelvin(percentiles[0], stats.min.to_unit(TemperatureUnit::Kelvin));
+        assert_kelvin(
+            percentiles[1],
+            stats.median.to_unit(TemperatureUnit::Kelvin),
+        );
+        assert_kelvin(percentiles[2], stats.p95.to_unit(TemperatureUnit::Kelvin));
+        assert_kelvin(percentiles[3], stats.max.to_unit(TemperatureUnit::Kelvin));
+    }
+
+    #[test]
+    fn stats_of_no_pixels() {
+        let data = ThermalData::new(0, 0, vec![]);
+        assert_kelvin(data.stats().median, 0.0);
+        assert_kelvin(data.percentiles(&[50.0])[0], 0.0);
+    }
+
+    #[test]
+    fn oriented_into_matches_rotated_and_mirrored() {
+        let data = grid();
+        // larger than any of the results, so the buffer is shrunk rather than allocated
+        let mut output = ThermalData::new(4, 4, vec![Temp::new(1.0); 16]);
+        let kelvin = |data: &ThermalData| -> Vec<f32> {
+            data.data
+                .iter()
+                .map(|temp| temp.to_unit(TemperatureUnit::Kelvin))
+                .collect()
+        };
+        let rotations = [
+            ImageRotation::None,
+            ImageRotation::Clockwise90,
+            ImageRotation::Clockwise180,
+            ImageRotation::Clockwise270,
+        ];
+        for rotation in rotations {
+            for (horizontal, vertical) in
+                [(false, false), (true, false), (false, true), (true, true)]
+            {
+                let expected = data.rotated(rotation).mirrored(horizontal, vertical);
+                data.oriented_into(rotation, horizontal, vertical, &mut output);
+                assert_eq!(
+                    (output.width, output.height),
+                    (expected.width, expected.height)
+                );
+                assert_eq!(
+                    kelvin(&output),
+                    kelvin(&expected),
+                    "{:?}, mirrored {} {}",
+                    rotation,
+                    horizontal,
+                    vertical
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn updated_histogram_matches_a_new_one() {
+        let data = grid();
+        let range = TempRange::new(Temp::new(300.0), Temp::new(360.0));
+        // points of a previous frame with more buckets
+        let mut histogram = ThermalDataHistogram::from_thermal_data(&data, range, 20);
+        histogram.update_from_thermal_data(&data, range, 6);
+        let expected = ThermalDataHistogram::from_thermal_data(&data, range, 6);
+        let counts = |histogram: &ThermalDataHistogram| -> Vec<usize> {
+            histogram.points.iter().map(|point| point.count).collect()
+        };
+        assert_eq!(counts(&histogram), counts(&expected));
+        assert_eq!(counts(&histogram), vec![1; 6]);
+
+        // the right column: 320 and 350
+        let rect =
+            ThermalDataRect::from_corners(ThermalDataPos::new(2, 0), ThermalDataPos::new(2, 1));
+        histogram.update_from_thermal_data_region(&data, rect, range, 6);
+        assert_eq!(counts(&histogram), vec![0, 0, 1, 0, 0, 1]);
+    }
+
+    #[cfg(feature = "color-image")]
+    #[test]
+    fn map_range_to_image_matches_the_per_pixel_path() {
+        use crate::thermal_gradient::THERMAL_GRADIENTS;
+
+        // a warm spot on a gradient background, with pixels below and above the range
+        let (width, height) = (64, 48);
+        let data = ThermalData::new(
+            width,
+            height,
+            (0..width * height)
+                .map(|i| {
+                    let x = (i % width) as f32 / width as f32;
+                    let y = (i / width) as f32 / height as f32;
+                    let spot = (-((x - 0.6).powi(2) + (y - 0.4).powi(2)) * 20.0).exp();
+                    Temp::from_celsius(15.0 + x * 10.0 + spot * 55.0)
+                })
+                .collect(),
+        );
+        let lut = GradientLut::from_gradient(&THERMAL_GRADIENTS[0]);
+        for range in [
+            TempRange::new(Temp::from_celsius(20.0), Temp::from_celsius(65.0)),
+            // inverted, mapped in reverse
+            TempRange::new(Temp::from_celsius(65.0), Temp::from_celsius(20.0)),
+        ] {
+            let expected = data.map_to_image(|temp| lut.color(range.factor(temp)));
+            let image = data.map_range_to_image(range, &lut);
+            assert_eq!(image.size, expected.size);
+
+            // the index is computed in another order, which can round to the neighbouring entry
+            // for a temperature right between two entries
+            let colors = lut.colors();
+            let last = colors.len() - 1;
+            let mut different = 0;
+            for (i, (pixel, temp)) in image.pixels.iter().zip(data.data.iter()).enumerate() {
+                let index =
+                    (range.factor(*temp) * last as f32 + 0.5).clamp(0.0, last as f32) as usize;
+                let neighbours = &colors[index.saturating_sub(1)..=(index + 1).min(last)];
+                assert!(
+                    neighbours.contains(pixel),
+                    "pixel {} is off by more than one entry",
+                    i
+                );
+                if *pixel != expected.pixels[i] {
+                    different += 1;
+                }
+            }
+            assert!(
+                different * 100 < image.pixels.len(),
+                "{} of {} pixels differ",
+                different,
+                image.pixels.len()
+            );
+        }
+    }
+
+    #[test]
+    fn gray16_round_trip() {
+        let range = TempRange::new(Temp::from_celsius(-20.0), Temp::from_celsius(120.0));
+        let data = ThermalData::new(
+            16,
+            8,
+            (0..128)
+                .map(|i| Temp::from_celsius(-20.0 + i as f32 * 1.0937))
+                .collect(),
+        );
+        let (values, scale, offset) = data.to_gray16(range);
+        assert_eq!(values.len(), data.data.len());
+        assert!((scale - 140.0 / u16::MAX as f32).abs() < 1e-6);
+
+        for (value, temp) in values.iter().zip(data.data.iter()) {
+            let decoded = offset + *value as f32 * scale;
+            let kelvin = temp.to_unit(TemperatureUnit::Kelvin);
+            assert!(
+                (decoded - kelvin).abs() <= scale,
+                "{} K decoded as {} K, step {} K",
+                kelvin,
+                decoded,
+                scale
+            );
+        }
+    }
+
+    #[test]
+    fn gray16_clamps_to_the_range() {
+        let range = TempRange::new(Temp::new(300.0), Temp::new(310.0));
+        let data = ThermalData::new(
+            3,
+            1,
+            vec![Temp::new(290.0), Temp::new(305.0), Temp::new(320.0)],
+        );
+        let (values, _, _) = data.to_gray16(range);
+        assert_eq!(values[0], 0);
+        assert_eq!(values[1], u16::MAX / 2 + 1);
+        assert_eq!(values[2], u16::MAX);
+    }
+}
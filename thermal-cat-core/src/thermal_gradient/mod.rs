@@ -0,0 +1,397 @@
+use std::{
+    fs::{self, File},
+    hash::{Hash, Hasher},
+    io::{BufReader, BufWriter},
+    path::PathBuf,
+};
+
+use anyhow::{anyhow, Result};
+use ecolor::Color32;
+#[cfg(feature = "color-image")]
+use epaint::ColorImage;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use uuid::{uuid, Uuid};
+
+pub mod palette_import;
+
+// Bump when the custom gradients file changes in a way older versions cannot read
+const CUSTOM_GRADIENTS_VERSION: u32 = 1;
+
+pub static THERMAL_GRADIENTS: Lazy<Vec<ThermalGradient>> = Lazy::new(|| {
+    vec![
+        ThermalGradient::new(
+            uuid!("1d6233d0-7f8e-47c1-b092-0831cf587610"),
+            "Cold-warm".to_string(),
+            vec![
+                ThermalGradientPoint::from_rgbv(0, 0, 0, 0.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(0, 0, 255, 1.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(0, 255, 255, 2.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(0, 255, 0, 3.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(255, 255, 0, 4.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(255, 128, 0, 5.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(255, 0, 0, 6.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(255, 0, 255, 7.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(255, 255, 255, 8.0 / 9.0),
+            ],
+        ),
+        ThermalGradient::new(
+            uuid!("6f2e8a5a-f38c-4347-9c23-2d9f2e7a4aae"),
+            "Black to white".to_string(),
+            vec![
+                ThermalGradientPoint::from_rgbv(0, 0, 0, 0.0),
+                ThermalGradientPoint::from_rgbv(255, 255, 255, 1.0),
+            ],
+        ),
+        ThermalGradient::new(
+            uuid!("07943b0b-0e36-463c-8895-5befe69c69d9"),
+            "White to black".to_string(),
+            vec![
+                ThermalGradientPoint::from_rgbv(255, 255, 255, 0.0),
+                ThermalGradientPoint::from_rgbv(0, 0, 0, 1.0),
+            ],
+        ),
+        // The following gradients are perceptually uniform and increase monotonically in luminance,
+        // so the temperature order stays readable with most kinds of color vision deficiency.
+        ThermalGradient::new(
+            uuid!("b38639b2-b1ba-4219-8e90-69555f1aa1f0"),
+            "Viridis".to_string(),
+            vec![
+                ThermalGradientPoint::from_rgbv(68, 1, 84, 0.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(72, 40, 120, 1.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(62, 73, 137, 2.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(49, 104, 142, 3.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(38, 130, 142, 4.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(31, 158, 137, 5.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(53, 183, 121, 6.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(110, 206, 88, 7.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(181, 222, 43, 8.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(253, 231, 37, 1.0),
+            ],
+        ),
+        // Designed to look nearly identical to people with deuteranopia
+        // (red-green color blindness) and people with normal color vision.
+        ThermalGradient::new(
+            uuid!("cbedd8fd-9808-413b-abdd-ddbba39b8009"),
+            "Cividis".to_string(),
+            vec![
+                ThermalGradientPoint::from_rgbv(0, 34, 78, 0.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(18, 53, 112, 1.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(59, 73, 108, 2.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(87, 93, 109, 3.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(112, 113, 115, 4.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(138, 135, 121, 5.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(166, 157, 117, 6.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(196, 181, 108, 7.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(228, 207, 91, 8.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(254, 232, 56, 1.0),
+            ],
+        ),
+        ThermalGradient::new(
+            uuid!("eed031e6-dbba-4c10-a596-3f1e5f1fd52e"),
+            "Inferno".to_string(),
+            vec![
+                ThermalGradientPoint::from_rgbv(0, 0, 4, 0.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(27, 12, 65, 1.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(74, 12, 107, 2.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(120, 28, 109, 3.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(165, 44, 96, 4.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(207, 68, 70, 5.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(237, 105, 37, 6.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(251, 155, 6, 7.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(247, 209, 61, 8.0 / 9.0),
+                ThermalGradientPoint::from_rgbv(252, 255, 164, 1.0),
+            ],
+        ),
+    ]
+});
+
+///
+/// Diverging gradient for temperature differences: blue below zero, white at zero and red above,
+/// when mapped to a range centered at zero.
+///
+pub static DELTA_GRADIENT: Lazy<ThermalGradient> = Lazy::new(|| {
+    ThermalGradient::new(
+        uuid!("5c0e7f0e-3c1a-4c55-9a39-6f5f0c7f2d41"),
+        "Difference".to_string(),
+        vec![
+            ThermalGradientPoint::from_rgbv(5, 48, 97, 0.0),
+            ThermalGradientPoint::from_rgbv(67, 147, 195, 0.25),
+            ThermalGradientPoint::from_rgbv(247, 247, 247, 0.5),
+            ThermalGradientPoint::from_rgbv(214, 96, 77, 0.75),
+            ThermalGradientPoint::from_rgbv(103, 0, 31, 1.0),
+        ],
+    )
+});
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ThermalGradientPoint {
+    pub color: Color32,
+    // Position of the stop, normalized to the range [0, 1]
+    pub pos: f32,
+}
+
+impl ThermalGradientPoint {
+    pub fn from_rgbv(r: u8, g: u8, b: u8, pos: f32) -> Self {
+        Self {
+            color: Color32::from_rgb(r, g, b),
+            pos,
+        }
+    }
+}
+
+impl Hash for ThermalGradientPoint {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.color.hash(state);
+        self.pos.to_bits().hash(state);
+    }
+}
+
+#[derive(Clone, Hash, PartialEq, Serialize, Deserialize)]
+pub struct ThermalGradient {
+    ///
+    /// UUID of the gradient, identifies custom gradients across renames
+    ///
+    pub uuid: Uuid,
+    pub name: String,
+    pub points: Vec<ThermalGradientPoint>,
+}
+impl ThermalGradient {
+    pub fn new(uuid: Uuid, name: String, points: Vec<ThermalGradientPoint>) -> Self {
+        let mut me = Self { uuid, name, points };
+        me.sort_points();
+
+        me
+    }
+
+    ///
+    /// Sorts the stops by position, as required by `get_color`.
+    /// Needed after the positions were edited.
+    ///
+    pub fn sort_points(&mut self) {
+        self.points.sort_by(|a, b| a.pos.total_cmp(&b.pos));
+    }
+
+    //
+    // Sample the function at a given position.
+    // The position is normalized to the range [0, 1].
+    //
+    pub fn get_color(&self, pos: f32) -> Color32 {
+        if self.points.is_empty() {
+            return Color32::from_rgb(0, 0, 0);
+        }
+        if self.points.len() == 1 {
+            return self.points[0].color;
+        }
+        if pos < self.points[0].pos {
+            return self.points[0].color;
+        }
+        if pos > self.points[self.points.len() - 1].pos {
+            return self.points[self.points.len() - 1].color;
+        }
+        let mut i = 0;
+        while i < self.points.len() - 1 {
+            if pos >= self.points[i].pos && pos <= self.points[i + 1].pos {
+                // hard edge, e.g. from an imported color table
+                if self.points[i + 1].pos <= self.points[i].pos {
+                    return self.points[i + 1].color;
+                }
+                let t = (pos - self.points[i].pos) / (self.points[i + 1].pos - self.points[i].pos);
+                return Color32::from_rgb(
+                    (self.points[i].color.r() as f32 * (1.0 - t)
+                        + self.points[i + 1].color.r() as f32 * t) as u8,
+                    (self.points[i].color.g() as f32 * (1.0 - t)
+                        + self.points[i + 1].color.g() as f32 * t) as u8,
+                    (self.points[i].color.b() as f32 * (1.0 - t)
+                        + self.points[i + 1].color.b() as f32 * t) as u8,
+                );
+            }
+            i += 1;
+        }
+        Color32::from_rgb(0, 0, 0)
+    }
+
+    #[cfg(feature = "color-image")]
+    pub fn create_demo_image(&self, width: usize, height: usize) -> ColorImage {
+        let mut pixels = vec![Color32::default(); width * height];
+
+        for (i, pixel) in pixels.iter_mut().enumerate() {
+            let x = i % width;
+            let _y = i / width;
+
+            let pos = x as f32 / width as f32;
+
+            *pixel = self.get_color(pos);
+        }
+
+        ColorImage {
+            pixels,
+            size: [width, height],
+        }
+    }
+}
+
+// Entries of a GradientLut, finer than the 256 steps of a color channel,
+// so steep parts of a gradient or curve don't band
+pub const GRADIENT_LUT_SIZE: usize = 1024;
+
+///
+/// A gradient sampled at evenly spaced factors, for colorizing whole frames without
+/// searching the stops of the gradient for every pixel.
+///
+#[derive(Clone)]
+pub struct GradientLut {
+    colors: Vec<Color32>,
+}
+
+impl GradientLut {
+    ///
+    /// Samples `color_at` for factors from 0.0 to 1.0, so anything applied to the factor
+    /// before the gradient (e.g. the dynamic range curve) is part of the table.
+    ///
+    pub fn new(color_at: impl Fn(f32) -> Color32) -> Self {
+        let last = (GRADIENT_LUT_SIZE - 1) as f32;
+        Self {
+            colors: (0..GRADIENT_LUT_SIZE)
+                .map(|i| color_at(i as f32 / last))
+                .collect(),
+        }
+    }
+
+    pub fn from_gradient(gradient: &ThermalGradient) -> Self {
+        Self::new(|factor| gradient.get_color(factor))
+    }
+
+    ///
+    /// Color of the nearest entry, factors outside of [0, 1] get the color of the end.
+    ///
+    #[inline(always)]
+    pub fn color(&self, factor: f32) -> Color32 {
+        let index = (factor * (GRADIENT_LUT_SIZE - 1) as f32 + 0.5)
+            .clamp(0.0, (GRADIENT_LUT_SIZE - 1) as f32) as usize;
+        self.colors[index]
+    }
+
+    #[inline(always)]
+    pub fn colors(&self) -> &[Color32] {
+        &self.colors
+    }
+}
+
+// Number of temperatures labeled along a color scale, including both ends
+pub const COLOR_SCALE_TICKS: usize = 5;
+
+///
+/// Renders a vertical color scale, e.g. the legend of an image. `color_at` returns the color
+/// for a factor from 0.0 at the bottom to 1.0 at the top, so the scale can show the gradient
+/// with everything else that changes the colors of the image (curve, isotherms, ...).
+///
+#[cfg(feature = "color-image")]
+pub fn create_scale_image(
+    width: usize,
+    height: usize,
+    color_at: impl Fn(f32) -> Color32,
+) -> ColorImage {
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        // pixel centers, so both ends of the range are half a pixel inside of the scale
+        let color = color_at(1.0 - (y as f32 + 0.5) / height as f32);
+        pixels.extend(std::iter::repeat_n(color, width));
+    }
+
+    ColorImage {
+        pixels,
+        size: [width, height],
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CustomGradientsFile {
+    version: u32,
+    gradients: Vec<ThermalGradient>,
+}
+
+pub fn custom_gradients_path() -> PathBuf {
+    let mut path = dirs::config_dir().unwrap();
+    path.push("thermal-viewer");
+    path.push("gradients.json");
+    path
+}
+
+///
+/// Loads the user-defined gradients from the config directory.
+/// Returns an empty list if none were saved yet.
+///
+pub fn load_custom_gradients() -> Result<Vec<ThermalGradient>> {
+    let path = custom_gradients_path();
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+    let file: CustomGradientsFile = serde_json::from_reader(reader)?;
+    if file.version > CUSTOM_GRADIENTS_VERSION {
+        return Err(anyhow!(
+            "Unknown custom gradients version {}. Please update the application.",
+            file.version
+        ));
+    }
+    Ok(file.gradients)
+}
+
+pub fn save_custom_gradients(gradients: &[ThermalGradient]) -> Result<()> {
+    let path = custom_gradients_path();
+    let dir_path = path.parent().unwrap();
+    if !dir_path.exists() {
+        fs::create_dir_all(dir_path)?;
+    }
+
+    let file = File::create(path)?;
+    let writer = BufWriter::new(file);
+    serde_json::to_writer_pretty(
+        writer,
+        &CustomGradientsFile {
+            version: CUSTOM_GRADIENTS_VERSION,
+            gradients: gradients.to_vec(),
+        },
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Relative luminance of an sRGB color, as defined by WCAG
+    fn luminance(color: Color32) -> f32 {
+        let linear = |channel: u8| {
+            let value = channel as f32 / 255.0;
+            if value <= 0.04045 {
+                value / 12.92
+            } else {
+                ((value + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * linear(color.r()) + 0.7152 * linear(color.g()) + 0.0722 * linear(color.b())
+    }
+
+    #[test]
+    fn color_blind_safe_gradients_increase_in_luminance() {
+        for name in ["Viridis", "Cividis", "Inferno", "Black to white"] {
+            let gradient = THERMAL_GRADIENTS
+                .iter()
+                .find(|gradient| gradient.name == name)
+                .unwrap_or_else(|| panic!("{} is missing", name));
+            for stops in gradient.points.windows(2) {
+                assert!(
+                    luminance(stops[1].color) > luminance(stops[0].color),
+                    "{} gets darker from the stop at {} to the one at {}",
+                    name,
+                    stops[0].pos,
+                    stops[1].pos
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,415 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use ecolor::Color32;
+use uuid::Uuid;
+
+use super::{ThermalGradient, ThermalGradientPoint};
+
+pub const PALETTE_EXTENSIONS: [&str; 2] = ["cpt", "pal"];
+
+const JASC_PAL_MAGIC: &[u8] = b"JASC-PAL";
+// Palettes with more entries than this are most likely not palettes at all
+const MAX_PALETTE_ENTRIES: usize = 4096;
+// Stops which are off the line between their neighbours by at most this much
+// (per channel, 0-255) are dropped when importing
+const SIMPLIFY_TOLERANCE: f32 = 1.5;
+
+///
+/// Reads a gradient from a palette file. Supported are:
+/// - GMT color palette tables (.cpt), RGB color model only
+/// - JASC palettes (.pal, starting with "JASC-PAL")
+/// - FLIR palettes (.pal), 8-bit RGB triplets without a header
+///
+/// Evenly spaced palettes are converted to stops, dropping the ones which
+/// can be interpolated from their neighbours.
+///
+pub fn import_palette(path: &Path) -> Result<ThermalGradient> {
+    let bytes = std::fs::read(path)?;
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    let points = if bytes.starts_with(JASC_PAL_MAGIC) {
+        parse_jasc_pal(std::str::from_utf8(&bytes)?)?
+    } else if extension == "cpt" {
+        parse_cpt(std::str::from_utf8(&bytes)?)?
+    } else if extension == "pal" {
+        parse_flir_pal(&bytes)?
+    } else {
+        return Err(anyhow!(
+            "{} is not a supported palette file",
+            path.display()
+        ));
+    };
+
+    let name = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "Imported gradient".to_string());
+    Ok(ThermalGradient::new(
+        Uuid::new_v4(),
+        name,
+        simplify_points(points),
+    ))
+}
+
+///
+/// GMT .cpt: each line is a segment "z0 color0 z1 color1", where a color is
+/// either "r g b", "r/g/b", "#rrggbb" or a single gray value.
+/// The z values are normalized to [0, 1].
+///
+fn parse_cpt(text: &str) -> Result<Vec<ThermalGradientPoint>> {
+    let mut segments: Vec<(f32, Color32, f32, Color32)> = vec![];
+    for (line_no, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            let upper = line.to_uppercase();
+            if upper.contains("COLOR_MODEL") && !upper.contains("RGB") {
+                return Err(anyhow!("Only the RGB color model is supported"));
+            }
+            continue;
+        }
+        // background, foreground and NaN colors have no place in a gradient
+        if line.is_empty() || line.starts_with(['B', 'F', 'N']) {
+            continue;
+        }
+        // an optional label follows a semicolon
+        let line = line.split(';').next().unwrap_or_default();
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let error = || anyhow!("Invalid color table line {}: {}", line_no + 1, line);
+
+        let numbers: Option<Vec<f32>> = tokens.iter().take(8).map(|t| t.parse().ok()).collect();
+        let segment = match numbers {
+            // "z0 r g b z1 r g b", possibly followed by an annotation flag
+            Some(v) if v.len() == 8 => (
+                v[0],
+                rgb_from_floats(v[1], v[2], v[3]).ok_or_else(error)?,
+                v[4],
+                rgb_from_floats(v[5], v[6], v[7]).ok_or_else(error)?,
+            ),
+            _ if tokens.len() >= 4 => (
+                tokens[0].parse().map_err(|_| error())?,
+                parse_cpt_color(tokens[1]).ok_or_else(error)?,
+                tokens[2].parse().map_err(|_| error())?,
+                parse_cpt_color(tokens[3]).ok_or_else(error)?,
+            ),
+            _ => return Err(error()),
+        };
+        if !segment.0.is_finite() || !segment.2.is_finite() || segment.2 < segment.0 {
+            return Err(error());
+        }
+        segments.push(segment);
+    }
+
+    let (Some(first), Some(last)) = (segments.first(), segments.last()) else {
+        return Err(anyhow!("The color table contains no colors"));
+    };
+    let (z_min, z_max) = (first.0, last.2);
+    if z_max <= z_min {
+        return Err(anyhow!("The color table has no extent"));
+    }
+    let normalize = |z: f32| ((z - z_min) / (z_max - z_min)).clamp(0.0, 1.0);
+
+    let mut points: Vec<ThermalGradientPoint> = vec![];
+    for (z0, color0, z1, color1) in segments {
+        let start = ThermalGradientPoint {
+            color: color0,
+            pos: normalize(z0),
+        };
+        // continuous tables repeat the end of the previous segment
+        if points
+            .last()
+            .is_none_or(|p| p.pos != start.pos || p.color != start.color)
+        {
+            points.push(start);
+        }
+        points.push(ThermalGradientPoint {
+            color: color1,
+            pos: normalize(z1),
+        });
+    }
+    Ok(points)
+}
+
+fn parse_cpt_color(token: &str) -> Option<Color32> {
+    if let Some(hex) = token.strip_prefix('#') {
+        let hex = u32::from_str_radix(hex, 16).ok()?;
+        return Some(Color32::from_rgb(
+            (hex >> 16) as u8,
+            (hex >> 8) as u8,
+            hex as u8,
+        ));
+    }
+    let components: Vec<f32> = token
+        .split('/')
+        .map(|c| c.parse().ok())
+        .collect::<Option<_>>()?;
+    match components[..] {
+        [r, g, b] => rgb_from_floats(r, g, b),
+        [gray] => rgb_from_floats(gray, gray, gray),
+        _ => None,
+    }
+}
+
+fn rgb_from_floats(r: f32, g: f32, b: f32) -> Option<Color32> {
+    let valid = |c: f32| (0.0..=255.0).contains(&c);
+    if !(valid(r) && valid(g) && valid(b)) {
+        return None;
+    }
+    Some(Color32::from_rgb(
+        r.round() as u8,
+        g.round() as u8,
+        b.round() as u8,
+    ))
+}
+
+///
+/// JASC .pal: "JASC-PAL", the version "0100", the number of colors and then
+/// one "r g b" line per color.
+///
+fn parse_jasc_pal(text: &str) -> Result<Vec<ThermalGradientPoint>> {
+    let mut lines = text.lines().map(str::trim).filter(|l| !l.is_empty());
+    lines.next(); // magic
+    let version = lines.next().unwrap_or_default();
+    if version != "0100" {
+        return Err(anyhow!("Unsupported JASC palette version {}", version));
+    }
+    let count: usize = lines
+        .next()
+        .and_then(|l| l.parse().ok())
+        .ok_or_else(|| anyhow!("JASC palette is missing the number of colors"))?;
+
+    let colors = lines
+        .take(count)
+        .map(|line| {
+            let components: Vec<f32> = line
+                .split_whitespace()
+                .map(|c| c.parse().ok())
+                .collect::<Option<_>>()
+                .unwrap_or_default();
+            match components[..] {
+                [r, g, b, ..] => rgb_from_floats(r, g, b),
+                _ => None,
+            }
+            .ok_or_else(|| anyhow!("Invalid JASC palette color: {}", line))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if colors.len() != count {
+        return Err(anyhow!(
+            "JASC palette declares {} colors, but contains {}",
+            count,
+            colors.len()
+        ));
+    }
+    evenly_spaced_points(colors)
+}
+
+///
+/// FLIR .pal: the colors as consecutive 8-bit RGB triplets, from cold to hot.
+///
+fn parse_flir_pal(bytes: &[u8]) -> Result<Vec<ThermalGradientPoint>> {
+    if !bytes.len().is_multiple_of(3) {
+        return Err(anyhow!(
+            "FLIR palette size of {} bytes is not a multiple of 3",
+            bytes.len()
+        ));
+    }
+    evenly_spaced_points(
+        bytes
+            .chunks_exact(3)
+            .map(|c| Color32::from_rgb(c[0], c[1], c[2]))
+            .collect(),
+    )
+}
+
+fn evenly_spaced_points(colors: Vec<Color32>) -> Result<Vec<ThermalGradientPoint>> {
+    if colors.len() < 2 || colors.len() > MAX_PALETTE_ENTRIES {
+        return Err(anyhow!(
+            "A palette needs between 2 and {} colors, found {}",
+            MAX_PALETTE_ENTRIES,
+            colors.len()
+        ));
+    }
+    let last = (colors.len() - 1) as f32;
+    Ok(colors
+        .into_iter()
+        .enumerate()
+        .map(|(i, color)| ThermalGradientPoint {
+            color,
+            pos: i as f32 / last,
+        })
+        .collect())
+}
+
+///
+/// Drops stops which lie on the straight line between the stops kept around them,
+/// so that a 256 color palette doesn't turn into 256 stops.
+///
+fn simplify_points(points: Vec<ThermalGradientPoint>) -> Vec<ThermalGradientPoint> {
+    if points.len() <= 2 {
+        return points;
+    }
+    let is_on_line =
+        |a: &ThermalGradientPoint, b: &ThermalGradientPoint, p: &ThermalGradientPoint| {
+            if b.pos <= a.pos {
+                return false;
+            }
+            let t = (p.pos - a.pos) / (b.pos - a.pos);
+            let channel = |a: u8, b: u8, p: u8| {
+                (a as f32 * (1.0 - t) + b as f32 * t - p as f32).abs() <= SIMPLIFY_TOLERANCE
+            };
+            channel(a.color.r(), b.color.r(), p.color.r())
+                && channel(a.color.g(), b.color.g(), p.color.g())
+                && channel(a.color.b(), b.color.b(), p.color.b())
+        };
+
+    let mut kept = vec![points[0].clone()];
+    let mut anchor = 0;
+    for candidate in 2..points.len() {
+        let skipped = &points[anchor + 1..candidate];
+        if !skipped
+            .iter()
+            .all(|p| is_on_line(&points[anchor], &points[candidate], p))
+        {
+            anchor = candidate - 1;
+            kept.push(points[anchor].clone());
+        }
+    }
+    kept.push(points[points.len() - 1].clone());
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(pos: f32, color: Color32) -> ThermalGradientPoint {
+        ThermalGradientPoint { color, pos }
+    }
+
+    fn gray(value: u8) -> Color32 {
+        Color32::from_rgb(value, value, value)
+    }
+
+    #[test]
+    fn cpt_continuous_segments() {
+        let text = "# COLOR_MODEL = RGB\n\
+                    0 0 0 255 50 0 255 0\n\
+                    50 0 255 0 100 255 0 0 ; hot\n\
+                    B 0 0 0\n\
+                    F 255 255 255\n\
+                    N 128 128 128\n";
+        assert_eq!(
+            parse_cpt(text).unwrap(),
+            vec![
+                point(0.0, Color32::BLUE),
+                point(0.5, Color32::GREEN),
+                point(1.0, Color32::RED),
+            ]
+        );
+    }
+
+    #[test]
+    fn cpt_color_notations() {
+        assert_eq!(
+            parse_cpt("-20 #000000 0 128\n0 128/128/128 20 255/255/255\n").unwrap(),
+            vec![
+                point(0.0, gray(0)),
+                point(0.5, gray(128)),
+                point(1.0, gray(255))
+            ]
+        );
+    }
+
+    #[test]
+    fn cpt_malformed() {
+        // only RGB is supported
+        assert!(parse_cpt("# COLOR_MODEL = HSV\n0 0 1 1 1 0 1 1\n").is_err());
+        // components out of range
+        assert!(parse_cpt("0 0 0 300 1 0 0 0\n").is_err());
+        // z going backwards
+        assert!(parse_cpt("10 0 0 0 5 255 255 255\n").is_err());
+        // not a color
+        assert!(parse_cpt("0 red 1 blue\n").is_err());
+        // too few tokens
+        assert!(parse_cpt("0 0 0 0\n1 255\n").is_err());
+        // no segments, or no extent
+        assert!(parse_cpt("# just a comment\n").is_err());
+        assert!(parse_cpt("5 0 0 0 5 255 255 255\n").is_err());
+    }
+
+    #[test]
+    fn jasc_pal() {
+        let text = "JASC-PAL\r\n0100\r\n3\r\n255 0 0\r\n0 255 0\r\n0 0 255\r\n";
+        assert_eq!(
+            parse_jasc_pal(text).unwrap(),
+            vec![
+                point(0.0, Color32::RED),
+                point(0.5, Color32::GREEN),
+                point(1.0, Color32::BLUE),
+            ]
+        );
+    }
+
+    #[test]
+    fn jasc_pal_malformed() {
+        // unknown version
+        assert!(parse_jasc_pal("JASC-PAL\n0200\n2\n0 0 0\n255 255 255\n").is_err());
+        // missing count
+        assert!(parse_jasc_pal("JASC-PAL\n0100\n").is_err());
+        // fewer colors than declared
+        assert!(parse_jasc_pal("JASC-PAL\n0100\n3\n0 0 0\n255 255 255\n").is_err());
+        // incomplete color
+        assert!(parse_jasc_pal("JASC-PAL\n0100\n2\n0 0\n255 255 255\n").is_err());
+        // a single color is no gradient
+        assert!(parse_jasc_pal("JASC-PAL\n0100\n1\n0 0 0\n").is_err());
+    }
+
+    #[test]
+    fn flir_pal() {
+        assert_eq!(
+            parse_flir_pal(&[0, 0, 0, 255, 0, 0, 255, 255, 255]).unwrap(),
+            vec![
+                point(0.0, Color32::BLACK),
+                point(0.5, Color32::RED),
+                point(1.0, Color32::WHITE),
+            ]
+        );
+    }
+
+    #[test]
+    fn flir_pal_malformed() {
+        // not made of triplets
+        assert!(parse_flir_pal(&[0, 0, 0, 255]).is_err());
+        // a single color, or too many to be a palette
+        assert!(parse_flir_pal(&[0, 0, 0]).is_err());
+        assert!(parse_flir_pal(&vec![0; (MAX_PALETTE_ENTRIES + 1) * 3]).is_err());
+    }
+
+    #[test]
+    fn simplify_keeps_endpoints() {
+        let ramp = (0..=255).map(gray).collect();
+        assert_eq!(
+            simplify_points(evenly_spaced_points(ramp).unwrap()),
+            vec![point(0.0, gray(0)), point(1.0, gray(255))]
+        );
+    }
+
+    #[test]
+    fn simplify_keeps_corners() {
+        // up to gray in the middle, then back down to black
+        let tent = (0..=16u8)
+            .map(|i| gray(if i <= 8 { i * 16 } else { (16 - i) * 16 }))
+            .collect();
+        assert_eq!(
+            simplify_points(evenly_spaced_points(tent).unwrap()),
+            vec![
+                point(0.0, gray(0)),
+                point(0.5, gray(128)),
+                point(1.0, gray(0))
+            ]
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use anyhow::{anyhow, Result};
+
+use crate::{
+    camera_adapter::{CameraControl, CameraControlValue},
+    thermal_data::ThermalData,
+};
+
+#[derive(Clone, Copy, Debug)]
+pub struct PlaybackPosition {
+    pub frame_index: usize,
+    pub frame_count: usize,
+    // Time of the current frame, since the start of the recording
+    pub timestamp_us: u64,
+    pub duration_us: u64,
+    pub paused: bool,
+}
+
+///
+/// Where the thermal capturer gets its frames from, e.g. a live camera or a recording.
+/// Everything after capturing (correction, range, gradient, gizmos) does not depend on the source.
+///
+pub trait ThermalSource: Send {
+    ///
+    /// Called on the capturer thread before the first frame is captured.
+    ///
+    fn open(&mut self) -> Result<()>;
+
+    ///
+    /// Called on the capturer thread when the capturer stops.
+    ///
+    fn close(&mut self) -> Result<()>;
+
+    ///
+    /// Blocks until the next frame is available and returns it.
+    ///
+    fn capture(&mut self) -> Result<ThermalData>;
+
+    ///
+    /// Called on the capturer thread after `capture` failed, e.g. because the camera was unplugged.
+    /// Tries to open the source again and returns an error while it isn't back yet.
+    /// Sources which can't come back (recordings) fail right away.
+    ///
+    fn reconnect(&mut self) -> Result<()> {
+        Err(anyhow!("The source can't be reconnected"))
+    }
+
+    ///
+    /// Whether `reconnect` is worth retrying after a failed capture.
+    ///
+    fn can_reconnect(&self) -> bool {
+        false
+    }
+
+    ///
+    /// Nominal frame rate of the source
+    ///
+    fn frame_rate(&self) -> f32;
+
+    ///
+    /// Identifies the camera for per-camera calibration files, None for recordings.
+    ///
+    fn camera_id(&self) -> Option<String> {
+        None
+    }
+
+    ///
+    /// Device-specific settings of the camera, see `CameraAdapter::controls`.
+    ///
+    fn controls(&self) -> Vec<CameraControl> {
+        vec![]
+    }
+
+    fn set_control(&mut self, _id: &str, _value: CameraControlValue) -> Result<()> {
+        Err(anyhow!("Only live cameras have controls"))
+    }
+
+    ///
+    /// Run the flat-field correction (shutter calibration) of the camera.
+    ///
+    fn trigger_ffc(&mut self) -> Result<()> {
+        Err(anyhow!("Only live cameras can be calibrated"))
+    }
+
+    ///
+    /// Position within a recording, None for live sources.
+    ///
+    fn playback_position(&self) -> Option<PlaybackPosition> {
+        None
+    }
+
+    ///
+    /// Jump to the given frame of a recording. Live sources ignore this.
+    ///
+    fn seek(&mut self, _frame_index: usize) {}
+
+    fn set_playback_paused(&mut self, _paused: bool) {}
+}
@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    temperature::{Temp, TemperatureUnit},
+    thermal_data::ThermalData,
+};
+
+// The readings of the two references have to differ at least this much (in Kelvin),
+// closer ones would make the slope explode
+pub const MIN_READING_DIFFERENCE_KELVIN: f32 = 1.0;
+
+///
+/// A reference spot: what the camera reads and what it really is (e.g. from a contact thermometer).
+///
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CalibrationPoint {
+    pub measured: Temp,
+    pub actual: Temp,
+}
+
+///
+/// User calibration correcting the readings of the whole image linearly,
+/// for cameras whose factory calibration is off by a few degrees at the extremes.
+///
+/// The line goes through the two reference points, so the readings between them are
+/// interpolated and the readings outside of them extrapolated.
+///
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TwoPointCalibration {
+    pub enabled: bool,
+    pub points: [CalibrationPoint; 2],
+}
+
+impl Default for TwoPointCalibration {
+    fn default() -> Self {
+        let point = |celsius| CalibrationPoint {
+            measured: Temp::from_celsius(celsius),
+            actual: Temp::from_celsius(celsius),
+        };
+        Self {
+            enabled: false,
+            points: [point(20.0), point(60.0)],
+        }
+    }
+}
+
+impl TwoPointCalibration {
+    ///
+    /// Returns the gain and offset (in Kelvin) of the correction,
+    /// None if it's disabled or the readings of the references are too close.
+    ///
+    pub fn correction(&self) -> Option<(f32, f32)> {
+        if !self.enabled {
+            return None;
+        }
+        let [first, second] = self.points;
+        let m1 = first.measured.to_unit(TemperatureUnit::Kelvin);
+        let m2 = second.measured.to_unit(TemperatureUnit::Kelvin);
+        if (m2 - m1).abs() < MIN_READING_DIFFERENCE_KELVIN {
+            return None;
+        }
+        let a1 = first.actual.to_unit(TemperatureUnit::Kelvin);
+        let a2 = second.actual.to_unit(TemperatureUnit::Kelvin);
+        let gain = (a2 - a1) / (m2 - m1);
+        Some((gain, a1 - m1 * gain))
+    }
+
+    pub fn apply(&self, data: &mut ThermalData) {
+        if let Some((gain, offset)) = self.correction() {
+            for temp in data.data.iter_mut() {
+                *temp = Temp::new(temp.to_unit(TemperatureUnit::Kelvin) * gain + offset);
+            }
+        }
+    }
+
+    ///
+    /// Inverts the correction, giving the reading the camera had for a corrected temperature.
+    /// Used to take a reference reading from the image while the calibration is active.
+    ///
+    pub fn uncorrected(&self, temp: Temp) -> Temp {
+        match self.correction() {
+            Some((gain, offset)) if gain.abs() > f32::EPSILON => {
+                Temp::new((temp.to_unit(TemperatureUnit::Kelvin) - offset) / gain)
+            }
+            _ => temp,
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+///
+/// Sensor gain of cameras which can trade resolution for range.
+/// High gain resolves small differences but saturates at lower temperatures,
+/// low gain covers hot objects at the cost of precision.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GainMode {
+    #[default]
+    High,
+    Low,
+}
+
+impl GainMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GainMode::High => "High Gain",
+            GainMode::Low => "Low Gain",
+        }
+    }
+}
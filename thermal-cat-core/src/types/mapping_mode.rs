@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+///
+/// How temperatures inside of the display range are spread over the gradient.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MappingMode {
+    // Equal temperature steps get equal steps of the gradient
+    #[default]
+    Linear,
+    // Each step of the gradient covers about the same number of pixels,
+    // so detail in a large uniform background isn't lost next to a small hot object
+    HistogramEqualization,
+}
+
+impl MappingMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MappingMode::Linear => "Linear",
+            MappingMode::HistogramEqualization => "Histogram Equalization",
+        }
+    }
+}
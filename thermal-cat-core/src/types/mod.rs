@@ -0,0 +1,3 @@
+pub mod gain_mode;
+pub mod image_rotation;
+pub mod mapping_mode;
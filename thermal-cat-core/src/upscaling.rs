@@ -0,0 +1,226 @@
+use std::f32::consts::PI;
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    temperature::{Temp, TemperatureUnit},
+    thermal_data::ThermalData,
+};
+
+pub const MIN_UPSCALE_FACTOR: usize = 2;
+pub const MAX_UPSCALE_FACTOR: usize = 8;
+
+///
+/// Interpolation used to enlarge the thermal data before it is colorized,
+/// so the tiny sensor images look smooth on large screens.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UpscaleFilter {
+    // The data is colorized at the sensor resolution
+    #[default]
+    Off,
+    Nearest,
+    Bilinear,
+    // Catmull-Rom spline, sharper than bilinear
+    Bicubic,
+    // Lanczos with 3 lobes, the sharpest, but may ring around hard edges
+    Lanczos,
+}
+
+impl UpscaleFilter {
+    pub const ALL: [UpscaleFilter; 5] = [
+        UpscaleFilter::Off,
+        UpscaleFilter::Nearest,
+        UpscaleFilter::Bilinear,
+        UpscaleFilter::Bicubic,
+        UpscaleFilter::Lanczos,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            UpscaleFilter::Off => "Off",
+            UpscaleFilter::Nearest => "Nearest",
+            UpscaleFilter::Bilinear => "Bilinear",
+            UpscaleFilter::Bicubic => "Bicubic",
+            UpscaleFilter::Lanczos => "Lanczos",
+        }
+    }
+
+    ///
+    /// Distance in source pixels beyond which the kernel is zero.
+    ///
+    fn support(&self) -> f32 {
+        match self {
+            UpscaleFilter::Off | UpscaleFilter::Nearest => 0.5,
+            UpscaleFilter::Bilinear => 1.0,
+            UpscaleFilter::Bicubic => 2.0,
+            UpscaleFilter::Lanczos => 3.0,
+        }
+    }
+
+    fn weight(&self, x: f32) -> f32 {
+        let x = x.abs();
+        match self {
+            UpscaleFilter::Off | UpscaleFilter::Nearest => {
+                if x < 0.5 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            UpscaleFilter::Bilinear => (1.0 - x).max(0.0),
+            UpscaleFilter::Bicubic => {
+                const A: f32 = -0.5;
+                if x < 1.0 {
+                    ((A + 2.0) * x - (A + 3.0)) * x * x + 1.0
+                } else if x < 2.0 {
+                    ((A * x - 5.0 * A) * x + 8.0 * A) * x - 4.0 * A
+                } else {
+                    0.0
+                }
+            }
+            UpscaleFilter::Lanczos => {
+                if x < 1e-6 {
+                    1.0
+                } else if x < 3.0 {
+                    let px = PI * x;
+                    3.0 * px.sin() * (px / 3.0).sin() / (px * px)
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+///
+/// Resampling weights along one axis. Every output pixel reads `taps` source pixels,
+/// their indices (already clamped to the image) and normalized weights are stored
+/// back to back, so the inner loops only multiply and add.
+///
+#[derive(Default)]
+struct AxisKernel {
+    taps: usize,
+    indices: Vec<usize>,
+    weights: Vec<f32>,
+}
+
+impl AxisKernel {
+    fn compute(&mut self, filter: UpscaleFilter, src_len: usize, factor: usize) {
+        let support = filter.support();
+        let taps = (support * 2.0).ceil() as usize + 1;
+        let dst_len = src_len * factor;
+        self.taps = taps;
+        self.indices.clear();
+        self.weights.clear();
+
+        for i in 0..dst_len {
+            // pixel centers are at integer source coordinates
+            let center = (i as f32 + 0.5) / factor as f32 - 0.5;
+            let first = (center - support).ceil() as isize;
+            let start = self.weights.len();
+            for tap in 0..taps as isize {
+                let src = first + tap;
+                self.indices
+                    .push(src.clamp(0, src_len as isize - 1) as usize);
+                self.weights.push(filter.weight(src as f32 - center));
+            }
+            let sum: f32 = self.weights[start..].iter().sum();
+            if sum != 0.0 {
+                self.weights[start..].iter_mut().for_each(|w| *w /= sum);
+            }
+        }
+    }
+}
+
+///
+/// Enlarges the frames into a buffer it keeps between them. The kernels are only
+/// computed again when the filter, the factor or the size of the frames change.
+///
+#[derive(Default)]
+pub struct Upscaler {
+    // Filter, factor, width and height the kernels were computed for
+    kernel_params: Option<(UpscaleFilter, usize, usize, usize)>,
+    horizontal: AxisKernel,
+    vertical: AxisKernel,
+    // The rows resampled horizontally, in Kelvin
+    rows: Vec<f32>,
+    upscaled: ThermalData,
+}
+
+impl Upscaler {
+    ///
+    /// Returns the data enlarged `factor` times in both directions, or the data itself
+    /// if it isn't enlarged. The filter is separable, so the rows are resampled first
+    /// and then the columns.
+    ///
+    pub fn upscale<'a>(
+        &'a mut self,
+        data: &'a ThermalData,
+        filter: UpscaleFilter,
+        factor: usize,
+    ) -> &'a ThermalData {
+        if filter == UpscaleFilter::Off || factor <= 1 || data.width == 0 || data.height == 0 {
+            return data;
+        }
+        let width = data.width * factor;
+        let height = data.height * factor;
+
+        let kernel_params = Some((filter, factor, data.width, data.height));
+        if self.kernel_params != kernel_params {
+            self.horizontal.compute(filter, data.width, factor);
+            self.vertical.compute(filter, data.height, factor);
+            self.kernel_params = kernel_params;
+        }
+
+        let horizontal = &self.horizontal;
+        let rows = &mut self.rows;
+        rows.resize(width * data.height, 0.0);
+        data.data
+            .par_chunks_exact(data.width)
+            .zip(rows.par_chunks_exact_mut(width))
+            .for_each(|(src_row, dst_row)| {
+                for ((dst, indices), weights) in dst_row
+                    .iter_mut()
+                    .zip(horizontal.indices.chunks_exact(horizontal.taps))
+                    .zip(horizontal.weights.chunks_exact(horizontal.taps))
+                {
+                    *dst = indices
+                        .iter()
+                        .zip(weights)
+                        .map(|(index, weight)| {
+                            src_row[*index].to_unit(TemperatureUnit::Kelvin) * weight
+                        })
+                        .sum();
+                }
+            });
+
+        // whole rows are weighted and summed, which the compiler can vectorize
+        let vertical = &self.vertical;
+        let rows = &self.rows;
+        let upscaled = &mut self.upscaled;
+        upscaled.width = width;
+        upscaled.height = height;
+        upscaled.data.clear();
+        upscaled.data.resize(width * height, Temp::new(0.0));
+        upscaled
+            .data
+            .par_chunks_exact_mut(width)
+            .zip(vertical.indices.par_chunks_exact(vertical.taps))
+            .zip(vertical.weights.par_chunks_exact(vertical.taps))
+            .for_each(|((dst_row, indices), weights)| {
+                for (index, weight) in indices.iter().zip(weights) {
+                    if *weight == 0.0 {
+                        continue;
+                    }
+                    let src_row = &rows[*index * width..(*index + 1) * width];
+                    for (dst, src) in dst_row.iter_mut().zip(src_row) {
+                        *dst = Temp::new(dst.to_unit(TemperatureUnit::Kelvin) + *src * *weight);
+                    }
+                }
+            });
+
+        &self.upscaled
+    }
+}